@@ -59,12 +59,157 @@ dispatch_action() {
   local arg="${1:-}"
   case "$arg" in
     open:*)
-      open "${arg#open:}"
+      # 交给二进制处理以清理跟踪参数；二进制不可用时退回系统 open
+      run_binary open "${arg#open:}" >/dev/null 2>&1 || open "${arg#open:}"
+      ;;
+    open-bg:*)
+      # 后台打开，不切换到浏览器，便于连续打开多个书签
+      run_binary open --background "${arg#open-bg:}" >/dev/null 2>&1 || open -g "${arg#open-bg:}"
       ;;
     copy:*)
       printf '%s' "${arg#copy:}" | pbcopy
       notify_user "URL copied"
       ;;
+    copyrich:*)
+      # 格式为 copyrich:<url><TAB><标题>
+      local payload="${arg#copyrich:}"
+      local url="${payload%%$'\t'*}"
+      local title=""
+      [[ "$payload" == *$'\t'* ]] && title="${payload#*$'\t'}"
+      if run_binary copy-rich "$url" "$title" >/dev/null 2>&1; then
+        notify_user "Link copied"
+      else
+        printf '%s' "$url" | pbcopy
+        notify_user "URL copied"
+      fi
+      ;;
+    search:*)
+      # 动作菜单中的“同目录 / 同站点 / 标签 / 返回”：让 Alfred 以 cb 重新搜索
+      # 格式为 search:<查询>[<TAB><会话状态>]；带状态时先保存，状态为空时清除，下一次搜索据此显示“返回”
+      local search_payload="${arg#search:}"
+      local search_query="${search_payload%%$'\t'*}"
+      if [[ "$search_payload" == *$'\t'* ]]; then
+        local search_state="${search_payload#*$'\t'}"
+        if [[ -n "$search_state" ]]; then
+          run_binary session set "$search_state" >/dev/null 2>&1 || true
+        else
+          run_binary session clear >/dev/null 2>&1 || true
+        fi
+      fi
+      osascript -e 'on run argv' \
+        -e 'tell application id "com.runningwithcrayons.Alfred" to search (item 1 of argv)' \
+        -e 'end run' "cb ${search_query}" >/dev/null 2>&1
+      ;;
+    details:*)
+      # 输出交给 Large Type 显示
+      run_binary details "${arg#details:}" 2>/dev/null || printf '%s' "Bookmark not found"
+      ;;
+    reveal:*)
+      if ! run_binary reveal "${arg#reveal:}" >/dev/null 2>&1; then
+        notify_user "Reveal failed"
+        return 1
+      fi
+      ;;
+    queue:*)
+      # 格式同 copyrich：queue:<url><TAB><标题>
+      local queue_payload="${arg#queue:}"
+      local queue_url="${queue_payload%%$'\t'*}"
+      local queue_title=""
+      [[ "$queue_payload" == *$'\t'* ]] && queue_title="${queue_payload#*$'\t'}"
+      local queue_output
+      if queue_output="$(run_binary queue add "$queue_url" "$queue_title" 2>/dev/null)"; then
+        notify_user "$(extract_subtitle_from_json "$queue_output")"
+      else
+        notify_user "Queue failed"
+        return 1
+      fi
+      ;;
+    action:queue_open_all)
+      local open_all_output
+      if open_all_output="$(run_binary queue open-all 2>/dev/null)"; then
+        notify_user "$(extract_subtitle_from_json "$open_all_output")"
+      else
+        notify_user "Open queue failed"
+        return 1
+      fi
+      ;;
+    action:queue_clear)
+      run_binary queue clear >/dev/null 2>&1 || true
+      notify_user "Queue cleared"
+      ;;
+    add:*)
+      # 来自 Universal Action：把 URL 存入本地书签，只发通知，不输出到 Large Type
+      local add_output
+      if add_output="$(run_binary add "${arg#add:}" 2>/dev/null)"; then
+        notify_user "$(extract_subtitle_from_json "$add_output")"
+      else
+        notify_user "Save failed"
+        return 1
+      fi
+      ;;
+    tag:*)
+      # 格式为 tag:<书签 id><TAB><逗号分隔的标签>
+      local tag_payload="${arg#tag:}"
+      local tag_output
+      if tag_output="$(run_binary tags add "${tag_payload%%$'\t'*}" "${tag_payload#*$'\t'}" 2>/dev/null)"; then
+        notify_user "$(extract_subtitle_from_json "$tag_output")"
+      else
+        notify_user "Tagging failed"
+        return 1
+      fi
+      ;;
+    pin:* | unpin:*)
+      local pin_output
+      if pin_output="$(run_binary "${arg%%:*}" "${arg#*:}" 2>/dev/null)"; then
+        notify_user "$(extract_subtitle_from_json "$pin_output")"
+      else
+        notify_user "Pin failed"
+        return 1
+      fi
+      ;;
+    plugin:*)
+      # 格式为 plugin:<插件名><TAB><书签 id>；插件脚本输出的第一行作为通知
+      local plugin_payload="${arg#plugin:}"
+      local plugin_output plugin_status=0
+      plugin_output="$(run_binary plugin "${plugin_payload%%$'\t'*}" "${plugin_payload#*$'\t'}" 2>/dev/null)" || plugin_status=$?
+      local plugin_msg
+      plugin_msg="$(extract_subtitle_from_json "$plugin_output")"
+      if [[ "$plugin_status" -eq 0 ]]; then
+        notify_user "${plugin_msg:-Done}"
+      else
+        notify_user "${plugin_msg:-Plugin failed}"
+        return 1
+      fi
+      ;;
+    delete:*)
+      local delete_output
+      if delete_output="$(run_binary delete "${arg#delete:}" 2>/dev/null)"; then
+        notify_user "$(extract_subtitle_from_json "$delete_output")"
+      else
+        notify_user "Delete failed"
+        return 1
+      fi
+      ;;
+    restore:*)
+      local restore_output
+      if restore_output="$(run_binary restore "${arg#restore:}" 2>/dev/null)"; then
+        notify_user "$(extract_subtitle_from_json "$restore_output")"
+      else
+        notify_user "Restore failed"
+        return 1
+      fi
+      ;;
+    qr:*)
+      # 用 Quick Look 显示二维码；生成失败时输出 URL，交给 Large Type 显示
+      local qr_url="${arg#qr:}"
+      local qr_path
+      if qr_path="$(run_binary qr "$qr_url" 2>/dev/null)" && [[ -f "$qr_path" ]]; then
+        qlmanage -p "$qr_path" >/dev/null 2>&1 &
+        disown || true
+      else
+        printf '%s' "$qr_url"
+      fi
+      ;;
     action:refresh)
       local refresh_output
       if refresh_output="$(run_binary refresh 2>/dev/null)"; then
@@ -87,6 +232,10 @@ dispatch_action() {
         return 1
       fi
       ;;
+    action:full_disk_access)
+      # 读取 Safari 书签需要 Alfred 拥有完全磁盘访问权限
+      open "x-apple.systempreferences:com.apple.preference.security?Privacy_AllFiles"
+      ;;
     action:open_readme)
       open "$WORKFLOW_DIR/README.md"
       ;;
@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OpenFlags};
+
+/// 归档格式版本；结构变化时递增，恢复时拒绝更新版本写出的归档
+const ARCHIVE_VERSION: i64 = 1;
+
+/// 纳入备份的本地数据库（均位于数据目录）：索引元数据与本地书签、回收站、标签、打开记录、搜索历史、链接检查结果
+pub const BACKUP_DATABASES: &[&str] = &[
+    "bookmarks.db",
+    "tags.db",
+    "usage.db",
+    "history.db",
+    "links.db",
+];
+
+/// 把数据目录中的数据库打包成单个归档 `<dir>/alfred-chrome-bookmarks-<YYYYMMDD-HHMMSS>.backup`
+///
+/// 归档本身是 SQLite 文件：`meta` 记录格式版本与创建时间，`files` 按文件名保存各数据库的快照。
+/// 快照用 `VACUUM INTO` 生成，不受 WAL 中未合并内容影响。返回归档路径与收录的文件名。
+pub fn create_backup(
+    data_dir: &Path,
+    out_dir: &Path,
+    created_at: i64,
+) -> Result<(PathBuf, Vec<String>), Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(out_dir)?;
+    let path = out_dir.join(format!(
+        "alfred-chrome-bookmarks-{}.backup",
+        archive_timestamp(created_at)
+    ));
+    let tmp = path.with_extension("backup.tmp");
+    let _ = std::fs::remove_file(&tmp);
+
+    let archive = Connection::open(&tmp)?;
+    archive.execute_batch(
+        "CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+         CREATE TABLE files (name TEXT PRIMARY KEY, data BLOB NOT NULL);",
+    )?;
+    archive.execute(
+        "INSERT INTO meta (key, value) VALUES ('format_version', ?1), ('created_at', ?2)",
+        params![ARCHIVE_VERSION.to_string(), created_at.to_string()],
+    )?;
+
+    let mut included = Vec::new();
+    for name in BACKUP_DATABASES {
+        let source = data_dir.join(name);
+        if !source.is_file() {
+            continue;
+        }
+        let snapshot = out_dir.join(format!("{}.snapshot", name));
+        let _ = std::fs::remove_file(&snapshot);
+        // 加密库的快照仍以同一密钥加密
+        let key =
+            crate::db_crypt::key().filter(|_| crate::db_crypt::ENCRYPTED_DATABASES.contains(name));
+        crate::db_crypt::open_with_flags(&source, OpenFlags::SQLITE_OPEN_READ_ONLY, key)?
+            .execute("VACUUM INTO ?1", params![snapshot.to_string_lossy()])?;
+        let data = std::fs::read(&snapshot);
+        let _ = std::fs::remove_file(&snapshot);
+        archive.execute(
+            "INSERT INTO files (name, data) VALUES (?1, ?2)",
+            params![name, data?],
+        )?;
+        included.push(name.to_string());
+    }
+    drop(archive);
+
+    std::fs::rename(&tmp, &path)?;
+    Ok((path, included))
+}
+
+/// 用归档覆盖数据目录中的数据库，返回恢复的文件名
+///
+/// 被覆盖的文件先改名为 `<文件名>.pre-restore`，误恢复时还能找回；归档中不认识的文件名会被忽略。
+pub fn restore_backup(
+    archive: &Path,
+    data_dir: &Path,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let archive = Connection::open_with_flags(archive, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let version: i64 = archive
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'format_version'",
+            [],
+            |row| row.get::<_, String>(0),
+        )?
+        .parse()?;
+    if version > ARCHIVE_VERSION {
+        return Err(format!("备份格式版本 {} 过新，请升级后再恢复", version).into());
+    }
+
+    let mut stmt = archive.prepare("SELECT name, data FROM files ORDER BY name")?;
+    let files = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    std::fs::create_dir_all(data_dir)?;
+    let mut restored = Vec::new();
+    for (name, data) in files {
+        if !BACKUP_DATABASES.contains(&name.as_str()) {
+            continue;
+        }
+        let target = data_dir.join(&name);
+        let tmp = data_dir.join(format!("{}.restore-tmp", name));
+        std::fs::write(&tmp, data)?;
+        if target.exists() {
+            std::fs::rename(&target, data_dir.join(format!("{}.pre-restore", name)))?;
+        }
+        // 旧数据库的 WAL 不属于新文件，留着会在下次打开时被错误回放
+        for suffix in ["-wal", "-shm"] {
+            let _ = std::fs::remove_file(data_dir.join(format!("{}{}", name, suffix)));
+        }
+        std::fs::rename(&tmp, &target)?;
+        restored.push(name);
+    }
+    Ok(restored)
+}
+
+/// Unix 秒转为 `YYYYMMDD-HHMMSS`（UTC），用于归档文件名
+fn archive_timestamp(unix_secs: i64) -> String {
+    let date = crate::report::format_date(unix_secs).replace('-', "");
+    let secs_of_day = unix_secs.rem_euclid(86_400);
+    format!(
+        "{}-{:02}{:02}{:02}",
+        date,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::TagManager;
+    use tempfile::tempdir;
+
+    #[test]
+    fn backup_round_trips_tags_and_keeps_previous_files() {
+        let dir = tempdir().expect("tempdir");
+        let data_dir = dir.path().join("data");
+        std::fs::create_dir_all(&data_dir).expect("mkdir");
+        let tags = TagManager::new(data_dir.join("tags.db")).expect("tags");
+        tags.add_tags("chrome:Default:1", "https://docs.rs", &["rust".to_string()])
+            .expect("tag");
+        drop(tags);
+
+        let (archive, included) =
+            create_backup(&data_dir, &dir.path().join("backups"), 1_700_000_000).expect("backup");
+        assert_eq!(included, vec!["tags.db".to_string()]);
+        assert!(archive.ends_with("alfred-chrome-bookmarks-20231114-221320.backup"));
+
+        std::fs::remove_file(data_dir.join("tags.db")).expect("remove");
+        TagManager::new(data_dir.join("tags.db")).expect("fresh tags");
+        let restored = restore_backup(&archive, &data_dir).expect("restore");
+        assert_eq!(restored, vec!["tags.db".to_string()]);
+        assert!(data_dir.join("tags.db.pre-restore").exists());
+
+        let tags = TagManager::new(data_dir.join("tags.db")).expect("tags");
+        assert_eq!(
+            tags.tags_for("chrome:Default:1").expect("tags for"),
+            vec!["rust".to_string()]
+        );
+    }
+}
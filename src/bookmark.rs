@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
@@ -14,15 +15,74 @@ pub struct ChromeBookmark {
     pub date_added: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub folder_path: Option<String>,
-    /// 预计算的小写名称，用于加速搜索
+    /// 解析书签文件时预计算的小写名称；从索引或缓存读出时留空，经 `lower_name` 按需计算
     #[serde(skip)]
     pub name_lower: String,
-    /// 预计算的小写URL
+    /// 预计算的小写URL（同上，经 `lower_url` 读取）
     #[serde(skip)]
     pub url_lower: String,
-    /// 预计算的小写文件夹路径
+    /// 预计算的小写文件夹路径（同上，经 `lower_folder_path` 读取）
     #[serde(skip)]
     pub folder_path_lower: Option<String>,
+    /// 来源浏览器（如 chrome、brave）
+    #[serde(default)]
+    pub browser: String,
+    /// 来源 profile 名称
+    #[serde(default)]
+    pub profile: String,
+    /// 浏览器为书签分配的 GUID（Chromium 的 `guid`、Firefox 的 `moz_bookmarks.guid`），跨重排、跨同步不变
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guid: Option<String>,
+}
+
+impl ChromeBookmark {
+    /// 小写名称：有预计算值时直接借用，否则现算；只有逐条打分、排序的路径才需要
+    pub fn lower_name(&self) -> Cow<'_, str> {
+        lowered(&self.name_lower, &self.name)
+    }
+
+    pub fn lower_url(&self) -> Cow<'_, str> {
+        lowered(&self.url_lower, &self.url)
+    }
+
+    pub fn lower_folder_path(&self) -> Option<Cow<'_, str>> {
+        match (&self.folder_path_lower, &self.folder_path) {
+            (Some(lower), _) => Some(Cow::Borrowed(lower)),
+            (None, Some(path)) => Some(Cow::Owned(path.to_lowercase())),
+            (None, None) => None,
+        }
+    }
+}
+
+/// 测试用书签：小写字段按输入预计算，来源与 GUID 留空；需要其他字段时用 `..test_bookmark(..)` 覆盖
+#[cfg(test)]
+pub(crate) fn test_bookmark(
+    id: &str,
+    name: &str,
+    url: &str,
+    folder: Option<&str>,
+) -> ChromeBookmark {
+    ChromeBookmark {
+        id: id.to_string(),
+        name: name.to_string(),
+        url: url.to_string(),
+        date_added: "0".to_string(),
+        folder_path: folder.map(ToString::to_string),
+        name_lower: name.to_lowercase(),
+        url_lower: url.to_lowercase(),
+        folder_path_lower: folder.map(str::to_lowercase),
+        browser: String::new(),
+        profile: String::new(),
+        guid: None,
+    }
+}
+
+fn lowered<'a>(precomputed: &'a str, raw: &str) -> Cow<'a, str> {
+    if precomputed.is_empty() && !raw.is_empty() {
+        Cow::Owned(raw.to_lowercase())
+    } else {
+        Cow::Borrowed(precomputed)
+    }
 }
 
 /// Chrome书签文件的根结构
@@ -50,6 +110,8 @@ pub struct BookmarkNode {
     #[serde(default)]
     pub date_added: Option<String>,
     #[serde(default)]
+    pub guid: Option<String>,
+    #[serde(default)]
     pub children: Vec<BookmarkNode>,
 }
 
@@ -57,20 +119,27 @@ pub struct BookmarkNode {
 enum BrowserEngine {
     Chromium,
     Firefox,
+    /// 数据目录在 `~/Library` 下而不是 Application Support，受“完全磁盘访问权限”保护
+    Safari,
 }
 
 #[derive(Clone, Copy)]
 struct BrowserSource {
     key: &'static str,
+    /// 展示用名称（来源徽标等）
+    name: &'static str,
     aliases: &'static [&'static str],
     roots: &'static [&'static str],
     dir_hints: &'static [&'static str],
+    /// macOS 应用的 bundle id，用于识别最前面的浏览器
+    bundle_ids: &'static [&'static str],
     engine: BrowserEngine,
 }
 
 const BROWSER_SOURCES: &[BrowserSource] = &[
     BrowserSource {
         key: "chrome",
+        name: "Chrome",
         aliases: &["google-chrome", "google"],
         roots: &[
             "Google/Chrome",
@@ -79,10 +148,17 @@ const BROWSER_SOURCES: &[BrowserSource] = &[
             "Google/Chrome Canary",
         ],
         dir_hints: &[],
+        bundle_ids: &[
+            "com.google.Chrome",
+            "com.google.Chrome.beta",
+            "com.google.Chrome.dev",
+            "com.google.Chrome.canary",
+        ],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "brave",
+        name: "Brave",
         aliases: &["brave-browser"],
         roots: &[
             "BraveSoftware/Brave-Browser",
@@ -90,10 +166,16 @@ const BROWSER_SOURCES: &[BrowserSource] = &[
             "BraveSoftware/Brave-Browser-Nightly",
         ],
         dir_hints: &[],
+        bundle_ids: &[
+            "com.brave.Browser",
+            "com.brave.Browser.beta",
+            "com.brave.Browser.nightly",
+        ],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "edge",
+        name: "Edge",
         aliases: &["microsoft-edge", "msedge"],
         roots: &[
             "Microsoft Edge",
@@ -102,31 +184,44 @@ const BROWSER_SOURCES: &[BrowserSource] = &[
             "Microsoft Edge Canary",
         ],
         dir_hints: &[],
+        bundle_ids: &[
+            "com.microsoft.edgemac",
+            "com.microsoft.edgemac.Beta",
+            "com.microsoft.edgemac.Dev",
+            "com.microsoft.edgemac.Canary",
+        ],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "chromium",
+        name: "Chromium",
         aliases: &[],
         roots: &["Chromium"],
         dir_hints: &[],
+        bundle_ids: &["org.chromium.Chromium"],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "vivaldi",
+        name: "Vivaldi",
         aliases: &[],
         roots: &["Vivaldi"],
         dir_hints: &[],
+        bundle_ids: &["com.vivaldi.Vivaldi"],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "arc",
+        name: "Arc",
         aliases: &[],
         roots: &["Arc", "The Browser Company/Arc"],
         dir_hints: &["arc"],
+        bundle_ids: &["company.thebrowser.Browser"],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "dia",
+        name: "Dia",
         aliases: &["dia-browser"],
         roots: &[
             "Dia",
@@ -135,57 +230,85 @@ const BROWSER_SOURCES: &[BrowserSource] = &[
             "The Browser Company/Dia Browser",
         ],
         dir_hints: &["dia"],
+        bundle_ids: &["company.thebrowser.dia"],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "opera",
+        name: "Opera",
         aliases: &["opera-stable"],
         roots: &["Opera", "com.operasoftware.Opera"],
         dir_hints: &[],
+        bundle_ids: &["com.operasoftware.Opera"],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "opera-developer",
+        name: "Opera Developer",
         aliases: &["opera-dev"],
         roots: &["com.operasoftware.OperaDeveloper"],
         dir_hints: &[],
+        bundle_ids: &["com.operasoftware.OperaDeveloper"],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "opera-next",
+        name: "Opera Next",
         aliases: &["opera-beta"],
         roots: &["com.operasoftware.OperaNext"],
         dir_hints: &[],
+        bundle_ids: &["com.operasoftware.OperaNext"],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "opera-gx",
+        name: "Opera GX",
         aliases: &["operagx"],
         roots: &["com.operasoftware.OperaGX"],
         dir_hints: &[],
+        bundle_ids: &["com.operasoftware.OperaGX"],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "sidekick",
+        name: "Sidekick",
         aliases: &[],
         roots: &["Sidekick"],
         dir_hints: &[],
+        bundle_ids: &["com.pushplaylabs.sidekick"],
         engine: BrowserEngine::Chromium,
     },
     BrowserSource {
         key: "firefox",
+        name: "Firefox",
         aliases: &["mozilla-firefox"],
         roots: &["Firefox", "Firefox Developer Edition", "Firefox Nightly"],
         dir_hints: &[],
+        bundle_ids: &[
+            "org.mozilla.firefox",
+            "org.mozilla.firefoxdeveloperedition",
+            "org.mozilla.nightly",
+        ],
         engine: BrowserEngine::Firefox,
     },
     BrowserSource {
         key: "zen",
+        name: "Zen",
         aliases: &["zen-browser"],
         roots: &["Zen", "zen", "Zen Browser"],
         dir_hints: &["zen"],
+        bundle_ids: &["app.zen-browser.zen"],
         engine: BrowserEngine::Firefox,
     },
+    BrowserSource {
+        key: "safari",
+        name: "Safari",
+        aliases: &["apple-safari"],
+        roots: &["Safari", "SafariTechnologyPreview"],
+        dir_hints: &[],
+        bundle_ids: &["com.apple.Safari", "com.apple.SafariTechnologyPreview"],
+        engine: BrowserEngine::Safari,
+    },
 ];
 
 impl ChromeBookmarks {
@@ -232,6 +355,9 @@ impl ChromeBookmarks {
                     url: url.clone(),
                     date_added: date_added.clone(),
                     folder_path: Some(folder_path_str),
+                    browser: String::new(),
+                    profile: String::new(),
+                    guid: node.guid.clone(),
                 });
             }
         } else if node.node_type == "folder" {
@@ -243,38 +369,97 @@ impl ChromeBookmarks {
     }
 }
 
-/// 使用缓存获取受支持浏览器书签路径，减少每次调用的目录扫描成本
-pub fn get_chrome_bookmarks_path_cached(cache_dir: &Path) -> Option<PathBuf> {
+/// 书签来源：一个浏览器 profile 对应的书签文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookmarkSource {
+    pub browser: String,
+    pub profile: String,
+    pub path: PathBuf,
+}
+
+/// 使用缓存获取书签来源（每个 profile 一项），减少每次调用的目录扫描成本
+/// `browsers` 为按优先级排列的浏览器 key；为空表示扫描全部受支持浏览器
+pub fn discover_bookmark_sources_cached(
+    cache_dir: &Path,
+    browsers: &[String],
+) -> Vec<BookmarkSource> {
     if let Some(configured) = resolve_configured_bookmarks_path() {
-        return Some(configured);
+        return vec![bookmark_source_for_path(configured)];
     }
 
-    let browser_key = resolve_configured_browser_key();
-    dirs::home_dir().and_then(|home| {
-        get_chrome_bookmarks_path_cached_from_home_for_browser(
-            &home,
-            cache_dir,
-            browser_key.as_deref(),
-        )
-    })
+    let mut sources = dirs::home_dir()
+        .map(|home| discover_bookmark_sources_cached_from_home(&home, cache_dir, browsers))
+        .unwrap_or_default();
+    // 来源缓存保存全部 profile，修改 profile 选择后无需 refresh 即生效
+    retain_profiles(&mut sources, &configured_profiles());
+    sources
 }
 
-fn get_chrome_bookmarks_path_from_home_for_browser(
-    home: &Path,
-    browser_key: Option<&str>,
-) -> Option<PathBuf> {
+/// 忽略缓存重新扫描，用于 refresh 时发现新建的 profile
+pub fn discover_bookmark_sources_fresh(
+    cache_dir: &Path,
+    browsers: &[String],
+) -> Vec<BookmarkSource> {
+    let _ = std::fs::remove_file(bookmark_sources_cache_file(cache_dir, browsers));
+    discover_bookmark_sources_cached(cache_dir, browsers)
+}
+
+fn discover_bookmark_sources_from_home(home: &Path, browsers: &[String]) -> Vec<BookmarkSource> {
     let app_support_dir = home.join("Library/Application Support");
-    let mut candidates = Vec::new();
-    collect_bookmark_candidates(&app_support_dir, browser_key, &mut candidates);
+    let mut sources = Vec::new();
+    collect_bookmark_sources(&app_support_dir, browsers, &mut sources);
+
+    // 按配置的浏览器优先级排列，未配置时按浏览器名排序
+    sources.sort_by(|a, b| {
+        (
+            browser_priority(browsers, &a.browser),
+            a.browser.as_str(),
+            a.profile.as_str(),
+            &a.path,
+        )
+            .cmp(&(
+                browser_priority(browsers, &b.browser),
+                b.browser.as_str(),
+                b.profile.as_str(),
+                &b.path,
+            ))
+    });
+    sources.dedup_by(|a, b| a.path == b.path);
+    disambiguate_profile_names(&mut sources);
+    sources
+}
 
-    select_latest_bookmarks(candidates)
+/// 同一浏览器的不同渠道（如 Chrome 与 Chrome Beta）可能出现同名 profile，追加序号区分
+fn disambiguate_profile_names(sources: &mut [BookmarkSource]) {
+    let mut seen: HashMap<(String, String), usize> = HashMap::new();
+    for source in sources.iter_mut() {
+        let count = seen
+            .entry((source.browser.clone(), source.profile.clone()))
+            .or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            source.profile = format!("{} ({})", source.profile, count);
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct BookmarksSourceCache {
-    path: String,
-    modified_nanos: u128,
-    size: u64,
+/// 要索引的 profile（显示名，已小写）；未设置或包含 `all` 时为空，表示同一浏览器的全部 profile
+fn configured_profiles() -> Vec<String> {
+    let profiles = std::env::var("ALFRED_CHROME_BOOKMARKS_PROFILES")
+        .map(|raw| crate::config::parse_list(&raw))
+        .unwrap_or_default();
+    if profiles.iter().any(|profile| profile == "all") {
+        return Vec::new();
+    }
+    profiles
+}
+
+/// 只保留显示名在 `profiles` 中的来源（不区分大小写）；`profiles` 为空时不过滤
+fn retain_profiles(sources: &mut Vec<BookmarkSource>, profiles: &[String]) {
+    if profiles.is_empty() {
+        return;
+    }
+    sources.retain(|source| profiles.contains(&source.profile.to_lowercase()));
 }
 
 fn resolve_configured_bookmarks_path() -> Option<PathBuf> {
@@ -287,37 +472,60 @@ fn resolve_configured_bookmarks_path() -> Option<PathBuf> {
     None
 }
 
-fn resolve_configured_browser_key() -> Option<String> {
-    let raw = std::env::var("ALFRED_CHROME_BOOKMARKS_BROWSER").ok()?;
-    let normalized = normalize_browser_identifier(&raw);
-    if normalized.is_empty() || normalized == "all" {
-        return None;
-    }
+/// 为手动指定的书签文件推断浏览器与 profile
+fn bookmark_source_for_path(path: PathBuf) -> BookmarkSource {
+    let path_str = path.to_string_lossy().to_string();
+    let browser = BROWSER_SOURCES
+        .iter()
+        .find(|source| {
+            source
+                .roots
+                .iter()
+                .any(|root| path_str.contains(&format!("/{}/", root)))
+        })
+        .map(|source| source.key)
+        .unwrap_or("custom");
+    let engine = match path.file_name().and_then(|name| name.to_str()) {
+        Some("places.sqlite") => BrowserEngine::Firefox,
+        Some(SAFARI_BOOKMARKS_FILE) => BrowserEngine::Safari,
+        _ => BrowserEngine::Chromium,
+    };
 
-    match find_browser_source(&normalized) {
-        Some(source) => Some(source.key.to_string()),
-        None => Some(normalized),
+    BookmarkSource {
+        browser: browser.to_string(),
+        profile: profile_display_name(&path, engine),
+        path,
     }
 }
 
-fn get_chrome_bookmarks_path_cached_from_home_for_browser(
+/// 浏览器在优先级列表中的位置；不在列表中（或未配置列表）的排在最后
+pub fn browser_priority(browsers: &[String], browser: &str) -> usize {
+    browsers
+        .iter()
+        .position(|key| key.eq_ignore_ascii_case(browser))
+        .unwrap_or(browsers.len())
+}
+
+fn discover_bookmark_sources_cached_from_home(
     home: &Path,
     cache_dir: &Path,
-    browser_key: Option<&str>,
-) -> Option<PathBuf> {
-    let cache_file = bookmarks_source_cache_file(cache_dir, browser_key);
-    if let Some(cached_path) = load_cached_bookmarks_source_path(&cache_file) {
-        return Some(cached_path);
+    browsers: &[String],
+) -> Vec<BookmarkSource> {
+    let cache_file = bookmark_sources_cache_file(cache_dir, browsers);
+    if let Some(cached) = load_cached_bookmark_sources(&cache_file) {
+        return cached;
     }
 
-    let discovered = get_chrome_bookmarks_path_from_home_for_browser(home, browser_key)?;
-    let _ = save_cached_bookmarks_source_path(&cache_file, &discovered);
-    Some(discovered)
+    let discovered = discover_bookmark_sources_from_home(home, browsers);
+    if !discovered.is_empty() {
+        let _ = save_cached_bookmark_sources(&cache_file, &discovered);
+    }
+    discovered
 }
 
-fn bookmarks_source_cache_file(cache_dir: &Path, browser_key: Option<&str>) -> PathBuf {
-    match browser_key {
-        Some(key) => {
+fn bookmark_sources_cache_file(cache_dir: &Path, browsers: &[String]) -> PathBuf {
+    match browsers.join(",") {
+        key if !key.is_empty() => {
             let safe_key = key
                 .chars()
                 .map(|ch| {
@@ -328,12 +536,49 @@ fn bookmarks_source_cache_file(cache_dir: &Path, browser_key: Option<&str>) -> P
                     }
                 })
                 .collect::<String>();
-            cache_dir.join(format!("bookmarks_source_path.{}.json", safe_key))
+            cache_dir.join(format!("bookmark_sources.{}.json", safe_key))
         }
-        None => cache_dir.join("bookmarks_source_path.json"),
+        _ => cache_dir.join("bookmark_sources.json"),
+    }
+}
+
+/// 将浏览器名或别名（如 google-chrome）规范为内部 key；未知名称原样（规范化后）返回
+pub fn canonical_browser_key(raw: &str) -> String {
+    let normalized = normalize_browser_identifier(raw);
+    match find_browser_source(&normalized) {
+        Some(source) => source.key.to_string(),
+        None => normalized,
     }
 }
 
+/// 浏览器 key 的展示名称（arc -> Arc）；未知 key 原样返回
+pub fn browser_display_name(key: &str) -> &str {
+    find_browser_source(key)
+        .map(|source| source.name)
+        .unwrap_or(key)
+}
+
+/// 浏览器 key 对应的全部 macOS bundle id；未知 key 返回空
+pub fn browser_bundle_ids(key: &str) -> &'static [&'static str] {
+    find_browser_source(key)
+        .map(|source| source.bundle_ids)
+        .unwrap_or(&[])
+}
+
+/// 由 macOS bundle id（如 com.google.Chrome）识别受支持的浏览器，返回其 key
+pub fn browser_key_for_bundle_id(bundle_id: &str) -> Option<&'static str> {
+    let bundle_id = bundle_id.trim();
+    BROWSER_SOURCES
+        .iter()
+        .find(|source| {
+            source
+                .bundle_ids
+                .iter()
+                .any(|id| id.eq_ignore_ascii_case(bundle_id))
+        })
+        .map(|source| source.key)
+}
+
 fn normalize_browser_identifier(raw: &str) -> String {
     raw.trim()
         .chars()
@@ -350,78 +595,143 @@ fn find_browser_source(identifier: &str) -> Option<&'static BrowserSource> {
         .find(|source| source.key == identifier || source.aliases.contains(&identifier))
 }
 
-fn collect_bookmark_candidates(
+fn collect_bookmark_sources(
     app_support_dir: &Path,
-    browser_key: Option<&str>,
-    candidates: &mut Vec<PathBuf>,
+    browsers: &[String],
+    sources: &mut Vec<BookmarkSource>,
 ) {
-    if let Some(key) = browser_key {
-        let Some(source) = find_browser_source(key) else {
-            return;
-        };
+    let selected: Vec<&BrowserSource> = if browsers.is_empty() {
+        BROWSER_SOURCES.iter().collect()
+    } else {
+        browsers
+            .iter()
+            .filter_map(|key| find_browser_source(key))
+            .collect()
+    };
+
+    for source in selected {
+        let mut candidates = Vec::new();
         for browser_root in source.roots {
-            collect_bookmarks_from_browser_root(
-                &app_support_dir.join(browser_root),
-                source.engine,
-                candidates,
-            );
+            match source.engine {
+                // 没有授权时读不到 Safari 书签；明确选择了 Safari 才保留该来源，以便提示授权
+                BrowserEngine::Safari => collect_safari_bookmarks(
+                    &app_support_dir
+                        .parent()
+                        .unwrap_or(app_support_dir)
+                        .join(browser_root),
+                    !browsers.is_empty(),
+                    &mut candidates,
+                ),
+                engine => collect_bookmarks_from_browser_root(
+                    &app_support_dir.join(browser_root),
+                    engine,
+                    &mut candidates,
+                ),
+            }
         }
-        if candidates.is_empty() && !source.dir_hints.is_empty() {
+        if !browsers.is_empty() && candidates.is_empty() && !source.dir_hints.is_empty() {
             collect_bookmarks_from_hints(
                 app_support_dir,
                 source.dir_hints,
                 source.engine,
-                candidates,
+                &mut candidates,
             );
         }
-        return;
+
+        sources.extend(candidates.into_iter().map(|path| BookmarkSource {
+            browser: source.key.to_string(),
+            profile: profile_display_name(&path, source.engine),
+            path,
+        }));
     }
+}
 
-    for source in BROWSER_SOURCES {
-        for browser_root in source.roots {
-            collect_bookmarks_from_browser_root(
-                &app_support_dir.join(browser_root),
-                source.engine,
-                candidates,
-            );
+/// profile 显示名：Chromium 优先使用 Local State 中用户设置的名称，Firefox 去掉目录名的随机前缀
+fn profile_display_name(bookmarks_path: &Path, engine: BrowserEngine) -> String {
+    let Some(profile_dir) = bookmarks_path.parent() else {
+        return "Default".to_string();
+    };
+    let dir_name = profile_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    match engine {
+        BrowserEngine::Chromium => {
+            if !is_chromium_profile_dir(&dir_name) {
+                return "Default".to_string();
+            }
+            profile_dir
+                .parent()
+                .and_then(|root| chromium_profile_names(root).remove(&dir_name))
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or(dir_name)
         }
+        BrowserEngine::Firefox => match dir_name.split_once('.') {
+            Some((_, name)) if !name.is_empty() => name.to_string(),
+            _ => dir_name,
+        },
+        BrowserEngine::Safari => "Default".to_string(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChromiumLocalState {
+    #[serde(default)]
+    profile: ChromiumProfiles,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChromiumProfiles {
+    #[serde(default)]
+    info_cache: HashMap<String, ChromiumProfileInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChromiumProfileInfo {
+    #[serde(default)]
+    name: String,
+}
+
+/// 读取 `Local State` 中的 profile 目录名 -> 显示名映射
+fn chromium_profile_names(user_data_root: &Path) -> HashMap<String, String> {
+    let local_state = user_data_root.join("Local State");
+    if !local_state.exists() {
+        return HashMap::new();
     }
+
+    std::fs::read(&local_state)
+        .ok()
+        .and_then(|data| serde_json::from_slice::<ChromiumLocalState>(&data).ok())
+        .map(|state| {
+            state
+                .profile
+                .info_cache
+                .into_iter()
+                .map(|(dir, info)| (dir, info.name))
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
-fn load_cached_bookmarks_source_path(cache_file: &Path) -> Option<PathBuf> {
+/// 缓存仅在所有来源文件仍存在时有效；新建 profile 需要 refresh 才会被发现
+fn load_cached_bookmark_sources(cache_file: &Path) -> Option<Vec<BookmarkSource>> {
     let data = std::fs::read(cache_file).ok()?;
-    let cache = serde_json::from_slice::<BookmarksSourceCache>(&data).ok()?;
-    let path = PathBuf::from(cache.path);
-    let metadata = std::fs::metadata(&path).ok()?;
-    let modified_nanos = metadata
-        .modified()
-        .ok()?
-        .duration_since(std::time::UNIX_EPOCH)
-        .ok()?
-        .as_nanos();
+    let sources = serde_json::from_slice::<Vec<BookmarkSource>>(&data).ok()?;
 
-    if modified_nanos == cache.modified_nanos && metadata.len() == cache.size {
-        Some(path)
+    if !sources.is_empty() && sources.iter().all(|source| source.path.exists()) {
+        Some(sources)
     } else {
         None
     }
 }
 
-fn save_cached_bookmarks_source_path(cache_file: &Path, path: &Path) -> std::io::Result<()> {
-    let metadata = std::fs::metadata(path)?;
-    let modified_nanos = metadata
-        .modified()
-        .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_nanos();
-
-    let cache = BookmarksSourceCache {
-        path: path.to_string_lossy().to_string(),
-        modified_nanos,
-        size: metadata.len(),
-    };
-    let bytes = serde_json::to_vec(&cache).map_err(|err| std::io::Error::other(err.to_string()))?;
+fn save_cached_bookmark_sources(
+    cache_file: &Path,
+    sources: &[BookmarkSource],
+) -> std::io::Result<()> {
+    let bytes =
+        serde_json::to_vec(sources).map_err(|err| std::io::Error::other(err.to_string()))?;
     write_atomic(cache_file, &bytes)
 }
 
@@ -450,6 +760,7 @@ fn collect_bookmarks_from_browser_root(
                 collect_firefox_bookmarks_from_profile_root(&profiles_root, candidates);
             }
         }
+        BrowserEngine::Safari => collect_safari_bookmarks(root, false, candidates),
     }
 }
 
@@ -509,6 +820,18 @@ fn collect_firefox_bookmarks_from_profile_root(root: &Path, candidates: &mut Vec
     }
 }
 
+/// `include_denied` 为真时，没有读取权限的 Bookmarks.plist 也作为来源，解析时再报告需要授权
+fn collect_safari_bookmarks(root: &Path, include_denied: bool, candidates: &mut Vec<PathBuf>) {
+    let bookmarks = root.join(SAFARI_BOOKMARKS_FILE);
+    match std::fs::File::open(&bookmarks) {
+        Ok(_) => candidates.push(bookmarks),
+        Err(err) if include_denied && err.kind() == std::io::ErrorKind::PermissionDenied => {
+            candidates.push(bookmarks)
+        }
+        Err(_) => {}
+    }
+}
+
 fn collect_bookmarks_from_hints(
     app_support_dir: &Path,
     hints: &[&str],
@@ -575,6 +898,11 @@ fn load_bookmarks_from_source(
 ) -> Result<Vec<ChromeBookmark>, Box<dyn std::error::Error>> {
     if path.file_name().is_some_and(|name| name == "places.sqlite") {
         load_firefox_bookmarks(path)
+    } else if path
+        .file_name()
+        .is_some_and(|name| name == SAFARI_BOOKMARKS_FILE)
+    {
+        load_safari_bookmarks(path)
     } else {
         let chrome_bookmarks = ChromeBookmarks::from_file(path.to_path_buf())?;
         Ok(chrome_bookmarks.extract_all_bookmarks())
@@ -618,7 +946,7 @@ fn load_firefox_bookmarks(path: &Path) -> Result<Vec<ChromeBookmark>, Box<dyn st
     };
 
     let mut stmt = connection.prepare(
-        "SELECT b.id, b.parent, b.title, p.url, b.dateAdded \
+        "SELECT b.id, b.parent, b.title, p.url, b.dateAdded, b.guid \
          FROM moz_bookmarks b \
          JOIN moz_places p ON b.fk = p.id \
          WHERE b.type = 1 AND p.url IS NOT NULL",
@@ -630,12 +958,13 @@ fn load_firefox_bookmarks(path: &Path) -> Result<Vec<ChromeBookmark>, Box<dyn st
             row.get::<_, Option<String>>(2)?,
             row.get::<_, String>(3)?,
             row.get::<_, i64>(4)?,
+            row.get::<_, Option<String>>(5)?,
         ))
     })?;
 
     let mut bookmarks = Vec::new();
     for row in rows {
-        let (id, parent, title, url, date_added) = row?;
+        let (id, parent, title, url, date_added, guid) = row?;
         let name = title.unwrap_or_else(|| url.clone());
         let folder_path = firefox_folder_path(parent, &folders, &roots);
 
@@ -648,6 +977,9 @@ fn load_firefox_bookmarks(path: &Path) -> Result<Vec<ChromeBookmark>, Box<dyn st
             url,
             date_added: date_added.to_string(),
             folder_path: Some(folder_path),
+            browser: String::new(),
+            profile: String::new(),
+            guid,
         });
     }
 
@@ -701,12 +1033,187 @@ fn firefox_folder_path(
     segments.join("/")
 }
 
+const SAFARI_BOOKMARKS_FILE: &str = "Bookmarks.plist";
+
+/// plist 日期（自 2001-01-01 起的秒数）与 Unix 纪元之差
+const APPLE_EPOCH_OFFSET_SECS: f64 = 978_307_200.0;
+
+/// 读取 Safari 书签被系统拒绝：终端或 Alfred 没有“完全磁盘访问权限”
+#[derive(Debug)]
+pub struct FullDiskAccessError {
+    pub path: PathBuf,
+}
+
+impl FullDiskAccessError {
+    const MESSAGE: &'static str = "没有读取 Safari 书签的权限（需要完全磁盘访问权限）";
+}
+
+impl std::fmt::Display for FullDiskAccessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", Self::MESSAGE, self.path.display())
+    }
+}
+
+impl std::error::Error for FullDiskAccessError {}
+
+/// 解析错误跨线程后只剩字符串，按固定的提示文本识别
+pub fn is_full_disk_access_error(message: &str) -> bool {
+    message.contains(FullDiskAccessError::MESSAGE)
+}
+
+/// Safari 书签文件的读取错误：权限被拒绝时换成需要授权的提示
+fn safari_read_error(path: &Path, err: std::io::Error) -> Box<dyn std::error::Error> {
+    if err.kind() == std::io::ErrorKind::PermissionDenied {
+        Box::new(FullDiskAccessError {
+            path: path.to_path_buf(),
+        })
+    } else {
+        Box::new(err)
+    }
+}
+
+fn load_safari_bookmarks(path: &Path) -> Result<Vec<ChromeBookmark>, Box<dyn std::error::Error>> {
+    let data = std::fs::read(path).map_err(|err| safari_read_error(path, err))?;
+    let root = crate::plist::parse(&data)?;
+    let mut bookmarks = Vec::new();
+    collect_safari_nodes(&root, None, &mut bookmarks);
+    Ok(bookmarks)
+}
+
+/// 递归展开 Safari 书签树：`WebBookmarkTypeList` 为目录，`WebBookmarkTypeLeaf` 为书签，
+/// `WebBookmarkTypeProxy`（历史记录入口）跳过。`folder` 为 None 表示当前在根节点
+fn collect_safari_nodes(
+    node: &crate::plist::Value,
+    folder: Option<&str>,
+    bookmarks: &mut Vec<ChromeBookmark>,
+) {
+    let title = node
+        .get("Title")
+        .and_then(crate::plist::Value::as_str)
+        .unwrap_or_default();
+    match node
+        .get("WebBookmarkType")
+        .and_then(crate::plist::Value::as_str)
+    {
+        Some("WebBookmarkTypeList") => {
+            let path = match folder {
+                None if title.is_empty() => None,
+                None => Some(safari_root_display_name(title).to_string()),
+                Some(parent) => Some(format!("{}/{}", parent, title)),
+            };
+            for child in node
+                .get("Children")
+                .and_then(crate::plist::Value::as_array)
+                .unwrap_or_default()
+            {
+                collect_safari_nodes(child, path.as_deref(), bookmarks);
+            }
+        }
+        Some("WebBookmarkTypeLeaf") => {
+            let Some(url) = node
+                .get("URLString")
+                .and_then(crate::plist::Value::as_str)
+                .filter(|url| !url.is_empty())
+            else {
+                return;
+            };
+            let name = node
+                .get("URIDictionary")
+                .and_then(|uri| uri.get("title"))
+                .and_then(crate::plist::Value::as_str)
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or(url)
+                .to_string();
+            let guid = node
+                .get("WebBookmarkUUID")
+                .and_then(crate::plist::Value::as_str)
+                .map(ToString::to_string);
+            // 只有阅读列表记录添加时间；普通书签视为未知
+            let date_added = node
+                .get("ReadingList")
+                .and_then(|reading| reading.get("DateAdded"))
+                .and_then(crate::plist::Value::as_date)
+                .map(|secs| ((secs + APPLE_EPOCH_OFFSET_SECS) as i64).to_string())
+                .unwrap_or_else(|| "0".to_string());
+            let folder_path = folder.unwrap_or("Safari书签").to_string();
+
+            bookmarks.push(ChromeBookmark {
+                // Safari 书签没有数字 id，以 UUID 代替；缺失时退回树中的顺序
+                id: guid.clone().unwrap_or_else(|| bookmarks.len().to_string()),
+                name_lower: name.to_lowercase(),
+                url_lower: url.to_lowercase(),
+                folder_path_lower: Some(folder_path.to_lowercase()),
+                name,
+                url: url.to_string(),
+                date_added,
+                folder_path: Some(folder_path),
+                browser: String::new(),
+                profile: String::new(),
+                guid,
+            });
+        }
+        _ => {}
+    }
+}
+
+fn safari_root_display_name(title: &str) -> &str {
+    match title {
+        "BookmarksBar" => "个人收藏",
+        "BookmarksMenu" => "书签菜单",
+        "com.apple.ReadingList" => "阅读列表",
+        _ => title,
+    }
+}
+
 fn matches_hint_prefix(dir_name_lower: &str, hints: &[&str]) -> bool {
     hints
         .iter()
         .any(|hint| dir_name_lower.starts_with(&hint.to_ascii_lowercase()))
 }
 
+/// 支持 `--profile-directory` 的浏览器：数据目录（相对 Application Support）-> 应用名
+const PROFILE_LAUNCH_APPS: &[(&str, &str)] = &[
+    ("Google/Chrome", "Google Chrome"),
+    ("Google/Chrome Beta", "Google Chrome Beta"),
+    ("Google/Chrome Dev", "Google Chrome Dev"),
+    ("Google/Chrome Canary", "Google Chrome Canary"),
+    ("BraveSoftware/Brave-Browser", "Brave Browser"),
+    ("BraveSoftware/Brave-Browser-Beta", "Brave Browser Beta"),
+    (
+        "BraveSoftware/Brave-Browser-Nightly",
+        "Brave Browser Nightly",
+    ),
+    ("Microsoft Edge", "Microsoft Edge"),
+    ("Microsoft Edge Beta", "Microsoft Edge Beta"),
+    ("Microsoft Edge Dev", "Microsoft Edge Dev"),
+    ("Microsoft Edge Canary", "Microsoft Edge Canary"),
+    ("Chromium", "Chromium"),
+    ("Vivaldi", "Vivaldi"),
+];
+
+/// 在书签所属 profile 中打开所需的信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileLaunch {
+    /// 交给 `open -a` 的应用名
+    pub app: &'static str,
+    /// profile 目录名（如 `Profile 1`），即 `--profile-directory` 的值
+    pub profile_dir: String,
+}
+
+/// 根据书签文件路径推断所属浏览器与 profile 目录；不支持按 profile 打开的浏览器返回 None
+pub fn profile_launch_for(bookmarks_path: &Path) -> Option<ProfileLaunch> {
+    let profile_path = bookmarks_path.parent()?;
+    let profile_dir = profile_path.file_name()?.to_string_lossy().to_string();
+    if !is_chromium_profile_dir(&profile_dir) {
+        return None;
+    }
+    let user_data_root = profile_path.parent()?;
+    PROFILE_LAUNCH_APPS
+        .iter()
+        .find(|(root, _)| user_data_root.ends_with(root))
+        .map(|(_, app)| ProfileLaunch { app, profile_dir })
+}
+
 fn is_chromium_profile_dir(name: &str) -> bool {
     name == "Default"
         || name == "Guest Profile"
@@ -715,40 +1222,82 @@ fn is_chromium_profile_dir(name: &str) -> bool {
         || name.starts_with("Person ")
 }
 
-fn select_latest_bookmarks(mut candidates: Vec<PathBuf>) -> Option<PathBuf> {
-    if candidates.is_empty() {
+/// Chromium 的 date_added 为自 1601-01-01 起的微秒数（WebKit 纪元）
+const WEBKIT_EPOCH_OFFSET_SECS: i64 = 11_644_473_600;
+
+/// 按数量级区分 date_added 单位的下限：Unix 微秒 / 毫秒（均约为 1973 年以后）
+const UNIX_MICROS_MIN: i64 = 100_000_000_000_000;
+const UNIX_MILLIS_MIN: i64 = 100_000_000_000;
+
+/// 将 date_added 解析为 Unix 秒
+///
+/// Chromium 使用 WebKit 纪元微秒，Firefox 使用 Unix 纪元微秒，导入的书签偶有 Unix 毫秒或秒；
+/// 1970 年以后的值在四种单位下数量级互不重叠，据此区分。
+pub fn date_added_to_unix_secs(raw: &str) -> Option<i64> {
+    let value: i64 = raw.trim().parse().ok()?;
+    if value <= 0 {
         return None;
     }
 
-    let mut selected: Option<(std::time::SystemTime, u64, PathBuf)> = None;
+    Some(if value >= WEBKIT_EPOCH_OFFSET_SECS * 1_000_000 {
+        value / 1_000_000 - WEBKIT_EPOCH_OFFSET_SECS
+    } else if value >= UNIX_MICROS_MIN {
+        value / 1_000_000
+    } else if value >= UNIX_MILLIS_MIN {
+        value / 1_000
+    } else {
+        value
+    })
+}
 
-    for path in candidates.drain(..) {
-        let metadata = match std::fs::metadata(&path) {
-            Ok(meta) => meta,
-            Err(_) => continue,
-        };
-        let modified = metadata
-            .modified()
-            .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-        let size = metadata.len();
-
-        match selected {
-            None => selected = Some((modified, size, path)),
-            Some((best_time, best_size, _)) => {
-                if modified > best_time || (modified == best_time && size > best_size) {
-                    selected = Some((modified, size, path));
-                }
-            }
-        }
-    }
+/// 与 `date_added_to_unix_secs` 相同规则的 SQL 表达式，`column` 为 date_added 列
+pub fn date_added_unix_secs_sql(column: &str) -> String {
+    let value = format!("CAST({} AS INTEGER)", column);
+    format!(
+        "CASE WHEN {v} >= {webkit} THEN {v} / 1000000 - {offset} \
+         WHEN {v} >= {micros} THEN {v} / 1000000 \
+         WHEN {v} >= {millis} THEN {v} / 1000 ELSE {v} END",
+        v = value,
+        webkit = WEBKIT_EPOCH_OFFSET_SECS * 1_000_000,
+        offset = WEBKIT_EPOCH_OFFSET_SECS,
+        micros = UNIX_MICROS_MIN,
+        millis = UNIX_MILLIS_MIN
+    )
+}
 
-    selected.map(|(_, _, path)| path)
+/// Unix 秒转为 Chromium 格式的 date_added（WebKit 纪元微秒），用于本地添加的书签
+pub fn unix_secs_to_date_added(secs: i64) -> String {
+    ((secs + WEBKIT_EPOCH_OFFSET_SECS) * 1_000_000).to_string()
+}
+
+/// 所有来源的组合指纹：任一来源变化或来源增减都会触发重建
+pub fn compute_sources_fingerprint(
+    sources: &[BookmarkSource],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let parts = sources
+        .iter()
+        .map(|source| {
+            compute_bookmarks_fingerprint(&source.path)
+                .map(|fingerprint| format!("{}:{}:{}", source.browser, source.profile, fingerprint))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(parts.join("|"))
 }
 
 pub fn compute_bookmarks_fingerprint(
     bookmarks_path: &Path,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let metadata = std::fs::metadata(bookmarks_path)?;
+    let metadata =
+        std::fs::metadata(bookmarks_path).map_err(|err| -> Box<dyn std::error::Error> {
+            if bookmarks_path
+                .file_name()
+                .is_some_and(|name| name == SAFARI_BOOKMARKS_FILE)
+            {
+                safari_read_error(bookmarks_path, err)
+            } else {
+                Box::new(err)
+            }
+        })?;
     let mtime_nanos = metadata
         .modified()?
         .duration_since(std::time::UNIX_EPOCH)?
@@ -764,8 +1313,26 @@ pub fn compute_bookmarks_fingerprint(
     ))
 }
 
+/// 压缩缓存文件的开头标记，其后为 zstd 压缩的 JSON；没有该标记的是旧版未压缩缓存
+/// 书签结构新增需要重新解析才能得到的字段（如 GUID）时递增版本号，旧缓存因解析失败而作废
+const CACHE_MAGIC: &[u8] = b"ACBZ2\n";
+const CACHE_ZSTD_LEVEL: i32 = 3;
+
+fn encode_cache(json: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut data = CACHE_MAGIC.to_vec();
+    data.extend(zstd::encode_all(json, CACHE_ZSTD_LEVEL)?);
+    Ok(data)
+}
+
+fn decode_cache(data: Vec<u8>) -> Option<Vec<u8>> {
+    match data.strip_prefix(CACHE_MAGIC) {
+        Some(compressed) => zstd::decode_all(compressed).ok(),
+        None => Some(data),
+    }
+}
+
 /// 带mtime缓存的书签加载器
-/// 将解析后的书签序列化到本地缓存文件，只有Chrome书签文件变化时才重新解析
+/// 将解析后的书签序列化并以 zstd 压缩到本地缓存文件，只有Chrome书签文件变化时才重新解析
 pub struct BookmarkCache {
     cache_path: PathBuf,
     mtime_path: PathBuf,
@@ -784,12 +1351,12 @@ impl BookmarkCache {
         let _ = std::fs::remove_file(&self.mtime_path);
     }
 
-    /// 加载书签，使用缓存（如果Chrome书签文件未变化）
+    /// 加载所有来源的书签，使用缓存（如果书签文件均未变化）
     pub fn load(
         &self,
-        bookmarks_path: &Path,
+        sources: &[BookmarkSource],
     ) -> Result<Vec<ChromeBookmark>, Box<dyn std::error::Error>> {
-        let source_fingerprint = Self::fingerprint(bookmarks_path)?;
+        let source_fingerprint = compute_sources_fingerprint(sources)?;
 
         // 检查缓存是否仍然有效
         if let Ok(cached_fingerprint) = std::fs::read_to_string(&self.mtime_path) {
@@ -800,20 +1367,44 @@ impl BookmarkCache {
             }
         }
 
-        // 缓存失效，重新解析
-        let bookmarks = match load_bookmarks_from_source(bookmarks_path) {
-            Ok(parsed) => parsed,
-            Err(err) => {
-                if let Some(bookmarks) = self.load_cached() {
-                    return Ok(bookmarks);
+        // 缓存失效，重新解析；单个来源解析失败时沿用该来源上次缓存的结果
+        let mut bookmarks = Vec::new();
+        let mut cached: Option<Vec<ChromeBookmark>> = None;
+        let mut used_fallback = false;
+        for (source, parsed) in sources.iter().zip(load_sources_parallel(sources)) {
+            match parsed {
+                Ok(parsed) => bookmarks.extend(parsed),
+                Err(err) => {
+                    if cached.is_none() {
+                        cached = self.load_cached();
+                    }
+                    let Some(previous) = cached.as_ref() else {
+                        return Err(err.into());
+                    };
+                    bookmarks.extend(
+                        previous
+                            .iter()
+                            .filter(|bookmark| {
+                                bookmark.browser == source.browser
+                                    && bookmark.profile == source.profile
+                            })
+                            .cloned(),
+                    );
+                    used_fallback = true;
                 }
-                return Err(err);
             }
-        };
+        }
+
+        if used_fallback {
+            return Ok(bookmarks);
+        }
 
         // 写入缓存（忽略写入失败，不影响功能）
-        if let Ok(json) = serde_json::to_vec(&bookmarks) {
-            let _ = write_atomic(&self.cache_path, &json);
+        if let Ok(data) = serde_json::to_vec(&bookmarks)
+            .map_err(std::io::Error::from)
+            .and_then(|json| encode_cache(&json))
+        {
+            let _ = write_atomic(&self.cache_path, &data);
         }
         let _ = write_atomic(&self.mtime_path, source_fingerprint.as_bytes());
 
@@ -821,25 +1412,56 @@ impl BookmarkCache {
     }
 
     fn load_cached(&self) -> Option<Vec<ChromeBookmark>> {
-        let cached_data = std::fs::read(&self.cache_path).ok()?;
-        let bookmarks = serde_json::from_slice::<Vec<ChromeBookmark>>(&cached_data).ok()?;
+        let cached_data = decode_cache(std::fs::read(&self.cache_path).ok()?)?;
+        // 小写字段不写入缓存，搜索时按需计算
+        serde_json::from_slice::<Vec<ChromeBookmark>>(&cached_data).ok()
+    }
+}
 
-        let bookmarks = bookmarks
-            .into_iter()
-            .map(|mut b| {
-                b.name_lower = b.name.to_lowercase();
-                b.url_lower = b.url.to_lowercase();
-                b.folder_path_lower = b.folder_path.as_ref().map(|p| p.to_lowercase());
-                b
-            })
-            .collect();
+/// 直接解析单个来源（不经过缓存），用于只刷新发生变化的来源
+pub fn load_source_bookmarks(
+    source: &BookmarkSource,
+) -> Result<Vec<ChromeBookmark>, Box<dyn std::error::Error>> {
+    Ok(load_bookmarks_from_source(&source.path)?
+        .into_iter()
+        .map(|bookmark| with_source(bookmark, source))
+        .collect())
+}
 
-        Some(bookmarks)
+/// 并行解析多个来源，每个来源一个线程，结果按 `sources` 的顺序返回；只有一个来源时直接在当前线程解析
+pub fn load_sources_parallel(
+    sources: &[BookmarkSource],
+) -> Vec<Result<Vec<ChromeBookmark>, String>> {
+    // 解析错误不是 Send，跨线程前先转成字符串
+    let parse = |source: &BookmarkSource| {
+        load_source_bookmarks(source).map_err(|err| format!("{}: {}", source.path.display(), err))
+    };
+    if sources.len() <= 1 {
+        return sources.iter().map(parse).collect();
     }
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = sources
+            .iter()
+            .map(|source| scope.spawn(move || parse(source)))
+            .collect();
+        handles
+            .into_iter()
+            .zip(sources)
+            .map(|(handle, source)| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(format!("{}: 解析线程异常退出", source.path.display())))
+            })
+            .collect()
+    })
+}
 
-    fn fingerprint(bookmarks_path: &Path) -> Result<String, Box<dyn std::error::Error>> {
-        compute_bookmarks_fingerprint(bookmarks_path)
-    }
+/// 标记书签来源；不同 profile 的书签 id 会重复，因此 id 带上来源前缀
+fn with_source(mut bookmark: ChromeBookmark, source: &BookmarkSource) -> ChromeBookmark {
+    bookmark.id = format!("{}:{}:{}", source.browser, source.profile, bookmark.id);
+    bookmark.browser = source.browser.clone();
+    bookmark.profile = source.profile.clone();
+    bookmark
 }
 
 fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
@@ -860,6 +1482,10 @@ mod tests {
     use std::fs;
     use tempfile::tempdir;
 
+    fn browsers(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(ToString::to_string).collect()
+    }
+
     fn write_bookmarks(path: &Path, include_other: bool) {
         let other_section = if include_other {
             r#"
@@ -898,6 +1524,7 @@ mod tests {
         {{
           "type": "url",
           "id": "10",
+          "guid": "0d6a3c4e-1b2f-4c5d-8e9f-a0b1c2d3e4f5",
           "name": "Rust",
           "url": "https://rust-lang.org",
           "date_added": "1"
@@ -938,6 +1565,10 @@ mod tests {
         let rust = bookmarks.iter().find(|b| b.id == "10").unwrap();
         assert_eq!(rust.name_lower, "rust");
         assert_eq!(rust.url_lower, "https://rust-lang.org");
+        assert_eq!(
+            rust.guid.as_deref(),
+            Some("0d6a3c4e-1b2f-4c5d-8e9f-a0b1c2d3e4f5")
+        );
 
         let nested = bookmarks.iter().find(|b| b.id == "12").unwrap();
         assert_eq!(
@@ -948,10 +1579,24 @@ mod tests {
             nested.folder_path_lower.as_deref(),
             Some("书签栏/bookmark bar/sub")
         );
+
+        // 未预计算（从索引或缓存读出）时按需计算出同样的结果
+        let read_back = ChromeBookmark {
+            name_lower: String::new(),
+            url_lower: String::new(),
+            folder_path_lower: None,
+            ..nested.clone()
+        };
+        assert_eq!(read_back.lower_name(), nested.name_lower);
+        assert_eq!(read_back.lower_url(), nested.url_lower);
+        assert_eq!(
+            read_back.lower_folder_path().as_deref(),
+            nested.folder_path_lower.as_deref()
+        );
     }
 
     #[test]
-    fn get_chrome_bookmarks_path_from_home_selects_latest_profile() {
+    fn discover_bookmark_sources_returns_every_profile() {
         let dir = tempdir().expect("tempdir");
         let home = dir.path();
 
@@ -963,18 +1608,20 @@ mod tests {
         fs::create_dir_all(&profile_path).expect("create profile");
         fs::write(profile_path.join("Bookmarks"), "{\"bigger\":true}").expect("write profile");
 
-        let found_profile =
-            get_chrome_bookmarks_path_from_home_for_browser(home, None).expect("find latest");
-        assert!(found_profile.ends_with("Profile 1/Bookmarks"));
+        let sources = discover_bookmark_sources_from_home(home, &[]);
+        assert_eq!(sources.len(), 2);
+        assert!(sources.iter().all(|source| source.browser == "chrome"));
+        let profiles: Vec<&str> = sources.iter().map(|s| s.profile.as_str()).collect();
+        assert_eq!(profiles, vec!["Default", "Profile 1"]);
 
         fs::remove_file(profile_path.join("Bookmarks")).expect("remove profile");
-        let found_default =
-            get_chrome_bookmarks_path_from_home_for_browser(home, None).expect("find default");
-        assert!(found_default.ends_with("Default/Bookmarks"));
+        let sources = discover_bookmark_sources_from_home(home, &[]);
+        assert_eq!(sources.len(), 1);
+        assert!(sources[0].path.ends_with("Default/Bookmarks"));
     }
 
     #[test]
-    fn get_chrome_bookmarks_path_supports_arc_and_dia_roots() {
+    fn discover_bookmark_sources_supports_arc_and_dia_roots() {
         let dir = tempdir().expect("tempdir");
         let home = dir.path();
 
@@ -987,14 +1634,14 @@ mod tests {
         fs::create_dir_all(&dia_profile).expect("create dia profile");
         fs::write(dia_profile.join("Bookmarks"), "{\"size\":1}").expect("write dia bookmarks");
 
-        let found =
-            get_chrome_bookmarks_path_from_home_for_browser(home, None).expect("find bookmarks");
-        let found_str = found.to_string_lossy();
-        assert!(found_str.contains("/Arc/") || found_str.contains("/Dia/"));
+        let sources = discover_bookmark_sources_from_home(home, &[]);
+        let browsers: Vec<&str> = sources.iter().map(|s| s.browser.as_str()).collect();
+        assert_eq!(browsers, vec!["arc", "dia"]);
+        assert_eq!(sources[1].profile, "Profile 2");
     }
 
     #[test]
-    fn get_chrome_bookmarks_path_from_home_respects_browser_filter() {
+    fn discover_bookmark_sources_respects_browser_filter() {
         let dir = tempdir().expect("tempdir");
         let home = dir.path();
 
@@ -1007,19 +1654,32 @@ mod tests {
         fs::create_dir_all(&dia_profile).expect("create dia profile");
         fs::write(dia_profile.join("Bookmarks"), "{\"bigger\":true}").expect("write dia bookmarks");
 
-        let chrome_only =
-            get_chrome_bookmarks_path_from_home_for_browser(home, Some("chrome")).expect("chrome");
-        assert!(chrome_only.ends_with("Google/Chrome/Default/Bookmarks"));
+        let chrome_only = discover_bookmark_sources_from_home(home, &browsers(&["chrome"]));
+        assert_eq!(chrome_only.len(), 1);
+        assert!(chrome_only[0]
+            .path
+            .ends_with("Google/Chrome/Default/Bookmarks"));
 
-        let dia_only =
-            get_chrome_bookmarks_path_from_home_for_browser(home, Some("dia")).expect("dia");
-        assert!(dia_only.ends_with("The Browser Company/Dia/Profile 2/Bookmarks"));
+        let dia_only = discover_bookmark_sources_from_home(home, &browsers(&["dia"]));
+        assert_eq!(dia_only.len(), 1);
+        assert!(dia_only[0]
+            .path
+            .ends_with("The Browser Company/Dia/Profile 2/Bookmarks"));
 
-        assert!(get_chrome_bookmarks_path_from_home_for_browser(home, Some("unknown")).is_none());
+        assert!(discover_bookmark_sources_from_home(home, &browsers(&["unknown"])).is_empty());
+
+        let ordered = discover_bookmark_sources_from_home(home, &browsers(&["dia", "chrome"]));
+        let keys: Vec<&str> = ordered
+            .iter()
+            .map(|source| source.browser.as_str())
+            .collect();
+        assert_eq!(keys, vec!["dia", "chrome"]);
+        assert_eq!(browser_priority(&browsers(&["dia", "chrome"]), "chrome"), 1);
+        assert_eq!(browser_priority(&browsers(&["dia", "chrome"]), "brave"), 2);
     }
 
     #[test]
-    fn get_chrome_bookmarks_path_supports_user_data_layout() {
+    fn discover_bookmark_sources_supports_user_data_layout() {
         let dir = tempdir().expect("tempdir");
         let home = dir.path();
 
@@ -1029,13 +1689,15 @@ mod tests {
         fs::write(dia_user_data.join("Bookmarks"), "{\"from\":\"user-data\"}")
             .expect("write dia user data bookmarks");
 
-        let found =
-            get_chrome_bookmarks_path_from_home_for_browser(home, Some("dia")).expect("find dia");
-        assert!(found.ends_with("The Browser Company/Dia/User Data/Default/Bookmarks"));
+        let sources = discover_bookmark_sources_from_home(home, &browsers(&["dia"]));
+        assert_eq!(sources.len(), 1);
+        assert!(sources[0]
+            .path
+            .ends_with("The Browser Company/Dia/User Data/Default/Bookmarks"));
     }
 
     #[test]
-    fn get_chrome_bookmarks_path_supports_dia_hint_dirs_under_vendor_root() {
+    fn discover_bookmark_sources_supports_dia_hint_dirs_under_vendor_root() {
         let dir = tempdir().expect("tempdir");
         let home = dir.path();
 
@@ -1045,18 +1707,20 @@ mod tests {
         fs::write(dia_alt.join("Bookmarks"), "{\"from\":\"hint\"}")
             .expect("write dia nightly bookmarks");
 
-        let found =
-            get_chrome_bookmarks_path_from_home_for_browser(home, Some("dia")).expect("find dia");
-        assert!(found.ends_with("The Browser Company/Dia Nightly/Default/Bookmarks"));
+        let sources = discover_bookmark_sources_from_home(home, &browsers(&["dia"]));
+        assert_eq!(sources.len(), 1);
+        assert!(sources[0]
+            .path
+            .ends_with("The Browser Company/Dia Nightly/Default/Bookmarks"));
     }
 
     #[test]
-    fn get_chrome_bookmarks_path_supports_firefox_and_zen_profiles() {
+    fn discover_bookmark_sources_supports_firefox_and_zen_profiles() {
         let dir = tempdir().expect("tempdir");
         let home = dir.path();
 
         let firefox_profile =
-            home.join("Library/Application Support/Firefox/Profiles/default-release");
+            home.join("Library/Application Support/Firefox/Profiles/x1y2z3.default-release");
         fs::create_dir_all(&firefox_profile).expect("create firefox profile");
         fs::write(firefox_profile.join("places.sqlite"), "").expect("write firefox places");
 
@@ -1064,13 +1728,127 @@ mod tests {
         fs::create_dir_all(&zen_profile).expect("create zen profile");
         fs::write(zen_profile.join("places.sqlite"), "").expect("write zen places");
 
-        let firefox_only = get_chrome_bookmarks_path_from_home_for_browser(home, Some("firefox"))
-            .expect("find firefox");
-        assert!(firefox_only.ends_with("Firefox/Profiles/default-release/places.sqlite"));
+        let firefox_only = discover_bookmark_sources_from_home(home, &browsers(&["firefox"]));
+        assert_eq!(firefox_only.len(), 1);
+        assert!(firefox_only[0]
+            .path
+            .ends_with("Firefox/Profiles/x1y2z3.default-release/places.sqlite"));
+        assert_eq!(firefox_only[0].profile, "default-release");
+
+        let zen_only = discover_bookmark_sources_from_home(home, &browsers(&["zen"]));
+        assert_eq!(zen_only.len(), 1);
+        assert!(zen_only[0]
+            .path
+            .ends_with("zen/Profiles/alpha.default/places.sqlite"));
+    }
+
+    #[test]
+    fn retain_profiles_keeps_selected_profiles_case_insensitively() {
+        let dir = tempdir().expect("tempdir");
+        let home = dir.path();
+        let chrome = home.join("Library/Application Support/Google/Chrome");
+        for profile in ["Default", "Profile 1", "Profile 2"] {
+            fs::create_dir_all(chrome.join(profile)).expect("create profile");
+            fs::write(chrome.join(profile).join("Bookmarks"), "{}").expect("write bookmarks");
+        }
+        fs::write(
+            chrome.join("Local State"),
+            r#"{"profile":{"info_cache":{"Profile 1":{"name":"Work"},"Profile 2":{"name":"Personal"}}}}"#,
+        )
+        .expect("write local state");
+
+        let mut sources = discover_bookmark_sources_from_home(home, &browsers(&["chrome"]));
+        assert_eq!(sources.len(), 3);
+        retain_profiles(&mut sources, &[]);
+        assert_eq!(sources.len(), 3);
+
+        retain_profiles(&mut sources, &["work".to_string(), "personal".to_string()]);
+        let profiles: Vec<&str> = sources.iter().map(|s| s.profile.as_str()).collect();
+        assert_eq!(profiles, vec!["Personal", "Work"]);
+    }
+
+    #[test]
+    fn discover_bookmark_sources_disambiguates_profiles_across_channels() {
+        let dir = tempdir().expect("tempdir");
+        let home = dir.path();
+
+        for root in ["Google/Chrome", "Google/Chrome Beta"] {
+            let profile = home
+                .join("Library/Application Support")
+                .join(root)
+                .join("Default");
+            fs::create_dir_all(&profile).expect("create profile");
+            fs::write(profile.join("Bookmarks"), "{}").expect("write bookmarks");
+        }
+
+        let sources = discover_bookmark_sources_from_home(home, &browsers(&["chrome"]));
+        let profiles: Vec<&str> = sources.iter().map(|s| s.profile.as_str()).collect();
+        assert_eq!(profiles, vec!["Default", "Default (2)"]);
+    }
+
+    #[test]
+    fn bundle_ids_identify_supported_browsers() {
+        assert_eq!(
+            browser_key_for_bundle_id("com.google.Chrome"),
+            Some("chrome")
+        );
+        assert_eq!(
+            browser_key_for_bundle_id("com.google.chrome.canary"),
+            Some("chrome")
+        );
+        assert_eq!(
+            browser_key_for_bundle_id("company.thebrowser.Browser"),
+            Some("arc")
+        );
+        assert_eq!(
+            browser_key_for_bundle_id("org.mozilla.firefox"),
+            Some("firefox")
+        );
+        assert_eq!(browser_key_for_bundle_id("com.apple.finder"), None);
+    }
+
+    #[test]
+    fn profile_launch_maps_user_data_dir_to_app() {
+        let support = Path::new("/Users/me/Library/Application Support");
+        assert_eq!(
+            profile_launch_for(&support.join("Google/Chrome/Profile 2/Bookmarks")),
+            Some(ProfileLaunch {
+                app: "Google Chrome",
+                profile_dir: "Profile 2".to_string(),
+            })
+        );
+        assert_eq!(
+            profile_launch_for(&support.join("Google/Chrome Beta/Default/Bookmarks"))
+                .map(|launch| launch.app),
+            Some("Google Chrome Beta")
+        );
+        assert_eq!(
+            profile_launch_for(&support.join("Arc/User Data/Default/Bookmarks")),
+            None
+        );
+        assert_eq!(
+            profile_launch_for(&support.join("Google/Chrome/Bookmarks")),
+            None
+        );
+    }
+
+    #[test]
+    fn chromium_profile_names_read_from_local_state() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().join("Google/Chrome");
+        let profile = root.join("Profile 3");
+        fs::create_dir_all(&profile).expect("create profile");
+        fs::write(profile.join("Bookmarks"), "{}").expect("write bookmarks");
+        fs::write(
+            root.join("Local State"),
+            r#"{"profile":{"info_cache":{"Profile 3":{"name":"Work"}}}}"#,
+        )
+        .expect("write local state");
 
-        let zen_only =
-            get_chrome_bookmarks_path_from_home_for_browser(home, Some("zen")).expect("find zen");
-        assert!(zen_only.ends_with("zen/Profiles/alpha.default/places.sqlite"));
+        assert_eq!(
+            profile_display_name(&profile.join("Bookmarks"), BrowserEngine::Chromium),
+            "Work"
+        );
     }
 
     #[test]
@@ -1092,7 +1870,8 @@ mod tests {
                     fk INTEGER,
                     parent INTEGER,
                     title TEXT,
-                    dateAdded INTEGER
+                    dateAdded INTEGER,
+                    guid TEXT
                 );
                 CREATE TABLE moz_bookmarks_roots (
                     root_name TEXT,
@@ -1106,8 +1885,8 @@ mod tests {
                     ('toolbar', 10);
                 INSERT INTO moz_places(id, url) VALUES
                     (100, 'https://example.com');
-                INSERT INTO moz_bookmarks(id, type, fk, parent, title, dateAdded) VALUES
-                    (200, 1, 100, 11, 'Example', 12345);
+                INSERT INTO moz_bookmarks(id, type, fk, parent, title, dateAdded, guid) VALUES
+                    (200, 1, 100, 11, 'Example', 12345, 'ffGuid000001');
             ",
             )
             .expect("create schema");
@@ -1117,10 +1896,117 @@ mod tests {
         let bookmark = &bookmarks[0];
         assert_eq!(bookmark.name, "Example");
         assert_eq!(bookmark.folder_path.as_deref(), Some("书签工具栏/work"));
+        assert_eq!(bookmark.guid.as_deref(), Some("ffGuid000001"));
+    }
+
+    fn safari_plist() -> Vec<u8> {
+        use crate::plist::Value;
+        let text = |value: &str| Value::String(value.to_string());
+        let dict = |entries: Vec<(&str, Value)>| {
+            Value::Dict(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect(),
+            )
+        };
+        let leaf = |uuid: &str, title: &str, url: &str| {
+            dict(vec![
+                ("WebBookmarkType", text("WebBookmarkTypeLeaf")),
+                ("WebBookmarkUUID", text(uuid)),
+                ("URLString", text(url)),
+                ("URIDictionary", dict(vec![("title", text(title))])),
+            ])
+        };
+        let list = |title: &str, children: Vec<Value>| {
+            dict(vec![
+                ("WebBookmarkType", text("WebBookmarkTypeList")),
+                ("Title", text(title)),
+                ("Children", Value::Array(children)),
+            ])
+        };
+        let mut reading = leaf("R1", "稍后读", "https://later.example");
+        if let Value::Dict(entries) = &mut reading {
+            entries.push((
+                "ReadingList".to_string(),
+                dict(vec![("DateAdded", Value::Date(700_000_000.0))]),
+            ));
+        }
+
+        crate::plist::encode(&list(
+            "",
+            vec![
+                dict(vec![
+                    ("WebBookmarkType", text("WebBookmarkTypeProxy")),
+                    ("Title", text("History")),
+                ]),
+                list(
+                    "BookmarksBar",
+                    vec![list(
+                        "Rust",
+                        vec![leaf("U1", "Rust Book", "https://doc.rust-lang.org/book/")],
+                    )],
+                ),
+                list(
+                    "BookmarksMenu",
+                    vec![leaf("U2", "", "https://menu.example")],
+                ),
+                list("com.apple.ReadingList", vec![reading]),
+            ],
+        ))
     }
 
     #[test]
-    fn cached_bookmarks_path_uses_saved_path_when_valid() {
+    fn load_safari_bookmarks_flattens_folders_and_reading_list() {
+        let dir = tempdir().expect("tempdir");
+        let safari = dir.path().join("Library/Safari");
+        fs::create_dir_all(&safari).expect("create safari dir");
+        fs::write(safari.join("Bookmarks.plist"), safari_plist()).expect("write plist");
+
+        let sources = discover_bookmark_sources_from_home(dir.path(), &browsers(&["safari"]));
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].browser, "safari");
+        assert_eq!(sources[0].profile, "Default");
+
+        let bookmarks = load_bookmarks_from_source(&sources[0].path).expect("load bookmarks");
+        let summary: Vec<(&str, &str, Option<&str>)> = bookmarks
+            .iter()
+            .map(|b| (b.id.as_str(), b.name.as_str(), b.folder_path.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("U1", "Rust Book", Some("个人收藏/Rust")),
+                ("U2", "https://menu.example", Some("书签菜单")),
+                ("R1", "稍后读", Some("阅读列表")),
+            ]
+        );
+        assert_eq!(bookmarks[0].guid.as_deref(), Some("U1"));
+        assert_eq!(date_added_to_unix_secs(&bookmarks[0].date_added), None);
+        assert_eq!(
+            date_added_to_unix_secs(&bookmarks[2].date_added),
+            Some(1_678_307_200)
+        );
+    }
+
+    #[test]
+    fn safari_permission_errors_ask_for_full_disk_access() {
+        let path = Path::new("/Users/me/Library/Safari/Bookmarks.plist");
+        let denied = safari_read_error(
+            path,
+            std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+        );
+        assert!(is_full_disk_access_error(&format!(
+            "读取书签失败: {}",
+            denied
+        )));
+
+        let missing = safari_read_error(path, std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(!is_full_disk_access_error(&missing.to_string()));
+    }
+
+    #[test]
+    fn cached_bookmark_sources_uses_saved_sources_when_valid() {
         let dir = tempdir().expect("tempdir");
         let home = dir.path();
         let cache_dir = home.join("cache");
@@ -1130,17 +2016,16 @@ mod tests {
         fs::create_dir_all(&default_path).expect("create default");
         fs::write(default_path.join("Bookmarks"), "{}").expect("write default");
 
-        let first = get_chrome_bookmarks_path_cached_from_home_for_browser(home, &cache_dir, None)
-            .expect("first resolve");
-        let second = get_chrome_bookmarks_path_cached_from_home_for_browser(home, &cache_dir, None)
-            .expect("second resolve");
+        let first = discover_bookmark_sources_cached_from_home(home, &cache_dir, &[]);
+        let second = discover_bookmark_sources_cached_from_home(home, &cache_dir, &[]);
 
         assert_eq!(first, second);
-        assert!(cache_dir.join("bookmarks_source_path.json").exists());
+        assert_eq!(first.len(), 1);
+        assert!(cache_dir.join("bookmark_sources.json").exists());
     }
 
     #[test]
-    fn cached_bookmarks_path_falls_back_to_rescan_when_cache_stale() {
+    fn cached_bookmark_sources_rescan_when_a_source_disappears() {
         let dir = tempdir().expect("tempdir");
         let home = dir.path();
         let cache_dir = home.join("cache");
@@ -1154,19 +2039,18 @@ mod tests {
         fs::create_dir_all(&profile_path).expect("create profile");
         fs::write(profile_path.join("Bookmarks"), "{\"bigger\":true}").expect("write profile");
 
-        let first = get_chrome_bookmarks_path_cached_from_home_for_browser(home, &cache_dir, None)
-            .expect("first resolve");
-        assert!(first.ends_with("Profile 1/Bookmarks"));
+        let first = discover_bookmark_sources_cached_from_home(home, &cache_dir, &[]);
+        assert_eq!(first.len(), 2);
 
         fs::remove_file(profile_path.join("Bookmarks")).expect("remove profile bookmarks");
 
-        let second = get_chrome_bookmarks_path_cached_from_home_for_browser(home, &cache_dir, None)
-            .expect("second resolve");
-        assert!(second.ends_with("Default/Bookmarks"));
+        let second = discover_bookmark_sources_cached_from_home(home, &cache_dir, &[]);
+        assert_eq!(second.len(), 1);
+        assert!(second[0].path.ends_with("Default/Bookmarks"));
     }
 
     #[test]
-    fn cached_bookmarks_path_isolated_by_browser_filter() {
+    fn cached_bookmark_sources_isolated_by_browser_filter() {
         let dir = tempdir().expect("tempdir");
         let home = dir.path();
         let cache_dir = home.join("cache");
@@ -1180,20 +2064,16 @@ mod tests {
         fs::create_dir_all(&dia_path).expect("create dia");
         fs::write(dia_path.join("Bookmarks"), "{\"dia\":1}").expect("write dia");
 
-        let chrome = get_chrome_bookmarks_path_cached_from_home_for_browser(
-            home,
-            &cache_dir,
-            Some("chrome"),
-        )
-        .expect("chrome resolve");
-        let dia =
-            get_chrome_bookmarks_path_cached_from_home_for_browser(home, &cache_dir, Some("dia"))
-                .expect("dia resolve");
+        let chrome =
+            discover_bookmark_sources_cached_from_home(home, &cache_dir, &browsers(&["chrome"]));
+        let dia = discover_bookmark_sources_cached_from_home(home, &cache_dir, &browsers(&["dia"]));
 
-        assert!(chrome.ends_with("Google/Chrome/Default/Bookmarks"));
-        assert!(dia.ends_with("The Browser Company/Dia/Default/Bookmarks"));
-        assert!(cache_dir.join("bookmarks_source_path.chrome.json").exists());
-        assert!(cache_dir.join("bookmarks_source_path.dia.json").exists());
+        assert!(chrome[0].path.ends_with("Google/Chrome/Default/Bookmarks"));
+        assert!(dia[0]
+            .path
+            .ends_with("The Browser Company/Dia/Default/Bookmarks"));
+        assert!(cache_dir.join("bookmark_sources.chrome.json").exists());
+        assert!(cache_dir.join("bookmark_sources.dia.json").exists());
     }
 
     #[test]
@@ -1204,12 +2084,13 @@ mod tests {
 
         write_bookmarks(&bookmarks_path, true);
         let cache = BookmarkCache::new(&data_dir);
-        let first = cache.load(&bookmarks_path).expect("first load");
+        let sources = vec![bookmark_source_for_path(bookmarks_path.clone())];
+        let first = cache.load(&sources).expect("first load");
         assert_eq!(first.len(), 3);
 
         write_bookmarks(&bookmarks_path, false);
 
-        let second = cache.load(&bookmarks_path).expect("second load");
+        let second = cache.load(&sources).expect("second load");
         assert_eq!(second.len(), 2);
 
         let cache_file = data_dir.join("bookmarks_cache.json");
@@ -1222,6 +2103,71 @@ mod tests {
         assert!(!mtime_file.exists());
     }
 
+    #[test]
+    fn cache_is_compressed_and_legacy_json_still_loads() {
+        let json = br#"[{"id":"1","name":"Rust","url":"https://rust-lang.org","date_added":"0"}]"#;
+        let encoded = encode_cache(json).expect("encode");
+        assert!(encoded.starts_with(CACHE_MAGIC));
+        assert_eq!(decode_cache(encoded).as_deref(), Some(&json[..]));
+        // 升级前写入的未压缩缓存原样读取
+        assert_eq!(decode_cache(json.to_vec()).as_deref(), Some(&json[..]));
+        // 损坏的压缩数据视为没有缓存
+        let mut corrupt = CACHE_MAGIC.to_vec();
+        corrupt.extend(b"not zstd");
+        assert_eq!(decode_cache(corrupt), None);
+    }
+
+    #[test]
+    fn date_added_parses_webkit_and_unix_micros() {
+        // 2024-01-01T00:00:00Z
+        assert_eq!(
+            date_added_to_unix_secs("13348540800000000"),
+            Some(1_704_067_200)
+        );
+        assert_eq!(
+            date_added_to_unix_secs("1704067200000000"),
+            Some(1_704_067_200)
+        );
+        assert_eq!(
+            date_added_to_unix_secs("1704067200000"),
+            Some(1_704_067_200)
+        );
+        assert_eq!(date_added_to_unix_secs(" 1704067200 "), Some(1_704_067_200));
+        assert_eq!(date_added_to_unix_secs("0"), None);
+        assert_eq!(date_added_to_unix_secs("abc"), None);
+    }
+
+    #[test]
+    fn parallel_load_keeps_source_order_and_reports_failures() {
+        let dir = tempdir().expect("tempdir");
+        let mut sources = Vec::new();
+        for profile in ["Default", "Profile 1", "Profile 2"] {
+            let path = dir.path().join(profile).join("Bookmarks");
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            write_bookmarks(&path, profile != "Profile 1");
+            sources.push(bookmark_source_for_path(path));
+        }
+        for (source, profile) in sources
+            .iter_mut()
+            .zip(["Default", "Profile 1", "Profile 2"])
+        {
+            source.profile = profile.to_string();
+        }
+        sources.push(bookmark_source_for_path(
+            dir.path().join("missing/Bookmarks"),
+        ));
+
+        let results = load_sources_parallel(&sources);
+        assert_eq!(results.len(), 4);
+        for (source, result) in sources.iter().zip(&results[..3]) {
+            let parsed = result.as_ref().expect("parsed");
+            assert!(!parsed.is_empty());
+            assert!(parsed.iter().all(|b| b.profile == source.profile));
+        }
+        assert!(results[0].as_ref().unwrap().len() > results[1].as_ref().unwrap().len());
+        assert!(results[3].as_ref().unwrap_err().contains("missing"));
+    }
+
     #[test]
     fn bookmark_cache_falls_back_when_parse_fails() {
         let dir = tempdir().expect("tempdir");
@@ -1230,12 +2176,13 @@ mod tests {
 
         write_bookmarks(&bookmarks_path, true);
         let cache = BookmarkCache::new(&data_dir);
-        let first = cache.load(&bookmarks_path).expect("first load");
+        let sources = vec![bookmark_source_for_path(bookmarks_path.clone())];
+        let first = cache.load(&sources).expect("first load");
         assert_eq!(first.len(), 3);
 
         fs::write(&bookmarks_path, "{ invalid json").expect("write invalid");
 
-        let fallback = cache.load(&bookmarks_path).expect("fallback load");
+        let fallback = cache.load(&sources).expect("fallback load");
         assert_eq!(fallback.len(), 3);
     }
 }
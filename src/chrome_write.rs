@@ -0,0 +1,604 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+/// Chromium 书签根节点及其目录名（与 `extract_all_bookmarks` 生成的目录前缀一致）
+const ROOTS: &[(&str, &str)] = &[
+    ("bookmark_bar", "书签栏"),
+    ("other", "其他书签"),
+    ("synced", "同步书签"),
+];
+/// 未指定根目录时写入的位置
+const DEFAULT_ROOT: &str = "other";
+
+/// 在 Chromium 的 Bookmarks 文件中新建 URL 书签，返回新书签的原生 id 与 GUID
+///
+/// 调用方需先确认浏览器已退出；写入前校验原文件的 checksum，写入后按 Chromium 的算法重新计算，
+/// 原文件先备份为 `Bookmarks.alfred-bak`，再以临时文件替换，中途失败不会留下半截文件。
+/// `folder` 的首段可以是根目录名（如 `书签栏/work`），否则视为“其他书签”下的路径，缺少的目录会被创建。
+pub fn add_url_bookmark(
+    path: &Path,
+    url: &str,
+    title: &str,
+    folder: Option<&str>,
+    date_added: &str,
+) -> Result<(String, String), String> {
+    let mut root = read_checked(path)?;
+    let id = (max_node_id(&root) + 1).to_string();
+    let guid = new_guid();
+    let mut next_id = id.parse::<u64>().unwrap_or(1) + 1;
+
+    let roots = root
+        .get_mut("roots")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| "书签文件缺少 roots".to_string())?;
+    let (parent, _) = resolve_folder(roots, folder, date_added, &mut next_id)?;
+    parent
+        .get_mut("children")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| "目标目录缺少 children".to_string())?
+        .push(json!({
+            "date_added": date_added,
+            "date_last_used": "0",
+            "guid": guid,
+            "id": id,
+            "name": title,
+            "type": "url",
+            "url": url,
+        }));
+    parent["date_modified"] = Value::String(date_added.to_string());
+
+    write_checked(path, root)?;
+    Ok((id, guid))
+}
+
+/// 从 Chromium 的 Bookmarks 文件中删除原生 id 对应的 URL 书签，安全检查与写入方式同 `add_url_bookmark`
+pub fn remove_url_bookmark(
+    path: &Path,
+    native_id: &str,
+    date_modified: &str,
+) -> Result<(), String> {
+    let mut root = read_checked(path)?;
+    take_url_node(&mut root, native_id, date_modified)
+        .ok_or_else(|| format!("书签文件中没有 id 为 {} 的书签", native_id))?;
+    write_checked(path, root)
+}
+
+/// 把多个 URL 书签移到目标目录（写法同 `add_url_bookmark` 的 `folder`），只写入一次；返回目标目录路径
+///
+/// 任一 id 不存在时整体放弃，文件保持不变
+pub fn move_url_bookmarks(
+    path: &Path,
+    native_ids: &[&str],
+    folder: &str,
+    date_modified: &str,
+) -> Result<String, String> {
+    let mut root = read_checked(path)?;
+    let mut next_id = max_node_id(&root) + 1;
+    let mut moved = Vec::with_capacity(native_ids.len());
+    for native_id in native_ids {
+        moved.push(
+            take_url_node(&mut root, native_id, date_modified)
+                .ok_or_else(|| format!("书签文件中没有 id 为 {} 的书签", native_id))?,
+        );
+    }
+
+    let roots = root
+        .get_mut("roots")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| "书签文件缺少 roots".to_string())?;
+    let (parent, label) = resolve_folder(roots, Some(folder), date_modified, &mut next_id)?;
+    parent
+        .get_mut("children")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| "目标目录缺少 children".to_string())?
+        .extend(moved);
+    parent["date_modified"] = Value::String(date_modified.to_string());
+
+    write_checked(path, root)?;
+    Ok(label)
+}
+
+/// 修改 URL 书签的标题
+pub fn retitle_url_bookmark(path: &Path, native_id: &str, title: &str) -> Result<(), String> {
+    fn rename(node: &mut Value, native_id: &str, title: &str) -> bool {
+        if node.get("type").and_then(Value::as_str) == Some("url") {
+            if node.get("id").and_then(Value::as_str) != Some(native_id) {
+                return false;
+            }
+            node["name"] = Value::String(title.to_string());
+            return true;
+        }
+        node.get_mut("children")
+            .and_then(Value::as_array_mut)
+            .is_some_and(|children| {
+                children
+                    .iter_mut()
+                    .any(|child| rename(child, native_id, title))
+            })
+    }
+
+    let mut root = read_checked(path)?;
+    let renamed = root
+        .get_mut("roots")
+        .and_then(Value::as_object_mut)
+        .is_some_and(|roots| {
+            ROOTS.iter().any(|(key, _)| {
+                roots
+                    .get_mut(*key)
+                    .is_some_and(|node| rename(node, native_id, title))
+            })
+        });
+    if !renamed {
+        return Err(format!("书签文件中没有 id 为 {} 的书签", native_id));
+    }
+    write_checked(path, root)
+}
+
+/// 批量修改 URL 书签标题（原生 id → 新标题），没有变化时不写文件；返回实际改动的书签数
+pub fn retitle_url_bookmarks(
+    path: &Path,
+    titles: &HashMap<String, String>,
+) -> Result<usize, String> {
+    fn rename_all(node: &mut Value, titles: &HashMap<String, String>) -> usize {
+        if node.get("type").and_then(Value::as_str) == Some("url") {
+            let Some(title) = node
+                .get("id")
+                .and_then(Value::as_str)
+                .and_then(|id| titles.get(id))
+            else {
+                return 0;
+            };
+            if node.get("name").and_then(Value::as_str) == Some(title.as_str()) {
+                return 0;
+            }
+            node["name"] = Value::String(title.clone());
+            return 1;
+        }
+        node.get_mut("children")
+            .and_then(Value::as_array_mut)
+            .map_or(0, |children| {
+                children
+                    .iter_mut()
+                    .map(|child| rename_all(child, titles))
+                    .sum()
+            })
+    }
+
+    let mut root = read_checked(path)?;
+    let changed = root
+        .get_mut("roots")
+        .and_then(Value::as_object_mut)
+        .map_or(0, |roots| {
+            let mut changed = 0;
+            for (key, _) in ROOTS {
+                if let Some(node) = roots.get_mut(*key) {
+                    changed += rename_all(node, titles);
+                }
+            }
+            changed
+        });
+    if changed > 0 {
+        write_checked(path, root)?;
+    }
+    Ok(changed)
+}
+
+/// 从所在目录中取出 URL 书签节点，并更新该目录的修改时间
+fn take_url_node(root: &mut Value, native_id: &str, date_modified: &str) -> Option<Value> {
+    fn take(node: &mut Value, native_id: &str, date_modified: &str) -> Option<Value> {
+        let children = node.get_mut("children").and_then(Value::as_array_mut)?;
+        let position = children.iter().position(|child| {
+            child.get("type").and_then(Value::as_str) == Some("url")
+                && child.get("id").and_then(Value::as_str) == Some(native_id)
+        });
+        if let Some(position) = position {
+            let taken = children.remove(position);
+            node["date_modified"] = Value::String(date_modified.to_string());
+            return Some(taken);
+        }
+        children
+            .iter_mut()
+            .find_map(|child| take(child, native_id, date_modified))
+    }
+
+    let roots = root.get_mut("roots").and_then(Value::as_object_mut)?;
+    ROOTS.iter().find_map(|(key, _)| {
+        roots
+            .get_mut(*key)
+            .and_then(|node| take(node, native_id, date_modified))
+    })
+}
+
+fn read_checked(path: &Path) -> Result<Value, String> {
+    let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let root: Value = serde_json::from_str(&content).map_err(|err| err.to_string())?;
+    if let Some(stored) = root.get("checksum").and_then(Value::as_str) {
+        if stored != bookmarks_checksum(&root) {
+            return Err("书签文件校验和不一致，可能正被浏览器写入，请稍后重试".to_string());
+        }
+    }
+    Ok(root)
+}
+
+fn write_checked(path: &Path, mut root: Value) -> Result<(), String> {
+    let checksum = bookmarks_checksum(&root);
+    if let Some(object) = root.as_object_mut() {
+        object.insert("checksum".to_string(), Value::String(checksum));
+    }
+    let content = serde_json::to_string_pretty(&root).map_err(|err| err.to_string())?;
+
+    std::fs::copy(path, path.with_extension("alfred-bak")).map_err(|err| err.to_string())?;
+    let tmp = path.with_extension("alfred-tmp");
+    std::fs::write(&tmp, content).map_err(|err| err.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|err| err.to_string())
+}
+
+/// 找到（必要时创建）目标目录节点，同时返回其目录路径（与索引中的 `folder_path` 写法一致）
+fn resolve_folder<'a>(
+    roots: &'a mut Map<String, Value>,
+    folder: Option<&str>,
+    date_added: &str,
+    next_id: &mut u64,
+) -> Result<(&'a mut Value, String), String> {
+    let mut segments: Vec<&str> = folder
+        .unwrap_or_default()
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    let named_root = segments.first().and_then(|first| {
+        ROOTS.iter().find_map(|(key, label)| {
+            let name = roots
+                .get(*key)
+                .and_then(|node| node.get("name"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            (first.eq_ignore_ascii_case(label) || first.eq_ignore_ascii_case(name)).then_some(*key)
+        })
+    });
+    let root_key = match named_root {
+        Some(key) => {
+            segments.remove(0);
+            key
+        }
+        None => DEFAULT_ROOT,
+    };
+    let root_name = roots
+        .get(root_key)
+        .and_then(|node| node.get("name"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    // 索引中的路径含根目录自身的名字（如 `书签栏/Bookmarks bar/dev`），照抄时不能把它当成子目录
+    if segments
+        .first()
+        .is_some_and(|first| first.eq_ignore_ascii_case(&root_name))
+    {
+        segments.remove(0);
+    }
+
+    let mut label = ROOTS
+        .iter()
+        .find(|(key, _)| *key == root_key)
+        .map(|(_, label)| format!("{}/{}", label, root_name))
+        .unwrap_or_default();
+    let mut node = roots
+        .get_mut(root_key)
+        .ok_or_else(|| format!("书签文件缺少根目录 {}", root_key))?;
+    for segment in segments {
+        label.push('/');
+        label.push_str(segment);
+        let children = node
+            .get_mut("children")
+            .and_then(Value::as_array_mut)
+            .ok_or_else(|| "目录节点缺少 children".to_string())?;
+        let position = children.iter().position(|child| {
+            child.get("type").and_then(Value::as_str) == Some("folder")
+                && child.get("name").and_then(Value::as_str) == Some(segment)
+        });
+        let position = match position {
+            Some(position) => position,
+            None => {
+                children.push(json!({
+                    "children": [],
+                    "date_added": date_added,
+                    "date_last_used": "0",
+                    "date_modified": date_added,
+                    "guid": new_guid(),
+                    "id": next_id.to_string(),
+                    "name": segment,
+                    "type": "folder",
+                }));
+                *next_id += 1;
+                children.len() - 1
+            }
+        };
+        node = &mut children[position];
+    }
+    Ok((node, label))
+}
+
+fn max_node_id(root: &Value) -> u64 {
+    fn walk(node: &Value, max: &mut u64) {
+        if let Some(id) = node
+            .get("id")
+            .and_then(Value::as_str)
+            .and_then(|id| id.parse::<u64>().ok())
+        {
+            *max = (*max).max(id);
+        }
+        for child in node
+            .get("children")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            walk(child, max);
+        }
+    }
+
+    let mut max = 0;
+    if let Some(roots) = root.get("roots").and_then(Value::as_object) {
+        for node in roots.values() {
+            walk(node, &mut max);
+        }
+    }
+    max
+}
+
+/// Chromium 的书签校验和：按根目录顺序深度优先遍历，依次累加 id、UTF-16 标题、类型（及 URL）后取 MD5
+fn bookmarks_checksum(root: &Value) -> String {
+    fn append(node: &Value, data: &mut Vec<u8>) {
+        let field = |key: &str| node.get(key).and_then(Value::as_str).unwrap_or_default();
+        data.extend_from_slice(field("id").as_bytes());
+        for unit in field("name").encode_utf16() {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        if field("type") == "url" {
+            data.extend_from_slice(b"url");
+            data.extend_from_slice(field("url").as_bytes());
+        } else {
+            data.extend_from_slice(b"folder");
+            for child in node
+                .get("children")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                append(child, data);
+            }
+        }
+    }
+
+    let mut data = Vec::new();
+    for (key, _) in ROOTS {
+        if let Some(node) = root.get("roots").and_then(|roots| roots.get(key)) {
+            append(node, &mut data);
+        }
+    }
+    md5_hex(&data)
+}
+
+/// 随机 GUID（v4 格式）；随机源为标准库 RandomState 的随机种子
+fn new_guid() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let mut bytes = [0u8; 16];
+    for (index, half) in bytes.chunks_mut(8).enumerate() {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(nanos);
+        hasher.write_usize(index);
+        half.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// 仅用于书签校验和的 MD5（RFC 1321）
+fn md5_hex(input: &[u8]) -> String {
+    const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+    let mut message = input.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((input.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks(64) {
+        let words: Vec<u32> = chunk
+            .chunks(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for round in 0..64 {
+            let (mixed, word) = match round / 16 {
+                0 => ((b & c) | (!b & d), round),
+                1 => ((d & b) | (!d & c), (5 * round + 1) % 16),
+                2 => (b ^ c ^ d, (3 * round + 5) % 16),
+                _ => (c ^ (b | !d), (7 * round) % 16),
+            };
+            let constant = (((round + 1) as f64).sin().abs() * 4_294_967_296.0) as u32;
+            let sum = mixed
+                .wrapping_add(a)
+                .wrapping_add(constant)
+                .wrapping_add(words[word]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(sum.rotate_left(SHIFTS[(round / 16) * 4 + round % 4]));
+        }
+        for (slot, value) in state.iter_mut().zip([a, b, c, d]) {
+            *slot = slot.wrapping_add(value);
+        }
+    }
+
+    state
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::ChromeBookmarks;
+    use tempfile::tempdir;
+
+    fn sample_file(dir: &Path) -> std::path::PathBuf {
+        let mut root = json!({
+            "roots": {
+                "bookmark_bar": {
+                    "children": [
+                        {"id": "5", "name": "Rust", "type": "url", "url": "https://rust-lang.org", "date_added": "1"}
+                    ],
+                    "id": "1", "name": "Bookmarks bar", "type": "folder"
+                },
+                "other": {"children": [], "id": "2", "name": "Other bookmarks", "type": "folder"},
+                "synced": {"children": [], "id": "3", "name": "Mobile bookmarks", "type": "folder"}
+            },
+            "version": 1
+        });
+        root["checksum"] = Value::String(bookmarks_checksum(&root));
+        let path = dir.join("Bookmarks");
+        std::fs::write(&path, serde_json::to_string(&root).expect("json")).expect("write");
+        path
+    }
+
+    #[test]
+    fn md5_matches_reference_vectors() {
+        assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5_hex(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            md5_hex(b"The quick brown fox jumps over the lazy dog"),
+            "9e107d9d372bb6826bd81d3542a419d6"
+        );
+    }
+
+    #[test]
+    fn added_bookmark_creates_folders_and_keeps_checksum_valid() {
+        let dir = tempdir().expect("tempdir");
+        let path = sample_file(dir.path());
+
+        let (id, guid) = add_url_bookmark(
+            &path,
+            "https://docs.rs",
+            "Docs",
+            Some("Bookmarks bar/dev/crates"),
+            "13300000000000000",
+        )
+        .expect("add");
+        assert_eq!(id, "6");
+        add_url_bookmark(&path, "https://tokio.rs", "Tokio", None, "1").expect("add");
+
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).expect("read")).expect("json");
+        assert_eq!(
+            written["checksum"].as_str(),
+            Some(bookmarks_checksum(&written).as_str())
+        );
+        assert!(path.with_extension("alfred-bak").exists());
+
+        let bookmarks = ChromeBookmarks::from_file(path.clone())
+            .expect("parse")
+            .extract_all_bookmarks();
+        let docs = bookmarks
+            .iter()
+            .find(|bookmark| bookmark.url == "https://docs.rs")
+            .expect("docs");
+        assert_eq!(
+            docs.folder_path.as_deref(),
+            Some("书签栏/Bookmarks bar/dev/crates")
+        );
+        assert_eq!(docs.guid.as_deref(), Some(guid.as_str()));
+        let tokio = bookmarks
+            .iter()
+            .find(|bookmark| bookmark.url == "https://tokio.rs")
+            .expect("tokio");
+        assert_eq!(
+            tokio.folder_path.as_deref(),
+            Some("其他书签/Other bookmarks")
+        );
+        assert_ne!(tokio.id, docs.id);
+    }
+
+    #[test]
+    fn removed_bookmark_disappears_and_checksum_is_updated() {
+        let dir = tempdir().expect("tempdir");
+        let path = sample_file(dir.path());
+
+        remove_url_bookmark(&path, "5", "2").expect("remove");
+        assert!(remove_url_bookmark(&path, "5", "3").is_err());
+
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).expect("read")).expect("json");
+        assert_eq!(
+            written["checksum"].as_str(),
+            Some(bookmarks_checksum(&written).as_str())
+        );
+        assert!(ChromeBookmarks::from_file(path)
+            .expect("parse")
+            .extract_all_bookmarks()
+            .is_empty());
+    }
+
+    #[test]
+    fn moved_and_retitled_bookmarks_keep_their_ids() {
+        let dir = tempdir().expect("tempdir");
+        let path = sample_file(dir.path());
+
+        let folder = move_url_bookmarks(&path, &["5"], "dev/lang", "2").expect("move");
+        assert_eq!(folder, "其他书签/Other bookmarks/dev/lang");
+        // 照抄索引中的完整路径也落在同一个目录
+        let same = move_url_bookmarks(&path, &["5"], "其他书签/Other bookmarks/dev/lang", "2")
+            .expect("move again");
+        assert_eq!(same, folder);
+        retitle_url_bookmark(&path, "5", "The Rust Language").expect("retitle");
+        let titles = HashMap::from([("5".to_string(), "The Rust Language".to_string())]);
+        assert_eq!(retitle_url_bookmarks(&path, &titles).expect("unchanged"), 0);
+        assert!(move_url_bookmarks(&path, &["5", "404"], "x", "3").is_err());
+
+        let bookmarks = ChromeBookmarks::from_file(path)
+            .expect("parse")
+            .extract_all_bookmarks();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].id, "5");
+        assert_eq!(bookmarks[0].name, "The Rust Language");
+        assert_eq!(
+            bookmarks[0].folder_path.as_deref(),
+            Some("其他书签/Other bookmarks/dev/lang")
+        );
+    }
+
+    #[test]
+    fn mismatched_checksum_is_refused() {
+        let dir = tempdir().expect("tempdir");
+        let path = sample_file(dir.path());
+        let content = std::fs::read_to_string(&path)
+            .expect("read")
+            .replace("https://rust-lang.org", "https://example.com");
+        std::fs::write(&path, content).expect("write");
+
+        assert!(add_url_bookmark(&path, "https://docs.rs", "Docs", None, "1").is_err());
+    }
+}
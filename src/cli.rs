@@ -1,45 +1,985 @@
-use structopt::StructOpt;
+use crate::config::{CACHE_DIR_VAR, DATA_DIR_VAR};
+use crate::report::GrowthPeriod;
+use crate::report_render::ReportFormat;
+use crate::searcher::SortOrder;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 
-#[derive(StructOpt, Debug)]
-#[structopt(name = "alfred-chrome-bookmarks")]
+const GLOBAL_HEADING: &str = "全局选项";
+
+/// `--help` 末尾列出的 workflow 变量；都可以写进 `--config` 文件
+const ENV_HELP: &str = "\
+环境变量（Alfred workflow 变量，也可写进 --config 文件，每行 KEY=VALUE）:
+  ALFRED_CHROME_BOOKMARKS_PATH               强制指定书签文件路径
+  ALFRED_CHROME_BOOKMARKS_BROWSER            扫描的浏览器，按优先级逗号分隔（如 arc,chrome；all 为全部）
+  ALFRED_CHROME_BOOKMARKS_PROFILES           只索引这些 profile（显示名，逗号分隔；默认 all 为全部）
+  ALFRED_CHROME_BOOKMARKS_PRIMARY_BROWSER    合并同一 URL 时优先保留的浏览器
+  ALFRED_CHROME_BOOKMARKS_INDEX_TTL          各命令可接受的索引陈旧时间（如 search=10s,stats=0）
+  ALFRED_CHROME_BOOKMARKS_POST_REFRESH_HOOK  刷新成功后在后台执行的命令
+  ALFRED_CHROME_BOOKMARKS_FAVICONS           browser：刷新时导出浏览器自带的图标；on：另外在后台下载站点图标
+  ALFRED_CHROME_BOOKMARKS_SUBSTRING          on：建立 trigram 子串索引
+  ALFRED_CHROME_BOOKMARKS_BOOST_DOMAINS      偏好域名，逗号分隔
+  ALFRED_CHROME_BOOKMARKS_MATCHER            模糊匹配算法：skim（默认）、nucleo、subsequence
+  ALFRED_CHROME_BOOKMARKS_DIVERSIFY          结果多样化：folder、domain，可带上限如 folder:2
+  ALFRED_CHROME_BOOKMARKS_USAGE              off：不记录书签打开次数
+  ALFRED_CHROME_BOOKMARKS_PLUGINS            插件目录（默认 <数据目录>/plugins），其中的 JSON 清单声明书签动作
+  ALFRED_CHROME_BOOKMARKS_SHARED_TAGS        团队共享标签目录（网络卷或同步盘），配合 tags sync
+  ALFRED_CHROME_BOOKMARKS_MEMBER             共享标签中的成员名，默认自动生成
+  ALFRED_CHROME_BOOKMARKS_DB_KEY             加密标签库与打开记录库的密钥，或 keychain:<服务名>（需 sqlcipher 构建）
+  ALFRED_CHROME_BOOKMARKS_OPEN_IN_PROFILE    off：不在书签所属 profile 中打开
+  ALFRED_CHROME_BOOKMARKS_OPEN_FRONTMOST     on：在最前面的受支持浏览器中打开
+  ALFRED_CHROME_BOOKMARKS_LATENCY            on：记录搜索耗时（stats latency）
+  ALFRED_CHROME_BOOKMARKS_MEMORY_BUDGET      全量加载书签的内存预算（MB，默认 32；off 不限制）
+  ALFRED_CHROME_BOOKMARKS_TOKENIZE           FTS 分词：unicode61/trigram/pinyin/jieba，可按字段 name=pinyin+jieba,url=trigram
+  ALFRED_CHROME_BOOKMARKS_RESULT_CACHE       缓存最近多少次搜索结果（默认 64；off 关闭）
+  ALFRED_CHROME_BOOKMARKS_HISTORY            on：记录搜索历史
+  ALFRED_CHROME_BOOKMARKS_TITLE_TAGS         on：把标题末尾的 #tag 解析为标签
+  ALFRED_CHROME_BOOKMARKS_FALLBACKS          无结果时的网页搜索（名称=URL 模板，分号分隔；none 关闭）
+  ALFRED_CHROME_BOOKMARKS_SOURCE_BADGE       off：不显示来源徽标
+  ALFRED_CHROME_BOOKMARKS_UID                结果 uid：stable（默认，按规范化 URL）、query（按查询学习排序）、none（不让 Alfred 调整排序）
+  ALFRED_CHROME_BOOKMARKS_STRIP_TRACKING     清理跟踪参数的动作：open、copy、all、none
+  ALFRED_CHROME_BOOKMARKS_TRACKING_PARAMS    追加需要清理的跟踪参数，逗号分隔";
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "alfred-chrome-bookmarks",
+    version,
+    about = "在 Alfred 中搜索 Chromium 系浏览器的书签",
+    after_help = ENV_HELP
+)]
 pub struct Opt {
-    #[structopt(subcommand)]
+    /// 数据目录（索引、标签、历史等）；未指定时使用 Alfred 的 alfred_workflow_data，再回退到 ~/.alfred-chrome-bookmarks
+    #[arg(
+        long = "data-dir",
+        global = true,
+        env = DATA_DIR_VAR,
+        help_heading = GLOBAL_HEADING,
+        value_name = "DIR"
+    )]
+    pub data_dir: Option<PathBuf>,
+
+    /// 缓存目录（书签缓存、任务进度、站点图标）；未指定时使用 alfred_workflow_cache，再回退到数据目录
+    #[arg(
+        long = "cache-dir",
+        global = true,
+        env = CACHE_DIR_VAR,
+        help_heading = GLOBAL_HEADING,
+        value_name = "DIR"
+    )]
+    pub cache_dir: Option<PathBuf>,
+
+    /// 配置文件：每行 KEY=VALUE，键同下方的环境变量；环境中已设置的变量优先
+    #[arg(
+        long = "config",
+        global = true,
+        env = "ALFRED_CHROME_BOOKMARKS_CONFIG",
+        help_heading = GLOBAL_HEADING,
+        value_name = "FILE"
+    )]
+    pub config: Option<PathBuf>,
+
+    /// 在标准错误输出中打印目录、配置来源与耗时等诊断信息
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        global = true,
+        help_heading = GLOBAL_HEADING
+    )]
+    pub verbose: bool,
+
+    #[command(subcommand)]
     pub cmd: SubCommand,
 }
 
-#[derive(StructOpt, Debug)]
+#[derive(Subcommand, Debug)]
 pub enum SubCommand {
     /// 搜索书签
-    #[structopt(name = "search", alias = "s")]
+    #[command(name = "search", alias = "s")]
     Search {
         /// 搜索关键词
         query: Vec<String>,
 
         /// 按目录过滤（逗号分隔，支持多级目录，如 work/project）
-        #[structopt(short = "p", long = "folders")]
+        #[arg(short = 'p', long = "folders")]
         folders: Option<String>,
 
+        /// 按浏览器 profile 过滤（逗号分隔，不区分大小写）
+        #[arg(long = "profile")]
+        profile: Option<String>,
+
+        /// 本次只搜索指定浏览器来源（逗号分隔，支持别名，如 chrome,arc；all 为全部）
+        #[arg(long = "sources", visible_alias = "browser")]
+        sources: Option<String>,
+
+        /// 按标签过滤（逗号分隔，需全部满足，不区分大小写）
+        #[arg(long = "tags")]
+        tags: Option<String>,
+
         /// 使用模糊搜索（更慢）
-        #[structopt(short = "f", long = "fuzzy")]
+        #[arg(short = 'f', long = "fuzzy")]
         fuzzy: bool,
 
+        /// 排序：relevance、added（最新添加在前）、title、url、frecency（常用且最近打开在前）；
+        /// relevance 以外的排序忽略 --fuzzy
+        #[arg(long = "sort", default_value = "relevance")]
+        sort: SortOrder,
+
         /// 限制结果数量
-        #[structopt(short = "l", long = "limit", default_value = "50")]
+        #[arg(short = 'l', long = "limit", default_value = "50")]
         limit: usize,
     },
 
+    /// 按 id 查询单个书签的完整记录（含标签、其他来源版本与链接检查结果）
+    #[command(name = "get")]
+    Get {
+        /// 书签 id，或默认 uid 模式下搜索结果的 uid（`urlhash:` 开头）
+        id: String,
+
+        /// 输出 JSON 而不是 Alfred 条目
+        #[arg(long = "json")]
+        json: bool,
+    },
+
     /// 刷新浏览器书签缓存与索引
-    #[structopt(name = "refresh", alias = "rf")]
+    #[command(name = "refresh", alias = "rf")]
     Refresh,
 
-    /// 显示统计信息
-    #[structopt(name = "stats", alias = "st")]
-    Stats,
+    /// 显示统计信息；`stats index` 查看索引数据库的健康指标，`stats latency` 查看搜索耗时
+    #[command(name = "stats", alias = "st")]
+    Stats {
+        #[command(subcommand)]
+        cmd: Option<StatsCommand>,
+    },
+
+    /// 诊断索引与各书签来源的状态
+    #[command(name = "doctor")]
+    Doctor,
+
+    /// 抓取书签网页，提取标题、描述与小标题建立正文索引，之后搜索也能匹配网页内容（需手动运行）
+    #[command(name = "index-content")]
+    IndexContent {
+        /// 本次最多抓取的网页数（优先抓取从未抓取或最久未抓取的）
+        #[arg(short = 'l', long = "limit", default_value = "100")]
+        limit: usize,
+
+        /// 单个请求超时秒数
+        #[arg(long = "timeout", default_value = "10")]
+        timeout: u64,
+
+        /// 抓取超过该天数的网页重新抓取
+        #[arg(long = "max-age", default_value = "30")]
+        max_age_days: u64,
+    },
+
+    /// 检查书签链接（跟随重定向并记录最终 URL）
+    #[command(name = "check-links")]
+    CheckLinks {
+        /// 本次最多检查的链接数（优先检查从未检查或最久未检查的）
+        #[arg(short = 'l', long = "limit", default_value = "100")]
+        limit: usize,
+
+        /// 单个请求超时秒数
+        #[arg(long = "timeout", default_value = "8")]
+        timeout: u64,
+
+        /// 只展示已保存的检查结果，不发起网络请求
+        #[arg(long = "report")]
+        report: bool,
+
+        /// 不立即检查，把全部待检查链接加入后台任务队列（由 run-jobs 处理）
+        #[arg(long = "enqueue", conflicts_with = "report")]
+        enqueue: bool,
+    },
+
+    /// 定时扫描失效链接：把一个周期内未检查的链接排队，每次只检查一部分
+    #[command(name = "scan-links")]
+    ScanLinks {
+        /// 两次扫描的间隔秒数（用于计算每次检查数量）
+        #[arg(long = "interval", default_value = "3600")]
+        interval: u64,
+
+        /// 多少天内把全部链接检查一遍
+        #[arg(long = "period-days", default_value = "7")]
+        period_days: u64,
+
+        /// 单次最多检查的链接数
+        #[arg(long = "max", default_value = "200")]
+        max: usize,
+
+        /// 单个请求超时秒数
+        #[arg(long = "timeout", default_value = "8")]
+        timeout: u64,
+    },
+
+    /// 处理后台任务队列（链接检查等），失败的任务按指数退避重试
+    #[command(name = "run-jobs")]
+    RunJobs {
+        /// 本次最多处理的任务数
+        #[arg(short = 'l', long = "limit", default_value = "50")]
+        limit: usize,
+
+        /// 单个网络请求超时秒数
+        #[arg(long = "timeout", default_value = "8")]
+        timeout: u64,
+
+        /// 只处理指定类型的任务（link_check / favicon）
+        #[arg(long = "kind")]
+        kind: Option<String>,
+    },
+
+    /// 为还没有图标的站点排队下载 favicon 并处理（刷新后自动在后台执行）
+    #[command(name = "prefetch-favicons")]
+    PrefetchFavicons {
+        /// 本次最多下载的图标数
+        #[arg(short = 'l', long = "limit", default_value = "100")]
+        limit: usize,
+
+        /// 单个请求超时秒数
+        #[arg(long = "timeout", default_value = "5")]
+        timeout: u64,
+    },
+
+    /// 列出已永久迁移的书签及其新地址
+    #[command(name = "fix-redirects")]
+    FixRedirects {
+        /// 导出为可被浏览器导入的 HTML 书签文件
+        #[arg(long = "export")]
+        export: Option<PathBuf>,
+    },
+
+    /// 查找重复书签（默认按规范化后的 URL 分组）
+    #[command(name = "dedupe", visible_alias = "duplicates")]
+    Dedupe {
+        /// 按近似标题聚类（标题相近但 URL 不同）
+        #[arg(long = "similar")]
+        similar: bool,
+
+        /// 近似标题的相似度阈值（0-1）
+        #[arg(long = "threshold", default_value = "0.85")]
+        threshold: f64,
+
+        /// 列出同一 URL 被多个浏览器 / profile 收藏的情况
+        #[arg(long = "across-sources", conflicts_with = "similar")]
+        across_sources: bool,
+    },
+
+    /// 标签维护
+    #[command(name = "tags")]
+    Tags {
+        #[command(subcommand)]
+        cmd: TagsCommand,
+    },
+
+    /// 为书签写备注（与标签一起保存，按 URL 关联）；备注参与搜索并显示在结果副标题中，不带内容时显示当前备注
+    #[command(name = "note")]
+    Note {
+        /// 书签 id（如搜索结果的 uid）
+        id: String,
+
+        /// 备注内容，替换原有备注
+        text: Vec<String>,
+
+        /// 删除备注
+        #[arg(long = "clear", conflicts_with = "text")]
+        clear: bool,
+    },
+
+    /// 置顶书签：空查询时列在最前，搜索命中时排在其他结果之前（按 URL 关联，刷新后保留）
+    #[command(name = "pin")]
+    Pin {
+        /// 书签 id（如搜索结果的 uid）
+        id: String,
+    },
+
+    /// 取消置顶
+    #[command(name = "unpin")]
+    Unpin {
+        /// 书签 id（如搜索结果的 uid）
+        id: String,
+    },
+
+    /// 打开链接（按配置清理跟踪参数）
+    #[command(name = "open")]
+    Open {
+        /// 要打开的 URL
+        url: String,
+
+        /// 在后台打开，不激活浏览器（`open -g`）
+        #[arg(long)]
+        background: bool,
+    },
+
+    /// 以富文本链接（标题为可点击的超链接）复制，同时保留纯文本 `标题 — URL`
+    #[command(name = "copy-rich")]
+    CopyRich {
+        /// 要复制的 URL（会按配置清理跟踪参数）
+        url: String,
+
+        /// 链接标题
+        #[arg(default_value = "")]
+        title: String,
+    },
+
+    /// 保存链接到本地书签，与浏览器书签一起参与搜索；`--to-chrome` 时改为写入 Chrome 书签
+    #[command(name = "add")]
+    Add {
+        /// 要保存的 URL
+        url: String,
+
+        /// 标题（缺省时使用 URL）
+        #[arg(default_value = "")]
+        title: String,
+
+        /// 所在目录（如 `Inbox/Reading`）
+        #[arg(long = "folder")]
+        folder: Option<String>,
+
+        /// 标签（逗号分隔）
+        #[arg(long = "tags")]
+        tags: Option<String>,
+
+        /// 写入 Chrome 的书签文件（需先退出 Chrome）
+        #[arg(long = "to-chrome")]
+        to_chrome: bool,
+
+        /// 配合 --to-chrome 指定 profile（缺省为第一个找到的 profile）
+        #[arg(long = "profile", requires = "to_chrome")]
+        profile: Option<String>,
+    },
+
+    /// 从纯文本链接列表批量导入本地书签（每行一个 URL，或 `标题<TAB>URL`）
+    #[command(name = "import")]
+    Import {
+        /// 链接列表文件
+        #[arg(long = "urls")]
+        urls: PathBuf,
+
+        /// 所在目录（如 `Inbox/Notes`）
+        #[arg(long = "folder")]
+        folder: Option<String>,
+
+        /// 给本批链接统一加的标签（逗号分隔）
+        #[arg(long = "tag")]
+        tag: Option<String>,
+    },
+
+    /// 把书签移入回收站：不再出现在任何搜索中，可用 `restore` 恢复
+    #[command(name = "delete")]
+    Delete {
+        /// 书签 ID
+        id: String,
+
+        /// 同时从 Chrome 的书签文件中删除（需先退出 Chrome，不进回收站、无法恢复）
+        #[arg(long = "from-chrome")]
+        from_chrome: bool,
+    },
+
+    /// 最近添加的书签，新的在前
+    #[command(name = "recent")]
+    Recent {
+        /// 关键词与过滤条件，语法同搜索（#目录、tag:、browser: 等）
+        query: Vec<String>,
+
+        /// 限制结果数量
+        #[arg(short = 'l', long = "limit", default_value = "30")]
+        limit: usize,
+    },
+
+    /// 通过本 Workflow 打开次数最多的书签
+    #[command(name = "frequent")]
+    Frequent {
+        /// 关键词与过滤条件，语法同搜索（#目录、tag:、browser: 等）
+        query: Vec<String>,
+
+        /// 限制结果数量
+        #[arg(short = 'l', long = "limit", default_value = "30")]
+        limit: usize,
+    },
+
+    /// 列出回收站中的书签
+    #[command(name = "trash")]
+    Trash {
+        /// 按标题 / URL 过滤的关键词
+        query: Vec<String>,
+    },
+
+    /// 从回收站恢复书签
+    #[command(name = "restore")]
+    Restore {
+        /// 书签 ID
+        id: String,
+    },
+
+    /// 从 `backup` 生成的归档恢复本地数据
+    #[command(name = "restore-backup")]
+    RestoreBackup {
+        /// 备份归档文件
+        archive: PathBuf,
+    },
+
+    /// 把本地数据库（索引、本地书签、回收站、标签、打开记录等）备份为单个归档
+    #[command(name = "backup")]
+    Backup {
+        /// 归档输出目录
+        dir: PathBuf,
+    },
+
+    /// 把书签移到其他目录；浏览器书签需加 `--to-chrome` 写回 Chrome
+    #[command(name = "move")]
+    Move {
+        /// 书签 ID
+        id: String,
+
+        /// 目标目录（如 `书签栏/ML`；Chrome 书签首段不是根目录名时放在“其他书签”下）
+        folder: String,
+
+        /// 写回 Chrome 的书签文件（需先退出 Chrome）
+        #[arg(long = "to-chrome")]
+        to_chrome: bool,
+    },
+
+    /// 修改书签标题；浏览器书签需加 `--to-chrome` 写回 Chrome
+    #[command(name = "retitle")]
+    Retitle {
+        /// 书签 ID
+        id: String,
+
+        /// 新标题
+        title: String,
+
+        /// 写回 Chrome 的书签文件（需先退出 Chrome）
+        #[arg(long = "to-chrome")]
+        to_chrome: bool,
+    },
+
+    /// 把搜索命中的全部书签移到同一目录（查询语法同 search，如 `tag:ml` / `#work rust`）
+    #[command(name = "move-all")]
+    MoveAll {
+        /// 查询与过滤条件（需用引号括成一个参数）
+        query: String,
+
+        /// 目标目录
+        folder: String,
+
+        /// 同时把 Chrome 书签写回 Chrome 的书签文件（需先退出 Chrome）
+        #[arg(long = "to-chrome")]
+        to_chrome: bool,
+
+        /// 只列出将被移动的书签，不修改
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+    },
+
+    /// 把源目录（含子目录）下的书签合并到目标目录，保留子目录结构
+    #[command(name = "merge-folders")]
+    MergeFolders {
+        /// 源目录（完整路径，如 `书签栏/Imported/Imported`）
+        src: String,
+
+        /// 目标目录
+        dst: String,
+
+        /// 同时把 Chrome 书签写回 Chrome 的书签文件（需先退出 Chrome）
+        #[arg(long = "to-chrome")]
+        to_chrome: bool,
+
+        /// 只列出将被移动的书签，不修改
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+    },
+
+    /// 列出单个书签的全部可用动作（二级菜单）
+    #[command(name = "actions-for")]
+    ActionsFor {
+        /// 书签 ID
+        id: String,
+    },
+
+    /// 输出书签详情（标题、URL、目录、标签、添加与最近打开时间），供 Large Type 显示
+    #[command(name = "details")]
+    Details {
+        /// 书签 ID
+        id: String,
+    },
+
+    /// 在浏览器的书签管理器中定位书签（仅支持按 profile 打开的 Chromium 浏览器）
+    #[command(name = "reveal")]
+    Reveal {
+        /// 书签 ID
+        id: String,
+    },
+
+    /// 重新显示上一次搜索的结果（误关 Alfred 后无需重新输入）
+    #[command(name = "last")]
+    Last,
+
+    /// 生成 URL 的二维码图片（PNG，缓存在 workflow 缓存目录），输出图片路径
+    #[command(name = "qr")]
+    Qr {
+        /// 要生成二维码的 URL（会按配置清理跟踪参数）
+        url: String,
+    },
+
+    /// 待打开队列：从多次搜索中收集书签，最后一次性打开
+    #[command(name = "queue")]
+    Queue {
+        #[command(subcommand)]
+        cmd: QueueCommand,
+    },
+
+    /// 管理链接检查与查重的忽略列表
+    #[command(name = "ignore")]
+    Ignore {
+        #[command(subcommand)]
+        cmd: IgnoreCommand,
+    },
+
+    /// 生成整理报告
+    #[command(name = "report")]
+    Report {
+        #[command(subcommand)]
+        cmd: ReportCommand,
+    },
+
+    /// 常驻监听书签文件，保存后立即增量刷新索引
+    #[command(name = "watch")]
+    Watch {
+        /// 收到变化后等待多少毫秒无新事件再刷新
+        #[arg(long = "debounce-ms", default_value = "300")]
+        debounce_ms: u64,
+    },
+
+    /// 执行插件目录中声明的书签动作，书签以 JSON 从 stdin 传给脚本（由 run.sh 调用）
+    #[command(name = "plugin")]
+    Plugin {
+        /// 插件名（清单文件名，不含 .json）
+        name: String,
+
+        /// 书签 id
+        id: String,
+    },
+
+    /// 保存或清除动作菜单跳转时的会话状态（由 run.sh 调用）
+    #[command(name = "session")]
+    Session {
+        #[command(subcommand)]
+        cmd: SessionCommand,
+    },
+
+    /// 通过 launchd 定时刷新索引
+    #[command(name = "schedule")]
+    Schedule {
+        #[command(subcommand)]
+        cmd: ScheduleCommand,
+    },
+
+    /// 预热数据库与书签来源缓存（由热键触发，让第一次按键更快）
+    #[command(name = "warmup")]
+    Warmup,
 
     /// 显示 workflow 动作列表
-    #[structopt(name = "actions", alias = "a")]
+    #[command(name = "actions", alias = "a")]
     Actions {
         /// 动作过滤关键词
         query: Vec<String>,
     },
 }
+
+impl SubCommand {
+    /// 用于按命令查找配置（如索引检查间隔）的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubCommand::Search { .. } => "search",
+            SubCommand::Get { .. } => "get",
+            SubCommand::Refresh => "refresh",
+            SubCommand::Stats { .. } => "stats",
+            SubCommand::Doctor => "doctor",
+            SubCommand::IndexContent { .. } => "index-content",
+            SubCommand::CheckLinks { .. } => "check-links",
+            SubCommand::ScanLinks { .. } => "scan-links",
+            SubCommand::RunJobs { .. } => "run-jobs",
+            SubCommand::PrefetchFavicons { .. } => "prefetch-favicons",
+            SubCommand::FixRedirects { .. } => "fix-redirects",
+            SubCommand::Dedupe { .. } => "dedupe",
+            SubCommand::Tags { .. } => "tags",
+            SubCommand::Note { .. } => "note",
+            SubCommand::Pin { .. } => "pin",
+            SubCommand::Unpin { .. } => "unpin",
+            SubCommand::Open { .. } => "open",
+            SubCommand::CopyRich { .. } => "copy-rich",
+            SubCommand::Add { .. } => "add",
+            SubCommand::Import { .. } => "import",
+            SubCommand::Delete { .. } => "delete",
+            SubCommand::Recent { .. } => "recent",
+            SubCommand::Frequent { .. } => "frequent",
+            SubCommand::Trash { .. } => "trash",
+            SubCommand::Restore { .. } => "restore",
+            SubCommand::RestoreBackup { .. } => "restore-backup",
+            SubCommand::Backup { .. } => "backup",
+            SubCommand::Move { .. } => "move",
+            SubCommand::Retitle { .. } => "retitle",
+            SubCommand::MoveAll { .. } => "move-all",
+            SubCommand::MergeFolders { .. } => "merge-folders",
+            SubCommand::ActionsFor { .. } => "actions-for",
+            SubCommand::Details { .. } => "details",
+            SubCommand::Reveal { .. } => "reveal",
+            SubCommand::Last => "last",
+            SubCommand::Qr { .. } => "qr",
+            SubCommand::Queue { .. } => "queue",
+            SubCommand::Ignore { .. } => "ignore",
+            SubCommand::Report { .. } => "report",
+            SubCommand::Watch { .. } => "watch",
+            SubCommand::Plugin { .. } => "plugin",
+            SubCommand::Session { .. } => "session",
+            SubCommand::Schedule { .. } => "schedule",
+            SubCommand::Warmup => "warmup",
+            SubCommand::Actions { .. } => "actions",
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StatsCommand {
+    /// 数据库文件与 WAL 大小、FTS 占用、各表行数、碎片率、上次 VACUUM 与刷新耗时
+    #[command(name = "index")]
+    Index {
+        /// 先执行 VACUUM 回收空闲页再报告
+        #[arg(long = "vacuum")]
+        vacuum: bool,
+
+        /// 输出 JSON 而不是 Alfred 条目
+        #[arg(long = "json")]
+        json: bool,
+    },
+
+    /// 最近搜索各阶段（索引检查、查询、输出、总计）耗时的 p50 / p95（需开启 ALFRED_CHROME_BOOKMARKS_LATENCY）
+    #[command(name = "latency")]
+    Latency {
+        /// 输出 JSON 而不是 Alfred 条目
+        #[arg(long = "json")]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TagsCommand {
+    /// 为书签添加本地标签
+    #[command(name = "add")]
+    Add {
+        /// 书签 id（如搜索结果的 uid）
+        id: String,
+
+        /// 标签（逗号分隔）
+        tags: String,
+    },
+
+    /// 去掉书签上的本地标签
+    #[command(name = "remove", alias = "rm")]
+    Remove {
+        /// 书签 id（如搜索结果的 uid）
+        id: String,
+
+        /// 标签（逗号分隔）
+        tags: String,
+    },
+
+    /// 列出全部标签及其书签数
+    #[command(name = "list", alias = "ls")]
+    List,
+
+    /// 列出书签上的本地标签
+    #[command(name = "show")]
+    Show {
+        /// 书签 id（如搜索结果的 uid）
+        id: String,
+    },
+
+    /// 标签改名；新名称已存在时合并进该标签
+    #[command(name = "rename", alias = "mv")]
+    Rename {
+        /// 原标签名（不区分大小写）
+        from: String,
+
+        /// 新标签名
+        to: String,
+    },
+
+    /// 与共享目录同步团队标签（需设置 ALFRED_CHROME_BOOKMARKS_SHARED_TAGS）
+    #[command(name = "sync")]
+    Sync,
+
+    /// 清理指向已删除书签的标签，并移除未被使用的标签
+    #[command(name = "prune")]
+    Prune {
+        /// 只列出将被清理的内容，不修改数据
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+    },
+
+    /// 把本地标签以 ` #tag` 后缀写入 Chrome 书签标题（需开启 ALFRED_CHROME_BOOKMARKS_TITLE_TAGS，并先退出 Chrome）
+    #[command(name = "sync-chrome")]
+    SyncChrome {
+        /// 只列出将写入的标题，不修改
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IgnoreCommand {
+    /// 添加忽略规则（完整 URL 或域名，域名包含子域名）
+    #[command(name = "add")]
+    Add { pattern: String },
+
+    /// 移除忽略规则
+    #[command(name = "remove", alias = "rm")]
+    Remove { pattern: String },
+
+    /// 列出全部忽略规则
+    #[command(name = "list", alias = "ls")]
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum QueueCommand {
+    /// 加入队列（同一 URL 只保留一条）
+    #[command(name = "add")]
+    Add {
+        url: String,
+
+        /// 链接标题
+        #[arg(default_value = "")]
+        title: String,
+    },
+
+    /// 列出队列中的链接
+    #[command(name = "list", alias = "ls")]
+    List,
+
+    /// 打开队列中的全部链接并清空队列
+    #[command(name = "open-all")]
+    OpenAll,
+
+    /// 清空队列
+    #[command(name = "clear")]
+    Clear,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SessionCommand {
+    /// 保存 `search:` 动作携带的会话状态
+    #[command(name = "set")]
+    Set { state: String },
+
+    /// 清除会话状态
+    #[command(name = "clear")]
+    Clear,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ReportCommand {
+    /// 汇总重复、失效、长期未整理与未打标签的书签（Markdown）
+    #[command(name = "cleanup")]
+    Cleanup {
+        /// 添加超过多少天视为长期未整理
+        #[arg(long = "stale-days", default_value = "730")]
+        stale_days: u64,
+
+        #[command(flatten)]
+        output: ReportOutput,
+    },
+
+    /// 每月 / 每年新增的书签数（文本直方图）
+    #[command(name = "growth")]
+    Growth {
+        /// 统计粒度：month、year
+        #[arg(long = "by", default_value = "month")]
+        by: GrowthPeriod,
+
+        #[command(flatten)]
+        output: ReportOutput,
+    },
+
+    /// 各标签的书签数；`--cooccurrence` 改为列出最常一起出现的标签对
+    #[command(name = "tags")]
+    Tags {
+        /// 统计标签共现（同一书签上同时出现的标签对）
+        #[arg(long = "cooccurrence")]
+        cooccurrence: bool,
+
+        /// 列出的条数
+        #[arg(short = 'l', long = "limit", default_value = "20")]
+        limit: usize,
+
+        #[command(flatten)]
+        output: ReportOutput,
+    },
+
+    /// 按目录子树统计书签数，以缩进的文本条形图输出
+    #[command(name = "folders")]
+    Folders {
+        /// 展开的目录层数（根目录为第 1 层）
+        #[arg(long = "depth", default_value = "2")]
+        depth: usize,
+
+        #[command(flatten)]
+        output: ReportOutput,
+    },
+
+    /// 书签最多的站点及其占比
+    #[command(name = "domains")]
+    Domains {
+        /// 列出的站点数
+        #[arg(short = 'l', long = "limit", default_value = "20")]
+        limit: usize,
+
+        #[command(flatten)]
+        output: ReportOutput,
+    },
+
+    /// 最常用的搜索与没有结果的搜索（需开启 ALFRED_CHROME_BOOKMARKS_HISTORY）
+    #[command(name = "queries")]
+    Queries {
+        /// 每类列出的条数
+        #[arg(short = 'l', long = "limit", default_value = "20")]
+        limit: usize,
+
+        #[command(flatten)]
+        output: ReportOutput,
+    },
+
+    /// 没有本地标签或直接放在根目录的书签（⌘ 回车直接添加标签）
+    #[command(name = "untagged")]
+    Untagged {
+        /// 列出的书签数
+        #[arg(short = 'l', long = "limit", default_value = "50")]
+        limit: usize,
+
+        #[command(flatten)]
+        output: ReportOutput,
+    },
+}
+
+/// 所有 `report` 子命令共用的导出选项；都不指定时输出各报告的默认视图
+#[derive(Args, Debug, Default)]
+pub struct ReportOutput {
+    /// 写入文件而不是标准输出；未指定 --format 时按扩展名（.md / .csv / .json）决定格式，默认 Markdown
+    #[arg(long = "out")]
+    pub out: Option<PathBuf>,
+
+    /// 导出格式：md、csv、json
+    #[arg(long = "format")]
+    pub format: Option<ReportFormat>,
+
+    /// 等同于 --format json
+    #[arg(long = "json", conflicts_with = "format")]
+    pub json: bool,
+}
+
+impl ReportOutput {
+    /// 本次要导出的格式；None 表示输出默认视图（Alfred 条目或文本）
+    pub fn format(&self) -> Option<ReportFormat> {
+        if let Some(format) = self.format {
+            return Some(format);
+        }
+        if self.json {
+            return Some(ReportFormat::Json);
+        }
+        self.out
+            .as_deref()
+            .map(|path| ReportFormat::from_path(path).unwrap_or(ReportFormat::Markdown))
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ScheduleCommand {
+    /// 安装（或更新）按间隔执行 refresh 的 launchd 任务
+    #[command(name = "install")]
+    Install {
+        /// 刷新间隔秒数（最小 60）
+        #[arg(long = "interval", default_value = "1800")]
+        interval: u64,
+
+        /// 同时安装失效链接扫描任务，按该间隔秒数执行 scan-links
+        #[arg(long = "link-scan-interval")]
+        link_scan_interval: Option<u64>,
+    },
+
+    /// 卸载全部 launchd 任务
+    #[command(name = "remove", alias = "uninstall")]
+    Remove,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use std::path::Path;
+
+    #[test]
+    fn cli_definition_is_consistent() {
+        Opt::command().debug_assert();
+    }
+
+    #[test]
+    fn global_flags_work_after_the_subcommand() {
+        let opt = Opt::try_parse_from([
+            "alfred-chrome-bookmarks",
+            "report",
+            "tags",
+            "--data-dir",
+            "/tmp/data",
+            "-v",
+        ])
+        .expect("parse");
+        assert_eq!(opt.data_dir, Some(PathBuf::from("/tmp/data")));
+        assert!(opt.verbose);
+        assert!(matches!(
+            opt.cmd,
+            SubCommand::Report {
+                cmd: ReportCommand::Tags { limit: 20, .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn restore_takes_a_trash_id_and_backups_have_their_own_command() {
+        let parse = |args: &[&str]| {
+            Opt::try_parse_from(["alfred-chrome-bookmarks"].iter().chain(args)).expect("parse")
+        };
+        assert!(matches!(
+            parse(&["restore", "12"]).cmd,
+            SubCommand::Restore { id } if id == "12"
+        ));
+        assert!(matches!(
+            parse(&["restore-backup", "backup.tar"]).cmd,
+            SubCommand::RestoreBackup { archive } if archive == Path::new("backup.tar")
+        ));
+    }
+
+    #[test]
+    fn search_accepts_browser_and_profile_filters() {
+        let opt = Opt::try_parse_from([
+            "alfred-chrome-bookmarks",
+            "search",
+            "--browser",
+            "arc",
+            "--profile",
+            "Work",
+            "rust",
+        ])
+        .expect("parse");
+        let SubCommand::Search {
+            query,
+            sources,
+            profile,
+            ..
+        } = opt.cmd
+        else {
+            panic!("expected search");
+        };
+        assert_eq!(query, vec!["rust".to_string()]);
+        assert_eq!(sources.as_deref(), Some("arc"));
+        assert_eq!(profile.as_deref(), Some("Work"));
+    }
+}
@@ -0,0 +1,120 @@
+use std::process::Command;
+
+/// 通过 JXA 同时写入纯文本、HTML 与 RTF 三种剪贴板格式；参数经 argv 传入，无需转义
+const PASTEBOARD_SCRIPT: &str = r#"
+ObjC.import('AppKit');
+function run(argv) {
+  var pb = $.NSPasteboard.generalPasteboard;
+  pb.clearContents;
+  pb.setStringForType($(argv[1]), $.NSPasteboardTypeHTML);
+  pb.setStringForType($(argv[2]), $.NSPasteboardTypeRTF);
+  pb.setStringForType($(argv[0]), $.NSPasteboardTypeString);
+}
+"#;
+
+/// 纯文本形式：`标题 — URL`；没有标题时只有 URL
+pub fn rich_link_plain(title: &str, url: &str) -> String {
+    if title.trim().is_empty() {
+        url.to_string()
+    } else {
+        format!("{} — {}", title.trim(), url)
+    }
+}
+
+pub fn rich_link_html(title: &str, url: &str) -> String {
+    let text = if title.trim().is_empty() {
+        url
+    } else {
+        title.trim()
+    };
+    format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(text))
+}
+
+/// RTF 超链接字段；非 ASCII 字符写成 `\uN?`（UTF-16 码元，RTF 要求有符号 16 位）
+pub fn rich_link_rtf(title: &str, url: &str) -> String {
+    let text = if title.trim().is_empty() {
+        url
+    } else {
+        title.trim()
+    };
+    format!(
+        "{{\\rtf1\\ansi\\deff0{{\\field{{\\*\\fldinst{{HYPERLINK \"{}\"}}}}{{\\fldrslt{{{}}}}}}}}}",
+        escape_rtf(url),
+        escape_rtf(text)
+    )
+}
+
+/// 把 `标题 — URL` 以富文本链接放入剪贴板，粘贴到 Pages / Mail 时是可点击的标题
+pub fn copy_rich_link(title: &str, url: &str) -> Result<(), String> {
+    let status = Command::new("osascript")
+        .arg("-l")
+        .arg("JavaScript")
+        .arg("-e")
+        .arg(PASTEBOARD_SCRIPT)
+        .arg(rich_link_plain(title, url))
+        .arg(rich_link_html(title, url))
+        .arg(rich_link_rtf(title, url))
+        .status()
+        .map_err(|err| err.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("osascript 退出码 {}", status.code().unwrap_or(-1)))
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_rtf(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '"' => escaped.push_str("\\'22"),
+            ch if ch.is_ascii() => escaped.push(ch),
+            ch => {
+                let mut units = [0u16; 2];
+                for unit in ch.encode_utf16(&mut units) {
+                    escaped.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rich_link_formats_escape_markup() {
+        let url = "https://example.com/?a=1&b=2";
+        assert_eq!(
+            rich_link_plain("Tom & Jerry", url),
+            "Tom & Jerry — https://example.com/?a=1&b=2"
+        );
+        assert_eq!(
+            rich_link_html("<Tom & Jerry>", url),
+            "<a href=\"https://example.com/?a=1&amp;b=2\">&lt;Tom &amp; Jerry&gt;</a>"
+        );
+        assert_eq!(rich_link_plain("  ", url), url);
+    }
+
+    #[test]
+    fn rtf_escapes_braces_and_unicode() {
+        let rtf = rich_link_rtf("设计{模式}", "https://a.example");
+        assert!(rtf.contains("HYPERLINK \"https://a.example\""));
+        assert!(rtf.contains("\\u-29762?\\u-29791?\\{\\u27169?\\u24335?\\}"));
+        assert!(rtf.starts_with("{\\rtf1") && rtf.ends_with('}'));
+    }
+}
@@ -0,0 +1,976 @@
+use crate::bookmark::ChromeBookmark;
+use crate::matcher::MatcherKind;
+use crate::searcher::DiversifyBy;
+use crate::tags::tag_key;
+use crate::tokenize::TokenizeConfig;
+use crate::url_normalize::DEFAULT_TRACKING_PARAMS;
+use std::path::{Path, PathBuf};
+
+/// 未单独配置的命令沿用的索引检查间隔，减少连续按键触发时的重复检查
+pub const DEFAULT_INDEX_TTL_MS: u64 = 2_000;
+
+/// 不经过 Alfred 运行时指定数据目录 / 缓存目录的变量，与 `--data-dir` / `--cache-dir` 对应
+pub const DATA_DIR_VAR: &str = "ALFRED_CHROME_BOOKMARKS_DATA_DIR";
+pub const CACHE_DIR_VAR: &str = "ALFRED_CHROME_BOOKMARKS_CACHE_DIR";
+
+/// 本次运行使用的数据目录与缓存目录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dirs {
+    pub data: PathBuf,
+    pub cache: PathBuf,
+}
+
+impl Dirs {
+    /// 数据目录依次取 `--data-dir`（含 `ALFRED_CHROME_BOOKMARKS_DATA_DIR`）、Alfred 的 `alfred_workflow_data`、
+    /// `~/.alfred-chrome-bookmarks`；缓存目录同理，最后回退到数据目录。
+    /// 相对路径按当前目录展开，后台子进程与 launchd 任务才能找到同一位置；无从确定时返回 None
+    pub fn resolve<F>(
+        data_dir: Option<&Path>,
+        cache_dir: Option<&Path>,
+        lookup: F,
+        home: Option<&Path>,
+        cwd: &Path,
+    ) -> Option<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let from_env = |key: &str| {
+            lookup(key)
+                .filter(|value| !value.trim().is_empty())
+                .map(PathBuf::from)
+        };
+        let data = data_dir
+            .map(Path::to_path_buf)
+            .or_else(|| from_env("alfred_workflow_data"))
+            .or_else(|| home.map(|home| home.join(".alfred-chrome-bookmarks")))?;
+        let data = cwd.join(data);
+        let cache = cache_dir
+            .map(Path::to_path_buf)
+            .or_else(|| from_env("alfred_workflow_cache"))
+            .map(|cache| cwd.join(cache))
+            .unwrap_or_else(|| data.clone());
+        Some(Dirs { data, cache })
+    }
+}
+
+/// 默认内存预算（MB），约合五万个书签
+pub const DEFAULT_MEMORY_BUDGET_MB: u64 = 32;
+
+/// 运行配置，来源于 Alfred workflow 变量（环境变量）
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub tracking: TrackingConfig,
+    pub merge: MergeConfig,
+    /// 按优先级排列的浏览器 key；为空表示扫描全部受支持浏览器
+    pub browsers: Vec<String>,
+    /// 索引了多个来源时，在结果副标题后追加来源徽标（如 `[Arc·Work]`）
+    pub source_badge: bool,
+    /// 搜索结果的 uid 策略，决定排序交给 Alfred 学习还是完全按本程序打分
+    pub uid: UidMode,
+    /// 搜索结果的图标来源：浏览器自带的图标库，或另外在后台下载站点图标（默认关闭）
+    pub favicons: FaviconMode,
+    /// 额外建立 trigram 子串索引，支持 URL 与中文标题的中间匹配（索引更大，默认关闭）
+    pub substring_search: bool,
+    /// 记录搜索历史，空查询或 `!` 开头时列出最近的搜索（默认关闭）
+    pub query_history: bool,
+    /// 记录书签打开次数（仅本地），供 `--sort frecency` 使用；默认开启
+    pub usage_tracking: bool,
+    /// 记录每次搜索各阶段的耗时（仅本地），供 `stats latency` 使用；默认关闭
+    pub latency_log: bool,
+    /// 全量加载书签的内存预算（字节）；FTS 不可用或模糊搜索需扫描全部书签且超出预算时改为分批流式打分，None 表示不限制
+    pub memory_budget: Option<u64>,
+    /// FTS 标题、URL、目录各自的分词方式；变化时自动重建 FTS
+    pub tokenize: TokenizeConfig,
+    /// 保留最近多少次搜索渲染好的结果，输入与数据都没变时直接输出；0 表示关闭
+    pub result_cache: usize,
+    /// 在书签所属的 Chrome profile 中打开（`--profile-directory`），而非当前最前的 profile；默认开启
+    pub open_in_profile: bool,
+    /// 最前面的应用是受支持的浏览器时，直接在该浏览器中打开（优先于按 profile 打开）；默认关闭
+    pub open_in_frontmost: bool,
+    /// 限制同一目录或站点在前列结果中的条数；None 表示保持原排序
+    pub diversify: Option<DiversifyConfig>,
+    /// 模糊搜索使用的匹配算法
+    pub matcher: MatcherKind,
+    /// 偏好域名（小写，含子域名），命中结果排序加权
+    pub boost_domains: Vec<String>,
+    pub index_ttl: IndexTtlConfig,
+    /// 刷新成功后在后台执行的命令
+    pub post_refresh_hook: Option<String>,
+    /// 标签库与打开记录库的加密密钥（原样保存，`keychain:` 前缀在启动时才解析）
+    pub db_key: Option<String>,
+    /// 插件目录；None 时为数据目录下的 `plugins/`
+    pub plugins_dir: Option<PathBuf>,
+    /// 团队共享标签目录；None 表示只用本地标签
+    pub shared_tags: Option<PathBuf>,
+    /// 共享标签中的成员名；None 时自动生成并记在标签库中
+    pub member: Option<String>,
+    /// 没有匹配书签时追加的网页搜索条目
+    pub fallbacks: Vec<FallbackSearch>,
+    /// 建立索引时把书签标题末尾的 ` #tag` 解析为本地标签，并允许 `tags sync-chrome` 写回；默认关闭
+    pub title_tags: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiversifyConfig {
+    pub by: DiversifyBy,
+    pub max_per_group: usize,
+}
+
+impl DiversifyConfig {
+    const DEFAULT_MAX_PER_GROUP: usize = 3;
+
+    /// `folder`、`domain`，可带上限如 `folder:2`；其他值（含 `off`）表示关闭
+    fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim().to_ascii_lowercase();
+        let (by, max) = match raw.split_once([':', '=']) {
+            Some((by, max)) => (by.trim().to_string(), Some(max.trim().parse().ok()?)),
+            None => (raw, None),
+        };
+        let by = match by.as_str() {
+            "folder" | "dir" => DiversifyBy::Folder,
+            "domain" | "site" => DiversifyBy::Domain,
+            _ => return None,
+        };
+        Some(DiversifyConfig {
+            by,
+            max_per_group: max
+                .filter(|max: &usize| *max > 0)
+                .unwrap_or(Self::DEFAULT_MAX_PER_GROUP),
+        })
+    }
+}
+
+/// 搜索结果图标的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FaviconMode {
+    /// 统一使用默认图标（默认）
+    #[default]
+    Off,
+    /// 刷新索引时从 Chromium 系浏览器 profile 的 Favicons 库导出页面图标，不访问网络
+    Browser,
+    /// 在 `Browser` 之外，刷新后在后台为仍缺图标的站点下载 favicon（会访问书签所在站点）
+    Download,
+}
+
+impl FaviconMode {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "browser" | "local" => Some(FaviconMode::Browser),
+            "on" | "true" | "1" | "yes" | "download" => Some(FaviconMode::Download),
+            "off" | "false" | "0" | "no" => Some(FaviconMode::Off),
+            _ => None,
+        }
+    }
+
+    pub fn enabled(self) -> bool {
+        self != FaviconMode::Off
+    }
+}
+
+/// 搜索结果 uid 的生成方式。Alfred 会按 uid 记住用户的选择并据此调整排序，
+/// 不给 uid 时结果完全按本程序给出的顺序显示
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UidMode {
+    /// 以规范化 URL 的摘要（即 `tag_key`）为 uid，跨刷新、跨重建索引不变（默认）
+    #[default]
+    Stable,
+    /// uid 带上查询词，Alfred 只在同一查询内学习排序；空查询不给 uid，列表顺序固定
+    Query,
+    /// 不给 uid，始终按本程序的打分排序
+    None,
+}
+
+impl UidMode {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "stable" | "guid" | "on" => Some(UidMode::Stable),
+            "query" | "per-query" => Some(UidMode::Query),
+            "none" | "off" => Some(UidMode::None),
+            _ => None,
+        }
+    }
+
+    /// 结果条目的 uid；`query` 为用户输入的原始查询
+    pub fn uid_for(self, bookmark: &ChromeBookmark, query: &str) -> Option<String> {
+        let query = query.trim().to_lowercase();
+        match self {
+            UidMode::Stable => Some(tag_key(bookmark)),
+            UidMode::Query if query.is_empty() => None,
+            UidMode::Query => Some(format!("{}|{}", query, tag_key(bookmark))),
+            UidMode::None => None,
+        }
+    }
+}
+
+/// 网页搜索兜底：`{query}` 会被替换为编码后的关键词
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FallbackSearch {
+    pub name: String,
+    pub template: String,
+}
+
+impl FallbackSearch {
+    pub fn url_for(&self, query: &str) -> String {
+        self.template.replace(
+            "{query}",
+            &crate::url_normalize::percent_encode_component(query),
+        )
+    }
+
+    fn defaults() -> Vec<Self> {
+        vec![
+            FallbackSearch {
+                name: "Google".to_string(),
+                template: "https://www.google.com/search?q={query}".to_string(),
+            },
+            FallbackSearch {
+                name: "GitHub".to_string(),
+                template: "https://github.com/search?q={query}".to_string(),
+            },
+        ]
+    }
+
+    /// `名称=URL模板`，多项用 `;` 或换行分隔；`none` 关闭；缺少 `{query}` 的项忽略
+    fn parse_list(raw: &str) -> Vec<Self> {
+        raw.split([';', '\n'])
+            .filter_map(|entry| {
+                let (name, template) = entry.split_once('=')?;
+                let (name, template) = (name.trim(), template.trim());
+                (!name.is_empty() && template.contains("{query}")).then(|| FallbackSearch {
+                    name: name.to_string(),
+                    template: template.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// 各命令可接受的索引陈旧时间：距上次检查不超过该值时跳过指纹检查
+#[derive(Debug, Clone)]
+pub struct IndexTtlConfig {
+    pub default_ms: u64,
+    pub per_command: Vec<(String, u64)>,
+}
+
+impl Default for IndexTtlConfig {
+    fn default() -> Self {
+        Self {
+            default_ms: DEFAULT_INDEX_TTL_MS,
+            // stats 用来确认索引状态，默认每次都检查
+            per_command: vec![("stats".to_string(), 0)],
+        }
+    }
+}
+
+impl IndexTtlConfig {
+    pub fn ttl_ms(&self, command: &str) -> u64 {
+        self.per_command
+            .iter()
+            .find(|(name, _)| name == command)
+            .map(|(_, ttl)| *ttl)
+            .unwrap_or(self.default_ms)
+    }
+
+    /// `search=10s,stats=0,default=1500ms`：不带命令名的值作为默认值；无法解析的项忽略
+    fn parse(raw: &str) -> Self {
+        let mut config = Self::default();
+        for entry in parse_list(raw) {
+            let (command, value) = match entry.split_once('=') {
+                Some((command, value)) => (command.trim(), value.trim()),
+                None => ("default", entry.as_str()),
+            };
+            let Some(ttl) = parse_duration_ms(value) else {
+                continue;
+            };
+            if command == "default" {
+                config.default_ms = ttl;
+            } else if let Some(slot) = config
+                .per_command
+                .iter_mut()
+                .find(|(name, _)| name == command)
+            {
+                slot.1 = ttl;
+            } else {
+                config.per_command.push((command.to_string(), ttl));
+            }
+        }
+        config
+    }
+}
+
+/// 毫秒数，支持 `ms` / `s` 后缀
+fn parse_duration_ms(raw: &str) -> Option<u64> {
+    if let Some(ms) = raw.strip_suffix("ms") {
+        ms.trim().parse().ok()
+    } else if let Some(secs) = raw.strip_suffix('s') {
+        secs.trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|secs| *secs >= 0.0)
+            .map(|secs| (secs * 1000.0) as u64)
+    } else {
+        raw.parse().ok()
+    }
+}
+
+/// 跨来源合并：同一规范 URL 默认取最新添加的版本，可指定优先浏览器
+#[derive(Debug, Clone, Default)]
+pub struct MergeConfig {
+    pub primary_browser: Option<String>,
+}
+
+/// 跟踪参数清理：可按动作（打开 / 复制）分别开关
+#[derive(Debug, Clone)]
+pub struct TrackingConfig {
+    pub strip_on_open: bool,
+    pub strip_on_copy: bool,
+    pub params: Vec<String>,
+}
+
+impl TrackingConfig {
+    pub fn clean_for_open(&self, url: &str) -> String {
+        if self.strip_on_open {
+            crate::url_normalize::strip_tracking_params(url, &self.params)
+        } else {
+            url.to_string()
+        }
+    }
+
+    pub fn clean_for_copy(&self, url: &str) -> String {
+        if self.strip_on_copy {
+            crate::url_normalize::strip_tracking_params(url, &self.params)
+        } else {
+            url.to_string()
+        }
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    pub fn from_lookup<F>(lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        // 未设置时默认在打开和复制时都清理；设置为 none/off/空 表示关闭
+        let actions = lookup("ALFRED_CHROME_BOOKMARKS_STRIP_TRACKING")
+            .map(|raw| parse_list(&raw))
+            .unwrap_or_else(|| vec!["open".to_string(), "copy".to_string()]);
+        let strip_all = actions.iter().any(|action| action == "all");
+
+        let mut params: Vec<String> = DEFAULT_TRACKING_PARAMS
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        if let Some(raw) = lookup("ALFRED_CHROME_BOOKMARKS_TRACKING_PARAMS") {
+            for param in parse_list(&raw) {
+                if !params.contains(&param) {
+                    params.push(param);
+                }
+            }
+        }
+
+        let primary_browser = lookup("ALFRED_CHROME_BOOKMARKS_PRIMARY_BROWSER")
+            .map(|raw| raw.trim().to_string())
+            .filter(|raw| !raw.is_empty())
+            .map(|raw| crate::bookmark::canonical_browser_key(&raw));
+
+        // `arc,chrome,edge`：既是扫描范围与顺序，也是合并结果的平手裁决顺序；含 all 时扫描全部
+        let mut browsers: Vec<String> = Vec::new();
+        for key in lookup("ALFRED_CHROME_BOOKMARKS_BROWSER")
+            .map(|raw| parse_list(&raw))
+            .unwrap_or_default()
+            .iter()
+            .map(|raw| crate::bookmark::canonical_browser_key(raw))
+        {
+            if !browsers.contains(&key) {
+                browsers.push(key);
+            }
+        }
+        if browsers.iter().any(|key| key == "all") {
+            browsers.clear();
+        }
+
+        let source_badge = !matches!(
+            lookup("ALFRED_CHROME_BOOKMARKS_SOURCE_BADGE")
+                .map(|raw| raw.trim().to_ascii_lowercase())
+                .as_deref(),
+            Some("off" | "none" | "false" | "0" | "hide")
+        );
+
+        let uid = lookup("ALFRED_CHROME_BOOKMARKS_UID")
+            .and_then(|raw| UidMode::parse(&raw))
+            .unwrap_or_default();
+
+        let favicons = lookup("ALFRED_CHROME_BOOKMARKS_FAVICONS")
+            .and_then(|raw| FaviconMode::parse(&raw))
+            .unwrap_or_default();
+
+        let substring_search = matches!(
+            lookup("ALFRED_CHROME_BOOKMARKS_SUBSTRING")
+                .map(|raw| raw.trim().to_ascii_lowercase())
+                .as_deref(),
+            Some("on" | "true" | "1" | "yes")
+        );
+
+        let query_history = matches!(
+            lookup("ALFRED_CHROME_BOOKMARKS_HISTORY")
+                .map(|raw| raw.trim().to_ascii_lowercase())
+                .as_deref(),
+            Some("on" | "true" | "1" | "yes")
+        );
+
+        let latency_log = matches!(
+            lookup("ALFRED_CHROME_BOOKMARKS_LATENCY")
+                .map(|raw| raw.trim().to_ascii_lowercase())
+                .as_deref(),
+            Some("on" | "true" | "1" | "yes")
+        );
+
+        let memory_budget = match lookup("ALFRED_CHROME_BOOKMARKS_MEMORY_BUDGET") {
+            Some(raw) => parse_memory_budget(&raw),
+            None => Some(DEFAULT_MEMORY_BUDGET_MB * 1024 * 1024),
+        };
+
+        let tokenize = lookup("ALFRED_CHROME_BOOKMARKS_TOKENIZE")
+            .map(|raw| TokenizeConfig::parse(&raw))
+            .unwrap_or_default();
+
+        let result_cache = match lookup("ALFRED_CHROME_BOOKMARKS_RESULT_CACHE")
+            .map(|raw| raw.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("off" | "none" | "false") => 0,
+            Some(raw) => raw
+                .parse()
+                .unwrap_or(crate::result_cache::DEFAULT_RESULT_CACHE_ENTRIES),
+            None => crate::result_cache::DEFAULT_RESULT_CACHE_ENTRIES,
+        };
+
+        let usage_tracking = !matches!(
+            lookup("ALFRED_CHROME_BOOKMARKS_USAGE")
+                .map(|raw| raw.trim().to_ascii_lowercase())
+                .as_deref(),
+            Some("off" | "none" | "false" | "0")
+        );
+
+        let open_in_profile = !matches!(
+            lookup("ALFRED_CHROME_BOOKMARKS_OPEN_IN_PROFILE")
+                .map(|raw| raw.trim().to_ascii_lowercase())
+                .as_deref(),
+            Some("off" | "none" | "false" | "0")
+        );
+
+        let open_in_frontmost = matches!(
+            lookup("ALFRED_CHROME_BOOKMARKS_OPEN_FRONTMOST")
+                .map(|raw| raw.trim().to_ascii_lowercase())
+                .as_deref(),
+            Some("on" | "true" | "1" | "yes")
+        );
+
+        let title_tags = matches!(
+            lookup("ALFRED_CHROME_BOOKMARKS_TITLE_TAGS")
+                .map(|raw| raw.trim().to_ascii_lowercase())
+                .as_deref(),
+            Some("on" | "true" | "1" | "yes")
+        );
+
+        let diversify = lookup("ALFRED_CHROME_BOOKMARKS_DIVERSIFY")
+            .and_then(|raw| DiversifyConfig::parse(&raw));
+
+        let matcher = lookup("ALFRED_CHROME_BOOKMARKS_MATCHER")
+            .and_then(|raw| MatcherKind::parse(&raw))
+            .unwrap_or_default();
+
+        let boost_domains = lookup("ALFRED_CHROME_BOOKMARKS_BOOST_DOMAINS")
+            .map(|raw| {
+                raw.split(',')
+                    .map(|domain| {
+                        domain
+                            .trim()
+                            .trim_start_matches("*.")
+                            .trim_matches('.')
+                            .to_ascii_lowercase()
+                    })
+                    .filter(|domain| !domain.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let index_ttl = lookup("ALFRED_CHROME_BOOKMARKS_INDEX_TTL")
+            .map(|raw| IndexTtlConfig::parse(&raw))
+            .unwrap_or_default();
+
+        let post_refresh_hook = lookup("ALFRED_CHROME_BOOKMARKS_POST_REFRESH_HOOK")
+            .map(|raw| raw.trim().to_string())
+            .filter(|raw| !raw.is_empty());
+
+        let db_key = lookup(crate::db_crypt::DB_KEY_VAR).filter(|raw| !raw.trim().is_empty());
+
+        let plugins_dir = lookup("ALFRED_CHROME_BOOKMARKS_PLUGINS")
+            .map(|raw| raw.trim().to_string())
+            .filter(|raw| !raw.is_empty())
+            .map(PathBuf::from);
+        let shared_tags = lookup("ALFRED_CHROME_BOOKMARKS_SHARED_TAGS")
+            .map(|raw| raw.trim().to_string())
+            .filter(|raw| !raw.is_empty())
+            .map(PathBuf::from);
+        let member = lookup("ALFRED_CHROME_BOOKMARKS_MEMBER")
+            .map(|raw| raw.trim().to_string())
+            .filter(|raw| !raw.is_empty());
+
+        let fallbacks = match lookup("ALFRED_CHROME_BOOKMARKS_FALLBACKS") {
+            Some(raw) if raw.trim().eq_ignore_ascii_case("none") => Vec::new(),
+            Some(raw) if !raw.trim().is_empty() => FallbackSearch::parse_list(&raw),
+            _ => FallbackSearch::defaults(),
+        };
+
+        Self {
+            merge: MergeConfig { primary_browser },
+            fallbacks,
+            post_refresh_hook,
+            db_key,
+            plugins_dir,
+            shared_tags,
+            member,
+            favicons,
+            substring_search,
+            query_history,
+            usage_tracking,
+            latency_log,
+            memory_budget,
+            tokenize,
+            result_cache,
+            open_in_profile,
+            open_in_frontmost,
+            title_tags,
+            diversify,
+            matcher,
+            boost_domains,
+            index_ttl,
+            browsers,
+            source_badge,
+            uid,
+            tracking: TrackingConfig {
+                strip_on_open: strip_all || actions.iter().any(|action| action == "open"),
+                strip_on_copy: strip_all || actions.iter().any(|action| action == "copy"),
+                params,
+            },
+        }
+    }
+}
+
+/// 解析逗号分隔的配置值：去空白、转小写、忽略空项
+pub fn parse_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// 解析 `--config` 文件：每行 `KEY=VALUE`，键与 workflow 变量同名；
+/// 支持 `#` 注释、`export ` 前缀与成对的引号
+pub fn parse_config_file(content: &str) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("第 {} 行缺少 =: {}", number + 1, line))?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("第 {} 行缺少变量名", number + 1));
+        }
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(*quote)
+                    .and_then(|rest| rest.strip_suffix(*quote))
+            })
+            .unwrap_or(value);
+        vars.push((key.to_string(), value.to_string()));
+    }
+    Ok(vars)
+}
+
+/// 把配置文件中的变量写入当前进程环境，环境中已设置的变量优先；
+/// 后台子进程与定时任务随之继承，返回实际生效的变量数
+pub fn apply_config_file(path: &Path) -> Result<usize, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("无法读取配置文件 {}: {}", path.display(), err))?;
+    let mut applied = 0;
+    for (key, value) in parse_config_file(&content)? {
+        if std::env::var_os(&key).is_none() {
+            std::env::set_var(&key, value);
+            applied += 1;
+        }
+    }
+    Ok(applied)
+}
+
+/// `64`、`64mb`（单位 MB）或 `1g`；`off`、`0` 表示不限制，无法识别时沿用默认值
+fn parse_memory_budget(raw: &str) -> Option<u64> {
+    const MB: u64 = 1024 * 1024;
+    let raw = raw.trim().to_ascii_lowercase();
+    if matches!(raw.as_str(), "off" | "none" | "false" | "0") {
+        return None;
+    }
+    let (number, scale) = match raw.strip_suffix("gb").or_else(|| raw.strip_suffix('g')) {
+        Some(number) => (number, 1024 * MB),
+        None => (
+            raw.strip_suffix("mb")
+                .or_else(|| raw.strip_suffix('m'))
+                .unwrap_or(&raw),
+            MB,
+        ),
+    };
+    match number.trim().parse::<u64>() {
+        Ok(value) if value > 0 => Some(value.saturating_mul(scale)),
+        _ => Some(DEFAULT_MEMORY_BUDGET_MB * MB),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+    use std::collections::HashMap;
+
+    fn config_from(vars: &[(&str, &str)]) -> Config {
+        let map: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Config::from_lookup(|key| map.get(key).cloned())
+    }
+
+    #[test]
+    fn tracking_cleanup_enabled_for_both_actions_by_default() {
+        let config = config_from(&[]);
+        assert!(config.tracking.strip_on_open);
+        assert!(config.tracking.strip_on_copy);
+        assert_eq!(
+            config
+                .tracking
+                .clean_for_copy("https://a.example/?utm_source=x"),
+            "https://a.example/"
+        );
+    }
+
+    #[test]
+    fn tracking_cleanup_can_be_toggled_per_action() {
+        let config = config_from(&[("ALFRED_CHROME_BOOKMARKS_STRIP_TRACKING", "copy")]);
+        assert!(!config.tracking.strip_on_open);
+        assert!(config.tracking.strip_on_copy);
+        assert_eq!(
+            config
+                .tracking
+                .clean_for_open("https://a.example/?utm_source=x"),
+            "https://a.example/?utm_source=x"
+        );
+
+        let disabled = config_from(&[("ALFRED_CHROME_BOOKMARKS_STRIP_TRACKING", "none")]);
+        assert!(!disabled.tracking.strip_on_open);
+        assert!(!disabled.tracking.strip_on_copy);
+    }
+
+    #[test]
+    fn tracking_params_can_be_extended() {
+        let config = config_from(&[("ALFRED_CHROME_BOOKMARKS_TRACKING_PARAMS", "spm, from_*")]);
+        assert_eq!(
+            config
+                .tracking
+                .clean_for_open("https://a.example/?id=1&spm=a&from_feed=1"),
+            "https://a.example/?id=1"
+        );
+    }
+
+    #[test]
+    fn primary_browser_is_canonicalized() {
+        assert_eq!(config_from(&[]).merge.primary_browser, None);
+        let config = config_from(&[("ALFRED_CHROME_BOOKMARKS_PRIMARY_BROWSER", " Google-Chrome ")]);
+        assert_eq!(config.merge.primary_browser.as_deref(), Some("chrome"));
+    }
+
+    #[test]
+    fn browser_list_keeps_order_and_canonicalizes_aliases() {
+        assert!(config_from(&[]).browsers.is_empty());
+        let config = config_from(&[(
+            "ALFRED_CHROME_BOOKMARKS_BROWSER",
+            "arc, google-chrome ,edge,chrome",
+        )]);
+        assert_eq!(config.browsers, vec!["arc", "chrome", "edge"]);
+        assert!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_BROWSER", "chrome,all")])
+                .browsers
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn source_badge_enabled_unless_turned_off() {
+        assert!(config_from(&[]).source_badge);
+        assert!(config_from(&[("ALFRED_CHROME_BOOKMARKS_SOURCE_BADGE", "auto")]).source_badge);
+        assert!(!config_from(&[("ALFRED_CHROME_BOOKMARKS_SOURCE_BADGE", "Off")]).source_badge);
+    }
+
+    #[test]
+    fn index_ttl_is_configurable_per_command() {
+        let defaults = config_from(&[]).index_ttl;
+        assert_eq!(defaults.ttl_ms("search"), DEFAULT_INDEX_TTL_MS);
+        assert_eq!(defaults.ttl_ms("stats"), 0);
+
+        let config = config_from(&[(
+            "ALFRED_CHROME_BOOKMARKS_INDEX_TTL",
+            "search=10s, stats=500ms, 1.5s, dedupe=oops",
+        )]);
+        assert_eq!(config.index_ttl.ttl_ms("search"), 10_000);
+        assert_eq!(config.index_ttl.ttl_ms("stats"), 500);
+        assert_eq!(config.index_ttl.ttl_ms("dedupe"), 1_500);
+    }
+
+    #[test]
+    fn blank_post_refresh_hook_is_ignored() {
+        assert_eq!(config_from(&[]).post_refresh_hook, None);
+        assert_eq!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_POST_REFRESH_HOOK", "  ")]).post_refresh_hook,
+            None
+        );
+        assert_eq!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_POST_REFRESH_HOOK", " notify.sh ")])
+                .post_refresh_hook
+                .as_deref(),
+            Some("notify.sh")
+        );
+    }
+
+    #[test]
+    fn fallback_searches_default_to_google_and_github() {
+        let names: Vec<String> = config_from(&[])
+            .fallbacks
+            .into_iter()
+            .map(|fallback| fallback.name)
+            .collect();
+        assert_eq!(names, vec!["Google", "GitHub"]);
+        assert!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_FALLBACKS", "none")])
+                .fallbacks
+                .is_empty()
+        );
+
+        let config = config_from(&[(
+            "ALFRED_CHROME_BOOKMARKS_FALLBACKS",
+            "DuckDuckGo=https://duckduckgo.com/?q={query}; broken=https://x.example",
+        )]);
+        assert_eq!(config.fallbacks.len(), 1);
+        assert_eq!(
+            config.fallbacks[0].url_for("rust 模式"),
+            "https://duckduckgo.com/?q=rust%20%E6%A8%A1%E5%BC%8F"
+        );
+    }
+
+    #[test]
+    fn diversify_parses_group_and_limit() {
+        assert_eq!(config_from(&[]).diversify, None);
+        assert_eq!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_DIVERSIFY", "Domain:2")]).diversify,
+            Some(DiversifyConfig {
+                by: DiversifyBy::Domain,
+                max_per_group: 2
+            })
+        );
+        assert_eq!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_DIVERSIFY", "folder")])
+                .diversify
+                .map(|diversify| diversify.max_per_group),
+            Some(3)
+        );
+        assert_eq!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_DIVERSIFY", "off")]).diversify,
+            None
+        );
+    }
+
+    #[test]
+    fn uid_mode_controls_stability_and_scope() {
+        let bookmark = ChromeBookmark {
+            browser: "chrome".to_string(),
+            profile: "Default".to_string(),
+            guid: Some("g-42".to_string()),
+            ..test_bookmark("chrome:Default:42", "Docs", "https://docs.rs/", None)
+        };
+        let uid = |raw: &str, query: &str| {
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_UID", raw)])
+                .uid
+                .uid_for(&bookmark, query)
+        };
+
+        assert_eq!(config_from(&[]).uid, UidMode::Stable);
+        assert_eq!(uid("bogus", "rust"), Some(tag_key(&bookmark)));
+        assert_eq!(
+            uid("query", " Rust "),
+            Some(format!("rust|{}", tag_key(&bookmark)))
+        );
+        assert_eq!(uid("query", ""), None);
+        assert_eq!(uid("off", "rust"), None);
+    }
+
+    #[test]
+    fn matcher_falls_back_to_skim() {
+        assert_eq!(config_from(&[]).matcher, MatcherKind::Skim);
+        assert_eq!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_MATCHER", "nucleo")]).matcher,
+            MatcherKind::Nucleo
+        );
+        assert_eq!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_MATCHER", "bogus")]).matcher,
+            MatcherKind::Skim
+        );
+    }
+
+    #[test]
+    fn boost_domains_are_normalized() {
+        let config = config_from(&[(
+            "ALFRED_CHROME_BOOKMARKS_BOOST_DOMAINS",
+            " Docs.rs, *.apple.com ,, .python.org.",
+        )]);
+        assert_eq!(
+            config.boost_domains,
+            vec!["docs.rs", "apple.com", "python.org"]
+        );
+    }
+
+    #[test]
+    fn favicons_are_opt_in() {
+        assert_eq!(config_from(&[]).favicons, FaviconMode::Off);
+        assert_eq!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_FAVICONS", " On ")]).favicons,
+            FaviconMode::Download
+        );
+        assert_eq!(
+            config_from(&[("ALFRED_CHROME_BOOKMARKS_FAVICONS", "Browser")]).favicons,
+            FaviconMode::Browser
+        );
+        assert!(!config_from(&[("ALFRED_CHROME_BOOKMARKS_FAVICONS", "off")])
+            .favicons
+            .enabled());
+        assert!(!config_from(&[]).substring_search);
+        assert!(config_from(&[("ALFRED_CHROME_BOOKMARKS_SUBSTRING", "yes")]).substring_search);
+        assert!(!config_from(&[]).query_history);
+        assert!(config_from(&[("ALFRED_CHROME_BOOKMARKS_HISTORY", "on")]).query_history);
+        assert!(config_from(&[]).usage_tracking);
+        assert!(!config_from(&[("ALFRED_CHROME_BOOKMARKS_USAGE", "off")]).usage_tracking);
+        assert!(!config_from(&[]).latency_log);
+        assert!(config_from(&[("ALFRED_CHROME_BOOKMARKS_LATENCY", "on")]).latency_log);
+        assert!(config_from(&[]).open_in_profile);
+        assert!(!config_from(&[]).title_tags);
+        assert!(config_from(&[("ALFRED_CHROME_BOOKMARKS_TITLE_TAGS", "on")]).title_tags);
+        assert!(!config_from(&[]).open_in_frontmost);
+        assert!(config_from(&[("ALFRED_CHROME_BOOKMARKS_OPEN_FRONTMOST", "on")]).open_in_frontmost);
+        assert!(
+            !config_from(&[("ALFRED_CHROME_BOOKMARKS_OPEN_IN_PROFILE", "false")]).open_in_profile
+        );
+    }
+
+    #[test]
+    fn dirs_prefer_flags_then_alfred_then_home() {
+        let home = Path::new("/Users/me");
+        let cwd = Path::new("/work");
+        let alfred = |key: &str| match key {
+            "alfred_workflow_data" => Some("/alfred/data".to_string()),
+            "alfred_workflow_cache" => Some(String::new()),
+            _ => None,
+        };
+
+        let dirs = Dirs::resolve(None, None, alfred, Some(home), cwd).expect("dirs");
+        assert_eq!(dirs.data, PathBuf::from("/alfred/data"));
+        // 空的 alfred_workflow_cache 视为未设置
+        assert_eq!(dirs.cache, PathBuf::from("/alfred/data"));
+
+        let dirs = Dirs::resolve(
+            Some(Path::new("copy-a")),
+            Some(Path::new("/tmp/cache-a")),
+            alfred,
+            Some(home),
+            cwd,
+        )
+        .expect("dirs");
+        assert_eq!(dirs.data, PathBuf::from("/work/copy-a"));
+        assert_eq!(dirs.cache, PathBuf::from("/tmp/cache-a"));
+
+        let dirs = Dirs::resolve(None, None, |_| None, Some(home), cwd).expect("dirs");
+        assert_eq!(
+            dirs.data,
+            PathBuf::from("/Users/me/.alfred-chrome-bookmarks")
+        );
+        assert!(Dirs::resolve(None, None, |_| None, None, cwd).is_none());
+    }
+
+    #[test]
+    fn config_file_lines_become_variables() {
+        let vars = parse_config_file(
+            "# workflow 变量\n\
+             ALFRED_CHROME_BOOKMARKS_HISTORY=on\n\n\
+             export ALFRED_CHROME_BOOKMARKS_BROWSER = \"arc, chrome\"\n\
+             ALFRED_CHROME_BOOKMARKS_FALLBACKS='Google=https://google.com/search?q={query}'\n",
+        )
+        .expect("parse");
+        assert_eq!(
+            vars,
+            vec![
+                (
+                    "ALFRED_CHROME_BOOKMARKS_HISTORY".to_string(),
+                    "on".to_string()
+                ),
+                (
+                    "ALFRED_CHROME_BOOKMARKS_BROWSER".to_string(),
+                    "arc, chrome".to_string()
+                ),
+                (
+                    "ALFRED_CHROME_BOOKMARKS_FALLBACKS".to_string(),
+                    "Google=https://google.com/search?q={query}".to_string()
+                ),
+            ]
+        );
+        assert!(parse_config_file("HISTORY on").is_err());
+        assert!(parse_config_file("=on").is_err());
+    }
+
+    #[test]
+    fn memory_budget_accepts_units_and_off() {
+        const MB: u64 = 1024 * 1024;
+        let budget = |raw: &str| config_from(&[("ALFRED_CHROME_BOOKMARKS_MEMORY_BUDGET", raw)]);
+        assert_eq!(
+            config_from(&[]).memory_budget,
+            Some(DEFAULT_MEMORY_BUDGET_MB * MB)
+        );
+        assert_eq!(budget("128").memory_budget, Some(128 * MB));
+        assert_eq!(budget(" 16MB ").memory_budget, Some(16 * MB));
+        assert_eq!(budget("1g").memory_budget, Some(1024 * MB));
+        assert_eq!(budget("off").memory_budget, None);
+        assert_eq!(
+            budget("lots").memory_budget,
+            Some(DEFAULT_MEMORY_BUDGET_MB * MB)
+        );
+    }
+
+    #[test]
+    fn result_cache_size_defaults_and_can_be_disabled() {
+        let entries =
+            |raw: &str| config_from(&[("ALFRED_CHROME_BOOKMARKS_RESULT_CACHE", raw)]).result_cache;
+        let default = crate::result_cache::DEFAULT_RESULT_CACHE_ENTRIES;
+        assert_eq!(config_from(&[]).result_cache, default);
+        assert_eq!(entries(" 16 "), 16);
+        assert_eq!(entries("0"), 0);
+        assert_eq!(entries("OFF"), 0);
+        assert_eq!(entries("many"), default);
+    }
+}
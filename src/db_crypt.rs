@@ -0,0 +1,235 @@
+use rusqlite::{ffi, params, Connection, ErrorCode, OpenFlags, Result};
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// 元数据库密钥：直接写密钥，或 `keychain:<服务名>` 从 macOS 钥匙串读取
+pub const DB_KEY_VAR: &str = "ALFRED_CHROME_BOOKMARKS_DB_KEY";
+
+/// 设置密钥后加密的库：标签与打开记录。书签索引可随时从浏览器重建，不加密
+pub const ENCRYPTED_DATABASES: &[&str] = &["tags.db", "usage.db"];
+
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+static KEY: OnceLock<Option<String>> = OnceLock::new();
+
+/// 本进程打开元数据库所用的密钥；启动时设置一次
+pub fn set_key(key: Option<String>) {
+    let _ = KEY.set(key);
+}
+
+pub fn key() -> Option<&'static str> {
+    KEY.get().and_then(|key| key.as_deref())
+}
+
+/// 解析 `ALFRED_CHROME_BOOKMARKS_DB_KEY`；钥匙串条目用 `security add-generic-password -s <服务名> -a $USER -w` 创建
+pub fn resolve_key(spec: &str) -> Result<String, String> {
+    let Some(service) = spec.strip_prefix("keychain:") else {
+        return Ok(spec.to_string());
+    };
+    let output = Command::new("security")
+        .args(["find-generic-password", "-w", "-s", service.trim()])
+        .output()
+        .map_err(|e| format!("无法读取钥匙串: {}", e))?;
+    let key = String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(['\r', '\n'])
+        .to_string();
+    if !output.status.success() || key.is_empty() {
+        return Err(format!("钥匙串中没有找到 {}", service.trim()));
+    }
+    Ok(key)
+}
+
+/// 当前链接的 SQLCipher 版本；普通 SQLite 构建（未启用 `sqlcipher` feature）返回 None
+pub fn cipher_version() -> Option<String> {
+    let conn = Connection::open_in_memory().ok()?;
+    conn.query_row("PRAGMA cipher_version", [], |row| row.get(0))
+        .ok()
+}
+
+pub fn open(path: &Path, key: Option<&str>) -> Result<Connection> {
+    open_with_flags(path, OpenFlags::default(), key)
+}
+
+/// 打开可能加密的库。有密钥时，尚未加密的旧库先原地转为加密库（只读打开时按明文读取）；
+/// 没有密钥却遇到加密库时给出明确的错误，而不是 SQLite 的 “file is not a database”
+pub fn open_with_flags(path: &Path, flags: OpenFlags, key: Option<&str>) -> Result<Connection> {
+    let Some(key) = key else {
+        if is_encrypted_file(path) {
+            return Err(failure(
+                ffi::SQLITE_NOTADB,
+                format!("{} 已加密，需设置 {}", path.display(), DB_KEY_VAR),
+            ));
+        }
+        return Connection::open_with_flags(path, flags);
+    };
+    if is_plaintext_file(path) {
+        if flags.contains(OpenFlags::SQLITE_OPEN_READ_ONLY) {
+            return Connection::open_with_flags(path, flags);
+        }
+        encrypt_in_place(path, key)?;
+    }
+    let conn = Connection::open_with_flags(path, flags)?;
+    unlock(&conn, key)?;
+    // 密钥不对要到第一次读取才会报错
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+        .map_err(|err| match err.sqlite_error_code() {
+            Some(ErrorCode::NotADatabase) => failure(
+                ffi::SQLITE_NOTADB,
+                format!("{} 无法解密，请检查 {}", path.display(), DB_KEY_VAR),
+            ),
+            _ => err,
+        })?;
+    Ok(conn)
+}
+
+/// 把库挂载到 `conn` 上；有密钥时以 `KEY` 解密
+pub fn attach(conn: &Connection, path: &Path, schema: &str, key: Option<&str>) -> Result<()> {
+    match key {
+        Some(key) => conn.execute(
+            &format!("ATTACH DATABASE ?1 AS {} KEY ?2", schema),
+            params![path.to_string_lossy(), key],
+        )?,
+        None => conn.execute(
+            &format!("ATTACH DATABASE ?1 AS {}", schema),
+            params![path.to_string_lossy()],
+        )?,
+    };
+    Ok(())
+}
+
+fn unlock(conn: &Connection, key: &str) -> Result<()> {
+    ensure_cipher(conn)?;
+    conn.pragma_update(None, "key", key)
+}
+
+fn ensure_cipher(conn: &Connection) -> Result<()> {
+    if conn
+        .query_row("PRAGMA cipher_version", [], |row| row.get::<_, String>(0))
+        .is_err()
+    {
+        return Err(failure(
+            ffi::SQLITE_MISUSE,
+            format!(
+                "设置了 {}，但当前构建不支持 SQLCipher（需 --features sqlcipher）",
+                DB_KEY_VAR
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// 用 `sqlcipher_export` 导出加密副本后替换原文件，明文不留在磁盘上
+fn encrypt_in_place(path: &Path, key: &str) -> Result<()> {
+    let tmp = path.with_extension("encrypting");
+    let _ = std::fs::remove_file(&tmp);
+    {
+        let conn = Connection::open(path)?;
+        ensure_cipher(&conn)?;
+        conn.execute(
+            "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+            params![tmp.to_string_lossy(), key],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+        conn.execute("DETACH DATABASE encrypted", [])?;
+    }
+    std::fs::rename(&tmp, path).map_err(|e| {
+        failure(
+            ffi::SQLITE_IOERR,
+            format!("无法替换 {}: {}", path.display(), e),
+        )
+    })?;
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let _ = std::fs::remove_file(sidecar);
+    }
+    Ok(())
+}
+
+fn file_header(path: &Path) -> Option<[u8; 16]> {
+    use std::io::Read;
+    let mut header = [0u8; 16];
+    std::fs::File::open(path)
+        .ok()?
+        .read_exact(&mut header)
+        .ok()?;
+    Some(header)
+}
+
+fn is_plaintext_file(path: &Path) -> bool {
+    file_header(path).is_some_and(|header| header == SQLITE_HEADER)
+}
+
+fn is_encrypted_file(path: &Path) -> bool {
+    file_header(path).is_some_and(|header| header != SQLITE_HEADER)
+}
+
+fn failure(code: i32, message: String) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(ffi::Error::new(code), Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn unencrypted_builds_refuse_a_key_and_reject_foreign_files() {
+        let dir = tempdir().expect("tempdir");
+        let plain = dir.path().join("usage.db");
+        open(&plain, None)
+            .and_then(|conn| conn.execute_batch("CREATE TABLE t (x INTEGER)"))
+            .expect("plain db");
+        assert!(is_plaintext_file(&plain));
+
+        let garbled = dir.path().join("tags.db");
+        std::fs::write(&garbled, [7u8; 64]).expect("write");
+        let err = open(&garbled, None).expect_err("encrypted without key");
+        assert!(err.to_string().contains(DB_KEY_VAR), "{}", err);
+
+        if cipher_version().is_none() {
+            let err = open(&plain, Some("secret")).expect_err("no sqlcipher");
+            assert!(err.to_string().contains("SQLCipher"), "{}", err);
+            // 明文库保持原样，没有被改写
+            assert!(is_plaintext_file(&plain));
+        }
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn plaintext_database_is_encrypted_in_place_and_attachable() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("tags.db");
+        open(&path, None)
+            .and_then(|conn| {
+                conn.execute_batch(
+                    "PRAGMA journal_mode = WAL;
+                     CREATE TABLE tags (name TEXT);
+                     INSERT INTO tags VALUES ('secret-project');",
+                )
+            })
+            .expect("plain db");
+
+        let conn = open(&path, Some("k1")).expect("migrate");
+        let name: String = conn
+            .query_row("SELECT name FROM tags", [], |row| row.get(0))
+            .expect("row");
+        assert_eq!(name, "secret-project");
+        drop(conn);
+        assert!(is_encrypted_file(&path));
+        let raw = std::fs::read(&path).expect("read");
+        assert!(!raw.windows(14).any(|window| window == b"secret-project"));
+
+        assert!(open(&path, None).is_err());
+        let err = open(&path, Some("wrong")).expect_err("wrong key");
+        assert!(err.to_string().contains("无法解密"), "{}", err);
+
+        let main = Connection::open_in_memory().expect("main");
+        attach(&main, &path, "tagdb", Some("k1")).expect("attach");
+        let count: i64 = main
+            .query_row("SELECT count(*) FROM tagdb.tags", [], |row| row.get(0))
+            .expect("count");
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::bookmark::ChromeBookmark;
+use crate::url_normalize::canonical_url;
+use std::collections::{HashMap, HashSet};
+
+/// 共享词出现次数超过该值时不再作为候选配对依据，避免常见词导致 O(n²)
+const MAX_TOKEN_FREQUENCY: usize = 64;
+
+/// 按规范 URL（协议与主机小写，去掉默认端口、片段、跟踪参数与末尾斜杠）分组，
+/// 返回每组书签下标（仅包含 2 条以上的组）
+pub fn group_by_normalized_url(bookmarks: &[ChromeBookmark]) -> Vec<Vec<usize>> {
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, bookmark) in bookmarks.iter().enumerate() {
+        groups
+            .entry(canonical_url(&bookmark.url))
+            .or_default()
+            .push(idx);
+    }
+
+    let mut result: Vec<Vec<usize>> = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    result.sort_by_key(|group| group[0]);
+    result
+}
+
+/// 按规范 URL 分组，只保留出现在多个来源（浏览器 / profile）中的组，便于换浏览器后整理
+pub fn group_across_sources(bookmarks: &[ChromeBookmark]) -> Vec<Vec<usize>> {
+    group_by_normalized_url(bookmarks)
+        .into_iter()
+        .filter(|group| {
+            let sources: HashSet<(&str, &str)> = group
+                .iter()
+                .map(|&idx| {
+                    (
+                        bookmarks[idx].browser.as_str(),
+                        bookmarks[idx].profile.as_str(),
+                    )
+                })
+                .collect();
+            sources.len() > 1
+        })
+        .collect()
+}
+
+/// 聚类标题近似但 URL 不同的书签（重复收藏的同一篇文章）
+pub fn cluster_similar_titles(bookmarks: &[ChromeBookmark], threshold: f64) -> Vec<Vec<usize>> {
+    let normalized: Vec<String> = bookmarks
+        .iter()
+        .map(|bookmark| normalize_title(&bookmark.name))
+        .collect();
+    let tokens: Vec<Vec<&str>> = normalized
+        .iter()
+        .map(|title| title.split(' ').filter(|t| t.chars().count() > 1).collect())
+        .collect();
+
+    let mut postings: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (idx, words) in tokens.iter().enumerate() {
+        let unique: HashSet<&str> = words.iter().copied().collect();
+        for word in unique {
+            postings.entry(word).or_default().push(idx);
+        }
+    }
+
+    let mut parents: Vec<usize> = (0..bookmarks.len()).collect();
+    let mut compared: HashSet<(usize, usize)> = HashSet::new();
+
+    for members in postings.values() {
+        if members.len() < 2 || members.len() > MAX_TOKEN_FREQUENCY {
+            continue;
+        }
+        for (pos, &a) in members.iter().enumerate() {
+            for &b in &members[pos + 1..] {
+                if !compared.insert((a, b)) {
+                    continue;
+                }
+                if bookmarks[a].url == bookmarks[b].url {
+                    continue;
+                }
+                if title_similarity(&normalized[a], &tokens[a], &normalized[b], &tokens[b])
+                    >= threshold
+                {
+                    union(&mut parents, a, b);
+                }
+            }
+        }
+    }
+
+    let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+    for idx in 0..bookmarks.len() {
+        let root = find(&mut parents, idx);
+        clusters.entry(root).or_default().push(idx);
+    }
+
+    let mut result: Vec<Vec<usize>> = clusters
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .collect();
+    result.sort_by_key(|members| members[0]);
+    result
+}
+
+/// 小写、去标点、合并空白，用于标题比较
+pub fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .chars()
+        .map(|ch| if ch.is_alphanumeric() { ch } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 取归一化编辑距离相似度与词重叠度（Jaccard）中的较大者
+fn title_similarity(a: &str, a_tokens: &[&str], b: &str, b_tokens: &[&str]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let a_set: HashSet<&str> = a_tokens.iter().copied().collect();
+    let b_set: HashSet<&str> = b_tokens.iter().copied().collect();
+    let union_len = a_set.union(&b_set).count();
+    let jaccard = if union_len == 0 {
+        0.0
+    } else {
+        a_set.intersection(&b_set).count() as f64 / union_len as f64
+    };
+
+    jaccard.max(levenshtein_similarity(a, b))
+}
+
+pub fn levenshtein_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0usize; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            current[j + 1] = (previous[j + 1] + 1)
+                .min(current[j] + 1)
+                .min(previous[j] + cost);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    1.0 - previous[b.len()] as f64 / max_len as f64
+}
+
+fn find(parents: &mut [usize], mut idx: usize) -> usize {
+    while parents[idx] != idx {
+        parents[idx] = parents[parents[idx]];
+        idx = parents[idx];
+    }
+    idx
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let root_a = find(parents, a);
+    let root_b = find(parents, b);
+    if root_a != root_b {
+        parents[root_a.max(root_b)] = root_a.min(root_b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+
+    fn bookmark(id: &str, name: &str, url: &str) -> ChromeBookmark {
+        test_bookmark(id, name, url, None)
+    }
+
+    #[test]
+    fn across_sources_groups_only_urls_in_multiple_sources() {
+        let mut bookmarks = vec![
+            bookmark("1", "Rust", "https://rust-lang.org/"),
+            bookmark("2", "Rust", "https://Rust-Lang.org#install"),
+            bookmark("3", "Tokio", "https://tokio.rs"),
+            bookmark("4", "Tokio", "https://tokio.rs"),
+        ];
+        for (bookmark, (browser, profile)) in bookmarks.iter_mut().zip([
+            ("chrome", "Default"),
+            ("arc", "Work"),
+            ("chrome", "Default"),
+            ("chrome", "Default"),
+        ]) {
+            bookmark.browser = browser.to_string();
+            bookmark.profile = profile.to_string();
+        }
+
+        assert_eq!(group_across_sources(&bookmarks), vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn similar_titles_with_different_urls_are_clustered() {
+        let bookmarks = vec![
+            bookmark("1", "Understanding Rust Lifetimes", "https://a.example/1"),
+            bookmark("2", "Understanding Rust lifetimes!", "https://b.example/2"),
+            bookmark("3", "Python Packaging Guide", "https://c.example/3"),
+        ];
+
+        let clusters = cluster_similar_titles(&bookmarks, 0.85);
+        assert_eq!(clusters, vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn identical_urls_are_not_reported_as_similar() {
+        let bookmarks = vec![
+            bookmark("1", "Rust Book", "https://doc.rust-lang.org/book"),
+            bookmark("2", "Rust Book", "https://doc.rust-lang.org/book"),
+        ];
+
+        assert!(cluster_similar_titles(&bookmarks, 0.85).is_empty());
+        assert_eq!(group_by_normalized_url(&bookmarks), vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn normalized_url_groups_ignore_case_slashes_and_tracking_params() {
+        let bookmarks = vec![
+            bookmark("1", "Axum", "https://GitHub.com/tokio-rs/axum/"),
+            bookmark("2", "Axum", "https://github.com/tokio-rs/axum?utm_source=x"),
+            bookmark("3", "Tokio", "https://github.com/tokio-rs/tokio"),
+            bookmark("4", "Axum docs", "https://github.com/tokio-rs/axum#readme"),
+        ];
+
+        assert_eq!(group_by_normalized_url(&bookmarks), vec![vec![0, 1, 3]]);
+    }
+
+    #[test]
+    fn dissimilar_titles_sharing_a_word_stay_apart() {
+        let bookmarks = vec![
+            bookmark("1", "Rust async book", "https://a.example"),
+            bookmark("2", "Rust embedded discovery", "https://b.example"),
+        ];
+
+        assert!(cluster_similar_titles(&bookmarks, 0.85).is_empty());
+    }
+
+    #[test]
+    fn levenshtein_similarity_handles_edge_cases() {
+        assert_eq!(levenshtein_similarity("", ""), 1.0);
+        assert_eq!(levenshtein_similarity("abc", "abc"), 1.0);
+        assert!(levenshtein_similarity("设计模式", "设计模式详解") > 0.6);
+        assert_eq!(normalize_title("  Hello,  World! "), "hello world");
+    }
+}
@@ -0,0 +1,117 @@
+use crate::bookmark::ChromeBookmark;
+use std::io::{self, Write};
+
+/// 以 Netscape Bookmark HTML 格式导出书签，Chrome/Firefox 均可直接导入
+/// 目录层级按 folder_path 还原为嵌套的 `<DL>` 结构
+pub fn write_netscape_html<W: Write>(
+    writer: &mut W,
+    bookmarks: &[ChromeBookmark],
+) -> io::Result<()> {
+    writeln!(writer, "<!DOCTYPE NETSCAPE-Bookmark-file-1>")?;
+    writeln!(
+        writer,
+        "<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">"
+    )?;
+    writeln!(writer, "<TITLE>Bookmarks</TITLE>")?;
+    writeln!(writer, "<H1>Bookmarks</H1>")?;
+    writeln!(writer, "<DL><p>")?;
+
+    let mut sorted: Vec<&ChromeBookmark> = bookmarks.iter().collect();
+    sorted.sort_by(|a, b| folder_segments(a).cmp(&folder_segments(b)));
+
+    let mut open: Vec<&str> = Vec::new();
+    for bookmark in sorted {
+        let segments = folder_segments(bookmark);
+        let common = open
+            .iter()
+            .zip(segments.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        while open.len() > common {
+            open.pop();
+            writeln!(writer, "{}</DL><p>", indent(open.len() + 1))?;
+        }
+        for segment in &segments[common..] {
+            writeln!(
+                writer,
+                "{}<DT><H3>{}</H3>",
+                indent(open.len() + 1),
+                escape_html(segment)
+            )?;
+            writeln!(writer, "{}<DL><p>", indent(open.len() + 1))?;
+            open.push(segment);
+        }
+
+        writeln!(
+            writer,
+            "{}<DT><A HREF=\"{}\">{}</A>",
+            indent(open.len() + 1),
+            escape_html(&bookmark.url),
+            escape_html(&bookmark.name)
+        )?;
+    }
+
+    while !open.is_empty() {
+        open.pop();
+        writeln!(writer, "{}</DL><p>", indent(open.len() + 1))?;
+    }
+
+    writeln!(writer, "</DL><p>")?;
+    Ok(())
+}
+
+fn folder_segments(bookmark: &ChromeBookmark) -> Vec<&str> {
+    bookmark
+        .folder_path
+        .as_deref()
+        .unwrap_or("")
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+fn indent(depth: usize) -> String {
+    "    ".repeat(depth)
+}
+
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+
+    fn bookmark(name: &str, url: &str, folder: &str) -> ChromeBookmark {
+        test_bookmark(name, name, url, Some(folder))
+    }
+
+    #[test]
+    fn netscape_html_nests_folders_and_escapes() {
+        let bookmarks = vec![
+            bookmark("A & B", "https://a.example/?x=1&y=2", "书签栏/Work"),
+            bookmark("Docs", "https://docs.example", "书签栏/Work/Docs"),
+            bookmark("Play", "https://play.example", "其他书签"),
+        ];
+
+        let mut out = Vec::new();
+        write_netscape_html(&mut out, &bookmarks).expect("write");
+        let html = String::from_utf8(out).expect("utf8");
+
+        assert!(html.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>"));
+        assert!(html.contains("<H3>Work</H3>"));
+        assert!(html.contains("A &amp; B"));
+        assert!(html.contains("x=1&amp;y=2"));
+        assert_eq!(
+            html.matches("<DL><p>").count(),
+            html.matches("</DL><p>").count()
+        );
+        assert_eq!(html.matches("<H3>书签栏</H3>").count(), 1);
+    }
+}
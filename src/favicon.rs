@@ -0,0 +1,288 @@
+use crate::bookmark::ChromeBookmark;
+use crate::url_normalize::{canonical_url, canonical_url_hash, url_host};
+use rusqlite::{Connection, OpenFlags};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// 站点图标缓存：下载的站点图标为 `<缓存目录>/favicons/<主机名>.ico`，
+/// 从浏览器图标库导出的页面图标为 `<缓存目录>/favicons/page-<规范 URL 摘要>.png`
+pub struct FaviconCache {
+    dir: PathBuf,
+}
+
+impl FaviconCache {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            dir: cache_dir.join("favicons"),
+        }
+    }
+
+    /// 书签已缓存的图标：页面自己的图标优先，其次是所在站点的图标
+    pub fn icon_for(&self, url: &str) -> Option<PathBuf> {
+        let page = self.page_icon_path(url);
+        if page.is_file() {
+            return Some(page);
+        }
+        let path = self.icon_path(&url_host(url)?);
+        path.is_file().then_some(path)
+    }
+
+    /// 还没有缓存图标的站点（去重、排序）；已有页面图标的书签不计入
+    pub fn missing_domains(&self, bookmarks: &[ChromeBookmark]) -> Vec<String> {
+        bookmarks
+            .iter()
+            .filter(|bookmark| is_web_url(&bookmark.url))
+            .filter(|bookmark| !self.page_icon_path(&bookmark.url).is_file())
+            .filter_map(|bookmark| url_host(&bookmark.url))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|domain| !self.icon_path(domain).is_file())
+            .collect()
+    }
+
+    /// 通过系统 curl 下载 `https://<domain>/favicon.ico`，只在拿到非空图片时写入缓存
+    pub fn fetch(&self, domain: &str, timeout: Duration) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(&self.dir).map_err(|err| err.to_string())?;
+        let path = self.icon_path(domain);
+        let tmp = path.with_extension("ico.part");
+
+        let output = Command::new("curl")
+            .arg("-sS")
+            .arg("-L")
+            .arg("--max-time")
+            .arg(timeout.as_secs().max(1).to_string())
+            .arg("-A")
+            .arg("Mozilla/5.0 (Macintosh) alfred-chrome-bookmarks")
+            .arg("-o")
+            .arg(&tmp)
+            .arg("-w")
+            .arg("%{http_code}\n%{content_type}")
+            .arg(format!("https://{}/favicon.ico", domain))
+            .output()
+            .map_err(|err| err.to_string())?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+        let status = lines.next().unwrap_or("").trim().to_string();
+        let content_type = lines.next().unwrap_or("").trim().to_ascii_lowercase();
+        let size = std::fs::metadata(&tmp).map(|meta| meta.len()).unwrap_or(0);
+
+        let result = if status != "200" {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            Err(if stderr.is_empty() {
+                format!("HTTP {}", status)
+            } else {
+                stderr
+            })
+        } else if size == 0 || !is_image_content_type(&content_type) {
+            Err(format!("不是图片: {}", content_type))
+        } else {
+            std::fs::rename(&tmp, &path)
+                .map(|_| path.clone())
+                .map_err(|err| err.to_string())
+        };
+        let _ = std::fs::remove_file(&tmp);
+        result
+    }
+
+    /// 从浏览器的 Favicons 库导出还没有图标的书签页面图标（只取 PNG，同一页面取最大的一张），返回导出数
+    ///
+    /// 浏览器运行时会锁住该库，先复制一份到缓存目录再读；不访问网络
+    pub fn import_browser_favicons(
+        &self,
+        favicons_db: &Path,
+        bookmarks: &[ChromeBookmark],
+    ) -> Result<usize, String> {
+        let mut wanted: HashMap<String, PathBuf> = bookmarks
+            .iter()
+            .filter(|bookmark| is_web_url(&bookmark.url))
+            .map(|bookmark| {
+                (
+                    canonical_url(&bookmark.url),
+                    self.page_icon_path(&bookmark.url),
+                )
+            })
+            .filter(|(_, path)| !path.is_file())
+            .collect();
+        if wanted.is_empty() {
+            return Ok(0);
+        }
+
+        std::fs::create_dir_all(&self.dir).map_err(|err| err.to_string())?;
+        let copy = self.dir.join("Favicons.part");
+        std::fs::copy(favicons_db, &copy).map_err(|err| err.to_string())?;
+        let result = export_page_icons(&copy, &mut wanted).map_err(|err| err.to_string());
+        let _ = std::fs::remove_file(&copy);
+        result
+    }
+
+    fn page_icon_path(&self, url: &str) -> PathBuf {
+        self.dir
+            .join(format!("page-{}.png", canonical_url_hash(url)))
+    }
+
+    fn icon_path(&self, domain: &str) -> PathBuf {
+        let safe: String = domain
+            .chars()
+            .map(|ch| {
+                if ch.is_ascii_alphanumeric() || ch == '.' || ch == '-' {
+                    ch
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.ico", safe))
+    }
+}
+
+/// Chromium 系 profile 目录下与 `Bookmarks` 同级的 `Favicons` 图标库；其他浏览器或文件不存在时为 None
+pub fn browser_favicons_db(bookmarks_file: &Path) -> Option<PathBuf> {
+    if bookmarks_file.file_name()? != "Bookmarks" {
+        return None;
+    }
+    let path = bookmarks_file.with_file_name("Favicons");
+    path.is_file().then_some(path)
+}
+
+/// 按图片宽度从大到小遍历页面与图标的对应关系，命中 `wanted` 的页面写出第一张 PNG 并从中移除
+fn export_page_icons(
+    db: &Path,
+    wanted: &mut HashMap<String, PathBuf>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let conn = Connection::open_with_flags(db, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare(
+        "SELECT m.page_url, b.image_data
+         FROM icon_mapping m
+         JOIN favicon_bitmaps b ON b.icon_id = m.icon_id
+         ORDER BY b.width DESC",
+    )?;
+    let mut rows = stmt.query([])?;
+    let mut exported = 0;
+    while let Some(row) = rows.next()? {
+        let page_url: String = row.get(0)?;
+        let key = canonical_url(&page_url);
+        if !wanted.contains_key(&key) {
+            continue;
+        }
+        let data: Vec<u8> = row.get(1)?;
+        if !data.starts_with(PNG_SIGNATURE) {
+            continue;
+        }
+        if let Some(path) = wanted.remove(&key) {
+            std::fs::write(path, data)?;
+            exported += 1;
+        }
+        if wanted.is_empty() {
+            break;
+        }
+    }
+    Ok(exported)
+}
+
+fn is_web_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// 部分站点不返回 Content-Type，此时按内容落地后由 Alfred 自行识别
+fn is_image_content_type(content_type: &str) -> bool {
+    content_type.is_empty()
+        || content_type.starts_with("image/")
+        || content_type.starts_with("application/octet-stream")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+
+    fn bookmark(url: &str) -> ChromeBookmark {
+        ChromeBookmark {
+            browser: "chrome".to_string(),
+            profile: "Default".to_string(),
+            ..test_bookmark(url, url, url, None)
+        }
+    }
+
+    #[test]
+    fn missing_domains_skip_cached_icons_and_non_web_urls() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cache = FaviconCache::new(dir.path());
+        std::fs::create_dir_all(dir.path().join("favicons")).expect("mkdir");
+        std::fs::write(dir.path().join("favicons/rust-lang.org.ico"), b"icon").expect("write");
+
+        let bookmarks = vec![
+            bookmark("https://rust-lang.org/learn"),
+            bookmark("https://Example.com/a"),
+            bookmark("https://example.com/b"),
+            bookmark("javascript:alert(1)"),
+        ];
+        assert_eq!(cache.missing_domains(&bookmarks), vec!["example.com"]);
+        assert_eq!(
+            cache.icon_for("https://rust-lang.org/"),
+            Some(dir.path().join("favicons/rust-lang.org.ico"))
+        );
+        assert_eq!(cache.icon_for("https://example.com/"), None);
+    }
+
+    #[test]
+    fn browser_favicons_are_exported_per_page() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let profile = dir.path().join("Default");
+        std::fs::create_dir_all(&profile).expect("mkdir");
+        std::fs::write(profile.join("Bookmarks"), "{}").expect("write");
+        assert_eq!(browser_favicons_db(&profile.join("Bookmarks")), None);
+
+        let png = |tag: u8| [PNG_SIGNATURE, &[tag]].concat();
+        let conn = Connection::open(profile.join("Favicons")).expect("open");
+        conn.execute_batch(
+            "CREATE TABLE icon_mapping (id INTEGER PRIMARY KEY, page_url TEXT, icon_id INTEGER);
+             CREATE TABLE favicon_bitmaps (
+                id INTEGER PRIMARY KEY, icon_id INTEGER, width INTEGER, image_data BLOB
+             );
+             INSERT INTO icon_mapping (page_url, icon_id) VALUES
+                ('https://docs.rs/?utm_source=x', 1), ('https://ico.example/', 2);",
+        )
+        .expect("schema");
+        for (icon_id, width, data) in [
+            (1, 16, png(16)),
+            (1, 32, png(32)),
+            (2, 16, b"GIF89a".to_vec()),
+        ] {
+            conn.execute(
+                "INSERT INTO favicon_bitmaps (icon_id, width, image_data) VALUES (?1, ?2, ?3)",
+                rusqlite::params![icon_id, width, data],
+            )
+            .expect("insert");
+        }
+        drop(conn);
+        let db = browser_favicons_db(&profile.join("Bookmarks")).expect("favicons db");
+
+        let cache = FaviconCache::new(dir.path());
+        let bookmarks = vec![
+            bookmark("https://docs.rs"),
+            bookmark("https://ico.example"),
+            bookmark("https://missing.example"),
+        ];
+        assert_eq!(cache.import_browser_favicons(&db, &bookmarks), Ok(1));
+        let icon = cache.icon_for("https://docs.rs/").expect("page icon");
+        assert_eq!(std::fs::read(icon).expect("read"), png(32));
+        assert_eq!(cache.icon_for("https://ico.example"), None);
+        assert_eq!(
+            cache.missing_domains(&bookmarks),
+            vec!["ico.example", "missing.example"]
+        );
+        assert_eq!(cache.import_browser_favicons(&db, &bookmarks), Ok(0));
+    }
+
+    #[test]
+    fn content_type_check_accepts_images_only() {
+        assert!(is_image_content_type("image/x-icon"));
+        assert!(is_image_content_type(""));
+        assert!(!is_image_content_type("text/html; charset=utf-8"));
+    }
+}
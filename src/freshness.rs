@@ -0,0 +1,164 @@
+use crate::bookmark::{compute_bookmarks_fingerprint, BookmarkSource, ChromeBookmark};
+use crate::index_db::SourceState;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreshnessStatus {
+    /// 已索引且书签文件未变化
+    Fresh,
+    /// 书签文件已变化或尚未索引，下次搜索会自动刷新
+    Pending,
+    /// 已索引但来源已不存在（如 profile 被删除）
+    Removed,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceFreshness {
+    pub browser: String,
+    pub profile: String,
+    pub path: String,
+    pub indexed: Option<SourceState>,
+    pub status: FreshnessStatus,
+}
+
+/// 对比当前发现的来源与索引中记录的来源状态
+pub fn source_freshness(
+    sources: &[BookmarkSource],
+    states: &[SourceState],
+) -> Vec<SourceFreshness> {
+    let mut result: Vec<SourceFreshness> = sources
+        .iter()
+        .map(|source| {
+            let indexed = states
+                .iter()
+                .find(|state| state.browser == source.browser && state.profile == source.profile)
+                .cloned();
+            let current = compute_bookmarks_fingerprint(&source.path).ok();
+            let status = match (&indexed, current) {
+                (Some(state), Some(fingerprint)) if state.fingerprint == fingerprint => {
+                    FreshnessStatus::Fresh
+                }
+                _ => FreshnessStatus::Pending,
+            };
+
+            SourceFreshness {
+                browser: source.browser.clone(),
+                profile: source.profile.clone(),
+                path: source.path.to_string_lossy().to_string(),
+                indexed,
+                status,
+            }
+        })
+        .collect();
+
+    for state in states {
+        let still_present = sources
+            .iter()
+            .any(|source| source.browser == state.browser && source.profile == state.profile);
+        if !still_present {
+            result.push(SourceFreshness {
+                browser: state.browser.clone(),
+                profile: state.profile.clone(),
+                path: state.path.clone(),
+                indexed: Some(state.clone()),
+                status: FreshnessStatus::Removed,
+            });
+        }
+    }
+
+    result
+}
+
+/// 刷新索引后每个来源的状态
+pub fn source_states_for(
+    sources: &[BookmarkSource],
+    bookmarks: &[ChromeBookmark],
+    refreshed_at: i64,
+) -> Vec<SourceState> {
+    sources
+        .iter()
+        .map(|source| SourceState {
+            browser: source.browser.clone(),
+            profile: source.profile.clone(),
+            path: source.path.to_string_lossy().to_string(),
+            fingerprint: compute_bookmarks_fingerprint(&source.path).unwrap_or_default(),
+            bookmark_count: bookmarks
+                .iter()
+                .filter(|bookmark| {
+                    bookmark.browser == source.browser && bookmark.profile == source.profile
+                })
+                .count(),
+            refreshed_at,
+        })
+        .collect()
+}
+
+/// 指纹较长（含路径），展示时只取摘要
+pub fn short_fingerprint(fingerprint: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    fingerprint.hash(&mut hasher);
+    format!("{:08x}", hasher.finish() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn source(browser: &str, profile: &str, path: std::path::PathBuf) -> BookmarkSource {
+        BookmarkSource {
+            browser: browser.to_string(),
+            profile: profile.to_string(),
+            path,
+        }
+    }
+
+    #[test]
+    fn freshness_detects_fresh_pending_and_removed_sources() {
+        let dir = tempdir().expect("tempdir");
+        let work = dir.path().join("work");
+        let home = dir.path().join("home");
+        fs::write(&work, "{}").expect("write work");
+        fs::write(&home, "{}").expect("write home");
+
+        let sources = vec![
+            source("chrome", "Work", work.clone()),
+            source("chrome", "Home", home),
+        ];
+        let mut states = source_states_for(&sources[..1], &[], 100);
+        states.push(SourceState {
+            browser: "brave".into(),
+            profile: "Default".into(),
+            path: "/gone/Bookmarks".into(),
+            fingerprint: "old".into(),
+            bookmark_count: 3,
+            refreshed_at: 50,
+        });
+
+        let freshness = source_freshness(&sources, &states);
+        let statuses: Vec<(&str, FreshnessStatus)> = freshness
+            .iter()
+            .map(|item| (item.profile.as_str(), item.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("Work", FreshnessStatus::Fresh),
+                ("Home", FreshnessStatus::Pending),
+                ("Default", FreshnessStatus::Removed),
+            ]
+        );
+
+        fs::write(&work, "{\"changed\":true}").expect("rewrite work");
+        let freshness = source_freshness(&sources, &states);
+        assert_eq!(freshness[0].status, FreshnessStatus::Pending);
+    }
+
+    #[test]
+    fn short_fingerprint_is_stable_and_compact() {
+        assert_eq!(short_fingerprint("a-b-c"), short_fingerprint("a-b-c"));
+        assert_eq!(short_fingerprint("a-b-c").len(), 8);
+    }
+}
@@ -0,0 +1,315 @@
+use rusqlite::{params, Connection, Result, ToSql};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 最多保留的历史条数，超出后删除最久未用的
+const MAX_ENTRIES: i64 = 200;
+/// Alfred 每敲一个字符就搜索一次：该时间内被更长查询取代的前缀视为输入过程，不保留
+const TYPING_WINDOW_SECS: u64 = 90;
+
+/// 搜索历史（history.db），仅在开启 `ALFRED_CHROME_BOOKMARKS_HISTORY` 时记录
+pub struct QueryHistory {
+    conn: Connection,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistoryEntry {
+    pub query: String,
+    pub uses: u32,
+    pub last_used: u64,
+    /// 最近一次搜索的结果数；旧版本记录的查询为 None
+    pub results: Option<usize>,
+}
+
+impl QueryHistory {
+    pub fn new(db_path: PathBuf) -> Result<Self> {
+        let conn = Connection::open(db_path)?;
+        let _ = conn.busy_timeout(Duration::from_millis(500));
+
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;",
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS queries (
+                query TEXT PRIMARY KEY COLLATE NOCASE,
+                uses INTEGER NOT NULL DEFAULT 0,
+                last_used INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_queries_last_used ON queries(last_used)",
+            [],
+        )?;
+        migrate_results_column(&conn)?;
+
+        Ok(Self { conn })
+    }
+
+    /// 记录一次搜索；刚输入过的前缀会被合并进完整查询，退格产生的更短前缀不记录
+    pub fn record(&self, query: &str, now: u64) -> Result<()> {
+        let query = normalize_query(query);
+        if query.is_empty() || query.starts_with('!') {
+            return Ok(());
+        }
+        let window_start = now.saturating_sub(TYPING_WINDOW_SECS) as i64;
+
+        let tx = self.conn.unchecked_transaction()?;
+        let is_backspace: bool = tx.query_row(
+            "SELECT EXISTS(
+                SELECT 1 FROM queries
+                WHERE last_used >= ?2 AND length(query) > length(?1)
+                  AND lower(substr(query, 1, length(?1))) = lower(?1))",
+            params![query, window_start],
+            |row| row.get(0),
+        )?;
+        if is_backspace {
+            return tx.commit();
+        }
+
+        tx.execute(
+            "DELETE FROM queries
+             WHERE last_used >= ?2 AND uses = 1 AND length(query) < length(?1)
+               AND lower(substr(?1, 1, length(query))) = lower(query)",
+            params![query, window_start],
+        )?;
+        tx.execute(
+            "INSERT INTO queries (query, uses, last_used) VALUES (?1, 1, ?2)
+             ON CONFLICT(query) DO UPDATE SET
+                uses = CASE WHEN queries.last_used >= ?3 THEN queries.uses ELSE queries.uses + 1 END,
+                last_used = excluded.last_used",
+            params![query, now as i64, window_start],
+        )?;
+        tx.execute(
+            "DELETE FROM queries WHERE query NOT IN (
+                SELECT query FROM queries ORDER BY last_used DESC LIMIT ?1)",
+            params![MAX_ENTRIES],
+        )?;
+        tx.commit()
+    }
+
+    /// 搜索完成后记下结果数；查询已作为输入前缀被合并时不做任何事
+    pub fn record_results(&self, query: &str, results: usize) -> Result<()> {
+        self.conn.execute(
+            "UPDATE queries SET results = ?2 WHERE query = ?1",
+            params![normalize_query(query), results as i64],
+        )?;
+        Ok(())
+    }
+
+    /// 最近的搜索（最新在前），可按前缀过滤（不区分大小写）
+    pub fn recent(&self, prefix: &str, limit: usize) -> Result<Vec<HistoryEntry>> {
+        self.entries(
+            "WHERE lower(substr(query, 1, length(?1))) = lower(?1)
+             ORDER BY last_used DESC
+             LIMIT ?2",
+            params![prefix.trim(), limit as i64],
+        )
+    }
+
+    /// 使用次数最多的搜索
+    pub fn most_used(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        self.entries(
+            "ORDER BY uses DESC, last_used DESC LIMIT ?1",
+            params![limit as i64],
+        )
+    }
+
+    /// 最近一次没有任何结果的搜索，使用次数多的在前
+    pub fn zero_results(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        self.entries(
+            "WHERE results = 0 ORDER BY uses DESC, last_used DESC LIMIT ?1",
+            params![limit as i64],
+        )
+    }
+
+    fn entries(&self, tail: &str, values: &[&dyn ToSql]) -> Result<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT query, uses, last_used, results FROM queries {}",
+            tail
+        ))?;
+        let rows = stmt.query_map(values, |row| {
+            Ok(HistoryEntry {
+                query: row.get(0)?,
+                uses: row.get(1)?,
+                last_used: row.get::<_, i64>(2)? as u64,
+                results: row.get::<_, Option<i64>>(3)?.map(|count| count as usize),
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 按 `rewrite` 改写已保存的查询（如目录合并后更新其中的目录过滤），返回改写条数；
+    /// 改写后与已有查询相同时合并使用次数
+    pub fn rewrite<F: Fn(&str) -> Option<String>>(&self, rewrite: F) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let entries = {
+            let mut stmt = tx.prepare("SELECT query, uses, last_used FROM queries")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+
+        // 目录改写后结果数可能变化，交给下次搜索重新记录
+        let mut rewritten = 0;
+        for (query, uses, last_used) in entries {
+            let Some(new_query) = rewrite(&query) else {
+                continue;
+            };
+            if new_query.eq_ignore_ascii_case(&query) {
+                continue;
+            }
+            tx.execute("DELETE FROM queries WHERE query = ?1", params![query])?;
+            tx.execute(
+                "INSERT INTO queries (query, uses, last_used) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(query) DO UPDATE SET
+                    uses = queries.uses + excluded.uses,
+                    last_used = max(queries.last_used, excluded.last_used)",
+                params![new_query, uses, last_used],
+            )?;
+            rewritten += 1;
+        }
+        tx.commit()?;
+        Ok(rewritten)
+    }
+}
+
+fn normalize_query(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 旧版 history.db 没有 results 列，补上后旧记录的结果数为 NULL
+fn migrate_results_column(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("PRAGMA table_info(queries)")?;
+    let has_results = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>>>()?
+        .iter()
+        .any(|column| column == "results");
+    if !has_results {
+        conn.execute("ALTER TABLE queries ADD COLUMN results INTEGER", [])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn queries(history: &QueryHistory, prefix: &str) -> Vec<String> {
+        history
+            .recent(prefix, 10)
+            .expect("recent")
+            .into_iter()
+            .map(|entry| entry.query)
+            .collect()
+    }
+
+    #[test]
+    fn typing_prefixes_collapse_into_final_query() {
+        let dir = tempdir().expect("tempdir");
+        let history = QueryHistory::new(dir.path().join("history.db")).expect("history");
+
+        for (offset, typed) in ["r", "ru", "rust", "rust  as", "rust async", "rust as"]
+            .iter()
+            .enumerate()
+        {
+            history
+                .record(typed, 1_000 + offset as u64)
+                .expect("record");
+        }
+        assert_eq!(queries(&history, ""), vec!["rust async"]);
+
+        // 过了输入窗口的旧查询不受影响
+        history.record("tokio", 5_000).expect("record");
+        history.record("tokio", 9_000).expect("record");
+        history.record("tok", 9_001).expect("record");
+        let recent = history.recent("TOK", 10).expect("recent");
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].uses, 2);
+        assert_eq!(queries(&history, ""), vec!["tokio", "rust async"]);
+    }
+
+    #[test]
+    fn most_used_and_zero_result_queries() {
+        let dir = tempdir().expect("tempdir");
+        let history = QueryHistory::new(dir.path().join("history.db")).expect("history");
+        for (at, query, results) in [
+            (1_000, "rust", 12),
+            (2_000, "rust", 9),
+            (3_000, "kubernetes operator", 0),
+            (4_000, "go", 4),
+            (5_000, "kubernetes  operator", 0),
+            (6_000, "zig", 0),
+        ] {
+            history.record(query, at).expect("record");
+            history.record_results(query, results).expect("results");
+        }
+
+        let top: Vec<(String, u32)> = history
+            .most_used(2)
+            .expect("most used")
+            .into_iter()
+            .map(|entry| (entry.query, entry.uses))
+            .collect();
+        assert_eq!(
+            top,
+            vec![
+                ("kubernetes operator".to_string(), 2),
+                ("rust".to_string(), 2)
+            ]
+        );
+        let zero = history.zero_results(10).expect("zero");
+        assert_eq!(
+            zero.iter()
+                .map(|entry| entry.query.as_str())
+                .collect::<Vec<_>>(),
+            vec!["kubernetes operator", "zig"]
+        );
+        assert_eq!(zero[0].results, Some(0));
+    }
+
+    #[test]
+    fn recall_queries_are_not_recorded() {
+        let dir = tempdir().expect("tempdir");
+        let history = QueryHistory::new(dir.path().join("history.db")).expect("history");
+        history.record("!rust", 1).expect("record");
+        history.record("   ", 2).expect("record");
+        assert!(queries(&history, "").is_empty());
+    }
+
+    #[test]
+    fn rewrite_renames_queries_and_merges_duplicates() {
+        let dir = tempdir().expect("tempdir");
+        let history = QueryHistory::new(dir.path().join("history.db")).expect("history");
+        history.record("#old rust", 1_000).expect("record");
+        history.record("#new rust", 2_000).expect("record");
+        history.record("go", 3_000).expect("record");
+
+        let rewritten = history
+            .rewrite(|query| {
+                query
+                    .strip_prefix("#old")
+                    .map(|rest| format!("#new{}", rest))
+            })
+            .expect("rewrite");
+        assert_eq!(rewritten, 1);
+
+        let entries = history.recent("", 10).expect("recent");
+        let merged = entries
+            .iter()
+            .find(|entry| entry.query == "#new rust")
+            .expect("merged");
+        assert_eq!((merged.uses, merged.last_used), (2, 2_000));
+        assert_eq!(queries(&history, "#old"), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,62 @@
+/// 纯文本链接列表中的一条：标题为空时由调用方以 URL 代替
+#[derive(Debug, PartialEq, Eq)]
+pub struct ListedUrl {
+    pub title: String,
+    pub url: String,
+}
+
+/// 解析纯文本链接列表：每行一个 URL，或 `标题<TAB>URL`
+///
+/// 空行与 `#` 开头的注释行被忽略；没有 `://` 的行视为无效，返回其行号（从 1 开始）。
+pub fn parse_url_list(content: &str) -> (Vec<ListedUrl>, Vec<usize>) {
+    let mut urls = Vec::new();
+    let mut invalid = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (title, url) = match line.split_once('\t') {
+            Some((title, url)) => (title.trim(), url.trim()),
+            None => ("", line),
+        };
+        if !url.contains("://") || url.contains(char::is_whitespace) {
+            invalid.push(number + 1);
+            continue;
+        }
+        urls.push(ListedUrl {
+            title: title.to_string(),
+            url: url.to_string(),
+        });
+    }
+    (urls, invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bare_urls_and_tab_separated_titles() {
+        let content = "https://docs.rs\n\n# 稍后阅读\nRust Book\thttps://doc.rust-lang.org/book \nnot a url\r\n  https://go.dev  \n";
+        let (urls, invalid) = parse_url_list(content);
+        assert_eq!(
+            urls,
+            vec![
+                ListedUrl {
+                    title: String::new(),
+                    url: "https://docs.rs".to_string(),
+                },
+                ListedUrl {
+                    title: "Rust Book".to_string(),
+                    url: "https://doc.rust-lang.org/book".to_string(),
+                },
+                ListedUrl {
+                    title: String::new(),
+                    url: "https://go.dev".to_string(),
+                },
+            ]
+        );
+        assert_eq!(invalid, vec![5]);
+    }
+}
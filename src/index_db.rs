@@ -1,17 +1,116 @@
-use crate::bookmark::ChromeBookmark;
-use crate::searcher::folder_filter_to_like_pattern;
+use crate::bookmark::{date_added_to_unix_secs, ChromeBookmark};
+use crate::merge::BookmarkVariant;
+use crate::query::{is_phrase, QueryExpr};
+use crate::schema::{self, Migration};
+use crate::searcher::{
+    compact_for_match, compact_host_forms, compact_name_forms, escape_like_value,
+    folder_filter_to_like_pattern, BookmarkFilters, SortOrder,
+};
+use crate::tags::{bookmark_key, tag_key};
+use crate::tokenize::{analyze, query_trigrams, words, TokenizeConfig};
+use crate::url_normalize::{canonical_url, url_in_domains};
 use rusqlite::{params, params_from_iter, Connection, Result, ToSql};
-use std::path::PathBuf;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 pub struct BookmarkIndex {
     conn: Connection,
     fts_enabled: bool,
+    /// 可选的 trigram 子串索引（标题与 URL），见 `set_substring_index`
+    substring_enabled: bool,
+    /// 偏好域名（已小写），FTS 结果按 bm25 排序后对其加权重排
+    boost_domains: Vec<String>,
+    /// FTS 各字段的分词方式，见 `set_tokenize`
+    tokenize: TokenizeConfig,
+}
+
+const FTS_INSERT_HEAD: &str =
+    "INSERT INTO bookmarks_fts (bookmark_id, name, url, folder_path, compact)";
+
+/// 批量插入时每条 INSERT 携带的行数（11 列 × 100 行，远低于 SQLite 的参数上限）
+const INSERT_BATCH_ROWS: usize = 100;
+/// 批量删除时每条语句的 id 个数：FTS 表按未建索引的 bookmark_id 删除要扫描整表，每批只扫一遍
+const DELETE_BATCH_IDS: usize = 500;
+
+/// bookmarks 表的二级索引；整体重建时先删除、写完再建，省去逐行维护索引的开销
+const BOOKMARK_INDEXES: &[(&str, &str)] = &[
+    ("idx_bookmarks_name", "bookmarks(name)"),
+    ("idx_bookmarks_url", "bookmarks(url)"),
+    ("idx_bookmarks_folder_path", "bookmarks(folder_path)"),
+    ("idx_bookmarks_profile", "bookmarks(profile)"),
+    ("idx_bookmarks_tag_key", "bookmarks(tag_key)"),
+    ("idx_bookmarks_added_at", "bookmarks(added_at)"),
+    ("idx_bookmarks_normalized_url", "bookmarks(normalized_url)"),
+];
+
+/// 偏好域名结果的 bm25 乘数（bm25 越小越相关，且为负数）
+const DOMAIN_BOOST_FACTOR: f64 = 1.5;
+/// 置顶书签的 bm25 乘数：远大于关键词之间的相关度差距，命中的置顶书签总排在其他结果之前
+const PINNED_BOOST_FACTOR: f64 = 1000.0;
+/// 有偏好域名时多取的候选倍数，让排在 limit 之后的偏好结果有机会上浮
+const DOMAIN_BOOST_CANDIDATE_MULTIPLIER: usize = 3;
+/// 每个内存中书签除文本外的开销：`ChromeBookmark` 的 11 个字符串头与分配器余量
+const BOOKMARK_OVERHEAD_BYTES: u64 = 320;
+
+/// 每个来源上次写入索引时的状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceState {
+    pub browser: String,
+    pub profile: String,
+    pub path: String,
+    pub fingerprint: String,
+    pub bookmark_count: usize,
+    /// Unix 秒
+    pub refreshed_at: i64,
+}
+
+/// `sync_bookmarks` 实际改动的行数
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncStats {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+/// `stats index` 展示的索引数据库健康指标
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexHealth {
+    pub page_size: u64,
+    pub page_count: u64,
+    /// 空闲页数，VACUUM 后归零
+    pub freelist_count: u64,
+    /// 各表行数，不含 FTS5 的影子表
+    pub tables: Vec<(String, u64)>,
+    /// FTS5 虚拟表连同影子表占用的字节数；SQLite 未启用 dbstat 时为 None
+    pub fts_bytes: Option<u64>,
+    /// 上次 VACUUM 的 Unix 秒
+    pub last_vacuum: Option<i64>,
+    /// 上次成功刷新的完成时间（Unix 秒）与耗时
+    pub last_refresh_at: Option<i64>,
+    pub last_refresh_ms: Option<u64>,
+}
+
+impl IndexHealth {
+    /// 空闲页占总页数的百分比
+    pub fn fragmentation(&self) -> f64 {
+        self.freelist_count as f64 * 100.0 / self.page_count.max(1) as f64
+    }
 }
 
 impl BookmarkIndex {
     pub fn new(db_path: PathBuf) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
+        Self::with_connection(Connection::open(db_path)?)
+    }
+
+    /// 数据目录不可写时使用的内存索引，只在本次运行中有效
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
         let _ = conn.busy_timeout(Duration::from_millis(500));
 
         conn.execute_batch(
@@ -22,38 +121,18 @@ impl BookmarkIndex {
              PRAGMA mmap_size = 268435456;",
         )?;
 
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS meta (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            )",
-            [],
-        )?;
-
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS bookmarks (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                url TEXT NOT NULL,
-                date_added TEXT NOT NULL,
-                folder_path TEXT
-            )",
-            [],
-        )?;
-
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_bookmarks_name ON bookmarks(name)",
-            [],
-        )?;
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_bookmarks_url ON bookmarks(url)",
-            [],
-        )?;
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_bookmarks_folder_path ON bookmarks(folder_path)",
-            [],
-        )?;
+        if let Err(err) = schema::migrate(&conn, INDEX_MIGRATIONS) {
+            if schema::is_environment_error(&err) {
+                return Err(err);
+            }
+            // 迁移失败时删掉可重建的表，从版本 0 重新建库；本地书签与回收站保留
+            reset_derived_tables(&conn)?;
+            schema::migrate(&conn, INDEX_MIGRATIONS)?;
+        }
+        // 二级索引不属于某个迁移步骤：整体重建时会先删除，这里补齐后续版本新增的列上的索引
+        create_bookmark_indexes(&conn)?;
 
+        let fts_outdated = migrate_fts_table(&conn)?;
         let fts_enabled = conn
             .execute(
                 "CREATE VIRTUAL TABLE IF NOT EXISTS bookmarks_fts USING fts5(
@@ -61,13 +140,132 @@ impl BookmarkIndex {
                     name,
                     url,
                     folder_path,
+                    compact,
                     tokenize = 'unicode61'
                 )",
                 [],
             )
             .is_ok();
 
-        Ok(Self { conn, fts_enabled })
+        let index = Self {
+            conn,
+            fts_enabled,
+            substring_enabled: false,
+            boost_domains: Vec::new(),
+            tokenize: TokenizeConfig::default(),
+        };
+        if fts_outdated && fts_enabled {
+            index.atomically(|| {
+                let bookmarks = index.load_all_bookmarks()?;
+                index.insert_fts_rows(bookmarks.iter())
+            })?;
+        }
+        Ok(index)
+    }
+
+    pub fn set_domain_boosts(&mut self, domains: Vec<String>) {
+        self.boost_domains = domains;
+    }
+
+    /// 切换 FTS 各字段的分词方式；与索引中记录的设置不同时，按新设置从书签表重建 FTS
+    pub fn set_tokenize(&mut self, tokenize: TokenizeConfig) -> Result<()> {
+        let signature = tokenize.signature();
+        self.tokenize = tokenize;
+        if !self.fts_enabled {
+            return Ok(());
+        }
+        let recorded = self
+            .get_meta("fts_tokenize")?
+            .unwrap_or_else(|| TokenizeConfig::default().signature());
+        if recorded == signature {
+            return Ok(());
+        }
+        self.atomically(|| {
+            self.conn.execute("DELETE FROM bookmarks_fts", [])?;
+            let bookmarks = self.load_all_bookmarks()?;
+            self.insert_fts_rows(bookmarks.iter())?;
+            self.set_meta("fts_tokenize", &signature)
+        })
+    }
+
+    /// 开启时建立 trigram 子串索引（首次建立会从现有书签回填），关闭时删除以释放空间
+    /// 当前 SQLite 不支持 trigram 分词器时静默保持关闭
+    pub fn set_substring_index(&mut self, enabled: bool) -> Result<()> {
+        let exists: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'bookmarks_trigram')",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if !enabled {
+            if exists {
+                self.conn
+                    .execute_batch("DROP TABLE IF EXISTS bookmarks_trigram;")?;
+            }
+            self.substring_enabled = false;
+            return Ok(());
+        }
+
+        if !exists {
+            let created = self
+                .conn
+                .execute(
+                    "CREATE VIRTUAL TABLE bookmarks_trigram USING fts5(
+                        bookmark_id UNINDEXED,
+                        name,
+                        url,
+                        tokenize = 'trigram'
+                    )",
+                    [],
+                )
+                .is_ok();
+            if !created {
+                self.substring_enabled = false;
+                return Ok(());
+            }
+            self.conn.execute(
+                "INSERT INTO bookmarks_trigram (bookmark_id, name, url)
+                 SELECT id, name, url FROM bookmarks",
+                [],
+            )?;
+        }
+        self.substring_enabled = true;
+        Ok(())
+    }
+
+    /// 在同一个事务中执行多步写入，全部成功才提交；嵌套调用时改用 SAVEPOINT
+    /// WAL 模式下未提交的写入对读者不可见，崩溃或断电后也会被丢弃，因此重建期间搜索始终看到完整的旧索引
+    pub fn atomically<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let nested = !self.conn.is_autocommit();
+        self.conn.execute_batch(if nested {
+            "SAVEPOINT nested_write;"
+        } else {
+            "BEGIN IMMEDIATE;"
+        })?;
+
+        match f() {
+            Ok(value) => {
+                self.conn.execute_batch(if nested {
+                    "RELEASE nested_write;"
+                } else {
+                    "COMMIT;"
+                })?;
+                Ok(value)
+            }
+            Err(err) => {
+                let _ = self.conn.execute_batch(if nested {
+                    "ROLLBACK TO nested_write; RELEASE nested_write;"
+                } else {
+                    "ROLLBACK;"
+                });
+                Err(err)
+            }
+        }
+    }
+
+    /// 最近一次写入索引时记录的来源指纹
+    pub fn bookmarks_fingerprint(&self) -> Result<Option<String>> {
+        self.get_meta("bookmarks_fingerprint")
     }
 
     pub fn bookmarks_need_refresh(&self, fingerprint: &str) -> Result<bool> {
@@ -75,434 +273,3694 @@ impl BookmarkIndex {
     }
 
     pub fn clear_bookmarks_index(&self) -> Result<()> {
-        self.conn.execute_batch("BEGIN IMMEDIATE;")?;
-        let result: Result<()> = (|| {
+        self.atomically(|| {
             self.conn.execute("DELETE FROM bookmarks", [])?;
             if self.fts_enabled {
                 self.conn.execute("DELETE FROM bookmarks_fts", [])?;
             }
-            self.conn
-                .execute("DELETE FROM meta WHERE key = 'bookmarks_fingerprint'", [])?;
-            Ok(())
-        })();
-
-        match result {
-            Ok(()) => {
-                self.conn.execute_batch("COMMIT;")?;
-                Ok(())
-            }
-            Err(err) => {
-                let _ = self.conn.execute_batch("ROLLBACK;");
-                Err(err)
+            if self.substring_enabled {
+                self.conn.execute("DELETE FROM bookmarks_trigram", [])?;
             }
-        }
+            self.conn.execute("DELETE FROM sources", [])?;
+            self.conn.execute("DELETE FROM bookmark_variants", [])?;
+            self.conn.execute(
+                "DELETE FROM meta WHERE key IN ('bookmarks_fingerprint', 'source_fingerprints')",
+                [],
+            )?;
+            Ok(())
+        })
     }
 
     pub fn replace_bookmarks(&self, bookmarks: &[ChromeBookmark], fingerprint: &str) -> Result<()> {
-        self.conn.execute_batch("BEGIN IMMEDIATE;")?;
-        let result: Result<()> = (|| {
+        self.atomically(|| {
             self.conn.execute("DELETE FROM bookmarks", [])?;
             if self.fts_enabled {
                 self.conn.execute("DELETE FROM bookmarks_fts", [])?;
             }
+            if self.substring_enabled {
+                self.conn.execute("DELETE FROM bookmarks_trigram", [])?;
+            }
+            for (name, _) in BOOKMARK_INDEXES {
+                self.conn
+                    .execute(&format!("DROP INDEX IF EXISTS {}", name), [])?;
+            }
+            self.insert_bookmarks(bookmarks.iter())?;
+            create_bookmark_indexes(&self.conn)?;
+            self.set_meta("bookmarks_fingerprint", fingerprint)?;
+            Ok(())
+        })
+    }
 
-            let mut stmt = self.conn.prepare(
-                "INSERT INTO bookmarks (id, name, url, date_added, folder_path)
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
-            )?;
+    /// 增量同步：按 id 增删行；`stale_sources` 是本次重新解析的来源，其中的行再按内容哈希比较，
+    /// 只重写内容变化的行。未变化的行连同 FTS 行保持不动
+    pub fn sync_bookmarks(
+        &self,
+        bookmarks: &[ChromeBookmark],
+        stale_sources: &[(String, String)],
+        fingerprint: &str,
+    ) -> Result<SyncStats> {
+        self.atomically(|| {
+            let mut stale_hashes: HashMap<String, u64> = HashMap::new();
+            {
+                let mut stmt = self.conn.prepare(
+                    "SELECT id, name, url, date_added, folder_path, browser, profile, guid
+                     FROM bookmarks WHERE browser = ?1 AND profile = ?2",
+                )?;
+                for (browser, profile) in stale_sources {
+                    for row in stmt.query_map(params![browser, profile], bookmark_from_row)? {
+                        let bookmark = row?;
+                        stale_hashes.insert(bookmark.id.clone(), row_hash(&bookmark));
+                    }
+                }
+            }
 
-            let mut fts_stmt = if self.fts_enabled {
-                Some(self.conn.prepare(
-                    "INSERT INTO bookmarks_fts (bookmark_id, name, url, folder_path)
-                         VALUES (?1, ?2, ?3, ?4)",
-                )?)
-            } else {
-                None
+            let existing: HashSet<String> = {
+                let mut stmt = self.conn.prepare("SELECT id FROM bookmarks")?;
+                let ids = stmt.query_map([], |row| row.get::<_, String>(0))?;
+                ids.collect::<Result<_>>()?
             };
-
-            for bookmark in bookmarks {
-                stmt.execute(params![
-                    bookmark.id,
-                    bookmark.name,
-                    bookmark.url,
-                    bookmark.date_added,
-                    bookmark.folder_path
-                ])?;
-
-                if let Some(ref mut fts_stmt) = fts_stmt {
-                    fts_stmt.execute(params![
-                        bookmark.id,
-                        bookmark.name,
-                        bookmark.url,
-                        bookmark.folder_path
-                    ])?;
+            let wanted: HashMap<&str, &ChromeBookmark> = bookmarks
+                .iter()
+                .map(|bookmark| (bookmark.id.as_str(), bookmark))
+                .collect();
+
+            let mut stats = SyncStats::default();
+            let mut changed: HashSet<&str> = HashSet::new();
+            let mut deleted: Vec<&str> = Vec::new();
+            for id in &existing {
+                match wanted.get(id.as_str()) {
+                    None => {
+                        deleted.push(id);
+                        stats.removed += 1;
+                    }
+                    Some(bookmark)
+                        if stale_hashes
+                            .get(id)
+                            .is_some_and(|hash| *hash != row_hash(bookmark)) =>
+                    {
+                        deleted.push(id);
+                        changed.insert(bookmark.id.as_str());
+                    }
+                    Some(_) => {}
                 }
             }
+            self.delete_indexed_bookmarks(&deleted)?;
+            stats.changed = changed.len();
+            stats.added = bookmarks
+                .iter()
+                .filter(|bookmark| !existing.contains(&bookmark.id))
+                .count();
+            self.insert_bookmarks(bookmarks.iter().filter(|bookmark| {
+                !existing.contains(&bookmark.id) || changed.contains(bookmark.id.as_str())
+            }))?;
 
             self.set_meta("bookmarks_fingerprint", fingerprint)?;
-            Ok(())
-        })();
+            Ok(stats)
+        })
+    }
 
-        match result {
-            Ok(()) => {
-                self.conn.execute_batch("COMMIT;")?;
-                Ok(())
+    fn insert_bookmarks<'a>(
+        &self,
+        bookmarks: impl Iterator<Item = &'a ChromeBookmark>,
+    ) -> Result<()> {
+        let bookmarks: Vec<&ChromeBookmark> = bookmarks.collect();
+        let tag_keys: Vec<String> = bookmarks.iter().map(|bookmark| tag_key(bookmark)).collect();
+        let added_at: Vec<i64> = bookmarks
+            .iter()
+            .map(|bookmark| date_added_to_unix_secs(&bookmark.date_added).unwrap_or(0))
+            .collect();
+        let normalized_urls: Vec<String> = bookmarks
+            .iter()
+            .map(|bookmark| canonical_url(&bookmark.url))
+            .collect();
+        let mut values: Vec<&dyn ToSql> = Vec::with_capacity(bookmarks.len() * 11);
+        for (((bookmark, key), added_at), normalized_url) in bookmarks
+            .iter()
+            .zip(&tag_keys)
+            .zip(&added_at)
+            .zip(&normalized_urls)
+        {
+            values.extend([
+                &bookmark.id as &dyn ToSql,
+                &bookmark.name,
+                &bookmark.url,
+                &bookmark.date_added,
+                &bookmark.folder_path,
+                &bookmark.browser,
+                &bookmark.profile,
+                &bookmark.guid,
+                key,
+                added_at,
+                normalized_url,
+            ]);
+        }
+        self.insert_batched(
+            "INSERT INTO bookmarks
+                (id, name, url, date_added, folder_path, browser, profile, guid, tag_key, added_at,
+                 normalized_url)",
+            11,
+            &values,
+        )?;
+
+        if self.fts_enabled {
+            self.insert_fts_rows(bookmarks.iter().copied())?;
+        }
+        if self.substring_enabled {
+            let mut values: Vec<&dyn ToSql> = Vec::with_capacity(bookmarks.len() * 3);
+            for bookmark in &bookmarks {
+                values.extend([&bookmark.id as &dyn ToSql, &bookmark.name, &bookmark.url]);
             }
-            Err(err) => {
-                let _ = self.conn.execute_batch("ROLLBACK;");
-                Err(err)
+            self.insert_batched(
+                "INSERT INTO bookmarks_trigram (bookmark_id, name, url)",
+                3,
+                &values,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 多行 VALUES 批量插入：整批共用一条缓存的预编译语句，不足一批的余数单独拼一条
+    fn insert_batched(&self, head: &str, columns: usize, values: &[&dyn ToSql]) -> Result<()> {
+        let batch_len = INSERT_BATCH_ROWS * columns;
+        let mut chunks = values.chunks_exact(batch_len);
+        if chunks.len() > 0 {
+            let mut stmt = self.conn.prepare_cached(&multi_row_insert_sql(
+                head,
+                columns,
+                INSERT_BATCH_ROWS,
+            ))?;
+            for chunk in chunks.by_ref() {
+                stmt.execute(params_from_iter(chunk))?;
             }
         }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            self.conn.execute(
+                &multi_row_insert_sql(head, columns, rest.len() / columns),
+                params_from_iter(rest),
+            )?;
+        }
+        Ok(())
     }
 
-    pub fn get_total_bookmarks(&self) -> Result<usize> {
-        self.conn
-            .query_row("SELECT COUNT(*) FROM bookmarks", [], |row| row.get(0))
+    /// 只写 FTS 行（整体写入或 FTS 表结构升级后从书签表回填）
+    fn insert_fts_rows<'a>(
+        &self,
+        bookmarks: impl Iterator<Item = &'a ChromeBookmark>,
+    ) -> Result<()> {
+        let rows: Vec<_> = bookmarks
+            .map(|bookmark| {
+                (
+                    bookmark,
+                    analyze(&bookmark.name, &self.tokenize.name),
+                    analyze(&bookmark.url, &self.tokenize.url),
+                    bookmark
+                        .folder_path
+                        .as_deref()
+                        .map(|folder| analyze(folder, &self.tokenize.folder)),
+                    compact_fts_text(bookmark),
+                )
+            })
+            .collect();
+        let mut values: Vec<&dyn ToSql> = Vec::with_capacity(rows.len() * 5);
+        for (bookmark, name, url, folder_path, compact) in &rows {
+            values.extend([&bookmark.id as &dyn ToSql, name, url, folder_path, compact]);
+        }
+        self.insert_batched(FTS_INSERT_HEAD, 5, &values)
     }
 
-    pub fn load_all_bookmarks(&self) -> Result<Vec<ChromeBookmark>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, name, url, date_added, folder_path
-             FROM bookmarks
-             ORDER BY rowid",
-        )?;
+    /// 保存本地书签并立即写入索引；URL 已存在时更新标题与目录。返回 (书签, 是否新增)
+    pub fn add_local_bookmark(
+        &self,
+        url: &str,
+        name: &str,
+        folder_path: Option<&str>,
+        date_added: &str,
+    ) -> Result<(ChromeBookmark, bool)> {
+        self.atomically(|| {
+            let existed: bool = self.conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM local_bookmarks WHERE url = ?1)",
+                params![url],
+                |row| row.get(0),
+            )?;
+            self.conn.execute(
+                "INSERT INTO local_bookmarks (url, name, folder_path, date_added)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(url) DO UPDATE SET
+                    name = excluded.name,
+                    folder_path = excluded.folder_path",
+                params![url, name, folder_path, date_added],
+            )?;
+            let bookmark = self.conn.query_row(
+                &format!("{} WHERE url = ?1", LOCAL_BOOKMARKS_SELECT),
+                params![url],
+                bookmark_from_row,
+            )?;
 
+            // 重新保存回收站中的本地书签视为恢复
+            self.conn
+                .execute("DELETE FROM trash WHERE id = ?1", params![bookmark.id])?;
+            self.delete_indexed_bookmark(&bookmark.id)?;
+            self.insert_bookmarks(std::iter::once(&bookmark))?;
+            Ok((bookmark, !existed))
+        })
+    }
+
+    /// 全部本地书签，转换为与浏览器书签相同的结构（来源为 `local · Local`）
+    pub fn local_bookmarks(&self) -> Result<Vec<ChromeBookmark>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{} ORDER BY id", LOCAL_BOOKMARKS_SELECT))?;
         let rows = stmt.query_map([], bookmark_from_row)?;
-        rows.collect::<Result<Vec<_>>>()
+        rows.collect()
     }
 
-    pub fn list_bookmarks(&self, limit: usize) -> Result<Vec<ChromeBookmark>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, name, url, date_added, folder_path
-             FROM bookmarks
-             ORDER BY rowid
-             LIMIT ?1",
-        )?;
+    fn delete_indexed_bookmark(&self, id: &str) -> Result<()> {
+        self.delete_indexed_bookmarks(&[id])
+    }
 
-        let rows = stmt.query_map(params![limit as i64], bookmark_from_row)?;
-        rows.collect::<Result<Vec<_>>>()
+    /// 按批删除书签行及其 FTS、trigram 行，每张表每批一条语句
+    fn delete_indexed_bookmarks(&self, ids: &[&str]) -> Result<()> {
+        let mut tables = vec![("bookmarks", "id")];
+        if self.fts_enabled {
+            tables.push(("bookmarks_fts", "bookmark_id"));
+        }
+        if self.substring_enabled {
+            tables.push(("bookmarks_trigram", "bookmark_id"));
+        }
+        for chunk in ids.chunks(DELETE_BATCH_IDS) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            for (table, column) in &tables {
+                self.conn.execute(
+                    &format!(
+                        "DELETE FROM {} WHERE {} IN ({})",
+                        table, column, placeholders
+                    ),
+                    params_from_iter(chunk),
+                )?;
+            }
+        }
+        Ok(())
     }
 
-    pub fn list_bookmarks_by_folder_filters(
+    /// 修改书签的标题与目录并立即重写其索引行；本地书签同时改写 local_bookmarks。id 不存在时返回 None
+    ///
+    /// 浏览器书签只改索引，调用方需先写回书签文件，否则下次解析该来源时会被还原
+    pub fn update_bookmark(
         &self,
-        folder_filters: &[String],
-        limit: usize,
-    ) -> Result<Vec<ChromeBookmark>> {
-        let patterns: Vec<String> = folder_filters
-            .iter()
-            .filter_map(|raw| folder_filter_to_like_pattern(raw))
-            .collect();
+        id: &str,
+        name: &str,
+        folder_path: Option<&str>,
+    ) -> Result<Option<ChromeBookmark>> {
+        self.atomically(|| {
+            let Some(mut bookmark) = self.get_bookmark(id)? else {
+                return Ok(None);
+            };
+            if bookmark.browser == LOCAL_BROWSER {
+                let local_id = id.rsplit(':').next().unwrap_or_default();
+                self.conn.execute(
+                    "UPDATE local_bookmarks SET name = ?1, folder_path = ?2 WHERE id = ?3",
+                    params![name, folder_path, local_id],
+                )?;
+            }
+            bookmark.name = name.to_string();
+            bookmark.name_lower = name.to_lowercase();
+            bookmark.folder_path = folder_path.map(ToString::to_string);
+            bookmark.folder_path_lower = folder_path.map(str::to_lowercase);
+
+            let indexed: bool = self.conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM bookmarks WHERE id = ?1)",
+                params![id],
+                |row| row.get(0),
+            )?;
+            if indexed {
+                self.delete_indexed_bookmark(id)?;
+                self.insert_bookmarks(std::iter::once(&bookmark))?;
+            } else {
+                self.conn.execute(
+                    "UPDATE bookmark_variants SET name = ?1, folder_path = ?2 WHERE id = ?3",
+                    params![name, folder_path, id],
+                )?;
+            }
+            Ok(Some(bookmark))
+        })
+    }
 
-        if patterns.is_empty() {
-            return self.list_bookmarks(limit);
-        }
+    /// 把书签移入回收站并立即从索引移除；返回被删除的书签，id 不存在时返回 None
+    ///
+    /// 同时清除索引指纹，下次搜索会重新合并，让同一 URL 的其他来源版本补上
+    pub fn trash_bookmark(&self, id: &str, deleted_at: i64) -> Result<Option<ChromeBookmark>> {
+        self.atomically(|| {
+            let Some(bookmark) = self.get_bookmark(id)? else {
+                return Ok(None);
+            };
+            self.conn.execute(
+                "INSERT OR REPLACE INTO trash
+                    (id, name, url, date_added, folder_path, browser, profile, guid, deleted_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    bookmark.id,
+                    bookmark.name,
+                    bookmark.url,
+                    bookmark.date_added,
+                    bookmark.folder_path,
+                    bookmark.browser,
+                    bookmark.profile,
+                    bookmark.guid,
+                    deleted_at
+                ],
+            )?;
+            self.forget_bookmark(id)?;
+            Ok(Some(bookmark))
+        })
+    }
 
-        let mut sql = String::from(
-            "SELECT id, name, url, date_added, folder_path
-             FROM bookmarks
-             WHERE 1=1",
-        );
+    /// 从回收站恢复，按保存的内容放回索引；不在回收站中时返回 None
+    pub fn restore_bookmark(&self, id: &str) -> Result<Option<ChromeBookmark>> {
+        self.atomically(|| {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, name, url, date_added, folder_path, browser, profile, guid
+                 FROM trash WHERE id = ?1",
+            )?;
+            let mut rows = stmt.query_map(params![id], bookmark_from_row)?;
+            let Some(bookmark) = rows.next().transpose()? else {
+                return Ok(None);
+            };
+            self.conn
+                .execute("DELETE FROM trash WHERE id = ?1", params![id])?;
+            self.delete_indexed_bookmark(id)?;
+            self.insert_bookmarks(std::iter::once(&bookmark))?;
+            self.conn
+                .execute("DELETE FROM meta WHERE key = 'bookmarks_fingerprint'", [])?;
+            Ok(Some(bookmark))
+        })
+    }
 
-        for _ in &patterns {
-            sql.push_str(" AND lower(ifnull(folder_path, '')) LIKE ? ESCAPE '\\'");
-        }
+    /// 回收站中的书签及删除时间（秒），最近删除的在前
+    pub fn trashed_bookmarks(&self) -> Result<Vec<(ChromeBookmark, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, url, date_added, folder_path, browser, profile, guid, deleted_at
+             FROM trash ORDER BY deleted_at DESC, rowid DESC",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((bookmark_from_row(row)?, row.get(8)?)))?;
+        rows.collect()
+    }
 
-        sql.push_str(" ORDER BY rowid LIMIT ?");
+    pub fn trashed_ids(&self) -> Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare("SELECT id FROM trash")?;
+        let ids = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        ids.collect()
+    }
 
-        let mut params: Vec<&dyn ToSql> = Vec::new();
-        for pattern in &patterns {
-            params.push(pattern as &dyn ToSql);
-        }
+    /// 从索引（含其他来源版本）移除书签但不进回收站，用于已从浏览器中删除的书签
+    pub fn forget_bookmark(&self, id: &str) -> Result<()> {
+        self.atomically(|| {
+            self.delete_indexed_bookmark(id)?;
+            self.conn
+                .execute("DELETE FROM bookmark_variants WHERE id = ?1", params![id])?;
+            self.conn
+                .execute("DELETE FROM meta WHERE key = 'bookmarks_fingerprint'", [])?;
+            Ok(())
+        })
+    }
 
-        let limit_param = limit as i64;
-        params.push(&limit_param);
+    /// 单个来源入库时的全部书签（含合并时落选、存在 variants 表中的版本），用于增量合并
+    pub fn source_bookmarks(&self, browser: &str, profile: &str) -> Result<Vec<ChromeBookmark>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, url, date_added, folder_path, browser, profile, guid
+             FROM bookmarks WHERE browser = ?1 AND profile = ?2
+             UNION ALL
+             SELECT id, name, url, date_added, folder_path, browser, profile, guid
+             FROM bookmark_variants WHERE browser = ?1 AND profile = ?2",
+        )?;
+        let rows = stmt.query_map(params![browser, profile], bookmark_from_row)?;
+        rows.collect()
+    }
 
-        let mut stmt = self.conn.prepare(&sql)?;
-        let rows = stmt.query_map(params.as_slice(), bookmark_from_row)?;
-        rows.collect::<Result<Vec<_>>>()
+    /// 每个来源上次入库时的指纹，(browser, profile) -> fingerprint
+    pub fn source_fingerprints(&self) -> Result<HashMap<(String, String), String>> {
+        let raw = self.get_meta("source_fingerprints")?.unwrap_or_default();
+        Ok(raw
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, '\t');
+                let browser = parts.next()?.to_string();
+                let profile = parts.next()?.to_string();
+                Some(((browser, profile), parts.next()?.to_string()))
+            })
+            .collect())
     }
 
-    pub fn search_bookmark_candidates_fts(
+    pub fn set_source_fingerprints(
         &self,
-        query: &str,
-        limit: usize,
-    ) -> Result<Option<Vec<ChromeBookmark>>> {
-        self.search_bookmarks_fts(query, limit)
+        fingerprints: &[((String, String), String)],
+    ) -> Result<()> {
+        let raw = fingerprints
+            .iter()
+            .map(|((browser, profile), fingerprint)| {
+                format!("{}\t{}\t{}", browser, profile, fingerprint)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.set_meta("source_fingerprints", &raw)
     }
 
-    pub fn search_bookmark_candidates_fts_with_folders(
-        &self,
-        query: &str,
-        folder_filters: &[String],
-        limit: usize,
-    ) -> Result<Option<Vec<ChromeBookmark>>> {
-        self.search_bookmarks_fts_with_folders(query, folder_filters, limit)
+    /// 整体替换来源状态（与 replace_bookmarks 配合使用）
+    pub fn replace_source_states(&self, states: &[SourceState]) -> Result<()> {
+        self.atomically(|| {
+            self.conn.execute("DELETE FROM sources", [])?;
+            let mut stmt = self.conn.prepare(
+                "INSERT OR REPLACE INTO sources
+                    (browser, profile, path, fingerprint, bookmark_count, refreshed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for state in states {
+                stmt.execute(params![
+                    state.browser,
+                    state.profile,
+                    state.path,
+                    state.fingerprint,
+                    state.bookmark_count as i64,
+                    state.refreshed_at
+                ])?;
+            }
+            Ok(())
+        })
     }
 
-    pub fn search_bookmarks_fts(
+    /// 整体替换跨来源合并时落选的版本
+    pub fn replace_variants(&self, variants: &[BookmarkVariant]) -> Result<()> {
+        self.atomically(|| {
+            self.conn.execute("DELETE FROM bookmark_variants", [])?;
+            let mut stmt = self.conn.prepare(
+                "INSERT INTO bookmark_variants
+                    (winner_id, id, name, url, date_added, folder_path, browser, profile, guid)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for variant in variants {
+                let bookmark = &variant.bookmark;
+                stmt.execute(params![
+                    variant.winner_id,
+                    bookmark.id,
+                    bookmark.name,
+                    bookmark.url,
+                    bookmark.date_added,
+                    bookmark.folder_path,
+                    bookmark.browser,
+                    bookmark.profile,
+                    bookmark.guid
+                ])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// 按胜出书签 id 查询其他来源版本
+    pub fn variants_for(
         &self,
-        query: &str,
-        limit: usize,
-    ) -> Result<Option<Vec<ChromeBookmark>>> {
-        if !self.fts_enabled {
-            return Ok(None);
+        winner_ids: &[&str],
+    ) -> Result<HashMap<String, Vec<ChromeBookmark>>> {
+        let mut variants: HashMap<String, Vec<ChromeBookmark>> = HashMap::new();
+        if winner_ids.is_empty() {
+            return Ok(variants);
         }
 
-        let fts_query = match build_fts_query(query) {
-            Some(value) => value,
-            None => return Ok(None),
-        };
+        let placeholders = vec!["?"; winner_ids.len()].join(", ");
+        let sql = format!(
+            "SELECT id, name, url, date_added, folder_path, browser, profile, guid, winner_id
+             FROM bookmark_variants
+             WHERE winner_id IN ({})
+             ORDER BY rowid",
+            placeholders
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(winner_ids), |row| {
+            let winner_id: String = row.get(8)?;
+            Ok((winner_id, bookmark_from_row(row)?))
+        })?;
+        for row in rows {
+            let (winner_id, bookmark) = row?;
+            variants.entry(winner_id).or_default().push(bookmark);
+        }
+        Ok(variants)
+    }
 
+    pub fn source_states(&self) -> Result<Vec<SourceState>> {
         let mut stmt = self.conn.prepare(
-            "SELECT b.id, b.name, b.url, b.date_added, b.folder_path
-             FROM bookmarks_fts
-             JOIN bookmarks b ON b.id = bookmarks_fts.bookmark_id
-             WHERE bookmarks_fts MATCH ?1
-             ORDER BY bm25(bookmarks_fts)
-             LIMIT ?2",
+            "SELECT browser, profile, path, fingerprint, bookmark_count, refreshed_at
+             FROM sources
+             ORDER BY browser, profile",
         )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(SourceState {
+                browser: row.get(0)?,
+                profile: row.get(1)?,
+                path: row.get(2)?,
+                fingerprint: row.get(3)?,
+                bookmark_count: row.get::<_, i64>(4)? as usize,
+                refreshed_at: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
 
-        let rows = stmt.query_map(params![fts_query, limit as i64], bookmark_from_row)?;
-        let results = rows.collect::<Result<Vec<_>>>()?;
-        Ok(Some(results))
+    /// URL 所属书签的来源文件路径（合并后以保留的那条为准），按规范化 URL 比较，原样相同的优先
+    pub fn source_path_for_url(&self, url: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.path FROM bookmarks b
+             JOIN sources s ON s.browser = b.browser AND s.profile = b.profile
+             WHERE b.normalized_url = ?1
+             ORDER BY b.url = ?2 DESC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query_map(params![canonical_url(url), url], |row| row.get(0))?;
+        rows.next().transpose()
     }
 
-    pub fn search_bookmarks_fts_with_folders(
+    /// 规范化 URL 与 `url` 相同的书签（见 `canonical_url`），用于粘贴完整 URL 搜索
+    pub fn bookmarks_with_url(
         &self,
-        query: &str,
-        folder_filters: &[String],
-        limit: usize,
-    ) -> Result<Option<Vec<ChromeBookmark>>> {
-        if !self.fts_enabled {
-            return Ok(None);
+        url: &str,
+        filters: &BookmarkFilters,
+    ) -> Result<Vec<ChromeBookmark>> {
+        let (clauses, filter_values) = filter_clauses(filters, "b.");
+        let sql = format!(
+            "SELECT b.id, b.name, b.url, b.date_added, b.folder_path, b.browser, b.profile, b.guid
+             FROM bookmarks b
+             WHERE b.normalized_url = ?{}
+             ORDER BY b.rowid",
+            clauses
+        );
+        let normalized = canonical_url(url);
+        let mut values: Vec<&dyn ToSql> = vec![&normalized];
+        for value in &filter_values {
+            values.push(value as &dyn ToSql);
         }
-
-        let fts_query = match build_fts_query(query) {
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), bookmark_from_row)?;
+        rows.collect()
+    }
+
+    pub fn fts_enabled(&self) -> bool {
+        self.fts_enabled
+    }
+
+    /// 预热：读一遍书签表与 FTS 索引的页，让首次搜索命中页缓存；返回书签数
+    pub fn warm_up(&self) -> Result<usize> {
+        let total = self.get_total_bookmarks()?;
+        if self.fts_enabled {
+            self.conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(length(block)), 0) FROM bookmarks_fts_data",
+                [],
+                |row| row.get::<_, i64>(0),
+            )?;
+        }
+        Ok(total)
+    }
+
+    pub fn get_total_bookmarks(&self) -> Result<usize> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM bookmarks", [], |row| row.get(0))
+    }
+
+    pub fn load_all_bookmarks(&self) -> Result<Vec<ChromeBookmark>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, url, date_added, folder_path, browser, profile, guid
+             FROM bookmarks
+             ORDER BY rowid",
+        )?;
+
+        let rows = stmt.query_map([], bookmark_from_row)?;
+        rows.collect::<Result<Vec<_>>>()
+    }
+
+    /// 合并时落选的全部其他来源版本
+    /// 按 id 查找书签；合并时落选的来源版本也能查到
+    pub fn get_bookmark(&self, id: &str) -> Result<Option<ChromeBookmark>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, url, date_added, folder_path, browser, profile, guid
+             FROM bookmarks WHERE id = ?1
+             UNION ALL
+             SELECT id, name, url, date_added, folder_path, browser, profile, guid
+             FROM bookmark_variants WHERE id = ?1
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query_map(params![id], bookmark_from_row)?;
+        rows.next().transpose()
+    }
+
+    /// 先按书签 id 查找，找不到时按 `tag_key`（默认 uid 模式下搜索结果的 uid）查找，
+    /// 同一 URL 有多个书签时取索引中的第一个
+    pub fn find_bookmark(&self, id_or_key: &str) -> Result<Option<ChromeBookmark>> {
+        if let Some(bookmark) = self.get_bookmark(id_or_key)? {
+            return Ok(Some(bookmark));
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, url, date_added, folder_path, browser, profile, guid
+             FROM bookmarks WHERE tag_key = ?1
+             ORDER BY rowid
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query_map(params![id_or_key], bookmark_from_row)?;
+        rows.next().transpose()
+    }
+
+    pub fn load_all_variants(&self) -> Result<Vec<ChromeBookmark>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, url, date_added, folder_path, browser, profile, guid
+             FROM bookmark_variants
+             ORDER BY rowid",
+        )?;
+
+        let rows = stmt.query_map([], bookmark_from_row)?;
+        rows.collect::<Result<Vec<_>>>()
+    }
+
+    /// 按索引顺序列出书签；挂载了标签库时置顶书签在前
+    pub fn list_bookmarks(&self, limit: usize) -> Result<Vec<ChromeBookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, name, url, date_added, folder_path, browser, profile, guid
+             FROM bookmarks
+             ORDER BY {}rowid
+             LIMIT ?1",
+            self.pinned_first_sql("")?
+        ))?;
+
+        let rows = stmt.query_map(params![limit as i64], bookmark_from_row)?;
+        rows.collect::<Result<Vec<_>>>()
+    }
+
+    pub fn list_bookmarks_filtered(
+        &self,
+        filters: &BookmarkFilters,
+        limit: usize,
+    ) -> Result<Vec<ChromeBookmark>> {
+        self.select_filtered(filters, Some(limit))
+    }
+
+    /// 只按过滤条件（目录、来源、标签）在 SQL 中预筛，不限数量；
+    /// 用于模糊搜索或 FTS 不可用时缩小候选集，代替加载全部书签
+    pub fn load_bookmarks_filtered(
+        &self,
+        filters: &BookmarkFilters,
+    ) -> Result<Vec<ChromeBookmark>> {
+        self.select_filtered(filters, None)
+    }
+
+    /// 估算把符合过滤条件的书签全部加载到内存所需的字节数
+    /// 名称、URL、目录另有小写副本，按两份计
+    pub fn estimate_load_bytes(&self, filters: &BookmarkFilters) -> Result<u64> {
+        let (clauses, values) = filter_clauses(filters, "");
+        let sql = format!(
+            "SELECT COUNT(*), COALESCE(SUM(
+                2 * (length(CAST(name AS BLOB)) + length(CAST(url AS BLOB))
+                     + ifnull(length(CAST(folder_path AS BLOB)), 0))
+                + length(CAST(id AS BLOB)) + length(CAST(date_added AS BLOB))
+                + length(CAST(browser AS BLOB)) + length(CAST(profile AS BLOB))
+                + ifnull(length(CAST(guid AS BLOB)), 0)), 0)
+             FROM bookmarks
+             WHERE 1=1{}",
+            clauses
+        );
+        let (count, text_bytes) =
+            self.conn
+                .query_row(&sql, params_from_iter(values.iter()), |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+                })?;
+        Ok(count as u64 * BOOKMARK_OVERHEAD_BYTES + text_bytes as u64)
+    }
+
+    /// 按 rowid 分批读取符合过滤条件的书签，每批交给回调；同一时刻内存中只有一批
+    pub fn for_each_chunk(
+        &self,
+        filters: &BookmarkFilters,
+        chunk_size: usize,
+        mut f: impl FnMut(Vec<ChromeBookmark>),
+    ) -> Result<()> {
+        let (clauses, values) = filter_clauses(filters, "");
+        let sql = format!(
+            "SELECT id, name, url, date_added, folder_path, browser, profile, guid, rowid
+             FROM bookmarks
+             WHERE rowid > ?{}
+             ORDER BY rowid LIMIT ?",
+            clauses
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let chunk_param = chunk_size.max(1) as i64;
+        let mut after = 0i64;
+        loop {
+            let mut params: Vec<&dyn ToSql> = vec![&after];
+            for value in &values {
+                params.push(value as &dyn ToSql);
+            }
+            params.push(&chunk_param);
+
+            let rows = stmt
+                .query_map(params.as_slice(), |row| {
+                    Ok((bookmark_from_row(row)?, row.get::<_, i64>(8)?))
+                })?
+                .collect::<Result<Vec<_>>>()?;
+            let Some(&(_, last)) = rows.last() else {
+                return Ok(());
+            };
+            let full = rows.len() as i64 == chunk_param;
+            f(rows.into_iter().map(|(bookmark, _)| bookmark).collect());
+            if !full {
+                return Ok(());
+            }
+            after = last;
+        }
+    }
+
+    /// 以 `tagdb` 名挂载标签库，使过滤条件中的标签能以子查询完成；已挂载时直接返回
+    pub fn attach_tags(&self, tags_db: &Path) -> Result<()> {
+        if self.is_attached("tagdb")? {
+            return Ok(());
+        }
+        crate::db_crypt::attach(&self.conn, tags_db, "tagdb", crate::db_crypt::key())
+    }
+
+    /// 以 `contentdb` 名挂载网页正文库（见 `ContentStore`），FTS 搜索结果不足时补充正文命中的书签
+    pub fn attach_content(&self, content_db: &Path) -> Result<()> {
+        if self.is_attached("contentdb")? {
+            return Ok(());
+        }
+        crate::db_crypt::attach(&self.conn, content_db, "contentdb", crate::db_crypt::key())
+    }
+
+    /// 以 `usagedb` 名挂载打开记录库，供 `SortOrder::Frecency` 排序
+    pub fn attach_usage(&self, usage_db: &Path) -> Result<()> {
+        if self.is_attached("usagedb")? {
+            return Ok(());
+        }
+        crate::db_crypt::attach(&self.conn, usage_db, "usagedb", crate::db_crypt::key())
+    }
+
+    fn is_attached(&self, schema: &str) -> Result<bool> {
+        let mut stmt = self.conn.prepare("SELECT name FROM pragma_database_list")?;
+        let names = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        for name in names {
+            if name? == schema {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// 置顶书签（见 `TagManager::pin`）的 id；未挂载标签库时为空
+    pub fn pinned_ids(&self) -> Result<HashSet<String>> {
+        if !self.is_attached("tagdb")? {
+            return Ok(HashSet::new());
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM bookmarks WHERE tag_key IN (SELECT bookmark_key FROM tagdb.pinned)",
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// ORDER BY 开头让置顶书签排在最前的排序项（带结尾逗号）；未挂载标签库时为空串
+    fn pinned_first_sql(&self, prefix: &str) -> Result<String> {
+        Ok(if self.is_attached("tagdb")? {
+            format!(
+                "{}tag_key IN (SELECT bookmark_key FROM tagdb.pinned) DESC, ",
+                prefix
+            )
+        } else {
+            String::new()
+        })
+    }
+
+    /// FTS 结果的排序分数：bm25，挂载了标签库时置顶书签乘以 `PINNED_BOOST_FACTOR`
+    fn fts_rank_sql(&self) -> Result<String> {
+        Ok(if self.is_attached("tagdb")? {
+            format!(
+                "bm25(bookmarks_fts) * CASE WHEN b.tag_key IN (SELECT bookmark_key FROM tagdb.pinned)
+                    THEN {:.1} ELSE 1 END",
+                PINNED_BOOST_FACTOR
+            )
+        } else {
+            "bm25(bookmarks_fts)".to_string()
+        })
+    }
+
+    /// 按指定顺序返回匹配的书签：关键词只用 FTS 过滤，排序交给 ORDER BY
+    /// 有关键词但 FTS 不可用时返回 None，由调用方回退到内存排序
+    pub fn search_sorted(
+        &self,
+        query: &str,
+        filters: &BookmarkFilters,
+        sort: SortOrder,
+        limit: usize,
+    ) -> Result<Option<Vec<ChromeBookmark>>> {
+        let Some((clauses, filter_values)) = self.keyword_filter_clauses(query, filters) else {
+            return Ok(None);
+        };
+
+        let mut sql = String::from(
+            "SELECT b.id, b.name, b.url, b.date_added, b.folder_path, b.browser, b.profile, b.guid
+             FROM bookmarks b
+             WHERE 1=1",
+        );
+        sql.push_str(&clauses);
+        sql.push_str(" ORDER BY ");
+        sql.push_str(&sort_order_sql(sort));
+        sql.push_str(" LIMIT ?");
+
+        let mut values: Vec<&dyn ToSql> = Vec::new();
+        for value in &filter_values {
+            values.push(value as &dyn ToSql);
+        }
+        let limit_param = limit as i64;
+        values.push(&limit_param);
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), bookmark_from_row)?;
+        rows.collect::<Result<Vec<_>>>().map(Some)
+    }
+
+    /// 通过本 workflow 打开次数最多的书签及其次数，只含打开过的；同次数时最近打开的在前。
+    /// 需先 `attach_usage`；有关键词但 FTS 不可用时返回 None
+    pub fn most_opened(
+        &self,
+        query: &str,
+        filters: &BookmarkFilters,
+        limit: usize,
+    ) -> Result<Option<Vec<(ChromeBookmark, u64)>>> {
+        let Some((clauses, filter_values)) = self.keyword_filter_clauses(query, filters) else {
+            return Ok(None);
+        };
+
+        let mut sql = String::from(
+            "SELECT b.id, b.name, b.url, b.date_added, b.folder_path, b.browser, b.profile, b.guid,
+                    u.count
+             FROM bookmarks b
+             JOIN usagedb.opens u ON u.url = b.url
+             WHERE u.count > 0",
+        );
+        sql.push_str(&clauses);
+        sql.push_str(" ORDER BY u.count DESC, u.last_opened DESC, b.rowid LIMIT ?");
+
+        let mut values: Vec<&dyn ToSql> = Vec::new();
+        for value in &filter_values {
+            values.push(value as &dyn ToSql);
+        }
+        let limit_param = limit as i64;
+        values.push(&limit_param);
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            Ok((bookmark_from_row(row)?, row.get::<_, i64>(8)? as u64))
+        })?;
+        rows.collect::<Result<Vec<_>>>().map(Some)
+    }
+
+    /// 关键词（FTS）与过滤条件拼成的 `AND …` 子句及参数，列名带 `b.` 前缀；
+    /// 有关键词但 FTS 不可用时返回 None
+    fn keyword_filter_clauses(
+        &self,
+        query: &str,
+        filters: &BookmarkFilters,
+    ) -> Option<(String, Vec<String>)> {
+        let mut sql = String::new();
+        let mut values = Vec::new();
+        if !query.trim().is_empty() {
+            let fts_query = build_fts_query(query, &self.tokenize.trigram_columns())
+                .filter(|_| self.fts_enabled)?;
+            sql.push_str(
+                " AND b.id IN (SELECT bookmark_id FROM bookmarks_fts WHERE bookmarks_fts MATCH ?)",
+            );
+            values.push(fts_query);
+        }
+        let (clauses, filter_values) = filter_clauses(filters, "b.");
+        sql.push_str(&clauses);
+        values.extend(filter_values);
+        Some((sql, values))
+    }
+
+    fn select_filtered(
+        &self,
+        filters: &BookmarkFilters,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChromeBookmark>> {
+        let (clauses, values) = filter_clauses(filters, "");
+        if values.is_empty() {
+            return match limit {
+                Some(limit) => self.list_bookmarks(limit),
+                None => self.load_all_bookmarks(),
+            };
+        }
+
+        let mut sql = String::from(
+            "SELECT id, name, url, date_added, folder_path, browser, profile, guid
+             FROM bookmarks
+             WHERE 1=1",
+        );
+        sql.push_str(&clauses);
+        sql.push_str(&format!(
+            " ORDER BY {}rowid LIMIT ?",
+            self.pinned_first_sql("")?
+        ));
+
+        let mut params: Vec<&dyn ToSql> = Vec::new();
+        for value in &values {
+            params.push(value as &dyn ToSql);
+        }
+
+        // SQLite 中负数 LIMIT 表示不限制
+        let limit_param = limit.map_or(-1, |limit| limit as i64);
+        params.push(&limit_param);
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params.as_slice(), bookmark_from_row)?;
+        rows.collect::<Result<Vec<_>>>()
+    }
+
+    pub fn search_bookmark_candidates_fts(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Option<Vec<ChromeBookmark>>> {
+        self.search_bookmarks_fts(query, limit)
+    }
+
+    pub fn search_bookmark_candidates_fts_filtered(
+        &self,
+        query: &str,
+        filters: &BookmarkFilters,
+        limit: usize,
+    ) -> Result<Option<Vec<ChromeBookmark>>> {
+        self.search_bookmarks_fts_filtered(query, filters, limit)
+    }
+
+    pub fn search_bookmarks_fts(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Option<Vec<ChromeBookmark>>> {
+        if !self.fts_enabled {
+            return Ok(None);
+        }
+
+        let fts_query = match build_fts_query(query, &self.tokenize.trigram_columns()) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT b.id, b.name, b.url, b.date_added, b.folder_path, b.browser, b.profile, b.guid,
+                    {} AS score
+             FROM bookmarks_fts
+             JOIN bookmarks b ON b.id = bookmarks_fts.bookmark_id
+             WHERE bookmarks_fts MATCH ?1
+             ORDER BY score
+             LIMIT ?2",
+            self.fts_rank_sql()?
+        ))?;
+
+        let rows = stmt.query_map(
+            params![fts_query, self.fts_candidate_limit(limit) as i64],
+            ranked_bookmark_from_row,
+        )?;
+        let results = self.apply_domain_boosts(rows.collect::<Result<Vec<_>>>()?, limit);
+        let results =
+            self.with_substring_hits(results, query, &BookmarkFilters::default(), limit)?;
+        let results = self.with_note_hits(results, query, &BookmarkFilters::default(), limit)?;
+        self.with_content_hits(results, query, &BookmarkFilters::default(), limit)
+            .map(Some)
+    }
+
+    pub fn search_bookmarks_fts_filtered(
+        &self,
+        query: &str,
+        filters: &BookmarkFilters,
+        limit: usize,
+    ) -> Result<Option<Vec<ChromeBookmark>>> {
+        if !self.fts_enabled {
+            return Ok(None);
+        }
+
+        let fts_query = match build_fts_query(query, &self.tokenize.trigram_columns()) {
             Some(value) => value,
             None => return Ok(None),
         };
 
-        let patterns: Vec<String> = folder_filters
+        let (clauses, filter_values) = filter_clauses(filters, "b.");
+        if filter_values.is_empty() {
+            return self.search_bookmarks_fts(query, limit);
+        }
+
+        let mut sql = format!(
+            "SELECT b.id, b.name, b.url, b.date_added, b.folder_path, b.browser, b.profile, b.guid,
+                    {} AS score
+             FROM bookmarks_fts
+             JOIN bookmarks b ON b.id = bookmarks_fts.bookmark_id
+             WHERE bookmarks_fts MATCH ?",
+            self.fts_rank_sql()?
+        );
+        sql.push_str(&clauses);
+        sql.push_str(" ORDER BY score LIMIT ?");
+
+        let mut values: Vec<&dyn ToSql> = Vec::new();
+        values.push(&fts_query);
+        for value in &filter_values {
+            values.push(value as &dyn ToSql);
+        }
+        let limit_param = self.fts_candidate_limit(limit) as i64;
+        values.push(&limit_param);
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), ranked_bookmark_from_row)?;
+        let results = self.apply_domain_boosts(rows.collect::<Result<Vec<_>>>()?, limit);
+
+        let results = self.with_substring_hits(results, query, filters, limit)?;
+        let results = self.with_note_hits(results, query, filters, limit)?;
+        self.with_content_hits(results, query, filters, limit)
+            .map(Some)
+    }
+
+    fn fts_candidate_limit(&self, limit: usize) -> usize {
+        if self.boost_domains.is_empty() {
+            limit
+        } else {
+            limit.saturating_mul(DOMAIN_BOOST_CANDIDATE_MULTIPLIER)
+        }
+    }
+
+    /// bm25 排序后的加权：偏好域名的分数乘以系数后重新排序（稳定排序，其余保持原顺序）
+    fn apply_domain_boosts(
+        &self,
+        mut ranked: Vec<(ChromeBookmark, f64)>,
+        limit: usize,
+    ) -> Vec<ChromeBookmark> {
+        if !self.boost_domains.is_empty() {
+            for (bookmark, rank) in &mut ranked {
+                if url_in_domains(&bookmark.url, &self.boost_domains) {
+                    *rank *= DOMAIN_BOOST_FACTOR;
+                }
+            }
+            ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+        }
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(bookmark, _)| bookmark)
+            .collect()
+    }
+
+    /// 词前缀匹配不足 `limit` 条时，用 trigram 索引补充标题或 URL 中间命中的书签
+    /// （如 `lang` 命中 `rust-lang.org`、`模式` 命中 `设计模式`），排在前缀结果之后
+    fn with_substring_hits(
+        &self,
+        mut results: Vec<ChromeBookmark>,
+        query: &str,
+        filters: &BookmarkFilters,
+        limit: usize,
+    ) -> Result<Vec<ChromeBookmark>> {
+        if !self.substring_enabled || results.len() >= limit {
+            return Ok(results);
+        }
+        let extra = self.search_bookmarks_substring(query, filters, limit)?;
+        let seen: HashSet<String> = results.iter().map(|b| b.id.clone()).collect();
+        results.extend(
+            extra
+                .into_iter()
+                .filter(|bookmark| !seen.contains(&bookmark.id))
+                .take(limit - results.len()),
+        );
+        Ok(results)
+    }
+
+    /// 标题与 URL 命中不足 `limit` 条时，补充备注命中的书签（见 `TagManager::set_note`）；未挂载标签库时原样返回
+    fn with_note_hits(
+        &self,
+        results: Vec<ChromeBookmark>,
+        query: &str,
+        filters: &BookmarkFilters,
+        limit: usize,
+    ) -> Result<Vec<ChromeBookmark>> {
+        self.with_attached_fts_hits(
+            results,
+            query,
+            filters,
+            limit,
+            "tagdb.note_text",
+            "bookmark_key",
+        )
+    }
+
+    /// 标题、URL 与备注命中仍不足 `limit` 条时，补充网页正文（标题、描述、小标题）命中的书签；
+    /// 未挂载正文库时原样返回
+    fn with_content_hits(
+        &self,
+        results: Vec<ChromeBookmark>,
+        query: &str,
+        filters: &BookmarkFilters,
+        limit: usize,
+    ) -> Result<Vec<ChromeBookmark>> {
+        self.with_attached_fts_hits(
+            results,
+            query,
+            filters,
+            limit,
+            "contentdb.page_text",
+            "url_key",
+        )
+    }
+
+    /// 在挂载库的 FTS 表 `table`（`库名.表名`）中按 bm25 找出命中的书签，追加在已有结果之后；
+    /// 该表以 `key_column` 与书签表的 `tag_key` 关联
+    fn with_attached_fts_hits(
+        &self,
+        mut results: Vec<ChromeBookmark>,
+        query: &str,
+        filters: &BookmarkFilters,
+        limit: usize,
+        table: &str,
+        key_column: &str,
+    ) -> Result<Vec<ChromeBookmark>> {
+        let (schema, name) = table.split_once('.').unwrap_or(("main", table));
+        if results.len() >= limit || !self.is_attached(schema)? {
+            return Ok(results);
+        }
+        let Some(expr) = QueryExpr::parse(query) else {
+            return Ok(results);
+        };
+        // 先取任一关键词命中该表的书签并按 bm25 排序，再按查询树筛选：关键词须命中该表，排除词看书签本身
+        let alternatives: Vec<String> = expr
+            .terms()
+            .into_iter()
+            .map(|term| fts_term_parts(term, &[]))
+            .filter(|parts| !parts.is_empty())
+            .map(|parts| format!("({})", parts.join(" ")))
+            .collect();
+        if alternatives.is_empty() {
+            return Ok(results);
+        }
+        let term_clause = |term: &str| {
+            let parts = fts_term_parts(term, &[]);
+            (!parts.is_empty()).then(|| {
+                (
+                    format!("{name}.rowid IN (SELECT rowid FROM {table} WHERE {name} MATCH ?)"),
+                    vec![parts.join(" ")],
+                )
+            })
+        };
+        let (condition, mut condition_values) = match query_condition(&expr, &term_clause) {
+            Some((sql, values)) => (format!(" AND {}", sql), values),
+            None => (String::new(), Vec::new()),
+        };
+
+        let (clauses, filter_values) = filter_clauses(filters, "b.");
+        condition_values.extend(filter_values);
+        let sql = format!(
+            "SELECT b.id, b.name, b.url, b.date_added, b.folder_path, b.browser, b.profile, b.guid
+             FROM {table}
+             JOIN bookmarks b ON b.tag_key = {name}.{key_column}
+             WHERE {name} MATCH ?{condition}{clauses}
+             ORDER BY bm25({name})
+             LIMIT ?",
+        );
+        let fts_query = alternatives.join(" OR ");
+        let mut values: Vec<&dyn ToSql> = vec![&fts_query];
+        for value in &condition_values {
+            values.push(value as &dyn ToSql);
+        }
+        let limit_param = limit as i64;
+        values.push(&limit_param);
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), bookmark_from_row)?;
+        let seen: HashSet<String> = results.iter().map(|b| b.id.clone()).collect();
+        for bookmark in rows {
+            let bookmark = bookmark?;
+            if results.len() >= limit {
+                break;
+            }
+            if !seen.contains(&bookmark.id) {
+                results.push(bookmark);
+            }
+        }
+        Ok(results)
+    }
+
+    /// 关键词以子串形式出现在标题或 URL 中（不区分大小写），按查询树组合（见 `query_condition`）；
+    /// 只有排除词时不查询
+    pub fn search_bookmarks_substring(
+        &self,
+        query: &str,
+        filters: &BookmarkFilters,
+        limit: usize,
+    ) -> Result<Vec<ChromeBookmark>> {
+        if !self.substring_enabled {
+            return Ok(Vec::new());
+        }
+        let Some(expr) = QueryExpr::parse(query).filter(|expr| !expr.terms().is_empty()) else {
+            return Ok(Vec::new());
+        };
+        let Some((condition, mut values)) = query_condition(&expr, &substring_clause) else {
+            return Ok(Vec::new());
+        };
+
+        let (clauses, filter_values) = filter_clauses(filters, "b.");
+        values.extend(filter_values);
+        let sql = format!(
+            "SELECT b.id, b.name, b.url, b.date_added, b.folder_path, b.browser, b.profile, b.guid
+             FROM bookmarks_trigram t
+             JOIN bookmarks b ON b.id = t.bookmark_id
+             WHERE {condition}{clauses}
+             ORDER BY b.rowid LIMIT ?",
+        );
+        let mut params: Vec<&dyn ToSql> = Vec::new();
+        for value in &values {
+            params.push(value as &dyn ToSql);
+        }
+        let limit_param = limit as i64;
+        params.push(&limit_param);
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(params), bookmark_from_row)?;
+        rows.collect()
+    }
+
+    /// 记录最近一次成功刷新的完成时间与耗时
+    pub fn record_refresh(&self, finished_at: i64, duration_ms: u64) -> Result<()> {
+        self.set_meta("last_refresh_at", &finished_at.to_string())?;
+        self.set_meta("last_refresh_ms", &duration_ms.to_string())
+    }
+
+    /// 重写数据库文件回收空闲页，并截断 WAL；完成时间写入 meta
+    pub fn vacuum(&self, now: i64) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        self.set_meta("last_vacuum", &now.to_string())?;
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+    }
+
+    /// 页数与空闲页、各表行数、FTS 占用，以及上次 VACUUM 与刷新的记录
+    pub fn health(&self) -> Result<IndexHealth> {
+        let pragma = |name: &str| -> Result<u64> {
+            self.conn
+                .query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0))
+                .map(|value| value.max(0) as u64)
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT name, sql LIKE 'CREATE VIRTUAL TABLE%' FROM sqlite_master
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+             ORDER BY name",
+        )?;
+        let schema = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        let virtual_tables: Vec<&str> = schema
+            .iter()
+            .filter(|(_, is_virtual)| *is_virtual)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let is_shadow = |name: &str| {
+            virtual_tables.iter().any(|table| {
+                name.strip_prefix(table)
+                    .is_some_and(|rest| rest.starts_with('_'))
+            })
+        };
+
+        let mut tables = Vec::new();
+        let mut fts_names = Vec::new();
+        for (name, is_virtual) in &schema {
+            if *is_virtual || is_shadow(name) {
+                fts_names.push(name.as_str());
+            }
+            if is_shadow(name) {
+                continue;
+            }
+            let rows: i64 = self.conn.query_row(
+                &format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")),
+                [],
+                |row| row.get(0),
+            )?;
+            tables.push((name.clone(), rows.max(0) as u64));
+        }
+
+        // dbstat 是可选编译的虚拟表，不可用时只是少一项指标
+        let fts_bytes = self
+            .conn
+            .prepare("SELECT name, SUM(pgsize) FROM dbstat GROUP BY name")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+                })?
+                .collect::<Result<Vec<_>>>()
+            })
+            .ok()
+            .map(|sizes| {
+                sizes
+                    .into_iter()
+                    .filter(|(name, _)| fts_names.contains(&name.as_str()))
+                    .map(|(_, bytes)| bytes.max(0) as u64)
+                    .sum()
+            });
+
+        let meta_number = |key: &str| -> Result<Option<i64>> {
+            Ok(self.get_meta(key)?.and_then(|value| value.parse().ok()))
+        };
+        Ok(IndexHealth {
+            page_size: pragma("page_size")?,
+            page_count: pragma("page_count")?,
+            freelist_count: pragma("freelist_count")?,
+            tables,
+            fts_bytes,
+            last_vacuum: meta_number("last_vacuum")?,
+            last_refresh_at: meta_number("last_refresh_at")?,
+            last_refresh_ms: meta_number("last_refresh_ms")?.map(|ms| ms.max(0) as u64),
+        })
+    }
+
+    fn get_meta(&self, key: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare("SELECT value FROM meta WHERE key = ?1")?;
+        let mut rows = stmt.query(params![key])?;
+        if let Some(row) = rows.next()? {
+            let value: String = row.get(0)?;
+            return Ok(Some(value));
+        }
+        Ok(None)
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+}
+
+/// 旧版 bookmarks 表没有来源列或标签键列；索引可重建，直接删除让其按新结构重建
+/// 索引库的结构迁移，按版本号升序；新增列或表时在末尾追加一步，不要改动已发布的步骤
+const INDEX_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        apply: create_index_tables,
+    },
+    Migration {
+        version: 2,
+        apply: add_guid_columns,
+    },
+    Migration {
+        version: 3,
+        apply: add_added_at_column,
+    },
+    Migration {
+        version: 4,
+        apply: add_normalized_url_column,
+    },
+    Migration {
+        version: 5,
+        apply: rekey_tag_keys_by_url_hash,
+    },
+];
+
+/// 版本 1：建表。引入版本号之前的库也从这里开始，缺少来源列的旧 bookmarks 表在此删除重建
+fn create_index_tables(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    migrate_bookmarks_table(conn)?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS bookmarks (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            url TEXT NOT NULL,
+            date_added TEXT NOT NULL,
+            folder_path TEXT,
+            browser TEXT NOT NULL DEFAULT '',
+            profile TEXT NOT NULL DEFAULT '',
+            guid TEXT,
+            tag_key TEXT NOT NULL DEFAULT ''
+        )",
+        [],
+    )?;
+
+    // 通过 `add` 保存的本地书签；刷新重建索引时与浏览器书签一起写入 bookmarks
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS local_bookmarks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            folder_path TEXT,
+            date_added TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // `delete` 移入回收站的书签：刷新时跳过这些 id，`restore` 时按保存的内容放回索引
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS trash (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            url TEXT NOT NULL,
+            date_added TEXT NOT NULL,
+            folder_path TEXT,
+            browser TEXT NOT NULL,
+            profile TEXT NOT NULL,
+            deleted_at INTEGER NOT NULL,
+            guid TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS bookmark_variants (
+            winner_id TEXT NOT NULL,
+            id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            url TEXT NOT NULL,
+            date_added TEXT NOT NULL,
+            folder_path TEXT,
+            browser TEXT NOT NULL,
+            profile TEXT NOT NULL,
+            guid TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_bookmark_variants_winner ON bookmark_variants(winner_id)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sources (
+            browser TEXT NOT NULL,
+            profile TEXT NOT NULL,
+            path TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            bookmark_count INTEGER NOT NULL,
+            refreshed_at INTEGER NOT NULL,
+            PRIMARY KEY (browser, profile)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// 版本 2：回收站与合并变体记录书签 GUID
+fn add_guid_columns(conn: &Connection) -> Result<()> {
+    add_missing_column(conn, "trash", "guid", "TEXT")?;
+    add_missing_column(conn, "bookmark_variants", "guid", "TEXT")
+}
+
+/// 版本 3：date_added 换算为 Unix 秒存入 added_at 并建索引，按添加时间排序时不必逐行换算
+fn add_added_at_column(conn: &Connection) -> Result<()> {
+    add_missing_column(conn, "bookmarks", "added_at", "INTEGER NOT NULL DEFAULT 0")?;
+    conn.execute(
+        &format!(
+            "UPDATE bookmarks SET added_at = MAX({}, 0)",
+            crate::bookmark::date_added_unix_secs_sql("date_added")
+        ),
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_bookmarks_added_at ON bookmarks(added_at)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// 版本 4：在原始 URL 之外保存 `canonical_url` 规范化后的 URL，按 URL 查找时不受跟踪参数、
+/// 主机大小写与 punycode 写法影响
+fn add_normalized_url_column(conn: &Connection) -> Result<()> {
+    add_missing_column(
+        conn,
+        "bookmarks",
+        "normalized_url",
+        "TEXT NOT NULL DEFAULT ''",
+    )?;
+    let rows: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, url FROM bookmarks")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_>>()?
+    };
+    let mut update = conn.prepare("UPDATE bookmarks SET normalized_url = ?1 WHERE id = ?2")?;
+    for (id, url) in &rows {
+        update.execute(params![canonical_url(url), id])?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_bookmarks_normalized_url ON bookmarks(normalized_url)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// 版本 5：`tag_key` 改为按规范 URL 摘要计算，按新规则重算；并清掉刷新指纹，
+/// 下次刷新时标签库中旧的 GUID 键随之换成新键（见 `TagManager::migrate_legacy_keys`）
+fn rekey_tag_keys_by_url_hash(conn: &Connection) -> Result<()> {
+    let rows: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, url FROM bookmarks")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_>>()?
+    };
+    let mut update = conn.prepare("UPDATE bookmarks SET tag_key = ?1 WHERE id = ?2")?;
+    for (id, url) in &rows {
+        update.execute(params![bookmark_key(url), id])?;
+    }
+    conn.execute(
+        "DELETE FROM meta WHERE key IN ('bookmarks_fingerprint', 'source_fingerprints')",
+        [],
+    )?;
+    Ok(())
+}
+
+/// 删除可由浏览器书签重新生成的表与元数据并把版本号归零，下次刷新时完整重建
+fn reset_derived_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "DROP TABLE IF EXISTS bookmarks;
+         DROP TABLE IF EXISTS bookmark_variants;
+         DROP TABLE IF EXISTS sources;
+         DROP TABLE IF EXISTS meta;",
+    )?;
+    let _ = conn.execute_batch("DROP TABLE IF EXISTS bookmarks_fts;");
+    let _ = conn.execute_batch("DROP TABLE IF EXISTS bookmarks_trigram;");
+    schema::set_user_version(conn, 0)
+}
+
+fn migrate_bookmarks_table(conn: &Connection) -> Result<()> {
+    let columns = table_columns(conn, "bookmarks")?;
+    if columns.is_empty()
+        || ["profile", "tag_key"]
+            .iter()
+            .all(|wanted| columns.iter().any(|column| column == wanted))
+    {
+        return Ok(());
+    }
+
+    conn.execute_batch("DROP TABLE bookmarks;")?;
+    let _ = conn.execute_batch("DROP TABLE IF EXISTS bookmarks_fts;");
+    // 来源指纹一并清除，否则增量刷新会把未变化的来源当作已在（已删除的）表中
+    conn.execute(
+        "DELETE FROM meta WHERE key IN ('bookmarks_fingerprint', 'source_fingerprints')",
+        [],
+    )?;
+    Ok(())
+}
+
+/// 给已存在的表补上新增的可空列（回收站、来源版本等不可重建的数据不能整表删除）
+fn add_missing_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    if table_columns(conn, table)?
+        .iter()
+        .any(|name| name == column)
+    {
+        return Ok(());
+    }
+    conn.execute_batch(&format!(
+        "ALTER TABLE {} ADD COLUMN {} {};",
+        table, column, decl
+    ))
+}
+
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    columns.collect()
+}
+
+/// frecency：打开次数除以距上次打开的周数（+1），需先 `attach_usage`
+/// added：各来源的 date_added 单位不同，按入库时换算好的 Unix 秒 added_at 比较
+fn sort_order_sql(sort: SortOrder) -> String {
+    match sort {
+        SortOrder::Relevance => "b.rowid".to_string(),
+        SortOrder::Added => "b.added_at DESC, b.rowid".to_string(),
+        SortOrder::Title => "b.name COLLATE NOCASE, b.rowid".to_string(),
+        SortOrder::Url => "b.url, b.rowid".to_string(),
+        SortOrder::Frecency => {
+            "IFNULL((SELECT u.count / (1.0 + (unixepoch() - u.last_opened) / 604800.0)
+                     FROM usagedb.opens u WHERE u.url = b.url), 0) DESC, b.rowid"
+                .to_string()
+        }
+    }
+}
+
+/// 旧版 FTS 表缺少 compact 列时删除，由调用方重建并回填；返回是否需要回填
+fn migrate_fts_table(conn: &Connection) -> Result<bool> {
+    let mut stmt = conn.prepare("PRAGMA table_info(bookmarks_fts)")?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>>>()?;
+
+    if columns.is_empty() || columns.iter().any(|column| column == "compact") {
+        return Ok(false);
+    }
+    conn.execute_batch("DROP TABLE bookmarks_fts;")?;
+    Ok(true)
+}
+
+/// compact 列：标题逐词与整体去标点后的形式，加上主机名从每一级开始去标点的形式，
+/// 前缀查询 `rustlang` 可命中 `users.rust-lang.org`
+fn compact_fts_text(bookmark: &ChromeBookmark) -> String {
+    let mut parts = compact_name_forms(&bookmark.name);
+    parts.extend(compact_host_forms(&bookmark.url));
+    parts.join(" ")
+}
+
+fn create_bookmark_indexes(conn: &Connection) -> Result<()> {
+    for (name, target) in BOOKMARK_INDEXES {
+        conn.execute(
+            &format!("CREATE INDEX IF NOT EXISTS {} ON {}", name, target),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// `head VALUES (?, …), (?, …)`：`rows` 行、每行 `columns` 个占位符
+fn multi_row_insert_sql(head: &str, columns: usize, rows: usize) -> String {
+    let row = format!("({})", vec!["?"; columns].join(", "));
+    format!("{} VALUES {}", head, vec![row; rows].join(", "))
+}
+
+/// 数据库因只读、无权限或磁盘已满而无法打开或写入；此时可改用内存索引
+pub fn is_unwritable_error(err: &rusqlite::Error) -> bool {
+    matches!(
+        err,
+        rusqlite::Error::SqliteFailure(failure, _)
+            if matches!(
+                failure.code,
+                rusqlite::ErrorCode::ReadOnly
+                    | rusqlite::ErrorCode::CannotOpen
+                    | rusqlite::ErrorCode::DiskFull
+                    | rusqlite::ErrorCode::PermissionDenied
+            )
+    )
+}
+
+/// 目录（LIKE）与标签（子查询）全部满足，浏览器、profile（IN）满足其一
+fn filter_clauses(filters: &BookmarkFilters, prefix: &str) -> (String, Vec<String>) {
+    let mut sql = String::new();
+    let mut values = Vec::new();
+
+    for pattern in filters
+        .folders
+        .iter()
+        .filter_map(|raw| folder_filter_to_like_pattern(raw))
+    {
+        sql.push_str(&format!(
+            " AND lower(ifnull({}folder_path, '')) LIKE ? ESCAPE '\\'",
+            prefix
+        ));
+        values.push(pattern);
+    }
+
+    for tag in &filters.tags {
+        sql.push_str(&format!(
+            " AND {}tag_key IN (
+                SELECT bt.bookmark_key FROM tagdb.bookmark_tags bt
+                JOIN tagdb.tags t ON t.id = bt.tag_id
+                WHERE t.name = ? COLLATE NOCASE)",
+            prefix
+        ));
+        values.push(tag.clone());
+    }
+
+    for (column, wanted) in [
+        ("browser", &filters.browsers),
+        ("profile", &filters.profiles),
+    ] {
+        if wanted.is_empty() {
+            continue;
+        }
+        // 合并时落选的其他来源版本也算命中，避免按来源过滤时丢结果
+        let placeholders = vec!["?"; wanted.len()].join(", ");
+        sql.push_str(&format!(
+            " AND (lower({prefix}{column}) IN ({placeholders}) OR {prefix}id IN (
+                SELECT winner_id FROM bookmark_variants WHERE lower({column}) IN ({placeholders})))",
+        ));
+        let lowered: Vec<String> = wanted.iter().map(|value| value.to_lowercase()).collect();
+        values.extend(lowered.iter().cloned());
+        values.extend(lowered);
+    }
+
+    (sql, values)
+}
+
+/// `trigram_columns` 附加了三字符片段：够长的词额外以“片段全部出现在这些列中”作为子串匹配
+///
+/// 查询树（见 `QueryExpr`）直接写成 FTS5 表达式：`OR` 与括号原样保留，
+/// 排除词（`-词`）在所在的组内以 `NOT` 去掉名称、URL 或目录中有以该词开头的词的书签（见 `fts_exclusion`）；
+/// 某个分支只有排除词（FTS5 的 `NOT` 不能单独使用）或整个查询没有可用于匹配的词时返回 None，
+/// 由调用方回退到内存过滤
+fn build_fts_query(query: &str, trigram_columns: &[&str]) -> Option<String> {
+    fts_expr(&QueryExpr::parse(query)?, trigram_columns)
+}
+
+fn fts_expr(expr: &QueryExpr, trigram_columns: &[&str]) -> Option<String> {
+    match expr {
+        QueryExpr::Or(branches) => {
+            let branches = branches
+                .iter()
+                .map(|branch| fts_expr(branch, trigram_columns))
+                .collect::<Option<Vec<_>>>()?;
+            Some(
+                branches
+                    .iter()
+                    .map(|branch| format!("({})", branch))
+                    .collect::<Vec<_>>()
+                    .join(" OR "),
+            )
+        }
+        QueryExpr::And(items) => fts_and_query(items, trigram_columns),
+        QueryExpr::Term(_) | QueryExpr::Exclude(_) => {
+            fts_and_query(std::slice::from_ref(expr), trigram_columns)
+        }
+    }
+}
+
+/// 一组需要全部命中的项：普通关键词合在一起（见 `fts_group_query`），括号内的 `OR` 各自加括号，
+/// 本组的排除词以 `NOT` 附在最后
+fn fts_and_query(items: &[QueryExpr], trigram_columns: &[&str]) -> Option<String> {
+    let terms: Vec<String> = items
+        .iter()
+        .filter_map(|item| match item {
+            QueryExpr::Term(term) => Some(term.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut parts: Vec<String> = fts_group_query(&terms, trigram_columns)
+        .into_iter()
+        .collect();
+    for item in items {
+        if let QueryExpr::And(_) | QueryExpr::Or(_) = item {
+            parts.push(fts_expr(item, trigram_columns)?);
+        }
+    }
+    let matched = match parts.as_slice() {
+        [] => return None,
+        [single] => single.clone(),
+        _ => parts
+            .iter()
+            .map(|part| format!("({})", part))
+            .collect::<Vec<_>>()
+            .join(" AND "),
+    };
+
+    let excluded: Vec<String> = items
+        .iter()
+        .filter_map(|item| match item {
+            QueryExpr::Exclude(word) => fts_exclusion(word),
+            _ => None,
+        })
+        .collect();
+    if excluded.is_empty() {
+        Some(matched)
+    } else {
+        Some(format!("({}) NOT ({})", matched, excluded.join(" OR ")))
+    }
+}
+
+/// 一组需要全部命中的关键词对应的 FTS 表达式；没有可用于匹配的词时为 None
+fn fts_group_query(group: &[String], trigram_columns: &[&str]) -> Option<String> {
+    let parts: Vec<String> = group
+        .iter()
+        .flat_map(|term| fts_term_parts(term, trigram_columns))
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+
+    // 同时在去标点的 compact 列上匹配整组关键词：`rustlang` 命中 rust-lang、`vs code` 命中 VSCode；
+    // 含短语的组要求逐词连续命中，不做这种宽松匹配
+    let compact: String = if group.iter().any(|term| is_phrase(term)) {
+        String::new()
+    } else {
+        compact_for_match(&group.join(" "))
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect()
+    };
+    if compact.is_empty() {
+        Some(parts.join(" "))
+    } else {
+        Some(format!(
+            "({}) OR compact : \"{}\"*",
+            parts.join(" "),
+            compact
+        ))
+    }
+}
+
+/// 排除词对应的 FTS 表达式，限定在名称、URL 与目录列：按词前缀匹配，多个词须依次相邻，
+/// 短语的最后一个词也须完整。与 `query::excluded_by` 的内存判断一致；排除词中没有可匹配的词时为 None
+fn fts_exclusion(word: &str) -> Option<String> {
+    let terms: Vec<String> = words(word).collect();
+    if terms.is_empty() {
+        return None;
+    }
+    let prefix = if is_phrase(word) { "" } else { "*" };
+    Some(format!(
+        "{{name url folder_path}} : \"{}\"{}",
+        terms.join(" "),
+        prefix
+    ))
+}
+
+/// 子串索引中一个关键词的条件：三个字符及以上的词以短语 MATCH 走 trigram 索引，
+/// 更短的词（如两字中文）只能逐行 LIKE
+fn substring_clause(term: &str) -> Option<(String, Vec<String>)> {
+    if term.chars().count() >= 3 {
+        return Some((
+            "t.rowid IN (SELECT rowid FROM bookmarks_trigram WHERE bookmarks_trigram MATCH ?)"
+                .to_string(),
+            vec![format!("\"{}\"", term.replace('"', "\"\""))],
+        ));
+    }
+    let pattern = format!("%{}%", escape_like_value(term));
+    Some((
+        "(t.name LIKE ? ESCAPE '\\' OR t.url LIKE ? ESCAPE '\\')".to_string(),
+        vec![pattern.clone(), pattern],
+    ))
+}
+
+/// SQL 条件片段及其按顺序绑定的参数
+type SqlCondition = (String, Vec<String>);
+
+/// 查询树对应的 SQL 条件及参数，用于不经过书签 FTS 表的查询（子串索引、备注与正文命中）：
+/// 关键词的条件由各检索路径的 `term_clause` 给出，返回 None 的关键词不作限制；
+/// 排除词借书签 FTS 表判断，与 `build_fts_query` 的排除语义相同，也只作用于所在的组
+fn query_condition(
+    expr: &QueryExpr,
+    term_clause: &dyn Fn(&str) -> Option<SqlCondition>,
+) -> Option<SqlCondition> {
+    let (items, joiner) = match expr {
+        QueryExpr::Term(term) => return term_clause(term),
+        QueryExpr::Exclude(word) => {
+            return fts_exclusion(word).map(|exclusion| {
+                (
+                "b.id NOT IN (SELECT bookmark_id FROM bookmarks_fts WHERE bookmarks_fts MATCH ?)"
+                    .to_string(),
+                vec![exclusion],
+            )
+            })
+        }
+        QueryExpr::And(items) => (items, " AND "),
+        QueryExpr::Or(items) => (items, " OR "),
+    };
+    let conditions: Vec<SqlCondition> = if let QueryExpr::Or(_) = expr {
+        // 不作限制的分支使整个 OR 不作限制
+        items
+            .iter()
+            .map(|item| query_condition(item, term_clause))
+            .collect::<Option<_>>()?
+    } else {
+        items
+            .iter()
+            .filter_map(|item| query_condition(item, term_clause))
+            .collect()
+    };
+    if conditions.is_empty() {
+        return None;
+    }
+    let (sql, values): (Vec<String>, Vec<Vec<String>>) = conditions.into_iter().unzip();
+    Some((format!("({})", sql.join(joiner)), values.concat()))
+}
+
+/// 一个关键词对应的 FTS 词项（隐式 AND）：短语加引号，其中的词须按顺序相邻出现；
+/// 其余的词见 `fts_prefix_terms`
+fn fts_term_parts(term: &str, trigram_columns: &[&str]) -> Vec<String> {
+    if is_phrase(term) {
+        vec![format!("\"{}\"", term.replace('"', "\"\""))]
+    } else {
+        fts_prefix_terms(term, trigram_columns)
+    }
+}
+
+/// 查询中每个词对应的 FTS 前缀短语（隐式 AND），不涉及具体列，也可用于其他 FTS 表
+fn fts_prefix_terms(query: &str, trigram_columns: &[&str]) -> Vec<String> {
+    let mut parts = Vec::new();
+
+    for token in query.split(|c: char| c.is_whitespace() || c == '/') {
+        let cleaned: String = token
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
+            .collect();
+
+        if cleaned.is_empty() {
+            continue;
+        }
+
+        // 带引号的前缀短语：`rust-lang` 这类含标点的词按分词器拆开，不会成为非法的 FTS5 语法
+        let prefix = format!("\"{}\"*", cleaned);
+        let grams = query_trigrams(&cleaned);
+        if trigram_columns.is_empty() || grams.is_empty() {
+            parts.push(prefix);
+        } else {
+            let grams: Vec<String> = grams.iter().map(|gram| format!("\"{}\"", gram)).collect();
+            parts.push(format!(
+                "({} OR {{{}}} : ({}))",
+                prefix,
+                trigram_columns.join(" "),
+                grams.join(" ")
+            ));
+        }
+    }
+    parts
+}
+
+/// 书签列之后第 9 列为 bm25 分数
+fn ranked_bookmark_from_row(row: &rusqlite::Row<'_>) -> Result<(ChromeBookmark, f64)> {
+    Ok((bookmark_from_row(row)?, row.get(8)?))
+}
+
+/// 本地书签的来源标识，作为 `browser` / `profile` 写入索引
+pub const LOCAL_BROWSER: &str = "local";
+pub const LOCAL_PROFILE: &str = "Local";
+
+/// 列顺序与 `bookmark_from_row` 一致；id 形如 `local:Local:3`
+const LOCAL_BOOKMARKS_SELECT: &str =
+    "SELECT 'local:Local:' || id, name, url, date_added, folder_path, 'local', 'Local', NULL
+     FROM local_bookmarks";
+
+/// 索引行的内容哈希，只在同一次同步内比较，不落盘
+fn row_hash(bookmark: &ChromeBookmark) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (
+        &bookmark.name,
+        &bookmark.url,
+        &bookmark.date_added,
+        &bookmark.folder_path,
+        &bookmark.browser,
+        &bookmark.profile,
+        &bookmark.guid,
+    )
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+fn bookmark_from_row(row: &rusqlite::Row<'_>) -> Result<ChromeBookmark> {
+    let id: String = row.get(0)?;
+    let name: String = row.get(1)?;
+    let url: String = row.get(2)?;
+    let date_added: String = row.get(3)?;
+    let folder_path: Option<String> = row.get(4)?;
+    let browser: String = row.get(5)?;
+    let profile: String = row.get(6)?;
+    let guid: Option<String> = row.get(7)?;
+
+    // 小写字段留空，只有需要内存打分的路径才经 `lower_*` 计算
+    Ok(ChromeBookmark {
+        id,
+        name,
+        url,
+        date_added,
+        folder_path,
+        name_lower: String::new(),
+        url_lower: String::new(),
+        folder_path_lower: None,
+        browser,
+        profile,
+        guid,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+    use tempfile::tempdir;
+
+    fn sample_bookmark(id: &str, name: &str, url: &str, folder: Option<&str>) -> ChromeBookmark {
+        test_bookmark(id, name, url, folder)
+    }
+
+    #[test]
+    fn warm_up_reports_indexed_bookmarks() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        assert_eq!(index.warm_up().expect("warm up"), 0);
+
+        index
+            .replace_bookmarks(
+                &[sample_bookmark("1", "Rust", "https://rust-lang.org", None)],
+                "fp",
+            )
+            .expect("replace");
+        assert_eq!(index.warm_up().expect("warm up"), 1);
+    }
+
+    #[test]
+    fn health_counts_rows_and_records_maintenance() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        index
+            .replace_bookmarks(
+                &[
+                    sample_bookmark("1", "Rust", "https://rust-lang.org", None),
+                    sample_bookmark("2", "Docs", "https://docs.rs", None),
+                ],
+                "fp",
+            )
+            .expect("replace");
+
+        let health = index.health().expect("health");
+        let rows = |table: &str| {
+            health
+                .tables
+                .iter()
+                .find(|(name, _)| name == table)
+                .map(|(_, rows)| *rows)
+        };
+        assert_eq!(rows("bookmarks"), Some(2));
+        assert_eq!(rows("bookmarks_fts"), Some(2));
+        assert_eq!(rows("bookmarks_fts_data"), None);
+        assert!(health.page_count > 0);
+        assert_eq!(health.last_vacuum, None);
+        assert_eq!(health.last_refresh_ms, None);
+
+        index.record_refresh(1_700_000_000, 42).expect("record");
+        index.vacuum(1_700_000_100).expect("vacuum");
+        let health = index.health().expect("health");
+        assert_eq!(health.freelist_count, 0);
+        assert_eq!(health.last_vacuum, Some(1_700_000_100));
+        assert_eq!(health.last_refresh_at, Some(1_700_000_000));
+        assert_eq!(health.last_refresh_ms, Some(42));
+    }
+
+    #[test]
+    fn replace_bookmarks_builds_index_and_searches() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("bookmarks.db");
+        let index = BookmarkIndex::new(db_path).expect("index");
+
+        let bookmarks = vec![
+            sample_bookmark("1", "Rust Lang", "https://rust-lang.org", Some("Work/Docs")),
+            sample_bookmark("2", "Example", "https://example.com", Some("Play/Read")),
+        ];
+
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+        assert!(!index.bookmarks_need_refresh("fp-1").expect("fingerprint"));
+        assert_eq!(index.get_total_bookmarks().expect("count"), 2);
+
+        let found = index
+            .search_bookmarks_fts("rust", 10)
+            .expect("fts")
+            .expect("enabled");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "1");
+    }
+
+    #[test]
+    fn list_bookmarks_by_folder_filters_supports_hierarchy_like_matching() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("bookmarks.db");
+        let index = BookmarkIndex::new(db_path).expect("index");
+
+        let bookmarks = vec![
+            sample_bookmark(
+                "1",
+                "Rust",
+                "https://rust-lang.org",
+                Some("Root/Work/Project/Rust"),
+            ),
+            sample_bookmark("2", "Music", "https://music.example", Some("Root/Play")),
+        ];
+
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+
+        let filtered = index
+            .list_bookmarks_filtered(
+                &BookmarkFilters {
+                    folders: vec!["work/project".into()],
+                    ..Default::default()
+                },
+                20,
+            )
+            .expect("filter");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, "1");
+    }
+
+    #[test]
+    fn search_bookmarks_fts_with_folders_applies_filter() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("bookmarks.db");
+        let index = BookmarkIndex::new(db_path).expect("index");
+
+        let bookmarks = vec![
+            sample_bookmark(
+                "1",
+                "Rust Book",
+                "https://doc.rust-lang.org",
+                Some("Root/Work/Docs"),
+            ),
+            sample_bookmark(
+                "2",
+                "Rust Game",
+                "https://game.example",
+                Some("Root/Play/Games"),
+            ),
+        ];
+
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+
+        let filtered = index
+            .search_bookmarks_fts_filtered(
+                "rust",
+                &BookmarkFilters {
+                    folders: vec!["work".into()],
+                    ..Default::default()
+                },
+                20,
+            )
+            .expect("fts")
+            .expect("enabled");
+
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, "1");
+    }
+
+    #[test]
+    fn clear_bookmarks_index_resets_data() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("bookmarks.db");
+        let index = BookmarkIndex::new(db_path).expect("index");
+
+        let bookmarks = vec![sample_bookmark(
+            "1",
+            "Rust",
+            "https://rust-lang.org",
+            Some("Root/Work"),
+        )];
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+
+        index.clear_bookmarks_index().expect("clear");
+        assert_eq!(index.get_total_bookmarks().expect("count"), 0);
+        assert!(index.bookmarks_need_refresh("fp-1").expect("refresh"));
+    }
+
+    #[test]
+    fn filters_restrict_results_to_profiles() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+
+        let mut work = sample_bookmark("1", "Rust Work", "https://work.example", None);
+        work.browser = "chrome".into();
+        work.profile = "Work".into();
+        let mut personal = sample_bookmark("2", "Rust Home", "https://home.example", None);
+        personal.browser = "chrome".into();
+        personal.profile = "Personal".into();
+        index
+            .replace_bookmarks(&[work, personal], "fp-1")
+            .expect("replace");
+
+        let filters = BookmarkFilters {
+            profiles: vec!["work".into()],
+            ..Default::default()
+        };
+        let listed = index.list_bookmarks_filtered(&filters, 10).expect("list");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].profile, "Work");
+
+        let found = index
+            .search_bookmarks_fts_filtered("rust", &filters, 10)
+            .expect("fts")
+            .expect("enabled");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "1");
+    }
+
+    #[test]
+    fn filters_combine_browser_and_profile() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+
+        let sources = [("chrome", "Work"), ("brave", "Work"), ("brave", "Default")];
+        let bookmarks: Vec<ChromeBookmark> = sources
             .iter()
-            .filter_map(|raw| folder_filter_to_like_pattern(raw))
+            .enumerate()
+            .map(|(idx, (browser, profile))| {
+                let mut bookmark = sample_bookmark(
+                    &idx.to_string(),
+                    "Rust Docs",
+                    &format!("https://{}.example", idx),
+                    Some("Root/Docs"),
+                );
+                bookmark.browser = browser.to_string();
+                bookmark.profile = profile.to_string();
+                bookmark
+            })
+            .collect();
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+
+        let filters = BookmarkFilters {
+            folders: vec!["docs".into()],
+            browsers: vec!["brave".into()],
+            profiles: vec!["work".into()],
+            ..Default::default()
+        };
+        let found = index
+            .search_bookmarks_fts_filtered("rust", &filters, 10)
+            .expect("fts")
+            .expect("enabled");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "1");
+    }
+
+    #[test]
+    fn tokenize_change_rebuilds_fts_and_enables_pinyin_and_substring_matches() {
+        let mut index = BookmarkIndex::in_memory().expect("index");
+        index
+            .replace_bookmarks(
+                &[
+                    sample_bookmark("1", "搜索引擎优化", "https://seo.example", None),
+                    sample_bookmark("2", "Repo", "https://github.com/rust-lang", None),
+                ],
+                "fp-1",
+            )
+            .expect("replace");
+        let ids = |index: &BookmarkIndex, query: &str| -> Vec<String> {
+            index
+                .search_bookmarks_fts(query, 10)
+                .expect("search")
+                .expect("fts")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+        assert!(ids(&index, "sousuo").is_empty());
+        assert!(ids(&index, "thub").is_empty());
+
+        index
+            .set_tokenize(crate::tokenize::TokenizeConfig::parse(
+                "name=pinyin,url=trigram",
+            ))
+            .expect("tokenize");
+        assert_eq!(ids(&index, "sousuo"), vec!["1"]);
+        assert_eq!(ids(&index, "ssyq"), vec!["1"]);
+        assert_eq!(ids(&index, "thub"), vec!["2"]);
+        assert_eq!(ids(&index, "ithu"), vec!["2"]);
+        // 新写入的书签同样按当前设置分词
+        index
+            .replace_bookmarks(
+                &[sample_bookmark(
+                    "3",
+                    "中文文档",
+                    "https://docs.example",
+                    None,
+                )],
+                "fp-2",
+            )
+            .expect("replace");
+        assert_eq!(ids(&index, "zhongwen"), vec!["3"]);
+
+        index
+            .set_tokenize(crate::tokenize::TokenizeConfig::default())
+            .expect("reset");
+        assert!(ids(&index, "zhongwen").is_empty());
+    }
+
+    #[test]
+    fn batched_rebuild_inserts_every_row_and_restores_indexes() {
+        let index = BookmarkIndex::in_memory().expect("index");
+        let bookmarks: Vec<_> = (0..INSERT_BATCH_ROWS * 2 + 7)
+            .map(|i| {
+                sample_bookmark(
+                    &i.to_string(),
+                    &format!("Page {}", i),
+                    &format!("https://example.com/{}", i),
+                    Some("书签栏/Bulk"),
+                )
+            })
+            .collect();
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+        assert_eq!(
+            index.load_all_bookmarks().expect("load").len(),
+            bookmarks.len()
+        );
+        assert_eq!(
+            index
+                .search_bookmarks_fts("page", 1_000)
+                .expect("search")
+                .expect("fts")
+                .len(),
+            bookmarks.len()
+        );
+
+        let indexes: usize = index
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name LIKE 'idx_bookmarks_%'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("count indexes");
+        assert_eq!(indexes, BOOKMARK_INDEXES.len());
+        assert_eq!(
+            multi_row_insert_sql("INSERT INTO t (a, b)", 2, 2),
+            "INSERT INTO t (a, b) VALUES (?, ?), (?, ?)"
+        );
+    }
+
+    #[test]
+    fn in_memory_index_supports_refresh_and_search() {
+        let index = BookmarkIndex::in_memory().expect("index");
+        index
+            .replace_bookmarks(
+                &[sample_bookmark(
+                    "1",
+                    "Rust Book",
+                    "https://doc.rust-lang.org/book",
+                    Some("书签栏/Docs"),
+                )],
+                "fp-1",
+            )
+            .expect("replace");
+        assert!(!index.bookmarks_need_refresh("fp-1").expect("fresh"));
+        let found = index
+            .search_bookmarks_fts("rust", 10)
+            .expect("search")
+            .expect("fts");
+        assert_eq!(found.len(), 1);
+
+        let read_only = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_READONLY),
+            None,
+        );
+        assert!(is_unwritable_error(&read_only));
+        assert!(!is_unwritable_error(&rusqlite::Error::QueryReturnedNoRows));
+    }
+
+    #[test]
+    fn chunks_cover_filtered_bookmarks_and_estimate_grows_with_text() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        let bookmarks: Vec<ChromeBookmark> = (1..=7)
+            .map(|i| {
+                let folder = if i % 2 == 0 { "Root/Even" } else { "Root/Odd" };
+                sample_bookmark(
+                    &i.to_string(),
+                    &format!("Item {}", i),
+                    &format!("https://example.com/{}", i),
+                    Some(folder),
+                )
+            })
             .collect();
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+
+        let mut chunks: Vec<Vec<String>> = Vec::new();
+        index
+            .for_each_chunk(&BookmarkFilters::default(), 3, |chunk| {
+                chunks.push(chunk.into_iter().map(|bookmark| bookmark.id).collect());
+            })
+            .expect("chunks");
+        assert_eq!(
+            chunks,
+            vec![vec!["1", "2", "3"], vec!["4", "5", "6"], vec!["7"]]
+        );
+
+        let odd = BookmarkFilters {
+            folders: vec!["odd".into()],
+            ..Default::default()
+        };
+        let mut odd_ids = Vec::new();
+        index
+            .for_each_chunk(&odd, 2, |chunk| {
+                odd_ids.extend(chunk.into_iter().map(|bookmark| bookmark.id));
+            })
+            .expect("chunks");
+        assert_eq!(odd_ids, vec!["1", "3", "5", "7"]);
+
+        let all = index
+            .estimate_load_bytes(&BookmarkFilters::default())
+            .expect("estimate");
+        let odd_only = index.estimate_load_bytes(&odd).expect("estimate");
+        assert!(all > odd_only && odd_only > 4 * BOOKMARK_OVERHEAD_BYTES);
+    }
+
+    #[test]
+    fn tag_and_folder_filters_prefilter_in_sql() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        let tags_db = dir.path().join("tags.db");
+        let tags = crate::tags::TagManager::new(tags_db.clone()).expect("tags");
+        drop(tags);
+        let conn = Connection::open(&tags_db).expect("open tags");
+        let key = crate::tags::bookmark_key;
+        conn.execute_batch(&format!(
+            "INSERT INTO tags (id, name) VALUES (1, 'Rust'), (2, 'web');
+             INSERT INTO bookmark_tags (bookmark_key, bookmark_url, tag_id) VALUES
+                ('{axum}', NULL, 1), ('{axum}', NULL, 2), ('{tokio}', NULL, 1),
+                ('{bevy}', NULL, 1);",
+            axum = key("https://axum.example"),
+            tokio = key("https://tokio.example"),
+            bevy = key("https://bevy.example"),
+        ))
+        .expect("seed tags");
+
+        // 标签按规范 URL 关联，与书签 id 无关
+        let bookmarks = vec![
+            sample_bookmark("1", "Axum", "https://axum.example", Some("Root/Work")),
+            sample_bookmark("2", "Tokio", "https://tokio.example", Some("Root/Work")),
+            sample_bookmark("3", "Bevy", "https://Bevy.example/", Some("Root/Play")),
+        ];
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+        index.attach_tags(&tags_db).expect("attach");
+        // 同一连接上重复挂载不报错
+        index.attach_tags(&tags_db).expect("attach again");
+
+        let ids = |filters: &BookmarkFilters| -> Vec<String> {
+            index
+                .load_bookmarks_filtered(filters)
+                .expect("filter")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+        let rust_at_work = BookmarkFilters {
+            folders: vec!["work".into()],
+            tags: vec!["rust".into()],
+            ..Default::default()
+        };
+        assert_eq!(ids(&rust_at_work), vec!["1", "2"]);
+        let rust_and_web = BookmarkFilters {
+            tags: vec!["RUST".into(), "web".into()],
+            ..Default::default()
+        };
+        assert_eq!(ids(&rust_and_web), vec!["1"]);
+        assert_eq!(
+            index
+                .search_bookmarks_fts_filtered("bevy", &rust_and_web, 20)
+                .expect("fts")
+                .expect("enabled")
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn sorted_search_orders_in_sql() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        let mut bookmarks = vec![
+            sample_bookmark("1", "b rust", "https://c.example", None),
+            sample_bookmark("2", "A rust", "https://b.example", None),
+            sample_bookmark("3", "c rust", "https://a.example", None),
+            sample_bookmark("4", "go", "https://d.example", None),
+        ];
+        for (bookmark, added) in bookmarks.iter_mut().zip(["300", "1000", "20", "5000"]) {
+            bookmark.date_added = added.to_string();
+        }
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+
+        let usage_db = dir.path().join("usage.db");
+        let usage = crate::usage::UsageStore::new(usage_db.clone()).expect("usage");
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("time")
+            .as_secs();
+        usage.record_open("https://a.example", now).expect("open");
+        usage.record_open("https://a.example", now).expect("open");
+        usage.record_open("https://c.example", now).expect("open");
+        index.attach_usage(&usage_db).expect("attach");
+
+        let ids = |query: &str, sort: SortOrder| -> Vec<String> {
+            index
+                .search_sorted(query, &BookmarkFilters::default(), sort, 10)
+                .expect("sorted")
+                .expect("fts")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+        assert_eq!(ids("rust", SortOrder::Added), vec!["2", "1", "3"]);
+        assert_eq!(ids("rust", SortOrder::Title), vec!["2", "1", "3"]);
+        assert_eq!(ids("rust", SortOrder::Url), vec!["3", "2", "1"]);
+        assert_eq!(ids("rust", SortOrder::Frecency), vec!["3", "1", "2"]);
+        assert_eq!(ids("", SortOrder::Added), vec!["4", "2", "1", "3"]);
+
+        let opened = |query: &str| -> Vec<(String, u64)> {
+            index
+                .most_opened(query, &BookmarkFilters::default(), 10)
+                .expect("opened")
+                .expect("fts")
+                .into_iter()
+                .map(|(bookmark, count)| (bookmark.id, count))
+                .collect()
+        };
+        assert_eq!(opened(""), vec![("3".to_string(), 2), ("1".to_string(), 1)]);
+        assert_eq!(opened("b rust"), vec![("1".to_string(), 1)]);
+    }
+
+    #[test]
+    fn fts_matching_ignores_punctuation_and_spacing() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        index
+            .replace_bookmarks(
+                &[
+                    sample_bookmark("1", "Rust Forum", "https://users.rust-lang.org", None),
+                    sample_bookmark("2", "VSCode Tips", "https://code.visualstudio.com", None),
+                    sample_bookmark("3", "Other", "https://other.example", None),
+                ],
+                "fp-1",
+            )
+            .expect("replace");
+
+        let ids = |query: &str| -> Vec<String> {
+            index
+                .search_bookmarks_fts(query, 10)
+                .expect("fts")
+                .expect("enabled")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+        assert_eq!(ids("rustlang"), vec!["1"]);
+        assert_eq!(ids("rust-lang"), vec!["1"]);
+        assert_eq!(ids("vs code"), vec!["2"]);
+        assert_eq!(ids("vscode"), vec!["2"]);
+    }
+
+    #[test]
+    fn outdated_fts_table_is_rebuilt_with_compact_column() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("bookmarks.db");
+        {
+            let index = BookmarkIndex::new(db_path.clone()).expect("index");
+            index
+                .replace_bookmarks(
+                    &[sample_bookmark("1", "VSCode", "https://code.example", None)],
+                    "fp-1",
+                )
+                .expect("replace");
+            index
+                .conn
+                .execute_batch(
+                    "DROP TABLE bookmarks_fts;
+                     CREATE VIRTUAL TABLE bookmarks_fts USING fts5(
+                        bookmark_id UNINDEXED, name, url, folder_path, tokenize = 'unicode61');",
+                )
+                .expect("downgrade");
+        }
+
+        let index = BookmarkIndex::new(db_path).expect("reopen");
+        let hits = index
+            .search_bookmarks_fts("vs code", 10)
+            .expect("fts")
+            .expect("enabled");
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn boosted_domains_move_up_in_bm25_results() {
+        let dir = tempdir().expect("tempdir");
+        let mut index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        index
+            .replace_bookmarks(
+                &[
+                    sample_bookmark("1", "serde serde", "https://blog.example/serde", None),
+                    sample_bookmark("2", "serde", "https://docs.rs/serde/latest", None),
+                ],
+                "fp-1",
+            )
+            .expect("replace");
+
+        let ids = |index: &BookmarkIndex| -> Vec<String> {
+            index
+                .search_bookmarks_fts("serde", 10)
+                .expect("fts")
+                .expect("enabled")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+        assert_eq!(ids(&index), vec!["1", "2"]);
+        index.set_domain_boosts(vec!["docs.rs".into()]);
+        assert_eq!(ids(&index), vec!["2", "1"]);
+    }
+
+    #[test]
+    fn substring_index_finds_mid_word_matches() {
+        let dir = tempdir().expect("tempdir");
+        let mut index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        index
+            .replace_bookmarks(
+                &[sample_bookmark(
+                    "1",
+                    "设计模式",
+                    "https://refactoring.guru",
+                    None,
+                )],
+                "fp-1",
+            )
+            .expect("replace");
+        // 开启时回填已有书签，之后的写入同步维护
+        index.set_substring_index(true).expect("enable");
+        if !index.substring_enabled {
+            return;
+        }
+        index
+            .sync_bookmarks(
+                &[
+                    sample_bookmark("1", "设计模式", "https://refactoring.guru", None),
+                    sample_bookmark("2", "Docs", "https://doc.rustlang.org/std", None),
+                ],
+                &[],
+                "fp-2",
+            )
+            .expect("sync");
+
+        let ids = |index: &BookmarkIndex, query: &str| -> Vec<String> {
+            index
+                .search_bookmarks_fts(query, 10)
+                .expect("search")
+                .unwrap_or_default()
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+        assert_eq!(ids(&index, "模式"), vec!["1"]);
+        assert_eq!(ids(&index, "LANG"), vec!["2"]);
+        assert_eq!(ids(&index, "factor guru"), vec!["1"]);
+        assert!(ids(&index, "模式 lang").is_empty());
+
+        index.set_substring_index(false).expect("disable");
+        assert!(ids(&index, "模式").is_empty());
+    }
+
+    #[test]
+    fn source_states_round_trip_and_clear() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+
+        let state = SourceState {
+            browser: "chrome".into(),
+            profile: "Work".into(),
+            path: "/tmp/Bookmarks".into(),
+            fingerprint: "fp".into(),
+            bookmark_count: 42,
+            refreshed_at: 1_700_000_000,
+        };
+        index
+            .replace_source_states(std::slice::from_ref(&state))
+            .expect("replace");
+        assert_eq!(index.source_states().expect("states"), vec![state]);
+
+        index.clear_bookmarks_index().expect("clear");
+        assert!(index.source_states().expect("states").is_empty());
+    }
+
+    #[test]
+    fn local_bookmarks_are_indexed_immediately_and_upserted() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+
+        let (added, is_new) = index
+            .add_local_bookmark("https://docs.rs", "Docs", Some("Inbox"), "1")
+            .expect("add");
+        assert!(is_new);
+        assert_eq!(added.id, "local:Local:1");
+        assert_eq!(added.browser, LOCAL_BROWSER);
+        assert_eq!(added.profile, LOCAL_PROFILE);
+
+        let (updated, is_new) = index
+            .add_local_bookmark("https://docs.rs", "Docs.rs", None, "2")
+            .expect("upsert");
+        assert!(!is_new);
+        assert_eq!(updated.id, "local:Local:1");
+        assert_eq!(updated.date_added, "1");
+
+        let found = index
+            .search_bookmarks_fts("docs", 10)
+            .expect("search")
+            .expect("fts");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "Docs.rs");
+        assert_eq!(found[0].folder_path, None);
+        assert_eq!(index.local_bookmarks().expect("local").len(), 1);
+
+        // 刷新时整体重建不影响本地书签表
+        index.clear_bookmarks_index().expect("clear");
+        assert_eq!(index.get_total_bookmarks().expect("total"), 0);
+        assert_eq!(
+            index.local_bookmarks().expect("local")[0].url,
+            "https://docs.rs"
+        );
+    }
+
+    #[test]
+    fn updated_local_bookmark_is_reindexed_and_persisted() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        let (added, _) = index
+            .add_local_bookmark("https://docs.rs", "Docs", Some("Inbox"), "1")
+            .expect("add");
+
+        let updated = index
+            .update_bookmark(&added.id, "Crate Docs", Some("Reference/Rust"))
+            .expect("update")
+            .expect("found");
+        assert_eq!(updated.folder_path.as_deref(), Some("Reference/Rust"));
+        assert!(index
+            .update_bookmark("missing", "x", None)
+            .expect("update")
+            .is_none());
+
+        let found = index
+            .search_bookmarks_fts("crate", 10)
+            .expect("search")
+            .expect("fts");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].folder_path.as_deref(), Some("Reference/Rust"));
+        let local = index.local_bookmarks().expect("local");
+        assert_eq!(local[0].name, "Crate Docs");
+        assert_eq!(local[0].folder_path.as_deref(), Some("Reference/Rust"));
+    }
+
+    #[test]
+    fn trashed_bookmarks_are_hidden_until_restored() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        index
+            .replace_bookmarks(
+                &[
+                    sample_bookmark("1", "Rust Book", "https://doc.rust-lang.org/book", None),
+                    sample_bookmark("2", "Rust Std", "https://doc.rust-lang.org/std", None),
+                ],
+                "fp",
+            )
+            .expect("replace");
+
+        let trashed = index.trash_bookmark("1", 100).expect("trash");
+        assert_eq!(
+            trashed.map(|bookmark| bookmark.name).as_deref(),
+            Some("Rust Book")
+        );
+        assert!(index
+            .trash_bookmark("missing", 100)
+            .expect("trash")
+            .is_none());
+        assert!(index.bookmarks_need_refresh("fp").expect("need refresh"));
+
+        let found = index
+            .search_bookmarks_fts("rust", 10)
+            .expect("search")
+            .expect("fts");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "2");
+        assert!(index.trashed_ids().expect("ids").contains("1"));
+        let listed = index.trashed_bookmarks().expect("list");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].1, 100);
+
+        let restored = index.restore_bookmark("1").expect("restore");
+        assert_eq!(restored.map(|bookmark| bookmark.id).as_deref(), Some("1"));
+        assert!(index
+            .restore_bookmark("1")
+            .expect("restore twice")
+            .is_none());
+        assert!(index.trashed_ids().expect("ids").is_empty());
+        assert_eq!(
+            index
+                .search_bookmarks_fts("rust", 10)
+                .expect("search")
+                .expect("fts")
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn source_path_found_by_bookmark_url() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+
+        let mut bookmark = sample_bookmark("chrome:Work:1", "Rust", "https://rust-lang.org", None);
+        bookmark.browser = "chrome".into();
+        bookmark.profile = "Work".into();
+        index
+            .replace_bookmarks(std::slice::from_ref(&bookmark), "fp-1")
+            .expect("replace");
+        index
+            .replace_source_states(&[SourceState {
+                browser: "chrome".into(),
+                profile: "Work".into(),
+                path: "/Chrome/Profile 1/Bookmarks".into(),
+                fingerprint: "fp".into(),
+                bookmark_count: 1,
+                refreshed_at: 0,
+            }])
+            .expect("sources");
+
+        assert_eq!(
+            index
+                .source_path_for_url("https://rust-lang.org")
+                .expect("lookup")
+                .as_deref(),
+            Some("/Chrome/Profile 1/Bookmarks")
+        );
+        assert_eq!(
+            index
+                .source_path_for_url("https://docs.rs")
+                .expect("lookup"),
+            None
+        );
+    }
+
+    #[test]
+    fn source_filters_match_merged_variants() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+
+        let mut winner = sample_bookmark("chrome:Work:1", "Rust", "https://rust-lang.org", None);
+        winner.browser = "chrome".into();
+        winner.profile = "Work".into();
+        let mut loser = winner.clone();
+        loser.id = "brave:Default:9".into();
+        loser.name = "Rust Language".into();
+        loser.browser = "brave".into();
+        loser.profile = "Default".into();
+
+        index
+            .replace_bookmarks(std::slice::from_ref(&winner), "fp-1")
+            .expect("replace");
+        index
+            .replace_variants(&[BookmarkVariant {
+                winner_id: winner.id.clone(),
+                bookmark: loser,
+            }])
+            .expect("variants");
+
+        let filters = BookmarkFilters {
+            browsers: vec!["brave".into()],
+            ..Default::default()
+        };
+        let listed = index.list_bookmarks_filtered(&filters, 10).expect("list");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, "chrome:Work:1");
+
+        let variants = index.variants_for(&["chrome:Work:1"]).expect("variants");
+        assert_eq!(variants["chrome:Work:1"][0].name, "Rust Language");
+        assert_eq!(index.load_all_variants().expect("all variants").len(), 1);
+        assert_eq!(
+            index
+                .get_bookmark("brave:Default:9")
+                .expect("get")
+                .map(|b| b.name),
+            Some("Rust Language".to_string())
+        );
+        assert!(index.get_bookmark("missing").expect("get").is_none());
+        assert_eq!(
+            index
+                .find_bookmark(&tag_key(&winner))
+                .expect("find")
+                .map(|b| b.id),
+            Some("chrome:Work:1".to_string())
+        );
+        assert_eq!(
+            index
+                .find_bookmark("brave:Default:9")
+                .expect("find")
+                .map(|b| b.id),
+            Some("brave:Default:9".to_string())
+        );
 
-        if patterns.is_empty() {
-            return self.search_bookmarks_fts(query, limit);
-        }
+        index.clear_bookmarks_index().expect("clear");
+        assert!(index
+            .variants_for(&["chrome:Work:1"])
+            .expect("variants")
+            .is_empty());
+    }
 
-        let mut sql = String::from(
-            "SELECT b.id, b.name, b.url, b.date_added, b.folder_path
-             FROM bookmarks_fts
-             JOIN bookmarks b ON b.id = bookmarks_fts.bookmark_id
-             WHERE bookmarks_fts MATCH ?",
-        );
+    #[test]
+    fn sync_bookmarks_rewrites_stale_sources_and_keeps_others() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+
+        let mut chrome = sample_bookmark("chrome:Default:1", "Rust", "https://rust-lang.org", None);
+        chrome.browser = "chrome".into();
+        chrome.profile = "Default".into();
+        let mut arc = sample_bookmark("arc:Default:1", "Tokio", "https://tokio.rs", None);
+        arc.browser = "arc".into();
+        arc.profile = "Default".into();
+        index
+            .replace_bookmarks(&[chrome.clone(), arc.clone()], "fp-1")
+            .expect("replace");
+        let arc_rowid: i64 = index
+            .conn
+            .query_row(
+                "SELECT rowid FROM bookmarks WHERE id = 'arc:Default:1'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("rowid");
 
-        for _ in &patterns {
-            sql.push_str(" AND lower(ifnull(b.folder_path, '')) LIKE ? ESCAPE '\\'");
+        let mut renamed = chrome.clone();
+        renamed.name = "Rust Lang".into();
+        let stale = vec![("chrome".to_string(), "Default".to_string())];
+        index
+            .sync_bookmarks(&[renamed, arc], &stale, "fp-2")
+            .expect("sync");
+
+        let all = index
+            .list_bookmarks_filtered(&BookmarkFilters::default(), 10)
+            .expect("list");
+        assert_eq!(all.len(), 2);
+        assert!(all.iter().any(|b| b.name == "Rust Lang"));
+        let arc_rowid_after: i64 = index
+            .conn
+            .query_row(
+                "SELECT rowid FROM bookmarks WHERE id = 'arc:Default:1'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("rowid");
+        assert_eq!(arc_rowid, arc_rowid_after);
+        assert!(!index.bookmarks_need_refresh("fp-2").expect("fingerprint"));
+        if index.fts_enabled() {
+            let hits = index.search_bookmarks_fts("rust", 10).expect("fts");
+            assert_eq!(hits.map(|b| b.len()), Some(1));
         }
 
-        sql.push_str(" ORDER BY bm25(bookmarks_fts) LIMIT ?");
+        index.sync_bookmarks(&[], &[], "fp-3").expect("sync");
+        assert_eq!(index.get_total_bookmarks().expect("total"), 0);
+    }
 
-        let mut values: Vec<&dyn ToSql> = Vec::new();
-        values.push(&fts_query);
-        for pattern in &patterns {
-            values.push(pattern as &dyn ToSql);
+    #[test]
+    fn sync_bookmarks_removes_many_rows_in_batches() {
+        let mut index = BookmarkIndex::in_memory().expect("index");
+        index.set_substring_index(true).expect("enable");
+        let bookmarks: Vec<_> = (0..DELETE_BATCH_IDS * 2 + 3)
+            .map(|i| {
+                sample_bookmark(
+                    &i.to_string(),
+                    &format!("Page {}", i),
+                    &format!("https://example.com/{}", i),
+                    None,
+                )
+            })
+            .collect();
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+
+        index
+            .sync_bookmarks(&bookmarks[..2], &[], "fp-2")
+            .expect("sync");
+        assert_eq!(index.get_total_bookmarks().expect("total"), 2);
+        let rows = |table: &str| -> i64 {
+            index
+                .conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                    row.get(0)
+                })
+                .expect("count")
+        };
+        if index.fts_enabled() {
+            assert_eq!(rows("bookmarks_fts"), 2);
         }
-        let limit_param = limit as i64;
-        values.push(&limit_param);
+        if index.substring_enabled {
+            assert_eq!(rows("bookmarks_trigram"), 2);
+        }
+    }
 
-        let mut stmt = self.conn.prepare(&sql)?;
-        let rows = stmt.query_map(params_from_iter(values), bookmark_from_row)?;
-        let results = rows.collect::<Result<Vec<_>>>()?;
+    #[test]
+    fn sync_bookmarks_only_rewrites_changed_rows_of_stale_sources() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        let chrome = |id: &str, name: &str, url: &str| {
+            let mut bookmark = sample_bookmark(id, name, url, Some("书签栏"));
+            bookmark.browser = "chrome".into();
+            bookmark.profile = "Default".into();
+            bookmark
+        };
+        let kept = chrome("chrome:Default:1", "Rust", "https://rust-lang.org");
+        let moved = chrome("chrome:Default:2", "Tokio", "https://tokio.rs");
+        let gone = chrome("chrome:Default:3", "Serde", "https://serde.rs");
+        index
+            .replace_bookmarks(&[kept.clone(), moved.clone(), gone], "fp-1")
+            .expect("replace");
+        let rowid = |id: &str| -> i64 {
+            index
+                .conn
+                .query_row("SELECT rowid FROM bookmarks WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })
+                .expect("rowid")
+        };
+        let kept_rowid = rowid("chrome:Default:1");
+
+        let mut moved = moved;
+        moved.folder_path = Some("书签栏/Async".into());
+        let added = chrome("chrome:Default:4", "Axum", "https://docs.rs/axum");
+        let stale = vec![("chrome".to_string(), "Default".to_string())];
+        let stats = index
+            .sync_bookmarks(&[kept, moved, added], &stale, "fp-2")
+            .expect("sync");
+
+        assert_eq!(
+            stats,
+            SyncStats {
+                added: 1,
+                removed: 1,
+                changed: 1
+            }
+        );
+        assert_eq!(rowid("chrome:Default:1"), kept_rowid);
+        assert_eq!(
+            index
+                .get_bookmark("chrome:Default:2")
+                .expect("get")
+                .and_then(|b| b.folder_path),
+            Some("书签栏/Async".to_string())
+        );
+        assert!(index
+            .get_bookmark("chrome:Default:3")
+            .expect("get")
+            .is_none());
+        if index.fts_enabled() {
+            let hits = index.search_bookmarks_fts("async", 10).expect("fts");
+            assert_eq!(hits.map(|b| b.len()), Some(1));
+        }
 
-        Ok(Some(results))
+        let unchanged = index
+            .sync_bookmarks(&index.load_all_bookmarks().expect("load"), &stale, "fp-3")
+            .expect("sync");
+        assert_eq!(unchanged, SyncStats::default());
     }
 
-    fn get_meta(&self, key: &str) -> Result<Option<String>> {
-        let mut stmt = self.conn.prepare("SELECT value FROM meta WHERE key = ?1")?;
-        let mut rows = stmt.query(params![key])?;
-        if let Some(row) = rows.next()? {
-            let value: String = row.get(0)?;
-            return Ok(Some(value));
-        }
-        Ok(None)
+    #[test]
+    fn failed_rebuild_keeps_previous_index() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        let old = vec![sample_bookmark("1", "Rust", "https://rust-lang.org", None)];
+        index.replace_bookmarks(&old, "fp-1").expect("replace");
+
+        let result: Result<()> = index.atomically(|| {
+            index.clear_bookmarks_index()?;
+            index.replace_bookmarks(
+                &[sample_bookmark("2", "Tokio", "https://tokio.rs", None)],
+                "fp-2",
+            )?;
+            Err(rusqlite::Error::InvalidQuery)
+        });
+        assert!(result.is_err());
+
+        let all = index.load_all_bookmarks().expect("load");
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].id, "1");
+        assert!(!index.bookmarks_need_refresh("fp-1").expect("fingerprint"));
     }
 
-    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO meta (key, value) VALUES (?1, ?2)
-             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
-            params![key, value],
-        )?;
-        Ok(())
+    #[test]
+    fn nested_atomic_failure_only_rolls_back_inner_step() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+
+        index
+            .atomically(|| {
+                index.replace_bookmarks(
+                    &[sample_bookmark("1", "Rust", "https://rust-lang.org", None)],
+                    "fp-1",
+                )?;
+                let inner: Result<()> = index.atomically(|| {
+                    index.replace_bookmarks(&[], "fp-2")?;
+                    Err(rusqlite::Error::InvalidQuery)
+                });
+                assert!(inner.is_err());
+                Ok(())
+            })
+            .expect("outer");
+
+        assert_eq!(index.get_total_bookmarks().expect("total"), 1);
+        assert!(!index.bookmarks_need_refresh("fp-1").expect("fingerprint"));
     }
-}
 
-fn build_fts_query(query: &str) -> Option<String> {
-    let mut parts = Vec::new();
+    #[test]
+    fn source_fingerprints_round_trip_and_clear() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        assert!(index.source_fingerprints().expect("read").is_empty());
 
-    for token in query.split(|c: char| c.is_whitespace() || c == '/') {
-        let cleaned: String = token
-            .chars()
-            .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
-            .collect();
+        let key = ("chrome".to_string(), "Work".to_string());
+        index
+            .set_source_fingerprints(&[(key.clone(), "1-2-/a".to_string())])
+            .expect("write");
+        assert_eq!(index.source_fingerprints().expect("read")[&key], "1-2-/a");
 
-        if cleaned.is_empty() {
-            continue;
+        index.clear_bookmarks_index().expect("clear");
+        assert!(index.source_fingerprints().expect("read").is_empty());
+    }
+
+    #[test]
+    fn legacy_bookmarks_table_is_rebuilt_with_source_columns() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("bookmarks.db");
+        {
+            let conn = Connection::open(&db_path).expect("open");
+            conn.execute_batch(
+                "CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+                 CREATE TABLE bookmarks (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    url TEXT NOT NULL,
+                    date_added TEXT NOT NULL,
+                    folder_path TEXT
+                 );
+                 INSERT INTO bookmarks VALUES ('1', 'Old', 'https://old.example', '0', NULL);
+                 INSERT INTO meta VALUES ('bookmarks_fingerprint', 'fp-old');",
+            )
+            .expect("legacy schema");
         }
 
-        parts.push(format!("{}*", cleaned));
+        let index = BookmarkIndex::new(db_path).expect("index");
+        assert_eq!(index.get_total_bookmarks().expect("count"), 0);
+        assert!(index.bookmarks_need_refresh("fp-old").expect("refresh"));
     }
 
-    if parts.is_empty() {
-        None
-    } else {
-        Some(parts.join(" "))
+    #[test]
+    fn guid_is_stored_and_added_to_tables_without_it() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("bookmarks.db");
+        {
+            let conn = Connection::open(&db_path).expect("open");
+            conn.execute_batch(
+                "CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+                 CREATE TABLE bookmarks (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    url TEXT NOT NULL,
+                    date_added TEXT NOT NULL,
+                    folder_path TEXT,
+                    browser TEXT NOT NULL DEFAULT '',
+                    profile TEXT NOT NULL DEFAULT ''
+                 );
+                 CREATE TABLE trash (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    url TEXT NOT NULL,
+                    date_added TEXT NOT NULL,
+                    folder_path TEXT,
+                    browser TEXT NOT NULL,
+                    profile TEXT NOT NULL,
+                    deleted_at INTEGER NOT NULL
+                 );
+                 INSERT INTO trash VALUES
+                    ('chrome:Default:9', 'Gone', 'https://gone.example', '0', NULL,
+                     'chrome', 'Default', 5);
+                 INSERT INTO meta VALUES ('source_fingerprints', 'chrome	Default	fp');",
+            )
+            .expect("old schema");
+        }
+
+        let index = BookmarkIndex::new(db_path).expect("index");
+        assert!(index.source_fingerprints().expect("read").is_empty());
+        let trashed = index.trashed_bookmarks().expect("trash");
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].0.guid, None);
+
+        let bookmark = ChromeBookmark {
+            guid: Some("7f1c".to_string()),
+            ..sample_bookmark("chrome:Default:1", "Rust", "https://rust-lang.org", None)
+        };
+        index
+            .replace_bookmarks(std::slice::from_ref(&bookmark), "fp-1")
+            .expect("replace");
+        let stored = index
+            .get_bookmark("chrome:Default:1")
+            .expect("get")
+            .expect("indexed");
+        assert_eq!(stored.guid.as_deref(), Some("7f1c"));
+
+        index.trash_bookmark("chrome:Default:1", 10).expect("trash");
+        let restored = index
+            .restore_bookmark("chrome:Default:1")
+            .expect("restore")
+            .expect("trashed");
+        assert_eq!(restored.guid.as_deref(), Some("7f1c"));
     }
-}
 
-fn bookmark_from_row(row: &rusqlite::Row<'_>) -> Result<ChromeBookmark> {
-    let id: String = row.get(0)?;
-    let name: String = row.get(1)?;
-    let url: String = row.get(2)?;
-    let date_added: String = row.get(3)?;
-    let folder_path: Option<String> = row.get(4)?;
+    #[test]
+    fn added_at_is_backfilled_for_version_two_indexes() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("bookmarks.db");
+        {
+            let index = BookmarkIndex::new(db_path.clone()).expect("index");
+            let mut bookmarks = vec![
+                sample_bookmark("1", "Old", "https://old.example", None),
+                sample_bookmark("2", "New", "https://new.example", None),
+            ];
+            bookmarks[0].date_added = "13300000000000000".to_string();
+            bookmarks[1].date_added = "1700000000000".to_string();
+            index
+                .replace_bookmarks(&bookmarks, "fp-1")
+                .expect("replace");
+            index
+                .conn
+                .execute_batch(
+                    "DROP INDEX idx_bookmarks_added_at;
+                     ALTER TABLE bookmarks DROP COLUMN added_at;
+                     PRAGMA user_version = 2;",
+                )
+                .expect("downgrade");
+        }
 
-    Ok(ChromeBookmark {
-        id,
-        name: name.clone(),
-        url: url.clone(),
-        date_added,
-        folder_path: folder_path.clone(),
-        name_lower: name.to_lowercase(),
-        url_lower: url.to_lowercase(),
-        folder_path_lower: folder_path.as_ref().map(|value| value.to_lowercase()),
-    })
-}
+        let index = BookmarkIndex::new(db_path).expect("reopen");
+        let added: Vec<(String, i64)> = index
+            .conn
+            .prepare("SELECT id, added_at FROM bookmarks ORDER BY added_at DESC")
+            .expect("prepare")
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("query")
+            .collect::<Result<_>>()
+            .expect("rows");
+        assert_eq!(
+            added,
+            vec![
+                ("2".to_string(), 1_700_000_000),
+                (
+                    "1".to_string(),
+                    13_300_000_000_000_000 / 1_000_000 - 11_644_473_600
+                )
+            ]
+        );
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
+    #[test]
+    fn note_and_page_content_hits_follow_title_hits() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        let mut bookmarks = vec![
+            sample_bookmark("1", "Tokio", "https://tokio.rs", None),
+            sample_bookmark("2", "Runtime notes", "https://notes.example", None),
+            sample_bookmark("3", "Other", "https://other.example", None),
+        ];
+        bookmarks[2].browser = "edge".into();
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
 
-    fn sample_bookmark(id: &str, name: &str, url: &str, folder: Option<&str>) -> ChromeBookmark {
-        ChromeBookmark {
-            id: id.to_string(),
-            name: name.to_string(),
-            url: url.to_string(),
-            date_added: "0".to_string(),
-            folder_path: folder.map(ToString::to_string),
-            name_lower: name.to_lowercase(),
-            url_lower: url.to_lowercase(),
-            folder_path_lower: folder.map(|value| value.to_lowercase()),
+        let content_db = dir.path().join("content.db");
+        let store = crate::page_content::ContentStore::new(content_db.clone()).expect("store");
+        for (url, description) in [
+            ("https://tokio.rs/", "An asynchronous runtime for Rust"),
+            (
+                "https://other.example",
+                "A long page that mentions a runtime once among many other words",
+            ),
+        ] {
+            let page = crate::page_content::PageText {
+                description: description.to_string(),
+                ..Default::default()
+            };
+            store.save(url, &page, &[], 1).expect("save");
         }
+
+        let ids = |filters: &BookmarkFilters| -> Vec<String> {
+            index
+                .search_bookmarks_fts_filtered("runtime", filters, 10)
+                .expect("search")
+                .expect("fts")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+        assert_eq!(ids(&BookmarkFilters::default()), vec!["2"]);
+
+        index.attach_content(&content_db).expect("attach");
+        assert_eq!(ids(&BookmarkFilters::default()), vec!["2", "1", "3"]);
+        let edge_only = BookmarkFilters {
+            browsers: vec!["edge".into()],
+            ..Default::default()
+        };
+        assert_eq!(ids(&edge_only), vec!["3"]);
+
+        // 备注命中排在正文命中之前
+        let tags_db = dir.path().join("tags.db");
+        let tags = crate::tags::TagManager::new(tags_db.clone()).expect("tags");
+        tags.set_note(
+            &crate::tags::bookmark_key("https://other.example"),
+            "https://other.example",
+            "runtime benchmarks",
+            &[],
+        )
+        .expect("note");
+        index.attach_tags(&tags_db).expect("attach tags");
+        assert_eq!(ids(&BookmarkFilters::default()), vec!["2", "3", "1"]);
+
+        // 排除词看书签本身，且只作用于所在的组
+        let query_ids = |query: &str| -> Vec<String> {
+            index
+                .search_bookmarks_fts(query, 10)
+                .expect("search")
+                .expect("fts")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+        assert_eq!(query_ids("runtime -tokio"), vec!["2", "3"]);
+        assert_eq!(
+            query_ids("(runtime -tokio) OR asynchronous"),
+            vec!["2", "3", "1"]
+        );
     }
 
     #[test]
-    fn replace_bookmarks_builds_index_and_searches() {
+    fn pinned_bookmarks_lead_listing_and_fts_results() {
         let dir = tempdir().expect("tempdir");
-        let db_path = dir.path().join("bookmarks.db");
-        let index = BookmarkIndex::new(db_path).expect("index");
-
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
         let bookmarks = vec![
-            sample_bookmark("1", "Rust Lang", "https://rust-lang.org", Some("Work/Docs")),
-            sample_bookmark("2", "Example", "https://example.com", Some("Play/Read")),
+            sample_bookmark("1", "Rust", "https://rust-lang.org", Some("Dev")),
+            sample_bookmark(
+                "2",
+                "Async book",
+                "https://rust-lang.github.io/async-book",
+                Some("Dev"),
+            ),
+            sample_bookmark("3", "Tokio", "https://tokio.rs", None),
         ];
-
         index
             .replace_bookmarks(&bookmarks, "fp-1")
             .expect("replace");
-        assert!(!index.bookmarks_need_refresh("fp-1").expect("fingerprint"));
-        assert_eq!(index.get_total_bookmarks().expect("count"), 2);
+
+        let tags_db = dir.path().join("tags.db");
+        let tags = crate::tags::TagManager::new(tags_db.clone()).expect("tags");
+        tags.pin(
+            &crate::tags::bookmark_key("https://rust-lang.github.io/async-book/"),
+            "https://rust-lang.github.io/async-book/",
+        )
+        .expect("pin");
+
+        let ids = |bookmarks: Vec<ChromeBookmark>| -> Vec<String> {
+            bookmarks.into_iter().map(|bookmark| bookmark.id).collect()
+        };
+        assert!(index.pinned_ids().expect("pinned").is_empty());
+        assert_eq!(
+            ids(index.list_bookmarks(10).expect("list")),
+            ["1", "2", "3"]
+        );
+
+        index.attach_tags(&tags_db).expect("attach");
+        assert_eq!(
+            index.pinned_ids().expect("pinned"),
+            HashSet::from(["2".to_string()])
+        );
+        assert_eq!(
+            ids(index.list_bookmarks(10).expect("list")),
+            ["2", "1", "3"]
+        );
+        let dev = BookmarkFilters {
+            folders: vec!["dev".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(index.list_bookmarks_filtered(&dev, 10).expect("list")),
+            ["2", "1"]
+        );
 
         let found = index
             .search_bookmarks_fts("rust", 10)
-            .expect("fts")
-            .expect("enabled");
-        assert_eq!(found.len(), 1);
-        assert_eq!(found[0].id, "1");
+            .expect("search")
+            .expect("fts");
+        assert_eq!(ids(found), ["2", "1"]);
+        let found = index
+            .search_bookmarks_fts_filtered("rust", &dev, 1)
+            .expect("search")
+            .expect("fts");
+        assert_eq!(ids(found), ["2"]);
     }
 
     #[test]
-    fn list_bookmarks_by_folder_filters_supports_hierarchy_like_matching() {
+    fn negated_terms_are_excluded_from_fts_results() {
         let dir = tempdir().expect("tempdir");
-        let db_path = dir.path().join("bookmarks.db");
-        let index = BookmarkIndex::new(db_path).expect("index");
-
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
         let bookmarks = vec![
-            sample_bookmark(
-                "1",
-                "Rust",
-                "https://rust-lang.org",
-                Some("Root/Work/Project/Rust"),
-            ),
-            sample_bookmark("2", "Music", "https://music.example", Some("Root/Play")),
+            sample_bookmark("1", "Rust", "https://rust-lang.org", Some("Dev")),
+            sample_bookmark("2", "Rust game", "https://rust.facepunch.com", None),
+            sample_bookmark("3", "Rust jam", "https://itch.io/jam", Some("Games")),
         ];
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
+
+        assert!(build_fts_query("-game", &[]).is_none());
+        let ids = |query: &str| -> Vec<String> {
+            index
+                .search_bookmarks_fts(query, 10)
+                .expect("search")
+                .expect("fts")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+        assert_eq!(ids("rust -game"), ["1"]);
+        assert_eq!(ids("rust -facepunch").len(), 2);
+    }
 
+    #[test]
+    fn negated_terms_mean_the_same_in_fts_and_memory() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        let bookmarks = vec![
+            sample_bookmark("1", "Rust endgame", "https://example.com/a", None),
+            sample_bookmark("2", "Rust games", "https://example.com/b", None),
+            sample_bookmark("3", "Rust", "https://example.com/game-jam", None),
+            sample_bookmark("4", "Rust", "https://example.com/c", Some("Gamedev")),
+            sample_bookmark("5", "Rust gam jam", "https://example.com/d", None),
+        ];
         index
             .replace_bookmarks(&bookmarks, "fp-1")
             .expect("replace");
 
-        let filtered = index
-            .list_bookmarks_by_folder_filters(&["work/project".into()], 20)
-            .expect("filter");
-        assert_eq!(filtered.len(), 1);
-        assert_eq!(filtered[0].id, "1");
+        let fts_ids = |query: &str| -> Vec<String> {
+            let mut ids: Vec<String> = index
+                .search_bookmarks_fts(query, 10)
+                .expect("search")
+                .expect("fts")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let searcher = crate::searcher::BookmarkSearcher::new();
+        for query in ["rust -game", "rust -\"game jam\"", "rust -gam"] {
+            for fuzzy in [false, true] {
+                let mut memory: Vec<String> = searcher
+                    .search(&bookmarks, query, &[], fuzzy, 10)
+                    .into_iter()
+                    .map(|result| result.bookmark.id)
+                    .collect();
+                memory.sort();
+                assert_eq!(memory, fts_ids(query), "{query}");
+            }
+        }
+        assert_eq!(fts_ids("rust -game"), ["1", "5"]);
     }
 
     #[test]
-    fn search_bookmarks_fts_with_folders_applies_filter() {
+    fn or_groups_are_alternatives_in_fts_results() {
         let dir = tempdir().expect("tempdir");
-        let db_path = dir.path().join("bookmarks.db");
-        let index = BookmarkIndex::new(db_path).expect("index");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
+        let bookmarks = vec![
+            sample_bookmark("1", "Rust book", "https://doc.rust-lang.org/book", None),
+            sample_bookmark("2", "Go tour", "https://go.dev/tour", None),
+            sample_bookmark("3", "Tokio", "https://tokio.rs", None),
+            sample_bookmark("4", "Rust async", "https://rust-lang.github.io/async", None),
+        ];
+        index
+            .replace_bookmarks(&bookmarks, "fp-1")
+            .expect("replace");
 
+        assert_eq!(
+            build_fts_query("go OR tokio -rs", &[]).as_deref(),
+            Some(
+                "((\"go\"*) OR compact : \"go\"*) OR \
+                 (((\"tokio\"*) OR compact : \"tokio\"*) NOT ({name url folder_path} : \"rs\"*))"
+            )
+        );
+        let ids = |query: &str| -> Vec<String> {
+            let mut ids: Vec<String> = index
+                .search_bookmarks_fts(query, 10)
+                .expect("search")
+                .expect("fts")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids("tour OR tokio"), ["2", "3"]);
+        assert_eq!(ids("rust (book OR async)"), ["1", "4"]);
+        assert_eq!(ids("rust (tour OR tokio)"), Vec::<String>::new());
+        // 排除词只作用于所在的组
+        assert_eq!(ids("(rust -async) OR async"), ["1", "4"]);
+        assert_eq!(ids("(rust -async) OR tokio"), ["1", "3"]);
+        assert!(build_fts_query("rust OR -async", &[]).is_none());
+    }
+
+    #[test]
+    fn quoted_phrases_match_in_order_in_fts() {
+        let dir = tempdir().expect("tempdir");
+        let index = BookmarkIndex::new(dir.path().join("bookmarks.db")).expect("index");
         let bookmarks = vec![
             sample_bookmark(
                 "1",
-                "Rust Book",
-                "https://doc.rust-lang.org",
-                Some("Root/Work/Docs"),
+                "The Rust Async Book",
+                "https://rust-lang.github.io",
+                None,
             ),
             sample_bookmark(
                 "2",
-                "Rust Game",
-                "https://game.example",
-                Some("Root/Play/Games"),
+                "Async in Rust: a book",
+                "https://example.com/async",
+                None,
             ),
         ];
-
         index
             .replace_bookmarks(&bookmarks, "fp-1")
             .expect("replace");
 
-        let filtered = index
-            .search_bookmarks_fts_with_folders("rust", &["work".into()], 20)
-            .expect("fts")
-            .expect("enabled");
-
-        assert_eq!(filtered.len(), 1);
-        assert_eq!(filtered[0].id, "1");
+        assert_eq!(
+            build_fts_query("\"rust async book\" -\"a book\"", &[]).as_deref(),
+            Some("(\"rust async book\") NOT ({name url folder_path} : \"a book\")")
+        );
+        let ids = |query: &str| -> Vec<String> {
+            let mut ids: Vec<String> = index
+                .search_bookmarks_fts(query, 10)
+                .expect("search")
+                .expect("fts")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids("rust async book"), ["1", "2"]);
+        assert_eq!(ids("\"rust async book\""), ["1"]);
+        assert_eq!(ids("\"async in\" book"), ["2"]);
+        assert_eq!(ids("book -\"async book\""), ["2"]);
     }
 
     #[test]
-    fn clear_bookmarks_index_resets_data() {
+    fn pasted_urls_match_by_normalized_url() {
         let dir = tempdir().expect("tempdir");
         let db_path = dir.path().join("bookmarks.db");
-        let index = BookmarkIndex::new(db_path).expect("index");
+        {
+            let index = BookmarkIndex::new(db_path.clone()).expect("index");
+            let mut bookmarks = vec![
+                sample_bookmark(
+                    "1",
+                    "Karte",
+                    "https://xn--mnchen-3ya.de/karte?utm_source=x",
+                    None,
+                ),
+                sample_bookmark("2", "Docs", "https://docs.rs/serde", None),
+            ];
+            bookmarks[1].browser = "edge".into();
+            index
+                .replace_bookmarks(&bookmarks, "fp-1")
+                .expect("replace");
+            index
+                .conn
+                .execute_batch(
+                    "DROP INDEX idx_bookmarks_normalized_url;
+                     ALTER TABLE bookmarks DROP COLUMN normalized_url;
+                     UPDATE bookmarks SET tag_key = 'guid:stale';
+                     PRAGMA user_version = 3;",
+                )
+                .expect("downgrade");
+        }
 
-        let bookmarks = vec![sample_bookmark(
-            "1",
-            "Rust",
-            "https://rust-lang.org",
-            Some("Root/Work"),
-        )];
-        index
-            .replace_bookmarks(&bookmarks, "fp-1")
-            .expect("replace");
+        let index = BookmarkIndex::new(db_path).expect("reopen");
+        let tag_key: String = index
+            .conn
+            .query_row("SELECT tag_key FROM bookmarks WHERE id = '2'", [], |row| {
+                row.get(0)
+            })
+            .expect("tag key");
+        assert_eq!(tag_key, bookmark_key("https://docs.rs/serde"));
+        assert_eq!(index.bookmarks_fingerprint().expect("meta"), None);
+        let ids = |url: &str, filters: &BookmarkFilters| -> Vec<String> {
+            index
+                .bookmarks_with_url(url, filters)
+                .expect("lookup")
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+        let all = BookmarkFilters::default();
+        assert_eq!(
+            ids("https://MÜNCHEN.de:443/karte?fbclid=1", &all),
+            vec!["1"]
+        );
+        assert_eq!(ids("https://docs.rs/serde#derive", &all), vec!["2"]);
+        assert!(ids("https://docs.rs/tokio", &all).is_empty());
+        let chrome_only = BookmarkFilters {
+            browsers: vec!["chrome".into()],
+            ..Default::default()
+        };
+        assert!(ids("https://docs.rs/serde", &chrome_only).is_empty());
+    }
 
-        index.clear_bookmarks_index().expect("clear");
-        assert_eq!(index.get_total_bookmarks().expect("count"), 0);
-        assert!(index.bookmarks_need_refresh("fp-1").expect("refresh"));
+    #[test]
+    fn schema_version_is_recorded_and_failed_migration_rebuilds_index() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("bookmarks.db");
+        {
+            let conn = Connection::open(&db_path).expect("open");
+            conn.execute_batch(
+                "CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+                 CREATE TABLE bookmark_variants (id TEXT);
+                 CREATE TABLE local_bookmarks (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    url TEXT NOT NULL UNIQUE,
+                    name TEXT NOT NULL,
+                    folder_path TEXT,
+                    date_added TEXT NOT NULL
+                 );
+                 INSERT INTO local_bookmarks (url, name, date_added)
+                    VALUES ('https://kept.example', 'Kept', '0');
+                 INSERT INTO meta VALUES ('bookmarks_fingerprint', 'fp-old');",
+            )
+            .expect("broken schema");
+        }
+
+        let index = BookmarkIndex::new(db_path.clone()).expect("index");
+        assert_eq!(
+            schema::user_version(&index.conn).expect("version"),
+            INDEX_MIGRATIONS.len() as i32
+        );
+        assert!(index.bookmarks_need_refresh("fp-old").expect("refresh"));
+        let local = index.local_bookmarks().expect("local");
+        assert_eq!(local.len(), 1);
+        assert_eq!(local[0].url, "https://kept.example");
+        drop(index);
+
+        let reopened = BookmarkIndex::new(db_path).expect("reopen");
+        assert_eq!(reopened.local_bookmarks().expect("local").len(), 1);
     }
 }
@@ -0,0 +1,317 @@
+use rusqlite::{params, Connection, Result};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 失败达到该次数后不再重试
+pub const MAX_ATTEMPTS: u32 = 5;
+const BACKOFF_BASE_SECS: u64 = 300;
+const BACKOFF_MAX_SECS: u64 = 24 * 3600;
+/// 领取后的租约：进程崩溃时任务在租约过期后重新可领取，并发的 run-jobs 不会重复领取
+const LEASE_SECS: u64 = 600;
+
+/// 后台任务类型；新功能（favicon、内容抓取等）在这里加类型并在 `run-jobs` 中分派
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JobKind {
+    LinkCheck,
+    /// target 为站点主机名
+    Favicon,
+}
+
+impl JobKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobKind::LinkCheck => "link_check",
+            JobKind::Favicon => "favicon",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "link_check" => Some(JobKind::LinkCheck),
+            "favicon" => Some(JobKind::Favicon),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub id: i64,
+    pub kind: JobKind,
+    pub target: String,
+    pub attempts: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobCounts {
+    pub queued: usize,
+    pub due: usize,
+    pub failed: usize,
+}
+
+/// 第 n 次失败后的等待时间：5 分钟起指数增长，最长 1 天
+pub fn backoff_secs(attempts: u32) -> u64 {
+    let exponent = attempts.saturating_sub(1).min(16);
+    BACKOFF_BASE_SECS
+        .saturating_mul(1 << exponent)
+        .min(BACKOFF_MAX_SECS)
+}
+
+pub struct JobQueue {
+    conn: Connection,
+}
+
+impl JobQueue {
+    pub fn new(db_path: PathBuf) -> Result<Self> {
+        let conn = Connection::open(db_path)?;
+        let _ = conn.busy_timeout(Duration::from_millis(500));
+
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;",
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                target TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'queued',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                run_after INTEGER NOT NULL,
+                last_error TEXT,
+                updated_at INTEGER NOT NULL,
+                UNIQUE (kind, target)
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_jobs_due ON jobs(status, run_after)",
+            [],
+        )?;
+
+        Ok(Self { conn })
+    }
+
+    /// 加入队列；已在队列中的任务保持原样，已完成或已放弃的任务重新排队。返回是否新排队
+    pub fn enqueue(&self, kind: JobKind, target: &str, now: u64) -> Result<bool> {
+        let changed = self.conn.execute(
+            "INSERT INTO jobs (kind, target, status, attempts, run_after, updated_at)
+             VALUES (?1, ?2, 'queued', 0, ?3, ?3)
+             ON CONFLICT(kind, target) DO UPDATE SET
+                status = 'queued',
+                attempts = 0,
+                run_after = excluded.run_after,
+                last_error = NULL,
+                updated_at = excluded.updated_at
+             WHERE jobs.status != 'queued'",
+            params![kind.as_str(), target, now as i64],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// 只排队从未出现过的任务，或已完成但需要重做的任务；已放弃的任务保持放弃
+    /// 用于每次刷新后自动补齐的任务，避免反复请求注定失败的目标
+    pub fn enqueue_missing(&self, kind: JobKind, target: &str, now: u64) -> Result<bool> {
+        let changed = self.conn.execute(
+            "INSERT INTO jobs (kind, target, status, attempts, run_after, updated_at)
+             VALUES (?1, ?2, 'queued', 0, ?3, ?3)
+             ON CONFLICT(kind, target) DO UPDATE SET
+                status = 'queued',
+                attempts = 0,
+                run_after = excluded.run_after,
+                last_error = NULL,
+                updated_at = excluded.updated_at
+             WHERE jobs.status = 'done'",
+            params![kind.as_str(), target, now as i64],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// 领取到期任务（按到期时间先后）并设置租约；`kind` 为 None 时领取全部类型
+    pub fn claim_due(&self, now: u64, limit: usize, kind: Option<JobKind>) -> Result<Vec<Job>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let jobs = {
+            let mut stmt = tx.prepare(
+                "SELECT id, kind, target, attempts FROM jobs
+                 WHERE status = 'queued' AND run_after <= ?1 AND (?3 IS NULL OR kind = ?3)
+                 ORDER BY run_after, id
+                 LIMIT ?2",
+            )?;
+            let kind = kind.map(JobKind::as_str);
+            let rows = stmt.query_map(params![now as i64, limit as i64, kind], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, u32>(3)?,
+                ))
+            })?;
+
+            let mut jobs = Vec::new();
+            for row in rows {
+                let (id, kind, target, attempts) = row?;
+                // 未知类型来自更新版本写入的任务，留给能处理它的版本
+                if let Some(kind) = JobKind::parse(&kind) {
+                    jobs.push(Job {
+                        id,
+                        kind,
+                        target,
+                        attempts,
+                    });
+                }
+            }
+            jobs
+        };
+        for job in &jobs {
+            tx.execute(
+                "UPDATE jobs SET run_after = ?2, updated_at = ?3 WHERE id = ?1",
+                params![job.id, (now + LEASE_SECS) as i64, now as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(jobs)
+    }
+
+    pub fn complete(&self, id: i64, now: u64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE jobs SET status = 'done', last_error = NULL, updated_at = ?2 WHERE id = ?1",
+            params![id, now as i64],
+        )?;
+        Ok(())
+    }
+
+    /// 记录失败：未超过重试上限时按退避时间重新排队，否则标记为 failed
+    pub fn fail(&self, job: &Job, error: &str, now: u64) -> Result<()> {
+        let attempts = job.attempts + 1;
+        let (status, run_after) = if attempts >= MAX_ATTEMPTS {
+            ("failed", now)
+        } else {
+            ("queued", now + backoff_secs(attempts))
+        };
+        self.conn.execute(
+            "UPDATE jobs SET status = ?2, attempts = ?3, run_after = ?4, last_error = ?5, updated_at = ?6
+             WHERE id = ?1",
+            params![job.id, status, attempts, run_after as i64, error, now as i64],
+        )?;
+        Ok(())
+    }
+
+    pub fn counts(&self, now: u64) -> Result<JobCounts> {
+        self.conn.query_row(
+            "SELECT
+                COALESCE(SUM(status = 'queued'), 0),
+                COALESCE(SUM(status = 'queued' AND run_after <= ?1), 0),
+                COALESCE(SUM(status = 'failed'), 0)
+             FROM jobs",
+            params![now as i64],
+            |row| {
+                Ok(JobCounts {
+                    queued: row.get::<_, i64>(0)? as usize,
+                    due: row.get::<_, i64>(1)? as usize,
+                    failed: row.get::<_, i64>(2)? as usize,
+                })
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn queue() -> (tempfile::TempDir, JobQueue) {
+        let dir = tempdir().expect("tempdir");
+        let queue = JobQueue::new(dir.path().join("jobs.db")).expect("queue");
+        (dir, queue)
+    }
+
+    #[test]
+    fn claimed_jobs_are_leased_until_completed() {
+        let (_dir, queue) = queue();
+        assert!(queue
+            .enqueue(JobKind::LinkCheck, "https://a.example", 100)
+            .expect("enqueue"));
+        assert!(!queue
+            .enqueue(JobKind::LinkCheck, "https://a.example", 100)
+            .expect("enqueue"));
+
+        let jobs = queue.claim_due(100, 10, None).expect("claim");
+        assert_eq!(jobs.len(), 1);
+        assert!(queue.claim_due(100, 10, None).expect("claim").is_empty());
+        assert_eq!(
+            queue
+                .claim_due(100 + LEASE_SECS, 10, None)
+                .expect("claim")
+                .len(),
+            1
+        );
+
+        queue.complete(jobs[0].id, 200).expect("complete");
+        assert_eq!(queue.counts(10_000).expect("counts"), JobCounts::default());
+        // 完成后可重新排队
+        assert!(queue
+            .enqueue(JobKind::LinkCheck, "https://a.example", 300)
+            .expect("enqueue"));
+    }
+
+    #[test]
+    fn failures_back_off_then_give_up() {
+        let (_dir, queue) = queue();
+        queue
+            .enqueue(JobKind::LinkCheck, "https://a.example", 0)
+            .expect("enqueue");
+
+        let mut now = 0;
+        for attempt in 1..=MAX_ATTEMPTS {
+            let job = queue.claim_due(now, 1, None).expect("claim").remove(0);
+            assert_eq!(job.attempts, attempt - 1);
+            queue.fail(&job, "timeout", now).expect("fail");
+            now += backoff_secs(attempt);
+        }
+
+        let counts = queue.counts(now).expect("counts");
+        assert_eq!((counts.queued, counts.failed), (0, 1));
+        let last_error: String = queue
+            .conn
+            .query_row("SELECT last_error FROM jobs", [], |row| row.get(0))
+            .expect("last error");
+        assert_eq!(last_error, "timeout");
+    }
+
+    #[test]
+    fn enqueue_missing_keeps_given_up_jobs_and_filters_by_kind() {
+        let (_dir, queue) = queue();
+        queue
+            .enqueue(JobKind::LinkCheck, "https://a.example", 0)
+            .expect("enqueue");
+        assert!(queue
+            .enqueue_missing(JobKind::Favicon, "a.example", 0)
+            .expect("enqueue"));
+
+        let jobs = queue
+            .claim_due(0, 10, Some(JobKind::Favicon))
+            .expect("claim");
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].target, "a.example");
+
+        let mut job = jobs[0].clone();
+        job.attempts = MAX_ATTEMPTS - 1;
+        queue.fail(&job, "HTTP 404", 0).expect("fail");
+        assert!(!queue
+            .enqueue_missing(JobKind::Favicon, "a.example", 1)
+            .expect("enqueue"));
+        assert!(queue
+            .claim_due(10_000, 10, Some(JobKind::Favicon))
+            .expect("claim")
+            .is_empty());
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        assert_eq!(backoff_secs(1), 300);
+        assert_eq!(backoff_secs(2), 600);
+        assert_eq!(backoff_secs(30), BACKOFF_MAX_SECS);
+    }
+}
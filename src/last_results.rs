@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+
+/// 上一次搜索输出的 Alfred JSON（`<缓存目录>/last_results.json`），供 `last` 原样重放
+pub struct LastResults {
+    path: PathBuf,
+    query_path: PathBuf,
+}
+
+impl LastResults {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            path: cache_dir.join("last_results.json"),
+            query_path: cache_dir.join("last_query.txt"),
+        }
+    }
+
+    /// 产生这些结果的查询，动作菜单据此记下“返回”的去处
+    pub fn save_query(&self, query: &str) -> std::io::Result<()> {
+        std::fs::write(&self.query_path, query)
+    }
+
+    pub fn load_query(&self) -> Option<String> {
+        std::fs::read_to_string(&self.query_path)
+            .ok()
+            .filter(|query| !query.trim().is_empty())
+    }
+
+    /// 先写临时文件再替换，Alfred 连续触发搜索时不会读到半截内容
+    pub fn save(&self, payload: &[u8]) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, payload)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+
+    pub fn load(&self) -> Option<Vec<u8>> {
+        std::fs::read(&self.path)
+            .ok()
+            .filter(|payload| !payload.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn saved_payload_is_replayed_verbatim() {
+        let dir = tempdir().expect("tempdir");
+        let last = LastResults::new(dir.path());
+        assert!(last.load().is_none());
+
+        last.save(br#"{"items":[{"title":"Rust"}]}"#).expect("save");
+        last.save(br#"{"items":[{"title":"Tokio"}]}"#)
+            .expect("save");
+        assert_eq!(
+            last.load().expect("load"),
+            br#"{"items":[{"title":"Tokio"}]}"#.to_vec()
+        );
+
+        assert!(last.load_query().is_none());
+        last.save_query("tokio").expect("save query");
+        assert_eq!(last.load_query().as_deref(), Some("tokio"));
+    }
+}
@@ -0,0 +1,261 @@
+use rusqlite::{params, Connection, Result};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// 最多保留的搜索样本数，超出后删除最旧的
+const MAX_SAMPLES: i64 = 2_000;
+
+/// 一次搜索的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// 检查书签文件是否变化，必要时刷新索引
+    Index,
+    /// 查询索引并排序
+    Query,
+    /// 生成并输出 Alfred 条目
+    Render,
+}
+
+/// 一次搜索各阶段的耗时（微秒）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchTiming {
+    pub index_us: u64,
+    pub query_us: u64,
+    pub render_us: u64,
+    pub total_us: u64,
+    pub results: usize,
+}
+
+/// 按阶段计时：每次 `lap` 把距上一次的耗时计入该阶段
+pub struct SearchTimer {
+    started: Instant,
+    last: Instant,
+    timing: SearchTiming,
+}
+
+impl SearchTimer {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            last: now,
+            timing: SearchTiming::default(),
+        }
+    }
+
+    pub fn lap(&mut self, phase: Phase) {
+        let now = Instant::now();
+        let micros = duration_us(now - self.last);
+        match phase {
+            Phase::Index => self.timing.index_us += micros,
+            Phase::Query => self.timing.query_us += micros,
+            Phase::Render => self.timing.render_us += micros,
+        }
+        self.last = now;
+    }
+
+    pub fn finish(self, results: usize) -> SearchTiming {
+        SearchTiming {
+            total_us: duration_us(self.started.elapsed()),
+            results,
+            ..self.timing
+        }
+    }
+}
+
+fn duration_us(duration: Duration) -> u64 {
+    duration.as_micros().min(u64::MAX as u128) as u64
+}
+
+/// 单个阶段的耗时分布（毫秒）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseLatency {
+    pub phase: &'static str,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/// `stats latency` 的汇总
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencySummary {
+    pub samples: usize,
+    /// 最早 / 最近一次样本的 Unix 秒
+    pub first_at: Option<u64>,
+    pub last_at: Option<u64>,
+    pub phases: Vec<PhaseLatency>,
+}
+
+/// 搜索耗时记录（latency.db），只保存在本地
+pub struct LatencyStore {
+    conn: Connection,
+}
+
+impl LatencyStore {
+    pub fn new(db_path: PathBuf) -> Result<Self> {
+        let conn = Connection::open(db_path)?;
+        let _ = conn.busy_timeout(Duration::from_millis(500));
+
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;",
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS search_timings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                recorded_at INTEGER NOT NULL,
+                index_us INTEGER NOT NULL,
+                query_us INTEGER NOT NULL,
+                render_us INTEGER NOT NULL,
+                total_us INTEGER NOT NULL,
+                results INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(Self { conn })
+    }
+
+    pub fn record(&self, timing: &SearchTiming, now: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO search_timings
+                (recorded_at, index_us, query_us, render_us, total_us, results)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                now as i64,
+                timing.index_us as i64,
+                timing.query_us as i64,
+                timing.render_us as i64,
+                timing.total_us as i64,
+                timing.results as i64
+            ],
+        )?;
+        self.conn.execute(
+            "DELETE FROM search_timings WHERE id <= (SELECT MAX(id) FROM search_timings) - ?1",
+            params![MAX_SAMPLES],
+        )?;
+        Ok(())
+    }
+
+    pub fn summary(&self) -> Result<LatencySummary> {
+        let mut stmt = self.conn.prepare(
+            "SELECT recorded_at, index_us, query_us, render_us, total_us FROM search_timings",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    [
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, i64>(3)?,
+                        row.get::<_, i64>(4)?,
+                    ],
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let phases = ["index", "query", "render", "total"]
+            .iter()
+            .enumerate()
+            .map(|(column, phase)| {
+                let mut values: Vec<i64> = rows.iter().map(|(_, us)| us[column]).collect();
+                values.sort_unstable();
+                PhaseLatency {
+                    phase,
+                    p50_ms: micros_to_ms(percentile(&values, 50)),
+                    p95_ms: micros_to_ms(percentile(&values, 95)),
+                    max_ms: micros_to_ms(values.last().copied().unwrap_or(0)),
+                }
+            })
+            .collect();
+        Ok(LatencySummary {
+            samples: rows.len(),
+            first_at: rows.iter().map(|(at, _)| *at as u64).min(),
+            last_at: rows.iter().map(|(at, _)| *at as u64).max(),
+            phases,
+        })
+    }
+}
+
+/// 最近秩法：已排序样本中第 ⌈p% × n⌉ 个
+fn percentile(sorted: &[i64], p: usize) -> i64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn micros_to_ms(micros: i64) -> f64 {
+    (micros as f64 / 100.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn timing(total_us: u64) -> SearchTiming {
+        SearchTiming {
+            index_us: 100,
+            query_us: total_us / 2,
+            render_us: 50,
+            total_us,
+            results: 3,
+        }
+    }
+
+    #[test]
+    fn summary_reports_percentiles_per_phase() {
+        let dir = tempdir().expect("tempdir");
+        let store = LatencyStore::new(dir.path().join("latency.db")).expect("store");
+        assert_eq!(store.summary().expect("summary").samples, 0);
+
+        for (at, ms) in (1..=20).enumerate() {
+            store
+                .record(&timing(ms * 1_000), at as u64 + 100)
+                .expect("record");
+        }
+
+        let summary = store.summary().expect("summary");
+        assert_eq!(summary.samples, 20);
+        assert_eq!((summary.first_at, summary.last_at), (Some(100), Some(119)));
+        let total = summary
+            .phases
+            .iter()
+            .find(|phase| phase.phase == "total")
+            .expect("total");
+        assert_eq!(
+            (total.p50_ms, total.p95_ms, total.max_ms),
+            (10.0, 19.0, 20.0)
+        );
+        let index = &summary.phases[0];
+        assert_eq!((index.phase, index.p95_ms), ("index", 0.1));
+    }
+
+    #[test]
+    fn old_samples_are_pruned() {
+        let dir = tempdir().expect("tempdir");
+        let store = LatencyStore::new(dir.path().join("latency.db")).expect("store");
+        for at in 0..(MAX_SAMPLES as u64 + 5) {
+            store.record(&timing(1_000), at).expect("record");
+        }
+        let summary = store.summary().expect("summary");
+        assert_eq!(summary.samples, MAX_SAMPLES as usize);
+        assert_eq!(summary.first_at, Some(5));
+    }
+
+    #[test]
+    fn timer_splits_elapsed_time_into_phases() {
+        let mut timer = SearchTimer::start();
+        timer.lap(Phase::Index);
+        timer.lap(Phase::Query);
+        timer.lap(Phase::Render);
+        let timing = timer.finish(7);
+        assert_eq!(timing.results, 7);
+        assert!(timing.total_us >= timing.index_us + timing.query_us + timing.render_us);
+    }
+}
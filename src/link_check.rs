@@ -0,0 +1,700 @@
+use rusqlite::{params, Connection, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAX_REDIRECT_HOPS: usize = 10;
+
+/// 单次 HTTP 请求的结果（不自动跟随重定向）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeHop {
+    pub status: u16,
+    pub location: Option<String>,
+}
+
+pub trait HttpProbe {
+    fn probe(&self, url: &str) -> std::result::Result<ProbeHop, String>;
+}
+
+/// 基于系统 `curl` 的探测实现，避免引入 HTTP 客户端依赖
+pub struct CurlProbe {
+    timeout: Duration,
+}
+
+impl CurlProbe {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+
+    fn run(&self, url: &str, head: bool) -> std::result::Result<ProbeHop, String> {
+        let mut command = Command::new("curl");
+        command
+            .arg("-sS")
+            .arg("-o")
+            .arg("/dev/null")
+            .arg("--max-time")
+            .arg(self.timeout.as_secs().max(1).to_string())
+            .arg("-A")
+            .arg("Mozilla/5.0 (Macintosh) alfred-chrome-bookmarks")
+            .arg("-w")
+            .arg("%{http_code}\n%{redirect_url}");
+        if head {
+            command.arg("-I");
+        }
+        command.arg(url);
+
+        let output = command.output().map_err(|err| err.to_string())?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+        let status = lines
+            .next()
+            .and_then(|line| line.trim().parse::<u16>().ok())
+            .unwrap_or(0);
+
+        if status == 0 {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            return Err(if stderr.is_empty() {
+                "连接失败".to_string()
+            } else {
+                stderr
+            });
+        }
+
+        let location = lines
+            .next()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string);
+
+        Ok(ProbeHop { status, location })
+    }
+}
+
+impl HttpProbe for CurlProbe {
+    fn probe(&self, url: &str) -> std::result::Result<ProbeHop, String> {
+        let hop = self.run(url, true)?;
+        // 部分站点拒绝 HEAD，退回 GET 再试一次
+        if matches!(hop.status, 403 | 405 | 501) {
+            return self.run(url, false);
+        }
+        Ok(hop)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedirectKind {
+    None,
+    Permanent,
+    Temporary,
+}
+
+impl RedirectKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            RedirectKind::None => "none",
+            RedirectKind::Permanent => "permanent",
+            RedirectKind::Temporary => "temporary",
+        }
+    }
+
+    fn from_str(value: &str) -> Self {
+        match value {
+            "permanent" => RedirectKind::Permanent,
+            "temporary" => RedirectKind::Temporary,
+            _ => RedirectKind::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LinkCheckResult {
+    pub url: String,
+    pub status: u16,
+    pub final_url: String,
+    pub redirect: RedirectKind,
+    pub error: Option<String>,
+    pub checked_at: u64,
+}
+
+impl LinkCheckResult {
+    pub fn is_dead(&self) -> bool {
+        self.error.is_some() || self.status == 0 || self.status >= 400
+    }
+
+    /// 所有跳转都是 301/308 且最终落地成功，才建议更新书签 URL
+    pub fn is_permanent_move(&self) -> bool {
+        self.redirect == RedirectKind::Permanent && !self.is_dead() && self.final_url != self.url
+    }
+}
+
+/// 定时扫描每次检查的链接数：按间隔推算一个周期内的执行次数，让全部链接在周期内轮完一遍
+pub fn scan_budget(total: usize, interval_secs: u64, period_secs: u64) -> usize {
+    let runs = (period_secs / interval_secs.max(1)).max(1) as usize;
+    total.div_ceil(runs)
+}
+
+/// 逐跳跟随重定向，记录最终 URL 以及整条链路是否全部为永久跳转
+pub fn resolve_link<P: HttpProbe + ?Sized>(probe: &P, url: &str) -> LinkCheckResult {
+    let mut current = url.to_string();
+    let mut redirect = RedirectKind::None;
+    let checked_at = now_secs();
+
+    for _ in 0..=MAX_REDIRECT_HOPS {
+        let hop = match probe.probe(&current) {
+            Ok(hop) => hop,
+            Err(err) => {
+                return LinkCheckResult {
+                    url: url.to_string(),
+                    status: 0,
+                    final_url: current,
+                    redirect,
+                    error: Some(err),
+                    checked_at,
+                };
+            }
+        };
+
+        let is_redirect = matches!(hop.status, 301 | 302 | 303 | 307 | 308);
+        let next = match (is_redirect, hop.location) {
+            (true, Some(location)) => resolve_location(&current, &location),
+            _ => {
+                return LinkCheckResult {
+                    url: url.to_string(),
+                    status: hop.status,
+                    final_url: current,
+                    redirect,
+                    error: None,
+                    checked_at,
+                };
+            }
+        };
+
+        redirect = match (redirect, hop.status) {
+            (RedirectKind::Temporary, _) => RedirectKind::Temporary,
+            (_, 301 | 308) => RedirectKind::Permanent,
+            _ => RedirectKind::Temporary,
+        };
+        current = next;
+    }
+
+    LinkCheckResult {
+        url: url.to_string(),
+        status: 0,
+        final_url: current,
+        redirect,
+        error: Some("重定向次数过多".to_string()),
+        checked_at,
+    }
+}
+
+/// 对 `http://` 链接探测其 `https://` 版本，非 http 链接返回 None
+pub fn check_https_upgrade<P: HttpProbe + ?Sized>(probe: &P, url: &str) -> Option<LinkCheckResult> {
+    let rest = url.strip_prefix("http://")?;
+    Some(resolve_link(probe, &format!("https://{}", rest)))
+}
+
+fn resolve_location(base: &str, location: &str) -> String {
+    if location.contains("://") {
+        return location.to_string();
+    }
+
+    let (scheme, rest) = base.split_once("://").unwrap_or(("https", base));
+    let origin_end = rest.find('/').unwrap_or(rest.len());
+    let origin = &rest[..origin_end];
+
+    if let Some(path) = location.strip_prefix("//") {
+        return format!("{}://{}", scheme, path);
+    }
+    if location.starts_with('/') {
+        return format!("{}://{}{}", scheme, origin, location);
+    }
+
+    let path = &rest[origin_end..];
+    let dir = match path.rfind('/') {
+        Some(idx) => &path[..=idx],
+        None => "/",
+    };
+    format!("{}://{}{}{}", scheme, origin, dir, location)
+}
+
+/// 卫生检查忽略规则：完整 URL 或域名（含子域名）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IgnoreRule {
+    Url(String),
+    Domain(String),
+}
+
+impl IgnoreRule {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+        if trimmed.contains("://") {
+            return Some(IgnoreRule::Url(trimmed.to_string()));
+        }
+
+        let domain = trimmed
+            .trim_start_matches("*.")
+            .trim_end_matches('/')
+            .to_ascii_lowercase();
+        if domain.is_empty() {
+            None
+        } else {
+            Some(IgnoreRule::Domain(domain))
+        }
+    }
+
+    pub fn pattern(&self) -> &str {
+        match self {
+            IgnoreRule::Url(url) => url,
+            IgnoreRule::Domain(domain) => domain,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            IgnoreRule::Url(_) => "url",
+            IgnoreRule::Domain(_) => "domain",
+        }
+    }
+
+    fn matches(&self, url: &str, host: Option<&str>) -> bool {
+        match self {
+            IgnoreRule::Url(pattern) => pattern == url,
+            IgnoreRule::Domain(domain) => host.is_some_and(|host| {
+                host == domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct IgnoreList {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreList {
+    pub fn new(rules: Vec<IgnoreRule>) -> Self {
+        Self { rules }
+    }
+
+    pub fn matches(&self, url: &str) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+        let host = crate::url_normalize::url_host(url);
+        self.rules
+            .iter()
+            .any(|rule| rule.matches(url, host.as_deref()))
+    }
+}
+
+/// 链接检查结果存储（独立于可重建的书签索引）
+pub struct LinkStore {
+    conn: Connection,
+}
+
+impl LinkStore {
+    pub fn new(db_path: PathBuf) -> Result<Self> {
+        let conn = Connection::open(db_path)?;
+        let _ = conn.busy_timeout(Duration::from_millis(500));
+
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;",
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS link_checks (
+                url TEXT PRIMARY KEY,
+                status INTEGER NOT NULL,
+                final_url TEXT NOT NULL,
+                redirect TEXT NOT NULL,
+                error TEXT,
+                checked_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS https_checks (
+                url TEXT PRIMARY KEY,
+                https_url TEXT NOT NULL,
+                upgradable INTEGER NOT NULL,
+                checked_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS ignore_rules (
+                pattern TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                added_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(Self { conn })
+    }
+
+    /// 添加忽略规则，已存在时返回 false
+    pub fn add_ignore(&self, rule: &IgnoreRule) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO ignore_rules (pattern, kind, added_at) VALUES (?1, ?2, ?3)",
+            params![rule.pattern(), rule.kind(), now_secs() as i64],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn remove_ignore(&self, rule: &IgnoreRule) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM ignore_rules WHERE pattern = ?1",
+            params![rule.pattern()],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn ignore_rules(&self) -> Result<Vec<IgnoreRule>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT pattern, kind FROM ignore_rules ORDER BY kind, pattern")?;
+        let rows = stmt.query_map([], |row| {
+            let pattern: String = row.get(0)?;
+            let kind: String = row.get(1)?;
+            Ok(if kind == "url" {
+                IgnoreRule::Url(pattern)
+            } else {
+                IgnoreRule::Domain(pattern)
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn ignore_list(&self) -> Result<IgnoreList> {
+        Ok(IgnoreList::new(self.ignore_rules()?))
+    }
+
+    /// 记录 http 链接的 https 探测结果；https 可正常访问时标记为可升级
+    pub fn record_https(&self, url: &str, https: &LinkCheckResult) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO https_checks (url, https_url, upgradable, checked_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(url) DO UPDATE SET
+                https_url = excluded.https_url,
+                upgradable = excluded.upgradable,
+                checked_at = excluded.checked_at",
+            params![url, https.url, !https.is_dead(), https.checked_at as i64],
+        )?;
+        Ok(())
+    }
+
+    /// 返回 http URL -> 可用的 https URL
+    pub fn https_upgrades(&self) -> Result<HashMap<String, String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT url, https_url FROM https_checks WHERE upgradable = 1")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    pub fn record(&self, result: &LinkCheckResult) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO link_checks (url, status, final_url, redirect, error, checked_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(url) DO UPDATE SET
+                status = excluded.status,
+                final_url = excluded.final_url,
+                redirect = excluded.redirect,
+                error = excluded.error,
+                checked_at = excluded.checked_at",
+            params![
+                result.url,
+                result.status,
+                result.final_url,
+                result.redirect.as_str(),
+                result.error,
+                result.checked_at as i64
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn last_checked(&self) -> Result<HashMap<String, u64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT url, checked_at FROM link_checks")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        rows.collect()
+    }
+
+    pub fn load_results(&self) -> Result<Vec<LinkCheckResult>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT url, status, final_url, redirect, error, checked_at
+             FROM link_checks
+             ORDER BY url",
+        )?;
+        let rows = stmt.query_map([], link_result_from_row)?;
+        rows.collect()
+    }
+
+    pub fn result_for(&self, url: &str) -> Result<Option<LinkCheckResult>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT url, status, final_url, redirect, error, checked_at
+             FROM link_checks
+             WHERE url = ?1",
+        )?;
+        let mut rows = stmt.query_map(params![url], link_result_from_row)?;
+        rows.next().transpose()
+    }
+
+    /// 返回 原URL -> 永久跳转后的最终URL
+    pub fn permanent_redirects(&self) -> Result<HashMap<String, String>> {
+        Ok(self
+            .load_results()?
+            .into_iter()
+            .filter(LinkCheckResult::is_permanent_move)
+            .map(|result| (result.url, result.final_url))
+            .collect())
+    }
+}
+
+fn link_result_from_row(row: &rusqlite::Row<'_>) -> Result<LinkCheckResult> {
+    let redirect: String = row.get(3)?;
+    Ok(LinkCheckResult {
+        url: row.get(0)?,
+        status: row.get(1)?,
+        final_url: row.get(2)?,
+        redirect: RedirectKind::from_str(&redirect),
+        error: row.get(4)?,
+        checked_at: row.get::<_, i64>(5)? as u64,
+    })
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    struct FakeProbe {
+        hops: HashMap<&'static str, ProbeHop>,
+    }
+
+    impl HttpProbe for FakeProbe {
+        fn probe(&self, url: &str) -> std::result::Result<ProbeHop, String> {
+            self.hops
+                .get(url)
+                .cloned()
+                .ok_or_else(|| "无法解析主机".to_string())
+        }
+    }
+
+    fn hop(status: u16, location: Option<&str>) -> ProbeHop {
+        ProbeHop {
+            status,
+            location: location.map(ToString::to_string),
+        }
+    }
+
+    #[test]
+    fn resolve_link_follows_permanent_redirect_chain() {
+        let probe = FakeProbe {
+            hops: HashMap::from([
+                (
+                    "http://old.example/a",
+                    hop(301, Some("https://old.example/a")),
+                ),
+                ("https://old.example/a", hop(308, Some("/b"))),
+                ("https://old.example/b", hop(200, None)),
+            ]),
+        };
+
+        let result = resolve_link(&probe, "http://old.example/a");
+        assert_eq!(result.status, 200);
+        assert_eq!(result.final_url, "https://old.example/b");
+        assert_eq!(result.redirect, RedirectKind::Permanent);
+        assert!(result.is_permanent_move());
+    }
+
+    #[test]
+    fn resolve_link_marks_mixed_chain_as_temporary() {
+        let probe = FakeProbe {
+            hops: HashMap::from([
+                ("https://a.example", hop(302, Some("https://b.example"))),
+                ("https://b.example", hop(301, Some("https://c.example"))),
+                ("https://c.example", hop(200, None)),
+            ]),
+        };
+
+        let result = resolve_link(&probe, "https://a.example");
+        assert_eq!(result.redirect, RedirectKind::Temporary);
+        assert!(!result.is_permanent_move());
+    }
+
+    #[test]
+    fn resolve_link_reports_errors_as_dead() {
+        let probe = FakeProbe {
+            hops: HashMap::from([("https://gone.example", hop(404, None))]),
+        };
+
+        assert!(resolve_link(&probe, "https://gone.example").is_dead());
+        assert!(resolve_link(&probe, "https://unknown.example").is_dead());
+    }
+
+    #[test]
+    fn https_upgrade_only_probes_plain_http_links() {
+        let probe = FakeProbe {
+            hops: HashMap::from([
+                ("https://plain.example/x", hop(200, None)),
+                ("https://broken.example", hop(500, None)),
+            ]),
+        };
+
+        let upgrade = check_https_upgrade(&probe, "http://plain.example/x").expect("http");
+        assert!(!upgrade.is_dead());
+        assert_eq!(upgrade.url, "https://plain.example/x");
+
+        let broken = check_https_upgrade(&probe, "http://broken.example").expect("http");
+        assert!(broken.is_dead());
+
+        assert!(check_https_upgrade(&probe, "https://plain.example/x").is_none());
+    }
+
+    #[test]
+    fn link_store_tracks_https_upgrades() {
+        let dir = tempdir().expect("tempdir");
+        let store = LinkStore::new(dir.path().join("links.db")).expect("store");
+        let ok = LinkCheckResult {
+            url: "https://a.example".into(),
+            status: 200,
+            final_url: "https://a.example".into(),
+            redirect: RedirectKind::None,
+            error: None,
+            checked_at: 1,
+        };
+        let failed = LinkCheckResult {
+            url: "https://b.example".into(),
+            status: 0,
+            error: Some("timeout".into()),
+            ..ok.clone()
+        };
+
+        store.record_https("http://a.example", &ok).expect("ok");
+        store
+            .record_https("http://b.example", &failed)
+            .expect("failed");
+
+        let upgrades = store.https_upgrades().expect("upgrades");
+        assert_eq!(upgrades.len(), 1);
+        assert_eq!(upgrades["http://a.example"], "https://a.example");
+    }
+
+    #[test]
+    fn ignore_rules_match_urls_and_subdomains() {
+        let list = IgnoreList::new(vec![
+            IgnoreRule::parse("*.Intranet.Example").expect("domain"),
+            IgnoreRule::parse("https://flaky.example/status").expect("url"),
+        ]);
+
+        assert!(list.matches("http://wiki.intranet.example/page"));
+        assert!(list.matches("https://intranet.example"));
+        assert!(!list.matches("https://notintranet.example"));
+        assert!(list.matches("https://flaky.example/status"));
+        assert!(!list.matches("https://flaky.example/other"));
+        assert!(IgnoreRule::parse("  ").is_none());
+    }
+
+    #[test]
+    fn link_store_persists_ignore_rules() {
+        let dir = tempdir().expect("tempdir");
+        let store = LinkStore::new(dir.path().join("links.db")).expect("store");
+        let rule = IgnoreRule::parse("intranet.example").expect("rule");
+
+        assert!(store.add_ignore(&rule).expect("add"));
+        assert!(!store.add_ignore(&rule).expect("add again"));
+        assert_eq!(store.ignore_rules().expect("rules"), vec![rule.clone()]);
+        assert!(store
+            .ignore_list()
+            .expect("list")
+            .matches("https://intranet.example/x"));
+
+        assert!(store.remove_ignore(&rule).expect("remove"));
+        assert!(store.ignore_rules().expect("rules").is_empty());
+    }
+
+    #[test]
+    fn scan_budget_covers_collection_within_period() {
+        let week = 7 * 24 * 3600;
+        // 每小时一次，一周 168 次
+        assert_eq!(scan_budget(1000, 3600, week), 6);
+        assert_eq!(scan_budget(0, 3600, week), 0);
+        // 间隔比周期还长时一次检查全部
+        assert_eq!(scan_budget(50, 2 * week, week), 50);
+    }
+
+    #[test]
+    fn resolve_location_handles_relative_targets() {
+        assert_eq!(
+            resolve_location("https://a.example/docs/page", "/new"),
+            "https://a.example/new"
+        );
+        assert_eq!(
+            resolve_location("https://a.example/docs/page", "next"),
+            "https://a.example/docs/next"
+        );
+        assert_eq!(
+            resolve_location("https://a.example", "//cdn.example/x"),
+            "https://cdn.example/x"
+        );
+    }
+
+    #[test]
+    fn link_store_returns_only_permanent_moves() {
+        let dir = tempdir().expect("tempdir");
+        let store = LinkStore::new(dir.path().join("links.db")).expect("store");
+
+        let moved = LinkCheckResult {
+            url: "http://a.example".into(),
+            status: 200,
+            final_url: "https://a.example".into(),
+            redirect: RedirectKind::Permanent,
+            error: None,
+            checked_at: 1,
+        };
+        let temporary = LinkCheckResult {
+            url: "https://b.example".into(),
+            redirect: RedirectKind::Temporary,
+            final_url: "https://b.example/login".into(),
+            ..moved.clone()
+        };
+
+        store.record(&moved).expect("record moved");
+        store.record(&temporary).expect("record temporary");
+
+        let redirects = store.permanent_redirects().expect("redirects");
+        assert_eq!(redirects.len(), 1);
+        assert_eq!(redirects["http://a.example"], "https://a.example");
+        assert_eq!(store.last_checked().expect("checked").len(), 2);
+        assert_eq!(
+            store.result_for("https://b.example").expect("result"),
+            Some(temporary)
+        );
+        assert_eq!(store.result_for("https://c.example").expect("result"), None);
+    }
+}
@@ -1,25 +1,93 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::{self, BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use clap::Parser;
 use serde::{Deserialize, Serialize};
-use structopt::StructOpt;
 use thiserror::Error;
 
+mod backup;
 mod bookmark;
+mod chrome_write;
 mod cli;
+mod clipboard;
+mod config;
+mod db_crypt;
+mod dedupe;
+mod export;
+mod favicon;
+mod freshness;
+mod history;
+mod import;
 mod index_db;
+mod jobs;
+mod last_results;
+mod latency;
+mod link_check;
+mod matcher;
+mod merge;
+mod open_queue;
+mod page_content;
+mod plist;
+mod plugins;
+mod progress;
+mod qr;
+mod query;
+mod report;
+mod report_render;
+mod result_cache;
+mod schedule;
+mod schema;
 mod searcher;
+mod session;
+mod shared_tags;
+mod tags;
+mod tokenize;
+mod url_normalize;
+mod usage;
+mod watch;
 
 use crate::bookmark::{
-    compute_bookmarks_fingerprint, get_chrome_bookmarks_path_cached, BookmarkCache,
+    browser_bundle_ids, browser_display_name, browser_key_for_bundle_id, canonical_browser_key,
+    compute_bookmarks_fingerprint, compute_sources_fingerprint, date_added_to_unix_secs,
+    discover_bookmark_sources_cached, discover_bookmark_sources_fresh, load_sources_parallel,
+    profile_launch_for, unix_secs_to_date_added, BookmarkCache, BookmarkSource, ProfileLaunch,
 };
-use crate::cli::{Opt, SubCommand};
-use crate::index_db::BookmarkIndex;
-use crate::searcher::BookmarkSearcher;
+use crate::cli::{
+    IgnoreCommand, Opt, QueueCommand, ReportCommand, ReportOutput, ScheduleCommand, SessionCommand,
+    StatsCommand, SubCommand, TagsCommand,
+};
+use crate::config::{Config, FaviconMode};
+use crate::favicon::{browser_favicons_db, FaviconCache};
+use crate::freshness::{
+    short_fingerprint, source_freshness, source_states_for, FreshnessStatus, SourceFreshness,
+};
+use crate::history::QueryHistory;
+use crate::index_db::{BookmarkIndex, IndexHealth, LOCAL_BROWSER, LOCAL_PROFILE};
+use crate::jobs::{JobKind, JobQueue};
+use crate::last_results::LastResults;
+use crate::latency::{LatencyStore, Phase, SearchTimer};
+use crate::link_check::{
+    check_https_upgrade, resolve_link, scan_budget, CurlProbe, IgnoreRule, LinkCheckResult,
+    LinkStore,
+};
+use crate::merge::merge_sources;
+use crate::open_queue::OpenQueue;
+use crate::page_content::{extract_page_text, fetch_page, ContentStore};
+use crate::progress::{
+    ProgressTracker, JOB_CHECK_LINKS, JOB_FAVICONS, JOB_INDEX_CONTENT, JOB_REFRESH,
+};
+use crate::report_render::Exportable;
+use crate::result_cache::ResultCache;
+use crate::schedule::ScheduledTask;
+use crate::searcher::{BookmarkFilters, BookmarkSearcher, SortOrder};
+use crate::session::{SessionState, SessionStore};
+use crate::tags::{bookmark_key, tag_key, TagManager};
+use crate::url_normalize::url_host;
+use crate::usage::UsageStore;
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -29,14 +97,24 @@ pub enum AppError {
     BookmarksReadError(String),
     #[error("索引数据库错误: {0}")]
     DatabaseError(String),
+    #[error("刷新已被更新的刷新任务取代")]
+    RefreshSuperseded,
     #[error("其他错误: {0}")]
     Other(String),
 }
 
-const INDEX_CHECK_TTL_MS: u64 = 2_000;
 const INDEX_CHECK_STATE_FILE: &str = "index_check_state.json";
 const FUZZY_CANDIDATE_LIMIT_MULTIPLIER: usize = 12;
 const FUZZY_CANDIDATE_LIMIT_FLOOR: usize = 200;
+/// 超出内存预算时每批读取的书签数
+const STREAM_CHUNK_SIZE: usize = 5_000;
+const DIVERSIFY_CANDIDATE_MULTIPLIER: usize = 4;
+/// 空查询时在结果顶部展示的最近搜索条数
+const RECENT_QUERIES_ON_EMPTY: usize = 5;
+/// 搜索结果副标题中最多显示的备注字符数
+const NOTE_PREVIEW_CHARS: usize = 40;
+/// 有后台任务进行时，Alfred 每隔多少秒重新运行脚本过滤器以更新进度
+const PROGRESS_RERUN_SECS: f64 = 0.5;
 const ICON_ACTION_REFRESH: &str = "icons/refresh.png";
 const ICON_ACTION_STATS: &str = "icons/stats.png";
 const ICON_ACTION_README: &str = "icons/readme.png";
@@ -44,6 +122,50 @@ const ICON_ACTION_GUIDE: &str = "icons/guide.png";
 const ICON_ACTION_FOLDERS: &str = "icons/folder.png";
 const ICON_ACTION_COPY: &str = "icons/copy.png";
 const ICON_BOOKMARK: &str = "icons/bookmark.png";
+const ICON_LOCAL_BOOKMARK: &str = "icons/local.png";
+const ICON_PINNED: &str = "icons/pinned.png";
+const ICON_ERROR: &str = "icons/error.png";
+
+/// `get --json` 的输出
+#[derive(Debug, Serialize)]
+struct BookmarkRecord<'a> {
+    id: &'a str,
+    name: &'a str,
+    url: &'a str,
+    folder_path: Option<&'a str>,
+    date_added: Option<i64>,
+    browser: &'a str,
+    profile: &'a str,
+    tags: Vec<String>,
+    note: Option<String>,
+    variants: Vec<crate::bookmark::ChromeBookmark>,
+    link_check: Option<LinkCheckRecord>,
+    /// 通过 workflow 打开的次数（usage.db）
+    open_count: u64,
+    last_opened: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct LinkCheckRecord {
+    status: u16,
+    final_url: String,
+    error: Option<String>,
+    checked_at: u64,
+    #[serde(skip)]
+    dead: bool,
+}
+
+impl From<LinkCheckResult> for LinkCheckRecord {
+    fn from(result: LinkCheckResult) -> Self {
+        Self {
+            dead: result.is_dead(),
+            status: result.status,
+            final_url: result.final_url,
+            error: result.error,
+            checked_at: result.checked_at,
+        }
+    }
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 struct IndexCheckState {
@@ -56,6 +178,8 @@ struct WorkflowAction {
     subtitle: &'static str,
     arg: &'static str,
     icon_path: &'static str,
+    /// 有值时回车补全到该子列表，而不是执行 `arg`
+    autocomplete: Option<&'static str>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -66,113 +190,825 @@ enum IndexEnsureStatus {
 }
 
 fn main() {
-    let opt: Opt = Opt::from_args();
+    let opt: Opt = Opt::parse();
 
     if let Err(e) = run(opt) {
-        show_error_alfred(e.to_string());
+        let message = e.to_string();
+        if crate::bookmark::is_full_disk_access_error(&message) {
+            show_full_disk_access_alfred(&message);
+        } else {
+            show_error_alfred(message);
+        }
         process::exit(1);
     }
 }
 
 fn run(opt: Opt) -> Result<(), Box<dyn std::error::Error>> {
-    let data_dir = if let Ok(dir) = std::env::var("alfred_workflow_data") {
-        std::path::PathBuf::from(dir)
-    } else {
-        dirs::home_dir()
-            .ok_or_else(|| AppError::Other("无法获取home目录".to_string()))?
-            .join(".alfred-chrome-bookmarks")
-    };
+    let started = Instant::now();
+    let crate::config::Dirs {
+        data: data_dir,
+        cache: cache_dir,
+    } = crate::config::Dirs::resolve(
+        opt.data_dir.as_deref(),
+        opt.cache_dir.as_deref(),
+        |key| std::env::var(key).ok(),
+        dirs::home_dir().as_deref(),
+        &std::env::current_dir()?,
+    )
+    .ok_or_else(|| AppError::Other("无法获取home目录".to_string()))?;
 
-    let cache_dir = if let Ok(dir) = std::env::var("alfred_workflow_cache") {
-        std::path::PathBuf::from(dir)
-    } else {
-        data_dir.clone()
-    };
-
-    std::fs::create_dir_all(&data_dir)?;
+    // 数据目录不可写（沙盒、磁盘已满、权限）时不报错，本次改用内存索引；缓存只是加速，写不进也无妨
+    let mut read_only: Option<String> = std::fs::create_dir_all(&data_dir)
+        .err()
+        .map(|err| format!("无法创建数据目录: {}", err));
     if cache_dir != data_dir {
-        std::fs::create_dir_all(&cache_dir)?;
+        let _ = std::fs::create_dir_all(&cache_dir);
     }
+    // 写回环境（已展开为绝对路径），后台子进程与定时任务沿用同一组目录
+    std::env::set_var(crate::config::DATA_DIR_VAR, &data_dir);
+    std::env::set_var(crate::config::CACHE_DIR_VAR, &cache_dir);
 
+    let config_vars = match &opt.config {
+        Some(path) => Some(crate::config::apply_config_file(path).map_err(AppError::Other)?),
+        None => None,
+    };
     let bookmark_cache = BookmarkCache::new(&cache_dir);
+    let mut config = Config::from_env();
+    // 密钥在打开任何元数据库之前确定；钥匙串读取失败时中止，避免写出未加密的新库
+    if let Some(spec) = &config.db_key {
+        crate::db_crypt::set_key(Some(
+            crate::db_crypt::resolve_key(spec).map_err(AppError::Other)?,
+        ));
+    }
+    if opt.verbose {
+        eprintln!("命令: {}", opt.cmd.name());
+        eprintln!("数据目录: {}", data_dir.display());
+        eprintln!("缓存目录: {}", cache_dir.display());
+        if let (Some(path), Some(applied)) = (&opt.config, config_vars) {
+            eprintln!("配置文件: {}（生效 {} 个变量）", path.display(), applied);
+        }
+    }
 
-    let needs_index = !matches!(opt.cmd, SubCommand::Actions { .. });
-    let needs_ensure_before_command =
-        matches!(opt.cmd, SubCommand::Search { .. } | SubCommand::Stats);
+    // 恢复备份会替换 bookmarks.db，不能先打开它
+    let needs_index = !matches!(
+        opt.cmd,
+        SubCommand::RestoreBackup { .. }
+            | SubCommand::Actions { .. }
+            | SubCommand::Open { .. }
+            | SubCommand::CopyRich { .. }
+            | SubCommand::Qr { .. }
+            | SubCommand::Queue { .. }
+            | SubCommand::Last
+            | SubCommand::Session { .. }
+            | SubCommand::Ignore { .. }
+            | SubCommand::Schedule { .. }
+            | SubCommand::RunJobs { .. }
+    );
+    let needs_ensure_before_command = matches!(
+        opt.cmd,
+        SubCommand::Search { .. }
+            | SubCommand::Recent { .. }
+            | SubCommand::Frequent { .. }
+            | SubCommand::Stats { cmd: None }
+            | SubCommand::IndexContent { .. }
+            | SubCommand::CheckLinks { .. }
+            | SubCommand::ScanLinks { .. }
+            | SubCommand::FixRedirects { .. }
+            | SubCommand::Dedupe { .. }
+            | SubCommand::Tags { .. }
+            | SubCommand::Note { .. }
+            | SubCommand::Pin { .. }
+            | SubCommand::Unpin { .. }
+            | SubCommand::Report { .. }
+    );
     let index = if needs_index {
-        let db_path = data_dir.join("bookmarks.db");
-        Some(BookmarkIndex::new(db_path).map_err(|e| AppError::DatabaseError(e.to_string()))?)
+        let opened = match &read_only {
+            Some(_) => None,
+            None => match BookmarkIndex::new(data_dir.join("bookmarks.db")) {
+                Ok(index) => Some(index),
+                Err(err) if crate::index_db::is_unwritable_error(&err) => {
+                    read_only = Some(format!("索引数据库不可写: {}", err));
+                    None
+                }
+                Err(err) => return Err(AppError::DatabaseError(err.to_string()).into()),
+            },
+        };
+        let mut index = match opened {
+            Some(index) => index,
+            None => {
+                eprintln!(
+                    "{}，本次改用内存索引",
+                    read_only.as_deref().unwrap_or_default()
+                );
+                BookmarkIndex::in_memory().map_err(|e| AppError::DatabaseError(e.to_string()))?
+            }
+        };
+        index
+            .set_substring_index(config.substring_search)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        index
+            .set_tokenize(config.tokenize.clone())
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        index.set_domain_boosts(config.boost_domains.clone());
+        Some(index)
     } else {
         None
     };
 
+    // 只读模式下历史、耗时与打开记录都写不进去，直接关闭
+    if read_only.is_some() {
+        config.query_history = false;
+        config.latency_log = false;
+        config.usage_tracking = false;
+    }
+    // 内存索引每次都从书签缓存 / 书签文件重新建立
+    let in_memory_index = read_only.is_some() && index.is_some();
+
+    // 搜索耗时从索引检查开始计
+    let mut search_timer = SearchTimer::start();
     let mut index_status = None;
-    if needs_ensure_before_command {
-        let bookmarks_path =
-            get_chrome_bookmarks_path_cached(&cache_dir).ok_or(AppError::BookmarksNotFound)?;
+    if needs_ensure_before_command || in_memory_index {
+        let sources = discover_bookmark_sources_cached(&cache_dir, &config.browsers);
+        if sources.is_empty() {
+            return Err(AppError::BookmarksNotFound.into());
+        }
         index_status = Some(ensure_bookmark_index(
             index.as_ref().expect("index initialized"),
             &bookmark_cache,
-            &bookmarks_path,
+            &sources,
+            &data_dir,
             &cache_dir,
+            &config,
+            if in_memory_index {
+                0
+            } else {
+                config.index_ttl.ttl_ms(opt.cmd.name())
+            },
         )?);
     }
+    search_timer.lap(Phase::Index);
+    if let Some(status) = index_status.filter(|_| opt.verbose) {
+        eprintln!(
+            "索引检查: {:?}（{} ms）",
+            status,
+            started.elapsed().as_millis()
+        );
+    }
+    let verbose = opt.verbose;
 
     match opt.cmd {
         SubCommand::Search {
             query,
             folders,
+            profile,
+            sources,
+            tags,
             fuzzy,
+            sort,
             limit,
         } => {
+            let filters = BookmarkFilters {
+                folders: folders
+                    .map(|raw| normalize_csv_terms(raw.split(',')))
+                    .unwrap_or_default(),
+                browsers: sources
+                    .map(|raw| parse_browser_terms(&raw))
+                    .unwrap_or_default(),
+                profiles: profile
+                    .map(|raw| normalize_csv_terms(raw.split(',')))
+                    .unwrap_or_default(),
+                tags: tags
+                    .map(|raw| normalize_csv_terms(raw.split(',')))
+                    .unwrap_or_default(),
+            };
             handle_search(
                 query,
-                folders,
+                filters,
                 fuzzy,
+                sort,
                 limit,
-                index_status,
+                // 内存索引每次都会重建，不提示“索引已更新”
+                index_status.filter(|_| !in_memory_index),
+                search_timer,
                 index.as_ref().expect("index initialized"),
+                &data_dir,
+                &cache_dir,
+                &config,
+                config
+                    .favicons
+                    .enabled()
+                    .then(|| FaviconCache::new(&cache_dir))
+                    .as_ref(),
+            )?;
+        }
+        SubCommand::Get { id, json } => {
+            let tags = TagManager::new(data_dir.join("tags.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            let links = LinkStore::new(data_dir.join("links.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            let usage = UsageStore::new(data_dir.join("usage.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_get(
+                index.as_ref().expect("index initialized"),
+                &tags,
+                &links,
+                &usage,
+                &id,
+                json,
             )?;
         }
         SubCommand::Refresh => {
-            let bookmarks_path =
-                get_chrome_bookmarks_path_cached(&cache_dir).ok_or(AppError::BookmarksNotFound)?;
+            let sources = discover_bookmark_sources_fresh(&cache_dir, &config.browsers);
+            if sources.is_empty() {
+                return Err(AppError::BookmarksNotFound.into());
+            }
             bookmark_cache.invalidate();
-            index
-                .as_ref()
-                .expect("index initialized")
-                .clear_bookmarks_index()
-                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
             refresh_bookmark_index(
                 index.as_ref().expect("index initialized"),
                 &bookmark_cache,
-                &bookmarks_path,
+                &sources,
+                &data_dir,
+                &cache_dir,
+                &config,
+                true,
             )?;
             mark_index_checked_recently(&cache_dir);
             show_info_alfred("浏览器书签缓存与索引已刷新");
         }
-        SubCommand::Stats => {
-            handle_stats(index.as_ref().expect("index initialized"))?;
+        SubCommand::Stats { cmd: None } => {
+            handle_stats(
+                index.as_ref().expect("index initialized"),
+                &data_dir,
+                &cache_dir,
+                &config.browsers,
+            )?;
+        }
+        SubCommand::Stats {
+            cmd: Some(StatsCommand::Index { vacuum, json }),
+        } => {
+            handle_stats_index(
+                index.as_ref().expect("index initialized"),
+                &data_dir,
+                vacuum,
+                json,
+            )?;
+        }
+        SubCommand::Stats {
+            cmd: Some(StatsCommand::Latency { json }),
+        } => {
+            let store = LatencyStore::new(data_dir.join("latency.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_stats_latency(&store, config.latency_log, json)?;
+        }
+        SubCommand::Doctor => {
+            handle_doctor(
+                index.as_ref().expect("index initialized"),
+                read_only.as_deref(),
+                &data_dir,
+                &cache_dir,
+                &config.browsers,
+            )?;
+        }
+        SubCommand::IndexContent {
+            limit,
+            timeout,
+            max_age_days,
+        } => {
+            let store = ContentStore::new(data_dir.join("content.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_index_content(
+                index.as_ref().expect("index initialized"),
+                &store,
+                &cache_dir,
+                &config,
+                limit,
+                timeout,
+                max_age_days,
+            )?;
+        }
+        SubCommand::CheckLinks {
+            limit,
+            timeout,
+            report,
+            enqueue,
+        } => {
+            let store = LinkStore::new(data_dir.join("links.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            if enqueue {
+                let queue = JobQueue::new(data_dir.join("jobs.db"))
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                handle_enqueue_link_checks(
+                    index.as_ref().expect("index initialized"),
+                    &store,
+                    &queue,
+                )?;
+            } else {
+                handle_check_links(
+                    index.as_ref().expect("index initialized"),
+                    &store,
+                    &cache_dir,
+                    limit,
+                    timeout,
+                    report,
+                )?;
+            }
+        }
+        SubCommand::ScanLinks {
+            interval,
+            period_days,
+            max,
+            timeout,
+        } => {
+            let store = LinkStore::new(data_dir.join("links.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            let queue = JobQueue::new(data_dir.join("jobs.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_scan_links(
+                index.as_ref().expect("index initialized"),
+                &store,
+                &queue,
+                &cache_dir,
+                ScanPlan {
+                    interval_secs: interval,
+                    period_secs: period_days.max(1) * 24 * 3600,
+                    max_per_run: max,
+                    timeout,
+                },
+            )?;
+        }
+        SubCommand::RunJobs {
+            limit,
+            timeout,
+            kind,
+        } => {
+            let kind = match kind {
+                Some(raw) => Some(
+                    JobKind::parse(raw.trim())
+                        .ok_or_else(|| AppError::Other(format!("未知任务类型: {}", raw)))?,
+                ),
+                None => None,
+            };
+            let store = LinkStore::new(data_dir.join("links.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            let queue = JobQueue::new(data_dir.join("jobs.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_run_jobs(&queue, &store, &cache_dir, kind, limit, timeout)?;
+        }
+        SubCommand::PrefetchFavicons { limit, timeout } => {
+            let store = LinkStore::new(data_dir.join("links.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            let queue = JobQueue::new(data_dir.join("jobs.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_prefetch_favicons(
+                index.as_ref().expect("index initialized"),
+                &queue,
+                &store,
+                &cache_dir,
+                limit,
+                timeout,
+            )?;
+        }
+        SubCommand::FixRedirects { export } => {
+            let store = LinkStore::new(data_dir.join("links.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_fix_redirects(
+                index.as_ref().expect("index initialized"),
+                &store,
+                export.as_deref(),
+            )?;
+        }
+        SubCommand::Dedupe {
+            similar,
+            threshold,
+            across_sources,
+        } => {
+            let store = LinkStore::new(data_dir.join("links.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_dedupe(
+                index.as_ref().expect("index initialized"),
+                &store,
+                similar,
+                threshold,
+                across_sources,
+            )?;
+        }
+        SubCommand::Ignore { cmd } => {
+            let store = LinkStore::new(data_dir.join("links.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_ignore(&store, cmd)?;
+        }
+        SubCommand::Tags { cmd } => {
+            let tags = TagManager::new(data_dir.join("tags.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            match cmd {
+                TagsCommand::Add { id, tags: names } => {
+                    handle_tags_add(
+                        index.as_ref().expect("index initialized"),
+                        &tags,
+                        &id,
+                        &names,
+                    )?;
+                    spawn_shared_tags_sync(&config);
+                }
+                TagsCommand::Remove { id, tags: names } => {
+                    handle_tags_remove(
+                        index.as_ref().expect("index initialized"),
+                        &tags,
+                        &id,
+                        &names,
+                    )?;
+                    spawn_shared_tags_sync(&config);
+                }
+                TagsCommand::List => handle_tags_list(&tags)?,
+                TagsCommand::Show { id } => {
+                    handle_tags_show(index.as_ref().expect("index initialized"), &tags, &id)?;
+                }
+                TagsCommand::Rename { from, to } => {
+                    handle_tags_rename(&tags, &from, &to)?;
+                    spawn_shared_tags_sync(&config);
+                }
+                TagsCommand::Sync => {
+                    handle_tags_sync(index.as_ref().expect("index initialized"), &tags, &config)?;
+                }
+                TagsCommand::Prune { dry_run } => {
+                    handle_tags_prune(index.as_ref().expect("index initialized"), &tags, dry_run)?;
+                }
+                TagsCommand::SyncChrome { dry_run } => {
+                    handle_tags_sync_chrome(
+                        index.as_ref().expect("index initialized"),
+                        &tags,
+                        &config,
+                        dry_run,
+                    )?;
+                }
+            }
+        }
+        SubCommand::Note { id, text, clear } => {
+            let tags = TagManager::new(data_dir.join("tags.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_note(
+                index.as_ref().expect("index initialized"),
+                &tags,
+                &config,
+                &id,
+                &text.join(" "),
+                clear,
+            )?;
+        }
+        SubCommand::Pin { id } => {
+            let tags = TagManager::new(data_dir.join("tags.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_pin(index.as_ref().expect("index initialized"), &tags, &id, true)?;
+        }
+        SubCommand::Unpin { id } => {
+            let tags = TagManager::new(data_dir.join("tags.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_pin(
+                index.as_ref().expect("index initialized"),
+                &tags,
+                &id,
+                false,
+            )?;
+        }
+        SubCommand::Report { cmd } => match cmd {
+            ReportCommand::Cleanup { stale_days, output } => {
+                let links = LinkStore::new(data_dir.join("links.db"))
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                let tags = TagManager::new(data_dir.join("tags.db"))
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                handle_report_cleanup(
+                    index.as_ref().expect("index initialized"),
+                    &links,
+                    &tags,
+                    stale_days,
+                    &output,
+                )?;
+            }
+            ReportCommand::Growth { by, output } => {
+                handle_report_growth(index.as_ref().expect("index initialized"), by, &output)?;
+            }
+            ReportCommand::Tags {
+                cooccurrence,
+                limit,
+                output,
+            } => {
+                let tags = TagManager::new(data_dir.join("tags.db"))
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                handle_report_tags(&tags, cooccurrence, limit, &output)?;
+            }
+            ReportCommand::Folders { depth, output } => {
+                handle_report_folders(index.as_ref().expect("index initialized"), depth, &output)?;
+            }
+            ReportCommand::Domains { limit, output } => {
+                handle_report_domains(index.as_ref().expect("index initialized"), limit, &output)?;
+            }
+            ReportCommand::Queries { limit, output } => {
+                let history = QueryHistory::new(data_dir.join("history.db"))
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                handle_report_queries(&history, config.query_history, limit, &output)?;
+            }
+            ReportCommand::Untagged { limit, output } => {
+                let tags = TagManager::new(data_dir.join("tags.db"))
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                handle_report_untagged(
+                    index.as_ref().expect("index initialized"),
+                    &tags,
+                    limit,
+                    &output,
+                )?;
+            }
+        },
+        SubCommand::Watch { debounce_ms } => {
+            handle_watch(
+                index.as_ref().expect("index initialized"),
+                &bookmark_cache,
+                &data_dir,
+                &cache_dir,
+                &config,
+                debounce_ms,
+            )?;
+        }
+        SubCommand::Schedule { cmd } => {
+            handle_schedule(cmd, &cache_dir)?;
+        }
+        SubCommand::Warmup => {
+            handle_warmup(
+                index.as_ref().expect("index initialized"),
+                &cache_dir,
+                &config.browsers,
+            )?;
+        }
+        SubCommand::Open { url, background } => {
+            if config.usage_tracking {
+                // 统计失败不影响打开
+                if let Ok(usage) = UsageStore::new(data_dir.join("usage.db")) {
+                    let _ = usage.record_open(&url, now_ms() / 1000);
+                }
+            }
+            let target = open_target_for(&data_dir, &url, &config);
+            handle_open(&url, background, &target, &config)?;
+        }
+        SubCommand::CopyRich { url, title } => {
+            let url = config.tracking.clean_for_copy(&url);
+            clipboard::copy_rich_link(&title, &url).map_err(AppError::Other)?;
+        }
+        SubCommand::Add {
+            url,
+            title,
+            folder,
+            tags,
+            to_chrome,
+            profile,
+        } => {
+            let tag_names = tags
+                .map(|raw| normalize_csv_terms(raw.split(',')))
+                .unwrap_or_default();
+            if to_chrome {
+                handle_add_to_chrome(
+                    &cache_dir,
+                    &data_dir,
+                    &url,
+                    &title,
+                    folder.as_deref(),
+                    profile.as_deref(),
+                    &tag_names,
+                )?;
+            } else {
+                handle_add(
+                    index.as_ref().expect("index initialized"),
+                    &data_dir,
+                    &url,
+                    &title,
+                    folder.as_deref(),
+                    &tag_names,
+                )?;
+            }
+        }
+        SubCommand::Import { urls, folder, tag } => {
+            let tag_names = tag
+                .map(|raw| normalize_csv_terms(raw.split(',')))
+                .unwrap_or_default();
+            handle_import_urls(
+                index.as_ref().expect("index initialized"),
+                &data_dir,
+                &urls,
+                folder.as_deref(),
+                &tag_names,
+            )?;
+        }
+        SubCommand::Delete { id, from_chrome } => {
+            handle_delete(index.as_ref().expect("index initialized"), &id, from_chrome)?;
+        }
+        SubCommand::Move {
+            id,
+            folder,
+            to_chrome,
+        } => {
+            handle_edit(
+                index.as_ref().expect("index initialized"),
+                &id,
+                BookmarkEdit::Move(&folder),
+                to_chrome,
+            )?;
+        }
+        SubCommand::Retitle {
+            id,
+            title,
+            to_chrome,
+        } => {
+            handle_edit(
+                index.as_ref().expect("index initialized"),
+                &id,
+                BookmarkEdit::Retitle(&title),
+                to_chrome,
+            )?;
+        }
+        SubCommand::MoveAll {
+            query,
+            folder,
+            to_chrome,
+            dry_run,
+        } => {
+            handle_move_all(
+                index.as_ref().expect("index initialized"),
+                &data_dir,
+                &query,
+                &folder,
+                to_chrome,
+                dry_run,
+            )?;
+        }
+        SubCommand::MergeFolders {
+            src,
+            dst,
+            to_chrome,
+            dry_run,
+        } => {
+            handle_merge_folders(
+                index.as_ref().expect("index initialized"),
+                &data_dir,
+                &src,
+                &dst,
+                to_chrome,
+                dry_run,
+            )?;
+        }
+        SubCommand::Recent { query, limit } => {
+            handle_recent(
+                index.as_ref().expect("index initialized"),
+                &data_dir,
+                &query,
+                limit,
+            )?;
+        }
+        SubCommand::Frequent { query, limit } => {
+            handle_frequent(
+                index.as_ref().expect("index initialized"),
+                &data_dir,
+                &query,
+                limit,
+            )?;
+        }
+        SubCommand::Trash { query } => {
+            handle_trash(index.as_ref().expect("index initialized"), &query)?;
+        }
+        SubCommand::RestoreBackup { archive } => {
+            let restored = backup::restore_backup(&archive, &data_dir)?;
+            show_info_alfred(format!("已从备份恢复: {}", restored.join("、")));
+        }
+        SubCommand::Backup { dir } => {
+            let (path, included) =
+                backup::create_backup(&data_dir, &dir, (now_ms() / 1000) as i64)?;
+            show_info_alfred(format!(
+                "已备份 {} 到 {}",
+                included.join("、"),
+                path.display()
+            ));
+        }
+        SubCommand::Restore { id } => {
+            let bookmark = index
+                .as_ref()
+                .expect("index initialized")
+                .restore_bookmark(&id)
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?
+                .ok_or_else(|| AppError::Other(format!("回收站中没有该书签: {}", id)))?;
+            show_info_alfred(format!("已恢复: {}", bookmark.name));
+        }
+        SubCommand::ActionsFor { id } => {
+            let tags = TagManager::new(data_dir.join("tags.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_actions_for(
+                index.as_ref().expect("index initialized"),
+                &tags,
+                &id,
+                &data_dir,
+                &cache_dir,
+                &config,
+            )?;
+        }
+        SubCommand::Plugin { name, id } => {
+            let tags = TagManager::new(data_dir.join("tags.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_plugin(
+                index.as_ref().expect("index initialized"),
+                &tags,
+                &data_dir,
+                &config,
+                &name,
+                &id,
+            )?;
+        }
+        SubCommand::Details { id } => {
+            handle_details(
+                index.as_ref().expect("index initialized"),
+                &data_dir,
+                &id,
+                &config,
+            )?;
+        }
+        SubCommand::Reveal { id } => {
+            handle_reveal(index.as_ref().expect("index initialized"), &id)?;
+        }
+        SubCommand::Last => match LastResults::new(&cache_dir).load() {
+            Some(payload) => io::stdout().write_all(&payload)?,
+            None => {
+                let item = alfred::ItemBuilder::new("还没有搜索结果")
+                    .subtitle("先用 cb 搜索，之后可在这里找回上一次的结果")
+                    .valid(false)
+                    .into_item();
+                alfred::json::write_items(io::stdout(), &[item])?;
+            }
+        },
+        SubCommand::Session { cmd } => {
+            let store = SessionStore::new(&cache_dir);
+            match cmd {
+                SessionCommand::Set { state } => match SessionState::decode(&state) {
+                    Some(state) => store.save(&state, now_ms() / 1000)?,
+                    None => store.clear(),
+                },
+                SessionCommand::Clear => store.clear(),
+            }
+        }
+        SubCommand::Queue { cmd } => {
+            handle_queue(cmd, &OpenQueue::new(&cache_dir), &data_dir, &config)?;
+        }
+        SubCommand::Qr { url } => {
+            let url = config.tracking.clean_for_copy(&url);
+            let path = qr::QrCache::new(&cache_dir)
+                .render(&url)
+                .map_err(|err| AppError::Other(format!("二维码生成失败: {}", err)))?;
+            println!("{}", path.display());
+        }
+        // `cba trash …` 复用动作入口浏览回收站
+        SubCommand::Actions { query } if query.first().map(String::as_str) == Some("trash") => {
+            let index = BookmarkIndex::new(data_dir.join("bookmarks.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_trash(&index, &query[1..])?;
+        }
+        // `cba stats` 列出可操作的统计
+        SubCommand::Actions { query } if query.first().map(String::as_str) == Some("stats") => {
+            let index = BookmarkIndex::new(data_dir.join("bookmarks.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_stats(&index, &data_dir, &cache_dir, &config.browsers)?;
+        }
+        // `cba untagged` 列出待打标签 / 待归档的书签
+        SubCommand::Actions { query } if query.first().map(String::as_str) == Some("untagged") => {
+            let index = BookmarkIndex::new(data_dir.join("bookmarks.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            let tags = TagManager::new(data_dir.join("tags.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            handle_report_untagged(
+                &index,
+                &tags,
+                UNTAGGED_ALFRED_LIMIT,
+                &ReportOutput::default(),
+            )?;
         }
         SubCommand::Actions { query } => {
-            handle_actions(query)?;
+            handle_actions(query, &cache_dir)?;
         }
     }
 
+    if verbose {
+        eprintln!("完成，用时 {} ms", started.elapsed().as_millis());
+    }
     Ok(())
 }
 
 fn ensure_bookmark_index(
     index: &BookmarkIndex,
     cache: &BookmarkCache,
-    bookmarks_path: &Path,
+    sources: &[BookmarkSource],
+    data_dir: &Path,
     cache_dir: &Path,
+    config: &Config,
+    ttl_ms: u64,
 ) -> Result<IndexEnsureStatus, Box<dyn std::error::Error>> {
-    if is_index_check_recent(cache_dir, now_ms()) {
+    if is_index_check_recent(cache_dir, now_ms(), ttl_ms) {
         return Ok(IndexEnsureStatus::SkippedRecent);
     }
 
-    let fingerprint = compute_bookmarks_fingerprint(bookmarks_path)?;
+    let fingerprint = index_fingerprint(sources, config)?;
 
     if !index
         .bookmarks_need_refresh(&fingerprint)
@@ -182,126 +1018,573 @@ fn ensure_bookmark_index(
         return Ok(IndexEnsureStatus::AlreadyFresh);
     }
 
-    refresh_bookmark_index(index, cache, bookmarks_path)?;
+    match refresh_bookmark_index(index, cache, sources, data_dir, cache_dir, config, false) {
+        Ok(()) => {}
+        // 更新的进程已接手刷新，本次直接使用现有索引
+        Err(err) if matches!(err.downcast_ref(), Some(AppError::RefreshSuperseded)) => {
+            return Ok(IndexEnsureStatus::SkippedRecent);
+        }
+        Err(err) => return Err(err),
+    }
     mark_index_checked_recently(cache_dir);
 
     Ok(IndexEnsureStatus::Refreshed)
 }
 
+/// 索引指纹：来源指纹 + 合并配置（主浏览器、优先级），配置变化时也会重建索引
+fn index_fingerprint(
+    sources: &[BookmarkSource],
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(format!(
+        "{}|{}",
+        compute_sources_fingerprint(sources)?,
+        merge_signature(config)
+    ))
+}
+
+fn merge_signature(config: &Config) -> String {
+    format!(
+        "primary={}|priority={}{}",
+        config.merge.primary_browser.as_deref().unwrap_or(""),
+        config.browsers.join(","),
+        // 标题标签会改变索引中的书签名，切换时需重新解析
+        if config.title_tags { "|title_tags" } else { "" }
+    )
+}
+
+/// 刷新索引：只重新解析书签文件发生变化的来源，其余来源直接复用索引中的数据再统一合并
+/// 解析全部完成后才在一个事务内写入，中途崩溃不会留下半空的索引；`force_full` 时忽略已记录的来源指纹整体重建
+/// 进度写入缓存目录；有更新的刷新启动时，本次在写入前以 `RefreshSuperseded` 放弃
 fn refresh_bookmark_index(
     index: &BookmarkIndex,
     cache: &BookmarkCache,
-    bookmarks_path: &Path,
+    sources: &[BookmarkSource],
+    data_dir: &Path,
+    cache_dir: &Path,
+    config: &Config,
+    force_full: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let fingerprint = compute_bookmarks_fingerprint(bookmarks_path)?;
-    let bookmarks = cache
-        .load(bookmarks_path)
-        .map_err(|e| AppError::BookmarksReadError(e.to_string()))?;
-
+    let started = Instant::now();
+    let mut progress = ProgressTracker::start(cache_dir, JOB_REFRESH);
+    let result = refresh_with_progress(index, cache, sources, config, force_full, &mut progress);
+    progress.finish();
+    let (count, fingerprint, title_tags) = result?;
     index
-        .replace_bookmarks(&bookmarks, &fingerprint)
+        .record_refresh(
+            (now_ms() / 1000) as i64,
+            started.elapsed().as_millis() as u64,
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    let tags = TagManager::new(data_dir.join("tags.db"))
         .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    // 旧版按书签 id 或 GUID 记录的标签，趁索引刚写好换成按规范 URL 的键；只需执行一次
+    if tags
+        .has_legacy_keys()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+    {
+        let live = index
+            .load_all_bookmarks()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        tags.migrate_legacy_keys(&live)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+
+    // 标签只增不减，重复解析同一标题不会产生重复关联
+    for (bookmark, names) in &title_tags {
+        tags.add_tags(&tag_key(bookmark), &bookmark.url, names)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+
+    // 浏览器自带的图标库在本地，随刷新导出页面图标；已有图标的书签不再读取
+    if config.favicons.enabled() {
+        let live = index
+            .load_all_bookmarks()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let favicons = FaviconCache::new(cache_dir);
+        for db in sources
+            .iter()
+            .filter_map(|source| browser_favicons_db(&source.path))
+        {
+            if let Err(err) = favicons.import_browser_favicons(&db, &live) {
+                eprintln!("读取浏览器图标库 {} 失败: {}", db.display(), err);
+            }
+        }
+    }
 
+    // 下载图标、共享标签与钩子都在后台执行，不拖慢触发刷新的这次搜索
+    if config.favicons == FaviconMode::Download {
+        spawn_detached(&["prefetch-favicons"]);
+    }
+    spawn_shared_tags_sync(config);
+    if let Some(hook) = &config.post_refresh_hook {
+        let _ = post_refresh_hook_command(hook, count, &fingerprint).spawn();
+    }
     Ok(())
 }
 
-fn handle_search(
-    query: Vec<String>,
-    folders: Option<String>,
-    fuzzy: bool,
-    limit: usize,
-    index_status: Option<IndexEnsureStatus>,
+/// 通过 `sh -c` 执行用户配置的钩子，书签数与索引指纹作为 `$1`、`$2` 传入；输出丢弃
+fn post_refresh_hook_command(hook: &str, count: usize, fingerprint: &str) -> process::Command {
+    let mut command = process::Command::new("/bin/sh");
+    command
+        .arg("-c")
+        .arg(format!("{} \"$@\"", hook))
+        .arg("alfred-chrome-bookmarks-hook")
+        .arg(count.to_string())
+        .arg(fingerprint)
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null());
+    command
+}
+
+fn refresh_with_progress(
     index: &BookmarkIndex,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let searcher = BookmarkSearcher::new();
+    cache: &BookmarkCache,
+    sources: &[BookmarkSource],
+    config: &Config,
+    force_full: bool,
+    progress: &mut ProgressTracker,
+) -> Result<TitleTaggedRefresh, Box<dyn std::error::Error>> {
+    let fingerprint = index_fingerprint(sources, config)?;
+    let merge_key = merge_signature(config);
+    let previous = index
+        .source_fingerprints()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-    let raw_query = query.join(" ");
-    let (query_str, inline_folder_filters) = parse_query_and_folder_filters(&raw_query);
+    let mut current = Vec::with_capacity(sources.len());
+    for source in sources {
+        current.push((
+            (source.browser.clone(), source.profile.clone()),
+            format!(
+                "{}|{}",
+                compute_bookmarks_fingerprint(&source.path)?,
+                merge_key
+            ),
+        ));
+    }
 
-    let mut folder_filters: Vec<String> = if let Some(folders_str) = folders {
-        normalize_csv_terms(folders_str.split(','))
+    // 没有记录过来源指纹（首次建立索引）或执行 refresh 时整体重建
+    let full_rebuild = force_full || previous.is_empty();
+    let mut bookmarks = Vec::new();
+    let mut stale_sources = Vec::new();
+    let mut recorded = Vec::with_capacity(sources.len());
+    if full_rebuild {
+        progress
+            .update("解析书签", 0, sources.len())
+            .map_err(|_| AppError::RefreshSuperseded)?;
+        bookmarks = cache
+            .load(sources)
+            .map_err(|e| AppError::BookmarksReadError(e.to_string()))?;
+        stale_sources = current.iter().map(|(key, _)| key.clone()).collect();
+        recorded = current;
     } else {
-        Vec::new()
-    };
-
-    for folder in inline_folder_filters {
-        if !folder_filters
+        progress
+            .update("解析书签", 0, sources.len())
+            .map_err(|_| AppError::RefreshSuperseded)?;
+        // 指纹变化的来源先并行解析，再按来源顺序与索引中的旧数据合并
+        let changed: Vec<BookmarkSource> = sources
             .iter()
-            .any(|existing| existing.eq_ignore_ascii_case(&folder))
-        {
-            folder_filters.push(folder);
+            .zip(&current)
+            .filter(|(_, (key, current))| previous.get(key) != Some(current))
+            .map(|(source, _)| source.clone())
+            .collect();
+        let mut parsed_changed = changed
+            .iter()
+            .map(|source| (source.browser.clone(), source.profile.clone()))
+            .zip(load_sources_parallel(&changed))
+            .collect::<HashMap<_, _>>();
+        for (source, (key, current)) in sources.iter().zip(current) {
+            if let Some(parsed) = parsed_changed.remove(&key) {
+                match parsed {
+                    Ok(parsed) => {
+                        bookmarks.extend(parsed);
+                        stale_sources.push(key.clone());
+                        recorded.push((key, current));
+                        continue;
+                    }
+                    // 解析失败（如浏览器正在写入）时沿用索引中的旧数据，指纹不更新以便下次重试
+                    Err(_) if previous.contains_key(&key) => {}
+                    Err(e) => return Err(AppError::BookmarksReadError(e.to_string()).into()),
+                }
+            }
+            bookmarks.extend(
+                index
+                    .source_bookmarks(&source.browser, &source.profile)
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?,
+            );
+            let kept_fingerprint = previous[&key].clone();
+            recorded.push((key, kept_fingerprint));
         }
     }
 
-    let fallback_exact =
-        || -> Result<Vec<crate::bookmark::ChromeBookmark>, Box<dyn std::error::Error>> {
-            let bookmarks = index
-                .load_all_bookmarks()
-                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-            let results = searcher.search(&bookmarks, &query_str, &folder_filters, false, limit);
-            Ok(results.into_iter().map(|item| item.bookmark).collect())
-        };
+    // 只有本次重新解析的书签带有标题标签，沿用索引的书签名已去掉后缀
+    let title_tags = if config.title_tags {
+        strip_title_tags(&mut bookmarks)
+    } else {
+        Vec::new()
+    };
 
-    let bookmarks = if fuzzy {
-        let candidate_limit = std::cmp::max(
-            limit.saturating_mul(FUZZY_CANDIDATE_LIMIT_MULTIPLIER),
-            FUZZY_CANDIDATE_LIMIT_FLOOR,
-        );
+    // 本地书签不对应书签文件，每次刷新都按 local_bookmarks 表整体重写
+    bookmarks.extend(
+        index
+            .local_bookmarks()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?,
+    );
+    stale_sources.push((LOCAL_BROWSER.to_string(), LOCAL_PROFILE.to_string()));
 
-        let candidates = if query_str.is_empty() {
-            if folder_filters.is_empty() {
-                index
-                    .list_bookmarks(candidate_limit)
-                    .map_err(|e| AppError::DatabaseError(e.to_string()))?
-            } else {
+    // 来源状态按合并前统计；未重新解析的来源保留上次的状态
+    let previous_states = index
+        .source_states()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let states: Vec<_> = source_states_for(sources, &bookmarks, (now_ms() / 1000) as i64)
+        .into_iter()
+        .map(|state| {
+            let key = (state.browser.clone(), state.profile.clone());
+            if stale_sources.contains(&key) {
+                return state;
+            }
+            previous_states
+                .iter()
+                .find(|prev| prev.browser == state.browser && prev.profile == state.profile)
+                .cloned()
+                .unwrap_or(state)
+        })
+        .collect();
+
+    // 回收站中的书签不参与合并，同一 URL 的其他来源版本可以补上
+    let trashed = index
+        .trashed_ids()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    if !trashed.is_empty() {
+        bookmarks.retain(|bookmark| !trashed.contains(&bookmark.id));
+    }
+    let (bookmarks, variants) = merge_sources(
+        bookmarks,
+        config.merge.primary_browser.as_deref(),
+        &config.browsers,
+    );
+
+    progress
+        .update("写入索引", 0, 0)
+        .map_err(|_| AppError::RefreshSuperseded)?;
+    index
+        .atomically(|| {
+            if full_rebuild {
+                index.clear_bookmarks_index()?;
+                index.replace_bookmarks(&bookmarks, &fingerprint)?;
+            } else {
+                index.sync_bookmarks(&bookmarks, &stale_sources, &fingerprint)?;
+            }
+            index.replace_variants(&variants)?;
+            index.replace_source_states(&states)?;
+            index.set_source_fingerprints(&recorded)
+        })
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok((bookmarks.len(), fingerprint, title_tags))
+}
+
+/// 刷新结果：书签数、索引指纹、从标题解析出的（书签, 标签）
+type TitleTaggedRefresh = (
+    usize,
+    String,
+    Vec<(crate::bookmark::ChromeBookmark, Vec<String>)>,
+);
+
+/// 去掉书签名末尾的 ` #tag` 后缀，返回带标签的书签
+fn strip_title_tags(
+    bookmarks: &mut [crate::bookmark::ChromeBookmark],
+) -> Vec<(crate::bookmark::ChromeBookmark, Vec<String>)> {
+    let mut tagged = Vec::new();
+    for bookmark in bookmarks.iter_mut() {
+        let (name, tags) = crate::tags::split_title_tags(&bookmark.name);
+        if tags.is_empty() {
+            continue;
+        }
+        bookmark.name_lower = name.to_lowercase();
+        bookmark.name = name;
+        tagged.push((bookmark.clone(), tags));
+    }
+    tagged
+}
+
+/// 确保标签库已建表，再挂载到索引连接上，标签过滤与 FTS、目录过滤在同一条 SQL 中完成
+fn attach_tag_store(index: &BookmarkIndex, data_dir: &Path) -> Result<(), AppError> {
+    let tags_db = data_dir.join("tags.db");
+    TagManager::new(tags_db.clone()).map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    index
+        .attach_tags(&tags_db)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_search(
+    query: Vec<String>,
+    mut filters: BookmarkFilters,
+    fuzzy: bool,
+    sort: SortOrder,
+    limit: usize,
+    index_status: Option<IndexEnsureStatus>,
+    mut timer: SearchTimer,
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    cache_dir: &Path,
+    config: &Config,
+    favicons: Option<&FaviconCache>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let searcher = BookmarkSearcher::new()
+        .with_domain_boosts(config.boost_domains.clone())
+        .with_matcher(config.matcher);
+
+    let raw_query = query.join(" ");
+    let history = if config.query_history {
+        Some(
+            QueryHistory::new(data_dir.join("history.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?,
+        )
+    } else {
+        None
+    };
+    // 在结果上按 Tab 补全为 `>ID`，进入该书签的动作菜单；`>ID +标签` 为该书签添加标签
+    if let Some(rest) = raw_query.trim().strip_prefix('>') {
+        let (id, tag_input) = match rest.split_once(" +") {
+            Some((id, input)) => (id.trim(), Some(input)),
+            None => (rest.trim().trim_end_matches('+').trim_end(), None),
+        };
+        if !id.is_empty() {
+            let tags = TagManager::new(data_dir.join("tags.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            return match tag_input {
+                Some(input) => handle_tag_input(index, &tags, id, input),
+                None => handle_actions_for(index, &tags, id, data_dir, cache_dir, config),
+            };
+        }
+    }
+    if let Some(history) = &history {
+        if let Some(prefix) = raw_query.trim_start().strip_prefix('!') {
+            let items = history_items(history, prefix, limit)?;
+            alfred::json::write_items(io::stdout(), &items)?;
+            return Ok(());
+        }
+        history
+            .record(&raw_query, now_ms() / 1000)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+
+    let (query_str, inline_filters) = parse_query_and_folder_filters(&raw_query);
+    append_unique_case_insensitive(&mut filters.folders, inline_filters.folders);
+    append_unique_case_insensitive(&mut filters.browsers, inline_filters.browsers);
+    append_unique_case_insensitive(&mut filters.profiles, inline_filters.profiles);
+    append_unique_case_insensitive(&mut filters.tags, inline_filters.tags);
+    filters.widen_all_browsers();
+
+    // 标签过滤要用到标签库；写过备注时也挂载，标题与 URL 命中不足时补充备注命中的书签
+    if !filters.tags.is_empty() || data_dir.join("tags.db").is_file() {
+        attach_tag_store(index, data_dir)?;
+    }
+    let pinned = index
+        .pinned_ids()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let searcher = searcher.with_pinned(pinned.clone());
+    // 运行过 index-content 才有正文库；标题与 URL 命中不足时补充网页内容命中的书签
+    let content_db = data_dir.join("content.db");
+    if content_db.is_file() {
+        index
+            .attach_content(&content_db)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+
+    // 从动作菜单跳转而来、且仍在那次搜索之内时，顶部显示“返回”条目
+    let session = SessionStore::new(cache_dir)
+        .load(now_ms() / 1000)
+        .filter(|session| session.applies_to(&raw_query));
+
+    // 刚刷新过索引时要显示提示、空查询要列出搜索历史，这两种情况不走结果缓存
+    let result_cache = (config.result_cache > 0
+        && !query_str.is_empty()
+        && !matches!(index_status, Some(IndexEnsureStatus::Refreshed)))
+    .then(|| ResultCache::new(cache_dir, config.result_cache));
+    let cache_key = match &result_cache {
+        Some(_) => Some(search_cache_key(
+            index,
+            data_dir,
+            cache_dir,
+            (&raw_query, &filters, fuzzy, sort, limit, &session),
+        )?),
+        None => None,
+    };
+    if let Some((cache, key)) = result_cache.as_ref().zip(cache_key.as_deref()) {
+        if let Some(cached) = cache.load(key) {
+            timer.lap(Phase::Query);
+            if let Some(history) = &history {
+                history
+                    .record_results(&raw_query, cached.count)
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            }
+            if cached.count > 0 {
+                let last = LastResults::new(cache_dir);
+                let _ = last.save(&cached.payload);
+                let _ = last.save_query(&raw_query);
+            }
+            let mut writer = BufWriter::new(io::stdout().lock());
+            writer.write_all(&cached.payload)?;
+            writer.flush()?;
+            timer.lap(Phase::Render);
+            if config.latency_log {
+                let timing = timer.finish(cached.count);
+                let _ = LatencyStore::new(data_dir.join("latency.db"))
+                    .and_then(|store| store.record(&timing, now_ms() / 1000));
+            }
+            return Ok(());
+        }
+    }
+
+    // FTS 不可用时在 SQL 中按目录、来源、标签预筛候选，再交给内存打分
+    let load_all_filtered =
+        || -> Result<Vec<crate::bookmark::ChromeBookmark>, Box<dyn std::error::Error>> {
+            let bookmarks = if filters.is_empty() {
+                index.load_all_bookmarks()
+            } else {
+                index.load_bookmarks_filtered(&filters)
+            };
+            Ok(bookmarks.map_err(|e| AppError::DatabaseError(e.to_string()))?)
+        };
+
+    // 多样化需要超出 limit 的候选，才能把拥挤分组的结果换成其他分组的
+    let fetch_limit = if config.diversify.is_some() {
+        limit.saturating_mul(DIVERSIFY_CANDIDATE_MULTIPLIER)
+    } else {
+        limit
+    };
+
+    // 需要逐条打分全部书签时，预计超出内存预算就按批流式读取，只保留当前最优的 fetch_limit 条
+    let scan_all =
+        |fuzzy: bool| -> Result<Vec<crate::bookmark::ChromeBookmark>, Box<dyn std::error::Error>> {
+            let over_budget = match config.memory_budget {
+                Some(budget) => {
+                    let estimate = index
+                        .estimate_load_bytes(&filters)
+                        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                    if estimate > budget {
+                        eprintln!(
+                            "书签预计占用 {}，超出内存预算 {}，改为分批流式搜索",
+                            format_bytes(estimate),
+                            format_bytes(budget)
+                        );
+                    }
+                    estimate > budget
+                }
+                None => false,
+            };
+            if !over_budget {
+                let bookmarks = load_all_filtered()?;
+                let results =
+                    searcher.search(&bookmarks, &query_str, &filters.folders, fuzzy, fetch_limit);
+                return Ok(results.into_iter().map(|item| item.bookmark).collect());
+            }
+
+            // 上一批的最优结果排在前面，同分时仍按 rowid 先后取舍
+            let mut best: Vec<crate::bookmark::ChromeBookmark> = Vec::new();
+            index
+                .for_each_chunk(&filters, STREAM_CHUNK_SIZE, |chunk| {
+                    best.extend(chunk);
+                    best = searcher
+                        .search(&best, &query_str, &filters.folders, fuzzy, fetch_limit)
+                        .into_iter()
+                        .map(|item| item.bookmark)
+                        .collect();
+                })
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            Ok(best)
+        };
+    let fallback_exact = || scan_all(false);
+
+    // 粘贴的完整 URL 先按规范化 URL 精确查找，跟踪参数或主机写法不同也能直接找到
+    let pasted_url = query_str.trim();
+    let same_url = if pasted_url.contains("://") && !pasted_url.contains(char::is_whitespace) {
+        index
+            .bookmarks_with_url(pasted_url, &filters)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+    } else {
+        Vec::new()
+    };
+
+    let bookmarks = if !same_url.is_empty() {
+        same_url
+    } else if sort != SortOrder::Relevance {
+        if sort == SortOrder::Frecency {
+            let usage_db = data_dir.join("usage.db");
+            UsageStore::new(usage_db.clone())
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            index
+                .attach_usage(&usage_db)
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+        match index
+            .search_sorted(&query_str, &filters, sort, limit)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        {
+            Some(results) => results,
+            None => {
+                let mut results = fallback_exact()?;
+                searcher::sort_bookmarks(&mut results, sort);
+                results
+            }
+        }
+    } else if fuzzy {
+        let candidate_limit = std::cmp::max(
+            fetch_limit.saturating_mul(FUZZY_CANDIDATE_LIMIT_MULTIPLIER),
+            FUZZY_CANDIDATE_LIMIT_FLOOR,
+        );
+
+        let candidates = if query_str.is_empty() {
+            if filters.is_empty() {
+                index
+                    .list_bookmarks(candidate_limit)
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            } else {
                 index
-                    .list_bookmarks_by_folder_filters(&folder_filters, candidate_limit)
+                    .list_bookmarks_filtered(&filters, candidate_limit)
                     .map_err(|e| AppError::DatabaseError(e.to_string()))?
             }
-        } else if folder_filters.is_empty() {
+        } else if filters.is_empty() {
             match index
                 .search_bookmark_candidates_fts(&query_str, candidate_limit)
                 .map_err(|e| AppError::DatabaseError(e.to_string()))?
             {
                 Some(results) => results,
-                None => index
-                    .load_all_bookmarks()
-                    .map_err(|e| AppError::DatabaseError(e.to_string()))?,
+                None => scan_all(true)?,
             }
         } else {
             match index
-                .search_bookmark_candidates_fts_with_folders(
-                    &query_str,
-                    &folder_filters,
-                    candidate_limit,
-                )
+                .search_bookmark_candidates_fts_filtered(&query_str, &filters, candidate_limit)
                 .map_err(|e| AppError::DatabaseError(e.to_string()))?
             {
                 Some(results) => results,
-                None => index
-                    .load_all_bookmarks()
-                    .map_err(|e| AppError::DatabaseError(e.to_string()))?,
+                None => scan_all(true)?,
             }
         };
 
         searcher
-            .search(&candidates, &query_str, &folder_filters, true, limit)
+            .search(&candidates, &query_str, &filters.folders, true, fetch_limit)
             .into_iter()
             .map(|item| item.bookmark)
             .collect()
     } else if query_str.is_empty() {
-        if folder_filters.is_empty() {
+        if filters.is_empty() {
             index
-                .list_bookmarks(limit)
+                .list_bookmarks(fetch_limit)
                 .map_err(|e| AppError::DatabaseError(e.to_string()))?
         } else {
             index
-                .list_bookmarks_by_folder_filters(&folder_filters, limit)
+                .list_bookmarks_filtered(&filters, fetch_limit)
                 .map_err(|e| AppError::DatabaseError(e.to_string()))?
         }
-    } else if folder_filters.is_empty() {
+    } else if filters.is_empty() {
         match index
-            .search_bookmarks_fts(&query_str, limit)
+            .search_bookmarks_fts(&query_str, fetch_limit)
             .map_err(|e| AppError::DatabaseError(e.to_string()))?
         {
             Some(results) => results,
@@ -309,32 +1592,116 @@ fn handle_search(
         }
     } else {
         match index
-            .search_bookmarks_fts_with_folders(&query_str, &folder_filters, limit)
+            .search_bookmarks_fts_filtered(&query_str, &filters, fetch_limit)
             .map_err(|e| AppError::DatabaseError(e.to_string()))?
         {
             Some(results) => results,
             None => fallback_exact()?,
         }
     };
+    // 明确指定排序时不再打乱顺序
+    let bookmarks = match config.diversify.filter(|_| sort == SortOrder::Relevance) {
+        Some(diversify) => searcher::diversify(bookmarks, diversify.by, diversify.max_per_group),
+        None => bookmarks,
+    };
+    timer.lap(Phase::Query);
+    // 结果数供 `report queries` 找出没有结果的搜索
+    if let Some(history) = &history {
+        history
+            .record_results(&raw_query, bookmarks.len())
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
 
     let stdout = io::stdout();
     let mut writer = BufWriter::new(stdout.lock());
 
     let mut items = Vec::with_capacity(bookmarks.len());
+    let shown_ids: Vec<&str> = bookmarks
+        .iter()
+        .take(limit)
+        .map(|bookmark| bookmark.id.as_str())
+        .collect();
+    let variants = index
+        .variants_for(&shown_ids)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    // 副标题中的备注与 ⌘L 详情卡片用到的标签、打开记录，整页各查一次；库打不开时相应行留空，不影响搜索
+    let shown_keys: Vec<String> = bookmarks.iter().take(limit).map(tag_key).collect();
+    let shown_key_refs: Vec<&str> = shown_keys.iter().map(String::as_str).collect();
+    let (page_tags, page_notes) = match TagManager::new(data_dir.join("tags.db")) {
+        Ok(store) => (
+            store.tags_for_keys(&shown_key_refs).unwrap_or_default(),
+            store.notes_for_keys(&shown_key_refs).unwrap_or_default(),
+        ),
+        Err(_) => Default::default(),
+    };
+    let page_last_opened = if config.usage_tracking {
+        let urls: Vec<&str> = bookmarks
+            .iter()
+            .take(limit)
+            .map(|bookmark| bookmark.url.as_str())
+            .collect();
+        UsageStore::new(data_dir.join("usage.db"))
+            .and_then(|usage| usage.last_opened_for(&urls))
+            .unwrap_or_default()
+    } else {
+        HashMap::new()
+    };
+    // 只有一个来源时徽标没有信息量，不显示
+    let show_badge = config.source_badge
+        && index
+            .source_states()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .len()
+            > 1;
 
-    for bookmark in bookmarks.iter().take(limit) {
+    for (bookmark, key) in bookmarks.iter().take(limit).zip(&shown_keys) {
         let domain = extract_domain(&bookmark.url);
-        let subtitle = build_subtitle(&bookmark.folder_path, &domain);
-        let cmd_subtitle = format!("复制URL: {}", bookmark.url);
-        let opt_subtitle = format!("#{}", bookmark.folder_path.as_deref().unwrap_or("未分类"));
+        let mut subtitle = build_subtitle(&bookmark.folder_path, &domain);
+        if show_badge {
+            subtitle.push_str("  ");
+            subtitle.push_str(&source_badge(bookmark));
+        }
+        let note = page_notes.get(key);
+        if let Some(note) = note {
+            subtitle.push_str("  ✎ ");
+            subtitle.push_str(&note_preview(note));
+        }
+        let copy_url = config.tracking.clean_for_copy(&bookmark.url);
+        let cmd_subtitle = format!("复制URL: {}", copy_url);
+        let opt_subtitle = format!(
+            "后台打开 · #{}",
+            bookmark.folder_path.as_deref().unwrap_or("未分类")
+        );
         let open_arg = format!("open:{}", bookmark.url);
-        let copy_arg = format!("copy:{}", bookmark.url);
+        let open_bg_arg = format!("open-bg:{}", bookmark.url);
+        let copy_arg = format!("copy:{}", copy_url);
+        // 标题放在制表符之后，run.sh 据此拆成两个参数
+        let copy_rich_arg = format!(
+            "copyrich:{}\t{}",
+            bookmark.url,
+            bookmark.name.replace(['\t', '\n', '\r'], " ")
+        );
+        let queue_arg = format!(
+            "queue:{}\t{}",
+            bookmark.url,
+            bookmark.name.replace(['\t', '\n', '\r'], " ")
+        );
+        // 置顶与本地书签固定用单独图标以示区分；其余已下载的站点图标优先，未下载时用默认图标
+        let icon = if pinned.contains(&bookmark.id) {
+            ICON_PINNED.to_string()
+        } else if bookmark.browser == LOCAL_BROWSER {
+            ICON_LOCAL_BOOKMARK.to_string()
+        } else {
+            favicons
+                .and_then(|cache| cache.icon_for(&bookmark.url))
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_else(|| ICON_BOOKMARK.to_string())
+        };
         let item = alfred::ItemBuilder::new(&bookmark.name)
             .subtitle(subtitle)
             .arg(open_arg)
-            .uid(&bookmark.id)
             .quicklook_url(&bookmark.url)
-            .icon_path(ICON_BOOKMARK)
+            .icon_path(icon)
             .valid(true)
             .modifier(
                 alfred::Modifier::Command,
@@ -346,13 +1713,47 @@ fn handle_search(
             .modifier(
                 alfred::Modifier::Option,
                 Some(opt_subtitle),
-                None::<&str>,
-                false,
+                Some(open_bg_arg),
+                true,
                 Some(alfred::Icon::Path(Cow::Borrowed(ICON_ACTION_FOLDERS))),
             )
+            .modifier(
+                alfred::Modifier::Shift,
+                Some(format!("复制富文本链接: {}", bookmark.name)),
+                Some(copy_rich_arg),
+                true,
+                Some(alfred::Icon::Path(Cow::Borrowed(ICON_ACTION_COPY))),
+            )
+            .modifier(
+                alfred::Modifier::Fn,
+                Some(format!("显示二维码: {}", copy_url)),
+                Some(format!("qr:{}", bookmark.url)),
+                true,
+                None,
+            )
+            .modifier(
+                alfred::Modifier::Control,
+                Some(format!(
+                    "加入待打开队列 · {}",
+                    variants_subtitle(bookmark, variants.get(&bookmark.id))
+                )),
+                Some(queue_arg),
+                true,
+                None,
+            )
+            .autocomplete(format!(">{}", bookmark.id))
             .text_copy(&bookmark.url)
-            .text_large_type(&bookmark.name)
-            .into_item();
+            .text_large_type(details_card(
+                bookmark,
+                page_tags.get(key).map(Vec::as_slice).unwrap_or_default(),
+                note.map(String::as_str),
+                page_last_opened.get(&bookmark.url).copied(),
+            ));
+        let item = match config.uid.uid_for(bookmark, &raw_query) {
+            Some(uid) => item.uid(uid),
+            None => item,
+        }
+        .into_item();
 
         items.push(item);
     }
@@ -368,7 +1769,30 @@ fn handle_search(
         );
     }
 
-    if query_str.is_empty() && folder_filters.is_empty() {
+    if let Some((back_query, back_state)) = session.as_ref().and_then(SessionState::back) {
+        let at = usize::from(matches!(index_status, Some(IndexEnsureStatus::Refreshed)));
+        let context = session
+            .as_ref()
+            .and_then(|session| session.context.as_deref())
+            .unwrap_or("相关搜索");
+        items.insert(
+            at,
+            alfred::ItemBuilder::new(format!("← 返回: {}", back_query.trim()))
+                .subtitle(format!("{} · ↩ 回到上一次搜索", context))
+                .arg(SessionState::search_arg(back_query, back_state.as_ref()))
+                .autocomplete(back_query)
+                .valid(true)
+                .icon_path(ICON_ACTION_GUIDE)
+                .into_item(),
+        );
+    }
+
+    if query_str.is_empty() && filters.is_empty() {
+        if let Some(history) = &history {
+            let recent = history_items(history, "", RECENT_QUERIES_ON_EMPTY)?;
+            let at = usize::from(matches!(index_status, Some(IndexEnsureStatus::Refreshed)));
+            items.splice(at..at, recent);
+        }
         items.push(
             alfred::ItemBuilder::new("试试目录过滤：#work rust")
                 .subtitle("使用 #目录 语法快速过滤目录并搜索")
@@ -389,15 +1813,46 @@ fn handle_search(
         );
     }
 
-    let empty_subtitle = if folder_filters.is_empty() {
+    let mut active_filters = Vec::new();
+    if !filters.folders.is_empty() {
+        active_filters.push(format!("当前目录过滤: {}", filters.folders.join(", ")));
+    }
+    if !filters.browsers.is_empty() {
+        active_filters.push(format!("当前浏览器: {}", filters.browsers.join(", ")));
+    }
+    if !filters.profiles.is_empty() {
+        active_filters.push(format!("当前 profile: {}", filters.profiles.join(", ")));
+    }
+    if !filters.tags.is_empty() {
+        active_filters.push(format!("当前标签: {}", filters.tags.join(", ")));
+    }
+    let empty_subtitle = if active_filters.is_empty() {
         "尝试使用不同的关键词".to_string()
     } else {
-        format!(
-            "当前目录过滤: {} | 尝试使用不同关键词",
-            folder_filters.join(", ")
-        )
+        format!("{} | 尝试使用不同关键词", active_filters.join(" | "))
     };
 
+    if bookmarks.is_empty() && !query_str.is_empty() {
+        items.push(
+            alfred::ItemBuilder::new("未找到书签")
+                .subtitle(&empty_subtitle)
+                .valid(false)
+                .into_item(),
+        );
+        // 关键词不落空：提供网页搜索兜底
+        for fallback in &config.fallbacks {
+            let url = fallback.url_for(&query_str);
+            items.push(
+                alfred::ItemBuilder::new(format!("在 {} 搜索 “{}”", fallback.name, query_str))
+                    .subtitle(url.clone())
+                    .arg(format!("open:{}", url))
+                    .valid(true)
+                    .icon_path(ICON_ACTION_GUIDE)
+                    .into_item(),
+            );
+        }
+    }
+
     if items.is_empty() {
         items.push(
             alfred::ItemBuilder::new("未找到书签")
@@ -407,11 +1862,63 @@ fn handle_search(
         );
     }
 
-    alfred::json::write_items(&mut writer, &items)?;
+    let mut payload = Vec::new();
+    alfred::json::write_items(&mut payload, &items)?;
+    // 只保存真正搜到书签的结果，供 `last` 找回；空查询的引导和无结果都不覆盖
+    if !query_str.is_empty() && !bookmarks.is_empty() {
+        let last = LastResults::new(cache_dir);
+        let _ = last.save(&payload);
+        let _ = last.save_query(&raw_query);
+    }
+    if let Some((cache, key)) = result_cache.as_ref().zip(cache_key.as_deref()) {
+        let _ = cache.save(key, bookmarks.len(), &payload);
+    }
+    writer.write_all(&payload)?;
     writer.flush()?;
+    timer.lap(Phase::Render);
+    // 耗时记录失败不影响已输出的结果
+    if config.latency_log {
+        let timing = timer.finish(bookmarks.len());
+        let _ = LatencyStore::new(data_dir.join("latency.db"))
+            .and_then(|store| store.record(&timing, now_ms() / 1000));
+    }
     Ok(())
 }
 
+/// 结果缓存的键：查询参数、workflow 变量，加上索引指纹与索引、标签、打开记录、站点图标的改动痕迹；
+/// 任何一项变化都会换一个键，旧条目随 LRU 淘汰
+fn search_cache_key<T: std::hash::Hash>(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    cache_dir: &Path,
+    query: T,
+) -> Result<String, AppError> {
+    let fingerprint = index
+        .bookmarks_fingerprint()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let stamps: Vec<_> = [
+        "bookmarks.db",
+        "bookmarks.db-wal",
+        "tags.db",
+        "tags.db-wal",
+        "content.db",
+        "content.db-wal",
+        "usage.db",
+        "usage.db-wal",
+    ]
+    .iter()
+    .map(|name| result_cache::file_stamp(&data_dir.join(name)))
+    .chain([result_cache::file_stamp(&cache_dir.join("favicons"))])
+    .collect();
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(key, _)| {
+            key.starts_with("ALFRED_CHROME_BOOKMARKS_") && key != crate::db_crypt::DB_KEY_VAR
+        })
+        .collect();
+    vars.sort();
+    Ok(ResultCache::key((query, fingerprint, stamps, vars)))
+}
+
 fn extract_domain(url: &str) -> String {
     url.split("://")
         .nth(1)
@@ -422,6 +1929,34 @@ fn extract_domain(url: &str) -> String {
         .to_string()
 }
 
+/// 最近的搜索条目：回车或 Tab 把查询填回输入框重新搜索
+fn history_items(
+    history: &QueryHistory,
+    prefix: &str,
+    limit: usize,
+) -> Result<Vec<alfred::Item<'static>>, AppError> {
+    let entries = history
+        .recent(prefix, limit)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    if entries.is_empty() && !prefix.trim().is_empty() {
+        return Ok(vec![alfred::ItemBuilder::new("没有匹配的搜索历史")
+            .subtitle(format!("以 “{}” 开头的搜索还没有记录", prefix.trim()))
+            .valid(false)
+            .into_item()]);
+    }
+    Ok(entries
+        .into_iter()
+        .map(|entry| {
+            alfred::ItemBuilder::new(entry.query.clone())
+                .subtitle(format!("最近搜索 · {} 次 · 回车重新搜索", entry.uses))
+                .autocomplete(entry.query)
+                .valid(false)
+                .icon_path(ICON_ACTION_GUIDE)
+                .into_item()
+        })
+        .collect())
+}
+
 fn now_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -433,7 +1968,10 @@ fn index_check_state_path(cache_dir: &Path) -> std::path::PathBuf {
     cache_dir.join(INDEX_CHECK_STATE_FILE)
 }
 
-fn is_index_check_recent(cache_dir: &Path, now: u64) -> bool {
+fn is_index_check_recent(cache_dir: &Path, now: u64, ttl_ms: u64) -> bool {
+    if ttl_ms == 0 {
+        return false;
+    }
     let path = index_check_state_path(cache_dir);
     let bytes = match std::fs::read(path) {
         Ok(bytes) => bytes,
@@ -445,7 +1983,7 @@ fn is_index_check_recent(cache_dir: &Path, now: u64) -> bool {
         Err(_) => return false,
     };
 
-    now.saturating_sub(state.last_checked_ms) <= INDEX_CHECK_TTL_MS
+    now.saturating_sub(state.last_checked_ms) <= ttl_ms
 }
 
 fn mark_index_checked_recently(cache_dir: &Path) {
@@ -480,17 +2018,36 @@ where
     normalized
 }
 
-fn parse_query_and_folder_filters(raw_query: &str) -> (String, Vec<String>) {
+/// 逗号分隔的浏览器名，统一为内部 key（google-chrome -> chrome）
+fn parse_browser_terms(raw: &str) -> Vec<String> {
+    normalize_csv_terms(raw.split(','))
+        .iter()
+        .map(|value| canonical_browser_key(value))
+        .collect()
+}
+
+fn parse_query_and_folder_filters(raw_query: &str) -> (String, BookmarkFilters) {
     let mut query_tokens = Vec::new();
-    let mut folder_filters: Vec<String> = Vec::new();
+    let mut filters = BookmarkFilters::default();
+    let mut in_phrase = false;
 
     for token in raw_query.split_whitespace() {
+        // 引号短语内的词原样保留：`"see tag:rust"` 不是标签过滤
+        let quoted = in_phrase;
+        if token.matches('"').count() % 2 == 1 {
+            in_phrase = !in_phrase;
+        }
+        if quoted {
+            query_tokens.push(token.to_string());
+            continue;
+        }
+
         if let Some(value) = token.strip_prefix('#') {
             if value.is_empty() {
                 continue;
             }
             let values = normalize_csv_terms(value.split(','));
-            append_unique_case_insensitive(&mut folder_filters, values);
+            append_unique_case_insensitive(&mut filters.folders, values);
             continue;
         }
 
@@ -501,14 +2058,35 @@ fn parse_query_and_folder_filters(raw_query: &str) -> (String, Vec<String>) {
             .or_else(|| token.strip_prefix("in:"))
         {
             let values = normalize_csv_terms(value.split(','));
-            append_unique_case_insensitive(&mut folder_filters, values);
+            append_unique_case_insensitive(&mut filters.folders, values);
+            continue;
+        }
+
+        if let Some(value) = token.strip_prefix("browser:") {
+            append_unique_case_insensitive(&mut filters.browsers, parse_browser_terms(value));
+            continue;
+        }
+
+        if let Some(value) = token.strip_prefix("profile:") {
+            let values = normalize_csv_terms(value.split(','));
+            append_unique_case_insensitive(&mut filters.profiles, values);
+            continue;
+        }
+
+        // `@rust` 是 `tag:rust` 的简写
+        if let Some(value) = token
+            .strip_prefix("tag:")
+            .or_else(|| token.strip_prefix('@'))
+        {
+            let values = normalize_csv_terms(value.split(','));
+            append_unique_case_insensitive(&mut filters.tags, values);
             continue;
         }
 
         query_tokens.push(token.to_string());
     }
 
-    (query_tokens.join(" "), folder_filters)
+    (query_tokens.join(" "), filters)
 }
 
 fn append_unique_case_insensitive(target: &mut Vec<String>, values: Vec<String>) {
@@ -529,28 +2107,69 @@ fn workflow_actions() -> Vec<WorkflowAction> {
             subtitle: "重新扫描书签并重建索引",
             arg: "action:refresh",
             icon_path: ICON_ACTION_REFRESH,
+            autocomplete: None,
         },
         WorkflowAction {
             title: "Show Stats",
-            subtitle: "显示当前书签总数",
+            subtitle: "书签总数、目录、站点、标签与来源状态",
             arg: "action:stats",
             icon_path: ICON_ACTION_STATS,
+            autocomplete: Some("stats "),
         },
         WorkflowAction {
             title: "Open Workflow Guide",
             subtitle: "打开本地 ALFRED_WORKFLOW_GUIDE.md",
             arg: "action:open_guide",
             icon_path: ICON_ACTION_GUIDE,
+            autocomplete: None,
         },
         WorkflowAction {
             title: "Open README",
             subtitle: "打开本地 README.md",
             arg: "action:open_readme",
             icon_path: ICON_ACTION_README,
+            autocomplete: None,
         },
     ]
 }
 
+/// ⌃ 查看合并时被折叠的其他来源版本
+fn variants_subtitle(
+    bookmark: &crate::bookmark::ChromeBookmark,
+    variants: Option<&Vec<crate::bookmark::ChromeBookmark>>,
+) -> String {
+    let source = format!("{} · {}", bookmark.browser, bookmark.profile);
+    match variants {
+        Some(variants) if !variants.is_empty() => {
+            let others: Vec<String> = variants
+                .iter()
+                .map(|variant| {
+                    format!(
+                        "{} · {}「{}」",
+                        variant.browser, variant.profile, variant.name
+                    )
+                })
+                .collect();
+            format!(
+                "来自 {} | 另有 {} 个来源版本: {}",
+                source,
+                variants.len(),
+                others.join(" / ")
+            )
+        }
+        _ => format!("来自 {}", source),
+    }
+}
+
+/// 来源徽标，如 `[Arc·Work]`
+fn source_badge(bookmark: &crate::bookmark::ChromeBookmark) -> String {
+    format!(
+        "[{}·{}]",
+        browser_display_name(&bookmark.browser),
+        bookmark.profile
+    )
+}
+
 fn build_subtitle(folder_path: &Option<String>, domain: &str) -> String {
     let mut parts = Vec::new();
 
@@ -570,80 +2189,3573 @@ fn build_subtitle(folder_path: &Option<String>, domain: &str) -> String {
     parts.join(" → ")
 }
 
-fn handle_stats(index: &BookmarkIndex) -> Result<(), Box<dyn std::error::Error>> {
-    let total_bookmarks = index
-        .get_total_bookmarks()
+/// 统计列表中每个分组列出的条数
+const STATS_TOP_N: usize = 5;
+/// `cba untagged` 列出的书签数
+const UNTAGGED_ALFRED_LIMIT: usize = 50;
+
+/// 统计列表：总数、各根目录、常用目录、常用站点、标签、来源状态；目录 / 站点 / 标签行回车即以 cb 搜索
+fn handle_stats(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    cache_dir: &Path,
+    browsers: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let states = index
+        .source_states()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let freshness = source_freshness(
+        &discover_bookmark_sources_cached(cache_dir, browsers),
+        &states,
+    );
+    let tag_counts = TagManager::new(data_dir.join("tags.db"))
+        .and_then(|tags| tags.tag_counts())
         .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let total = bookmarks.len();
+
+    // run.sh 的 action:stats 会把第一项的 subtitle 作为通知内容
+    let mut items = vec![alfred::ItemBuilder::new("统计信息")
+        .subtitle(format!(
+            "书签总数: {} | {} 个来源 | {} 个标签",
+            total,
+            freshness.len(),
+            tag_counts.len()
+        ))
+        .valid(false)
+        .icon_path(ICON_ACTION_STATS)
+        .into_item()];
+
+    let stat_item = |title: String, count: usize, search: String, icon: &'static str| {
+        alfred::ItemBuilder::new(title)
+            .subtitle(format!(
+                "{} 个书签 · {:.1}% · ↩ 搜索",
+                count,
+                count as f64 * 100.0 / total.max(1) as f64
+            ))
+            .arg(format!("search:{}", search))
+            .valid(true)
+            .icon_path(icon)
+            .into_item()
+    };
+    let roots = crate::report::count_by(&bookmarks, |bookmark| {
+        let folder = bookmark.folder_path.as_deref()?;
+        Some(folder.split('/').next().unwrap_or(folder).to_string())
+    });
+    items.extend(roots.into_iter().map(|(root, count)| {
+        let search = format!("#{} ", folder_filter_token(&root));
+        stat_item(
+            format!("根目录 · {}", root),
+            count,
+            search,
+            ICON_ACTION_FOLDERS,
+        )
+    }));
+    let folders = crate::report::count_by(&bookmarks, |bookmark| bookmark.folder_path.clone());
+    items.extend(
+        folders
+            .into_iter()
+            .take(STATS_TOP_N)
+            .map(|(folder, count)| {
+                let search = format!("#{} ", folder_filter_token(&folder));
+                stat_item(
+                    format!("目录 · {}", folder),
+                    count,
+                    search,
+                    ICON_ACTION_FOLDERS,
+                )
+            }),
+    );
+    let domains = crate::report::count_by(&bookmarks, crate::report::bookmark_domain);
+    items.extend(
+        domains
+            .into_iter()
+            .take(STATS_TOP_N)
+            .map(|(domain, count)| {
+                let search = format!("{} ", domain);
+                stat_item(format!("站点 · {}", domain), count, search, ICON_BOOKMARK)
+            }),
+    );
+    items.extend(
+        tag_counts
+            .into_iter()
+            .take(STATS_TOP_N)
+            .map(|(tag, count)| {
+                let search = format!("tag:{} ", tag);
+                stat_item(format!("标签 · {}", tag), count, search, ICON_ACTION_STATS)
+            }),
+    );
+    items.extend(source_freshness_items(&freshness));
 
-    show_info_alfred(format!("书签总数: {}", total_bookmarks));
+    alfred::json::write_items(io::stdout(), &items)?;
     Ok(())
 }
 
-fn handle_actions(query: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    let keyword = query.join(" ").trim().to_lowercase();
-    let mut items = Vec::new();
-
-    for action in workflow_actions() {
-        if !keyword.is_empty() {
-            let title_match = action.title.to_lowercase().contains(&keyword);
-            let subtitle_match = action.subtitle.to_lowercase().contains(&keyword);
-            if !title_match && !subtitle_match {
-                continue;
-            }
-        }
+/// 最近搜索的耗时分布；未开启记录时提示如何开启
+fn handle_stats_latency(
+    store: &LatencyStore,
+    enabled: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let summary = store
+        .summary()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        items.push(
-            alfred::ItemBuilder::new(action.title)
-                .subtitle(action.subtitle)
-                .arg(action.arg)
-                .valid(true)
-                .icon_path(action.icon_path)
-                .into_item(),
-        );
+    if json {
+        let stdout = io::stdout();
+        let mut writer = BufWriter::new(stdout.lock());
+        serde_json::to_writer_pretty(&mut writer, &summary)?;
+        writeln!(writer)?;
+        return Ok(());
     }
 
-    if items.is_empty() {
-        items.push(
-            alfred::ItemBuilder::new("未找到动作")
-                .subtitle("尝试输入 refresh / stats / guide / readme")
-                .valid(false)
-                .into_item(),
-        );
+    let header = match (summary.first_at, summary.last_at) {
+        (Some(first), Some(last)) => {
+            alfred::ItemBuilder::new(format!("最近 {} 次搜索的耗时", summary.samples)).subtitle(
+                format!(
+                    "{} — {} UTC{}",
+                    crate::report::format_datetime(first as i64),
+                    crate::report::format_datetime(last as i64),
+                    if enabled { "" } else { " | 记录已关闭" }
+                ),
+            )
+        }
+        _ => alfred::ItemBuilder::new("还没有耗时记录").subtitle(if enabled {
+            "搜索几次后再来查看"
+        } else {
+            "设置 ALFRED_CHROME_BOOKMARKS_LATENCY=on 后开始记录（仅保存在本地）"
+        }),
+    };
+    let mut items = vec![header.valid(false).icon_path(ICON_ACTION_STATS).into_item()];
+    if summary.samples > 0 {
+        items.extend(summary.phases.iter().map(|phase| {
+            let name = match phase.phase {
+                "index" => "索引检查",
+                "query" => "查询",
+                "render" => "输出",
+                _ => "总计",
+            };
+            alfred::ItemBuilder::new(format!(
+                "{} · p50 {:.1} ms · p95 {:.1} ms",
+                name, phase.p50_ms, phase.p95_ms
+            ))
+            .subtitle(format!("最慢 {:.1} ms", phase.max_ms))
+            .valid(false)
+            .icon_path(ICON_ACTION_STATS)
+            .into_item()
+        }));
     }
-
     alfred::json::write_items(io::stdout(), &items)?;
     Ok(())
 }
 
-fn show_error_alfred<'a, T: Into<Cow<'a, str>>>(s: T) {
-    let item = alfred::ItemBuilder::new("✗ 操作失败")
-        .subtitle(s)
-        .icon_path("icons/error.png")
-        .valid(false)
-        .into_item();
-    let _ = alfred::json::write_items(io::stdout(), &[item]);
+/// `stats index --json` 中的单个数据库文件
+#[derive(Debug, Serialize)]
+struct DatabaseFile {
+    name: String,
+    bytes: u64,
+    wal_bytes: u64,
 }
 
-fn show_info_alfred<'a, T: Into<Cow<'a, str>>>(s: T) {
-    let item = alfred::ItemBuilder::new("✓ 操作完成")
-        .subtitle(s)
-        .icon_path("icons/info.png")
-        .valid(false)
-        .into_item();
-    let _ = alfred::json::write_items(io::stdout(), &[item]);
+/// `stats index --json` 的输出
+#[derive(Debug, Serialize)]
+struct IndexStatsRecord {
+    files: Vec<DatabaseFile>,
+    index: IndexHealth,
 }
 
-#[cfg(test)]
-mod tests {
+/// 数据目录下的全部 SQLite 文件（按名称排序）及各自的 WAL 大小
+fn database_files(data_dir: &Path) -> Vec<DatabaseFile> {
+    let file_size = |path: &Path| std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+    let mut files: Vec<DatabaseFile> = std::fs::read_dir(data_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "db"))
+                .map(|path| {
+                    let mut wal = path.clone().into_os_string();
+                    wal.push("-wal");
+                    DatabaseFile {
+                        name: path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default(),
+                        bytes: file_size(&path),
+                        wal_bytes: file_size(Path::new(&wal)),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    files
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// 索引健康指标：文件与 WAL 大小、FTS 占用、碎片率、上次 VACUUM 与刷新耗时、各表行数
+fn handle_stats_index(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    vacuum: bool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if vacuum {
+        index
+            .vacuum((now_ms() / 1000) as i64)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+    let health = index
+        .health()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let files = database_files(data_dir);
+
+    if json {
+        let stdout = io::stdout();
+        let mut writer = BufWriter::new(stdout.lock());
+        serde_json::to_writer_pretty(
+            &mut writer,
+            &IndexStatsRecord {
+                files,
+                index: health,
+            },
+        )?;
+        writeln!(writer)?;
+        return Ok(());
+    }
+
+    let total_bytes: u64 = files.iter().map(|file| file.bytes + file.wal_bytes).sum();
+    let wal_bytes: u64 = files.iter().map(|file| file.wal_bytes).sum();
+    let mut items = vec![alfred::ItemBuilder::new("索引健康")
+        .subtitle(format!(
+            "数据文件共 {} | WAL {} | 碎片 {:.1}%",
+            format_bytes(total_bytes),
+            format_bytes(wal_bytes),
+            health.fragmentation()
+        ))
+        .valid(false)
+        .icon_path(ICON_ACTION_STATS)
+        .into_item()];
+
+    let info_item = |title: String, subtitle: String| {
+        alfred::ItemBuilder::new(title)
+            .subtitle(subtitle)
+            .valid(false)
+            .icon_path(ICON_ACTION_STATS)
+            .into_item()
+    };
+    items.extend(files.iter().map(|file| {
+        info_item(
+            format!("{} · {}", file.name, format_bytes(file.bytes)),
+            format!("WAL {}", format_bytes(file.wal_bytes)),
+        )
+    }));
+    items.push(info_item(
+        format!(
+            "全文索引 · {}",
+            health
+                .fts_bytes
+                .map(format_bytes)
+                .unwrap_or_else(|| "无法统计".to_string())
+        ),
+        "FTS5 虚拟表连同影子表占用的空间".to_string(),
+    ));
+    items.push(info_item(
+        format!("碎片 · {:.1}%", health.fragmentation()),
+        format!(
+            "{} / {} 页空闲（每页 {}）| 上次 VACUUM {}",
+            health.freelist_count,
+            health.page_count,
+            format_bytes(health.page_size),
+            health
+                .last_vacuum
+                .map(|at| format!("{} UTC", crate::report::format_datetime(at)))
+                .unwrap_or_else(|| "从未".to_string())
+        ),
+    ));
+    items.push(info_item(
+        match health.last_refresh_ms {
+            Some(ms) => format!("上次刷新耗时 · {} ms", ms),
+            None => "上次刷新耗时 · 未记录".to_string(),
+        },
+        health
+            .last_refresh_at
+            .map(|at| format!("完成于 {} UTC", crate::report::format_datetime(at)))
+            .unwrap_or_else(|| "执行一次 refresh 后记录".to_string()),
+    ));
+    items.extend(
+        health
+            .tables
+            .iter()
+            .map(|(table, rows)| info_item(format!("表 · {}", table), format!("{} 行", rows))),
+    );
+
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+fn handle_doctor(
+    index: &BookmarkIndex,
+    read_only: Option<&str>,
+    data_dir: &Path,
+    cache_dir: &Path,
+    browsers: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let total_bookmarks = index
+        .get_total_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let states = index
+        .source_states()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let sources = discover_bookmark_sources_cached(cache_dir, browsers);
+    let freshness = source_freshness(&sources, &states);
+
+    let problems = freshness
+        .iter()
+        .filter(|item| item.status != FreshnessStatus::Fresh)
+        .count();
+    let summary = if sources.is_empty() {
+        alfred::ItemBuilder::new("未找到书签来源")
+            .subtitle("检查浏览器是否已安装，或设置 ALFRED_CHROME_BOOKMARKS_PATH")
+            .icon_path(ICON_ERROR)
+    } else if problems == 0 {
+        alfred::ItemBuilder::new("一切正常")
+            .subtitle(format!("{} 个来源均已索引且为最新", sources.len()))
+            .icon_path(ICON_ACTION_STATS)
+    } else {
+        alfred::ItemBuilder::new(format!("{} 个来源待刷新", problems))
+            .subtitle("下次搜索会自动刷新，或回车立即刷新")
+            .arg("action:refresh")
+            .icon_path(ICON_ACTION_REFRESH)
+    };
+
+    let mut items = Vec::new();
+    if let Some(reason) = read_only {
+        items.push(
+            alfred::ItemBuilder::new("只读模式：本次使用内存索引")
+                .subtitle(format!(
+                    "{} · 搜索可用，但标签、历史与打开记录不会保存",
+                    reason
+                ))
+                .valid(false)
+                .icon_path(ICON_ERROR)
+                .into_item(),
+        );
+    }
+    items.extend([
+        summary.valid(problems > 0).into_item(),
+        alfred::ItemBuilder::new(format!("索引: {} 条书签", total_bookmarks))
+            .subtitle(format!(
+                "FTS5 {} | 数据目录 {}",
+                if index.fts_enabled() {
+                    "可用"
+                } else {
+                    "不可用（回退为全量扫描）"
+                },
+                data_dir.display()
+            ))
+            .valid(false)
+            .icon_path(ICON_ACTION_STATS)
+            .into_item(),
+    ]);
+    if crate::db_crypt::key().is_some() {
+        items.push(
+            alfred::ItemBuilder::new("元数据库已加密")
+                .subtitle(format!(
+                    "{} · SQLCipher {}",
+                    crate::db_crypt::ENCRYPTED_DATABASES.join("、"),
+                    crate::db_crypt::cipher_version().unwrap_or_default()
+                ))
+                .valid(false)
+                .icon_path(ICON_ACTION_STATS)
+                .into_item(),
+        );
+    }
+    items.extend(schedule_status_items());
+    items.extend(source_freshness_items(&freshness));
+
+    write_items_with_progress(items, cache_dir)
+}
+
+/// 热键触发时在后台执行：打开数据库（PRAGMA 在 `BookmarkIndex::new` 中完成）、预读索引页、写好来源缓存
+/// 输出被丢弃，因此不打印 Alfred JSON
+fn handle_warmup(
+    index: &BookmarkIndex,
+    cache_dir: &Path,
+    browsers: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    index
+        .warm_up()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    if discover_bookmark_sources_cached(cache_dir, browsers).is_empty() {
+        return Err(AppError::BookmarksNotFound.into());
+    }
+    Ok(())
+}
+
+/// 供后续 workflow 步骤按 item 变量中的 id 取回完整记录
+fn handle_get(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    links: &LinkStore,
+    usage: &UsageStore,
+    id: &str,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .find_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let record = bookmark_record(index, tags, links, usage, &bookmark)?;
+
+    if json {
+        let stdout = io::stdout();
+        let mut writer = BufWriter::new(stdout.lock());
+        serde_json::to_writer_pretty(&mut writer, &record)?;
+        writeln!(writer)?;
+        return Ok(());
+    }
+
+    let mut details = vec![format!("{} · {}", bookmark.browser, bookmark.profile)];
+    details.push(format!(
+        "#{}",
+        bookmark.folder_path.as_deref().unwrap_or("未分类")
+    ));
+    if !record.tags.is_empty() {
+        details.push(format!("标签: {}", record.tags.join(", ")));
+    }
+    if let Some(note) = &record.note {
+        details.push(format!("备注: {}", note_preview(note)));
+    }
+    if !record.variants.is_empty() {
+        details.push(format!("另有 {} 个来源版本", record.variants.len()));
+    }
+    if let Some(result) = &record.link_check {
+        details.push(if result.dead {
+            "链接失效".to_string()
+        } else {
+            format!("链接 {}", result.status)
+        });
+    }
+    if record.open_count > 0 {
+        details.push(format!("打开 {} 次", record.open_count));
+    }
+
+    let item = alfred::ItemBuilder::new(bookmark.name.as_str())
+        .subtitle(details.join(" | "))
+        .arg(format!("open:{}", bookmark.url))
+        .uid(bookmark.id.as_str())
+        .variable("bookmark_id", bookmark.id.as_str())
+        .variable("bookmark_url", bookmark.url.as_str())
+        .text_copy(bookmark.url.as_str())
+        .icon_path(ICON_BOOKMARK)
+        .valid(true)
+        .into_item();
+    alfred::json::write_items(io::stdout(), &[item])?;
+    Ok(())
+}
+
+/// 汇总一个书签在各个库中的信息：标签与备注（tags.db）、其他来源版本、链接检查结果（links.db）与打开记录（usage.db）
+fn bookmark_record<'a>(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    links: &LinkStore,
+    usage: &UsageStore,
+    bookmark: &'a crate::bookmark::ChromeBookmark,
+) -> Result<BookmarkRecord<'a>, AppError> {
+    let tag_names = tags
+        .tags_for(&tag_key(bookmark))
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let note = tags
+        .note_for(&tag_key(bookmark))
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let variants = index
+        .variants_for(&[bookmark.id.as_str()])
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .remove(&bookmark.id)
+        .unwrap_or_default();
+    let link_check = links
+        .result_for(&bookmark.url)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let opens = usage
+        .open_stats(&bookmark.url)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok(BookmarkRecord {
+        id: &bookmark.id,
+        name: &bookmark.name,
+        url: &bookmark.url,
+        folder_path: bookmark.folder_path.as_deref(),
+        date_added: date_added_to_unix_secs(&bookmark.date_added),
+        browser: &bookmark.browser,
+        profile: &bookmark.profile,
+        tags: tag_names,
+        note,
+        variants,
+        link_check: link_check.map(LinkCheckRecord::from),
+        open_count: opens.map_or(0, |(count, _)| count),
+        last_opened: opens.map(|(_, last_opened)| last_opened),
+    })
+}
+
+/// 常驻进程：日志写到 stderr，不输出 Alfred JSON
+fn handle_watch(
+    index: &BookmarkIndex,
+    cache: &BookmarkCache,
+    data_dir: &Path,
+    cache_dir: &Path,
+    config: &Config,
+    debounce_ms: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let sources = discover_bookmark_sources_fresh(cache_dir, &config.browsers);
+    if sources.is_empty() {
+        return Err(AppError::BookmarksNotFound.into());
+    }
+    refresh_bookmark_index(index, cache, &sources, data_dir, cache_dir, config, false)?;
+    mark_index_checked_recently(cache_dir);
+    eprintln!(
+        "正在监听 {} 个书签来源（新建 profile 后需重启 watch）",
+        sources.len()
+    );
+
+    crate::watch::watch_sources(&sources, Duration::from_millis(debounce_ms), || {
+        let started = Instant::now();
+        match refresh_bookmark_index(index, cache, &sources, data_dir, cache_dir, config, false) {
+            Ok(()) => {
+                mark_index_checked_recently(cache_dir);
+                eprintln!("索引已更新（{} ms）", started.elapsed().as_millis());
+            }
+            Err(err) => eprintln!("刷新失败: {}", err),
+        }
+    })
+}
+
+fn scheduled_task_name(task: ScheduledTask) -> &'static str {
+    match task {
+        ScheduledTask::Refresh => "定时刷新",
+        ScheduledTask::LinkScan => "失效链接扫描",
+    }
+}
+
+/// 定时刷新总是显示（未安装时给出提示）；链接扫描只在安装后显示
+fn schedule_status_items() -> Vec<alfred::Item<'static>> {
+    let mut items = vec![schedule_status_item(ScheduledTask::Refresh)];
+    let home = dirs::home_dir();
+    if home
+        .as_deref()
+        .and_then(|home| crate::schedule::status(home, ScheduledTask::LinkScan))
+        .is_some()
+    {
+        items.push(schedule_status_item(ScheduledTask::LinkScan));
+    }
+    items
+}
+
+fn schedule_status_item(task: ScheduledTask) -> alfred::Item<'static> {
+    let status = dirs::home_dir().and_then(|home| crate::schedule::status(&home, task));
+    let (title, subtitle) = match status {
+        Some(status) => (
+            format!(
+                "{}: 每 {} 分钟{}",
+                scheduled_task_name(task),
+                status
+                    .interval_secs
+                    .unwrap_or(crate::schedule::DEFAULT_INTERVAL_SECS)
+                    / 60,
+                if status.loaded { "" } else { "（未加载）" }
+            ),
+            status.plist_path.display().to_string(),
+        ),
+        None => (
+            "定时刷新: 未安装".to_string(),
+            "运行 schedule install 让索引在后台保持最新".to_string(),
+        ),
+    };
+
+    alfred::ItemBuilder::new(title)
+        .subtitle(subtitle)
+        .valid(false)
+        .icon_path(ICON_ACTION_REFRESH)
+        .into_item()
+}
+
+fn handle_schedule(
+    cmd: ScheduleCommand,
+    cache_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let home = dirs::home_dir().ok_or_else(|| AppError::Other("无法获取home目录".to_string()))?;
+
+    match cmd {
+        ScheduleCommand::Install {
+            interval,
+            link_scan_interval,
+        } => {
+            let program = std::env::current_exe()?;
+            let env = crate::schedule::forwarded_env();
+            let mut tasks = vec![(ScheduledTask::Refresh, interval)];
+            tasks.extend(link_scan_interval.map(|secs| (ScheduledTask::LinkScan, secs)));
+
+            let mut installed = Vec::new();
+            for (task, interval) in tasks {
+                let plist = crate::schedule::render_plist(
+                    task,
+                    &program,
+                    interval,
+                    &env,
+                    &cache_dir.join(task.log_file()),
+                );
+                crate::schedule::install(&home, task, &plist)
+                    .map_err(|e| AppError::Other(e.to_string()))?;
+                installed.push(format!(
+                    "{}（每 {} 秒）",
+                    scheduled_task_name(task),
+                    interval.max(crate::schedule::MIN_INTERVAL_SECS)
+                ));
+            }
+            show_info_alfred(format!("已安装{}", installed.join("、")));
+        }
+        ScheduleCommand::Remove => {
+            let mut removed = Vec::new();
+            for task in [ScheduledTask::Refresh, ScheduledTask::LinkScan] {
+                if crate::schedule::remove(&home, task)
+                    .map_err(|e| AppError::Other(e.to_string()))?
+                {
+                    removed.push(scheduled_task_name(task));
+                }
+            }
+            show_info_alfred(if removed.is_empty() {
+                "未安装定时任务".to_string()
+            } else {
+                format!("已移除{}", removed.join("、"))
+            });
+        }
+    }
+    Ok(())
+}
+
+fn source_freshness_items(freshness: &[SourceFreshness]) -> Vec<alfred::Item<'static>> {
+    freshness
+        .iter()
+        .map(|item| {
+            let status = match item.status {
+                FreshnessStatus::Fresh => "已最新",
+                FreshnessStatus::Pending => "待刷新",
+                FreshnessStatus::Removed => "来源已不存在",
+            };
+            let detail = match &item.indexed {
+                Some(state) => format!(
+                    "{} 条 | 上次刷新 {} UTC | 指纹 {}",
+                    state.bookmark_count,
+                    crate::report::format_datetime(state.refreshed_at),
+                    short_fingerprint(&state.fingerprint)
+                ),
+                None => "尚未索引".to_string(),
+            };
+
+            // 待刷新的来源回车即刷新索引
+            alfred::ItemBuilder::new(format!("{} · {} — {}", item.browser, item.profile, status))
+                .subtitle(detail)
+                .text_copy(item.path.clone())
+                .arg("action:refresh")
+                .valid(item.status != FreshnessStatus::Fresh)
+                .icon_path(if item.status == FreshnessStatus::Fresh {
+                    ICON_BOOKMARK
+                } else {
+                    ICON_ERROR
+                })
+                .into_item()
+        })
+        .collect()
+}
+
+/// 可检查的链接：http(s)、未被忽略、去重后保持书签顺序
+fn checkable_urls<'a>(
+    bookmarks: &'a [crate::bookmark::ChromeBookmark],
+    store: &LinkStore,
+) -> Result<Vec<&'a str>, Box<dyn std::error::Error>> {
+    let ignore = store
+        .ignore_list()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let mut seen = HashSet::new();
+    Ok(bookmarks
+        .iter()
+        .map(|bookmark| bookmark.url.as_str())
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .filter(|url| !ignore.matches(url))
+        .filter(|url| seen.insert(*url))
+        .collect())
+}
+
+/// 检查单个链接并保存结果（含 HTTPS 升级检查）
+fn check_link(
+    probe: &CurlProbe,
+    store: &LinkStore,
+    url: &str,
+) -> Result<LinkCheckResult, Box<dyn std::error::Error>> {
+    let result = resolve_link(probe, url);
+    store
+        .record(&result)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    if let Some(https) = check_https_upgrade(probe, url) {
+        store
+            .record_https(url, &https)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+    Ok(result)
+}
+
+/// 把待检查链接加入后台队列，最久未检查的排在前面
+fn handle_enqueue_link_checks(
+    index: &BookmarkIndex,
+    store: &LinkStore,
+    queue: &JobQueue,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let mut urls = checkable_urls(&bookmarks, store)?;
+    let last_checked = store
+        .last_checked()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    urls.sort_by_key(|url| last_checked.get(*url).copied().unwrap_or(0));
+
+    let now = now_ms() / 1000;
+    let mut queued = 0usize;
+    for url in &urls {
+        if queue
+            .enqueue(JobKind::LinkCheck, url, now)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        {
+            queued += 1;
+        }
+    }
+
+    show_info_alfred(format!(
+        "已加入 {} 个链接检查任务（{} 个已在队列中），运行 run-jobs 处理",
+        queued,
+        urls.len() - queued
+    ));
+    Ok(())
+}
+
+/// 处理到期的后台任务；网络错误视为失败并按退避时间重试，超过上限后放弃
+/// 交互式的 check-links 启动时让出（取代进度），未处理的任务在租约过期后重新可领取
+fn process_jobs(
+    queue: &JobQueue,
+    store: &LinkStore,
+    cache_dir: &Path,
+    kind: Option<JobKind>,
+    limit: usize,
+    timeout: u64,
+) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let jobs = queue
+        .claim_due(now_ms() / 1000, limit, kind)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    if jobs.is_empty() {
+        return Ok((0, 0));
+    }
+    let ignore = store
+        .ignore_list()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let probe = CurlProbe::new(Duration::from_secs(timeout));
+    let favicons = FaviconCache::new(cache_dir);
+    // 图标下载与链接检查分开汇报进度，互不取代
+    let mut progress = ProgressTracker::start(
+        cache_dir,
+        if kind == Some(JobKind::Favicon) {
+            JOB_FAVICONS
+        } else {
+            JOB_CHECK_LINKS
+        },
+    );
+
+    let (mut done, mut failed) = (0usize, 0usize);
+    for job in &jobs {
+        if progress
+            .update("后台任务", done + failed, jobs.len())
+            .is_err()
+        {
+            break;
+        }
+        let outcome = match job.kind {
+            JobKind::LinkCheck if ignore.matches(&job.target) => Ok(()),
+            JobKind::LinkCheck => match check_link(&probe, store, &job.target)?.error {
+                Some(error) => Err(error),
+                None => Ok(()),
+            },
+            JobKind::Favicon => favicons
+                .fetch(&job.target, Duration::from_secs(timeout))
+                .map(|_| ()),
+        };
+
+        let finished_at = now_ms() / 1000;
+        let recorded = match outcome {
+            Ok(()) => {
+                done += 1;
+                queue.complete(job.id, finished_at)
+            }
+            Err(error) => {
+                failed += 1;
+                queue.fail(job, &error, finished_at)
+            }
+        };
+        recorded.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+    progress.finish();
+
+    Ok((done, failed))
+}
+
+fn jobs_summary(
+    queue: &JobQueue,
+    done: usize,
+    failed: usize,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let counts = queue
+        .counts(now_ms() / 1000)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    Ok(format!(
+        "完成 {} 个任务，失败 {} 个 | 队列剩余 {}（已到期 {}），已放弃 {}",
+        done, failed, counts.queued, counts.due, counts.failed
+    ))
+}
+
+fn handle_run_jobs(
+    queue: &JobQueue,
+    store: &LinkStore,
+    cache_dir: &Path,
+    kind: Option<JobKind>,
+    limit: usize,
+    timeout: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (done, failed) = process_jobs(queue, store, cache_dir, kind, limit, timeout)?;
+    show_info_alfred(jobs_summary(queue, done, failed)?);
+    Ok(())
+}
+
+/// 为缺少图标的站点排队并下载；下载失败的站点按退避重试，放弃后不再自动排队
+fn handle_prefetch_favicons(
+    index: &BookmarkIndex,
+    queue: &JobQueue,
+    store: &LinkStore,
+    cache_dir: &Path,
+    limit: usize,
+    timeout: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let now = now_ms() / 1000;
+    for domain in FaviconCache::new(cache_dir).missing_domains(&bookmarks) {
+        queue
+            .enqueue_missing(JobKind::Favicon, &domain, now)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+
+    let (done, failed) = process_jobs(
+        queue,
+        store,
+        cache_dir,
+        Some(JobKind::Favicon),
+        limit,
+        timeout,
+    )?;
+    show_info_alfred(format!("已下载 {} 个站点图标，失败 {} 个", done, failed));
+    Ok(())
+}
+
+/// 在后台启动本工具的另一个子命令，不等待、不继承输出（Alfred 读取的是当前进程的 stdout）
+fn spawn_detached(args: &[&str]) {
+    if let Ok(program) = std::env::current_exe() {
+        let _ = process::Command::new(program)
+            .args(args)
+            .stdin(process::Stdio::null())
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .spawn();
+    }
+}
+
+struct ScanPlan {
+    interval_secs: u64,
+    period_secs: u64,
+    max_per_run: usize,
+    timeout: u64,
+}
+
+/// 定时失效链接扫描：周期内未检查过的链接进入队列，本次只处理按周期摊分的数量
+fn handle_scan_links(
+    index: &BookmarkIndex,
+    store: &LinkStore,
+    queue: &JobQueue,
+    cache_dir: &Path,
+    plan: ScanPlan,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let mut urls = checkable_urls(&bookmarks, store)?;
+    let last_checked = store
+        .last_checked()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    urls.sort_by_key(|url| last_checked.get(*url).copied().unwrap_or(0));
+
+    let now = now_ms() / 1000;
+    let cutoff = now.saturating_sub(plan.period_secs);
+    for url in urls
+        .iter()
+        .filter(|url| last_checked.get(**url).copied().unwrap_or(0) < cutoff)
+    {
+        queue
+            .enqueue(JobKind::LinkCheck, url, now)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+
+    let needed = scan_budget(urls.len(), plan.interval_secs, plan.period_secs);
+    let budget = needed.min(plan.max_per_run);
+    let (done, failed) = process_jobs(
+        queue,
+        store,
+        cache_dir,
+        Some(JobKind::LinkCheck),
+        budget,
+        plan.timeout,
+    )?;
+    let mut summary = jobs_summary(queue, done, failed)?;
+    if budget < needed {
+        summary.push_str(&format!(
+            " | 每次上限 {} 低于所需 {}，一个周期内检查不完",
+            plan.max_per_run, needed
+        ));
+    }
+    show_info_alfred(summary);
+    Ok(())
+}
+
+/// 正文、备注等长文本与书签标题用同样的附加分词；trigram 片段只对短字段有意义，长文本不附加
+fn text_tokenizers(config: &Config) -> Vec<crate::tokenize::Tokenizer> {
+    config
+        .tokenize
+        .name
+        .iter()
+        .copied()
+        .filter(|tokenizer| *tokenizer != crate::tokenize::Tokenizer::Trigram)
+        .collect()
+}
+
+/// 抓取从未抓取或已过期的书签网页并写入正文库；失败的网页同样等到过期后再重试
+fn handle_index_content(
+    index: &BookmarkIndex,
+    store: &ContentStore,
+    cache_dir: &Path,
+    config: &Config,
+    limit: usize,
+    timeout: u64,
+    max_age_days: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let stale_before = (now_ms() / 1000).saturating_sub(max_age_days.saturating_mul(86_400));
+    let pending = store
+        .pending(&bookmarks, stale_before)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let tokenizers = text_tokenizers(config);
+
+    let total = pending.len().min(limit);
+    let (mut fetched, mut failed) = (0usize, 0usize);
+    let mut progress = ProgressTracker::start(cache_dir, JOB_INDEX_CONTENT);
+    for bookmark in pending.iter().take(limit) {
+        // 新的 index-content 已接手时停止，已抓取的结果保留
+        if progress
+            .update("抓取网页", fetched + failed, total)
+            .is_err()
+        {
+            break;
+        }
+        let saved = match fetch_page(&bookmark.url, Duration::from_secs(timeout)) {
+            Ok(html) => {
+                fetched += 1;
+                store.save(
+                    &bookmark.url,
+                    &extract_page_text(&html),
+                    &tokenizers,
+                    now_ms() / 1000,
+                )
+            }
+            Err(error) => {
+                failed += 1;
+                store.save_failure(&bookmark.url, &error, now_ms() / 1000)
+            }
+        };
+        saved.map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+    progress.finish();
+
+    let indexed = store
+        .indexed_pages()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    show_info_alfred(format!(
+        "已抓取 {} 个网页，失败 {} 个；正文索引共 {} 个网页，待抓取 {} 个",
+        fetched,
+        failed,
+        indexed,
+        pending.len().saturating_sub(fetched + failed)
+    ));
+    Ok(())
+}
+
+fn handle_check_links(
+    index: &BookmarkIndex,
+    store: &LinkStore,
+    cache_dir: &Path,
+    limit: usize,
+    timeout: u64,
+    report_only: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let mut urls = checkable_urls(&bookmarks, store)?;
+    let seen: HashSet<&str> = urls.iter().copied().collect();
+
+    let mut checked = 0usize;
+    if !report_only {
+        let last_checked = store
+            .last_checked()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        urls.sort_by_key(|url| last_checked.get(*url).copied().unwrap_or(0));
+
+        let probe = CurlProbe::new(std::time::Duration::from_secs(timeout));
+        let total = urls.len().min(limit);
+        let mut progress = ProgressTracker::start(cache_dir, JOB_CHECK_LINKS);
+        for url in urls.iter().take(limit) {
+            // 新的 check-links 已接手时停止，已检查的结果保留
+            if progress.update("检查链接", checked, total).is_err() {
+                break;
+            }
+            check_link(&probe, store, url)?;
+            checked += 1;
+        }
+        progress.finish();
+    }
+
+    let results: Vec<LinkCheckResult> = store
+        .load_results()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .filter(|result| seen.contains(result.url.as_str()))
+        .collect();
+    let mut upgrades: Vec<(String, String)> = store
+        .https_upgrades()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .filter(|(url, _)| seen.contains(url.as_str()))
+        .collect();
+    upgrades.sort();
+
+    let dead: Vec<&LinkCheckResult> = results.iter().filter(|r| r.is_dead()).collect();
+    let moved = results.iter().filter(|r| r.is_permanent_move()).count();
+
+    let title = if report_only {
+        format!("已保存 {} 条检查结果", results.len())
+    } else {
+        format!("已检查 {} 个链接", checked)
+    };
+    let mut items = vec![alfred::ItemBuilder::new(title)
+        .subtitle(format!(
+            "失效 {} | 永久迁移 {}（运行 fix-redirects 查看） | 可升级 HTTPS {}",
+            dead.len(),
+            moved,
+            upgrades.len()
+        ))
+        .valid(false)
+        .icon_path(ICON_ACTION_STATS)
+        .into_item()];
+
+    for result in dead {
+        let reason = match &result.error {
+            Some(err) => err.clone(),
+            None => format!("HTTP {}", result.status),
+        };
+        items.push(
+            alfred::ItemBuilder::new(result.url.as_str())
+                .subtitle(format!("失效: {}", reason))
+                .arg(format!("open:{}", result.url))
+                .valid(true)
+                .icon_path(ICON_ERROR)
+                .into_item(),
+        );
+    }
+
+    for (url, https_url) in &upgrades {
+        items.push(
+            alfred::ItemBuilder::new(url.as_str())
+                .subtitle(format!("可升级 HTTPS: {}", https_url))
+                .arg(format!("open:{}", https_url))
+                .valid(true)
+                .icon_path(ICON_BOOKMARK)
+                .modifier(
+                    alfred::Modifier::Command,
+                    Some(format!("复制新URL: {}", https_url)),
+                    Some(format!("copy:{}", https_url)),
+                    true,
+                    Some(alfred::Icon::Path(Cow::Borrowed(ICON_ACTION_COPY))),
+                )
+                .into_item(),
+        );
+    }
+
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+fn handle_fix_redirects(
+    index: &BookmarkIndex,
+    store: &LinkStore,
+    export: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let redirects = store
+        .permanent_redirects()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let ignore = store
+        .ignore_list()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    let moved: Vec<(String, crate::bookmark::ChromeBookmark)> = bookmarks
+        .into_iter()
+        .filter(|bookmark| !ignore.matches(&bookmark.url))
+        .filter_map(|mut bookmark| {
+            let final_url = redirects.get(&bookmark.url)?.clone();
+            let original = std::mem::replace(&mut bookmark.url, final_url);
+            Some((original, bookmark))
+        })
+        .collect();
+
+    let mut items = Vec::new();
+
+    if let Some(path) = export {
+        let updated: Vec<crate::bookmark::ChromeBookmark> =
+            moved.iter().map(|(_, bookmark)| bookmark.clone()).collect();
+        let file = std::fs::File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        crate::export::write_netscape_html(&mut writer, &updated)?;
+        writer.flush()?;
+        items.push(
+            alfred::ItemBuilder::new(format!("已导出 {} 条更新后的书签", updated.len()))
+                .subtitle(format!("{} | 可在浏览器书签管理器中导入", path.display()))
+                .arg(format!("open:file://{}", path.display()))
+                .valid(true)
+                .icon_path(ICON_ACTION_COPY)
+                .into_item(),
+        );
+    }
+
+    for (original, bookmark) in &moved {
+        items.push(
+            alfred::ItemBuilder::new(bookmark.name.as_str())
+                .subtitle(format!("{} → {}", original, bookmark.url))
+                .arg(format!("open:{}", bookmark.url))
+                .valid(true)
+                .icon_path(ICON_BOOKMARK)
+                .modifier(
+                    alfred::Modifier::Command,
+                    Some(format!("复制新URL: {}", bookmark.url)),
+                    Some(format!("copy:{}", bookmark.url)),
+                    true,
+                    Some(alfred::Icon::Path(Cow::Borrowed(ICON_ACTION_COPY))),
+                )
+                .into_item(),
+        );
+    }
+
+    if items.is_empty() {
+        items.push(
+            alfred::ItemBuilder::new("没有需要更新的书签")
+                .subtitle("尚未发现永久重定向，可先运行 check-links")
+                .valid(false)
+                .into_item(),
+        );
+    }
+
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+fn handle_dedupe(
+    index: &BookmarkIndex,
+    store: &LinkStore,
+    similar: bool,
+    threshold: f64,
+    across_sources: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let ignore = store
+        .ignore_list()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let mut bookmarks: Vec<crate::bookmark::ChromeBookmark> = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    // 跨来源的重复在建索引时已被合并，落选版本保存在 variants 中
+    if across_sources {
+        bookmarks.extend(
+            index
+                .load_all_variants()
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?,
+        );
+    }
+    bookmarks.retain(|bookmark| !ignore.matches(&bookmark.url));
+
+    let groups = if across_sources {
+        crate::dedupe::group_across_sources(&bookmarks)
+    } else if similar {
+        crate::dedupe::cluster_similar_titles(&bookmarks, threshold.clamp(0.0, 1.0))
+    } else {
+        crate::dedupe::group_by_normalized_url(&bookmarks)
+    };
+
+    let mut items = Vec::new();
+    for (group_no, group) in groups.iter().enumerate() {
+        for &idx in group {
+            let bookmark = &bookmarks[idx];
+            let domain = extract_domain(&bookmark.url);
+            let mut subtitle = format!(
+                "[组 {} · {} 条] {}",
+                group_no + 1,
+                group.len(),
+                build_subtitle(&bookmark.folder_path, &domain)
+            );
+            if across_sources {
+                subtitle.push_str("  ");
+                subtitle.push_str(&source_badge(bookmark));
+            }
+            items.push(
+                alfred::ItemBuilder::new(bookmark.name.as_str())
+                    .subtitle(subtitle)
+                    .arg(format!("open:{}", bookmark.url))
+                    .valid(true)
+                    .icon_path(ICON_BOOKMARK)
+                    .modifier(
+                        alfred::Modifier::Command,
+                        Some(format!("复制URL: {}", bookmark.url)),
+                        Some(format!("copy:{}", bookmark.url)),
+                        true,
+                        Some(alfred::Icon::Path(Cow::Borrowed(ICON_ACTION_COPY))),
+                    )
+                    .into_item(),
+            );
+        }
+    }
+
+    if items.is_empty() {
+        items.push(
+            alfred::ItemBuilder::new("未发现重复书签")
+                .subtitle(if across_sources {
+                    "没有同时出现在多个浏览器 / profile 中的书签"
+                } else if similar {
+                    "可尝试降低 --threshold"
+                } else {
+                    "可尝试 --similar 查找标题近似的书签"
+                })
+                .valid(false)
+                .into_item(),
+        );
+    }
+
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+fn handle_ignore(store: &LinkStore, cmd: IgnoreCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let parse = |pattern: &str| {
+        IgnoreRule::parse(pattern).ok_or_else(|| AppError::Other("忽略规则不能为空".to_string()))
+    };
+
+    match cmd {
+        IgnoreCommand::Add { pattern } => {
+            let rule = parse(&pattern)?;
+            let added = store
+                .add_ignore(&rule)
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            show_info_alfred(if added {
+                format!("已忽略: {}", rule.pattern())
+            } else {
+                format!("已在忽略列表中: {}", rule.pattern())
+            });
+        }
+        IgnoreCommand::Remove { pattern } => {
+            let rule = parse(&pattern)?;
+            let removed = store
+                .remove_ignore(&rule)
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            show_info_alfred(if removed {
+                format!("已移除忽略: {}", rule.pattern())
+            } else {
+                format!("忽略列表中没有: {}", rule.pattern())
+            });
+        }
+        IgnoreCommand::List => {
+            let rules = store
+                .ignore_rules()
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            let mut items: Vec<alfred::Item> = rules
+                .iter()
+                .map(|rule| {
+                    let kind = match rule {
+                        IgnoreRule::Url(_) => "URL",
+                        IgnoreRule::Domain(_) => "域名（含子域名）",
+                    };
+                    alfred::ItemBuilder::new(rule.pattern())
+                        .subtitle(format!("忽略{}：不参与链接检查与查重", kind))
+                        .valid(false)
+                        .into_item()
+                })
+                .collect();
+            if items.is_empty() {
+                items.push(
+                    alfred::ItemBuilder::new("忽略列表为空")
+                        .subtitle("使用 ignore add <url|域名> 添加")
+                        .valid(false)
+                        .into_item(),
+                );
+            }
+            alfred::json::write_items(io::stdout(), &items)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `tags sync-chrome`：按本地标签重写 Chrome 书签标题的 ` #tag` 后缀，每个书签文件只写回一次
+fn handle_tags_sync_chrome(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    config: &Config,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // 未开启时标题后缀不会被解析，写回后会原样出现在书签名中
+    if !config.title_tags {
+        return Err(AppError::Other(
+            "请先设置 ALFRED_CHROME_BOOKMARKS_TITLE_TAGS=on 再同步标签到 Chrome".to_string(),
+        )
+        .into());
+    }
+
+    let mut paths: HashMap<String, PathBuf> = HashMap::new();
+    let mut files: BTreeMap<PathBuf, HashMap<String, String>> = BTreeMap::new();
+    let mut preview = Vec::new();
+    for bookmark in index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .filter(|bookmark| bookmark.browser == "chrome")
+    {
+        let names = tags
+            .tags_for(&tag_key(&bookmark))
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let title = crate::tags::title_with_tags(&bookmark.name, &names);
+        if !names.is_empty() {
+            preview.push(
+                alfred::ItemBuilder::new(title.clone())
+                    .subtitle(bookmark.url.clone())
+                    .valid(false)
+                    .into_item(),
+            );
+        }
+        let path = match paths.get(&bookmark.profile) {
+            Some(path) => path.clone(),
+            None => {
+                let path = chrome_source_path(index, &bookmark)?;
+                paths.insert(bookmark.profile.clone(), path.clone());
+                path
+            }
+        };
+        // 没有标签的书签也按原标题写回，没有变化的书签不会改动文件
+        files
+            .entry(path)
+            .or_default()
+            .insert(native_bookmark_id(&bookmark.id).to_string(), title);
+    }
+
+    if dry_run {
+        let mut items = vec![alfred::ItemBuilder::new(format!(
+            "将把标签写入 {} 个 Chrome 书签标题（dry-run）",
+            preview.len()
+        ))
+        .valid(false)
+        .icon_path(ICON_ACTION_STATS)
+        .into_item()];
+        items.extend(preview);
+        alfred::json::write_items(io::stdout(), &items)?;
+        return Ok(());
+    }
+    if files.is_empty() {
+        show_info_alfred("索引中没有 Chrome 书签");
+        return Ok(());
+    }
+
+    ensure_chrome_not_running()?;
+    let mut changed = 0;
+    for (path, titles) in &files {
+        changed += chrome_write::retitle_url_bookmarks(path, titles)
+            .map_err(AppError::BookmarksReadError)?;
+    }
+    show_info_alfred(format!("已更新 {} 个 Chrome 书签标题", changed));
+    Ok(())
+}
+
+fn handle_tags_add(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    id: &str,
+    raw: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let names = normalize_csv_terms(raw.split(','));
+    if names.is_empty() {
+        return Err(AppError::Other("没有要添加的标签".to_string()).into());
+    }
+    tags.add_tags(&tag_key(&bookmark), &bookmark.url, &names)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    show_info_alfred(format!(
+        "已添加标签 {}: {}",
+        names.join(", "),
+        bookmark.name
+    ));
+    Ok(())
+}
+
+fn handle_tags_remove(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    id: &str,
+    raw: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let names = normalize_csv_terms(raw.split(','));
+    let removed = tags
+        .remove_tags(&tag_key(&bookmark), &names)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    show_info_alfred(if removed == 0 {
+        format!("{} 没有这些标签", bookmark.name)
+    } else {
+        format!("已移除 {} 个标签: {}", removed, bookmark.name)
+    });
+    Ok(())
+}
+
+/// `tags list`：每个标签一项，补全为 `tag:<标签>` 即可按标签搜索
+fn handle_tags_list(tags: &TagManager) -> Result<(), Box<dyn std::error::Error>> {
+    let counts = tags
+        .tag_counts()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let mut items: Vec<alfred::Item> = counts
+        .iter()
+        .map(|(name, count)| {
+            alfred::ItemBuilder::new(name.as_str())
+                .subtitle(format!("{} 个书签 · ⇥ 按此标签搜索", count))
+                .autocomplete(format!("tag:{} ", name))
+                .valid(false)
+                .into_item()
+        })
+        .collect();
+    if items.is_empty() {
+        items.push(
+            alfred::ItemBuilder::new("还没有标签")
+                .subtitle("使用 tags add <id> <tag1,tag2> 添加")
+                .valid(false)
+                .into_item(),
+        );
+    }
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+fn handle_tags_show(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let names = tags
+        .tags_for(&tag_key(&bookmark))
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let mut items: Vec<alfred::Item> = names
+        .iter()
+        .map(|name| {
+            alfred::ItemBuilder::new(name.as_str())
+                .subtitle(format!("{} · ⇥ 按此标签搜索", bookmark.name))
+                .autocomplete(format!("tag:{} ", name))
+                .valid(false)
+                .into_item()
+        })
+        .collect();
+    if items.is_empty() {
+        items.push(
+            alfred::ItemBuilder::new(format!("{} 没有标签", bookmark.name))
+                .subtitle(format!("使用 tags add {} <tag1,tag2> 添加", bookmark.id))
+                .valid(false)
+                .into_item(),
+        );
+    }
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+/// `note`：有内容时替换书签备注，`--clear` 时删除，否则显示当前备注
+fn handle_note(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    config: &Config,
+    id: &str,
+    text: &str,
+    clear: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let key = tag_key(&bookmark);
+    if clear {
+        let removed = tags
+            .remove_note(&key)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        show_info_alfred(if removed {
+            format!("已删除备注: {}", bookmark.name)
+        } else {
+            format!("{} 没有备注", bookmark.name)
+        });
+        return Ok(());
+    }
+
+    let text = text.trim();
+    if text.is_empty() {
+        let note = tags
+            .note_for(&key)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let item = match note.as_deref() {
+            Some(note) => alfred::ItemBuilder::new(note)
+                .subtitle(format!("{} · ⌘L 查看全文", bookmark.name))
+                .text_large_type(note)
+                .text_copy(note),
+            None => alfred::ItemBuilder::new(format!("{} 没有备注", bookmark.name))
+                .subtitle(format!("使用 note {} <内容> 添加", bookmark.id)),
+        };
+        alfred::json::write_items(io::stdout(), &[item.valid(false).into_item()])?;
+        return Ok(());
+    }
+
+    tags.set_note(&key, &bookmark.url, text, &text_tokenizers(config))
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    show_info_alfred(format!("已保存备注: {}", bookmark.name));
+    Ok(())
+}
+
+/// `pin` / `unpin`：置顶或取消置顶书签
+fn handle_pin(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    id: &str,
+    pin: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let key = tag_key(&bookmark);
+    let message = if pin {
+        let added = tags
+            .pin(&key, &bookmark.url)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if added {
+            format!("已置顶: {}", bookmark.name)
+        } else {
+            format!("{} 已经置顶", bookmark.name)
+        }
+    } else {
+        let removed = tags
+            .unpin(&key)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if removed {
+            format!("已取消置顶: {}", bookmark.name)
+        } else {
+            format!("{} 没有置顶", bookmark.name)
+        }
+    };
+    show_info_alfred(message);
+    Ok(())
+}
+
+fn handle_tags_rename(
+    tags: &TagManager,
+    from: &str,
+    to: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let to = to.trim();
+    if to.is_empty() {
+        return Err(AppError::Other("新标签名不能为空".to_string()).into());
+    }
+    let renamed = tags
+        .rename_tag(from, to)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("没有标签: {}", from.trim())))?;
+    show_info_alfred(format!(
+        "已把标签 {} 改为 {}（{} 个书签）",
+        from.trim(),
+        to,
+        renamed
+    ));
+    Ok(())
+}
+
+/// 共享目录可能在网络卷上，标签编辑与刷新后都在后台同步
+fn spawn_shared_tags_sync(config: &Config) {
+    if config.shared_tags.is_some() {
+        spawn_detached(&["tags", "sync"]);
+    }
+}
+
+fn handle_tags_sync(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = config
+        .shared_tags
+        .as_ref()
+        .ok_or_else(|| AppError::Other("未设置 ALFRED_CHROME_BOOKMARKS_SHARED_TAGS".to_string()))?;
+    let member = crate::shared_tags::member_name(tags, config.member.as_deref())
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let report = crate::shared_tags::SharedTags::new(dir, &member).sync(tags, &bookmarks)?;
+    show_info_alfred(format!(
+        "共享标签已同步（{} 位成员）：发布 {} 条编辑，新增 {} 个、移除 {} 个标签关联",
+        report.members, report.published, report.added, report.removed
+    ));
+    Ok(())
+}
+
+fn handle_tags_prune(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    // 索引为空时所有标签都会被判定为孤立，直接拒绝以免误删
+    if bookmarks.is_empty() {
+        return Err(AppError::Other("书签索引为空，已跳过标签清理".to_string()).into());
+    }
+
+    let report = tags
+        .prune(&bookmarks, dry_run)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    if report.is_empty() {
+        show_info_alfred("标签数据无需清理");
+        return Ok(());
+    }
+
+    let verb = if dry_run { "将" } else { "已" };
+    let mut items = vec![alfred::ItemBuilder::new(if dry_run {
+        "标签清理预览（dry-run）"
+    } else {
+        "标签清理完成"
+    })
+    .subtitle(format!(
+        "{}迁移 {} | {}移除孤立关联 {} | {}删除未使用标签 {}",
+        verb,
+        report.remapped.len(),
+        verb,
+        report.orphaned.len(),
+        verb,
+        report.unused_tags.len()
+    ))
+    .valid(false)
+    .icon_path(ICON_ACTION_STATS)
+    .into_item()];
+
+    for (old_id, new_id, tag) in &report.remapped {
+        items.push(
+            alfred::ItemBuilder::new(format!("#{}", tag))
+                .subtitle(format!("书签 {} → {}（URL 未变）", old_id, new_id))
+                .valid(false)
+                .into_item(),
+        );
+    }
+    for (bookmark_key, tag) in &report.orphaned {
+        items.push(
+            alfred::ItemBuilder::new(format!("#{}", tag))
+                .subtitle(format!("孤立：书签 {} 已不存在", bookmark_key))
+                .valid(false)
+                .icon_path(ICON_ERROR)
+                .into_item(),
+        );
+    }
+    for tag in &report.unused_tags {
+        items.push(
+            alfred::ItemBuilder::new(format!("#{}", tag))
+                .subtitle("未被任何书签使用")
+                .valid(false)
+                .into_item(),
+        );
+    }
+
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+/// `report growth`：按月 / 年统计新增书签
+fn handle_report_growth(
+    index: &BookmarkIndex,
+    by: crate::report::GrowthPeriod,
+    output: &ReportOutput,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let report = crate::report::GrowthReport::build(&bookmarks, by);
+    if export_report(&report, output)? {
+        return Ok(());
+    }
+
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout.lock());
+    writer.write_all(report.render_text().as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn handle_report_folders(
+    index: &BookmarkIndex,
+    depth: usize,
+    output: &ReportOutput,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let report = crate::report::FolderReport::build(&bookmarks, depth);
+    if export_report(&report, output)? {
+        return Ok(());
+    }
+
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout.lock());
+    writer.write_all(report.render_text().as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// `report tags`：标签用量或标签共现，默认输出文本表格
+fn handle_report_tags(
+    tags: &TagManager,
+    cooccurrence: bool,
+    limit: usize,
+    output: &ReportOutput,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if cooccurrence {
+        let pairs = tags
+            .tag_pairs(limit)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if export_report(&pairs, output)? {
+            return Ok(());
+        }
+        let stdout = io::stdout();
+        let mut writer = BufWriter::new(stdout.lock());
+        // 数字列在前，标签名长短不一也能对齐
+        writeln!(writer, "标签共现（前 {} 对）", pairs.len())?;
+        writeln!(writer, " 共同  重合度  标签对")?;
+        for pair in &pairs {
+            writeln!(
+                writer,
+                "{:>5}  {:>5.1}%  {} + {}",
+                pair.count, pair.overlap, pair.tags.0, pair.tags.1
+            )?;
+        }
+        writer.flush()?;
+    } else {
+        let counts: Vec<crate::tags::TagCount> = tags
+            .tag_counts()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .take(limit)
+            .map(|(tag, count)| crate::tags::TagCount { tag, count })
+            .collect();
+        if export_report(&counts, output)? {
+            return Ok(());
+        }
+        let stdout = io::stdout();
+        let mut writer = BufWriter::new(stdout.lock());
+        writeln!(writer, "标签用量（前 {} 个）", counts.len())?;
+        for row in &counts {
+            writeln!(writer, "{:>5}  {}", row.count, row.tag)?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// `report domains`：书签最多的站点，回车以 cb 搜索该站点
+fn handle_report_domains(
+    index: &BookmarkIndex,
+    limit: usize,
+    output: &ReportOutput,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let report = crate::report::DomainReport::build(&bookmarks, limit);
+    if export_report(&report, output)? {
+        return Ok(());
+    }
+
+    let mut items =
+        vec![
+            alfred::ItemBuilder::new(format!("书签最多的 {} 个站点", report.domains.len()))
+                .subtitle(format!(
+                    "共 {} 个书签，分布在 {} 个站点",
+                    report.total, report.domain_total
+                ))
+                .valid(false)
+                .icon_path(ICON_ACTION_STATS)
+                .into_item(),
+        ];
+    items.extend(report.domains.iter().map(|row| {
+        alfred::ItemBuilder::new(row.domain.clone())
+            .subtitle(format!(
+                "{} 个书签 · {:.1}% · ↩ 搜索",
+                row.count, row.percent
+            ))
+            .arg(format!("search:{} ", row.domain))
+            .valid(true)
+            .icon_path(ICON_BOOKMARK)
+            .into_item()
+    }));
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+/// `report queries` 的导出内容
+#[derive(Debug, Serialize)]
+struct QueriesReport {
+    top: Vec<crate::history::HistoryEntry>,
+    zero_results: Vec<crate::history::HistoryEntry>,
+}
+
+impl Exportable for QueriesReport {
+    fn summary(&self) -> String {
+        format!(
+            "搜索报告（常用 {} / 无结果 {}）",
+            self.top.len(),
+            self.zero_results.len()
+        )
+    }
+
+    /// 两个列表合成一张表，`kind` 区分 top / zero_results
+    fn table(&self) -> crate::report_render::ReportTable {
+        let rows = self
+            .top
+            .iter()
+            .map(|entry| ("top", entry))
+            .chain(
+                self.zero_results
+                    .iter()
+                    .map(|entry| ("zero_results", entry)),
+            )
+            .map(|(kind, entry)| {
+                vec![
+                    kind.to_string(),
+                    entry.query.clone(),
+                    entry.uses.to_string(),
+                    crate::report::format_datetime(entry.last_used as i64),
+                    entry.results.map(|n| n.to_string()).unwrap_or_default(),
+                ]
+            })
+            .collect();
+        crate::report_render::ReportTable {
+            title: "搜索记录".to_string(),
+            notes: vec![format!(
+                "最常用的 {} 个搜索，{} 个搜索没有结果",
+                self.top.len(),
+                self.zero_results.len()
+            )],
+            columns: vec!["kind", "query", "uses", "last_used", "results"],
+            rows,
+        }
+    }
+}
+
+/// 最常用的搜索与没有结果的搜索；后者提示该收藏或打标签的内容，回车都以 cb 重新搜索
+fn handle_report_queries(
+    history: &QueryHistory,
+    enabled: bool,
+    limit: usize,
+    output: &ReportOutput,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let report = QueriesReport {
+        top: history
+            .most_used(limit)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?,
+        zero_results: history
+            .zero_results(limit)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?,
+    };
+    if export_report(&report, output)? {
+        return Ok(());
+    }
+
+    let header = if report.top.is_empty() {
+        alfred::ItemBuilder::new("还没有搜索记录").subtitle(if enabled {
+            "搜索几次后再来查看"
+        } else {
+            "设置 ALFRED_CHROME_BOOKMARKS_HISTORY=on 后开始记录（仅保存在本地）"
+        })
+    } else {
+        alfred::ItemBuilder::new(format!(
+            "最常用的 {} 个搜索 · {} 个搜索没有结果",
+            report.top.len(),
+            report.zero_results.len()
+        ))
+        .subtitle("没有结果的搜索提示下一步该收藏或打标签的内容")
+    };
+    let mut items = vec![header.valid(false).icon_path(ICON_ACTION_STATS).into_item()];
+    items.extend(report.zero_results.iter().map(|entry| {
+        alfred::ItemBuilder::new(format!("无结果 · {}", entry.query))
+            .subtitle(format!(
+                "搜索过 {} 次 · 上次 {} UTC · ↩ 重新搜索",
+                entry.uses,
+                crate::report::format_datetime(entry.last_used as i64)
+            ))
+            .arg(format!("search:{}", entry.query))
+            .valid(true)
+            .icon_path(ICON_ERROR)
+            .into_item()
+    }));
+    items.extend(report.top.iter().map(|entry| {
+        let results = match entry.results {
+            Some(count) => format!("上次 {} 个结果", count),
+            None => "结果数未记录".to_string(),
+        };
+        alfred::ItemBuilder::new(entry.query.clone())
+            .subtitle(format!(
+                "搜索过 {} 次 · {} · ↩ 重新搜索",
+                entry.uses, results
+            ))
+            .arg(format!("search:{}", entry.query))
+            .valid(true)
+            .icon_path(ICON_ACTION_GUIDE)
+            .into_item()
+    }));
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+fn handle_report_untagged(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    limit: usize,
+    output: &ReportOutput,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmarks = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let tagged_keys = tags
+        .tagged_keys()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let report = crate::report::UntaggedReport::build(&bookmarks, &tagged_keys, limit);
+    if export_report(&report, output)? {
+        return Ok(());
+    }
+
+    let mut items = vec![alfred::ItemBuilder::new(format!(
+        "{} 个书签没有标签 · {} 个直接放在根目录",
+        report.untagged, report.at_root
+    ))
+    .subtitle(format!("共 {} 个书签 · ⌘↩ 直接添加标签", report.total))
+    .valid(false)
+    .icon_path(ICON_ACTION_STATS)
+    .into_item()];
+    items.extend(report.entries.iter().map(|entry| {
+        let bookmark = entry.bookmark;
+        let mut problems = Vec::new();
+        if entry.untagged {
+            problems.push("无标签".to_string());
+        }
+        if entry.at_root {
+            problems.push(format!(
+                "根目录 {}",
+                bookmark.folder_path.as_deref().unwrap_or("未分类")
+            ));
+        }
+        alfred::ItemBuilder::new(bookmark.name.clone())
+            .subtitle(format!("{} · {}", problems.join(" · "), bookmark.url))
+            .arg(format!("open:{}", bookmark.url))
+            .uid(bookmark.id.clone())
+            .valid(true)
+            .icon_path(ICON_BOOKMARK)
+            .modifier(
+                alfred::Modifier::Command,
+                Some(format!("添加标签: {}", bookmark.name)),
+                Some(format!("search:>{} +", bookmark.id)),
+                true,
+                Some(alfred::Icon::Path(Cow::Borrowed(ICON_ACTION_FOLDERS))),
+            )
+            .autocomplete(format!(">{}", bookmark.id))
+            .into_item()
+    }));
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+fn handle_report_cleanup(
+    index: &BookmarkIndex,
+    links: &LinkStore,
+    tags: &TagManager,
+    stale_days: u64,
+    output: &ReportOutput,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let ignore = links
+        .ignore_list()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let bookmarks: Vec<crate::bookmark::ChromeBookmark> = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .filter(|bookmark| !ignore.matches(&bookmark.url))
+        .collect();
+    let link_results = links
+        .load_results()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let tagged_keys = tags
+        .tagged_keys()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let duplicate_groups = crate::dedupe::group_by_normalized_url(&bookmarks);
+
+    let report = crate::report::CleanupReport::build(
+        &bookmarks,
+        &duplicate_groups,
+        &link_results,
+        &tagged_keys,
+        stale_days,
+        (now_ms() / 1000) as i64,
+    );
+    if export_report(&report, output)? {
+        return Ok(());
+    }
+
+    let stdout = io::stdout();
+    let mut writer = stdout.lock();
+    writer.write_all(report.render_markdown().as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// 按 `--out` / `--format` 导出报告；没有指定导出时返回 false，由调用方输出默认视图
+fn export_report<R: Exportable + ?Sized>(
+    report: &R,
+    output: &ReportOutput,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let Some(format) = output.format() else {
+        return Ok(false);
+    };
+    let rendered = crate::report_render::render(report, format)?;
+
+    match &output.out {
+        Some(path) => {
+            std::fs::write(path, rendered)?;
+            show_info_alfred(format!("已导出{}: {}", report.summary(), path.display()));
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut writer = stdout.lock();
+            writer.write_all(rendered.as_bytes())?;
+            writer.flush()?;
+        }
+    }
+    Ok(true)
+}
+
+/// 打开链接所用的应用
+enum OpenTarget {
+    /// 系统默认浏览器
+    Default,
+    /// 书签所属浏览器的指定 profile
+    Profile(ProfileLaunch),
+    /// 最前面的受支持浏览器（bundle id）
+    Frontmost(String),
+}
+
+fn open_target_for(data_dir: &Path, url: &str, config: &Config) -> OpenTarget {
+    if config.open_in_frontmost {
+        if let Some(bundle_id) = frontmost_browser_bundle_id() {
+            return OpenTarget::Frontmost(bundle_id);
+        }
+    }
+    if config.open_in_profile {
+        if let Some(launch) = profile_launch_for_url(data_dir, url) {
+            return OpenTarget::Profile(launch);
+        }
+    }
+    OpenTarget::Default
+}
+
+/// 通过 NSWorkspace 读取最前面的应用；不是受支持的浏览器时返回 None
+fn frontmost_browser_bundle_id() -> Option<String> {
+    let output = process::Command::new("osascript")
+        .arg("-l")
+        .arg("JavaScript")
+        .arg("-e")
+        .arg("ObjC.import('AppKit'); $.NSWorkspace.sharedWorkspace.frontmostApplication.bundleIdentifier.js")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let bundle_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    browser_key_for_bundle_id(&bundle_id).map(|_| bundle_id)
+}
+
+/// 查找 URL 对应书签的来源 profile；索引不可用或浏览器不支持时返回 None，退回默认打开方式
+fn profile_launch_for_url(data_dir: &Path, url: &str) -> Option<ProfileLaunch> {
+    let index = BookmarkIndex::new(data_dir.join("bookmarks.db")).ok()?;
+    let path = index.source_path_for_url(url).ok()??;
+    profile_launch_for(Path::new(&path))
+}
+
+fn handle_open(
+    url: &str,
+    background: bool,
+    open_target: &OpenTarget,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let target = config.tracking.clean_for_open(url);
+    let mut command = process::Command::new("open");
+    if background {
+        command.arg("-g");
+    }
+    match open_target {
+        // -n 让参数在浏览器已运行时也生效，Chromium 会把请求转交给现有进程
+        OpenTarget::Profile(launch) => {
+            command
+                .arg("-na")
+                .arg(launch.app)
+                .arg("--args")
+                .arg(format!("--profile-directory={}", launch.profile_dir))
+                .arg(&target);
+        }
+        OpenTarget::Frontmost(bundle_id) => {
+            command.arg("-b").arg(bundle_id).arg(&target);
+        }
+        OpenTarget::Default => {
+            command.arg(&target);
+        }
+    }
+    let status = command.status()?;
+    if !status.success() {
+        return Err(AppError::Other(format!("无法打开: {}", target)).into());
+    }
+    Ok(())
+}
+
+fn handle_add(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    url: &str,
+    title: &str,
+    folder: Option<&str>,
+    tags: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (url, title, folder) = normalize_add_input(url, title, folder)?;
+    let (bookmark, added) = index
+        .add_local_bookmark(
+            url,
+            title,
+            folder,
+            &unix_secs_to_date_added((now_ms() / 1000) as i64),
+        )
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    if !tags.is_empty() {
+        TagManager::new(data_dir.join("tags.db"))
+            .and_then(|store| store.add_tags(&tag_key(&bookmark), &bookmark.url, tags))
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+    show_info_alfred(if added {
+        format!("已保存到本地书签: {}", bookmark.name)
+    } else {
+        format!("已更新本地书签: {}", bookmark.name)
+    });
+    Ok(())
+}
+
+/// `import --urls`：逐行存入本地书签，已存在的 URL 更新标题与目录，标签统一追加到本批链接
+fn handle_import_urls(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    path: &Path,
+    folder: Option<&str>,
+    tags: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)?;
+    let (urls, invalid) = crate::import::parse_url_list(&content);
+    if urls.is_empty() {
+        return Err(AppError::Other(format!("{} 中没有可导入的链接", path.display())).into());
+    }
+
+    let store = if tags.is_empty() {
+        None
+    } else {
+        Some(
+            TagManager::new(data_dir.join("tags.db"))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?,
+        )
+    };
+    let date_added = unix_secs_to_date_added((now_ms() / 1000) as i64);
+    let (mut added, mut updated) = (0, 0);
+    for listed in &urls {
+        let (url, title, folder) = normalize_add_input(&listed.url, &listed.title, folder)?;
+        let (bookmark, is_new) = index
+            .add_local_bookmark(url, title, folder, &date_added)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if is_new {
+            added += 1;
+        } else {
+            updated += 1;
+        }
+        if let Some(store) = &store {
+            store
+                .add_tags(&tag_key(&bookmark), &bookmark.url, tags)
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+    }
+
+    let mut message = format!(
+        "已导入 {} 个链接（新增 {}，更新 {}）",
+        urls.len(),
+        added,
+        updated
+    );
+    if !invalid.is_empty() {
+        let lines: Vec<String> = invalid.iter().map(ToString::to_string).collect();
+        message.push_str(&format!("，跳过无效的第 {} 行", lines.join("、")));
+    }
+    show_info_alfred(message);
+    Ok(())
+}
+
+/// 校验 `add` 的输入：URL 需带协议；标题缺省时用 URL；目录去掉首尾 `/`
+fn normalize_add_input<'a>(
+    url: &'a str,
+    title: &'a str,
+    folder: Option<&'a str>,
+) -> Result<(&'a str, &'a str, Option<&'a str>), AppError> {
+    let url = url.trim();
+    if !url.contains("://") {
+        return Err(AppError::Other(format!("不是有效的 URL: {}", url)));
+    }
+    let title = match title.trim() {
+        "" => url,
+        title => title,
+    };
+    let folder = folder
+        .map(|folder| folder.trim().trim_matches('/'))
+        .filter(|folder| !folder.is_empty());
+    Ok((url, title, folder))
+}
+
+/// 直接写入 Chrome 的 Bookmarks 文件；Chrome 运行时会在退出前用内存中的书签覆盖文件，因此只在其退出后写入
+fn handle_add_to_chrome(
+    cache_dir: &Path,
+    data_dir: &Path,
+    url: &str,
+    title: &str,
+    folder: Option<&str>,
+    profile: Option<&str>,
+    tags: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (url, title, folder) = normalize_add_input(url, title, folder)?;
+    let source = discover_bookmark_sources_cached(cache_dir, &["chrome".to_string()])
+        .into_iter()
+        .filter(|source| source.browser == "chrome")
+        .find(|source| profile.is_none_or(|name| source.profile.eq_ignore_ascii_case(name)))
+        .ok_or_else(|| match profile {
+            Some(name) => AppError::Other(format!("未找到 Chrome profile: {}", name)),
+            None => AppError::BookmarksNotFound,
+        })?;
+    ensure_chrome_not_running()?;
+
+    chrome_write::add_url_bookmark(
+        &source.path,
+        url,
+        title,
+        folder,
+        &unix_secs_to_date_added((now_ms() / 1000) as i64),
+    )
+    .map_err(AppError::BookmarksReadError)?;
+    if !tags.is_empty() {
+        TagManager::new(data_dir.join("tags.db"))
+            .and_then(|store| store.add_tags(&bookmark_key(url), url, tags))
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+    show_info_alfred(format!(
+        "已写入 Chrome 书签（{}）: {}",
+        source.profile, title
+    ));
+    Ok(())
+}
+
+/// 任一 bundle id 对应的应用在运行即返回 true；无法确认时（osascript 失败）也按运行处理
+fn is_browser_running(bundle_ids: &[&str]) -> bool {
+    let output = process::Command::new("osascript")
+        .arg("-l")
+        .arg("JavaScript")
+        .arg("-e")
+        .arg("ObjC.import('AppKit'); function run(argv) { return argv.some(function (id) { return $.NSRunningApplication.runningApplicationsWithBundleIdentifier(id).count > 0; }); }")
+        .args(bundle_ids)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).trim() != "false"
+        }
+        _ => true,
+    }
+}
+
+fn handle_actions_for(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    id: &str,
+    data_dir: &Path,
+    cache_dir: &Path,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let tag_names = tags
+        .tags_for(&tag_key(&bookmark))
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let pinned = tags
+        .is_pinned(&tag_key(&bookmark))
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let revealable = reveal_target(index, &bookmark)?.is_some();
+    // 打开菜单前所在的搜索，“同目录 / 同站点 / 标签”跳转后可以返回
+    let origin = LastResults::new(cache_dir).load_query().unwrap_or_default();
+    let session = SessionStore::new(cache_dir).load(now_ms() / 1000);
+    let navigate = |query: &str, context: String| {
+        let state = SessionState::navigate(session.as_ref(), &origin, query, &context);
+        SessionState::search_arg(query, Some(&state).filter(|state| !state.back.is_empty()))
+    };
+    let plugins = crate::plugins::load_plugins(&crate::plugins::plugin_dir(
+        data_dir,
+        config.plugins_dir.as_deref(),
+    ));
+    let items = bookmark_action_items(
+        &bookmark, &tag_names, pinned, revealable, &plugins, config, &navigate,
+    );
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+/// 执行插件动作；脚本输出的第一行作为通知内容
+fn handle_plugin(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    data_dir: &Path,
+    config: &Config,
+    name: &str,
+    id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let plugin = crate::plugins::load_plugins(&crate::plugins::plugin_dir(
+        data_dir,
+        config.plugins_dir.as_deref(),
+    ))
+    .into_iter()
+    .find(|plugin| plugin.name == name)
+    .ok_or_else(|| AppError::Other(format!("未找到插件: {}", name)))?;
+    let tag_names = tags
+        .tags_for(&tag_key(&bookmark))
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let message = plugin.run(&bookmark, &tag_names).map_err(AppError::Other)?;
+    show_info_alfred(if message.is_empty() {
+        format!("{}: {}", plugin.title, bookmark.name)
+    } else {
+        message
+    });
+    Ok(())
+}
+
+/// `>ID +标签` 的输入状态：尚未输入时提示，输入后回车经 run.sh 执行 `tags add`
+fn handle_tag_input(
+    index: &BookmarkIndex,
+    tags: &TagManager,
+    id: &str,
+    input: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let names = normalize_csv_terms(input.split(','));
+    let item = if names.is_empty() {
+        let existing = tags
+            .tags_for(&tag_key(&bookmark))
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        alfred::ItemBuilder::new(format!("为「{}」添加标签", bookmark.name))
+            .subtitle(if existing.is_empty() {
+                "输入标签，逗号分隔".to_string()
+            } else {
+                format!("输入标签，逗号分隔 · 已有: {}", existing.join(", "))
+            })
+            .valid(false)
+    } else {
+        alfred::ItemBuilder::new(format!("添加标签: {}", names.join(", ")))
+            .subtitle(format!("↩ 保存到 {}", bookmark.name))
+            .arg(format!("tag:{}\t{}", bookmark.id, names.join(",")))
+            .valid(true)
+    };
+    alfred::json::write_items(
+        io::stdout(),
+        &[item.icon_path(ICON_ACTION_FOLDERS).into_item()],
+    )?;
+    Ok(())
+}
+
+/// 二级菜单的条目；`search:` 动作由 run.sh 交给 Alfred 以 `cb <查询>` 重新搜索，
+/// `navigate` 为跳转类动作生成带会话状态的 arg
+fn bookmark_action_items(
+    bookmark: &crate::bookmark::ChromeBookmark,
+    tags: &[String],
+    pinned: bool,
+    revealable: bool,
+    plugins: &[crate::plugins::Plugin],
+    config: &Config,
+    navigate: &dyn Fn(&str, String) -> String,
+) -> Vec<alfred::Item<'static>> {
+    let copy_url = config.tracking.clean_for_copy(&bookmark.url);
+    let title = bookmark.name.replace(['\t', '\n', '\r'], " ");
+    let mut actions: Vec<(String, String, String, &'static str)> = vec![
+        (
+            "打开".to_string(),
+            bookmark.url.clone(),
+            format!("open:{}", bookmark.url),
+            ICON_BOOKMARK,
+        ),
+        (
+            "显示详情".to_string(),
+            "以大字显示完整 URL、目录、标签与时间".to_string(),
+            format!("details:{}", bookmark.id),
+            ICON_ACTION_STATS,
+        ),
+        (
+            "后台打开".to_string(),
+            "不切换到浏览器".to_string(),
+            format!("open-bg:{}", bookmark.url),
+            ICON_BOOKMARK,
+        ),
+        (
+            "复制 URL".to_string(),
+            copy_url.clone(),
+            format!("copy:{}", copy_url),
+            ICON_ACTION_COPY,
+        ),
+        (
+            "复制 Markdown 链接".to_string(),
+            markdown_link(&title, &copy_url),
+            format!("copy:{}", markdown_link(&title, &copy_url)),
+            ICON_ACTION_COPY,
+        ),
+        (
+            "复制富文本链接".to_string(),
+            format!("{} — {}", title, copy_url),
+            format!("copyrich:{}\t{}", bookmark.url, title),
+            ICON_ACTION_COPY,
+        ),
+        (
+            "显示二维码".to_string(),
+            "用手机扫码打开".to_string(),
+            format!("qr:{}", bookmark.url),
+            ICON_BOOKMARK,
+        ),
+        (
+            "加入待打开队列".to_string(),
+            "在 cba 中选择 Open Queue 一次性打开".to_string(),
+            format!("queue:{}\t{}", bookmark.url, title),
+            ICON_BOOKMARK,
+        ),
+    ];
+    if let Some(folder) = bookmark.folder_path.as_deref() {
+        actions.push((
+            "同目录书签".to_string(),
+            format!("#{}", folder),
+            navigate(
+                &format!("folder:{} ", folder_filter_token(folder)),
+                format!("同目录: {}", folder),
+            ),
+            ICON_ACTION_FOLDERS,
+        ));
+    }
+    if let Some(host) = url_host(&bookmark.url) {
+        actions.push((
+            "同站点书签".to_string(),
+            host.clone(),
+            navigate(&host, format!("同站点: {}", host)),
+            ICON_ACTION_FOLDERS,
+        ));
+    }
+    actions.push((
+        "添加标签".to_string(),
+        "输入标签，逗号分隔".to_string(),
+        format!("search:>{} +", bookmark.id),
+        ICON_ACTION_FOLDERS,
+    ));
+    for tag in tags {
+        actions.push((
+            format!("标签: {}", tag),
+            "查看带该标签的书签".to_string(),
+            navigate(&format!("tag:{} ", tag), format!("标签: {}", tag)),
+            ICON_ACTION_FOLDERS,
+        ));
+    }
+    actions.push(if pinned {
+        (
+            "取消置顶".to_string(),
+            "恢复按相关度排序".to_string(),
+            format!("unpin:{}", bookmark.id),
+            ICON_PINNED,
+        )
+    } else {
+        (
+            "置顶".to_string(),
+            "空查询时列在最前，搜索命中时排在其他结果之前".to_string(),
+            format!("pin:{}", bookmark.id),
+            ICON_PINNED,
+        )
+    });
+    if revealable {
+        actions.push((
+            "在书签管理器中显示".to_string(),
+            format!("{} · {}", bookmark.browser, bookmark.profile),
+            format!("reveal:{}", bookmark.id),
+            ICON_ACTION_FOLDERS,
+        ));
+    }
+    for plugin in plugins.iter().filter(|plugin| plugin.applies_to(bookmark)) {
+        actions.push((
+            plugin.title.clone(),
+            plugin
+                .subtitle
+                .clone()
+                .unwrap_or_else(|| format!("插件: {}", plugin.name)),
+            format!("plugin:{}\t{}", plugin.name, bookmark.id),
+            ICON_BOOKMARK,
+        ));
+    }
+    actions.push((
+        "移到回收站".to_string(),
+        "不再出现在搜索中，可在 cba trash 中恢复".to_string(),
+        format!("delete:{}", bookmark.id),
+        ICON_ERROR,
+    ));
+
+    actions
+        .into_iter()
+        .map(|(title, subtitle, arg, icon)| {
+            alfred::ItemBuilder::new(title)
+                .subtitle(subtitle)
+                .arg(arg)
+                .valid(true)
+                .icon_path(icon)
+                .into_item()
+        })
+        .collect()
+}
+
+/// 副标题中显示的备注：只取第一行，过长时截断
+fn note_preview(note: &str) -> String {
+    let note = note.trim();
+    let line = note.lines().next().unwrap_or_default().trim_end();
+    let preview: String = line.chars().take(NOTE_PREVIEW_CHARS).collect();
+    if preview.len() < note.len() {
+        format!("{}…", preview.trim_end())
+    } else {
+        preview
+    }
+}
+
+/// Large Type 详情卡片：完整标题与 URL，以及副标题放不下的目录、标签、备注、时间
+fn details_card(
+    bookmark: &crate::bookmark::ChromeBookmark,
+    tags: &[String],
+    note: Option<&str>,
+    last_opened: Option<u64>,
+) -> String {
+    let mut lines = vec![
+        bookmark.name.clone(),
+        String::new(),
+        bookmark.url.clone(),
+        String::new(),
+        format!(
+            "目录: {}",
+            bookmark.folder_path.as_deref().unwrap_or("未分类")
+        ),
+        format!(
+            "标签: {}",
+            if tags.is_empty() {
+                "无".to_string()
+            } else {
+                tags.join(", ")
+            }
+        ),
+    ];
+    if let Some(note) = note {
+        lines.push(format!("备注: {}", note));
+    }
+    if !bookmark.browser.is_empty() {
+        lines.push(format!("来源: {} · {}", bookmark.browser, bookmark.profile));
+    }
+    if let Some(added) = date_added_to_unix_secs(&bookmark.date_added) {
+        lines.push(format!("添加: {}", crate::report::format_date(added)));
+    }
+    lines.push(match last_opened {
+        Some(secs) => format!(
+            "最近打开: {} (UTC)",
+            crate::report::format_datetime(secs as i64)
+        ),
+        None => "最近打开: 未通过本 workflow 打开过".to_string(),
+    });
+    lines.join("\n")
+}
+
+fn handle_details(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    id: &str,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let store = TagManager::new(data_dir.join("tags.db"))
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let tags = store
+        .tags_for(&tag_key(&bookmark))
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let note = store
+        .note_for(&tag_key(&bookmark))
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let last_opened = if config.usage_tracking {
+        UsageStore::new(data_dir.join("usage.db"))
+            .and_then(|usage| usage.last_opened(&bookmark.url))
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+    } else {
+        None
+    };
+    println!(
+        "{}",
+        details_card(&bookmark, &tags, note.as_deref(), last_opened)
+    );
+    Ok(())
+}
+
+/// 查询语法按空白分词：含空格的目录段取其中最长的一段，目录过滤按段做子串匹配，仍能命中
+fn folder_filter_token(folder: &str) -> String {
+    folder
+        .split('/')
+        .filter_map(|segment| {
+            segment
+                .split_whitespace()
+                .max_by_key(|part| part.chars().count())
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `[标题](URL)`，转义标题中的方括号与 URL 中的右括号
+fn markdown_link(title: &str, url: &str) -> String {
+    let title = if title.trim().is_empty() {
+        url
+    } else {
+        title.trim()
+    };
+    format!(
+        "[{}]({})",
+        title.replace('[', "\\[").replace(']', "\\]"),
+        url.replace(')', "%29")
+    )
+}
+
+/// 书签管理器的打开方式与书签在浏览器中的原始 ID
+fn reveal_target(
+    index: &BookmarkIndex,
+    bookmark: &crate::bookmark::ChromeBookmark,
+) -> Result<Option<(ProfileLaunch, String)>, AppError> {
+    let states = index
+        .source_states()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let launch = states
+        .iter()
+        .find(|state| state.browser == bookmark.browser && state.profile == bookmark.profile)
+        .and_then(|state| profile_launch_for(Path::new(&state.path)));
+    let native_id = bookmark
+        .id
+        .rsplit(':')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    Ok(launch.map(|launch| (launch, native_id)))
+}
+
+fn handle_reveal(index: &BookmarkIndex, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let (launch, native_id) = reveal_target(index, &bookmark)?
+        .ok_or_else(|| AppError::Other(format!("{} 不支持定位书签", bookmark.browser)))?;
+    let status = process::Command::new("open")
+        .arg("-na")
+        .arg(launch.app)
+        .arg("--args")
+        .arg(format!("--profile-directory={}", launch.profile_dir))
+        .arg(format!("chrome://bookmarks/?id={}", native_id))
+        .status()?;
+    if !status.success() {
+        return Err(AppError::Other(format!("无法打开书签管理器: {}", launch.app)).into());
+    }
+    Ok(())
+}
+
+fn handle_delete(
+    index: &BookmarkIndex,
+    id: &str,
+    from_chrome: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let deleted_at = (now_ms() / 1000) as i64;
+    if !from_chrome {
+        let bookmark = index
+            .trash_bookmark(id, deleted_at)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+        show_info_alfred(format!("已移到回收站: {}", bookmark.name));
+        return Ok(());
+    }
+
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let path = chrome_source_path(index, &bookmark)?;
+    ensure_chrome_not_running()?;
+    chrome_write::remove_url_bookmark(
+        &path,
+        native_bookmark_id(&bookmark.id),
+        &unix_secs_to_date_added(deleted_at),
+    )
+    .map_err(AppError::BookmarksReadError)?;
+    index
+        .forget_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    show_info_alfred(format!("已从 Chrome 删除: {}", bookmark.name));
+    Ok(())
+}
+
+enum BookmarkEdit<'a> {
+    Move(&'a str),
+    Retitle(&'a str),
+}
+
+/// 移动 / 改名：本地书签直接修改；浏览器书签只在 `--to-chrome` 时写回 Chrome 后再更新索引
+fn handle_edit(
+    index: &BookmarkIndex,
+    id: &str,
+    edit: BookmarkEdit,
+    to_chrome: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bookmark = index
+        .get_bookmark(id)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other(format!("未找到书签: {}", id)))?;
+    let (name, folder) = match edit {
+        BookmarkEdit::Move(folder) => {
+            let folder = folder.trim().trim_matches('/');
+            if folder.is_empty() {
+                return Err(AppError::Other("目标目录不能为空".to_string()).into());
+            }
+            (bookmark.name.clone(), Some(folder.to_string()))
+        }
+        BookmarkEdit::Retitle(title) => {
+            let title = title.trim();
+            if title.is_empty() {
+                return Err(AppError::Other("标题不能为空".to_string()).into());
+            }
+            (title.to_string(), bookmark.folder_path.clone())
+        }
+    };
+
+    let folder = if bookmark.browser == LOCAL_BROWSER {
+        folder
+    } else if to_chrome {
+        let path = chrome_source_path(index, &bookmark)?;
+        ensure_chrome_not_running()?;
+        let native_id = native_bookmark_id(&bookmark.id);
+        match edit {
+            BookmarkEdit::Move(_) => Some(
+                chrome_write::move_url_bookmarks(
+                    &path,
+                    &[native_id],
+                    folder.as_deref().unwrap_or_default(),
+                    &unix_secs_to_date_added((now_ms() / 1000) as i64),
+                )
+                .map_err(AppError::BookmarksReadError)?,
+            ),
+            BookmarkEdit::Retitle(_) => {
+                chrome_write::retitle_url_bookmark(&path, native_id, &name)
+                    .map_err(AppError::BookmarksReadError)?;
+                folder
+            }
+        }
+    } else {
+        return Err(AppError::Other(format!(
+            "{} 书签需加 --to-chrome 才会写回浏览器",
+            bookmark.browser
+        ))
+        .into());
+    };
+
+    index
+        .update_bookmark(id, &name, folder.as_deref())
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    show_info_alfred(match edit {
+        BookmarkEdit::Move(_) => format!("已移到 {}: {}", folder.unwrap_or_default(), name),
+        BookmarkEdit::Retitle(_) => format!("已改名: {}", name),
+    });
+    Ok(())
+}
+
+/// 按搜索语法找出全部命中的书签，不受结果条数限制
+fn matching_bookmarks(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    raw_query: &str,
+) -> Result<Vec<crate::bookmark::ChromeBookmark>, Box<dyn std::error::Error>> {
+    let (query, mut filters) = parse_query_and_folder_filters(raw_query);
+    filters.widen_all_browsers();
+    // 空查询会命中全部书签，批量操作时视为误用
+    if query.trim().is_empty() && filters.is_empty() {
+        return Err(AppError::Other("请提供查询或过滤条件".to_string()).into());
+    }
+    if !filters.tags.is_empty() {
+        attach_tag_store(index, data_dir)?;
+    }
+    let bookmarks = if filters.is_empty() {
+        index.load_all_bookmarks()
+    } else {
+        index.load_bookmarks_filtered(&filters)
+    }
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    if query.trim().is_empty() {
+        return Ok(bookmarks);
+    }
+    Ok(BookmarkSearcher::new()
+        .search(&bookmarks, &query, &filters.folders, false, bookmarks.len())
+        .into_iter()
+        .map(|result| result.bookmark)
+        .collect())
+}
+
+/// 批量移动的结果；`skipped` 为未加 `--to-chrome` 或无法写回的浏览器书签
+struct MoveSummary {
+    moved: usize,
+    skipped: usize,
+}
+
+/// 批量移动：本地书签直接修改；Chrome 书签在 `to_chrome` 时按（书签文件, 目标目录）分组，每组只写回一次
+fn move_bookmarks(
+    index: &BookmarkIndex,
+    moves: &[(crate::bookmark::ChromeBookmark, String)],
+    to_chrome: bool,
+) -> Result<MoveSummary, Box<dyn std::error::Error>> {
+    let mut summary = MoveSummary {
+        moved: 0,
+        skipped: 0,
+    };
+    let mut local = Vec::new();
+    let mut chrome_groups: BTreeMap<(PathBuf, String), Vec<&crate::bookmark::ChromeBookmark>> =
+        BTreeMap::new();
+    for (bookmark, folder) in moves {
+        if bookmark.browser == LOCAL_BROWSER {
+            local.push((bookmark, folder));
+        } else if to_chrome && bookmark.browser == "chrome" {
+            let path = chrome_source_path(index, bookmark)?;
+            chrome_groups
+                .entry((path, folder.clone()))
+                .or_default()
+                .push(bookmark);
+        } else {
+            summary.skipped += 1;
+        }
+    }
+    // 先确认 Chrome 已退出，避免本地书签已移动而 Chrome 部分失败
+    if !chrome_groups.is_empty() {
+        ensure_chrome_not_running()?;
+    }
+
+    for (bookmark, folder) in local {
+        index
+            .update_bookmark(&bookmark.id, &bookmark.name, Some(folder))
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        summary.moved += 1;
+    }
+    let date_modified = unix_secs_to_date_added((now_ms() / 1000) as i64);
+    for ((path, folder), bookmarks) in chrome_groups {
+        let native_ids: Vec<&str> = bookmarks
+            .iter()
+            .map(|bookmark| native_bookmark_id(&bookmark.id))
+            .collect();
+        let label = chrome_write::move_url_bookmarks(&path, &native_ids, &folder, &date_modified)
+            .map_err(AppError::BookmarksReadError)?;
+        for bookmark in bookmarks {
+            index
+                .update_bookmark(&bookmark.id, &bookmark.name, Some(&label))
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            summary.moved += 1;
+        }
+    }
+    Ok(summary)
+}
+
+/// `move-all`：把搜索命中的书签移到同一目录，`dry_run` 时只列出
+fn handle_move_all(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    raw_query: &str,
+    folder: &str,
+    to_chrome: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let folder = folder.trim().trim_matches('/');
+    if folder.is_empty() {
+        return Err(AppError::Other("目标目录不能为空".to_string()).into());
+    }
+    let bookmarks = matching_bookmarks(index, data_dir, raw_query)?;
+    if bookmarks.is_empty() {
+        show_info_alfred(format!("没有匹配的书签: {}", raw_query));
+        return Ok(());
+    }
+
+    let moves: Vec<_> = bookmarks
+        .into_iter()
+        .map(|bookmark| (bookmark, folder.to_string()))
+        .collect();
+    if dry_run {
+        return write_move_preview(
+            format!("将移动 {} 个书签到 {}（dry-run）", moves.len(), folder),
+            &moves,
+        );
+    }
+    let summary = move_bookmarks(index, &moves, to_chrome)?;
+    let mut message = format!("已移动 {} 个书签到 {}", summary.moved, folder);
+    if summary.skipped > 0 {
+        message.push_str(&format!(
+            "，跳过 {} 个浏览器书签（Chrome 书签需加 --to-chrome）",
+            summary.skipped
+        ));
+    }
+    show_info_alfred(message);
+    Ok(())
+}
+
+/// 批量移动的 dry-run 预览：每个书签一行“原目录 → 新目录”
+fn write_move_preview(
+    title: String,
+    moves: &[(crate::bookmark::ChromeBookmark, String)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut items = vec![alfred::ItemBuilder::new(title).valid(false).into_item()];
+    items.extend(moves.iter().map(|(bookmark, folder)| {
+        alfred::ItemBuilder::new(bookmark.name.clone())
+            .subtitle(format!(
+                "{} → {}",
+                bookmark.folder_path.as_deref().unwrap_or_default(),
+                folder
+            ))
+            .valid(false)
+            .into_item()
+    }));
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+/// `merge-folders`：把 `src` 及其子目录下的书签移到 `dst`，保留子目录结构；
+/// 书签 id 不变，标签随之保留，全部移动成功后再改写搜索历史中的目录过滤
+fn handle_merge_folders(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    src: &str,
+    dst: &str,
+    to_chrome: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let src = src.trim().trim_matches('/');
+    let dst = dst.trim().trim_matches('/');
+    if src.is_empty() || dst.is_empty() {
+        return Err(AppError::Other("源目录和目标目录都不能为空".to_string()).into());
+    }
+    if strip_folder_prefix(dst, src).is_some() {
+        return Err(AppError::Other("目标目录不能是源目录或其子目录".to_string()).into());
+    }
+
+    let moves: Vec<_> = index
+        .load_all_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .filter_map(|bookmark| {
+            let folder = merged_folder(bookmark.folder_path.as_deref()?, src, dst)?;
+            Some((bookmark, folder))
+        })
+        .collect();
+    if moves.is_empty() {
+        show_info_alfred(format!("目录下没有书签: {}", src));
+        return Ok(());
+    }
+    if dry_run {
+        return write_move_preview(
+            format!(
+                "将把 {} 个书签从 {} 合并到 {}（dry-run）",
+                moves.len(),
+                src,
+                dst
+            ),
+            &moves,
+        );
+    }
+
+    let summary = move_bookmarks(index, &moves, to_chrome)?;
+    let mut message = format!("已把 {} 个书签从 {} 合并到 {}", summary.moved, src, dst);
+    if summary.skipped > 0 {
+        // 源目录仍有书签，保留指向它的搜索历史
+        message.push_str(&format!(
+            "，跳过 {} 个浏览器书签（Chrome 书签需加 --to-chrome）",
+            summary.skipped
+        ));
+    } else if data_dir.join("history.db").exists() {
+        let rewritten = QueryHistory::new(data_dir.join("history.db"))
+            .and_then(|history| history.rewrite(|query| rewrite_folder_filters(query, src, dst)))
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if rewritten > 0 {
+            message.push_str(&format!("，更新 {} 条搜索历史", rewritten));
+        }
+    }
+    show_info_alfred(message);
+    Ok(())
+}
+
+/// 目录路径以 `prefix` 开头（按整段比较、不区分大小写）时返回其余部分
+fn strip_folder_prefix<'a>(folder: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = folder.get(..prefix.len())?;
+    let rest = &folder[prefix.len()..];
+    if !head.eq_ignore_ascii_case(prefix) || !(rest.is_empty() || rest.starts_with('/')) {
+        return None;
+    }
+    Some(rest.trim_start_matches('/'))
+}
+
+/// `src` 下（含子目录）的目录合并到 `dst` 后的路径；不在 `src` 下时为 None
+fn merged_folder(folder: &str, src: &str, dst: &str) -> Option<String> {
+    let rest = strip_folder_prefix(folder, src)?;
+    Some(if rest.is_empty() {
+        dst.to_string()
+    } else {
+        format!("{}/{}", dst, rest)
+    })
+}
+
+/// 把查询中指向 `src`（或其子目录）的目录过滤改写到 `dst`；没有可改写的过滤时为 None
+fn rewrite_folder_filters(query: &str, src: &str, dst: &str) -> Option<String> {
+    let mut changed = false;
+    let tokens: Vec<String> = query
+        .split_whitespace()
+        .map(|token| {
+            let Some(prefix) = ["#", "dir:", "folder:", "path:", "in:"]
+                .into_iter()
+                .find(|prefix| token.starts_with(prefix))
+            else {
+                return token.to_string();
+            };
+            let values: Vec<String> = token[prefix.len()..]
+                .split(',')
+                .map(|value| match merged_folder(value, src, dst) {
+                    Some(folder) => {
+                        changed = true;
+                        folder_filter_token(&folder)
+                    }
+                    None => value.to_string(),
+                })
+                .collect();
+            format!("{}{}", prefix, values.join(","))
+        })
+        .collect();
+    changed.then(|| tokens.join(" "))
+}
+
+/// 写回 Chrome 的书签文件路径；书签需来自已入库的 Chrome 来源
+fn chrome_source_path(
+    index: &BookmarkIndex,
+    bookmark: &crate::bookmark::ChromeBookmark,
+) -> Result<PathBuf, AppError> {
+    if bookmark.browser != "chrome" {
+        return Err(AppError::Other(format!(
+            "不是 Chrome 书签: {}",
+            bookmark.id
+        )));
+    }
+    index
+        .source_states()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .find(|state| state.browser == bookmark.browser && state.profile == bookmark.profile)
+        .map(|state| PathBuf::from(state.path))
+        .ok_or(AppError::BookmarksNotFound)
+}
+
+/// Chrome 运行时会在退出前用内存中的书签覆盖文件，写回前必须确认其已退出
+fn ensure_chrome_not_running() -> Result<(), AppError> {
+    if is_browser_running(browser_bundle_ids("chrome")) {
+        return Err(AppError::Other(
+            "Chrome 正在运行，请先退出 Chrome 再修改书签".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// 书签 id（`浏览器:profile:原生 id`）中的原生 id
+fn native_bookmark_id(id: &str) -> &str {
+    id.rsplit(':').next().unwrap_or_default()
+}
+
+/// 回收站列表：↩ 恢复
+/// `recent` / `frequent` 共用：解析查询中的内联过滤，需要时挂载标签库
+fn listing_query(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    query: &[String],
+) -> Result<(String, BookmarkFilters), AppError> {
+    let (keywords, mut filters) = parse_query_and_folder_filters(&query.join(" "));
+    filters.widen_all_browsers();
+    if !filters.tags.is_empty() {
+        attach_tag_store(index, data_dir)?;
+    }
+    Ok((keywords, filters))
+}
+
+fn listing_item(
+    bookmark: &crate::bookmark::ChromeBookmark,
+    detail: String,
+) -> alfred::Item<'static> {
+    alfred::ItemBuilder::new(bookmark.name.clone())
+        .subtitle(format!(
+            "{} · {} · {}",
+            detail,
+            bookmark.folder_path.as_deref().unwrap_or("未分类"),
+            bookmark.url
+        ))
+        .arg(format!("open:{}", bookmark.url))
+        .quicklook_url(bookmark.url.clone())
+        .autocomplete(format!(">{}", bookmark.id))
+        .text_copy(bookmark.url.clone())
+        .valid(true)
+        .icon_path(ICON_BOOKMARK)
+        .into_item()
+}
+
+fn handle_recent(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    query: &[String],
+    limit: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (keywords, filters) = listing_query(index, data_dir, query)?;
+    let bookmarks = index
+        .search_sorted(&keywords, &filters, SortOrder::Added, limit)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other("全文索引不可用，无法按关键词过滤".to_string()))?;
+    let mut items: Vec<alfred::Item> = bookmarks
+        .iter()
+        .map(|bookmark| {
+            let added = date_added_to_unix_secs(&bookmark.date_added)
+                .map(crate::report::format_date)
+                .unwrap_or_else(|| "添加时间未知".to_string());
+            listing_item(bookmark, added)
+        })
+        .collect();
+    if items.is_empty() {
+        items.push(
+            alfred::ItemBuilder::new("没有符合条件的书签")
+                .valid(false)
+                .into_item(),
+        );
+    }
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+fn handle_frequent(
+    index: &BookmarkIndex,
+    data_dir: &Path,
+    query: &[String],
+    limit: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (keywords, filters) = listing_query(index, data_dir, query)?;
+    let usage_db = data_dir.join("usage.db");
+    UsageStore::new(usage_db.clone()).map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    index
+        .attach_usage(&usage_db)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let opened = index
+        .most_opened(&keywords, &filters, limit)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::Other("全文索引不可用，无法按关键词过滤".to_string()))?;
+    let mut items: Vec<alfred::Item> = opened
+        .iter()
+        .map(|(bookmark, count)| listing_item(bookmark, format!("打开 {} 次", count)))
+        .collect();
+    if items.is_empty() {
+        items.push(
+            alfred::ItemBuilder::new("还没有通过本 Workflow 打开过书签")
+                .subtitle("在搜索结果上回车打开书签后会记录次数")
+                .valid(false)
+                .into_item(),
+        );
+    }
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+fn handle_trash(index: &BookmarkIndex, query: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let keyword = query.join(" ").trim().to_lowercase();
+    let mut items: Vec<alfred::Item> = index
+        .trashed_bookmarks()
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .filter(|(bookmark, _)| {
+            keyword.is_empty()
+                || bookmark.name.to_lowercase().contains(&keyword)
+                || bookmark.url.to_lowercase().contains(&keyword)
+        })
+        .map(|(bookmark, deleted_at)| {
+            alfred::ItemBuilder::new(bookmark.name.clone())
+                .subtitle(format!(
+                    "↩ 恢复 · 删除于 {} · {}",
+                    crate::report::format_date(deleted_at),
+                    bookmark.url
+                ))
+                .arg(format!("restore:{}", bookmark.id))
+                .valid(true)
+                .icon_path(ICON_BOOKMARK)
+                .into_item()
+        })
+        .collect();
+    if items.is_empty() {
+        items.push(
+            alfred::ItemBuilder::new("回收站为空")
+                .subtitle("在书签的动作菜单（⇥）中选择“移到回收站”")
+                .valid(false)
+                .into_item(),
+        );
+    }
+    alfred::json::write_items(io::stdout(), &items)?;
+    Ok(())
+}
+
+fn handle_queue(
+    cmd: QueueCommand,
+    queue: &OpenQueue,
+    data_dir: &Path,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match cmd {
+        QueueCommand::Add { url, title } => {
+            let (added, total) = queue.add(&url, &title)?;
+            show_info_alfred(if added {
+                format!("已加入队列，共 {} 个待打开", total)
+            } else {
+                format!("已在队列中，共 {} 个待打开", total)
+            });
+        }
+        QueueCommand::List => {
+            let mut items: Vec<alfred::Item> = queue
+                .entries()
+                .into_iter()
+                .map(|entry| {
+                    let title = if entry.title.is_empty() {
+                        entry.url.clone()
+                    } else {
+                        entry.title
+                    };
+                    alfred::ItemBuilder::new(title)
+                        .subtitle(entry.url.clone())
+                        .arg(format!("open:{}", entry.url))
+                        .valid(true)
+                        .icon_path(ICON_BOOKMARK)
+                        .into_item()
+                })
+                .collect();
+            if items.is_empty() {
+                items.push(
+                    alfred::ItemBuilder::new("队列为空")
+                        .subtitle("在搜索结果上按 ⌃↩ 加入队列")
+                        .valid(false)
+                        .into_item(),
+                );
+            }
+            alfred::json::write_items(io::stdout(), &items)?;
+        }
+        QueueCommand::OpenAll => {
+            let entries = queue.entries();
+            // 除最后一个外都在后台打开，避免浏览器反复抢焦点
+            for (position, entry) in entries.iter().enumerate() {
+                let target = open_target_for(data_dir, &entry.url, config);
+                let background = position + 1 < entries.len();
+                handle_open(&entry.url, background, &target, config)?;
+            }
+            queue.clear()?;
+            show_info_alfred(format!("已打开队列中的 {} 个链接", entries.len()));
+        }
+        QueueCommand::Clear => {
+            queue.clear()?;
+            show_info_alfred("队列已清空");
+        }
+    }
+    Ok(())
+}
+
+fn handle_actions(query: Vec<String>, cache_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let keyword = query.join(" ").trim().to_lowercase();
+    let mut items = Vec::new();
+
+    // 队列非空时排在最前，供一次性打开
+    let queued = OpenQueue::new(cache_dir).entries().len();
+    if queued > 0 {
+        for (title, subtitle, arg) in [
+            (
+                format!("Open Queue ({})", queued),
+                "打开队列中的全部书签并清空队列".to_string(),
+                "action:queue_open_all",
+            ),
+            (
+                "Clear Queue".to_string(),
+                format!("清空待打开队列（{} 个）", queued),
+                "action:queue_clear",
+            ),
+        ] {
+            if keyword.is_empty()
+                || title.to_lowercase().contains(&keyword)
+                || subtitle.contains(&keyword)
+            {
+                items.push(
+                    alfred::ItemBuilder::new(title)
+                        .subtitle(subtitle)
+                        .arg(arg)
+                        .valid(true)
+                        .icon_path(ICON_BOOKMARK)
+                        .into_item(),
+                );
+            }
+        }
+    }
+
+    if keyword.is_empty() || "trash 回收站".contains(&keyword) {
+        items.push(
+            alfred::ItemBuilder::new("Trash")
+                .subtitle("浏览回收站中的书签，↩ 恢复")
+                .autocomplete("trash ")
+                .valid(false)
+                .icon_path(ICON_ERROR)
+                .into_item(),
+        );
+    }
+
+    for action in workflow_actions() {
+        if !keyword.is_empty() {
+            let title_match = action.title.to_lowercase().contains(&keyword);
+            let subtitle_match = action.subtitle.to_lowercase().contains(&keyword);
+            if !title_match && !subtitle_match {
+                continue;
+            }
+        }
+
+        let mut item = alfred::ItemBuilder::new(action.title)
+            .subtitle(action.subtitle)
+            .arg(action.arg)
+            .valid(action.autocomplete.is_none())
+            .icon_path(action.icon_path);
+        if let Some(autocomplete) = action.autocomplete {
+            item = item.autocomplete(autocomplete);
+        }
+        items.push(item.into_item());
+    }
+
+    if items.is_empty() {
+        items.push(
+            alfred::ItemBuilder::new("未找到动作")
+                .subtitle("尝试输入 refresh / stats / guide / readme")
+                .valid(false)
+                .into_item(),
+        );
+    }
+
+    write_items_with_progress(items, cache_dir)
+}
+
+/// 正在进行的刷新 / 链接检查排在最前，并让 Alfred 定时 rerun 以刷新进度
+fn write_items_with_progress(
+    items: Vec<alfred::Item<'static>>,
+    cache_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut all: Vec<alfred::Item> = [
+        JOB_REFRESH,
+        JOB_CHECK_LINKS,
+        JOB_FAVICONS,
+        JOB_INDEX_CONTENT,
+    ]
+    .iter()
+    .filter_map(|job| crate::progress::read_active(cache_dir, job))
+    .map(|progress| {
+        let title = match progress.job.as_str() {
+            JOB_REFRESH => "正在刷新索引",
+            JOB_FAVICONS => "正在下载站点图标",
+            JOB_INDEX_CONTENT => "正在抓取网页正文",
+            _ => "正在检查链接",
+        };
+        alfred::ItemBuilder::new(format!("{}: {}", title, progress.describe()))
+            .subtitle("完成后此条目自动消失")
+            .valid(false)
+            .icon_path(ICON_ACTION_REFRESH)
+            .into_item()
+    })
+    .collect();
+
+    if all.is_empty() {
+        alfred::json::write_items(io::stdout(), &items)?;
+    } else {
+        all.extend(items);
+        // alfred crate 的 Builder 不支持 rerun，在生成的 JSON 上补上该字段
+        let mut json = alfred::json::Builder::with_items(&all).into_json();
+        if let serde_json::Value::Object(root) = &mut json {
+            root.insert("rerun".to_string(), serde_json::json!(PROGRESS_RERUN_SECS));
+        }
+        serde_json::to_writer(io::stdout(), &json)?;
+    }
+    Ok(())
+}
+
+fn show_error_alfred<'a, T: Into<Cow<'a, str>>>(s: T) {
+    let item = alfred::ItemBuilder::new("✗ 操作失败")
+        .subtitle(s)
+        .icon_path("icons/error.png")
+        .valid(false)
+        .into_item();
+    let _ = alfred::json::write_items(io::stdout(), &[item]);
+}
+
+/// 读取 Safari 书签被拒绝时的提示，回车打开系统设置中的“完全磁盘访问权限”
+fn show_full_disk_access_alfred(detail: &str) {
+    let item = alfred::ItemBuilder::new("需要完全磁盘访问权限才能读取 Safari 书签")
+        .subtitle("回车打开 系统设置 › 隐私与安全性 › 完全磁盘访问权限，允许 Alfred 后重试")
+        .arg("action:full_disk_access")
+        .text_large_type(detail)
+        .icon_path(ICON_ERROR)
+        .valid(true)
+        .into_item();
+    let _ = alfred::json::write_items(io::stdout(), &[item]);
+}
+
+fn show_info_alfred<'a, T: Into<Cow<'a, str>>>(s: T) {
+    let item = alfred::ItemBuilder::new("✓ 操作完成")
+        .subtitle(s)
+        .icon_path("icons/info.png")
+        .valid(false)
+        .into_item();
+    let _ = alfred::json::write_items(io::stdout(), &[item]);
+}
+
+#[cfg(test)]
+mod tests {
     use super::{
-        is_index_check_recent, normalize_csv_terms, now_ms, parse_query_and_folder_filters,
-        workflow_actions, IndexCheckState, INDEX_CHECK_STATE_FILE,
+        bookmark_record, details_card, folder_filter_token, is_index_check_recent, markdown_link,
+        matching_bookmarks, merged_folder, move_bookmarks, normalize_csv_terms, note_preview,
+        now_ms, parse_browser_terms, parse_query_and_folder_filters, post_refresh_hook_command,
+        rewrite_folder_filters, source_badge, workflow_actions, BookmarkFilters, BookmarkIndex,
+        IndexCheckState, INDEX_CHECK_STATE_FILE, NOTE_PREVIEW_CHARS,
     };
+    use crate::bookmark::{test_bookmark, ChromeBookmark};
+    use crate::link_check::LinkStore;
+    use crate::tags::TagManager;
+    use crate::usage::UsageStore;
     use tempfile::TempDir;
 
+    #[test]
+    fn action_menu_links_and_folder_tokens() {
+        assert_eq!(
+            markdown_link("Rust [book]", "https://a.example/(x)"),
+            "[Rust \\[book\\]](https://a.example/(x%29)"
+        );
+        assert_eq!(
+            markdown_link(" ", "https://a.example"),
+            "[https://a.example](https://a.example)"
+        );
+        assert_eq!(
+            folder_filter_token("Work Stuff/Project A/rust"),
+            "Stuff/Project/rust"
+        );
+
+        let (_, filters) = parse_query_and_folder_filters(&format!(
+            "folder:{} ",
+            folder_filter_token("书签栏/Dev Tools")
+        ));
+        assert_eq!(filters.folders, vec!["书签栏/Tools"]);
+    }
+
     #[test]
     fn parse_query_extracts_inline_folder_filters() {
-        let (query, folders) = parse_query_and_folder_filters("rust dir:work/project folder:tech");
+        let (query, BookmarkFilters { folders, .. }) =
+            parse_query_and_folder_filters("rust dir:work/project folder:tech");
         assert_eq!(query, "rust");
         assert_eq!(
             folders,
@@ -653,9 +5765,20 @@ mod tests {
 
     #[test]
     fn parse_query_keeps_regular_terms() {
-        let (query, folders) = parse_query_and_folder_filters("rust async tokio");
+        let (query, BookmarkFilters { folders, .. }) =
+            parse_query_and_folder_filters("rust async tokio");
         assert_eq!(query, "rust async tokio");
         assert!(folders.is_empty());
+
+        let (query, BookmarkFilters { folders, .. }) =
+            parse_query_and_folder_filters("(rust OR go) #work -game");
+        assert_eq!(query, "(rust OR go) -game");
+        assert_eq!(folders, vec!["work".to_string()]);
+
+        let (query, BookmarkFilters { tags, .. }) =
+            parse_query_and_folder_filters("\"see  tag:rust\" @web");
+        assert_eq!(query, "\"see tag:rust\"");
+        assert_eq!(tags, vec!["web".to_string()]);
     }
 
     #[test]
@@ -666,21 +5789,23 @@ mod tests {
 
     #[test]
     fn parse_query_extracts_hash_folder_filters() {
-        let (query, folders) = parse_query_and_folder_filters("#work #project rust");
+        let (query, BookmarkFilters { folders, .. }) =
+            parse_query_and_folder_filters("#work #project rust");
         assert_eq!(query, "rust");
         assert_eq!(folders, vec!["work".to_string(), "project".to_string()]);
     }
 
     #[test]
     fn parse_query_supports_mixed_hash_and_plain_keywords() {
-        let (query, folders) = parse_query_and_folder_filters("tokio #backend #docs async");
+        let (query, BookmarkFilters { folders, .. }) =
+            parse_query_and_folder_filters("tokio #backend #docs async");
         assert_eq!(query, "tokio async");
         assert_eq!(folders, vec!["backend".to_string(), "docs".to_string()]);
     }
 
     #[test]
     fn parse_query_merges_hash_and_inline_folder_filters() {
-        let (query, folders) =
+        let (query, BookmarkFilters { folders, .. }) =
             parse_query_and_folder_filters("rust #work dir:project folder:docs #WORK");
         assert_eq!(query, "rust");
         assert_eq!(
@@ -695,18 +5820,123 @@ mod tests {
 
     #[test]
     fn parse_query_ignores_empty_hash_token() {
-        let (query, folders) = parse_query_and_folder_filters("# rust #");
+        let (query, BookmarkFilters { folders, .. }) = parse_query_and_folder_filters("# rust #");
         assert_eq!(query, "rust");
         assert!(folders.is_empty());
     }
 
     #[test]
     fn parse_query_accepts_hash_comma_separated_folders() {
-        let (query, folders) = parse_query_and_folder_filters("#work,project rust");
+        let (query, BookmarkFilters { folders, .. }) =
+            parse_query_and_folder_filters("#work,project rust");
         assert_eq!(query, "rust");
         assert_eq!(folders, vec!["work".to_string(), "project".to_string()]);
     }
 
+    #[test]
+    fn parse_query_extracts_browser_filters_with_aliases() {
+        let (query, filters) = parse_query_and_folder_filters("browser:Google-Chrome,brave tokio");
+        assert_eq!(query, "tokio");
+        assert_eq!(
+            filters.browsers,
+            vec!["chrome".to_string(), "brave".to_string()]
+        );
+    }
+
+    #[test]
+    fn browser_all_lifts_other_browser_filters() {
+        let (query, mut filters) =
+            parse_query_and_folder_filters("browser:arc rust browser:all profile:Work");
+        filters.widen_all_browsers();
+        assert_eq!(query, "rust");
+        assert!(filters.browsers.is_empty());
+        assert_eq!(filters.profiles, vec!["Work".to_string()]);
+    }
+
+    #[test]
+    fn parse_browser_terms_canonicalizes_sources_flag() {
+        assert_eq!(
+            parse_browser_terms("Arc, google-chrome,,"),
+            vec!["arc".to_string(), "chrome".to_string()]
+        );
+    }
+
+    #[test]
+    fn details_card_lists_metadata() {
+        let bookmark = ChromeBookmark {
+            date_added: "13348540800000000".to_string(),
+            browser: "chrome".to_string(),
+            profile: "Work".to_string(),
+            ..test_bookmark(
+                "chrome:Work:1",
+                "Rust",
+                "https://rust-lang.org/learn",
+                Some("书签栏/Dev"),
+            )
+        };
+        let card = details_card(
+            &bookmark,
+            &["lang".to_string(), "docs".to_string()],
+            Some("read chapter 3"),
+            Some(1_704_110_400),
+        );
+        assert_eq!(
+            card,
+            "Rust\n\nhttps://rust-lang.org/learn\n\n目录: 书签栏/Dev\n标签: lang, docs\n\
+             备注: read chapter 3\n来源: chrome · Work\n添加: 2024-01-01\n最近打开: 2024-01-01 12:00 (UTC)"
+        );
+        let card = details_card(&bookmark, &[], None, None);
+        assert!(card.contains("标签: 无"));
+        assert!(!card.contains("备注"));
+    }
+
+    #[test]
+    fn note_preview_keeps_first_line_and_truncates() {
+        assert_eq!(note_preview("  read later "), "read later");
+        assert_eq!(note_preview("summary\nmore details"), "summary…");
+        let long = "长".repeat(NOTE_PREVIEW_CHARS + 5);
+        assert_eq!(
+            note_preview(&long),
+            format!("{}…", "长".repeat(NOTE_PREVIEW_CHARS))
+        );
+    }
+
+    #[test]
+    fn source_badge_uses_browser_display_name() {
+        let bookmark = ChromeBookmark {
+            browser: "opera-gx".to_string(),
+            profile: "Work".to_string(),
+            ..test_bookmark("opera-gx:Work:1", "Rust", "https://rust-lang.org", None)
+        };
+        assert_eq!(source_badge(&bookmark), "[Opera GX·Work]");
+    }
+
+    #[test]
+    fn parse_query_extracts_profile_filters() {
+        let (query, filters) = parse_query_and_folder_filters("rust profile:Work,Personal #docs");
+        assert_eq!(query, "rust");
+        assert_eq!(
+            filters.profiles,
+            vec!["Work".to_string(), "Personal".to_string()]
+        );
+        assert_eq!(filters.folders, vec!["docs".to_string()]);
+    }
+
+    #[test]
+    fn parse_query_extracts_tag_filters() {
+        let (query, filters) = parse_query_and_folder_filters("tag:rust,web async tag:RUST");
+        assert_eq!(query, "async");
+        assert_eq!(filters.tags, vec!["rust".to_string(), "web".to_string()]);
+
+        let (query, filters) = parse_query_and_folder_filters("@rust #work tokio @Async,web");
+        assert_eq!(query, "tokio");
+        assert_eq!(filters.folders, vec!["work".to_string()]);
+        assert_eq!(
+            filters.tags,
+            vec!["rust".to_string(), "Async".to_string(), "web".to_string()]
+        );
+    }
+
     #[test]
     fn workflow_actions_contains_core_entries() {
         let actions = workflow_actions();
@@ -715,6 +5945,114 @@ mod tests {
         assert!(actions.iter().any(|action| action.arg == "action:stats"));
     }
 
+    #[test]
+    fn post_refresh_hook_receives_count_and_fingerprint() {
+        let command = post_refresh_hook_command("~/bin/backup.sh --quiet", 42, "abc|primary=");
+        let args: Vec<String> = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(command.get_program(), "/bin/sh");
+        assert_eq!(
+            args,
+            vec![
+                "-c",
+                "~/bin/backup.sh --quiet \"$@\"",
+                "alfred-chrome-bookmarks-hook",
+                "42",
+                "abc|primary=",
+            ]
+        );
+    }
+
+    #[test]
+    fn move_all_moves_matching_local_bookmarks_and_skips_browser_ones() {
+        let tmp = TempDir::new().expect("tempdir");
+        let index = BookmarkIndex::new(tmp.path().join("bookmarks.db")).expect("index");
+        let chrome = ChromeBookmark {
+            browser: "chrome".to_string(),
+            profile: "Default".to_string(),
+            ..test_bookmark(
+                "chrome:Default:7",
+                "Rust Book",
+                "https://doc.rust-lang.org/book",
+                Some("Inbox"),
+            )
+        };
+        index.replace_bookmarks(&[chrome], "fp").expect("replace");
+        index
+            .add_local_bookmark("https://docs.rs", "Rust Docs", Some("Inbox"), "1")
+            .expect("add");
+        index
+            .add_local_bookmark("https://go.dev", "Go", Some("Inbox"), "1")
+            .expect("add");
+
+        assert!(matching_bookmarks(&index, tmp.path(), "  ").is_err());
+        let matched = matching_bookmarks(&index, tmp.path(), "rust #inbox").expect("match");
+        assert_eq!(matched.len(), 2);
+
+        let moves: Vec<_> = matched
+            .into_iter()
+            .map(|bookmark| (bookmark, "Reference/Rust".to_string()))
+            .collect();
+        let summary = move_bookmarks(&index, &moves, false).expect("move");
+        assert_eq!((summary.moved, summary.skipped), (1, 1));
+
+        let moved = matching_bookmarks(&index, tmp.path(), "#Reference/Rust").expect("match");
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].name, "Rust Docs");
+    }
+
+    #[test]
+    fn get_record_reports_workflow_opens() {
+        let tmp = TempDir::new().expect("tempdir");
+        let index = BookmarkIndex::new(tmp.path().join("bookmarks.db")).expect("index");
+        let tags = TagManager::new(tmp.path().join("tags.db")).expect("tags");
+        let links = LinkStore::new(tmp.path().join("links.db")).expect("links");
+        let usage = UsageStore::new(tmp.path().join("usage.db")).expect("usage");
+        let bookmark = test_bookmark("1", "Rust Docs", "https://docs.rs", None);
+        index
+            .replace_bookmarks(std::slice::from_ref(&bookmark), "fp")
+            .expect("replace");
+
+        let record = bookmark_record(&index, &tags, &links, &usage, &bookmark).expect("record");
+        assert_eq!((record.open_count, record.last_opened), (0, None));
+
+        usage.record_open("https://docs.rs", 100).expect("open");
+        usage.record_open("https://docs.rs", 250).expect("open");
+        let record = bookmark_record(&index, &tags, &links, &usage, &bookmark).expect("record");
+        assert_eq!((record.open_count, record.last_opened), (2, Some(250)));
+        let json = serde_json::to_value(&record).expect("json");
+        assert_eq!(json["open_count"], 2);
+        assert_eq!(json["last_opened"], 250);
+    }
+
+    #[test]
+    fn merge_folders_reparents_subfolders_and_rewrites_filters() {
+        let src = "书签栏/Imported/Imported";
+        let dst = "书签栏/Imported";
+        assert_eq!(
+            merged_folder("书签栏/Imported/Imported", src, dst).as_deref(),
+            Some("书签栏/Imported")
+        );
+        assert_eq!(
+            merged_folder("书签栏/imported/Imported/Dev/Rust", src, dst).as_deref(),
+            Some("书签栏/Imported/Dev/Rust")
+        );
+        assert_eq!(merged_folder("书签栏/Imported/Imported2", src, dst), None);
+        assert_eq!(merged_folder("书签栏/Imported", src, dst), None);
+
+        assert_eq!(
+            rewrite_folder_filters("rust #书签栏/Imported/Imported/Dev,work", src, dst).as_deref(),
+            Some("rust #书签栏/Imported/Dev,work")
+        );
+        assert_eq!(
+            rewrite_folder_filters("in:Inbox/Old", "Inbox/Old", "Reading List").as_deref(),
+            Some("in:Reading")
+        );
+        assert_eq!(rewrite_folder_filters("rust #work", src, dst), None);
+    }
+
     #[test]
     fn index_check_recent_respects_ttl() {
         let tmp = TempDir::new().expect("tempdir");
@@ -726,13 +6064,15 @@ mod tests {
         };
         let bytes = serde_json::to_vec(&state).expect("serialize state");
         std::fs::write(&state_path, bytes).expect("write state");
-        assert!(is_index_check_recent(tmp.path(), now));
+        assert!(is_index_check_recent(tmp.path(), now, 2_000));
+        assert!(!is_index_check_recent(tmp.path(), now, 0));
 
         let old_state = IndexCheckState {
             last_checked_ms: now.saturating_sub(10_000),
         };
         let bytes = serde_json::to_vec(&old_state).expect("serialize stale state");
         std::fs::write(state_path, bytes).expect("write stale state");
-        assert!(!is_index_check_recent(tmp.path(), now));
+        assert!(!is_index_check_recent(tmp.path(), now, 2_000));
+        assert!(is_index_check_recent(tmp.path(), now, 30_000));
     }
 }
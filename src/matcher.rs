@@ -0,0 +1,173 @@
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use nucleo_matcher::pattern::{Atom, AtomKind, CaseMatching, Normalization};
+use nucleo_matcher::Utf32Str;
+use std::cell::RefCell;
+
+/// 模糊匹配打分：返回 None 表示不匹配，分数越高越相关
+pub trait TextMatcher {
+    fn score(&self, haystack: &str, needle: &str) -> Option<i64>;
+}
+
+/// 可选的模糊匹配实现
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatcherKind {
+    /// skim 的 V2 算法（默认）
+    #[default]
+    Skim,
+    /// helix/nucleo 的算法，对长文本更快，打分更接近 fzf
+    Nucleo,
+    /// 只要求按顺序出现的子序列匹配，打分简单、结果可预期
+    Subsequence,
+}
+
+impl MatcherKind {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "skim" | "default" => Some(MatcherKind::Skim),
+            "nucleo" | "fzf" => Some(MatcherKind::Nucleo),
+            "subsequence" | "simple" => Some(MatcherKind::Subsequence),
+            _ => None,
+        }
+    }
+
+    pub fn build(self) -> Box<dyn TextMatcher> {
+        match self {
+            MatcherKind::Skim => Box::new(SkimTextMatcher::default()),
+            MatcherKind::Nucleo => Box::new(NucleoTextMatcher::default()),
+            MatcherKind::Subsequence => Box::new(SubsequenceMatcher),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SkimTextMatcher {
+    inner: SkimMatcherV2,
+}
+
+impl TextMatcher for SkimTextMatcher {
+    fn score(&self, haystack: &str, needle: &str) -> Option<i64> {
+        self.inner.fuzzy_match(haystack, needle)
+    }
+}
+
+/// nucleo 的 Matcher 打分时需要可变的暂存区，单线程搜索用 RefCell 复用即可
+pub struct NucleoTextMatcher {
+    matcher: RefCell<nucleo_matcher::Matcher>,
+    buf: RefCell<Vec<char>>,
+}
+
+impl Default for NucleoTextMatcher {
+    fn default() -> Self {
+        Self {
+            matcher: RefCell::new(nucleo_matcher::Matcher::new(
+                nucleo_matcher::Config::DEFAULT,
+            )),
+            buf: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl TextMatcher for NucleoTextMatcher {
+    fn score(&self, haystack: &str, needle: &str) -> Option<i64> {
+        let atom = Atom::new(
+            needle,
+            CaseMatching::Ignore,
+            Normalization::Smart,
+            AtomKind::Fuzzy,
+            false,
+        );
+        let mut buf = self.buf.borrow_mut();
+        let mut matcher = self.matcher.borrow_mut();
+        atom.score(Utf32Str::new(haystack, &mut buf), &mut matcher)
+            .map(i64::from)
+    }
+}
+
+/// 忽略大小写的子序列匹配：每个命中字符得分，连续命中与落在词首额外加分，
+/// 首个命中之前的字符略微扣分
+pub struct SubsequenceMatcher;
+
+impl SubsequenceMatcher {
+    const MATCH: i64 = 16;
+    const CONSECUTIVE_BONUS: i64 = 8;
+    const BOUNDARY_BONUS: i64 = 12;
+    const MAX_LEADING_PENALTY: i64 = 15;
+}
+
+impl TextMatcher for SubsequenceMatcher {
+    fn score(&self, haystack: &str, needle: &str) -> Option<i64> {
+        let mut needle = needle.chars().flat_map(char::to_lowercase).peekable();
+        needle.peek()?;
+
+        let mut score = 0i64;
+        let mut first_match = None;
+        let mut prev_matched = false;
+        let mut prev_char: Option<char> = None;
+        for (pos, ch) in haystack.chars().flat_map(char::to_lowercase).enumerate() {
+            let Some(&want) = needle.peek() else {
+                break;
+            };
+            if ch == want {
+                needle.next();
+                score += Self::MATCH;
+                if prev_matched {
+                    score += Self::CONSECUTIVE_BONUS;
+                }
+                if !prev_char.is_some_and(char::is_alphanumeric) {
+                    score += Self::BOUNDARY_BONUS;
+                }
+                first_match.get_or_insert(pos);
+                prev_matched = true;
+            } else {
+                prev_matched = false;
+            }
+            prev_char = Some(ch);
+        }
+
+        if needle.peek().is_some() {
+            return None;
+        }
+        let leading = first_match.unwrap_or(0) as i64;
+        Some(score - leading.min(Self::MAX_LEADING_PENALTY))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_names_and_aliases() {
+        assert_eq!(MatcherKind::parse("Nucleo"), Some(MatcherKind::Nucleo));
+        assert_eq!(
+            MatcherKind::parse(" simple "),
+            Some(MatcherKind::Subsequence)
+        );
+        assert_eq!(MatcherKind::parse("skim"), Some(MatcherKind::Skim));
+        assert_eq!(MatcherKind::parse("levenshtein"), None);
+    }
+
+    #[test]
+    fn every_backend_matches_subsequences_and_rejects_misses() {
+        for kind in [
+            MatcherKind::Skim,
+            MatcherKind::Nucleo,
+            MatcherKind::Subsequence,
+        ] {
+            let matcher = kind.build();
+            assert!(matcher.score("GitHub Docs", "ghd").is_some(), "{kind:?}");
+            assert!(matcher.score("GitHub Docs", "xyz").is_none(), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn subsequence_prefers_consecutive_and_word_start_matches() {
+        let matcher = SubsequenceMatcher;
+        let word = matcher.score("rust book", "book").unwrap();
+        let scattered = matcher.score("big robot kit", "book").unwrap();
+        assert!(word > scattered);
+        assert!(matcher.score("Rust", "rust").unwrap() > matcher.score("trust", "rust").unwrap());
+        assert_eq!(matcher.score("rust", ""), None);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::bookmark::{browser_priority, date_added_to_unix_secs, ChromeBookmark};
+use crate::url_normalize::canonical_url;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// 合并时落选的其他来源版本，挂在胜出书签的 id 下
+#[derive(Debug, Clone)]
+pub struct BookmarkVariant {
+    pub winner_id: String,
+    pub bookmark: ChromeBookmark,
+}
+
+/// 合并多个来源：同一规范 URL 出现在多个来源时只保留一条
+/// 优先主浏览器，其次 date_added 最新，再按浏览器优先级列表；其余来源的版本记为 variant
+/// 同一来源内部的重复书签保持不变（由 dedupe 处理）
+pub fn merge_sources(
+    bookmarks: Vec<ChromeBookmark>,
+    primary_browser: Option<&str>,
+    priority: &[String],
+) -> (Vec<ChromeBookmark>, Vec<BookmarkVariant>) {
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, bookmark) in bookmarks.iter().enumerate() {
+        groups
+            .entry(canonical_url(&bookmark.url))
+            .or_default()
+            .push(idx);
+    }
+
+    // 落选书签下标 -> 胜出书签下标
+    let mut loser_of: HashMap<usize, usize> = HashMap::new();
+    for members in groups.values() {
+        let first_source = source_key(&bookmarks[members[0]]);
+        if members
+            .iter()
+            .all(|&idx| source_key(&bookmarks[idx]) == first_source)
+        {
+            continue;
+        }
+
+        let winner = *members
+            .iter()
+            .max_by_key(|&&idx| {
+                let bookmark = &bookmarks[idx];
+                (
+                    primary_browser
+                        .is_some_and(|primary| primary.eq_ignore_ascii_case(&bookmark.browser)),
+                    date_added_to_unix_secs(&bookmark.date_added).unwrap_or(0),
+                    Reverse(browser_priority(priority, &bookmark.browser)),
+                    Reverse(idx),
+                )
+            })
+            .expect("group is never empty");
+        let winner_source = source_key(&bookmarks[winner]);
+
+        for &idx in members {
+            if source_key(&bookmarks[idx]) != winner_source {
+                loser_of.insert(idx, winner);
+            }
+        }
+    }
+
+    if loser_of.is_empty() {
+        return (bookmarks, Vec::new());
+    }
+
+    let winner_ids: HashMap<usize, String> = loser_of
+        .values()
+        .map(|&winner| (winner, bookmarks[winner].id.clone()))
+        .collect();
+    let mut kept = Vec::with_capacity(bookmarks.len() - loser_of.len());
+    let mut variants = Vec::with_capacity(loser_of.len());
+    for (idx, bookmark) in bookmarks.into_iter().enumerate() {
+        match loser_of.get(&idx) {
+            Some(winner) => variants.push(BookmarkVariant {
+                winner_id: winner_ids[winner].clone(),
+                bookmark,
+            }),
+            None => kept.push(bookmark),
+        }
+    }
+
+    (kept, variants)
+}
+
+fn source_key(bookmark: &ChromeBookmark) -> (&str, &str) {
+    (&bookmark.browser, &bookmark.profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+
+    fn bookmark(id: &str, browser: &str, url: &str, date_added: &str) -> ChromeBookmark {
+        ChromeBookmark {
+            date_added: date_added.to_string(),
+            browser: browser.to_string(),
+            profile: "Default".to_string(),
+            ..test_bookmark(
+                &format!("{}:Default:{}", browser, id),
+                &format!("Title {}", id),
+                url,
+                None,
+            )
+        }
+    }
+
+    #[test]
+    fn newest_bookmark_wins_across_sources() {
+        let bookmarks = vec![
+            bookmark("1", "chrome", "https://example.com/a", "13348540800000000"),
+            bookmark(
+                "2",
+                "firefox",
+                "https://EXAMPLE.com/a/#top",
+                "1704153600000000",
+            ),
+            bookmark("3", "chrome", "https://other.example", "0"),
+        ];
+
+        let (kept, variants) = merge_sources(bookmarks, None, &[]);
+        let kept_ids: Vec<&str> = kept.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(kept_ids, vec!["firefox:Default:2", "chrome:Default:3"]);
+        assert_eq!(variants.len(), 1);
+        assert_eq!(variants[0].winner_id, "firefox:Default:2");
+        assert_eq!(variants[0].bookmark.id, "chrome:Default:1");
+    }
+
+    #[test]
+    fn primary_browser_beats_newer_bookmark() {
+        let bookmarks = vec![
+            bookmark("1", "chrome", "https://example.com", "13348540800000000"),
+            bookmark("2", "brave", "https://example.com", "13348627200000000"),
+        ];
+
+        let (kept, variants) = merge_sources(bookmarks, Some("chrome"), &[]);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].browser, "chrome");
+        assert_eq!(variants[0].bookmark.browser, "brave");
+    }
+
+    #[test]
+    fn browser_priority_breaks_date_ties() {
+        let bookmarks = vec![
+            bookmark("1", "chrome", "https://example.com", "13348540800000000"),
+            bookmark("2", "arc", "https://example.com", "13348540800000000"),
+        ];
+        let priority = vec!["arc".to_string(), "chrome".to_string()];
+
+        let (kept, variants) = merge_sources(bookmarks, None, &priority);
+        assert_eq!(kept[0].browser, "arc");
+        assert_eq!(variants[0].bookmark.browser, "chrome");
+    }
+
+    #[test]
+    fn duplicates_within_one_source_are_kept() {
+        let bookmarks = vec![
+            bookmark("1", "chrome", "https://example.com", "1"),
+            bookmark("2", "chrome", "https://example.com", "2"),
+        ];
+
+        let (kept, variants) = merge_sources(bookmarks, None, &[]);
+        assert_eq!(kept.len(), 2);
+        assert!(variants.is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+use std::path::{Path, PathBuf};
+
+/// 待打开队列：`<缓存目录>/open_queue.tsv`，每行 `URL<TAB>标题`，按加入顺序排列
+pub struct OpenQueue {
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedLink {
+    pub url: String,
+    pub title: String,
+}
+
+impl OpenQueue {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            path: cache_dir.join("open_queue.tsv"),
+        }
+    }
+
+    pub fn entries(&self) -> Vec<QueuedLink> {
+        let Ok(content) = std::fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+        content
+            .lines()
+            .filter_map(|line| {
+                let (url, title) = line.split_once('\t').unwrap_or((line, ""));
+                let url = url.trim();
+                (!url.is_empty()).then(|| QueuedLink {
+                    url: url.to_string(),
+                    title: title.trim().to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// 加入队列（同一 URL 只保留一条）；返回 (是否新加入, 队列长度)
+    pub fn add(&self, url: &str, title: &str) -> std::io::Result<(bool, usize)> {
+        let mut entries = self.entries();
+        let url = url.trim();
+        if entries.iter().any(|entry| entry.url == url) {
+            return Ok((false, entries.len()));
+        }
+        entries.push(QueuedLink {
+            url: url.to_string(),
+            title: title.replace(['\t', '\n', '\r'], " ").trim().to_string(),
+        });
+        self.write(&entries)?;
+        Ok((true, entries.len()))
+    }
+
+    pub fn clear(&self) -> std::io::Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    fn write(&self, entries: &[QueuedLink]) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content: String = entries
+            .iter()
+            .map(|entry| format!("{}\t{}\n", entry.url, entry.title))
+            .collect();
+        let tmp = self.path.with_extension("tsv.tmp");
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn queue_keeps_order_and_skips_duplicates() {
+        let dir = tempdir().expect("tempdir");
+        let queue = OpenQueue::new(dir.path());
+        assert!(queue.entries().is_empty());
+
+        assert_eq!(
+            queue.add("https://docs.rs", "Docs\tRS").expect("add"),
+            (true, 1)
+        );
+        assert_eq!(
+            queue.add("https://rust-lang.org", "").expect("add"),
+            (true, 2)
+        );
+        assert_eq!(
+            queue.add("https://docs.rs", "again").expect("add"),
+            (false, 2)
+        );
+
+        let entries = queue.entries();
+        assert_eq!(entries[0].url, "https://docs.rs");
+        assert_eq!(entries[0].title, "Docs RS");
+        assert_eq!(entries[1].url, "https://rust-lang.org");
+
+        queue.clear().expect("clear");
+        assert!(queue.entries().is_empty());
+        queue.clear().expect("clear twice");
+    }
+}
@@ -0,0 +1,423 @@
+use crate::bookmark::ChromeBookmark;
+use crate::schema::{self, Migration};
+use crate::tags::bookmark_key;
+use crate::tokenize::{analyze, Tokenizer};
+use rusqlite::{params, Connection, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+/// 单个网页最多下载的字节数；只需要标题、描述与小标题，超出的大页面放弃
+const MAX_PAGE_BYTES: u64 = 2 * 1024 * 1024;
+/// 每个字段最多保存的字符数
+const MAX_FIELD_CHARS: usize = 2000;
+
+/// 从网页中提取、用于正文搜索的可读文本
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageText {
+    pub title: String,
+    pub description: String,
+    /// `<h1>`–`<h3>` 的文字，按出现顺序以换行分隔
+    pub headings: String,
+}
+
+impl PageText {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_empty() && self.description.is_empty() && self.headings.is_empty()
+    }
+}
+
+/// 网页正文索引（content.db），由 `index-content` 手动建立
+///
+/// 按规范 URL 的键（与 `tag_key` 相同）保存，刷新或重建书签索引都不会丢失；
+/// 搜索时挂载到索引连接上，以书签表的 `tag_key` 关联
+pub struct ContentStore {
+    conn: Connection,
+}
+
+impl ContentStore {
+    pub fn new(db_path: PathBuf) -> Result<Self> {
+        let conn = crate::db_crypt::open(&db_path, crate::db_crypt::key())?;
+        let _ = conn.busy_timeout(Duration::from_millis(500));
+
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;",
+        )?;
+
+        schema::migrate(&conn, CONTENT_MIGRATIONS)?;
+
+        Ok(Self { conn })
+    }
+
+    /// 需要抓取的书签：从未抓取过，或上次抓取早于 `stale_before`（秒）；同一 URL 只取一次，
+    /// 从未抓取的排在最前，其余按上次抓取时间从早到晚
+    pub fn pending<'a>(
+        &self,
+        bookmarks: &'a [ChromeBookmark],
+        stale_before: u64,
+    ) -> Result<Vec<&'a ChromeBookmark>> {
+        let fetched: HashMap<String, u64> = {
+            let mut stmt = self.conn.prepare("SELECT url_key, fetched_at FROM pages")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+
+        let mut seen = HashSet::new();
+        let mut pending: Vec<(&ChromeBookmark, u64)> = bookmarks
+            .iter()
+            .filter(|bookmark| {
+                bookmark.url.starts_with("http://") || bookmark.url.starts_with("https://")
+            })
+            .filter_map(|bookmark| {
+                let key = bookmark_key(&bookmark.url);
+                let last = fetched.get(&key).copied().unwrap_or(0);
+                (last < stale_before && seen.insert(key)).then_some((bookmark, last))
+            })
+            .collect();
+        pending.sort_by_key(|(_, last)| *last);
+        Ok(pending.into_iter().map(|(bookmark, _)| bookmark).collect())
+    }
+
+    /// 保存网页文本，替换该 URL 之前的内容；`tokenizers` 与标题列的附加分词方式一致
+    pub fn save(
+        &self,
+        url: &str,
+        page: &PageText,
+        tokenizers: &[Tokenizer],
+        now: u64,
+    ) -> Result<()> {
+        let key = bookmark_key(url);
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM page_text WHERE url_key = ?1", params![key])?;
+        if !page.is_empty() {
+            tx.execute(
+                "INSERT INTO page_text (url_key, title, description, headings)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    key,
+                    analyze(&page.title, tokenizers),
+                    analyze(&page.description, tokenizers),
+                    analyze(&page.headings, tokenizers),
+                ],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO pages (url_key, url, fetched_at, error)
+             VALUES (?1, ?2, ?3, NULL)",
+            params![key, url, now as i64],
+        )?;
+        tx.commit()
+    }
+
+    /// 记录抓取失败；已保存的文本保留，到期后再重试
+    pub fn save_failure(&self, url: &str, error: &str, now: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO pages (url_key, url, fetched_at, error) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(url_key) DO UPDATE SET fetched_at = excluded.fetched_at, error = excluded.error",
+            params![bookmark_key(url), url, now as i64, error],
+        )?;
+        Ok(())
+    }
+
+    /// 已建立正文索引的网页数
+    pub fn indexed_pages(&self) -> Result<usize> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM page_text", [], |row| row.get(0))
+    }
+}
+
+/// 通过系统 curl 下载网页（跟随重定向），只接受 2xx 的 HTML 响应
+pub fn fetch_page(url: &str, timeout: Duration) -> std::result::Result<String, String> {
+    let output = Command::new("curl")
+        .arg("-sS")
+        .arg("-L")
+        .arg("--compressed")
+        .arg("--max-time")
+        .arg(timeout.as_secs().max(1).to_string())
+        .arg("--max-filesize")
+        .arg(MAX_PAGE_BYTES.to_string())
+        .arg("-A")
+        .arg("Mozilla/5.0 (Macintosh) alfred-chrome-bookmarks")
+        .arg("-w")
+        .arg("\n%{http_code}\n%{content_type}")
+        .arg(url)
+        .output()
+        .map_err(|err| err.to_string())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut tail = stdout.rsplitn(3, '\n');
+    let content_type = tail.next().unwrap_or("").trim().to_ascii_lowercase();
+    let status = tail.next().unwrap_or("").trim().to_string();
+    let body = tail.next().unwrap_or("");
+
+    if !status.starts_with('2') {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(if stderr.is_empty() {
+            format!("HTTP {}", status)
+        } else {
+            stderr
+        });
+    }
+    if !content_type.is_empty() && !content_type.contains("html") {
+        return Err(format!("不是网页: {}", content_type));
+    }
+    Ok(body.to_string())
+}
+
+/// 从 HTML 中提取标题、描述（`description` / `og:description`）与 `<h1>`–`<h3>` 的文字
+///
+/// 只做简单的标签扫描，不构建 DOM；标签内的嵌套标签去掉，常见字符实体解码，空白合并
+pub fn extract_page_text(html: &str) -> PageText {
+    // ASCII 小写不改变字节位置，查找标签用小写副本，取文字用原文
+    let lower = html.to_ascii_lowercase();
+    let mut page = PageText::default();
+
+    if let Some((_, title)) = element_text(html, &lower, 0, "title") {
+        page.title = title;
+    }
+
+    let mut pos = 0;
+    while let Some(start) = lower[pos..].find("<meta").map(|offset| pos + offset) {
+        let end = lower[start..]
+            .find('>')
+            .map_or(lower.len(), |offset| start + offset);
+        let tag = &html[start..end];
+        pos = end;
+        if !page.description.is_empty() {
+            break;
+        }
+        let name = attr_value(tag, "name").or_else(|| attr_value(tag, "property"));
+        if matches!(
+            name.map(|name| name.to_ascii_lowercase()).as_deref(),
+            Some("description" | "og:description")
+        ) {
+            page.description = clean_text(&attr_value(tag, "content").unwrap_or_default());
+        }
+    }
+
+    let mut headings = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = lower[pos..].find("<h").map(|offset| pos + offset) {
+        let level = lower.as_bytes().get(start + 2).copied();
+        let after = lower.as_bytes().get(start + 3).copied();
+        pos = start + 2;
+        let is_heading = matches!(level, Some(b'1'..=b'3'))
+            && matches!(after, Some(b'>' | b' ' | b'\t' | b'\n' | b'\r'));
+        if !is_heading {
+            continue;
+        }
+        let name = &lower[start + 1..start + 3];
+        if let Some((end, text)) = element_text(html, &lower, start, name) {
+            if !text.is_empty() {
+                headings.push(text);
+            }
+            pos = end;
+        }
+    }
+    page.headings = truncate_chars(&headings.join("\n"));
+    page
+}
+
+/// 从 `from` 起第一个 `<name ...>...</name>` 的文字，返回 (结束标签之后的位置, 文字)
+fn element_text(html: &str, lower: &str, from: usize, name: &str) -> Option<(usize, String)> {
+    let open = from + lower[from..].find(&format!("<{}", name))?;
+    let content_start = open + lower[open..].find('>')? + 1;
+    let close = format!("</{}", name);
+    let content_end = content_start + lower[content_start..].find(&close)?;
+    let text = clean_text(&strip_tags(&html[content_start..content_end]));
+    Some((content_end + close.len(), text))
+}
+
+/// 标签内 `name="..."`（或单引号、无引号）的值
+fn attr_value(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let mut pos = 0;
+    while let Some(offset) = lower[pos..].find(name) {
+        let start = pos + offset;
+        pos = start + name.len();
+        let boundary = start > 0 && bytes[start - 1].is_ascii_whitespace();
+        let rest = lower[pos..].trim_start();
+        if !boundary || !rest.starts_with('=') {
+            continue;
+        }
+        let value_start = lower.len() - rest.len() + 1;
+        let value = tag[value_start..].trim_start();
+        return Some(match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or("").to_string(),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .next()
+                .unwrap_or("")
+                .to_string(),
+        });
+    }
+    None
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// 解码常见字符实体、合并空白并截断到 `MAX_FIELD_CHARS`
+fn clean_text(raw: &str) -> String {
+    let mut decoded = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end]).map(|ch| (ch, end)));
+        match entity {
+            Some((ch, end)) => {
+                decoded.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    truncate_chars(&decoded.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let value = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(value)
+        }
+    }
+}
+
+fn truncate_chars(text: &str) -> String {
+    match text.char_indices().nth(MAX_FIELD_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text.to_string(),
+    }
+}
+
+/// 正文库的结构迁移，按版本号升序；新增列或表时在末尾追加一步，不要改动已发布的步骤
+const CONTENT_MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    apply: create_content_tables,
+}];
+
+/// 版本 1：抓取记录与正文 FTS 表
+fn create_content_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS pages (
+            url_key TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            fetched_at INTEGER NOT NULL,
+            error TEXT
+         );
+         CREATE VIRTUAL TABLE IF NOT EXISTS page_text USING fts5(
+            url_key UNINDEXED,
+            title,
+            description,
+            headings,
+            tokenize = 'unicode61'
+         );",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+    use tempfile::tempdir;
+
+    fn bookmark(id: &str, url: &str) -> ChromeBookmark {
+        test_bookmark(id, id, url, None)
+    }
+
+    #[test]
+    fn extract_page_text_reads_title_description_and_headings() {
+        let html = r#"<!doctype html><HTML><head>
+            <TITLE>Tokio &amp; friends</TITLE>
+            <meta charset="utf-8">
+            <meta property='og:description' content='An async runtime &#8212; fast'>
+            <meta name="description" content="ignored, first one wins">
+            </head><body><header>nav</header>
+            <h1 class="hero">Build <em>reliable</em>
+               apps</h1><h4>skip</h4><h2>Tutorial</h2><hr></body></HTML>"#;
+        assert_eq!(
+            extract_page_text(html),
+            PageText {
+                title: "Tokio & friends".to_string(),
+                description: "An async runtime — fast".to_string(),
+                headings: "Build reliable apps\nTutorial".to_string(),
+            }
+        );
+        assert!(extract_page_text("<p>plain</p>").is_empty());
+    }
+
+    #[test]
+    fn pending_skips_fresh_pages_and_failures_are_retried_later() {
+        let dir = tempdir().expect("tempdir");
+        let store = ContentStore::new(dir.path().join("content.db")).expect("store");
+        let bookmarks = vec![
+            bookmark("1", "https://docs.rs/"),
+            bookmark("2", "https://docs.rs?utm_source=x"),
+            bookmark("3", "https://tokio.rs"),
+            bookmark("4", "chrome://settings"),
+            bookmark("5", "https://gone.example"),
+        ];
+        let page = PageText {
+            title: "Docs.rs".to_string(),
+            ..PageText::default()
+        };
+        store
+            .save("https://docs.rs", &page, &[], 100)
+            .expect("save");
+        store
+            .save_failure("https://gone.example", "HTTP 404", 50)
+            .expect("failure");
+
+        let ids = |stale_before: u64| -> Vec<String> {
+            store
+                .pending(&bookmarks, stale_before)
+                .expect("pending")
+                .into_iter()
+                .map(|bookmark| bookmark.id.clone())
+                .collect()
+        };
+        assert_eq!(ids(60), vec!["3", "5"]);
+        assert_eq!(ids(200), vec!["3", "5", "1"]);
+
+        store
+            .save("https://docs.rs/", &PageText::default(), &[], 300)
+            .expect("resave");
+        assert_eq!(store.indexed_pages().expect("count"), 0);
+    }
+}
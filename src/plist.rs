@@ -0,0 +1,419 @@
+//! 二进制 plist（`bplist00`）的最小解析器，只覆盖读取 Safari 书签所需的对象类型
+
+use std::fmt;
+
+/// 解析出的 plist 对象；字典保持文件中的键顺序
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    /// 自 2001-01-01 UTC 起的秒数
+    Date(f64),
+    Data(Vec<u8>),
+    String(String),
+    Uid(u64),
+    Array(Vec<Value>),
+    Dict(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_date(&self) -> Option<f64> {
+        match self {
+            Value::Date(secs) => Some(*secs),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "无法解析二进制 plist: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+const MAGIC: &[u8] = b"bplist00";
+const TRAILER_LEN: usize = 32;
+/// 对象嵌套上限；正常的书签层级远小于此，超出视为引用成环
+const MAX_DEPTH: usize = 256;
+
+/// 解析完整的二进制 plist，返回顶层对象
+pub fn parse(data: &[u8]) -> Result<Value> {
+    if !data.starts_with(MAGIC) {
+        return Err(ParseError("缺少 bplist00 文件头".to_string()));
+    }
+    if data.len() < MAGIC.len() + TRAILER_LEN {
+        return Err(ParseError("文件过短".to_string()));
+    }
+
+    let trailer = &data[data.len() - TRAILER_LEN..];
+    let offset_size = trailer[6] as usize;
+    let ref_size = trailer[7] as usize;
+    let num_objects = be_uint(&trailer[8..16]) as usize;
+    let top_object = be_uint(&trailer[16..24]) as usize;
+    let table_offset = be_uint(&trailer[24..32]) as usize;
+    if !(1..=8).contains(&offset_size) || !(1..=8).contains(&ref_size) {
+        return Err(ParseError("trailer 中的整数宽度无效".to_string()));
+    }
+    let table_end = num_objects
+        .checked_mul(offset_size)
+        .and_then(|len| len.checked_add(table_offset))
+        .filter(|end| *end <= data.len() - TRAILER_LEN)
+        .ok_or_else(|| ParseError("偏移表越界".to_string()))?;
+
+    let offsets = data[table_offset..table_end]
+        .chunks(offset_size)
+        .map(|chunk| be_uint(chunk) as usize)
+        .collect();
+    Parser {
+        data,
+        offsets,
+        ref_size,
+    }
+    .object(top_object, 0)
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    offsets: Vec<usize>,
+    ref_size: usize,
+}
+
+impl Parser<'_> {
+    fn object(&self, index: usize, depth: usize) -> Result<Value> {
+        if depth > MAX_DEPTH {
+            return Err(ParseError("对象嵌套过深".to_string()));
+        }
+        let offset = *self
+            .offsets
+            .get(index)
+            .ok_or_else(|| ParseError(format!("对象引用 {} 越界", index)))?;
+        let marker = *self
+            .data
+            .get(offset)
+            .ok_or_else(|| ParseError(format!("对象偏移 {} 越界", offset)))?;
+        let info = (marker & 0x0F) as usize;
+
+        match marker >> 4 {
+            0x0 => match marker {
+                0x00 => Ok(Value::Null),
+                0x08 => Ok(Value::Bool(false)),
+                0x09 => Ok(Value::Bool(true)),
+                _ => Err(ParseError(format!("未知的单字节对象 {:#04x}", marker))),
+            },
+            0x1 => {
+                let bytes = self.bytes(offset + 1, 1 << info)?;
+                // 16 字节整数只在值超出 i64 时出现，取低 8 字节
+                Ok(Value::Integer(
+                    be_uint(&bytes[bytes.len().saturating_sub(8)..]) as i64,
+                ))
+            }
+            0x2 => Ok(Value::Real(self.real(offset + 1, 1 << info)?)),
+            0x3 => Ok(Value::Date(self.real(offset + 1, 8)?)),
+            0x4 => {
+                let (len, start) = self.count(offset, info)?;
+                Ok(Value::Data(self.bytes(start, len)?.to_vec()))
+            }
+            0x5 => {
+                let (len, start) = self.count(offset, info)?;
+                let bytes = self.bytes(start, len)?;
+                Ok(Value::String(bytes.iter().map(|&b| b as char).collect()))
+            }
+            0x6 => {
+                let (len, start) = self.count(offset, info)?;
+                let units: Vec<u16> = self
+                    .bytes(start, len.saturating_mul(2))?
+                    .chunks(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+                Ok(Value::String(String::from_utf16_lossy(&units)))
+            }
+            0x8 => Ok(Value::Uid(be_uint(self.bytes(offset + 1, info + 1)?))),
+            0xA | 0xC => {
+                let (len, start) = self.count(offset, info)?;
+                let items = self
+                    .refs(start, len)?
+                    .into_iter()
+                    .map(|item| self.object(item, depth + 1))
+                    .collect::<Result<_>>()?;
+                Ok(Value::Array(items))
+            }
+            0xD => {
+                let (len, start) = self.count(offset, info)?;
+                let keys = self.refs(start, len)?;
+                let values =
+                    self.refs(start.saturating_add(len.saturating_mul(self.ref_size)), len)?;
+                let mut entries = Vec::with_capacity(len);
+                for (key, value) in keys.into_iter().zip(values) {
+                    let Value::String(key) = self.object(key, depth + 1)? else {
+                        return Err(ParseError("字典的键不是字符串".to_string()));
+                    };
+                    entries.push((key, self.object(value, depth + 1)?));
+                }
+                Ok(Value::Dict(entries))
+            }
+            _ => Err(ParseError(format!("不支持的对象类型 {:#04x}", marker))),
+        }
+    }
+
+    /// 变长对象的元素个数：低 4 位为 0xF 时个数另以整数对象跟在标记之后
+    fn count(&self, offset: usize, info: usize) -> Result<(usize, usize)> {
+        if info != 0x0F {
+            return Ok((info, offset + 1));
+        }
+        let marker = *self
+            .data
+            .get(offset + 1)
+            .ok_or_else(|| ParseError("长度标记越界".to_string()))?;
+        if marker >> 4 != 0x1 {
+            return Err(ParseError("长度不是整数对象".to_string()));
+        }
+        let width = 1usize << (marker & 0x0F);
+        let len = be_uint(self.bytes(offset + 2, width)?) as usize;
+        Ok((len, offset + 2 + width))
+    }
+
+    fn refs(&self, start: usize, len: usize) -> Result<Vec<usize>> {
+        Ok(self
+            .bytes(start, len.saturating_mul(self.ref_size))?
+            .chunks(self.ref_size)
+            .map(|chunk| be_uint(chunk) as usize)
+            .collect())
+    }
+
+    fn real(&self, start: usize, width: usize) -> Result<f64> {
+        let bytes = self.bytes(start, width)?;
+        match width {
+            4 => Ok(f32::from_be_bytes(bytes.try_into().unwrap_or_default()) as f64),
+            8 => Ok(f64::from_be_bytes(bytes.try_into().unwrap_or_default())),
+            _ => Err(ParseError(format!("不支持 {} 字节的浮点数", width))),
+        }
+    }
+
+    fn bytes(&self, start: usize, len: usize) -> Result<&[u8]> {
+        start
+            .checked_add(len)
+            .and_then(|end| self.data.get(start..end))
+            .ok_or_else(|| ParseError(format!("读取 {} 字节越界（偏移 {}）", len, start)))
+    }
+}
+
+fn be_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0u64, |acc, &byte| (acc << 8) | u64::from(byte))
+}
+
+/// 测试用的二进制 plist 写入器：对象按先序编号，引用与偏移固定用 2 字节
+#[cfg(test)]
+pub(crate) fn encode(value: &Value) -> Vec<u8> {
+    fn flatten<'v>(value: &'v Value, objects: &mut Vec<Flat<'v>>) -> usize {
+        let index = objects.len();
+        objects.push(Flat::Leaf(value));
+        match value {
+            Value::Array(items) => {
+                let refs = items.iter().map(|item| flatten(item, objects)).collect();
+                objects[index] = Flat::Array(refs);
+            }
+            Value::Dict(entries) => {
+                let keys: Vec<usize> = entries
+                    .iter()
+                    .map(|(key, _)| {
+                        objects.push(Flat::Key(key));
+                        objects.len() - 1
+                    })
+                    .collect();
+                let values = entries
+                    .iter()
+                    .map(|(_, value)| flatten(value, objects))
+                    .collect();
+                objects[index] = Flat::Dict(keys, values);
+            }
+            _ => {}
+        }
+        index
+    }
+
+    enum Flat<'v> {
+        Leaf(&'v Value),
+        Key(&'v str),
+        Array(Vec<usize>),
+        Dict(Vec<usize>, Vec<usize>),
+    }
+
+    fn header(out: &mut Vec<u8>, kind: u8, len: usize) {
+        if len < 0x0F {
+            out.push((kind << 4) | len as u8);
+        } else {
+            out.push((kind << 4) | 0x0F);
+            out.push(0x13);
+            out.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+
+    fn string(out: &mut Vec<u8>, value: &str) {
+        if value.is_ascii() {
+            header(out, 0x5, value.len());
+            out.extend_from_slice(value.as_bytes());
+        } else {
+            let units: Vec<u16> = value.encode_utf16().collect();
+            header(out, 0x6, units.len());
+            for unit in units {
+                out.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+    }
+
+    let mut objects = Vec::new();
+    flatten(value, &mut objects);
+
+    let mut out = MAGIC.to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for object in &objects {
+        offsets.push(out.len());
+        match object {
+            Flat::Key(key) => string(&mut out, key),
+            Flat::Array(refs) => {
+                header(&mut out, 0xA, refs.len());
+                refs.iter()
+                    .for_each(|r| out.extend_from_slice(&(*r as u16).to_be_bytes()));
+            }
+            Flat::Dict(keys, values) => {
+                header(&mut out, 0xD, keys.len());
+                keys.iter()
+                    .chain(values)
+                    .for_each(|r| out.extend_from_slice(&(*r as u16).to_be_bytes()));
+            }
+            Flat::Leaf(value) => match value {
+                Value::Null => out.push(0x00),
+                Value::Bool(flag) => out.push(if *flag { 0x09 } else { 0x08 }),
+                Value::Integer(number) => {
+                    out.push(0x13);
+                    out.extend_from_slice(&number.to_be_bytes());
+                }
+                Value::Real(number) => {
+                    out.push(0x23);
+                    out.extend_from_slice(&number.to_be_bytes());
+                }
+                Value::Date(secs) => {
+                    out.push(0x33);
+                    out.extend_from_slice(&secs.to_be_bytes());
+                }
+                Value::Data(bytes) => {
+                    header(&mut out, 0x4, bytes.len());
+                    out.extend_from_slice(bytes);
+                }
+                Value::String(text) => string(&mut out, text),
+                Value::Uid(uid) => {
+                    out.push(0x87);
+                    out.extend_from_slice(&uid.to_be_bytes());
+                }
+                Value::Array(_) | Value::Dict(..) => unreachable!(),
+            },
+        }
+    }
+
+    let table_offset = out.len();
+    for offset in &offsets {
+        out.extend_from_slice(&(*offset as u16).to_be_bytes());
+    }
+    out.extend_from_slice(&[0; 6]);
+    out.push(2);
+    out.push(2);
+    out.extend_from_slice(&(objects.len() as u64).to_be_bytes());
+    out.extend_from_slice(&0u64.to_be_bytes());
+    out.extend_from_slice(&(table_offset as u64).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dict(entries: &[(&str, Value)]) -> Value {
+        Value::Dict(
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn round_trips_nested_objects_and_unicode() {
+        let value = dict(&[
+            ("Title", Value::String("个人收藏".to_string())),
+            ("Count", Value::Integer(-3)),
+            ("Added", Value::Date(700_000_000.5)),
+            (
+                "Children",
+                Value::Array(vec![
+                    Value::Bool(true),
+                    Value::Data(vec![1, 2, 3]),
+                    Value::String("x".repeat(40)),
+                ]),
+            ),
+        ]);
+        let parsed = parse(&encode(&value)).unwrap();
+        assert_eq!(parsed, value);
+        assert_eq!(
+            parsed.get("Title").and_then(Value::as_str),
+            Some("个人收藏")
+        );
+        assert_eq!(
+            parsed.get("Added").and_then(Value::as_date),
+            Some(700_000_000.5)
+        );
+        assert_eq!(
+            parsed
+                .get("Children")
+                .and_then(Value::as_array)
+                .map(<[_]>::len),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn rejects_xml_truncated_and_out_of_range_input() {
+        assert!(parse(b"<?xml version=\"1.0\"?><plist></plist>").is_err());
+        assert!(parse(b"bplist00").is_err());
+
+        let mut data = encode(&Value::Array(vec![Value::Integer(1)]));
+        // 偏移表第二项指向文件之外
+        let table = data.len() - TRAILER_LEN - 2;
+        data[table..table + 2].copy_from_slice(&u16::MAX.to_be_bytes());
+        assert!(parse(&data).is_err());
+    }
+}
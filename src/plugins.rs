@@ -0,0 +1,203 @@
+use crate::bookmark::ChromeBookmark;
+use crate::url_normalize::url_in_domains;
+use serde::Deserialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// 插件目录中的 `<名称>.json`：声明一个书签动作及执行它的脚本
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    title: String,
+    #[serde(default)]
+    subtitle: Option<String>,
+    /// 可执行脚本，相对路径相对插件目录
+    command: String,
+    /// 只对这些站点（含子域名）的书签显示；为空时对全部书签显示
+    #[serde(default)]
+    domains: Vec<String>,
+}
+
+/// 动作菜单中的一个外部脚本动作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    /// 清单文件名（不含扩展名），作为动作的标识
+    pub name: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub command: PathBuf,
+    pub domains: Vec<String>,
+}
+
+impl Plugin {
+    pub fn applies_to(&self, bookmark: &ChromeBookmark) -> bool {
+        self.domains.is_empty() || url_in_domains(&bookmark.url, &self.domains)
+    }
+
+    /// 在插件目录中执行脚本，书签 JSON 从 stdin 传入；返回标准输出的第一行，供通知显示。
+    /// 退出码非零时以标准错误的第一行作为错误
+    pub fn run(&self, bookmark: &ChromeBookmark, tags: &[String]) -> Result<String, String> {
+        let mut payload = serde_json::to_value(bookmark).map_err(|e| e.to_string())?;
+        payload["tags"] = serde_json::json!(tags);
+        let mut child = Command::new(&self.command)
+            .current_dir(self.command.parent().unwrap_or(Path::new(".")))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("无法执行插件 {}: {}", self.name, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // 脚本不读 stdin 就退出时写入会失败，不算错误
+            let _ = stdin.write_all(payload.to_string().as_bytes());
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("插件 {} 执行失败: {}", self.name, e))?;
+        let first_line = |bytes: &[u8]| {
+            String::from_utf8_lossy(bytes)
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .unwrap_or_default()
+                .to_string()
+        };
+        if output.status.success() {
+            Ok(first_line(&output.stdout))
+        } else {
+            let message = first_line(&output.stderr);
+            Err(if message.is_empty() {
+                format!("插件 {} 失败（{}）", self.name, output.status)
+            } else {
+                message
+            })
+        }
+    }
+}
+
+/// 插件目录：`ALFRED_CHROME_BOOKMARKS_PLUGINS`，默认为数据目录下的 `plugins/`
+pub fn plugin_dir(data_dir: &Path, configured: Option<&Path>) -> PathBuf {
+    configured
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| data_dir.join("plugins"))
+}
+
+/// 目录中的全部插件，按名称排序；清单无法解析或脚本不可执行的跳过
+pub fn load_plugins(dir: &Path) -> Vec<Plugin> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<Plugin> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            let manifest: Manifest = serde_json::from_slice(&std::fs::read(&path).ok()?).ok()?;
+            let command = dir.join(manifest.command.trim());
+            if manifest.title.trim().is_empty() || !is_executable(&command) {
+                return None;
+            }
+            Some(Plugin {
+                name,
+                title: manifest.title.trim().to_string(),
+                subtitle: manifest
+                    .subtitle
+                    .filter(|subtitle| !subtitle.trim().is_empty()),
+                command,
+                domains: manifest
+                    .domains
+                    .iter()
+                    .map(|domain| domain.trim().trim_start_matches("www.").to_lowercase())
+                    .filter(|domain| !domain.is_empty())
+                    .collect(),
+            })
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+    use tempfile::tempdir;
+
+    fn bookmark(url: &str) -> ChromeBookmark {
+        ChromeBookmark {
+            browser: "chrome".to_string(),
+            profile: "Default".to_string(),
+            ..test_bookmark("chrome:Default:7", "Docs", url, Some("书签栏/Rust"))
+        }
+    }
+
+    fn write_script(path: &Path, body: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::write(path, format!("#!/bin/sh\n{}\n", body)).expect("script");
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).expect("chmod");
+    }
+
+    #[test]
+    fn manifests_declare_actions_and_scripts_receive_the_bookmark() {
+        let dir = tempdir().expect("tempdir");
+        write_script(
+            &dir.path().join("echo.sh"),
+            "cat > payload.json; echo saved",
+        );
+        std::fs::write(
+            dir.path().join("pin.json"),
+            r#"{"title": "存到 Pinboard", "command": "echo.sh", "domains": ["www.GitHub.com"]}"#,
+        )
+        .expect("manifest");
+        write_script(&dir.path().join("fail.sh"), "echo 'no token' >&2; exit 3");
+        std::fs::write(
+            dir.path().join("broken.json"),
+            r#"{"title": "失败", "command": "fail.sh"}"#,
+        )
+        .expect("manifest");
+        // 脚本不可执行、清单不完整的都被忽略
+        std::fs::write(dir.path().join("plain.sh"), "echo").expect("plain");
+        std::fs::write(
+            dir.path().join("plain.json"),
+            r#"{"title": "x", "command": "plain.sh"}"#,
+        )
+        .expect("manifest");
+        std::fs::write(dir.path().join("bad.json"), "{").expect("manifest");
+
+        let plugins = load_plugins(dir.path());
+        let names: Vec<&str> = plugins.iter().map(|plugin| plugin.name.as_str()).collect();
+        assert_eq!(names, vec!["broken", "pin"]);
+
+        let pin = &plugins[1];
+        assert_eq!(pin.domains, vec!["github.com"]);
+        assert!(pin.applies_to(&bookmark("https://gist.github.com/x")));
+        assert!(!pin.applies_to(&bookmark("https://docs.rs")));
+        assert!(plugins[0].applies_to(&bookmark("https://docs.rs")));
+
+        let target = bookmark("https://github.com/rust-lang");
+        assert_eq!(
+            pin.run(&target, &["rust".to_string()]),
+            Ok("saved".to_string())
+        );
+        let payload: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.path().join("payload.json")).unwrap())
+                .expect("json");
+        assert_eq!(payload["url"], "https://github.com/rust-lang");
+        assert_eq!(payload["folder_path"], "书签栏/Rust");
+        assert_eq!(payload["tags"], serde_json::json!(["rust"]));
+
+        assert_eq!(plugins[0].run(&target, &[]), Err("no token".to_string()));
+    }
+}
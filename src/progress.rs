@@ -0,0 +1,199 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const JOB_REFRESH: &str = "refresh";
+pub const JOB_CHECK_LINKS: &str = "check-links";
+pub const JOB_FAVICONS: &str = "favicons";
+pub const JOB_INDEX_CONTENT: &str = "index-content";
+/// 超过该时间没有更新的进度视为进程已退出（崩溃或被杀），不再展示
+const STALE_AFTER_MS: u64 = 60_000;
+
+/// 后台任务的进度，以 `key=value` 行写入缓存目录，便于 Alfred 的 rerun 条目和脚本读取
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub job: String,
+    pub token: String,
+    pub phase: String,
+    pub processed: usize,
+    pub total: usize,
+    pub updated_ms: u64,
+}
+
+impl Progress {
+    /// 如 “解析书签 3/7”；总数未知时只显示阶段
+    pub fn describe(&self) -> String {
+        if self.total == 0 {
+            self.phase.clone()
+        } else {
+            format!("{} {}/{}", self.phase, self.processed, self.total)
+        }
+    }
+}
+
+/// 同一任务的新进程启动后会覆盖进度文件中的 token，旧进程下一次汇报进度时据此得知自己已被取代
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Superseded;
+
+pub struct ProgressTracker {
+    path: PathBuf,
+    progress: Progress,
+}
+
+impl ProgressTracker {
+    pub fn start(dir: &Path, job: &str) -> Self {
+        let now = now_ms();
+        let tracker = ProgressTracker {
+            path: progress_path(dir, job),
+            progress: Progress {
+                job: job.to_string(),
+                token: format!("{}-{}", std::process::id(), now),
+                phase: "准备".to_string(),
+                processed: 0,
+                total: 0,
+                updated_ms: now,
+            },
+        };
+        tracker.write();
+        tracker
+    }
+
+    /// 写入新的进度；若已有更新的同类任务接手则返回 `Superseded`，调用方应放弃后续写入
+    pub fn update(
+        &mut self,
+        phase: &str,
+        processed: usize,
+        total: usize,
+    ) -> Result<(), Superseded> {
+        if self.is_superseded() {
+            return Err(Superseded);
+        }
+        self.progress.phase = phase.to_string();
+        self.progress.processed = processed;
+        self.progress.total = total;
+        self.progress.updated_ms = now_ms();
+        self.write();
+        Ok(())
+    }
+
+    pub fn is_superseded(&self) -> bool {
+        read_file(&self.path).is_some_and(|current| current.token != self.progress.token)
+    }
+
+    /// 任务结束：只删除自己写入的进度，避免误删接手任务的进度
+    pub fn finish(self) {
+        if !self.is_superseded() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    fn write(&self) {
+        // 先写临时文件再重命名，读取方不会读到半行
+        let tmp = self.path.with_extension("progress.tmp");
+        if std::fs::write(&tmp, render(&self.progress)).is_ok() {
+            let _ = std::fs::rename(&tmp, &self.path);
+        }
+    }
+}
+
+/// 读取正在进行的任务进度；没有任务或进度已过期时返回 None
+pub fn read_active(dir: &Path, job: &str) -> Option<Progress> {
+    read_file(&progress_path(dir, job))
+        .filter(|progress| now_ms().saturating_sub(progress.updated_ms) <= STALE_AFTER_MS)
+}
+
+fn progress_path(dir: &Path, job: &str) -> PathBuf {
+    dir.join(format!("{}.progress", job))
+}
+
+fn read_file(path: &Path) -> Option<Progress> {
+    parse(&std::fs::read_to_string(path).ok()?)
+}
+
+fn render(progress: &Progress) -> String {
+    format!(
+        "job={}\ntoken={}\nphase={}\nprocessed={}\ntotal={}\nupdated_ms={}\n",
+        progress.job,
+        progress.token,
+        progress.phase,
+        progress.processed,
+        progress.total,
+        progress.updated_ms
+    )
+}
+
+fn parse(content: &str) -> Option<Progress> {
+    let mut progress = Progress {
+        job: String::new(),
+        token: String::new(),
+        phase: String::new(),
+        processed: 0,
+        total: 0,
+        updated_ms: 0,
+    };
+    for line in content.lines() {
+        let (key, value) = line.split_once('=')?;
+        match key {
+            "job" => progress.job = value.to_string(),
+            "token" => progress.token = value.to_string(),
+            "phase" => progress.phase = value.to_string(),
+            "processed" => progress.processed = value.parse().ok()?,
+            "total" => progress.total = value.parse().ok()?,
+            "updated_ms" => progress.updated_ms = value.parse().ok()?,
+            _ => {}
+        }
+    }
+    (!progress.token.is_empty()).then_some(progress)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_round_trips_and_is_removed_on_finish() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut tracker = ProgressTracker::start(dir.path(), JOB_REFRESH);
+        tracker.update("解析书签", 3, 7).expect("update");
+
+        let progress = read_active(dir.path(), JOB_REFRESH).expect("active");
+        assert_eq!(progress.job, JOB_REFRESH);
+        assert_eq!(progress.describe(), "解析书签 3/7");
+
+        tracker.finish();
+        assert!(read_active(dir.path(), JOB_REFRESH).is_none());
+    }
+
+    #[test]
+    fn newer_tracker_supersedes_older_one() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut older = ProgressTracker::start(dir.path(), JOB_REFRESH);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let newer = ProgressTracker::start(dir.path(), JOB_REFRESH);
+
+        assert_eq!(older.update("写入索引", 0, 0), Err(Superseded));
+        older.finish();
+        // 旧任务结束时不能删掉新任务的进度
+        assert_eq!(
+            read_active(dir.path(), JOB_REFRESH).map(|p| p.token),
+            Some(newer.progress.token.clone())
+        );
+    }
+
+    #[test]
+    fn stale_progress_is_ignored() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            progress_path(dir.path(), JOB_CHECK_LINKS),
+            "job=check-links\ntoken=1-1\nphase=检查链接\nprocessed=1\ntotal=9\nupdated_ms=1\n",
+        )
+        .expect("write");
+        assert!(read_active(dir.path(), JOB_CHECK_LINKS).is_none());
+    }
+}
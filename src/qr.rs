@@ -0,0 +1,117 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 二维码边长（像素），足够手机在屏幕上扫描
+const QR_SIZE: u32 = 480;
+
+/// 通过 CoreImage 的 CIQRCodeGenerator 生成 PNG；参数：内容、输出路径、边长
+const QR_SCRIPT: &str = r#"
+ObjC.import('AppKit');
+ObjC.import('CoreImage');
+function run(argv) {
+  var size = parseInt(argv[2], 10);
+  var margin = Math.round(size / 12);
+  var filter = $.CIFilter.filterWithName('CIQRCodeGenerator');
+  filter.setValueForKey($(argv[0]).dataUsingEncoding($.NSUTF8StringEncoding), 'inputMessage');
+  filter.setValueForKey($('M'), 'inputCorrectionLevel');
+  var code = $.NSCIImageRep.imageRepWithCIImage(filter.outputImage);
+
+  var bitmap = $.NSBitmapImageRep.alloc.initWithBitmapDataPlanesPixelsWidePixelsHighBitsPerSampleSamplesPerPixelHasAlphaIsPlanarColorSpaceNameBytesPerRowBitsPerPixel(
+    null, size, size, 8, 4, true, false, $.NSDeviceRGBColorSpace, 0, 0);
+  $.NSGraphicsContext.saveGraphicsState;
+  var context = $.NSGraphicsContext.graphicsContextWithBitmapImageRep(bitmap);
+  context.imageInterpolation = $.NSImageInterpolationNone;
+  $.NSGraphicsContext.currentContext = context;
+  $.NSColor.whiteColor.setFill;
+  $.NSRectFill($.NSMakeRect(0, 0, size, size));
+  code.drawInRect($.NSMakeRect(margin, margin, size - 2 * margin, size - 2 * margin));
+  $.NSGraphicsContext.restoreGraphicsState;
+
+  var png = bitmap.representationUsingTypeProperties($.NSBitmapImageFileTypePNG, $());
+  if (!png.writeToFileAtomically($(argv[1]), true)) {
+    throw new Error('write failed');
+  }
+}
+"#;
+
+/// 二维码缓存：`<缓存目录>/qr/<URL 哈希>.png`，同一 URL 只生成一次
+pub struct QrCache {
+    dir: PathBuf,
+}
+
+impl QrCache {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            dir: cache_dir.join("qr"),
+        }
+    }
+
+    pub fn png_path(&self, url: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        url.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.png", hasher.finish()))
+    }
+
+    /// 返回 URL 对应的二维码图片，缓存中没有时调用 osascript 生成
+    pub fn render(&self, url: &str) -> Result<PathBuf, String> {
+        let path = self.png_path(url);
+        if is_non_empty_file(&path) {
+            return Ok(path);
+        }
+        std::fs::create_dir_all(&self.dir).map_err(|err| err.to_string())?;
+
+        let output = Command::new("osascript")
+            .arg("-l")
+            .arg("JavaScript")
+            .arg("-e")
+            .arg(QR_SCRIPT)
+            .arg(url)
+            .arg(&path)
+            .arg(QR_SIZE.to_string())
+            .output()
+            .map_err(|err| err.to_string())?;
+        if !output.status.success() {
+            let _ = std::fs::remove_file(&path);
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        if !is_non_empty_file(&path) {
+            return Err("二维码图片为空".to_string());
+        }
+        Ok(path)
+    }
+}
+
+fn is_non_empty_file(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.len() > 0)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn png_path_is_stable_per_url() {
+        let dir = tempdir().expect("tempdir");
+        let cache = QrCache::new(dir.path());
+        let docs = cache.png_path("https://docs.rs");
+        assert_eq!(docs, cache.png_path("https://docs.rs"));
+        assert_ne!(docs, cache.png_path("https://docs.rs/tokio"));
+        assert!(docs.starts_with(dir.path().join("qr")));
+        assert_eq!(docs.extension().and_then(|ext| ext.to_str()), Some("png"));
+    }
+
+    #[test]
+    fn cached_png_is_reused_without_rendering() {
+        let dir = tempdir().expect("tempdir");
+        let cache = QrCache::new(dir.path());
+        let path = cache.png_path("https://docs.rs");
+        std::fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+        std::fs::write(&path, b"png").expect("write");
+        assert_eq!(cache.render("https://docs.rs").expect("render"), path);
+    }
+}
@@ -0,0 +1,378 @@
+use crate::bookmark::ChromeBookmark;
+use crate::tokenize::words;
+
+/// 过滤条件之外的搜索关键词，解析为表达式树
+///
+/// 空白分隔的词全部需要命中；`a OR b` 命中其一即可，括号用于分组（`rust (async OR tokio)`），
+/// OR 的优先级低于空格。`-词` 为排除词，只作用于所在的组：`(rust -game) OR go` 中的 `-game` 不影响 `go`。
+/// 双引号括起的短语（`"rust async book"`）作为一个整体连续匹配，也可以排除（`-"game jam"`）；
+/// 引号未闭合时短语延续到查询末尾，引号内只有一个词时与不加引号相同。
+/// 单独的 `-`、小写的 `or` 与不成对的括号按普通写法处理：`-` 忽略，`or` 是普通关键词，
+/// 多余的 `)` 保留在词中（`foo(bar)` 不会被拆开）；`rust-lang` 这类中间带连字符的词仍是普通关键词
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryExpr {
+    /// 关键词，保持原样（模糊匹配区分大小写与否交给匹配算法）；短语内的空白统一为单个空格，见 `is_phrase`
+    Term(String),
+    /// 排除词，已去掉开头的 `-` 并转为小写，见 `excluded_by`
+    Exclude(String),
+    /// 全部需要命中；其中不会再直接嵌套 `And`
+    And(Vec<QueryExpr>),
+    /// 命中其一即可；其中不会再直接嵌套 `Or`
+    Or(Vec<QueryExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Or,
+    Word(String),
+    Exclude(String),
+}
+
+impl QueryExpr {
+    /// 没有任何关键词与排除词时为 None
+    pub fn parse(query: &str) -> Option<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(query),
+            pos: 0,
+            depth: 0,
+        };
+        parser.parse_or()
+    }
+
+    /// 关键词全部转为小写的副本，供精确匹配使用
+    pub fn lowercased(&self) -> Self {
+        match self {
+            QueryExpr::Term(term) => QueryExpr::Term(term.to_lowercase()),
+            QueryExpr::Exclude(word) => QueryExpr::Exclude(word.clone()),
+            QueryExpr::And(items) => QueryExpr::And(items.iter().map(Self::lowercased).collect()),
+            QueryExpr::Or(items) => QueryExpr::Or(items.iter().map(Self::lowercased).collect()),
+        }
+    }
+
+    /// 树中全部关键词（不含排除词），按出现顺序
+    pub fn terms(&self) -> Vec<&str> {
+        match self {
+            QueryExpr::Term(term) => vec![term.as_str()],
+            QueryExpr::Exclude(_) => Vec::new(),
+            QueryExpr::And(items) | QueryExpr::Or(items) => {
+                items.iter().flat_map(Self::terms).collect()
+            }
+        }
+    }
+}
+
+/// 书签名称、URL 或目录中有以排除词 `word` 开头的词（`-game` 排除 “Games”，不排除 “Endgame”）；
+/// 排除词本身含多个词（短语、`rust-lang`）时须依次相邻出现，短语的最后一个词也须完整。
+/// 与书签 FTS 表中排除词的写法（`"…"*`）一致，内存搜索与索引搜索的结果相同
+pub fn excluded_by(bookmark: &ChromeBookmark, word: &str) -> bool {
+    let needle: Vec<String> = words(&word.to_lowercase()).collect();
+    let Some((last, init)) = needle.split_last() else {
+        return false;
+    };
+    let phrase = is_phrase(word);
+    let matches = |text: &str| {
+        let haystack: Vec<String> = words(text).collect();
+        haystack.windows(needle.len()).any(|window| {
+            window[..init.len()] == *init
+                && if phrase {
+                    window[init.len()] == *last
+                } else {
+                    window[init.len()].starts_with(last.as_str())
+                }
+        })
+    };
+    matches(&bookmark.lower_name())
+        || matches(&bookmark.lower_url())
+        || bookmark
+            .lower_folder_path()
+            .is_some_and(|folder| matches(&folder))
+}
+
+/// 关键词是否为引号括起的短语：只有短语含空白
+pub fn is_phrase(term: &str) -> bool {
+    term.contains(char::is_whitespace)
+}
+
+/// 按空白切分，双引号内的空白不切分
+fn split_quoted(query: &str) -> Vec<&str> {
+    let mut raws = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    for (idx, c) in query.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        if c.is_whitespace() && !quoted {
+            if let Some(begin) = start.take() {
+                raws.push(&query[begin..idx]);
+            }
+        } else if start.is_none() {
+            start = Some(idx);
+        }
+    }
+    if let Some(begin) = start {
+        raws.push(&query[begin..]);
+    }
+    raws
+}
+
+/// 按空白分词（引号内除外），再剥出词首的 `(` 与词尾能配对的 `)`
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    for raw in split_quoted(query) {
+        let mut word = raw;
+        while let Some(rest) = word.strip_prefix('(') {
+            tokens.push(Token::Open);
+            depth += 1;
+            word = rest;
+        }
+        let mut closes = 0;
+        while closes < depth {
+            match word.strip_suffix(')') {
+                Some(rest) => {
+                    word = rest;
+                    closes += 1;
+                }
+                None => break,
+            }
+        }
+
+        let (negated, term) = match word.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, word),
+        };
+        let term = match term.strip_prefix('"') {
+            Some(phrase) => phrase
+                .strip_suffix('"')
+                .unwrap_or(phrase)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            None if !negated && term == "OR" => {
+                tokens.push(Token::Or);
+                String::new()
+            }
+            None => term.to_string(),
+        };
+        if !term.is_empty() {
+            tokens.push(if negated {
+                Token::Exclude(term.to_lowercase())
+            } else {
+                Token::Word(term)
+            });
+        }
+        tokens.extend(std::iter::repeat_n(Token::Close, closes));
+        depth -= closes;
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    /// `and_expr (OR and_expr)*`；空的分支（如 `rust OR`）忽略，全部为空时为 None
+    fn parse_or(&mut self) -> Option<QueryExpr> {
+        let mut branches = Vec::new();
+        loop {
+            match self.parse_and() {
+                Some(QueryExpr::Or(inner)) => branches.extend(inner),
+                Some(branch) => branches.push(branch),
+                None => {}
+            }
+            if self.tokens.get(self.pos) != Some(&Token::Or) {
+                break;
+            }
+            self.pos += 1;
+        }
+        collapse(branches, QueryExpr::Or)
+    }
+
+    /// 相邻的词、排除词与括号组；括号内的排除词随括号组留在原处，只约束该组
+    fn parse_and(&mut self) -> Option<QueryExpr> {
+        let mut items = Vec::new();
+        while let Some(token) = self.tokens.get(self.pos).cloned() {
+            match token {
+                Token::Or => break,
+                Token::Close if self.depth > 0 => break,
+                Token::Close => self.pos += 1,
+                Token::Exclude(word) => {
+                    self.pos += 1;
+                    items.push(QueryExpr::Exclude(word));
+                }
+                Token::Word(word) => {
+                    self.pos += 1;
+                    items.push(QueryExpr::Term(word));
+                }
+                Token::Open => {
+                    self.pos += 1;
+                    self.depth += 1;
+                    let inner = self.parse_or();
+                    self.depth -= 1;
+                    if self.tokens.get(self.pos) == Some(&Token::Close) {
+                        self.pos += 1;
+                    }
+                    match inner {
+                        Some(QueryExpr::And(inner)) => items.extend(inner),
+                        Some(inner) => items.push(inner),
+                        None => {}
+                    }
+                }
+            }
+        }
+        collapse(items, QueryExpr::And)
+    }
+}
+
+/// 没有子项时为 None，只有一项时不再包一层
+fn collapse(mut items: Vec<QueryExpr>, wrap: fn(Vec<QueryExpr>) -> QueryExpr) -> Option<QueryExpr> {
+    match items.len() {
+        0 => None,
+        1 => items.pop(),
+        _ => Some(wrap(items)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+
+    fn term(word: &str) -> QueryExpr {
+        QueryExpr::Term(word.to_string())
+    }
+
+    fn exclude(word: &str) -> QueryExpr {
+        QueryExpr::Exclude(word.to_string())
+    }
+
+    fn and(items: &[QueryExpr]) -> QueryExpr {
+        QueryExpr::And(items.to_vec())
+    }
+
+    fn or(items: &[QueryExpr]) -> QueryExpr {
+        QueryExpr::Or(items.to_vec())
+    }
+
+    fn parse(query: &str) -> Option<QueryExpr> {
+        QueryExpr::parse(query)
+    }
+
+    #[test]
+    fn minus_prefixed_words_are_exclusions() {
+        assert_eq!(
+            parse("Rust -Game  rust-lang - -"),
+            Some(and(&[term("Rust"), exclude("game"), term("rust-lang")]))
+        );
+        assert_eq!(parse("-game"), Some(exclude("game")));
+
+        let bookmark = test_bookmark("1", "Rust", "https://example.com", Some("Games/Indie"));
+        assert!(excluded_by(&bookmark, "game"));
+        assert!(!excluded_by(&bookmark, "book"));
+    }
+
+    #[test]
+    fn exclusions_match_word_prefixes() {
+        let bookmark = test_bookmark(
+            "1",
+            "Endgame: Rust-lang game jam",
+            "https://example.com/play",
+            None,
+        );
+        assert!(excluded_by(&bookmark, "gam"));
+        assert!(excluded_by(&bookmark, "rust-l"));
+        assert!(excluded_by(&bookmark, "game jam"));
+        assert!(excluded_by(&bookmark, "pla"));
+        assert!(!excluded_by(&bookmark, "dgame"));
+        assert!(!excluded_by(&bookmark, "game ja"));
+        assert!(!excluded_by(&bookmark, "lang-rust"));
+        assert!(!excluded_by(&bookmark, "--"));
+    }
+
+    #[test]
+    fn quoted_phrases_stay_whole() {
+        let expr = parse("\"Rust  async book\" (\"OR\" OR -\"game jam\") \"tokio\" \"\"").unwrap();
+        assert_eq!(
+            expr,
+            and(&[
+                term("Rust async book"),
+                or(&[term("OR"), exclude("game jam")]),
+                term("tokio")
+            ])
+        );
+        assert_eq!(expr.terms(), ["Rust async book", "OR", "tokio"]);
+        assert!(is_phrase("Rust async book"));
+        assert!(!is_phrase("tokio"));
+
+        assert_eq!(
+            parse("(\"open source\" OR oss)"),
+            Some(or(&[term("open source"), term("oss")]))
+        );
+        assert_eq!(parse("\"unclosed phrase"), Some(term("unclosed phrase")));
+    }
+
+    #[test]
+    fn or_and_parentheses_build_a_tree() {
+        assert_eq!(parse("rust OR go"), Some(or(&[term("rust"), term("go")])));
+        assert_eq!(
+            parse("rust (async OR tokio) book"),
+            Some(and(&[
+                term("rust"),
+                or(&[term("async"), term("tokio")]),
+                term("book")
+            ]))
+        );
+        assert_eq!(
+            parse("(a OR b) (c OR (d e))"),
+            Some(and(&[
+                or(&[term("a"), term("b")]),
+                or(&[term("c"), and(&[term("d"), term("e")])])
+            ]))
+        );
+        assert_eq!(
+            parse("(a b) c OR (d OR e)"),
+            Some(or(&[
+                and(&[term("a"), term("b"), term("c")]),
+                term("d"),
+                term("e")
+            ]))
+        );
+        assert_eq!(
+            parse("rust or go"),
+            Some(and(&[term("rust"), term("or"), term("go")]))
+        );
+        assert_eq!(parse("OR rust OR"), Some(term("rust")));
+        assert_eq!(parse("(rust go"), Some(and(&[term("rust"), term("go")])));
+        assert_eq!(
+            parse("foo(bar) x)"),
+            Some(and(&[term("foo(bar)"), term("x)")]))
+        );
+        assert_eq!(parse("( ) OR"), None);
+
+        // 组合再多也不会展开或截断
+        let many = parse("(a OR b) (c OR d) (e OR f) (g OR h) (i OR j)").unwrap();
+        assert_eq!(many.terms().len(), 10);
+    }
+
+    #[test]
+    fn exclusions_stay_in_their_group() {
+        assert_eq!(
+            parse("(rust -game) OR go"),
+            Some(or(&[and(&[term("rust"), exclude("game")]), term("go")]))
+        );
+        assert_eq!(
+            parse("go OR tokio -rs"),
+            Some(or(&[term("go"), and(&[term("tokio"), exclude("rs")])]))
+        );
+        assert_eq!(
+            parse("(Rust OR go) -Game").map(|expr| expr.lowercased()),
+            Some(and(&[or(&[term("rust"), term("go")]), exclude("game")]))
+        );
+    }
+}
@@ -0,0 +1,942 @@
+use crate::bookmark::{date_added_to_unix_secs, ChromeBookmark};
+use crate::link_check::LinkCheckResult;
+use crate::report_render::{Exportable, ReportTable};
+use crate::tags::tag_key;
+use crate::url_normalize::url_host;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// 每个分类最多列出的条目数，避免报告过长
+const SECTION_ITEM_LIMIT: usize = 200;
+
+/// 书签整理报告：重复、失效、长期未整理、未打标签
+#[derive(Serialize)]
+pub struct CleanupReport<'a> {
+    pub total: usize,
+    pub generated_at: i64,
+    pub duplicates: Vec<Vec<&'a ChromeBookmark>>,
+    pub dead: Vec<(&'a ChromeBookmark, &'a LinkCheckResult)>,
+    pub stale: Vec<&'a ChromeBookmark>,
+    pub stale_days: u64,
+    /// None 表示尚未使用任何标签，此时不单独列出
+    pub untagged: Option<Vec<&'a ChromeBookmark>>,
+}
+
+impl<'a> CleanupReport<'a> {
+    pub fn build(
+        bookmarks: &'a [ChromeBookmark],
+        duplicate_groups: &[Vec<usize>],
+        link_results: &'a [LinkCheckResult],
+        tagged_keys: &HashSet<String>,
+        stale_days: u64,
+        now_secs: i64,
+    ) -> Self {
+        let duplicates = duplicate_groups
+            .iter()
+            .map(|group| group.iter().map(|&idx| &bookmarks[idx]).collect())
+            .collect();
+
+        let dead_by_url: HashMap<&str, &LinkCheckResult> = link_results
+            .iter()
+            .filter(|result| result.is_dead())
+            .map(|result| (result.url.as_str(), result))
+            .collect();
+        let dead = bookmarks
+            .iter()
+            .filter_map(|bookmark| {
+                dead_by_url
+                    .get(bookmark.url.as_str())
+                    .map(|result| (bookmark, *result))
+            })
+            .collect();
+
+        let cutoff = now_secs.saturating_sub(stale_days as i64 * 86_400);
+        let stale = bookmarks
+            .iter()
+            .filter(|bookmark| {
+                date_added_to_unix_secs(&bookmark.date_added).is_some_and(|added| added < cutoff)
+            })
+            .collect();
+
+        let untagged = if tagged_keys.is_empty() {
+            None
+        } else {
+            Some(
+                bookmarks
+                    .iter()
+                    .filter(|bookmark| !tagged_keys.contains(&tag_key(bookmark)))
+                    .collect(),
+            )
+        };
+
+        Self {
+            total: bookmarks.len(),
+            generated_at: now_secs,
+            duplicates,
+            dead,
+            stale,
+            stale_days,
+            untagged,
+        }
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# 书签整理报告");
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "- 生成日期：{}\n- 书签总数：{}",
+            format_date(self.generated_at),
+            self.total
+        );
+
+        let _ = writeln!(out, "\n## 重复书签（{} 组）\n", self.duplicates.len());
+        for group in self.duplicates.iter().take(SECTION_ITEM_LIMIT) {
+            let _ = writeln!(out, "- `{}`", group[0].url);
+            for bookmark in group {
+                let _ = writeln!(
+                    out,
+                    "  - {} — {}",
+                    escape_markdown(&bookmark.name),
+                    bookmark.folder_path.as_deref().unwrap_or("未分类")
+                );
+            }
+        }
+        write_overflow(&mut out, self.duplicates.len());
+
+        let _ = writeln!(out, "\n## 失效链接（{} 条）\n", self.dead.len());
+        for (bookmark, result) in self.dead.iter().take(SECTION_ITEM_LIMIT) {
+            let reason = match &result.error {
+                Some(err) => err.clone(),
+                None => format!("HTTP {}", result.status),
+            };
+            let _ = writeln!(
+                out,
+                "- {} — {}（{}）",
+                markdown_link(bookmark),
+                reason,
+                format_date(result.checked_at as i64)
+            );
+        }
+        write_overflow(&mut out, self.dead.len());
+
+        let _ = writeln!(
+            out,
+            "\n## 长期未整理（添加超过 {} 天，{} 条）\n",
+            self.stale_days,
+            self.stale.len()
+        );
+        for bookmark in self.stale.iter().take(SECTION_ITEM_LIMIT) {
+            let added = date_added_to_unix_secs(&bookmark.date_added).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "- {} — 添加于 {}",
+                markdown_link(bookmark),
+                format_date(added)
+            );
+        }
+        write_overflow(&mut out, self.stale.len());
+
+        match &self.untagged {
+            Some(untagged) => {
+                let _ = writeln!(out, "\n## 未打标签（{} 条）\n", untagged.len());
+                for bookmark in untagged.iter().take(SECTION_ITEM_LIMIT) {
+                    let _ = writeln!(out, "- {}", markdown_link(bookmark));
+                }
+                write_overflow(&mut out, untagged.len());
+            }
+            None => {
+                let _ = writeln!(out, "\n## 未打标签\n\n尚未使用标签。");
+            }
+        }
+
+        out
+    }
+}
+
+impl Exportable for CleanupReport<'_> {
+    fn summary(&self) -> String {
+        format!(
+            "整理报告（重复 {} 组 / 失效 {} / 长期未整理 {}）",
+            self.duplicates.len(),
+            self.dead.len(),
+            self.stale.len()
+        )
+    }
+
+    /// 每个问题书签一行，`section` 标明所属分类；CSV 不截断
+    fn table(&self) -> ReportTable {
+        let row = |section: &str, bookmark: &ChromeBookmark, detail: String| {
+            vec![
+                section.to_string(),
+                bookmark.name.clone(),
+                bookmark.url.clone(),
+                bookmark.folder_path.clone().unwrap_or_default(),
+                detail,
+            ]
+        };
+        let mut rows = Vec::new();
+        for (group, bookmarks) in self.duplicates.iter().enumerate() {
+            for bookmark in bookmarks {
+                rows.push(row("duplicate", bookmark, format!("组 {}", group + 1)));
+            }
+        }
+        for (bookmark, result) in &self.dead {
+            let reason = match &result.error {
+                Some(err) => err.clone(),
+                None => format!("HTTP {}", result.status),
+            };
+            rows.push(row("dead", bookmark, reason));
+        }
+        for bookmark in &self.stale {
+            let added = date_added_to_unix_secs(&bookmark.date_added).unwrap_or_default();
+            rows.push(row("stale", bookmark, format_date(added)));
+        }
+        for bookmark in self.untagged.iter().flatten() {
+            rows.push(row("untagged", bookmark, String::new()));
+        }
+        ReportTable {
+            title: "书签整理报告".to_string(),
+            notes: vec![
+                format!("生成日期：{}", format_date(self.generated_at)),
+                format!("书签总数：{}", self.total),
+            ],
+            columns: vec!["section", "name", "url", "folder", "detail"],
+            rows,
+        }
+    }
+
+    fn markdown(&self) -> String {
+        self.render_markdown()
+    }
+}
+
+fn write_overflow(out: &mut String, total: usize) {
+    if total > SECTION_ITEM_LIMIT {
+        let _ = writeln!(out, "- …… 还有 {} 条", total - SECTION_ITEM_LIMIT);
+    }
+}
+
+fn markdown_link(bookmark: &ChromeBookmark) -> String {
+    format!(
+        "[{}]({})",
+        escape_markdown(&bookmark.name),
+        bookmark.url.replace(' ', "%20").replace(')', "%29")
+    )
+}
+
+fn escape_markdown(value: &str) -> String {
+    value.replace('[', "\\[").replace(']', "\\]")
+}
+
+/// 按 `key` 统计书签数：数量降序，同数按名称排序；`key` 为 None 的书签不计入
+pub fn count_by<F: Fn(&ChromeBookmark) -> Option<String>>(
+    bookmarks: &[ChromeBookmark],
+    key: F,
+) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for bookmark in bookmarks {
+        if let Some(key) = key(bookmark) {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// `report domains` 的一行
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DomainCount {
+    pub domain: String,
+    pub count: usize,
+    /// 占全部书签的百分比，保留一位小数
+    pub percent: f64,
+}
+
+/// 书签最多的站点
+#[derive(Debug, Serialize)]
+pub struct DomainReport {
+    /// 书签总数（含非网页书签）
+    pub total: usize,
+    /// 出现过的站点数
+    pub domain_total: usize,
+    pub domains: Vec<DomainCount>,
+}
+
+impl DomainReport {
+    pub fn build(bookmarks: &[ChromeBookmark], limit: usize) -> Self {
+        let counts = count_by(bookmarks, bookmark_domain);
+        let total = bookmarks.len();
+        Self {
+            total,
+            domain_total: counts.len(),
+            domains: counts
+                .into_iter()
+                .take(limit)
+                .map(|(domain, count)| DomainCount {
+                    domain,
+                    count,
+                    percent: (count * 1_000 / total.max(1)) as f64 / 10.0,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 待整理的书签：没有本地标签，和 / 或直接放在根目录（没有子目录）
+#[derive(Debug, Serialize)]
+pub struct UntaggedEntry<'a> {
+    pub bookmark: &'a ChromeBookmark,
+    pub untagged: bool,
+    pub at_root: bool,
+}
+
+/// `report untagged`：两项都缺的排在前面，其余保持索引顺序
+#[derive(Debug, Serialize)]
+pub struct UntaggedReport<'a> {
+    pub total: usize,
+    pub untagged: usize,
+    pub at_root: usize,
+    pub entries: Vec<UntaggedEntry<'a>>,
+}
+
+impl<'a> UntaggedReport<'a> {
+    pub fn build(
+        bookmarks: &'a [ChromeBookmark],
+        tagged_keys: &HashSet<String>,
+        limit: usize,
+    ) -> Self {
+        let mut entries: Vec<UntaggedEntry> = bookmarks
+            .iter()
+            .map(|bookmark| UntaggedEntry {
+                bookmark,
+                untagged: !tagged_keys.contains(&tag_key(bookmark)),
+                at_root: bookmark
+                    .folder_path
+                    .as_deref()
+                    .is_none_or(|folder| !folder.contains('/')),
+            })
+            .filter(|entry| entry.untagged || entry.at_root)
+            .collect();
+        let untagged = entries.iter().filter(|entry| entry.untagged).count();
+        let at_root = entries.iter().filter(|entry| entry.at_root).count();
+        entries.sort_by_key(|entry| !(entry.untagged && entry.at_root));
+        entries.truncate(limit);
+        Self {
+            total: bookmarks.len(),
+            untagged,
+            at_root,
+            entries,
+        }
+    }
+}
+
+impl Exportable for DomainReport {
+    fn summary(&self) -> String {
+        format!("站点报告（{} 个站点）", self.domains.len())
+    }
+
+    fn table(&self) -> ReportTable {
+        ReportTable {
+            title: format!("书签最多的 {} 个站点", self.domains.len()),
+            notes: vec![format!(
+                "共 {} 个书签，分布在 {} 个站点",
+                self.total, self.domain_total
+            )],
+            columns: vec!["domain", "count", "percent"],
+            rows: self
+                .domains
+                .iter()
+                .map(|row| {
+                    vec![
+                        row.domain.clone(),
+                        row.count.to_string(),
+                        format!("{:.1}", row.percent),
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Exportable for UntaggedReport<'_> {
+    fn summary(&self) -> String {
+        format!(
+            "待整理书签报告（无标签 {} / 根目录 {}）",
+            self.untagged, self.at_root
+        )
+    }
+
+    fn table(&self) -> ReportTable {
+        let flag = |value: bool| if value { "yes" } else { "no" }.to_string();
+        ReportTable {
+            title: "没有标签或直接放在根目录的书签".to_string(),
+            notes: vec![format!(
+                "共 {} 个书签：{} 个没有标签，{} 个直接放在根目录",
+                self.total, self.untagged, self.at_root
+            )],
+            columns: vec!["name", "url", "folder", "untagged", "at_root"],
+            rows: self
+                .entries
+                .iter()
+                .map(|entry| {
+                    vec![
+                        entry.bookmark.name.clone(),
+                        entry.bookmark.url.clone(),
+                        entry.bookmark.folder_path.clone().unwrap_or_default(),
+                        flag(entry.untagged),
+                        flag(entry.at_root),
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 增长报告的统计粒度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrowthPeriod {
+    Month,
+    Year,
+}
+
+impl std::str::FromStr for GrowthPeriod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "month" => Ok(GrowthPeriod::Month),
+            "year" => Ok(GrowthPeriod::Year),
+            other => Err(format!("未知粒度: {}（可选 month、year）", other)),
+        }
+    }
+}
+
+/// 一个月 / 年新增的书签数与截至该期的累计数
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct GrowthBucket {
+    /// `YYYY-MM` 或 `YYYY`
+    pub period: String,
+    pub count: usize,
+    pub cumulative: usize,
+}
+
+/// 按添加时间统计的书签增长；首末两期之间没有新增的月份 / 年份也会列出
+#[derive(Debug, Serialize)]
+pub struct GrowthReport {
+    pub by: &'static str,
+    pub total: usize,
+    /// 没有可用添加时间的书签数
+    pub undated: usize,
+    pub buckets: Vec<GrowthBucket>,
+}
+
+/// 文本直方图的最大宽度（字符）
+const HISTOGRAM_WIDTH: usize = 40;
+
+impl GrowthReport {
+    pub fn build(bookmarks: &[ChromeBookmark], by: GrowthPeriod) -> Self {
+        // 期序号：年 * 12 + 月序（按年统计时只用年）
+        let mut counts: HashMap<i64, usize> = HashMap::new();
+        let mut undated = 0;
+        for bookmark in bookmarks {
+            let Some(secs) = date_added_to_unix_secs(&bookmark.date_added) else {
+                undated += 1;
+                continue;
+            };
+            let (year, month, _) = civil_from_days(secs.div_euclid(86_400));
+            let key = match by {
+                GrowthPeriod::Month => year * 12 + i64::from(month) - 1,
+                GrowthPeriod::Year => year,
+            };
+            *counts.entry(key).or_default() += 1;
+        }
+
+        let mut buckets = Vec::new();
+        if let (Some(&first), Some(&last)) = (counts.keys().min(), counts.keys().max()) {
+            let mut cumulative = 0;
+            for key in first..=last {
+                let count = counts.get(&key).copied().unwrap_or(0);
+                cumulative += count;
+                buckets.push(GrowthBucket {
+                    period: match by {
+                        GrowthPeriod::Month => {
+                            format!("{:04}-{:02}", key.div_euclid(12), key.rem_euclid(12) + 1)
+                        }
+                        GrowthPeriod::Year => format!("{:04}", key),
+                    },
+                    count,
+                    cumulative,
+                });
+            }
+        }
+
+        Self {
+            by: match by {
+                GrowthPeriod::Month => "month",
+                GrowthPeriod::Year => "year",
+            },
+            total: bookmarks.len(),
+            undated,
+            buckets,
+        }
+    }
+
+    /// 文本直方图：每期一行，条形按最多的一期缩放到 `HISTOGRAM_WIDTH`
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "书签增长（按{}，共 {} 个，{} 个没有添加时间）",
+            if self.by == "year" { "年" } else { "月" },
+            self.total,
+            self.undated
+        );
+        let max = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.count)
+            .max()
+            .unwrap_or(0);
+        for bucket in &self.buckets {
+            let width = if bucket.count == 0 {
+                0
+            } else {
+                (bucket.count * HISTOGRAM_WIDTH / max).max(1)
+            };
+            let _ = writeln!(
+                out,
+                "{:<7}  {:<bar$}  {:>5}  (累计 {})",
+                bucket.period,
+                "█".repeat(width),
+                bucket.count,
+                bucket.cumulative,
+                bar = HISTOGRAM_WIDTH
+            );
+        }
+        out
+    }
+}
+
+impl Exportable for GrowthReport {
+    fn summary(&self) -> String {
+        format!("增长报告（{} 期）", self.buckets.len())
+    }
+
+    fn table(&self) -> ReportTable {
+        ReportTable {
+            title: format!(
+                "书签增长（按{}）",
+                if self.by == "year" { "年" } else { "月" }
+            ),
+            notes: vec![format!(
+                "共 {} 个书签，{} 个没有添加时间",
+                self.total, self.undated
+            )],
+            columns: vec!["period", "count", "cumulative"],
+            rows: self
+                .buckets
+                .iter()
+                .map(|bucket| {
+                    vec![
+                        bucket.period.clone(),
+                        bucket.count.to_string(),
+                        bucket.cumulative.to_string(),
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 目录树中的一个节点，计数包含更深层子目录中的书签
+#[derive(Debug, Serialize)]
+pub struct FolderNode {
+    pub path: String,
+    pub name: String,
+    /// 根目录为 1
+    pub depth: usize,
+    pub count: usize,
+}
+
+/// `report folders`：按目录子树统计书签数，只展开到 `depth` 层
+#[derive(Debug, Serialize)]
+pub struct FolderReport {
+    pub total: usize,
+    /// 不在任何目录中的书签数
+    pub unfiled: usize,
+    pub depth: usize,
+    /// 树的先序遍历，同级按书签数降序
+    pub folders: Vec<FolderNode>,
+}
+
+impl FolderReport {
+    pub fn build(bookmarks: &[ChromeBookmark], depth: usize) -> Self {
+        let depth = depth.max(1);
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        let mut unfiled = 0;
+        for bookmark in bookmarks {
+            let Some(folder) = bookmark.folder_path.as_deref().filter(|f| !f.is_empty()) else {
+                unfiled += 1;
+                continue;
+            };
+            let segments: Vec<&str> = folder.split('/').collect();
+            for level in 1..=segments.len().min(depth) {
+                *counts.entry(segments[..level].join("/")).or_default() += 1;
+            }
+        }
+
+        let mut children: HashMap<&str, Vec<(&str, usize)>> = HashMap::new();
+        for (path, &count) in &counts {
+            let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+            children.entry(parent).or_default().push((path, count));
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        }
+
+        let mut folders = Vec::with_capacity(counts.len());
+        let mut stack: Vec<(&str, usize)> = children
+            .get("")
+            .map(|roots| roots.iter().rev().map(|&(path, _)| (path, 1)).collect())
+            .unwrap_or_default();
+        while let Some((path, level)) = stack.pop() {
+            folders.push(FolderNode {
+                path: path.to_string(),
+                name: path.rsplit('/').next().unwrap_or(path).to_string(),
+                depth: level,
+                count: counts[path],
+            });
+            if let Some(nested) = children.get(path) {
+                stack.extend(nested.iter().rev().map(|&(child, _)| (child, level + 1)));
+            }
+        }
+
+        Self {
+            total: bookmarks.len(),
+            unfiled,
+            depth,
+            folders,
+        }
+    }
+
+    /// 缩进的条形图：每层缩进两格，条形按最大的根目录缩放到 `HISTOGRAM_WIDTH`
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "书签目录分布（展开 {} 层，共 {} 个，{} 个不在目录中）",
+            self.depth, self.total, self.unfiled
+        );
+        let labels: Vec<String> = self
+            .folders
+            .iter()
+            .map(|node| format!("{}{}", "  ".repeat(node.depth - 1), node.name))
+            .collect();
+        let label_width = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let max = self
+            .folders
+            .iter()
+            .map(|node| node.count)
+            .max()
+            .unwrap_or(0);
+        for (node, label) in self.folders.iter().zip(&labels) {
+            let width = (node.count * HISTOGRAM_WIDTH / max.max(1)).max(1);
+            let _ = writeln!(
+                out,
+                "{}{}  {:<bar$}  {:>5}  {:>5.1}%",
+                label,
+                " ".repeat(label_width - label.chars().count()),
+                "█".repeat(width),
+                node.count,
+                node.count as f64 * 100.0 / self.total.max(1) as f64,
+                bar = HISTOGRAM_WIDTH
+            );
+        }
+        out
+    }
+}
+
+impl Exportable for FolderReport {
+    fn summary(&self) -> String {
+        format!("目录分布报告（{} 个目录）", self.folders.len())
+    }
+
+    fn table(&self) -> ReportTable {
+        ReportTable {
+            title: format!("书签目录分布（展开 {} 层）", self.depth),
+            notes: vec![format!(
+                "共 {} 个书签，{} 个不在目录中",
+                self.total, self.unfiled
+            )],
+            columns: vec!["folder", "depth", "count", "percent"],
+            rows: self
+                .folders
+                .iter()
+                .map(|node| {
+                    vec![
+                        node.path.clone(),
+                        node.depth.to_string(),
+                        node.count.to_string(),
+                        format!(
+                            "{:.1}",
+                            node.count as f64 * 100.0 / self.total.max(1) as f64
+                        ),
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 网页书签所在站点：小写主机名，去掉 `www.`
+pub fn bookmark_domain(bookmark: &ChromeBookmark) -> Option<String> {
+    if !bookmark.url.starts_with("http://") && !bookmark.url.starts_with("https://") {
+        return None;
+    }
+    let host = url_host(&bookmark.url)?;
+    Some(match host.strip_prefix("www.") {
+        Some(stripped) => stripped.to_string(),
+        None => host,
+    })
+}
+
+/// Unix 秒转为 `YYYY-MM-DD`（UTC），避免为日期格式化引入额外依赖
+pub fn format_date(unix_secs: i64) -> String {
+    let (year, month, day) = civil_from_days(unix_secs.div_euclid(86_400));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Unix 秒转为 `YYYY-MM-DD HH:MM`（UTC）
+pub fn format_datetime(unix_secs: i64) -> String {
+    let secs_of_day = unix_secs.rem_euclid(86_400);
+    format!(
+        "{} {:02}:{:02}",
+        format_date(unix_secs),
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60
+    )
+}
+
+/// Howard Hinnant 的 days-from-civil 逆算法
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+    use crate::link_check::RedirectKind;
+
+    fn bookmark(id: &str, url: &str, date_added: &str) -> ChromeBookmark {
+        ChromeBookmark {
+            date_added: date_added.to_string(),
+            guid: Some(id.to_string()),
+            ..test_bookmark(id, &format!("Title {}", id), url, Some("书签栏/Work"))
+        }
+    }
+
+    #[test]
+    fn counts_domains_and_folders_by_frequency() {
+        let mut bookmarks = vec![
+            bookmark("1", "https://www.docs.rs/a", "0"),
+            bookmark("2", "https://docs.rs/b", "0"),
+            bookmark("3", "http://go.dev", "0"),
+            bookmark("4", "chrome://settings", "0"),
+        ];
+        bookmarks[3].folder_path = None;
+
+        assert_eq!(
+            count_by(&bookmarks, bookmark_domain),
+            vec![("docs.rs".to_string(), 2), ("go.dev".to_string(), 1)]
+        );
+        assert_eq!(
+            count_by(&bookmarks, |bookmark| bookmark.folder_path.clone()),
+            vec![("书签栏/Work".to_string(), 3)]
+        );
+
+        let report = DomainReport::build(&bookmarks, 1);
+        assert_eq!((report.total, report.domain_total), (4, 2));
+        assert_eq!(
+            report.domains,
+            vec![DomainCount {
+                domain: "docs.rs".to_string(),
+                count: 2,
+                percent: 50.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn untagged_report_flags_missing_tags_and_root_bookmarks() {
+        let mut bookmarks = vec![
+            bookmark("1", "https://a.example", "0"),
+            bookmark("2", "https://b.example", "0"),
+            bookmark("3", "https://c.example", "0"),
+            bookmark("4", "https://d.example", "0"),
+        ];
+        bookmarks[1].folder_path = Some("书签栏".to_string());
+        bookmarks[2].folder_path = None;
+        let tagged: HashSet<String> = [&bookmarks[0], &bookmarks[2]]
+            .into_iter()
+            .map(tag_key)
+            .collect();
+
+        let report = UntaggedReport::build(&bookmarks, &tagged, 10);
+        assert_eq!((report.total, report.untagged, report.at_root), (4, 2, 2));
+        let flags: Vec<(&str, bool, bool)> = report
+            .entries
+            .iter()
+            .map(|entry| (entry.bookmark.id.as_str(), entry.untagged, entry.at_root))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![("2", true, true), ("3", false, true), ("4", true, false)]
+        );
+
+        let report = UntaggedReport::build(&bookmarks, &tagged, 1);
+        assert_eq!(report.entries.len(), 1);
+        assert_eq!(report.untagged, 2);
+    }
+
+    #[test]
+    fn folder_report_nests_subtrees_up_to_depth() {
+        let folders = [
+            Some("书签栏/Work/Rust"),
+            Some("书签栏/Work/Go"),
+            Some("书签栏/Work"),
+            Some("书签栏/Read"),
+            Some("其他书签"),
+            None,
+        ];
+        let bookmarks: Vec<ChromeBookmark> = folders
+            .iter()
+            .enumerate()
+            .map(|(idx, folder)| {
+                let mut bookmark = bookmark(&idx.to_string(), "https://a.example", "0");
+                bookmark.folder_path = folder.map(str::to_string);
+                bookmark
+            })
+            .collect();
+
+        let report = FolderReport::build(&bookmarks, 2);
+        assert_eq!((report.total, report.unfiled), (6, 1));
+        let rows: Vec<(&str, usize, usize)> = report
+            .folders
+            .iter()
+            .map(|node| (node.path.as_str(), node.depth, node.count))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("书签栏", 1, 4),
+                ("书签栏/Work", 2, 3),
+                ("书签栏/Read", 2, 1),
+                ("其他书签", 1, 1),
+            ]
+        );
+
+        let text = report.render_text();
+        assert!(text.contains("\n  Work  "));
+        assert_eq!(FolderReport::build(&bookmarks, 0).folders.len(), 2);
+    }
+
+    #[test]
+    fn growth_fills_empty_months_and_accumulates() {
+        let bookmarks = vec![
+            // 2023-11-15（WebKit 微秒）
+            bookmark("1", "https://a.example", "13343940000000000"),
+            // 2024-01-01（Unix 微秒）
+            bookmark("2", "https://b.example", "1704067200000000"),
+            bookmark("3", "https://c.example", "1704153600"),
+            bookmark("4", "https://d.example", "0"),
+        ];
+
+        let report = GrowthReport::build(&bookmarks, GrowthPeriod::Month);
+        assert_eq!((report.total, report.undated), (4, 1));
+        let periods: Vec<_> = report
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.period.as_str(), bucket.count, bucket.cumulative))
+            .collect();
+        assert_eq!(
+            periods,
+            vec![("2023-11", 1, 1), ("2023-12", 0, 1), ("2024-01", 2, 3)]
+        );
+        let text = report.render_text();
+        assert!(text.contains("2023-12"));
+        assert!(text
+            .lines()
+            .nth(3)
+            .unwrap_or_default()
+            .starts_with("2024-01  ████"));
+
+        let yearly = GrowthReport::build(&bookmarks, GrowthPeriod::Year);
+        assert_eq!(yearly.buckets.len(), 2);
+        assert_eq!(yearly.buckets[1].period, "2024");
+    }
+
+    #[test]
+    fn format_date_handles_epoch_and_leap_years() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(1_709_164_800), "2024-02-29");
+        assert_eq!(format_date(1_704_067_200), "2024-01-01");
+        assert_eq!(format_datetime(1_704_067_200 + 3_723), "2024-01-01 01:02");
+    }
+
+    #[test]
+    fn cleanup_report_combines_all_sections() {
+        let now = 1_704_067_200;
+        let bookmarks = vec![
+            bookmark("1", "https://dup.example", "1704067200000000"),
+            bookmark("2", "https://dup.example", "1704067200000000"),
+            bookmark("3", "https://dead.example", "1600000000000000"),
+        ];
+        let results = vec![LinkCheckResult {
+            url: "https://dead.example".into(),
+            status: 404,
+            final_url: "https://dead.example".into(),
+            redirect: RedirectKind::None,
+            error: None,
+            checked_at: now as u64,
+        }];
+        let tagged: HashSet<String> = HashSet::from([tag_key(&bookmarks[2])]);
+
+        let report = CleanupReport::build(&bookmarks, &[vec![0, 1]], &results, &tagged, 365, now);
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.dead.len(), 1);
+        assert_eq!(report.stale.len(), 1);
+        assert_eq!(report.untagged.as_ref().map(Vec::len), Some(2));
+
+        let markdown = report.render_markdown();
+        assert!(markdown.contains("## 重复书签（1 组）"));
+        assert!(markdown.contains("[Title 3](https://dead.example) — HTTP 404"));
+        assert!(markdown.contains("## 未打标签（2 条）"));
+    }
+
+    #[test]
+    fn cleanup_report_skips_untagged_section_without_tags() {
+        let bookmarks = vec![bookmark("1", "https://a.example", "0")];
+        let report = CleanupReport::build(&bookmarks, &[], &[], &HashSet::new(), 365, 0);
+        assert!(report.untagged.is_none());
+        assert!(report.stale.is_empty());
+        assert!(report.render_markdown().contains("尚未使用标签"));
+    }
+}
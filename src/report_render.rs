@@ -0,0 +1,154 @@
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::Path;
+
+/// `report * --format` 的导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Csv,
+    Json,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Ok(ReportFormat::Markdown),
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            other => Err(format!("不支持的报告格式: {}（可选 md、csv、json）", other)),
+        }
+    }
+}
+
+impl ReportFormat {
+    /// 按 `--out` 的扩展名推断格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+/// 报告的表格形式：Markdown 与 CSV 都由它生成
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportTable {
+    pub title: String,
+    /// 表格前的说明（总数、统计口径等），只出现在 Markdown 中
+    pub notes: Vec<String>,
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ReportTable {
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}\n", self.title);
+        for note in &self.notes {
+            let _ = writeln!(out, "- {}", note);
+        }
+        if !self.notes.is_empty() {
+            let _ = writeln!(out);
+        }
+        let _ = writeln!(out, "| {} |", self.columns.join(" | "));
+        let _ = writeln!(out, "|{}", " --- |".repeat(self.columns.len()));
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(|cell| escape_cell(cell)).collect();
+            let _ = writeln!(out, "| {} |", cells.join(" | "));
+        }
+        out
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| csv_field(column))
+            .collect();
+        let _ = writeln!(out, "{}", header.join(","));
+        for row in &self.rows {
+            let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            let _ = writeln!(out, "{}", fields.join(","));
+        }
+        out
+    }
+}
+
+/// 可用 `--out` / `--format` 导出的报告；JSON 即报告本身的序列化结果
+pub trait Exportable: Serialize {
+    /// 导出文件后通知里的一句话
+    fn summary(&self) -> String;
+
+    fn table(&self) -> ReportTable;
+
+    /// 分多节的报告可以覆盖，默认渲染表格
+    fn markdown(&self) -> String {
+        self.table().to_markdown()
+    }
+}
+
+pub fn render<R: Exportable + ?Sized>(
+    report: &R,
+    format: ReportFormat,
+) -> serde_json::Result<String> {
+    Ok(match format {
+        ReportFormat::Markdown => report.markdown(),
+        ReportFormat::Csv => report.table().to_csv(),
+        ReportFormat::Json => serde_json::to_string_pretty(report)? + "\n",
+    })
+}
+
+fn escape_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// RFC 4180：含逗号、引号或换行的字段加引号，内部引号加倍
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> ReportTable {
+        ReportTable {
+            title: "站点".to_string(),
+            notes: vec!["共 3 个书签".to_string()],
+            columns: vec!["domain", "title"],
+            rows: vec![
+                vec!["docs.rs".to_string(), "a | b".to_string()],
+                vec!["example.com".to_string(), "say \"hi\", bye".to_string()],
+            ],
+        }
+    }
+
+    #[test]
+    fn table_renders_markdown_and_csv() {
+        assert_eq!(
+            table().to_markdown(),
+            "# 站点\n\n- 共 3 个书签\n\n| domain | title |\n| --- | --- |\n\
+             | docs.rs | a \\| b |\n| example.com | say \"hi\", bye |\n"
+        );
+        assert_eq!(
+            table().to_csv(),
+            "domain,title\ndocs.rs,a | b\nexample.com,\"say \"\"hi\"\", bye\"\n"
+        );
+    }
+
+    #[test]
+    fn format_parses_names_and_extensions() {
+        assert_eq!("MD".parse(), Ok(ReportFormat::Markdown));
+        assert!("xml".parse::<ReportFormat>().is_err());
+        assert_eq!(
+            ReportFormat::from_path(Path::new("out/report.csv")),
+            Some(ReportFormat::Csv)
+        );
+        assert_eq!(ReportFormat::from_path(Path::new("report")), None);
+    }
+}
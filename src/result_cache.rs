@@ -0,0 +1,141 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 默认保留的搜索结果条数
+pub const DEFAULT_RESULT_CACHE_ENTRIES: usize = 64;
+
+/// 最近若干次搜索渲染好的 Alfred JSON（`<缓存目录>/results/`），一个键一个文件；
+/// 命中时刷新修改时间，写入后按修改时间淘汰最久未用的条目
+pub struct ResultCache {
+    dir: PathBuf,
+    capacity: usize,
+}
+
+/// 命中的缓存：结果中的书签数（供搜索历史记录）与原样输出的 JSON
+#[derive(Debug, PartialEq, Eq)]
+pub struct CachedResults {
+    pub count: usize,
+    pub payload: Vec<u8>,
+}
+
+impl ResultCache {
+    pub fn new(cache_dir: &Path, capacity: usize) -> Self {
+        Self {
+            dir: cache_dir.join("results"),
+            capacity,
+        }
+    }
+
+    /// 把查询、过滤条件与数据状态等组成部分散列成文件名
+    pub fn key<T: Hash>(parts: T) -> String {
+        let mut hasher = DefaultHasher::new();
+        parts.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    pub fn load(&self, key: &str) -> Option<CachedResults> {
+        let path = self.entry_path(key);
+        let data = std::fs::read(&path).ok()?;
+        let split = data.iter().position(|&byte| byte == b'\n')?;
+        let count = std::str::from_utf8(&data[..split]).ok()?.parse().ok()?;
+        let payload = data[split + 1..].to_vec();
+        if payload.is_empty() {
+            return None;
+        }
+        // 刷新修改时间，标记为最近使用
+        let _ = std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        Some(CachedResults { count, payload })
+    }
+
+    /// 先写临时文件再替换，随后淘汰超出容量的旧条目
+    pub fn save(&self, key: &str, count: usize, payload: &[u8]) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.entry_path(key);
+        let tmp = path.with_extension("tmp");
+        let mut data = format!("{}\n", count).into_bytes();
+        data.extend_from_slice(payload);
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, &path)?;
+        self.evict()
+    }
+
+    fn evict(&self) -> std::io::Result<()> {
+        let mut entries: Vec<(SystemTime, PathBuf)> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let modified = path.metadata().and_then(|meta| meta.modified()).ok()?;
+                Some((modified, path))
+            })
+            .collect();
+        if entries.len() <= self.capacity {
+            return Ok(());
+        }
+        entries.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        for (_, path) in entries.into_iter().skip(self.capacity) {
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(())
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+/// 文件的修改时间与大小；不存在时为空，用于判断索引、标签库等是否被改动过
+pub fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn entries_round_trip_and_least_recent_is_evicted() {
+        let dir = tempdir().expect("tempdir");
+        let cache = ResultCache::new(dir.path(), 2);
+        let (a, b, c) = (
+            ResultCache::key(("rust", 1)),
+            ResultCache::key(("tokio", 1)),
+            ResultCache::key(("rust", 2)),
+        );
+        assert_ne!(a, c);
+        assert!(cache.load(&a).is_none());
+
+        let age = |key: &str, secs: u64| {
+            std::fs::File::options()
+                .write(true)
+                .open(cache.entry_path(key))
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(secs))
+                .unwrap();
+        };
+        cache.save(&a, 3, br#"{"items":[]}"#).expect("save a");
+        age(&a, 30);
+        cache.save(&b, 1, br#"{"items":[1]}"#).expect("save b");
+        age(&b, 20);
+        // 读取 a 使其成为最近使用，写入 c 时淘汰的是 b
+        assert_eq!(
+            cache.load(&a),
+            Some(CachedResults {
+                count: 3,
+                payload: br#"{"items":[]}"#.to_vec()
+            })
+        );
+        cache.save(&c, 0, br#"{"items":[2]}"#).expect("save c");
+
+        assert!(cache.load(&a).is_some());
+        assert!(cache.load(&b).is_none());
+        assert_eq!(cache.load(&c).expect("c").count, 0);
+    }
+}
@@ -0,0 +1,259 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const LAUNCHD_LABEL: &str = "com.alfred-chrome-bookmarks.refresh";
+pub const LINK_SCAN_LABEL: &str = "com.alfred-chrome-bookmarks.link-scan";
+pub const DEFAULT_INTERVAL_SECS: u64 = 1800;
+/// launchd 对过短的间隔会节流，这里直接限制下限
+pub const MIN_INTERVAL_SECS: u64 = 60;
+
+/// 由 launchd 定时执行的任务
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduledTask {
+    Refresh,
+    LinkScan,
+}
+
+impl ScheduledTask {
+    pub fn label(self) -> &'static str {
+        match self {
+            ScheduledTask::Refresh => LAUNCHD_LABEL,
+            ScheduledTask::LinkScan => LINK_SCAN_LABEL,
+        }
+    }
+
+    pub fn log_file(self) -> &'static str {
+        match self {
+            ScheduledTask::Refresh => "schedule.log",
+            ScheduledTask::LinkScan => "link-scan.log",
+        }
+    }
+
+    /// 传给本工具的子命令参数；链接扫描需要知道间隔来计算每次的检查数量
+    fn arguments(self, interval_secs: u64) -> Vec<String> {
+        match self {
+            ScheduledTask::Refresh => vec!["refresh".to_string()],
+            ScheduledTask::LinkScan => vec![
+                "scan-links".to_string(),
+                "--interval".to_string(),
+                interval_secs.to_string(),
+            ],
+        }
+    }
+}
+
+/// 已安装的定时任务
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleStatus {
+    pub plist_path: PathBuf,
+    pub interval_secs: Option<u64>,
+    pub loaded: bool,
+}
+
+pub fn plist_path(home: &Path, task: ScheduledTask) -> PathBuf {
+    home.join("Library/LaunchAgents")
+        .join(format!("{}.plist", task.label()))
+}
+
+/// 定时任务不经过 Alfred 启动，需要把数据目录与本工具的配置一并写入 plist
+pub fn forwarded_env() -> Vec<(String, String)> {
+    let mut env: Vec<(String, String)> = std::env::vars()
+        .filter(|(key, _)| {
+            key.starts_with("ALFRED_CHROME_BOOKMARKS_")
+                || key == "alfred_workflow_data"
+                || key == "alfred_workflow_cache"
+        })
+        .collect();
+    env.sort();
+    env
+}
+
+pub fn render_plist(
+    task: ScheduledTask,
+    program: &Path,
+    interval_secs: u64,
+    env: &[(String, String)],
+    log_path: &Path,
+) -> String {
+    let interval_secs = interval_secs.max(MIN_INTERVAL_SECS);
+    let mut arguments = String::new();
+    for argument in task.arguments(interval_secs) {
+        arguments.push_str(&format!(
+            "\n        <string>{}</string>",
+            xml_escape(&argument)
+        ));
+    }
+    let mut env_entries = String::new();
+    for (key, value) in env {
+        env_entries.push_str(&format!(
+            "        <key>{}</key>\n        <string>{}</string>\n",
+            xml_escape(key),
+            xml_escape(value)
+        ));
+    }
+    let log = xml_escape(&log_path.to_string_lossy());
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{program}</string>{arguments}
+    </array>
+    <key>StartInterval</key>
+    <integer>{interval}</integer>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>ProcessType</key>
+    <string>Background</string>
+    <key>LowPriorityIO</key>
+    <true/>
+    <key>EnvironmentVariables</key>
+    <dict>
+{env_entries}    </dict>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = task.label(),
+        program = xml_escape(&program.to_string_lossy()),
+        arguments = arguments,
+        interval = interval_secs,
+        env_entries = env_entries,
+        log = log,
+    )
+}
+
+/// 写入 plist 并交给 launchd 加载；已安装时先卸载旧任务再覆盖
+pub fn install(home: &Path, task: ScheduledTask, plist: &str) -> std::io::Result<PathBuf> {
+    let path = plist_path(home, task);
+    if path.exists() {
+        let _ = launchctl(&["unload", "-w"], &path);
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, plist)?;
+    launchctl(&["load", "-w"], &path)?;
+    Ok(path)
+}
+
+/// 卸载并删除 plist；未安装时返回 false
+pub fn remove(home: &Path, task: ScheduledTask) -> std::io::Result<bool> {
+    let path = plist_path(home, task);
+    if !path.exists() {
+        return Ok(false);
+    }
+    let _ = launchctl(&["unload", "-w"], &path);
+    std::fs::remove_file(&path)?;
+    Ok(true)
+}
+
+pub fn status(home: &Path, task: ScheduledTask) -> Option<ScheduleStatus> {
+    let path = plist_path(home, task);
+    let content = std::fs::read_to_string(&path).ok()?;
+    let loaded = Command::new("launchctl")
+        .arg("list")
+        .arg(task.label())
+        .output()
+        .is_ok_and(|output| output.status.success());
+
+    Some(ScheduleStatus {
+        interval_secs: parse_interval(&content),
+        plist_path: path,
+        loaded,
+    })
+}
+
+fn launchctl(args: &[&str], plist: &Path) -> std::io::Result<()> {
+    let output = Command::new("launchctl").args(args).arg(plist).output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "launchctl {} 失败: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+fn parse_interval(plist: &str) -> Option<u64> {
+    let rest = &plist[plist.find("<key>StartInterval</key>")?..];
+    let start = rest.find("<integer>")? + "<integer>".len();
+    let end = rest[start..].find("</integer>")? + start;
+    rest[start..end].trim().parse().ok()
+}
+
+fn xml_escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendered_plist_runs_refresh_with_forwarded_env() {
+        let plist = render_plist(
+            ScheduledTask::Refresh,
+            Path::new("/Apps/Alfred & Co/alfred-chrome-bookmarks"),
+            900,
+            &[(
+                "ALFRED_CHROME_BOOKMARKS_BROWSER".to_string(),
+                "arc,chrome".to_string(),
+            )],
+            Path::new("/tmp/schedule.log"),
+        );
+
+        assert!(plist.contains("<string>/Apps/Alfred &amp; Co/alfred-chrome-bookmarks</string>"));
+        assert!(plist.contains("<string>refresh</string>"));
+        assert!(plist.contains("<key>ALFRED_CHROME_BOOKMARKS_BROWSER</key>"));
+        assert!(plist.contains("<string>arc,chrome</string>"));
+        assert_eq!(parse_interval(&plist), Some(900));
+    }
+
+    #[test]
+    fn interval_is_clamped_to_minimum() {
+        let plist = render_plist(
+            ScheduledTask::Refresh,
+            Path::new("/bin/true"),
+            5,
+            &[],
+            Path::new("/tmp/log"),
+        );
+        assert_eq!(parse_interval(&plist), Some(MIN_INTERVAL_SECS));
+    }
+
+    #[test]
+    fn link_scan_plist_passes_interval_to_scan_links() {
+        let plist = render_plist(
+            ScheduledTask::LinkScan,
+            Path::new("/bin/acb"),
+            3600,
+            &[],
+            Path::new("/tmp/link-scan.log"),
+        );
+        assert!(plist.contains(&format!("<string>{}</string>", LINK_SCAN_LABEL)));
+        assert!(plist.contains(
+            "<string>scan-links</string>\n        <string>--interval</string>\n        <string>3600</string>"
+        ));
+    }
+
+    #[test]
+    fn remove_without_plist_is_a_no_op() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        assert!(!remove(dir.path(), ScheduledTask::LinkScan).expect("remove"));
+        assert!(status(dir.path(), ScheduledTask::Refresh).is_none());
+    }
+}
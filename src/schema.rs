@@ -0,0 +1,137 @@
+use rusqlite::{Connection, Result};
+
+/// 一步结构迁移：把数据库从 `version - 1` 升级到 `version`
+///
+/// 版本号记在 SQLite 文件头的 `PRAGMA user_version` 中；引入版本号之前创建的数据库为 0，
+/// 因此每一步都要能在「已经部分具备该结构」的旧库上重复执行（`IF NOT EXISTS`、先查列再加列）。
+pub struct Migration {
+    pub version: i32,
+    pub apply: fn(&Connection) -> Result<()>,
+}
+
+pub fn user_version(conn: &Connection) -> Result<i32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+pub fn set_user_version(conn: &Connection, version: i32) -> Result<()> {
+    conn.execute_batch(&format!("PRAGMA user_version = {};", version))
+}
+
+/// 依次执行版本号高于当前 `user_version` 的迁移（`migrations` 按版本号升序），返回执行的步数
+///
+/// 每一步单独占一个 `BEGIN IMMEDIATE` 事务，结构改动与新版本号一起提交；某步失败则整步回滚，
+/// 数据库停在上一个完整的版本。进入事务后重新读取版本号，另一个进程已抢先完成的步骤会被跳过。
+/// 数据库版本比本程序已知的更新（降级运行）时不做任何改动。
+pub fn migrate(conn: &Connection, migrations: &[Migration]) -> Result<usize> {
+    let mut applied = 0;
+    for step in migrations {
+        if user_version(conn)? >= step.version {
+            continue;
+        }
+        conn.execute_batch("BEGIN IMMEDIATE;")?;
+        let result = (|| {
+            if user_version(conn)? >= step.version {
+                return Ok(false);
+            }
+            (step.apply)(conn)?;
+            set_user_version(conn, step.version)?;
+            Ok(true)
+        })();
+        match result {
+            Ok(ran) => {
+                conn.execute_batch("COMMIT;")?;
+                applied += usize::from(ran);
+            }
+            Err(err) => {
+                let _ = conn.execute_batch("ROLLBACK;");
+                return Err(err);
+            }
+        }
+    }
+    Ok(applied)
+}
+
+/// 数据库忙、只读或无权限等与结构无关的失败：重试或换用其他存储即可，不应据此删表重建
+pub fn is_environment_error(err: &rusqlite::Error) -> bool {
+    matches!(
+        err,
+        rusqlite::Error::SqliteFailure(failure, _)
+            if matches!(
+                failure.code,
+                rusqlite::ErrorCode::DatabaseBusy
+                    | rusqlite::ErrorCode::DatabaseLocked
+                    | rusqlite::ErrorCode::ReadOnly
+                    | rusqlite::ErrorCode::CannotOpen
+                    | rusqlite::ErrorCode::DiskFull
+                    | rusqlite::ErrorCode::PermissionDenied
+                    | rusqlite::ErrorCode::NotADatabase
+            )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_items(conn: &Connection) -> Result<()> {
+        conn.execute_batch("CREATE TABLE IF NOT EXISTS items (id INTEGER PRIMARY KEY);")
+    }
+
+    fn add_label(conn: &Connection) -> Result<()> {
+        conn.execute_batch("ALTER TABLE items ADD COLUMN label TEXT;")
+    }
+
+    fn broken(conn: &Connection) -> Result<()> {
+        conn.execute_batch("CREATE TABLE half_done (id INTEGER); SELECT * FROM missing;")
+    }
+
+    const STEPS: &[Migration] = &[
+        Migration {
+            version: 1,
+            apply: create_items,
+        },
+        Migration {
+            version: 2,
+            apply: add_label,
+        },
+    ];
+
+    #[test]
+    fn migrate_runs_pending_steps_once_and_records_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(migrate(&conn, &STEPS[..1]).unwrap(), 1);
+        assert_eq!(user_version(&conn).unwrap(), 1);
+
+        assert_eq!(migrate(&conn, STEPS).unwrap(), 1);
+        assert_eq!(migrate(&conn, STEPS).unwrap(), 0);
+        assert_eq!(user_version(&conn).unwrap(), 2);
+        conn.execute("INSERT INTO items (label) VALUES ('x')", [])
+            .unwrap();
+    }
+
+    #[test]
+    fn failed_step_rolls_back_and_keeps_previous_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        let steps = [
+            Migration {
+                version: 1,
+                apply: create_items,
+            },
+            Migration {
+                version: 2,
+                apply: broken,
+            },
+        ];
+        assert!(migrate(&conn, &steps).is_err());
+        assert_eq!(user_version(&conn).unwrap(), 1);
+        assert!(conn.is_autocommit());
+        let half_done: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'half_done'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(half_done, 0);
+    }
+}
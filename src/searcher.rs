@@ -1,25 +1,54 @@
-use crate::bookmark::ChromeBookmark;
-use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
+use crate::bookmark::{date_added_to_unix_secs, ChromeBookmark};
+use crate::matcher::{MatcherKind, TextMatcher};
+use crate::query::{excluded_by, is_phrase, QueryExpr};
+use crate::url_normalize::{canonical_url, url_host, url_in_domains};
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 
 #[derive(Debug)]
 pub struct SearchResult {
     pub bookmark: ChromeBookmark,
 }
 
+/// 偏好域名在精确匹配中的加分，约等于关键词命中一次 URL
+const DOMAIN_BOOST_SCORE: i64 = 150;
+/// 置顶书签的加分，远大于任何匹配得分：命中的置顶书签总排在未置顶的结果之前
+const PINNED_BOOST_SCORE: i64 = 1_000_000;
+
 pub struct BookmarkSearcher {
-    fuzzy_matcher: SkimMatcherV2,
+    matcher: Box<dyn TextMatcher>,
+    boost_domains: Vec<String>,
+    pinned_ids: HashSet<String>,
 }
 
 impl BookmarkSearcher {
     pub fn new() -> Self {
         Self {
-            fuzzy_matcher: SkimMatcherV2::default(),
+            matcher: MatcherKind::default().build(),
+            boost_domains: Vec::new(),
+            pinned_ids: HashSet::new(),
         }
     }
 
+    /// 偏好域名（已小写，含子域名）的命中结果额外加分
+    pub fn with_domain_boosts(mut self, domains: Vec<String>) -> Self {
+        self.boost_domains = domains;
+        self
+    }
+
+    /// 置顶书签（按书签 id）在精确与模糊搜索中都大幅加分
+    pub fn with_pinned(mut self, ids: HashSet<String>) -> Self {
+        self.pinned_ids = ids;
+        self
+    }
+
+    /// 模糊搜索改用指定的匹配算法
+    pub fn with_matcher(mut self, kind: MatcherKind) -> Self {
+        self.matcher = kind.build();
+        self
+    }
+
     pub fn search(
         &self,
         bookmarks: &[ChromeBookmark],
@@ -33,7 +62,6 @@ impl BookmarkSearcher {
         }
 
         let normalized_folder_filters = normalize_folder_filters(folder_filters);
-        let query_lower = query.to_lowercase();
 
         if query.is_empty() {
             return bookmarks
@@ -45,6 +73,9 @@ impl BookmarkSearcher {
                 .collect();
         }
 
+        // 精确匹配比较小写；模糊匹配保持原样，区分大小写与否交给匹配算法
+        let expr = QueryExpr::parse(query).map(|expr| if fuzzy { expr } else { expr.lowercased() });
+
         #[derive(Debug)]
         struct HeapItem {
             score: i64,
@@ -87,15 +118,27 @@ impl BookmarkSearcher {
                 continue;
             }
 
-            let score = if fuzzy {
-                self.fuzzy_search(bookmark, query)
-            } else {
-                self.exact_search(bookmark, &query_lower)
+            let score = match expr
+                .as_ref()
+                .and_then(|expr| self.expr_score(bookmark, expr, fuzzy))
+            {
+                // 只有排除词时，其余书签都算命中
+                Some(0) => 1,
+                Some(score) if !fuzzy && url_in_domains(&bookmark.url, &self.boost_domains) => {
+                    score + DOMAIN_BOOST_SCORE
+                }
+                Some(score) => score,
+                None => continue,
             };
 
             if score <= 0 {
                 continue;
             }
+            let score = if self.pinned_ids.contains(&bookmark.id) {
+                score + PINNED_BOOST_SCORE
+            } else {
+                score
+            };
 
             let candidate = HeapItem {
                 score,
@@ -137,84 +180,113 @@ impl BookmarkSearcher {
             .collect()
     }
 
-    fn fuzzy_search(&self, bookmark: &ChromeBookmark, query: &str) -> i64 {
-        let tokens: Vec<&str> = query.split_whitespace().collect();
-        if tokens.is_empty() {
-            return 0;
+    /// 表达式树的得分，未命中为 None：组内各项得分相加，`OR` 取得分最高的分支，
+    /// 排除词不加分（命中时整组不算命中）
+    fn expr_score(&self, bookmark: &ChromeBookmark, expr: &QueryExpr, fuzzy: bool) -> Option<i64> {
+        match expr {
+            QueryExpr::Term(term) => {
+                let score = if fuzzy {
+                    self.fuzzy_search(bookmark, term)
+                } else {
+                    self.exact_search(bookmark, term)
+                };
+                (score > 0).then_some(score)
+            }
+            QueryExpr::Exclude(word) => (!excluded_by(bookmark, word)).then_some(0),
+            QueryExpr::And(items) => items.iter().try_fold(0, |total, item| {
+                Some(total + self.expr_score(bookmark, item, fuzzy)?)
+            }),
+            QueryExpr::Or(items) => items
+                .iter()
+                .filter_map(|item| self.expr_score(bookmark, item, fuzzy))
+                .max(),
         }
+    }
 
-        let mut total_score = 0i64;
-
-        for token in &tokens {
-            let mut max_score = 0i64;
+    fn fuzzy_search(&self, bookmark: &ChromeBookmark, token: &str) -> i64 {
+        let mut max_score = 0i64;
 
-            if let Some(score) = self.fuzzy_matcher.fuzzy_match(&bookmark.name, token) {
-                max_score = max_score.max(score * 2);
+        // 短语须连续出现，命中后仍用模糊打分排序
+        let phrase = is_phrase(token).then(|| token.to_lowercase());
+        let score = |text: &str| -> Option<i64> {
+            match &phrase {
+                Some(phrase) if !text.to_lowercase().contains(phrase.as_str()) => None,
+                Some(phrase) => Some(self.matcher.score(text, phrase).unwrap_or(1)),
+                None => self.matcher.score(text, token),
             }
+        };
 
-            if let Some(score) = self.fuzzy_matcher.fuzzy_match(&bookmark.url, token) {
-                max_score = max_score.max(score);
-            }
+        if let Some(score) = score(&bookmark.name) {
+            max_score = max_score.max(score * 2);
+        }
 
-            if let Some(ref folder_path) = bookmark.folder_path {
-                if let Some(score) = self.fuzzy_matcher.fuzzy_match(folder_path, token) {
-                    max_score = max_score.max(score / 2);
-                }
-            }
+        if let Some(score) = score(&bookmark.url) {
+            max_score = max_score.max(score);
+        }
 
-            if max_score <= 0 {
-                return 0;
+        if let Some(ref folder_path) = bookmark.folder_path {
+            if let Some(score) = score(folder_path) {
+                max_score = max_score.max(score / 2);
             }
-
-            total_score += max_score;
         }
 
-        total_score
+        max_score
     }
 
-    fn exact_search(&self, bookmark: &ChromeBookmark, query_lower: &str) -> i64 {
-        let tokens: Vec<&str> = query_lower
-            .split_whitespace()
-            .filter(|t| !t.is_empty())
-            .collect();
+    /// `token` 为已转小写的关键词
+    fn exact_search(&self, bookmark: &ChromeBookmark, token: &str) -> i64 {
+        let mut token_score = 0i64;
+        let name_lower = bookmark.lower_name();
+        let url_lower = bookmark.lower_url();
+        let folder_lower = bookmark.lower_folder_path();
 
-        if tokens.is_empty() {
-            return 0;
+        if name_lower.contains(token) {
+            token_score += 200;
+            if name_lower == token {
+                token_score += 100;
+            }
+            if name_lower.starts_with(token) {
+                token_score += 50;
+            }
         }
 
-        let mut total_score = 0i64;
+        if url_lower.contains(token) {
+            token_score += 100;
+        }
 
-        for token in &tokens {
-            let mut token_score = 0i64;
+        if let Some(ref folder_lower) = folder_lower {
+            if folder_lower.contains(token) {
+                token_score += 50;
+            }
+        }
 
-            if bookmark.name_lower.contains(token) {
-                token_score += 200;
-                if bookmark.name_lower == *token {
-                    token_score += 100;
+        // 忽略标点再比一次：`rustlang` 命中 “rust-lang”；与索引的 compact 列一致，只按词首前缀匹配，
+        // 避免 `go` 命中 “lang.org” 中间；短语须原样连续出现，不做这一步
+        if token_score == 0 && !is_phrase(token) {
+            let compact_token = compact_for_match(token);
+            if !compact_token.is_empty() {
+                let prefixes =
+                    |forms: Vec<String>| forms.iter().any(|form| form.starts_with(&compact_token));
+                if prefixes(compact_name_forms(&bookmark.name)) {
+                    token_score += 150;
                 }
-                if bookmark.name_lower.starts_with(token) {
-                    token_score += 50;
+                if prefixes(compact_host_forms(&bookmark.url)) {
+                    token_score += 80;
                 }
             }
+        }
 
-            if bookmark.url_lower.contains(token) {
+        // 按规范化 URL 再比一次：粘贴的完整 URL 去掉跟踪参数后相同即命中，punycode 主机按解码后的写法匹配
+        if token_score == 0 && (token.contains("://") || url_lower.contains("xn--")) {
+            let normalized = canonical_url(&bookmark.url).to_lowercase();
+            if token.contains("://") && normalized == canonical_url(token).to_lowercase() {
+                token_score += 300;
+            } else if normalized.contains(token) {
                 token_score += 100;
             }
-
-            if let Some(ref folder_lower) = bookmark.folder_path_lower {
-                if folder_lower.contains(token) {
-                    token_score += 50;
-                }
-            }
-
-            if token_score == 0 {
-                return 0;
-            }
-
-            total_score += token_score;
         }
 
-        total_score
+        token_score
     }
 }
 
@@ -224,6 +296,150 @@ impl Default for BookmarkSearcher {
     }
 }
 
+/// 搜索过滤条件：目录按层级匹配、标签不区分大小写（均需全部满足）；浏览器与 profile 不区分大小写（各自满足其一即可）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BookmarkFilters {
+    pub folders: Vec<String>,
+    /// 已规范化的浏览器 key（见 `canonical_browser_key`）
+    pub browsers: Vec<String>,
+    pub profiles: Vec<String>,
+    /// 标签过滤在 SQL 中完成，需要先 `BookmarkIndex::attach_tags`
+    pub tags: Vec<String>,
+}
+
+impl BookmarkFilters {
+    pub fn is_empty(&self) -> bool {
+        self.folders.is_empty()
+            && self.browsers.is_empty()
+            && self.profiles.is_empty()
+            && self.tags.is_empty()
+    }
+
+    /// 浏览器条件中出现 `all`（如 `browser:all`）表示跨全部已索引浏览器搜索，
+    /// 覆盖同时给出的其他浏览器条件，便于临时放宽预设了 `--sources` 的关键词
+    pub fn widen_all_browsers(&mut self) {
+        if self.browsers.iter().any(|browser| browser == "all") {
+            self.browsers.clear();
+        }
+    }
+}
+
+/// 去掉 `-`、`_`、`.` 与空白并转小写，使 `rustlang` 能匹配 “rust-lang”、`vs code` 能匹配 “VSCode”
+pub fn compact_for_match(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '-' | '_' | '.'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 标题逐词与整体去标点后的形式，供前缀匹配 `rustlang`、`vscode` 这类连写关键词
+pub fn compact_name_forms(name: &str) -> Vec<String> {
+    let mut forms: Vec<String> = name
+        .split_whitespace()
+        .map(compact_for_match)
+        .filter(|form| !form.is_empty())
+        .collect();
+    if forms.len() > 1 {
+        forms.push(compact_for_match(name));
+    }
+    forms
+}
+
+/// 主机名从每一级开始去标点的形式（`users.rust-lang.org` 得到 `usersrustlangorg`、`rustlangorg`）
+pub fn compact_host_forms(url: &str) -> Vec<String> {
+    let Some(host) = url_host(url) else {
+        return Vec::new();
+    };
+    let labels: Vec<&str> = host.split('.').collect();
+    (0..labels.len().saturating_sub(1))
+        .map(|start| compact_for_match(&labels[start..].concat()))
+        .collect()
+}
+
+/// 搜索结果排序；relevance 以外的顺序在 SQL 中完成
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SortOrder {
+    #[default]
+    Relevance,
+    /// 最新添加在前
+    Added,
+    Title,
+    Url,
+    /// 打开次数按最近一次打开时间衰减，常用且最近用过的在前
+    Frecency,
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "relevance" => Ok(SortOrder::Relevance),
+            "added" => Ok(SortOrder::Added),
+            "title" => Ok(SortOrder::Title),
+            "url" => Ok(SortOrder::Url),
+            "frecency" => Ok(SortOrder::Frecency),
+            other => Err(format!(
+                "未知排序: {}（可选 relevance、added、title、url、frecency）",
+                other
+            )),
+        }
+    }
+}
+
+/// FTS 不可用时的内存排序；frecency 需要打开记录，保持原有相关度顺序
+pub fn sort_bookmarks(bookmarks: &mut [ChromeBookmark], sort: SortOrder) {
+    match sort {
+        SortOrder::Relevance | SortOrder::Frecency => {}
+        SortOrder::Added => bookmarks.sort_by_key(|bookmark| {
+            std::cmp::Reverse(date_added_to_unix_secs(&bookmark.date_added).unwrap_or(0))
+        }),
+        SortOrder::Title => {
+            bookmarks.sort_by_cached_key(|bookmark| bookmark.lower_name().into_owned())
+        }
+        SortOrder::Url => bookmarks.sort_by(|a, b| a.url.cmp(&b.url)),
+    }
+}
+
+/// 结果多样化的分组依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiversifyBy {
+    Folder,
+    Domain,
+}
+
+/// 同一目录（或站点）最多占据前列的条数，超出的结果按原顺序排到后面而不是丢弃
+pub fn diversify(
+    bookmarks: Vec<ChromeBookmark>,
+    by: DiversifyBy,
+    max_per_group: usize,
+) -> Vec<ChromeBookmark> {
+    let max_per_group = max_per_group.max(1);
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut head = Vec::with_capacity(bookmarks.len());
+    let mut overflow = Vec::new();
+
+    for bookmark in bookmarks {
+        let key = match by {
+            DiversifyBy::Folder => bookmark
+                .lower_folder_path()
+                .map(Cow::into_owned)
+                .unwrap_or_default(),
+            DiversifyBy::Domain => url_host(&bookmark.url).unwrap_or_default(),
+        };
+        let count = counts.entry(key).or_insert(0);
+        *count += 1;
+        if *count > max_per_group {
+            overflow.push(bookmark);
+        } else {
+            head.push(bookmark);
+        }
+    }
+
+    head.extend(overflow);
+    head
+}
+
 pub fn normalize_folder_filters(raw_filters: &[String]) -> Vec<Vec<String>> {
     raw_filters
         .iter()
@@ -253,7 +469,7 @@ pub fn matches_folder_filters(bookmark: &ChromeBookmark, folder_filters: &[Vec<S
         return true;
     }
 
-    let Some(folder_lower) = bookmark.folder_path_lower.as_deref() else {
+    let Some(folder_lower) = bookmark.lower_folder_path() else {
         return false;
     };
 
@@ -315,7 +531,7 @@ fn folder_matches_hierarchy(folder_segments: &[&str], filter_segments: &[String]
     true
 }
 
-fn escape_like_value(value: &str) -> String {
+pub fn escape_like_value(value: &str) -> String {
     value
         .replace('\\', "\\\\")
         .replace('%', "\\%")
@@ -324,8 +540,7 @@ fn escape_like_value(value: &str) -> String {
 
 fn bookmark_folder_depth(bookmark: &ChromeBookmark) -> usize {
     bookmark
-        .folder_path_lower
-        .as_deref()
+        .lower_folder_path()
         .map(|path| {
             path.split('/')
                 .filter(|segment| !segment.is_empty())
@@ -337,20 +552,223 @@ fn bookmark_folder_depth(bookmark: &ChromeBookmark) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bookmark::test_bookmark;
 
     fn bookmark(id: &str, name: &str, url: &str, folder: Option<&str>) -> ChromeBookmark {
-        ChromeBookmark {
-            id: id.to_string(),
-            name: name.to_string(),
-            url: url.to_string(),
-            date_added: "0".to_string(),
-            folder_path: folder.map(ToString::to_string),
-            name_lower: name.to_lowercase(),
-            url_lower: url.to_lowercase(),
-            folder_path_lower: folder.map(|p| p.to_lowercase()),
+        test_bookmark(id, name, url, folder)
+    }
+
+    #[test]
+    fn diversify_demotes_crowded_groups_without_dropping() {
+        let bookmarks = vec![
+            bookmark("1", "a", "https://a.com/1", Some("Articles")),
+            bookmark("2", "b", "https://a.com/2", Some("Articles")),
+            bookmark("3", "c", "https://b.com/1", Some("Articles")),
+            bookmark("4", "d", "https://c.com/1", Some("Docs")),
+            bookmark("5", "e", "https://A.com/3", None),
+        ];
+        let ids = |results: Vec<ChromeBookmark>| -> Vec<String> {
+            results.into_iter().map(|bookmark| bookmark.id).collect()
+        };
+
+        assert_eq!(
+            ids(diversify(bookmarks.clone(), DiversifyBy::Folder, 2)),
+            vec!["1", "2", "4", "5", "3"]
+        );
+        assert_eq!(
+            ids(diversify(bookmarks, DiversifyBy::Domain, 1)),
+            vec!["1", "3", "4", "2", "5"]
+        );
+    }
+
+    #[test]
+    fn pinned_matches_rank_first_in_both_modes() {
+        let bookmarks = vec![
+            bookmark("1", "Rust", "https://rust-lang.org", None),
+            bookmark("2", "Notes on rust", "https://example.com/notes", None),
+            bookmark("3", "Tokio", "https://tokio.rs", None),
+        ];
+        let ids = |searcher: &BookmarkSearcher, fuzzy: bool| -> Vec<String> {
+            searcher
+                .search(&bookmarks, "rust", &[], fuzzy, 10)
+                .into_iter()
+                .map(|result| result.bookmark.id)
+                .collect()
+        };
+
+        let plain = BookmarkSearcher::new();
+        assert_eq!(ids(&plain, false), vec!["1", "2"]);
+
+        // 没有命中的置顶书签不会因此出现在结果中
+        let pinned =
+            BookmarkSearcher::new().with_pinned(HashSet::from(["2".to_string(), "3".to_string()]));
+        assert_eq!(ids(&pinned, false), vec!["2", "1"]);
+        assert_eq!(ids(&pinned, true), vec!["2", "1"]);
+    }
+
+    #[test]
+    fn negated_terms_exclude_name_url_and_folder_matches() {
+        let bookmarks = vec![
+            bookmark("1", "Rust book", "https://doc.rust-lang.org/book", None),
+            bookmark("2", "Rust game", "https://rust.facepunch.com", None),
+            bookmark("3", "Bevy", "https://bevyengine.org/rust", Some("GameDev")),
+            bookmark("4", "Tokio", "https://tokio.rs", None),
+        ];
+        let searcher = BookmarkSearcher::new();
+        let ids = |query: &str, fuzzy: bool| -> Vec<String> {
+            searcher
+                .search(&bookmarks, query, &[], fuzzy, 10)
+                .into_iter()
+                .map(|result| result.bookmark.id)
+                .collect()
+        };
+
+        assert_eq!(ids("rust", false).len(), 3);
+        assert_eq!(ids("rust -game", false), vec!["1"]);
+        assert_eq!(ids("rust -GAME", true), vec!["1"]);
+        // 只有排除词时列出其余书签
+        assert_eq!(ids("-game -book", false), vec!["4"]);
+    }
+
+    #[test]
+    fn or_groups_match_any_alternative() {
+        let bookmarks = vec![
+            bookmark("1", "Rust book", "https://doc.rust-lang.org/book", None),
+            bookmark("2", "Go tour", "https://go.dev/tour", None),
+            bookmark("3", "Tokio", "https://tokio.rs", None),
+            bookmark(
+                "4",
+                "Rust async",
+                "https://rust-lang.github.io/async-book",
+                None,
+            ),
+        ];
+        let searcher = BookmarkSearcher::new();
+        let ids = |query: &str, fuzzy: bool| -> Vec<String> {
+            let mut ids: Vec<String> = searcher
+                .search(&bookmarks, query, &[], fuzzy, 10)
+                .into_iter()
+                .map(|result| result.bookmark.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(ids("go OR tokio", false), vec!["2", "3"]);
+        assert_eq!(ids("rust (tour OR async)", false), vec!["4"]);
+        assert_eq!(ids("(rust -async) OR tokio", false), vec!["1", "3"]);
+        assert_eq!(ids("(rust -async) OR async", false), vec!["1", "4"]);
+        assert_eq!(ids("(rust -async) OR async", true), vec!["1", "4"]);
+    }
+
+    #[test]
+    fn quoted_phrases_match_contiguously() {
+        let bookmarks = vec![
+            bookmark(
+                "1",
+                "The Rust Async Book",
+                "https://rust-lang.github.io/async-book",
+                None,
+            ),
+            bookmark(
+                "2",
+                "Async in Rust: a book",
+                "https://example.com/async",
+                None,
+            ),
+            bookmark("3", "RustAsyncBook mirror", "https://mirror.example", None),
+        ];
+        let searcher = BookmarkSearcher::new();
+        let ids = |query: &str, fuzzy: bool| -> Vec<String> {
+            searcher
+                .search(&bookmarks, query, &[], fuzzy, 10)
+                .into_iter()
+                .map(|result| result.bookmark.id)
+                .collect()
+        };
+
+        assert_eq!(ids("rust async book", false).len(), 3);
+        assert_eq!(ids("\"rust async book\"", false), vec!["1"]);
+        assert_eq!(ids("\"Rust Async Book\"", true), vec!["1"]);
+        assert_eq!(ids("book -\"in rust\"", false), vec!["1", "3"]);
+    }
+
+    #[test]
+    fn exact_search_ignores_punctuation() {
+        let bookmarks = vec![
+            bookmark("1", "Rust-Lang Forum", "https://users.rust-lang.org", None),
+            bookmark("2", "VSCode Tips", "https://code.visualstudio.com", None),
+            bookmark("3", "Other", "https://other.example", None),
+        ];
+        let searcher = BookmarkSearcher::new();
+        let ids = |query: &str| -> Vec<String> {
+            searcher
+                .search(&bookmarks, query, &[], false, 10)
+                .into_iter()
+                .map(|result| result.bookmark.id)
+                .collect()
+        };
+
+        assert_eq!(ids("rustlang"), vec!["1"]);
+        assert_eq!(ids("vs code"), vec!["2"]);
+        assert_eq!(ids("users.rustlang"), vec!["1"]);
+        assert_eq!(compact_for_match("VS Code_v1.2-x"), "vscodev12x");
+    }
+
+    #[test]
+    fn exact_search_matches_normalized_urls() {
+        let bookmarks = vec![
+            bookmark("1", "Docs", "https://Docs.RS/serde?utm_source=x", None),
+            bookmark("2", "Beispiel", "https://xn--bcher-kva.example/", None),
+            bookmark("3", "Other", "https://other.example", None),
+        ];
+        let searcher = BookmarkSearcher::new();
+        let ids = |query: &str| -> Vec<String> {
+            searcher
+                .search(&bookmarks, query, &[], false, 10)
+                .into_iter()
+                .map(|result| result.bookmark.id)
+                .collect()
+        };
+
+        assert_eq!(ids("https://docs.rs:443/serde?fbclid=abc"), vec!["1"]);
+        assert_eq!(ids("bücher"), vec!["2"]);
+        assert!(ids("https://docs.rs/tokio").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_search_uses_selected_matcher() {
+        let bookmarks = vec![
+            bookmark("1", "Rust Book", "https://doc.rust-lang.org/book", None),
+            bookmark("2", "Tokio", "https://tokio.rs", Some("Async")),
+        ];
+        for kind in [
+            MatcherKind::Skim,
+            MatcherKind::Nucleo,
+            MatcherKind::Subsequence,
+        ] {
+            let searcher = BookmarkSearcher::new().with_matcher(kind);
+            let results = searcher.search(&bookmarks, "rsbk", &[], true, 10);
+            let ids: Vec<&str> = results.iter().map(|r| r.bookmark.id.as_str()).collect();
+            assert_eq!(ids, ["1"], "{kind:?}");
         }
     }
 
+    #[test]
+    fn boosted_domains_outrank_equal_matches() {
+        let bookmarks = vec![
+            bookmark("1", "serde guide", "https://blog.example/serde", None),
+            bookmark("2", "serde guide", "https://docs.rs/serde", None),
+            bookmark("3", "unrelated", "https://docs.rs/tokio", None),
+        ];
+        let searcher = BookmarkSearcher::new().with_domain_boosts(vec!["docs.rs".into()]);
+
+        let results = searcher.search(&bookmarks, "serde guide", &[], false, 10);
+        let ids: Vec<&str> = results.iter().map(|r| r.bookmark.id.as_str()).collect();
+        // 加分只作用于已命中的结果
+        assert_eq!(ids, vec!["2", "1"]);
+    }
+
     #[test]
     fn exact_search_ranks_full_match_first() {
         let searcher = BookmarkSearcher::new();
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 会话状态的有效期；超过后视为新的一轮搜索
+pub const SESSION_TTL_SECS: u64 = 10 * 60;
+/// “返回”最多能回溯的层数
+const MAX_BACK_LEVELS: usize = 5;
+
+/// 跨多次 script filter 调用的会话状态：从动作菜单跳到“同目录 / 同站点 / 标签”搜索时记下来路，
+/// 随 `search:` 的 arg 交给 run.sh，经 `session set` 写入缓存目录，下一次搜索据此显示“返回”条目
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SessionState {
+    /// 跳转后的查询；输入框仍以它开头时会话才生效，改搜别的内容即自然失效
+    pub query: String,
+    /// 跳转的说明，如“同目录: 书签栏/Work”
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// 此前经过的查询及其说明，最近的在最后
+    #[serde(default)]
+    pub back: Vec<(String, Option<String>)>,
+}
+
+impl SessionState {
+    /// 从 `origin`（打开动作菜单前的查询，`current` 为它所在的会话）跳转到 `query`
+    pub fn navigate(
+        current: Option<&SessionState>,
+        origin: &str,
+        query: &str,
+        context: &str,
+    ) -> Self {
+        let mut back = Vec::new();
+        let mut origin_context = None;
+        if let Some(current) = current.filter(|state| state.applies_to(origin)) {
+            back.extend(current.back.iter().cloned());
+            origin_context = current.context.clone();
+        }
+        if !origin.trim().is_empty() {
+            back.push((origin.to_string(), origin_context));
+        }
+        if back.len() > MAX_BACK_LEVELS {
+            back.drain(..back.len() - MAX_BACK_LEVELS);
+        }
+        SessionState {
+            query: query.to_string(),
+            context: Some(context.to_string()),
+            back,
+        }
+    }
+
+    pub fn applies_to(&self, raw_query: &str) -> bool {
+        let query = self.query.trim();
+        !query.is_empty() && raw_query.trim_start().starts_with(query)
+    }
+
+    /// 上一层的查询，以及回到那里后的会话（已回到最初一层时为 None）
+    pub fn back(&self) -> Option<(&str, Option<SessionState>)> {
+        let ((query, context), rest) = self.back.split_last()?;
+        let state = (!rest.is_empty()).then(|| SessionState {
+            query: query.clone(),
+            context: context.clone(),
+            back: rest.to_vec(),
+        });
+        Some((query.as_str(), state))
+    }
+
+    /// `search:` 动作的 arg：`search:<查询><TAB><会话状态>`，状态为空时 run.sh 清除会话
+    pub fn search_arg(query: &str, state: Option<&SessionState>) -> String {
+        format!(
+            "search:{}\t{}",
+            query,
+            state.map(SessionState::encode).unwrap_or_default()
+        )
+    }
+
+    pub fn encode(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn decode(raw: &str) -> Option<Self> {
+        serde_json::from_str(raw.trim()).ok()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredSession {
+    saved_at: u64,
+    state: SessionState,
+}
+
+/// 会话状态文件（`<缓存目录>/session.json`）
+pub struct SessionStore {
+    path: PathBuf,
+}
+
+impl SessionStore {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            path: cache_dir.join("session.json"),
+        }
+    }
+
+    pub fn save(&self, state: &SessionState, now: u64) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let stored = StoredSession {
+            saved_at: now,
+            state: state.clone(),
+        };
+        std::fs::write(&self.path, serde_json::to_vec(&stored)?)
+    }
+
+    /// 过期或损坏的状态视为没有会话
+    pub fn load(&self, now: u64) -> Option<SessionState> {
+        let stored: StoredSession =
+            serde_json::from_slice(&std::fs::read(&self.path).ok()?).ok()?;
+        (now.saturating_sub(stored.saved_at) <= SESSION_TTL_SECS).then_some(stored.state)
+    }
+
+    pub fn clear(&self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn navigation_builds_a_back_stack_that_unwinds() {
+        let first = SessionState::navigate(None, "rust", "folder:Work ", "同目录: Work");
+        assert!(first.applies_to("folder:Work axum"));
+        assert!(!first.applies_to("tokio"));
+        let (query, state) = first.back().expect("back");
+        assert_eq!((query, state), ("rust", None));
+
+        let second = SessionState::navigate(
+            Some(&first),
+            "folder:Work axum",
+            "docs.rs",
+            "同站点: docs.rs",
+        );
+        let (query, state) = second.back().expect("back");
+        assert_eq!(query, "folder:Work axum");
+        let state = state.expect("still in session");
+        assert_eq!(state.context.as_deref(), Some("同目录: Work"));
+        assert_eq!(state.back().expect("back").0, "rust");
+
+        // 来路不在当前会话内时重新开始
+        let fresh = SessionState::navigate(Some(&second), "bevy", "tag:game ", "标签: game");
+        assert_eq!(fresh.back, vec![("bevy".to_string(), None)]);
+
+        let arg = SessionState::search_arg("tag:game ", Some(&fresh));
+        let (query, encoded) = arg
+            .strip_prefix("search:")
+            .and_then(|rest| rest.split_once('\t'))
+            .expect("arg");
+        assert_eq!(query, "tag:game ");
+        assert_eq!(SessionState::decode(encoded), Some(fresh));
+        assert!(SessionState::search_arg("rust", None).ends_with('\t'));
+    }
+
+    #[test]
+    fn stored_session_expires() {
+        let dir = tempdir().expect("tempdir");
+        let store = SessionStore::new(dir.path());
+        assert!(store.load(100).is_none());
+
+        let state = SessionState::navigate(None, "rust", "folder:Work ", "同目录: Work");
+        store.save(&state, 100).expect("save");
+        assert_eq!(store.load(100 + SESSION_TTL_SECS), Some(state));
+        assert!(store.load(101 + SESSION_TTL_SECS).is_none());
+        store.clear();
+        assert!(store.load(100).is_none());
+    }
+}
@@ -0,0 +1,314 @@
+use crate::bookmark::ChromeBookmark;
+use crate::tags::{TagEdit, TagManager};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// 团队共享标签：共享目录（网络卷或同步盘）中每个成员只追加写自己的 `<成员>.jsonl` 编辑日志，
+/// 从不改动别人的文件，同步盘因此不会产生冲突副本。
+///
+/// 合并时每个 (URL, 标签) 取最新的一次编辑：先比时间，同一毫秒再比成员名，仍相同则添加优先。
+/// 结果与读取顺序、重复读取无关，各成员同步后得到同一份标签。
+pub struct SharedTags {
+    dir: PathBuf,
+    member: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct LogLine {
+    #[serde(flatten)]
+    edit: TagEdit,
+    by: String,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// 本次写入共享目录的本地编辑数
+    pub published: usize,
+    /// 读到编辑日志的成员数（含自己）
+    pub members: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl SharedTags {
+    pub fn new(dir: &Path, member: &str) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            member: sanitize_member(member),
+        }
+    }
+
+    fn log_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", self.member))
+    }
+
+    /// 发布本地编辑，读取全部成员的日志合并后应用到本地标签库
+    pub fn sync(
+        &self,
+        tags: &TagManager,
+        live: &[ChromeBookmark],
+    ) -> Result<SyncReport, Box<dyn std::error::Error>> {
+        let pending = tags.unpublished_edits()?;
+        let edits: Vec<TagEdit> = pending.iter().map(|(_, edit)| edit.clone()).collect();
+        self.publish(&edits)?;
+        if let Some((through, _)) = pending.last() {
+            tags.mark_published(*through)?;
+        }
+
+        let (lines, members) = self.read_all()?;
+        let (added, removed) = tags.apply_shared(&merge(lines), live)?;
+        Ok(SyncReport {
+            published: edits.len(),
+            members,
+            added,
+            removed,
+        })
+    }
+
+    /// 追加到自己的日志；上次写入被打断留下的半行先补上换行，避免与新内容粘在一起
+    fn publish(&self, edits: &[TagEdit]) -> std::io::Result<()> {
+        if edits.is_empty() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        let mut file = std::fs::File::options()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(self.log_path())?;
+        let mut out = Vec::new();
+        if file.seek(SeekFrom::End(0))? > 0 {
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                out.push(b'\n');
+            }
+        }
+        for edit in edits {
+            let line = LogLine {
+                edit: edit.clone(),
+                by: self.member.clone(),
+            };
+            serde_json::to_writer(&mut out, &line)?;
+            out.push(b'\n');
+        }
+        file.write_all(&out)?;
+        file.sync_all()
+    }
+
+    /// 全部成员的编辑与成员数；无法解析的行（同步到一半、手工改坏）跳过
+    fn read_all(&self) -> std::io::Result<(Vec<LogLine>, usize)> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+            Err(err) => return Err(err),
+        };
+        let mut lines = Vec::new();
+        let mut members = 0;
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        {
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            members += 1;
+            lines.extend(
+                content
+                    .lines()
+                    .filter_map(|line| serde_json::from_str::<LogLine>(line).ok()),
+            );
+        }
+        Ok((lines, members))
+    }
+}
+
+/// 每个 (URL, 标签) 的最终状态，按 URL 与标签排序
+fn merge(lines: impl IntoIterator<Item = LogLine>) -> Vec<TagEdit> {
+    let mut winners: HashMap<(String, String), LogLine> = HashMap::new();
+    for line in lines {
+        let key = (line.edit.url.clone(), line.edit.tag.to_lowercase());
+        let wins = winners.get(&key).is_none_or(|current| {
+            (line.edit.at, &line.by, line.edit.added)
+                > (current.edit.at, &current.by, current.edit.added)
+        });
+        if wins {
+            winners.insert(key, line);
+        }
+    }
+    let mut state: Vec<((String, String), TagEdit)> = winners
+        .into_iter()
+        .map(|(key, line)| (key, line.edit))
+        .collect();
+    state.sort_by(|a, b| a.0.cmp(&b.0));
+    state.into_iter().map(|(_, edit)| edit).collect()
+}
+
+/// 本机在共享目录中的成员名：优先用配置，否则首次使用时生成 `<用户名>-<随机后缀>` 并记在标签库中。
+/// 同一个人的多台电脑因此各写各的日志
+pub fn member_name(tags: &TagManager, configured: Option<&str>) -> rusqlite::Result<String> {
+    if let Some(member) = configured
+        .map(str::trim)
+        .filter(|member| !member.is_empty())
+    {
+        return Ok(sanitize_member(member));
+    }
+    if let Some(member) = tags.meta_value("shared_member")? {
+        return Ok(member);
+    }
+    let user = std::env::var("USER").unwrap_or_else(|_| "member".to_string());
+    let suffix = RandomState::new().hash_one(&user) & 0xff_ffff;
+    let member = sanitize_member(&format!("{}-{:06x}", user, suffix));
+    tags.set_meta_value("shared_member", &member)?;
+    Ok(member)
+}
+
+/// 成员名用作文件名，只保留字母数字与 `-_.`
+fn sanitize_member(member: &str) -> String {
+    let cleaned: String = member
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches('.').to_string();
+    if cleaned.is_empty() {
+        "member".to_string()
+    } else {
+        cleaned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+    use crate::tags::tag_key;
+    use tempfile::tempdir;
+
+    fn line(url: &str, tag: &str, added: bool, at: u64, by: &str) -> LogLine {
+        LogLine {
+            edit: TagEdit {
+                url: url.to_string(),
+                tag: tag.to_string(),
+                added,
+                at,
+            },
+            by: by.to_string(),
+        }
+    }
+
+    fn bookmark(id: &str, url: &str) -> ChromeBookmark {
+        ChromeBookmark {
+            guid: Some(format!("guid-{}", id)),
+            ..test_bookmark(id, id, url, None)
+        }
+    }
+
+    #[test]
+    fn merge_keeps_latest_edit_and_is_order_independent() {
+        let lines = vec![
+            line("https://a", "Rust", true, 10, "alice"),
+            line("https://a", "rust", false, 20, "bob"),
+            line("https://b", "docs", false, 30, "alice"),
+            line("https://b", "docs", true, 30, "alice"),
+            line("https://c", "ops", true, 40, "bob"),
+            line("https://c", "ops", false, 40, "alice"),
+        ];
+        let forward = merge(lines.clone());
+        let backward = merge(lines.into_iter().rev());
+        assert_eq!(forward, backward);
+        let state: Vec<(&str, bool)> = forward
+            .iter()
+            .map(|edit| (edit.url.as_str(), edit.added))
+            .collect();
+        // 后发生的移除胜出；同一时刻添加优先；同一时刻不同成员按成员名决定
+        assert_eq!(
+            state,
+            vec![
+                ("https://a", false),
+                ("https://b", true),
+                ("https://c", true)
+            ]
+        );
+    }
+
+    #[test]
+    fn members_converge_through_the_shared_directory() {
+        let dir = tempdir().expect("tempdir");
+        let shared = dir.path().join("team");
+        let alice_tags = TagManager::new(dir.path().join("alice.db")).expect("alice");
+        let bob_tags = TagManager::new(dir.path().join("bob.db")).expect("bob");
+        let alice = SharedTags::new(&shared, "alice");
+        let bob = SharedTags::new(&shared, "bob/laptop");
+        // 两人的书签 id 不同，按 URL 对应
+        let alice_live = vec![bookmark("chrome:Default:1", "https://docs.rs")];
+        let bob_live = vec![
+            bookmark("arc:Default:9", "https://docs.rs"),
+            bookmark("arc:Default:10", "https://crates.io"),
+        ];
+
+        alice_tags
+            .add_tags(
+                &tag_key(&alice_live[0]),
+                "https://docs.rs",
+                &["rust".into()],
+            )
+            .expect("tag");
+        let report = alice.sync(&alice_tags, &alice_live).expect("sync");
+        assert_eq!((report.published, report.members), (1, 1));
+        assert!(shared.join("alice.jsonl").is_file());
+
+        let report = bob.sync(&bob_tags, &bob_live).expect("sync");
+        assert_eq!((report.members, report.added), (1, 1));
+        assert_eq!(
+            bob_tags.tags_for(&tag_key(&bob_live[0])).unwrap(),
+            vec!["rust"]
+        );
+
+        bob_tags
+            .remove_tags(&tag_key(&bob_live[0]), &["RUST".into()])
+            .expect("remove");
+        bob_tags
+            .add_tags(
+                &tag_key(&bob_live[1]),
+                "https://crates.io",
+                &["rust".into()],
+            )
+            .expect("tag");
+        let report = bob.sync(&bob_tags, &bob_live).expect("sync");
+        assert_eq!((report.published, report.members), (2, 2));
+        assert!(shared.join("bob_laptop.jsonl").is_file());
+
+        let report = alice.sync(&alice_tags, &alice_live).expect("sync");
+        assert_eq!((report.published, report.removed), (0, 1));
+        assert!(alice_tags
+            .tags_for(&tag_key(&alice_live[0]))
+            .unwrap()
+            .is_empty());
+        // 再次同步没有变化
+        let report = alice.sync(&alice_tags, &alice_live).expect("sync");
+        assert_eq!((report.added, report.removed), (0, 0));
+    }
+
+    #[test]
+    fn member_name_is_generated_once_and_sanitized() {
+        let dir = tempdir().expect("tempdir");
+        let tags = TagManager::new(dir.path().join("tags.db")).expect("tags");
+        let generated = member_name(&tags, None).expect("member");
+        assert_eq!(member_name(&tags, None).expect("member"), generated);
+        assert_eq!(
+            member_name(&tags, Some(" ../a b ")).expect("member"),
+            "_a_b"
+        );
+    }
+}
@@ -0,0 +1,1369 @@
+use crate::bookmark::ChromeBookmark;
+use crate::report_render::{Exportable, ReportTable};
+use crate::schema::{self, Migration};
+use crate::tokenize::{analyze, Tokenizer};
+use crate::url_normalize::{canonical_url, canonical_url_hash};
+use rusqlite::{params, params_from_iter, Connection, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 本地标签、备注与置顶存储（tags.db），与可重建的书签索引分离，刷新索引不会丢失
+pub struct TagManager {
+    conn: Connection,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// (旧书签键, 新书签键, 标签)：书签键变化（如旧版按 GUID 记录的键）但 URL 仍存在，标签随之迁移
+    pub remapped: Vec<(String, String, String)>,
+    /// (书签键, 标签)：书签已不存在于任何来源
+    pub orphaned: Vec<(String, String)>,
+    /// 没有任何书签使用的标签
+    pub unused_tags: Vec<String>,
+}
+
+/// `report tags` 的一行：标签及其书签数
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+impl Exportable for Vec<TagCount> {
+    fn summary(&self) -> String {
+        format!("标签用量报告（{} 个标签）", self.len())
+    }
+
+    fn table(&self) -> ReportTable {
+        ReportTable {
+            title: format!("标签用量（前 {} 个）", self.len()),
+            notes: Vec::new(),
+            columns: vec!["tag", "count"],
+            rows: self
+                .iter()
+                .map(|row| vec![row.tag.clone(), row.count.to_string()])
+                .collect(),
+        }
+    }
+}
+
+/// 经常一起出现的两个标签
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TagPair {
+    /// 按名称排序的两个标签
+    pub tags: (String, String),
+    /// 同时带有两个标签的书签数
+    pub count: usize,
+    /// 共同书签占较少一方的比例（%）；100 表示一个标签总是伴随另一个出现，可考虑合并
+    pub overlap: f64,
+}
+
+impl Exportable for Vec<TagPair> {
+    fn summary(&self) -> String {
+        format!("标签共现报告（{} 对）", self.len())
+    }
+
+    fn table(&self) -> ReportTable {
+        ReportTable {
+            title: format!("标签共现（前 {} 对）", self.len()),
+            notes: vec!["重合度：共同书签占较少一方的比例".to_string()],
+            columns: vec!["tag_a", "tag_b", "count", "overlap"],
+            rows: self
+                .iter()
+                .map(|pair| {
+                    vec![
+                        pair.tags.0.clone(),
+                        pair.tags.1.clone(),
+                        pair.count.to_string(),
+                        format!("{:.1}", pair.overlap),
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 一次标签编辑：给某 URL 加上或去掉标签，`at` 为 Unix 毫秒。本地记在 `tag_log` 中，供共享标签同步发布
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagEdit {
+    pub url: String,
+    pub tag: String,
+    pub added: bool,
+    pub at: u64,
+}
+
+impl PruneReport {
+    pub fn is_empty(&self) -> bool {
+        self.remapped.is_empty() && self.orphaned.is_empty() && self.unused_tags.is_empty()
+    }
+}
+
+impl TagManager {
+    pub fn new(db_path: PathBuf) -> Result<Self> {
+        let conn = crate::db_crypt::open(&db_path, crate::db_crypt::key())?;
+        let _ = conn.busy_timeout(Duration::from_millis(500));
+
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;",
+        )?;
+
+        // 标签是用户数据，不能像书签索引那样删表重建：迁移失败时该步回滚，原样报错
+        schema::migrate(&conn, TAG_MIGRATIONS)?;
+
+        Ok(Self { conn })
+    }
+
+    /// 书签的标签名（按名称排序），`bookmark_key` 由 `tag_key` 得到
+    pub fn tags_for(&self, bookmark_key: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.name FROM bookmark_tags bt
+             JOIN tags t ON t.id = bt.tag_id
+             WHERE bt.bookmark_key = ?1
+             ORDER BY t.name COLLATE NOCASE",
+        )?;
+        let rows = stmt.query_map(params![bookmark_key], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// 一页结果中各书签的标签名，按 `bookmark_key` 分组（组内按名称排序）；没有标签的书签不出现
+    pub fn tags_for_keys(&self, bookmark_keys: &[&str]) -> Result<HashMap<String, Vec<String>>> {
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        if bookmark_keys.is_empty() {
+            return Ok(tags);
+        }
+        let sql = format!(
+            "SELECT bt.bookmark_key, t.name FROM bookmark_tags bt
+             JOIN tags t ON t.id = bt.tag_id
+             WHERE bt.bookmark_key IN ({})
+             ORDER BY t.name COLLATE NOCASE",
+            vec!["?"; bookmark_keys.len()].join(", ")
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(bookmark_keys), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (key, name) = row?;
+            tags.entry(key).or_default().push(name);
+        }
+        Ok(tags)
+    }
+
+    /// 各标签关联的书签数：数量降序，同数按名称排序；没有书签的标签不列出
+    pub fn tag_counts(&self) -> Result<Vec<(String, usize)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.name, COUNT(*) AS uses FROM bookmark_tags bt
+             JOIN tags t ON t.id = bt.tag_id
+             GROUP BY t.id
+             ORDER BY uses DESC, t.name COLLATE NOCASE",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?;
+        rows.collect()
+    }
+
+    /// 标签共现：同时出现在同一书签上次数最多的 `limit` 对标签
+    pub fn tag_pairs(&self, limit: usize) -> Result<Vec<TagPair>> {
+        let mut stmt = self.conn.prepare(
+            "WITH uses AS (
+                SELECT tag_id, COUNT(*) AS n FROM bookmark_tags GROUP BY tag_id
+             )
+             SELECT ta.name, tb.name, COUNT(*) AS together, MIN(ua.n, ub.n)
+             FROM bookmark_tags a
+             JOIN bookmark_tags b ON b.bookmark_key = a.bookmark_key AND b.tag_id > a.tag_id
+             JOIN tags ta ON ta.id = a.tag_id
+             JOIN tags tb ON tb.id = b.tag_id
+             JOIN uses ua ON ua.tag_id = a.tag_id
+             JOIN uses ub ON ub.tag_id = b.tag_id
+             GROUP BY a.tag_id, b.tag_id
+             ORDER BY together DESC, ta.name COLLATE NOCASE, tb.name COLLATE NOCASE
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            let (first, second): (String, String) = (row.get(0)?, row.get(1)?);
+            let count = row.get::<_, i64>(2)? as usize;
+            let smaller = row.get::<_, i64>(3)?.max(1) as usize;
+            Ok(TagPair {
+                tags: if first.to_lowercase() <= second.to_lowercase() {
+                    (first, second)
+                } else {
+                    (second, first)
+                },
+                count,
+                overlap: (count * 1_000 / smaller) as f64 / 10.0,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 为书签添加标签（不存在的标签自动创建，已有的关联保持不变）
+    pub fn add_tags(&self, bookmark_key: &str, bookmark_url: &str, names: &[String]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let now = now_ms();
+        for name in names
+            .iter()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+        {
+            tx.execute(
+                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+                params![name],
+            )?;
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO bookmark_tags (bookmark_key, bookmark_url, tag_id)
+                 SELECT ?1, ?2, id FROM tags WHERE name = ?3",
+                params![bookmark_key, bookmark_url, name],
+            )?;
+            if inserted > 0 && !bookmark_url.is_empty() {
+                log_edit(&tx, bookmark_url, name, true, now)?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// 去掉书签上的标签，返回实际移除的个数；标签本身留给 `prune` 清理
+    pub fn remove_tags(&self, bookmark_key: &str, names: &[String]) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let now = now_ms();
+        let mut removed = 0;
+        for name in names
+            .iter()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+        {
+            let found: Option<(i64, Option<String>, String)> = tx
+                .query_row(
+                    "SELECT bt.tag_id, bt.bookmark_url, t.name FROM bookmark_tags bt
+                     JOIN tags t ON t.id = bt.tag_id
+                     WHERE bt.bookmark_key = ?1 AND t.name = ?2",
+                    params![bookmark_key, name],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .map(Some)
+                .or_else(|err| match err {
+                    rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                    err => Err(err),
+                })?;
+            let Some((tag_id, url, tag)) = found else {
+                continue;
+            };
+            tx.execute(
+                "DELETE FROM bookmark_tags WHERE bookmark_key = ?1 AND tag_id = ?2",
+                params![bookmark_key, tag_id],
+            )?;
+            if let Some(url) = url.filter(|url| !url.is_empty()) {
+                log_edit(&tx, &url, &tag, false, now)?;
+            }
+            removed += 1;
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// 书签的备注，`bookmark_key` 由 `tag_key` 得到
+    pub fn note_for(&self, bookmark_key: &str) -> Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT body FROM notes WHERE bookmark_key = ?1")?;
+        let mut rows = stmt.query_map(params![bookmark_key], |row| row.get(0))?;
+        rows.next().transpose()
+    }
+
+    /// 一页结果中各书签的备注，按 `bookmark_key` 索引；没有备注的书签不出现
+    pub fn notes_for_keys(&self, bookmark_keys: &[&str]) -> Result<HashMap<String, String>> {
+        if bookmark_keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let sql = format!(
+            "SELECT bookmark_key, body FROM notes WHERE bookmark_key IN ({})",
+            vec!["?"; bookmark_keys.len()].join(", ")
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(bookmark_keys), |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        rows.collect()
+    }
+
+    /// 设置书签备注，替换原有内容并同步 `note_text` 全文索引；`tokenizers` 与标题列的附加分词方式一致
+    pub fn set_note(
+        &self,
+        bookmark_key: &str,
+        bookmark_url: &str,
+        body: &str,
+        tokenizers: &[Tokenizer],
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO notes (bookmark_key, bookmark_url, body, updated_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![bookmark_key, bookmark_url, body, now_ms() as i64],
+        )?;
+        tx.execute(
+            "DELETE FROM note_text WHERE bookmark_key = ?1",
+            params![bookmark_key],
+        )?;
+        tx.execute(
+            "INSERT INTO note_text (bookmark_key, body) VALUES (?1, ?2)",
+            params![bookmark_key, analyze(body, tokenizers)],
+        )?;
+        tx.commit()
+    }
+
+    /// 删除书签备注，返回原来是否有备注
+    pub fn remove_note(&self, bookmark_key: &str) -> Result<bool> {
+        let tx = self.conn.unchecked_transaction()?;
+        let removed = tx.execute(
+            "DELETE FROM notes WHERE bookmark_key = ?1",
+            params![bookmark_key],
+        )?;
+        tx.execute(
+            "DELETE FROM note_text WHERE bookmark_key = ?1",
+            params![bookmark_key],
+        )?;
+        tx.commit()?;
+        Ok(removed > 0)
+    }
+
+    /// 置顶书签，返回是否新置顶（已置顶时为 false）
+    pub fn pin(&self, bookmark_key: &str, bookmark_url: &str) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO pinned (bookmark_key, bookmark_url, pinned_at)
+             VALUES (?1, ?2, ?3)",
+            params![bookmark_key, bookmark_url, now_ms() as i64],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// 取消置顶，返回原来是否已置顶
+    pub fn unpin(&self, bookmark_key: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM pinned WHERE bookmark_key = ?1",
+            params![bookmark_key],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn is_pinned(&self, bookmark_key: &str) -> Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pinned WHERE bookmark_key = ?1)",
+            params![bookmark_key],
+            |row| row.get(0),
+        )
+    }
+
+    /// 标签改名；新名称已存在（不区分大小写）时合并进该标签。返回涉及的书签数，没有 `from` 标签时为 None
+    pub fn rename_tag(&self, from: &str, to: &str) -> Result<Option<usize>> {
+        let to = to.trim();
+        let tx = self.conn.unchecked_transaction()?;
+        let found: Option<(i64, String)> = tx
+            .query_row(
+                "SELECT id, name FROM tags WHERE name = ?1",
+                params![from.trim()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map(Some)
+            .or_else(|err| match err {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                err => Err(err),
+            })?;
+        let Some((from_id, from_name)) = found else {
+            return Ok(None);
+        };
+
+        let tagged: Vec<(String, Option<String>)> = {
+            let mut stmt = tx.prepare(
+                "SELECT bookmark_key, bookmark_url FROM bookmark_tags WHERE tag_id = ?1",
+            )?;
+            let rows = stmt.query_map(params![from_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+
+        tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![to])?;
+        let (to_id, to_name): (i64, String) = tx.query_row(
+            "SELECT id, name FROM tags WHERE name = ?1",
+            params![to],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if to_id == from_id {
+            // 只改大小写：关联不变，也不算编辑
+            tx.execute(
+                "UPDATE tags SET name = ?1 WHERE id = ?2",
+                params![to, from_id],
+            )?;
+            tx.commit()?;
+            return Ok(Some(tagged.len()));
+        }
+
+        tx.execute(
+            "INSERT OR IGNORE INTO bookmark_tags (bookmark_key, bookmark_url, tag_id)
+             SELECT bookmark_key, bookmark_url, ?2 FROM bookmark_tags WHERE tag_id = ?1",
+            params![from_id, to_id],
+        )?;
+        tx.execute(
+            "DELETE FROM bookmark_tags WHERE tag_id = ?1",
+            params![from_id],
+        )?;
+        tx.execute("DELETE FROM tags WHERE id = ?1", params![from_id])?;
+
+        let now = now_ms();
+        for url in tagged.iter().filter_map(|(_, url)| url.as_deref()) {
+            if !url.is_empty() {
+                log_edit(&tx, url, &from_name, false, now)?;
+                log_edit(&tx, url, &to_name, true, now)?;
+            }
+        }
+        tx.commit()?;
+        Ok(Some(tagged.len()))
+    }
+
+    /// 尚未发布的编辑（按发生顺序）及其序号。首次调用时把已有的全部标签关联记为一次添加，
+    /// 使启用共享之前打的标签也能发布出去
+    pub fn unpublished_edits(&self) -> Result<Vec<(i64, TagEdit)>> {
+        if self.meta_value("edit_log_seeded")?.is_none() {
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute(
+                "INSERT INTO tag_log (url, tag, added, at)
+                 SELECT DISTINCT bt.bookmark_url, t.name, 1, ?1 FROM bookmark_tags bt
+                 JOIN tags t ON t.id = bt.tag_id
+                 WHERE bt.bookmark_url IS NOT NULL AND bt.bookmark_url != ''
+                   AND NOT EXISTS (
+                       SELECT 1 FROM tag_log l
+                       WHERE l.url = bt.bookmark_url AND l.tag = t.name COLLATE NOCASE
+                   )",
+                params![now_ms() as i64],
+            )?;
+            tx.execute(
+                "INSERT OR REPLACE INTO tag_meta (key, value) VALUES ('edit_log_seeded', '1')",
+                [],
+            )?;
+            tx.commit()?;
+        }
+        let mut stmt = self
+            .conn
+            .prepare("SELECT seq, url, tag, added, at FROM tag_log ORDER BY seq")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                TagEdit {
+                    url: row.get(1)?,
+                    tag: row.get(2)?,
+                    added: row.get(3)?,
+                    at: row.get::<_, i64>(4)? as u64,
+                },
+            ))
+        })?;
+        rows.collect()
+    }
+
+    /// 序号不超过 `through` 的编辑已写入共享目录
+    pub fn mark_published(&self, through: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM tag_log WHERE seq <= ?1", params![through])?;
+        Ok(())
+    }
+
+    /// 把合并后的共享状态落到本地书签上（按 URL 对应，同一 URL 的多个书签一并处理），不产生新的编辑记录。
+    /// 返回（新增，移除）的关联数
+    pub fn apply_shared(
+        &self,
+        state: &[TagEdit],
+        live: &[ChromeBookmark],
+    ) -> Result<(usize, usize)> {
+        let mut keys_by_url: HashMap<&str, Vec<String>> = HashMap::new();
+        for bookmark in live {
+            keys_by_url
+                .entry(bookmark.url.as_str())
+                .or_default()
+                .push(tag_key(bookmark));
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        let (mut added, mut removed) = (0, 0);
+        for edit in state {
+            if edit.added {
+                let Some(keys) = keys_by_url.get(edit.url.as_str()) else {
+                    continue;
+                };
+                tx.execute(
+                    "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+                    params![edit.tag],
+                )?;
+                for key in keys {
+                    added += tx.execute(
+                        "INSERT OR IGNORE INTO bookmark_tags (bookmark_key, bookmark_url, tag_id)
+                         SELECT ?1, ?2, id FROM tags WHERE name = ?3",
+                        params![key, edit.url, edit.tag],
+                    )?;
+                }
+            } else {
+                removed += tx.execute(
+                    "DELETE FROM bookmark_tags
+                     WHERE bookmark_url = ?1
+                       AND tag_id IN (SELECT id FROM tags WHERE name = ?2)",
+                    params![edit.url, edit.tag],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok((added, removed))
+    }
+
+    pub fn meta_value(&self, key: &str) -> Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT value FROM tag_meta WHERE key = ?1")?;
+        let mut rows = stmt.query_map(params![key], |row| row.get(0))?;
+        rows.next().transpose()
+    }
+
+    pub fn set_meta_value(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO tag_meta (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// 至少带一个标签的书签键（与 `tag_key` 比较）
+    pub fn tagged_keys(&self) -> Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT bookmark_key FROM bookmark_tags")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// 是否还有旧版按书签 id、GUID 或原始规范 URL 记录，尚未换成 `tag_key` 的关联
+    pub fn has_legacy_keys(&self) -> Result<bool> {
+        Ok(self.meta_value("legacy_keys")?.is_some())
+    }
+
+    /// 把旧版的关联换成 `tag_key`：旧键是仍在索引（`live` 取自 bookmarks 表）中的书签 id 或
+    /// `guid:<GUID>` 时取该书签当前 URL 的键，否则按关联中保存的 URL 计算，书签重新出现时自动接上。
+    /// 返回迁移的关联数
+    ///
+    /// 既没有匹配书签也没有 URL 的关联原样保留，留给 `prune` 报告为孤立
+    pub fn migrate_legacy_keys(&self, live: &[ChromeBookmark]) -> Result<usize> {
+        if !self.has_legacy_keys()? {
+            return Ok(0);
+        }
+        let mut key_by_old_key: HashMap<String, String> = HashMap::new();
+        for bookmark in live {
+            key_by_old_key.insert(bookmark.id.clone(), tag_key(bookmark));
+            if let Some(guid) = bookmark.guid.as_deref().filter(|guid| !guid.is_empty()) {
+                key_by_old_key.insert(format!("guid:{}", guid), tag_key(bookmark));
+            }
+        }
+
+        let legacy: Vec<(String, Option<String>, i64)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT bookmark_key, bookmark_url, tag_id FROM bookmark_tags
+                 WHERE bookmark_key NOT LIKE 'urlhash:%'",
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut migrated = 0;
+        for (old_key, url, tag_id) in &legacy {
+            let url = url
+                .as_deref()
+                .filter(|url| !url.is_empty())
+                .or_else(|| old_key.strip_prefix("url:"));
+            let new_key = key_by_old_key
+                .get(old_key)
+                .cloned()
+                .or_else(|| url.map(bookmark_key));
+            let Some(new_key) = new_key else {
+                continue;
+            };
+            tx.execute(
+                "INSERT OR IGNORE INTO bookmark_tags (bookmark_key, bookmark_url, tag_id)
+                 VALUES (?1, ?2, ?3)",
+                params![new_key, url, tag_id],
+            )?;
+            tx.execute(
+                "DELETE FROM bookmark_tags WHERE bookmark_key = ?1 AND tag_id = ?2",
+                params![old_key, tag_id],
+            )?;
+            migrated += 1;
+        }
+        tx.execute("DELETE FROM tag_meta WHERE key = 'legacy_keys'", [])?;
+        tx.commit()?;
+        Ok(migrated)
+    }
+
+    /// 清理指向已删除书签的标签关联，并找出未被使用的标签
+    /// 书签键失效但规范 URL 仍能在当前索引中找到时，将关联迁移到新键而不是删除
+    pub fn prune(&self, live: &[ChromeBookmark], dry_run: bool) -> Result<PruneReport> {
+        let live_keys: HashSet<String> = live.iter().map(tag_key).collect();
+        let key_by_url = keys_by_canonical_url(live);
+
+        let links: Vec<(String, Option<String>, i64, String)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT bt.bookmark_key, bt.bookmark_url, bt.tag_id, t.name
+                 FROM bookmark_tags bt
+                 JOIN tags t ON t.id = bt.tag_id
+                 ORDER BY bt.bookmark_key, t.name",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+
+        let mut report = PruneReport::default();
+        let mut remaining_tag_ids: HashSet<i64> = HashSet::new();
+
+        self.conn.execute_batch("BEGIN IMMEDIATE;")?;
+        let result: Result<()> = (|| {
+            for (bookmark_key, bookmark_url, tag_id, tag_name) in &links {
+                if live_keys.contains(bookmark_key) {
+                    remaining_tag_ids.insert(*tag_id);
+                    continue;
+                }
+
+                let new_key = bookmark_url
+                    .as_deref()
+                    .and_then(|url| key_by_url.get(&canonical_url(url)));
+
+                match new_key {
+                    Some(new_key) => {
+                        remaining_tag_ids.insert(*tag_id);
+                        report.remapped.push((
+                            bookmark_key.clone(),
+                            new_key.clone(),
+                            tag_name.clone(),
+                        ));
+                        if !dry_run {
+                            self.conn.execute(
+                                "INSERT OR IGNORE INTO bookmark_tags (bookmark_key, bookmark_url, tag_id)
+                                 VALUES (?1, ?2, ?3)",
+                                params![new_key, bookmark_url, tag_id],
+                            )?;
+                        }
+                    }
+                    None => {
+                        report
+                            .orphaned
+                            .push((bookmark_key.clone(), tag_name.clone()));
+                    }
+                }
+
+                if !dry_run {
+                    self.conn.execute(
+                        "DELETE FROM bookmark_tags WHERE bookmark_key = ?1 AND tag_id = ?2",
+                        params![bookmark_key, tag_id],
+                    )?;
+                }
+            }
+
+            let all_tags: Vec<(i64, String)> = {
+                let mut stmt = self
+                    .conn
+                    .prepare("SELECT id, name FROM tags ORDER BY name")?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<Result<Vec<_>>>()?
+            };
+
+            for (tag_id, name) in all_tags {
+                if remaining_tag_ids.contains(&tag_id) {
+                    continue;
+                }
+                report.unused_tags.push(name);
+                if !dry_run {
+                    self.conn
+                        .execute("DELETE FROM tags WHERE id = ?1", params![tag_id])?;
+                }
+            }
+
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => {
+                self.conn.execute_batch("COMMIT;")?;
+                Ok(report)
+            }
+            Err(err) => {
+                let _ = self.conn.execute_batch("ROLLBACK;");
+                Err(err)
+            }
+        }
+    }
+}
+
+/// 标签关联用的书签键：`urlhash:<规范 URL 摘要>`，见 `canonical_url_hash`
+///
+/// Chromium 的数字 id 会在同步、导入后重新分配，GUID 在书签删除后重建、重新导入时也会变；
+/// 只要地址不变，按规范 URL 得到的键就不变，同一 URL 的书签共用一组标签
+pub fn tag_key(bookmark: &ChromeBookmark) -> String {
+    bookmark_key(&bookmark.url)
+}
+
+/// 尚无 `ChromeBookmark` 时（如刚写入浏览器的书签）按 URL 直接得到 `tag_key`
+pub fn bookmark_key(url: &str) -> String {
+    format!("urlhash:{}", canonical_url_hash(url))
+}
+
+/// 规范 URL → 该 URL 第一个书签的键
+fn keys_by_canonical_url(live: &[ChromeBookmark]) -> HashMap<String, String> {
+    let mut keys = HashMap::new();
+    for bookmark in live {
+        keys.entry(canonical_url(&bookmark.url))
+            .or_insert_with(|| tag_key(bookmark));
+    }
+    keys
+}
+
+/// 标签库的结构迁移，按版本号升序；新增列或表时在末尾追加一步，不要改动已发布的步骤
+const TAG_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        apply: create_tag_tables,
+    },
+    Migration {
+        version: 2,
+        apply: key_tags_by_bookmark_key,
+    },
+    Migration {
+        version: 3,
+        apply: create_tag_log,
+    },
+    Migration {
+        version: 4,
+        apply: key_tags_by_url_hash,
+    },
+    Migration {
+        version: 5,
+        apply: create_notes,
+    },
+    Migration {
+        version: 6,
+        apply: create_pinned,
+    },
+];
+
+/// 版本 1：标签与书签关联表。新库直接建成当前结构，之后的步骤对其为空操作
+fn create_tag_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS tag_meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+         CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE
+         );
+         CREATE TABLE IF NOT EXISTS bookmark_tags (
+            bookmark_key TEXT NOT NULL,
+            bookmark_url TEXT,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (bookmark_key, tag_id)
+         );
+         CREATE INDEX IF NOT EXISTS idx_bookmark_tags_tag ON bookmark_tags(tag_id);",
+    )
+}
+
+/// 版本 2：bookmark_key 见 `tag_key`；旧版按书签 id 关联，原样改名后由 `migrate_legacy_keys` 换成新键
+fn key_tags_by_bookmark_key(conn: &Connection) -> Result<()> {
+    if !table_has_column(conn, "bookmark_tags", "bookmark_id")? {
+        return Ok(());
+    }
+    conn.execute_batch(
+        "ALTER TABLE bookmark_tags RENAME COLUMN bookmark_id TO bookmark_key;
+         INSERT OR REPLACE INTO tag_meta (key, value) VALUES ('legacy_keys', '1');",
+    )
+}
+
+/// 版本 3：手动添加 / 移除标签的记录；发布到共享目录后删除。`prune` 与同步带来的变化不记录
+fn create_tag_log(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS tag_log (
+            seq INTEGER PRIMARY KEY,
+            url TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            added INTEGER NOT NULL,
+            at INTEGER NOT NULL
+         );",
+    )
+}
+
+/// 版本 4：bookmark_key 从 GUID / 规范 URL 改为规范 URL 摘要（见 `tag_key`）。GUID 要对照书签索引
+/// 才能换成 URL，这里只做标记，由 `migrate_legacy_keys` 在下次刷新索引后统一换键
+fn key_tags_by_url_hash(conn: &Connection) -> Result<()> {
+    let stale: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM bookmark_tags WHERE bookmark_key NOT LIKE 'urlhash:%'",
+        [],
+        |row| row.get(0),
+    )?;
+    if stale > 0 {
+        conn.execute(
+            "INSERT OR REPLACE INTO tag_meta (key, value) VALUES ('legacy_keys', '1')",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// 版本 5：书签备注。与标签一样按 `tag_key` 关联，同一 URL 的书签共用一条备注，删除后重新收藏也能接上；
+/// `note_text` 为备注的全文索引（正文附加分词），搜索时随标签库挂载
+fn create_notes(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS notes (
+            bookmark_key TEXT PRIMARY KEY,
+            bookmark_url TEXT,
+            body TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+         );
+         CREATE VIRTUAL TABLE IF NOT EXISTS note_text USING fts5(
+            bookmark_key UNINDEXED,
+            body
+         );",
+    )
+}
+
+/// 版本 6：置顶书签，同样按 `tag_key` 关联。搜索时随标签库挂载，空查询列在最前、有关键词时大幅加分
+fn create_pinned(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS pinned (
+            bookmark_key TEXT PRIMARY KEY,
+            bookmark_url TEXT,
+            pinned_at INTEGER NOT NULL
+         );",
+    )
+}
+
+fn table_has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(columns.iter().any(|name| name == column))
+}
+
+fn log_edit(conn: &Connection, url: &str, tag: &str, added: bool, at: u64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tag_log (url, tag, added, at) VALUES (?1, ?2, ?3, ?4)",
+        params![url, tag, added, at as i64],
+    )?;
+    Ok(())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// 拆出标题末尾的 ` #tag1 #tag2` 后缀，返回（原标题, 标签）
+///
+/// 纯数字的 `#123`（如 issue 编号）不视为标签；整个标题都是标签时保持原样。
+pub fn split_title_tags(name: &str) -> (String, Vec<String>) {
+    let tokens: Vec<&str> = name.split_whitespace().collect();
+    let base_len = tokens
+        .iter()
+        .rposition(|token| {
+            token.strip_prefix('#').is_none_or(|tag| {
+                tag.is_empty() || tag.contains('#') || tag.chars().all(|c| c.is_ascii_digit())
+            })
+        })
+        .map_or(0, |pos| pos + 1);
+    if base_len == 0 || base_len == tokens.len() {
+        return (name.to_string(), Vec::new());
+    }
+
+    let mut tags: Vec<String> = Vec::new();
+    for tag in tokens[base_len..]
+        .iter()
+        .map(|token| token[1..].to_lowercase())
+    {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    (tokens[..base_len].join(" "), tags)
+}
+
+/// `split_title_tags` 的逆操作：把标签作为 ` #tag` 后缀追加到标题
+pub fn title_with_tags(name: &str, tags: &[String]) -> String {
+    tags.iter().fold(name.to_string(), |mut title, tag| {
+        title.push_str(" #");
+        title.push_str(&tag.replace(char::is_whitespace, "-"));
+        title
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmark::test_bookmark;
+    use tempfile::tempdir;
+
+    /// 带 GUID 的书签，GUID 与 id 相同
+    fn bookmark(id: &str, url: &str) -> ChromeBookmark {
+        ChromeBookmark {
+            guid: Some(id.to_string()),
+            ..test_bookmark(id, id, url, None)
+        }
+    }
+
+    fn seed(manager: &TagManager) {
+        manager
+            .conn
+            .execute_batch(
+                "INSERT INTO tags (id, name) VALUES (1, 'rust'), (2, 'old'), (3, 'empty');
+                 INSERT INTO bookmark_tags (bookmark_key, bookmark_url, tag_id) VALUES
+                    ('guid:10', 'https://rust-lang.org', 1),
+                    ('guid:11', 'https://moved.example', 1),
+                    ('guid:12', 'https://gone.example', 2);",
+            )
+            .expect("seed");
+    }
+
+    fn count(manager: &TagManager, sql: &str) -> i64 {
+        manager
+            .conn
+            .query_row(sql, [], |row| row.get(0))
+            .expect("count")
+    }
+
+    #[test]
+    fn tag_counts_skip_unused_tags() {
+        let dir = tempdir().expect("tempdir");
+        let manager = TagManager::new(dir.path().join("tags.db")).expect("tags");
+        seed(&manager);
+
+        assert_eq!(
+            manager.tag_counts().expect("counts"),
+            vec![("rust".to_string(), 2), ("old".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn tag_pairs_rank_co_occurring_tags() {
+        let dir = tempdir().expect("tempdir");
+        let manager = TagManager::new(dir.path().join("tags.db")).expect("tags");
+        let tags = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+        manager
+            .add_tags("1", "https://a.example", &tags(&["rust", "async", "web"]))
+            .expect("add");
+        manager
+            .add_tags("2", "https://b.example", &tags(&["rust", "async"]))
+            .expect("add");
+        manager
+            .add_tags("3", "https://c.example", &tags(&["rust"]))
+            .expect("add");
+
+        let pairs = manager.tag_pairs(2).expect("pairs");
+        assert_eq!(
+            pairs[0],
+            TagPair {
+                tags: ("async".to_string(), "rust".to_string()),
+                count: 2,
+                overlap: 100.0,
+            }
+        );
+        assert_eq!(pairs[1].count, 1);
+        assert_eq!(pairs.len(), 2);
+    }
+
+    #[test]
+    fn rename_tag_merges_into_existing_tag_and_logs_edits() {
+        let dir = tempdir().expect("tempdir");
+        let manager = TagManager::new(dir.path().join("tags.db")).expect("tags");
+        seed(&manager);
+        manager
+            .add_tags("guid:12", "https://gone.example", &["rust".to_string()])
+            .expect("add");
+        manager.unpublished_edits().expect("seed log");
+        manager.mark_published(i64::MAX).expect("published");
+
+        assert_eq!(manager.rename_tag("missing", "x").expect("rename"), None);
+        assert_eq!(manager.rename_tag("RUST", "Rust").expect("case"), Some(3));
+        assert_eq!(manager.tags_for("guid:10").expect("tags"), vec!["Rust"]);
+        assert!(manager.unpublished_edits().expect("edits").is_empty());
+
+        assert_eq!(manager.rename_tag("old", "rust").expect("merge"), Some(1));
+        assert_eq!(manager.tags_for("guid:12").expect("tags"), vec!["Rust"]);
+        assert_eq!(count(&manager, "SELECT COUNT(*) FROM tags"), 2);
+
+        let edits: Vec<(String, bool)> = manager
+            .unpublished_edits()
+            .expect("edits")
+            .into_iter()
+            .map(|(_, edit)| (edit.tag, edit.added))
+            .collect();
+        assert_eq!(
+            edits,
+            vec![("old".to_string(), false), ("Rust".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn prune_remaps_by_url_and_removes_orphans() {
+        let dir = tempdir().expect("tempdir");
+        let manager = TagManager::new(dir.path().join("tags.db")).expect("tags");
+        seed(&manager);
+
+        let live = vec![
+            bookmark("10", "https://rust-lang.org"),
+            bookmark("99", "https://Moved.example/?utm_source=feed"),
+        ];
+        let report = manager.prune(&live, false).expect("prune");
+
+        assert_eq!(
+            report.remapped,
+            vec![
+                (
+                    "guid:10".to_string(),
+                    bookmark_key("https://rust-lang.org"),
+                    "rust".to_string()
+                ),
+                (
+                    "guid:11".to_string(),
+                    bookmark_key("https://moved.example"),
+                    "rust".to_string()
+                )
+            ]
+        );
+        assert_eq!(
+            report.orphaned,
+            vec![("guid:12".to_string(), "old".to_string())]
+        );
+        assert_eq!(
+            report.unused_tags,
+            vec!["empty".to_string(), "old".to_string()]
+        );
+
+        assert_eq!(
+            manager.tags_for(&tag_key(&live[1])).expect("tags"),
+            vec!["rust"]
+        );
+        assert_eq!(count(&manager, "SELECT COUNT(*) FROM bookmark_tags"), 2);
+        assert_eq!(count(&manager, "SELECT COUNT(*) FROM tags"), 1);
+    }
+
+    #[test]
+    fn add_tags_creates_missing_tags_case_insensitively() {
+        let dir = tempdir().expect("tempdir");
+        let manager = TagManager::new(dir.path().join("tags.db")).expect("manager");
+        seed(&manager);
+
+        manager
+            .add_tags(
+                "local:Local:1",
+                "https://docs.rs",
+                &["Rust".to_string(), "reading".to_string(), " ".to_string()],
+            )
+            .expect("add");
+        manager
+            .add_tags("local:Local:1", "https://docs.rs", &["reading".to_string()])
+            .expect("add again");
+
+        assert_eq!(
+            manager.tags_for("local:Local:1").expect("tags"),
+            vec!["reading", "rust"]
+        );
+        assert_eq!(count(&manager, "SELECT COUNT(*) FROM tags"), 4);
+    }
+
+    #[test]
+    fn tags_and_notes_load_for_a_page_of_keys() {
+        let dir = tempdir().expect("tempdir");
+        let manager = TagManager::new(dir.path().join("tags.db")).expect("manager");
+        let docs = bookmark_key("https://docs.rs");
+        let rust = bookmark_key("https://rust-lang.org");
+        let bare = bookmark_key("https://bare.example");
+        manager
+            .add_tags(&docs, "https://docs.rs", &["rust".into(), "Docs".into()])
+            .expect("tag");
+        manager
+            .add_tags(&rust, "https://rust-lang.org", &["lang".into()])
+            .expect("tag");
+        manager
+            .set_note(&rust, "https://rust-lang.org", "start here", &[])
+            .expect("note");
+
+        let keys = [docs.as_str(), rust.as_str(), bare.as_str()];
+        let tags = manager.tags_for_keys(&keys).expect("tags");
+        assert_eq!(tags[&docs], vec!["Docs", "rust"]);
+        assert_eq!(tags[&rust], vec!["lang"]);
+        assert!(!tags.contains_key(&bare));
+        let notes = manager.notes_for_keys(&keys).expect("notes");
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[&rust], "start here");
+        assert!(manager.tags_for_keys(&[]).expect("empty").is_empty());
+    }
+
+    #[test]
+    fn notes_are_replaced_indexed_and_removed() {
+        let dir = tempdir().expect("tempdir");
+        let manager = TagManager::new(dir.path().join("tags.db")).expect("manager");
+        let key = bookmark_key("https://docs.rs");
+        assert_eq!(manager.note_for(&key).expect("note"), None);
+
+        manager
+            .set_note(&key, "https://docs.rs", "first draft", &[])
+            .expect("set");
+        manager
+            .set_note(&key, "https://docs.rs", "crate docs mirror", &[])
+            .expect("replace");
+        assert_eq!(
+            manager.note_for(&key).expect("note").as_deref(),
+            Some("crate docs mirror")
+        );
+        assert_eq!(
+            count(
+                &manager,
+                "SELECT COUNT(*) FROM note_text WHERE note_text MATCH 'mirror'"
+            ),
+            1
+        );
+        assert_eq!(
+            count(
+                &manager,
+                "SELECT COUNT(*) FROM note_text WHERE note_text MATCH 'draft'"
+            ),
+            0
+        );
+
+        assert!(manager.remove_note(&key).expect("remove"));
+        assert!(!manager.remove_note(&key).expect("remove again"));
+        assert_eq!(manager.note_for(&key).expect("note"), None);
+        assert_eq!(count(&manager, "SELECT COUNT(*) FROM note_text"), 0);
+    }
+
+    #[test]
+    fn pins_are_recorded_once_per_url() {
+        let dir = tempdir().expect("tempdir");
+        let manager = TagManager::new(dir.path().join("tags.db")).expect("manager");
+        let key = bookmark_key("https://docs.rs");
+        assert!(!manager.is_pinned(&key).expect("pinned"));
+
+        assert!(manager.pin(&key, "https://docs.rs").expect("pin"));
+        assert!(!manager
+            .pin(
+                &bookmark_key("https://docs.rs/?utm_source=x"),
+                "https://docs.rs"
+            )
+            .expect("pin again"));
+        assert!(manager.is_pinned(&key).expect("pinned"));
+
+        assert!(manager.unpin(&key).expect("unpin"));
+        assert!(!manager.unpin(&key).expect("unpin again"));
+        assert!(!manager.is_pinned(&key).expect("pinned"));
+    }
+
+    #[test]
+    fn tagged_keys_lists_distinct_keys() {
+        let dir = tempdir().expect("tempdir");
+        let manager = TagManager::new(dir.path().join("tags.db")).expect("tags");
+        seed(&manager);
+
+        let keys = manager.tagged_keys().expect("keys");
+        assert_eq!(keys.len(), 3);
+        assert!(keys.contains("guid:10"));
+        assert_eq!(manager.tags_for("guid:10").expect("tags"), vec!["rust"]);
+        assert!(manager.tags_for("missing").expect("tags").is_empty());
+    }
+
+    #[test]
+    fn tag_key_hashes_canonical_url_and_ignores_ids() {
+        assert_eq!(
+            tag_key(&bookmark("abc", "https://docs.rs")),
+            "urlhash:87b353361292e162".to_string()
+        );
+        let recreated = ChromeBookmark {
+            guid: None,
+            ..bookmark("local:Local:1", "HTTPS://Docs.rs/?utm_source=x#top")
+        };
+        assert_eq!(tag_key(&recreated), bookmark_key("https://docs.rs/"));
+        assert_ne!(bookmark_key("https://docs.rs/serde"), tag_key(&recreated));
+    }
+
+    #[test]
+    fn legacy_id_keyed_tags_are_renamed_and_remapped() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("tags.db");
+        Connection::open(&db_path)
+            .expect("open")
+            .execute_batch(
+                "CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE COLLATE NOCASE);
+                 CREATE TABLE bookmark_tags (
+                    bookmark_id TEXT NOT NULL,
+                    bookmark_url TEXT,
+                    tag_id INTEGER NOT NULL,
+                    PRIMARY KEY (bookmark_id, tag_id)
+                 );
+                 INSERT INTO tags (id, name) VALUES (1, 'rust'), (2, 'old');
+                 INSERT INTO bookmark_tags (bookmark_id, bookmark_url, tag_id) VALUES
+                    ('chrome:Default:10', 'https://rust-lang.org', 1),
+                    ('chrome:Default:11', 'https://moved.example', 1),
+                    ('chrome:Default:12', 'https://gone.example', 2),
+                    ('chrome:Default:13', NULL, 2);",
+            )
+            .expect("legacy schema");
+
+        let manager = TagManager::new(db_path).expect("tags");
+        assert!(manager.has_legacy_keys().expect("legacy"));
+        assert_eq!(
+            schema::user_version(&manager.conn).expect("version"),
+            TAG_MIGRATIONS.len() as i32
+        );
+
+        let live = vec![
+            ChromeBookmark {
+                id: "chrome:Default:10".to_string(),
+                ..bookmark("g10", "https://rust-lang.org")
+            },
+            // Chrome 同步后数字 id 变了，只能按 URL 找回
+            bookmark("g99", "https://moved.example/"),
+        ];
+        assert_eq!(manager.migrate_legacy_keys(&live).expect("migrate"), 3);
+        assert!(!manager.has_legacy_keys().expect("legacy"));
+
+        assert_eq!(
+            manager
+                .tags_for(&bookmark_key("https://rust-lang.org"))
+                .expect("tags"),
+            vec!["rust"]
+        );
+        assert_eq!(
+            manager
+                .tags_for(&bookmark_key("https://moved.example"))
+                .expect("tags"),
+            vec!["rust"]
+        );
+        assert_eq!(
+            manager
+                .tags_for(&bookmark_key("https://gone.example"))
+                .expect("tags"),
+            vec!["old"]
+        );
+        // 无从定位的关联留给 prune
+        assert_eq!(
+            manager.tags_for("chrome:Default:13").expect("tags"),
+            vec!["old"]
+        );
+        assert_eq!(manager.migrate_legacy_keys(&live).expect("again"), 0);
+    }
+
+    #[test]
+    fn guid_and_url_keyed_tags_are_rekeyed_by_url_hash() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("tags.db");
+        {
+            let conn = Connection::open(&db_path).expect("open");
+            for step in &TAG_MIGRATIONS[..3] {
+                (step.apply)(&conn).expect("old schema");
+            }
+            schema::set_user_version(&conn, 3).expect("version");
+            conn.execute_batch(
+                "INSERT INTO tags (id, name) VALUES (1, 'rust'), (2, 'docs');
+                 INSERT INTO bookmark_tags (bookmark_key, bookmark_url, tag_id) VALUES
+                    ('guid:g1', 'https://old.rust-lang.org', 1),
+                    ('url:https://docs.rs', NULL, 2),
+                    ('guid:gone', 'https://gone.example', 2);",
+            )
+            .expect("seed");
+        }
+
+        let manager = TagManager::new(db_path).expect("tags");
+        assert!(manager.has_legacy_keys().expect("legacy"));
+
+        // GUID 仍在书签表中时按书签当前的 URL 换键，而不是关联里记下的旧 URL
+        let live = vec![bookmark("g1", "https://www.rust-lang.org")];
+        assert_eq!(manager.migrate_legacy_keys(&live).expect("migrate"), 3);
+        assert_eq!(
+            manager.tags_for(&tag_key(&live[0])).expect("tags"),
+            vec!["rust"]
+        );
+        assert_eq!(
+            manager
+                .tags_for(&bookmark_key("https://docs.rs/"))
+                .expect("tags"),
+            vec!["docs"]
+        );
+        assert_eq!(
+            manager
+                .tags_for(&bookmark_key("https://gone.example"))
+                .expect("tags"),
+            vec!["docs"]
+        );
+
+        // 重新导入后 GUID 变了，只要 URL 相同标签仍在
+        let reimported = bookmark("g2", "https://www.rust-lang.org/");
+        assert_eq!(
+            manager.tags_for(&tag_key(&reimported)).expect("tags"),
+            vec!["rust"]
+        );
+    }
+
+    #[test]
+    fn failed_migration_keeps_existing_tags() {
+        let dir = tempdir().expect("tempdir");
+        let db_path = dir.path().join("tags.db");
+        let conn = Connection::open(&db_path).expect("open");
+        conn.execute_batch(
+            "CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE COLLATE NOCASE);
+             INSERT INTO tags (id, name) VALUES (1, 'rust');
+             CREATE VIEW bookmark_tags AS SELECT id AS tag_id FROM tags;",
+        )
+        .expect("unexpected schema");
+
+        assert!(TagManager::new(db_path).is_err());
+        assert_eq!(schema::user_version(&conn).expect("version"), 0);
+        let names: i64 = conn
+            .query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0))
+            .expect("count");
+        assert_eq!(names, 1);
+    }
+
+    #[test]
+    fn prune_dry_run_leaves_database_untouched() {
+        let dir = tempdir().expect("tempdir");
+        let manager = TagManager::new(dir.path().join("tags.db")).expect("tags");
+        seed(&manager);
+
+        let report = manager
+            .prune(&[bookmark("10", "https://rust-lang.org")], true)
+            .expect("prune");
+
+        assert_eq!(report.orphaned.len(), 2);
+        assert!(!report.is_empty());
+        assert_eq!(count(&manager, "SELECT COUNT(*) FROM bookmark_tags"), 3);
+        assert_eq!(count(&manager, "SELECT COUNT(*) FROM tags"), 3);
+    }
+
+    #[test]
+    fn title_tags_round_trip() {
+        assert_eq!(
+            split_title_tags("Rust Book  #Rust #reading #rust"),
+            (
+                "Rust Book".to_string(),
+                vec!["rust".to_string(), "reading".to_string()]
+            )
+        );
+        assert_eq!(
+            split_title_tags("Fix crash #123"),
+            ("Fix crash #123".to_string(), Vec::new())
+        );
+        assert_eq!(split_title_tags("#rust"), ("#rust".to_string(), Vec::new()));
+        assert_eq!(
+            split_title_tags("C# guide #dotnet").1,
+            vec!["dotnet".to_string()]
+        );
+
+        let title = title_with_tags("Rust Book", &["rust".to_string(), "to read".to_string()]);
+        assert_eq!(title, "Rust Book #rust #to-read");
+        assert_eq!(
+            split_title_tags(&title),
+            (
+                "Rust Book".to_string(),
+                vec!["rust".to_string(), "to-read".to_string()]
+            )
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use pinyin::ToPinyin;
+use std::sync::OnceLock;
+
+/// FTS 字段的分词方式。FTS5 只能整表指定一个分词器，因此表本身保持 unicode61，
+/// 其余方式在写入前把额外的词附加到该列文本之后
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tokenizer {
+    /// 按 Unicode 单词切分（默认），连续的中文视为一个词
+    Unicode61,
+    /// 附加每个词的三字符片段，配合查询展开支持词中间的子串匹配
+    Trigram,
+    /// 附加汉字的全拼与首字母，`sousuo`、`ss` 可命中“搜索”
+    Pinyin,
+    /// 附加结巴分词的结果，“搜索引擎”也能以“引擎”命中
+    Jieba,
+}
+
+impl Tokenizer {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "unicode61" | "unicode" | "default" => Some(Tokenizer::Unicode61),
+            "trigram" => Some(Tokenizer::Trigram),
+            "pinyin" => Some(Tokenizer::Pinyin),
+            "jieba" => Some(Tokenizer::Jieba),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Tokenizer::Unicode61 => "unicode61",
+            Tokenizer::Trigram => "trigram",
+            Tokenizer::Pinyin => "pinyin",
+            Tokenizer::Jieba => "jieba",
+        }
+    }
+}
+
+/// 标题、URL、目录各自的分词方式；一个字段可组合多种（如 `pinyin+jieba`），空表示 unicode61
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TokenizeConfig {
+    pub name: Vec<Tokenizer>,
+    pub url: Vec<Tokenizer>,
+    pub folder: Vec<Tokenizer>,
+}
+
+impl TokenizeConfig {
+    /// `jieba+pinyin` 对全部字段生效；`name=pinyin+jieba,url=trigram` 逐字段指定，未提及的字段保持默认
+    pub fn parse(raw: &str) -> Self {
+        let mut config = TokenizeConfig::default();
+        for part in raw.split([',', ';']).filter(|part| !part.trim().is_empty()) {
+            let (field, value) = match part.split_once('=') {
+                Some((field, value)) => (Some(field.trim().to_ascii_lowercase()), value),
+                None => (None, part),
+            };
+            let mut tokenizers: Vec<Tokenizer> = value
+                .split('+')
+                .filter_map(Tokenizer::parse)
+                .filter(|tokenizer| *tokenizer != Tokenizer::Unicode61)
+                .collect();
+            tokenizers.sort();
+            tokenizers.dedup();
+            match field.as_deref() {
+                None => {
+                    config.name = tokenizers.clone();
+                    config.url = tokenizers.clone();
+                    config.folder = tokenizers;
+                }
+                Some("name" | "title") => config.name = tokenizers,
+                Some("url") => config.url = tokenizers,
+                Some("folder" | "folder_path" | "path") => config.folder = tokenizers,
+                Some(_) => {}
+            }
+        }
+        config
+    }
+
+    /// 记录在索引 meta 中，与当前配置不同时重建 FTS
+    pub fn signature(&self) -> String {
+        let field = |tokenizers: &[Tokenizer]| {
+            if tokenizers.is_empty() {
+                Tokenizer::Unicode61.name().to_string()
+            } else {
+                tokenizers
+                    .iter()
+                    .map(|tokenizer| tokenizer.name())
+                    .collect::<Vec<_>>()
+                    .join("+")
+            }
+        };
+        format!(
+            "name={};url={};folder={}",
+            field(&self.name),
+            field(&self.url),
+            field(&self.folder)
+        )
+    }
+
+    /// 附加了三字符片段的 FTS 列，查询时对这些列展开子串匹配
+    pub fn trigram_columns(&self) -> Vec<&'static str> {
+        [
+            ("name", &self.name),
+            ("url", &self.url),
+            ("folder_path", &self.folder),
+        ]
+        .into_iter()
+        .filter(|(_, tokenizers)| tokenizers.contains(&Tokenizer::Trigram))
+        .map(|(column, _)| column)
+        .collect()
+    }
+}
+
+/// 写入 FTS 列的文本：原文之后附加各分词方式产生的额外词
+pub fn analyze(text: &str, tokenizers: &[Tokenizer]) -> String {
+    let mut extra: Vec<String> = Vec::new();
+    for tokenizer in tokenizers {
+        match tokenizer {
+            Tokenizer::Unicode61 => {}
+            Tokenizer::Trigram => extra.extend(words(text).flat_map(|word| trigrams(&word))),
+            Tokenizer::Pinyin => extra.extend(pinyin_terms(text)),
+            Tokenizer::Jieba => extra.extend(jieba_terms(text)),
+        }
+    }
+    if extra.is_empty() {
+        return text.to_string();
+    }
+    format!("{} {}", text, extra.join(" "))
+}
+
+/// 单词的三字符片段；不超过三个字符的词靠前缀匹配即可，返回空
+pub fn trigrams(word: &str) -> Vec<String> {
+    let chars: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+    if chars.len() <= 3 {
+        return Vec::new();
+    }
+    chars
+        .windows(3)
+        .map(|window| window.iter().collect())
+        .collect()
+}
+
+/// 查询词中每个单词的三字符片段，全部命中才算子串匹配
+pub fn query_trigrams(token: &str) -> Vec<String> {
+    words(token).flat_map(|word| trigrams(&word)).collect()
+}
+
+/// 按非字母数字字符切词，与 FTS5 unicode61 分词器的切分方式一致
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+}
+
+fn is_han(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}')
+}
+
+/// 每段连续汉字的全拼（连写）与首字母，以及逐字的拼音
+fn pinyin_terms(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut run = String::new();
+    for c in text.chars().chain([' ']) {
+        if is_han(c) {
+            run.push(c);
+            continue;
+        }
+        if run.is_empty() {
+            continue;
+        }
+        let syllables: Vec<&str> = run
+            .as_str()
+            .to_pinyin()
+            .flatten()
+            .map(|pinyin| pinyin.plain())
+            .collect();
+        if !syllables.is_empty() {
+            terms.push(syllables.concat());
+            terms.push(
+                syllables
+                    .iter()
+                    .filter_map(|syllable| syllable.chars().next())
+                    .collect(),
+            );
+            if syllables.len() > 1 {
+                terms.extend(syllables.iter().map(|syllable| syllable.to_string()));
+            }
+        }
+        run.clear();
+    }
+    terms
+}
+
+/// 搜索引擎模式的结巴分词，只保留含汉字且不同于原文整段的词；词典首次使用时才加载
+fn jieba_terms(text: &str) -> Vec<String> {
+    static JIEBA: OnceLock<jieba_rs::Jieba> = OnceLock::new();
+    if !text.chars().any(is_han) {
+        return Vec::new();
+    }
+    let jieba = JIEBA.get_or_init(jieba_rs::Jieba::new);
+    let runs: Vec<&str> = text
+        .split(|c: char| !is_han(c))
+        .filter(|run| !run.is_empty())
+        .collect();
+    let mut terms = Vec::new();
+    for run in runs {
+        for token in jieba.cut_for_search(run, true) {
+            let word = token.word;
+            if word != run && word.chars().any(is_han) && !terms.iter().any(|term| term == word) {
+                terms.push(word.to_string());
+            }
+        }
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_parses_global_and_per_field_settings() {
+        assert_eq!(TokenizeConfig::parse(""), TokenizeConfig::default());
+        let all = TokenizeConfig::parse("pinyin+jieba");
+        assert_eq!(all.name, vec![Tokenizer::Pinyin, Tokenizer::Jieba]);
+        assert_eq!(all.url, all.name);
+
+        let fields =
+            TokenizeConfig::parse("title=jieba+pinyin, url=trigram, folder=unicode61, x=y");
+        assert_eq!(fields.name, vec![Tokenizer::Pinyin, Tokenizer::Jieba]);
+        assert_eq!(fields.url, vec![Tokenizer::Trigram]);
+        assert!(fields.folder.is_empty());
+        assert_eq!(
+            fields.signature(),
+            "name=pinyin+jieba;url=trigram;folder=unicode61"
+        );
+        assert_eq!(fields.trigram_columns(), vec!["url"]);
+        assert_ne!(fields.signature(), TokenizeConfig::default().signature());
+    }
+
+    #[test]
+    fn analyzers_append_extra_terms() {
+        assert_eq!(analyze("Rust 文档", &[]), "Rust 文档");
+
+        let trigram = analyze("GitHub", &[Tokenizer::Trigram]);
+        assert_eq!(trigram, "GitHub git ith thu hub");
+        assert!(trigrams("abc").is_empty());
+
+        let pinyin = analyze("搜索引擎 Rust", &[Tokenizer::Pinyin]);
+        assert!(pinyin.starts_with("搜索引擎 Rust "));
+        assert!(pinyin.contains("sousuoyinqing"));
+        assert!(pinyin.contains(" ssyq"));
+        assert!(pinyin.contains(" yin "));
+
+        let jieba = analyze("搜索引擎", &[Tokenizer::Jieba]);
+        assert!(jieba.contains("引擎"), "{}", jieba);
+        assert!(analyze("rust only", &[Tokenizer::Jieba]) == "rust only");
+    }
+}
@@ -0,0 +1,334 @@
+/// 常见的跟踪参数；以 `*` 结尾表示前缀匹配
+pub const DEFAULT_TRACKING_PARAMS: &[&str] = &[
+    "utm_*", "gclid", "fbclid", "msclkid", "yclid", "mc_cid", "mc_eid", "igshid", "ref",
+];
+
+/// 移除 URL 查询串中的跟踪参数，保留其余参数顺序与片段（#...）
+pub fn strip_tracking_params<S: AsRef<str>>(url: &str, patterns: &[S]) -> String {
+    let (without_fragment, fragment) = match url.split_once('#') {
+        Some((head, fragment)) => (head, Some(fragment)),
+        None => (url, None),
+    };
+
+    let Some((base, query)) = without_fragment.split_once('?') else {
+        return url.to_string();
+    };
+
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| {
+            let key = pair.split_once('=').map(|(key, _)| key).unwrap_or(pair);
+            !patterns
+                .iter()
+                .any(|pattern| tracking_param_matches(pattern.as_ref(), key))
+        })
+        .collect();
+
+    let mut cleaned = base.to_string();
+    if !kept.is_empty() {
+        cleaned.push('?');
+        cleaned.push_str(&kept.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        cleaned.push('#');
+        cleaned.push_str(fragment);
+    }
+    cleaned
+}
+
+fn tracking_param_matches(pattern: &str, key: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix)),
+        None => key.eq_ignore_ascii_case(pattern),
+    }
+}
+
+/// 跨来源比较、按 URL 查找书签用的规范 URL：协议与主机小写，`xn--` 主机标签解码为 Unicode，
+/// 去掉默认端口、片段、跟踪参数与末尾斜杠
+pub fn canonical_url(url: &str) -> String {
+    let stripped = strip_tracking_params(url, DEFAULT_TRACKING_PARAMS);
+    let without_fragment = stripped.split('#').next().unwrap_or("");
+    let Some((scheme, rest)) = without_fragment.split_once("://") else {
+        return without_fragment.to_string();
+    };
+
+    let scheme = scheme.to_ascii_lowercase();
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(pos) => rest.split_at(pos),
+        None => (rest, ""),
+    };
+    let authority = authority.to_lowercase();
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if port.bytes().all(|byte| byte.is_ascii_digit()) => (host, Some(port)),
+        _ => (authority.as_str(), None),
+    };
+    let default_port = match scheme.as_str() {
+        "http" => "80",
+        "https" => "443",
+        _ => "",
+    };
+    let mut authority = decode_idn_host(host);
+    if let Some(port) = port.filter(|port| !port.is_empty() && *port != default_port) {
+        authority.push(':');
+        authority.push_str(port);
+    }
+
+    let path = match path.split_once('?') {
+        Some((path, query)) => format!("{}?{}", path.trim_end_matches('/'), query),
+        None => path.trim_end_matches('/').to_string(),
+    };
+    format!("{}://{}{}", scheme, authority, path)
+}
+
+/// `canonical_url` 的 64 位 FNV-1a 摘要（16 位十六进制）；与标准库的哈希不同，结果不随编译器版本变化，
+/// 可以写入数据库长期保存
+pub fn canonical_url_hash(url: &str) -> String {
+    let hash = canonical_url(url)
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+/// 主机名中的 `xn--` 标签按 punycode 解码为 Unicode，同一国际化域名的两种写法因此一致；
+/// 无法解码的标签原样保留
+fn decode_idn_host(host: &str) -> String {
+    host.split('.')
+        .map(|label| {
+            label
+                .get(..4)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("xn--"))
+                .and_then(|_| decode_punycode(&label[4..]))
+                .map(|decoded| decoded.to_lowercase())
+                .unwrap_or_else(|| label.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+const PUNYCODE_BASE: u32 = 36;
+const PUNYCODE_TMIN: u32 = 1;
+const PUNYCODE_TMAX: u32 = 26;
+const PUNYCODE_SKEW: u32 = 38;
+const PUNYCODE_DAMP: u32 = 700;
+const PUNYCODE_INITIAL_BIAS: u32 = 72;
+const PUNYCODE_INITIAL_N: u32 = 128;
+
+/// RFC 3492 punycode 解码（不含 `xn--` 前缀），输入非法或溢出时返回 None
+fn decode_punycode(input: &str) -> Option<String> {
+    let (basic, encoded) = match input.rfind('-') {
+        Some(pos) => (&input[..pos], &input[pos + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+
+    let mut output: Vec<char> = basic.chars().collect();
+    let mut n = PUNYCODE_INITIAL_N;
+    let mut bias = PUNYCODE_INITIAL_BIAS;
+    let mut i: u32 = 0;
+    let mut digits = encoded.bytes().peekable();
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut weight: u32 = 1;
+        let mut k = PUNYCODE_BASE;
+        loop {
+            let digit = match digits.next()? {
+                byte @ b'a'..=b'z' => byte - b'a',
+                byte @ b'A'..=b'Z' => byte - b'A',
+                byte @ b'0'..=b'9' => byte - b'0' + 26,
+                _ => return None,
+            } as u32;
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let threshold = if k <= bias {
+                PUNYCODE_TMIN
+            } else if k >= bias + PUNYCODE_TMAX {
+                PUNYCODE_TMAX
+            } else {
+                k - bias
+            };
+            if digit < threshold {
+                break;
+            }
+            weight = weight.checked_mul(PUNYCODE_BASE - threshold)?;
+            k += PUNYCODE_BASE;
+        }
+
+        let len = output.len() as u32 + 1;
+        bias = punycode_adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+fn punycode_adapt(delta: u32, num_points: u32, first: bool) -> u32 {
+    let mut delta = delta / if first { PUNYCODE_DAMP } else { 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((PUNYCODE_BASE - PUNYCODE_TMIN) * PUNYCODE_TMAX) / 2 {
+        delta /= PUNYCODE_BASE - PUNYCODE_TMIN;
+        k += PUNYCODE_BASE;
+    }
+    k + (PUNYCODE_BASE - PUNYCODE_TMIN + 1) * delta / (delta + PUNYCODE_SKEW)
+}
+
+/// 按 RFC 3986 对查询参数值做百分号编码（保留非保留字符，其余按 UTF-8 字节编码）
+pub fn percent_encode_component(raw: &str) -> String {
+    let mut encoded = String::with_capacity(raw.len());
+    for byte in raw.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// 提取小写主机名（去掉协议、用户信息与端口）
+pub fn url_host(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map(|(_, rest)| rest)?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = if host.starts_with('[') {
+        host.split(']').next().map(|h| &h[1..]).unwrap_or(host)
+    } else {
+        host.split(':').next().unwrap_or(host)
+    };
+
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_ascii_lowercase())
+    }
+}
+
+/// URL 的主机是否属于某个域名（含子域名），`domains` 需已小写
+pub fn url_in_domains<S: AsRef<str>>(url: &str, domains: &[S]) -> bool {
+    let Some(host) = url_host(url) else {
+        return false;
+    };
+    domains.iter().any(|domain| {
+        let domain = domain.as_ref();
+        host == domain
+            || host
+                .strip_suffix(domain)
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_in_domains_matches_subdomains_only_on_label_boundary() {
+        let domains = ["docs.rs", "apple.com"];
+        assert!(url_in_domains("https://docs.rs/serde", &domains));
+        assert!(url_in_domains("https://developer.apple.com/", &domains));
+        assert!(!url_in_domains("https://notdocs.rs/", &domains));
+        assert!(!url_in_domains("javascript:void(0)", &domains));
+    }
+
+    #[test]
+    fn percent_encoding_keeps_unreserved_and_encodes_utf8() {
+        assert_eq!(percent_encode_component("rust lang"), "rust%20lang");
+        assert_eq!(percent_encode_component("a&b=c~d"), "a%26b%3Dc~d");
+        assert_eq!(percent_encode_component("模式"), "%E6%A8%A1%E5%BC%8F");
+    }
+
+    #[test]
+    fn strip_tracking_params_removes_known_params_and_keeps_others() {
+        let url = "https://example.com/a?id=7&utm_source=x&UTM_Medium=y&gclid=z&ref=hn#top";
+        assert_eq!(
+            strip_tracking_params(url, DEFAULT_TRACKING_PARAMS),
+            "https://example.com/a?id=7#top"
+        );
+    }
+
+    #[test]
+    fn strip_tracking_params_drops_empty_query() {
+        assert_eq!(
+            strip_tracking_params(
+                "https://example.com/?utm_campaign=a",
+                DEFAULT_TRACKING_PARAMS
+            ),
+            "https://example.com/"
+        );
+    }
+
+    #[test]
+    fn strip_tracking_params_leaves_urls_without_query_untouched() {
+        let url = "https://example.com/path#utm_source=frag";
+        assert_eq!(strip_tracking_params(url, DEFAULT_TRACKING_PARAMS), url);
+    }
+
+    #[test]
+    fn canonical_url_normalizes_case_port_fragment_and_slash() {
+        assert_eq!(
+            canonical_url("HTTPS://Example.COM:443/Docs/?utm_source=x#intro"),
+            "https://example.com/Docs"
+        );
+        assert_eq!(
+            canonical_url("https://example.com/a/?id=1"),
+            "https://example.com/a?id=1"
+        );
+        assert_eq!(
+            canonical_url("http://example.com:8080/"),
+            "http://example.com:8080"
+        );
+        assert_eq!(canonical_url("javascript:void(0)"), "javascript:void(0)");
+    }
+
+    #[test]
+    fn canonical_url_decodes_punycode_hosts() {
+        assert_eq!(decode_idn_host("xn--mnchen-3ya.de"), "münchen.de");
+        assert_eq!(decode_idn_host("www.xn--fiqs8s"), "www.中国");
+        assert_eq!(
+            decode_idn_host("xn--invalid!.example"),
+            "xn--invalid!.example"
+        );
+        assert_eq!(
+            canonical_url("https://XN--MNCHEN-3YA.de:443/Karte/?fbclid=1"),
+            canonical_url("https://München.de/Karte")
+        );
+        assert_eq!(canonical_url("http://xn--fiqs8s:8080/"), "http://中国:8080");
+    }
+
+    #[test]
+    fn canonical_url_hash_is_stable_across_equivalent_urls() {
+        assert_eq!(canonical_url_hash("https://docs.rs"), "87b353361292e162");
+        assert_eq!(
+            canonical_url_hash("HTTPS://Docs.RS:443/?utm_source=x#top"),
+            "87b353361292e162"
+        );
+        assert_ne!(
+            canonical_url_hash("https://docs.rs/serde"),
+            "87b353361292e162"
+        );
+    }
+
+    #[test]
+    fn url_host_strips_userinfo_port_and_case() {
+        assert_eq!(
+            url_host("https://User@Intranet.Example:8443/a?b#c").as_deref(),
+            Some("intranet.example")
+        );
+        assert_eq!(url_host("http://[::1]:80/").as_deref(), Some("::1"));
+        assert_eq!(url_host("javascript:void(0)"), None);
+    }
+
+    #[test]
+    fn strip_tracking_params_does_not_match_similar_keys() {
+        let url = "https://example.com/?referrer=1&refresh=2";
+        assert_eq!(strip_tracking_params(url, DEFAULT_TRACKING_PARAMS), url);
+    }
+}
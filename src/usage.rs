@@ -0,0 +1,127 @@
+use rusqlite::{params, params_from_iter, Connection, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 书签打开记录（usage.db），按 URL 计数，用于 `--sort frecency`
+pub struct UsageStore {
+    conn: Connection,
+}
+
+impl UsageStore {
+    pub fn new(db_path: PathBuf) -> Result<Self> {
+        let conn = crate::db_crypt::open(&db_path, crate::db_crypt::key())?;
+        let _ = conn.busy_timeout(Duration::from_millis(500));
+
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;",
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS opens (
+                url TEXT PRIMARY KEY,
+                count INTEGER NOT NULL DEFAULT 0,
+                last_opened INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(Self { conn })
+    }
+
+    pub fn record_open(&self, url: &str, now: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO opens (url, count, last_opened) VALUES (?1, 1, ?2)
+             ON CONFLICT(url) DO UPDATE SET
+                count = opens.count + 1,
+                last_opened = excluded.last_opened",
+            params![url, now as i64],
+        )?;
+        Ok(())
+    }
+
+    /// 最近一次通过 workflow 打开的时间（Unix 秒）
+    pub fn last_opened(&self, url: &str) -> Result<Option<u64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT last_opened FROM opens WHERE url = ?1")?;
+        let mut rows = stmt.query_map(params![url], |row| row.get::<_, i64>(0))?;
+        Ok(rows.next().transpose()?.map(|secs| secs as u64))
+    }
+
+    /// 打开次数与最近一次打开的时间（Unix 秒）；从未通过 workflow 打开时为 None
+    pub fn open_stats(&self, url: &str) -> Result<Option<(u64, u64)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT count, last_opened FROM opens WHERE url = ?1")?;
+        let mut rows = stmt.query_map(params![url], |row| {
+            Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64))
+        })?;
+        rows.next().transpose()
+    }
+
+    /// 一页结果中各 URL 最近一次打开的时间（Unix 秒）；没有打开记录的 URL 不出现
+    pub fn last_opened_for(&self, urls: &[&str]) -> Result<HashMap<String, u64>> {
+        if urls.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let sql = format!(
+            "SELECT url, last_opened FROM opens WHERE url IN ({})",
+            vec!["?"; urls.len()].join(", ")
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(urls), |row| {
+            Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        rows.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn opens_are_counted_per_url() {
+        let dir = tempdir().expect("tempdir");
+        let usage = UsageStore::new(dir.path().join("usage.db")).expect("usage");
+        usage.record_open("https://docs.rs", 10).expect("record");
+        usage.record_open("https://docs.rs", 20).expect("record");
+        usage
+            .record_open("https://rust-lang.org", 15)
+            .expect("record");
+
+        let (count, last): (i64, i64) = usage
+            .conn
+            .query_row(
+                "SELECT count, last_opened FROM opens WHERE url = 'https://docs.rs'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("row");
+        assert_eq!((count, last), (2, 20));
+        assert_eq!(
+            usage.last_opened("https://docs.rs").expect("last"),
+            Some(20)
+        );
+        assert_eq!(usage.last_opened("https://a.example").expect("last"), None);
+        assert_eq!(
+            usage.open_stats("https://docs.rs").expect("stats"),
+            Some((2, 20))
+        );
+        assert_eq!(usage.open_stats("https://a.example").expect("stats"), None);
+
+        let page = usage
+            .last_opened_for(&[
+                "https://docs.rs",
+                "https://rust-lang.org",
+                "https://a.example",
+            ])
+            .expect("page");
+        assert_eq!(page.len(), 2);
+        assert_eq!(page["https://docs.rs"], 20);
+        assert_eq!(page["https://rust-lang.org"], 15);
+    }
+}
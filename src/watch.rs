@@ -0,0 +1,114 @@
+use crate::bookmark::BookmarkSource;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// 需要监听的目录：浏览器以“写临时文件再重命名”的方式保存书签，直接监听文件会在第一次保存后失效
+pub fn watch_dirs(sources: &[BookmarkSource]) -> Vec<PathBuf> {
+    sources
+        .iter()
+        .filter_map(|source| source.path.parent().map(Path::to_path_buf))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// 会触发刷新的文件：书签文件本身，以及 Firefox 的 `places.sqlite-wal`
+pub fn watch_targets(sources: &[BookmarkSource]) -> HashSet<PathBuf> {
+    let mut targets = HashSet::new();
+    for source in sources {
+        let mut wal = source.path.clone().into_os_string();
+        wal.push("-wal");
+        for path in [source.path.clone(), PathBuf::from(wal)] {
+            // FSEvents 上报的是真实路径（如 /private/var/...），两种形式都记录
+            if let Some(canonical) = path
+                .parent()
+                .and_then(|parent| parent.canonicalize().ok())
+                .zip(path.file_name())
+                .map(|(parent, name)| parent.join(name))
+            {
+                targets.insert(canonical);
+            }
+            targets.insert(path);
+        }
+    }
+    targets
+}
+
+pub fn touches_targets(paths: &[PathBuf], targets: &HashSet<PathBuf>) -> bool {
+    paths.iter().any(|path| targets.contains(path))
+}
+
+/// 监听书签文件，变化后等待 `debounce` 内没有新事件再调用一次 `on_change`；只在监听本身失败时返回
+pub fn watch_sources<F>(
+    sources: &[BookmarkSource],
+    debounce: Duration,
+    mut on_change: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: FnMut(),
+{
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for dir in watch_dirs(sources) {
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    }
+    let targets = watch_targets(sources);
+
+    loop {
+        let event = rx.recv()??;
+        if matches!(event.kind, EventKind::Access(_)) || !touches_targets(&event.paths, &targets) {
+            continue;
+        }
+
+        // 浏览器保存一次会连续触发多个事件，合并为一次刷新
+        while rx.recv_timeout(debounce).is_ok() {}
+        on_change();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(path: &Path) -> BookmarkSource {
+        BookmarkSource {
+            browser: "chrome".to_string(),
+            profile: "Default".to_string(),
+            path: path.to_path_buf(),
+        }
+    }
+
+    #[test]
+    fn watch_dirs_are_deduplicated_parents() {
+        let sources = vec![
+            source(Path::new("/a/Default/Bookmarks")),
+            source(Path::new("/a/Default/Bookmarks")),
+            source(Path::new("/a/Profile 1/Bookmarks")),
+        ];
+        assert_eq!(
+            watch_dirs(&sources),
+            vec![PathBuf::from("/a/Default"), PathBuf::from("/a/Profile 1")]
+        );
+    }
+
+    #[test]
+    fn only_bookmark_files_and_wal_trigger_refresh() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let places = dir.path().join("places.sqlite");
+        let targets = watch_targets(&[source(&places)]);
+
+        assert!(touches_targets(std::slice::from_ref(&places), &targets));
+        assert!(touches_targets(
+            &[dir.path().join("places.sqlite-wal")],
+            &targets
+        ));
+        assert!(!touches_targets(
+            &[dir.path().join("places.sqlite-shm")],
+            &targets
+        ));
+        assert!(!touches_targets(&[dir.path().join("History")], &targets));
+    }
+}